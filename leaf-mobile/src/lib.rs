@@ -1,9 +1,13 @@
-use std::{ffi::CStr, os::raw::c_char};
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    ptr,
+};
 
 use bytes::BytesMut;
 use log::*;
 
-use leaf::config;
+use leaf::{config, proxy::failover};
 
 pub mod ios;
 
@@ -79,3 +83,76 @@ pub extern "C" fn run_leaf(path: *const c_char) {
         return;
     }
 }
+
+fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+/// Runs a health check of all failover outbounds right away, to be called
+/// by platform code on network changes, e.g. when switching between Wi-Fi
+/// and cellular.
+#[no_mangle]
+pub extern "C" fn leaf_failover_recheck() {
+    failover::controls().trigger_recheck();
+}
+
+/// Takes the actor tagged `actor` of the failover outbound tagged `outbound`
+/// out of service, or puts it back, e.g. to drain an upstream for
+/// maintenance. Returns false if there is no such actor.
+#[no_mangle]
+pub extern "C" fn leaf_failover_set_actor_enabled(
+    outbound: *const c_char,
+    actor: *const c_char,
+    enabled: bool,
+) -> bool {
+    match (to_str(outbound), to_str(actor)) {
+        (Some(outbound), Some(actor)) => {
+            failover::controls().set_actor_enabled(outbound, actor, enabled)
+        }
+        _ => {
+            error!("invalid failover outbound or actor tag");
+            false
+        }
+    }
+}
+
+/// Returns the response times of the actors of the failover outbound tagged
+/// `outbound`, one actor per line: `tcp` or `udp` for the health checks
+/// they come from, the tag of the actor, then the number of samples and the
+/// 50th, 95th and 99th percentiles in milliseconds, or `-` without
+/// successful checks. Returns null if there is no such outbound, the string
+/// returned is freed with `leaf_free_string`.
+#[no_mangle]
+pub extern "C" fn leaf_failover_latencies(outbound: *const c_char) -> *mut c_char {
+    let (tcp, udp) = match to_str(outbound).and_then(|o| failover::controls().latencies(o)) {
+        Some(v) => v,
+        None => return ptr::null_mut(),
+    };
+    let mut lines = Vec::new();
+    for (network, latencies) in [("tcp", tcp), ("udp", udp)].iter() {
+        for (actor, summary) in latencies.iter() {
+            lines.push(match summary {
+                Some(s) => format!(
+                    "{} {} {} {} {} {}",
+                    network, actor, s.samples, s.p50, s.p95, s.p99
+                ),
+                None => format!("{} {} -", network, actor),
+            });
+        }
+    }
+    match CString::new(lines.join("\n")) {
+        Ok(s) => s.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Frees a string returned by the functions above.
+#[no_mangle]
+pub extern "C" fn leaf_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
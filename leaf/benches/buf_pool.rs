@@ -0,0 +1,63 @@
+//! Compares allocations of per-operation buffers against pooled ones.
+//!
+//! Run with `cargo bench --bench buf_pool`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use leaf::common::buf_pool::BufferPool;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ITERATIONS: usize = 1_000_000;
+const BUF_SIZE: usize = 2 * 1024;
+
+fn run<F: FnMut(usize) -> u8>(name: &str, mut f: F) {
+    let allocs = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    // Keeps the buffers from being optimized away.
+    let mut checksum = 0u8;
+    for i in 0..ITERATIONS {
+        checksum = checksum.wrapping_add(f(i));
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<8} {:>10} allocations {:>8.1}ns/op (checksum {})",
+        name,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocs,
+        elapsed.as_nanos() as f64 / ITERATIONS as f64,
+        checksum
+    );
+}
+
+fn main() {
+    run("vec", |i| {
+        let mut buf = vec![0u8; BUF_SIZE];
+        buf[i % BUF_SIZE] = i as u8;
+        buf[(i * 7) % BUF_SIZE]
+    });
+
+    let pool = BufferPool::new(16);
+    run("pooled", |i| {
+        let mut buf = pool.get(BUF_SIZE);
+        buf[i % BUF_SIZE] = i as u8;
+        buf[(i * 7) % BUF_SIZE]
+    });
+}
@@ -0,0 +1,74 @@
+//! Compares relay throughput over a simulated high-latency link with fixed
+//! and tuned relay buffers.
+//!
+//! Run with `cargo bench --bench relay_buffer`.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::{ready, Future};
+use tokio::io::{AsyncReadExt, AsyncWrite};
+use tokio::time::{delay_for, Delay};
+
+use leaf::app::dispatcher::transfer;
+
+const TOTAL: u64 = 4 * 1024 * 1024;
+const RTT: Duration = Duration::from_millis(5);
+
+// A link taking a round trip to acknowledge each write, like a TCP
+// connection whose window is filled by every write.
+struct Link {
+    ack: Option<Pin<Box<Delay>>>,
+}
+
+impl AsyncWrite for Link {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Some(ack) = self.ack.as_mut() {
+            ready!(ack.as_mut().poll(cx));
+        }
+        self.ack = Some(Box::pin(delay_for(RTT)));
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+async fn run(name: &str, buf_size: usize, max_buf_size: usize) {
+    let source = tokio::io::repeat(0).take(TOTAL);
+    let start = Instant::now();
+    let n = transfer(source, Link { ack: None }, buf_size, max_buf_size)
+        .await
+        .unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(n, TOTAL);
+    println!(
+        "{:<12} {:>8.1} MB/s",
+        name,
+        TOTAL as f64 / 1024.0 / 1024.0 / elapsed.as_secs_f64(),
+    );
+}
+
+fn main() {
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        run("fixed 2KB", 2 * 1024, 2 * 1024).await;
+        run("fixed 16KB", 16 * 1024, 16 * 1024).await;
+        run("tuned", 16 * 1024, 256 * 1024).await;
+    });
+}
@@ -0,0 +1,93 @@
+//! Compares throughput and CPU time of relaying through userspace buffers
+//! against splice(2).
+//!
+//! Run with `cargo bench --features splice --bench splice`.
+
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use leaf::app::splice::Splice;
+
+const TOTAL: usize = 4 * 1024 * 1024 * 1024;
+const CHUNK: usize = 256 * 1024;
+
+fn cpu_time() -> Duration {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+    let tv = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+    };
+    tv(usage.ru_utime) + tv(usage.ru_stime)
+}
+
+async fn socket_pair() -> (TcpStream, TcpStream) {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (a, b) = tokio::join!(TcpStream::connect(addr), listener.accept());
+    (a.unwrap(), b.unwrap().0)
+}
+
+// Sends TOTAL bytes from a client to a server through a relay.
+async fn run(name: &str, splice: bool) {
+    let (mut client, a) = socket_pair().await;
+    let (c, mut server) = socket_pair().await;
+
+    let cpu = cpu_time();
+    let start = Instant::now();
+    let relay = if splice {
+        let relay = Splice::new(a.as_raw_fd(), c.as_raw_fd()).unwrap();
+        drop(a);
+        drop(c);
+        tokio::spawn(async move { relay.await.unwrap() })
+    } else {
+        tokio::spawn(async move {
+            let (mut ar, _aw) = tokio::io::split(a);
+            let (_cr, mut cw) = tokio::io::split(c);
+            let n = tokio::io::copy(&mut ar, &mut cw).await.unwrap();
+            cw.shutdown().await.unwrap();
+            n
+        })
+    };
+    let writer = tokio::spawn(async move {
+        let buf = vec![0u8; CHUNK];
+        for _ in 0..TOTAL / CHUNK {
+            client.write_all(&buf).await.unwrap();
+        }
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+    });
+    let mut buf = vec![0u8; CHUNK];
+    let mut received = 0;
+    loop {
+        let n = server.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        received += n;
+    }
+    writer.await.unwrap();
+    relay.await.unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(received, TOTAL);
+
+    println!(
+        "{:<8} {:>8.1} MB/s {:>8}ms cpu",
+        name,
+        TOTAL as f64 / 1024.0 / 1024.0 / elapsed.as_secs_f64(),
+        (cpu_time() - cpu).as_millis()
+    );
+}
+
+fn main() {
+    let mut rt = tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        run("copy", false).await;
+        run("splice", true).await;
+    });
+}
@@ -0,0 +1,104 @@
+//! Compares the syscalls and CPU time of sending and receiving datagrams one
+//! at a time against batches with sendmmsg(2) and recvmmsg(2).
+//!
+//! Run with `cargo bench --features udp-mmsg --bench udp_mmsg`.
+
+use std::net::{SocketAddr, UdpSocket};
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use leaf::proxy::direct::mmsg::{recv_mmsg, send_mmsg, BATCH_SIZE, MAX_DATAGRAM_SIZE};
+
+const DATAGRAMS: usize = 1_000_000;
+const DATAGRAM_SIZE: usize = 64;
+
+fn cpu_time() -> Duration {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) };
+    let tv = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+    };
+    tv(usage.ru_utime) + tv(usage.ru_stime)
+}
+
+// Sends DATAGRAMS datagrams from one socket to another, returns the number
+// received and the syscalls made by each side. Datagrams dropped when the
+// receiver falls behind end the receive through its timeout.
+fn transfer(batched: bool) -> (usize, usize, usize) {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    let target: SocketAddr = receiver.local_addr().unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    let recv = thread::spawn(move || {
+        let mut received = 0;
+        let mut syscalls = 0;
+        if batched {
+            let mut bufs = vec![vec![0u8; MAX_DATAGRAM_SIZE]; BATCH_SIZE];
+            let mut got = Vec::with_capacity(BATCH_SIZE);
+            while received < DATAGRAMS {
+                syscalls += 1;
+                match recv_mmsg(receiver.as_raw_fd(), &mut bufs, &mut got) {
+                    Ok(n) => received += n,
+                    Err(_) => break,
+                }
+            }
+        } else {
+            let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+            while received < DATAGRAMS {
+                syscalls += 1;
+                match receiver.recv_from(&mut buf) {
+                    Ok(_) => received += 1,
+                    Err(_) => break,
+                }
+            }
+        }
+        (received, syscalls)
+    });
+
+    let payload = [0u8; DATAGRAM_SIZE];
+    let mut syscalls = 0;
+    if batched {
+        let pkts: Vec<(&[u8], SocketAddr)> =
+            (0..BATCH_SIZE).map(|_| (&payload[..], target)).collect();
+        let mut sent = 0;
+        while sent < DATAGRAMS {
+            let n = BATCH_SIZE.min(DATAGRAMS - sent);
+            syscalls += 1;
+            sent += send_mmsg(sender.as_raw_fd(), &pkts[..n]).unwrap();
+        }
+    } else {
+        for _ in 0..DATAGRAMS {
+            syscalls += 1;
+            sender.send_to(&payload, &target).unwrap();
+        }
+    }
+
+    let (received, recv_syscalls) = recv.join().unwrap();
+    (received, syscalls, recv_syscalls)
+}
+
+fn run(name: &str, batched: bool) {
+    let cpu = cpu_time();
+    let start = Instant::now();
+    let (received, send_syscalls, recv_syscalls) = transfer(batched);
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<10} {:>8} received {:>8} send {:>8} recv syscalls {:>6.0} kpps {:>6}ms cpu",
+        name,
+        received,
+        send_syscalls,
+        recv_syscalls,
+        DATAGRAMS as f64 / 1000.0 / elapsed.as_secs_f64(),
+        (cpu_time() - cpu).as_millis()
+    );
+}
+
+fn main() {
+    run("unbatched", false);
+    run("batched", true);
+}
@@ -0,0 +1,313 @@
+//! Captures the payload of selected TCP connections to files, for debugging
+//! protocol issues.
+//!
+//! A capture file holds the records of a single connection, each record is
+//! the time in microseconds since the Unix epoch (8 bytes), the direction (1
+//! byte, 0 for up and 1 for down), the data length (4 bytes) and the data,
+//! integers in big-endian. Records are written from a thread of their own and
+//! dropped rather than slowing down the connection when the thread can't
+//! keep up.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc::{self, error::TrySendError, Sender};
+
+use crate::{proxy::ProxyStream, session::Session};
+
+/// Number of records buffered for the file before new ones are dropped.
+pub const CAPTURE_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// From the client to the remote peer.
+    Up,
+    /// From the remote peer to the client.
+    Down,
+}
+
+/// Bytes read or written on a captured connection.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    /// Microseconds since the Unix epoch.
+    pub micros: u64,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+/// Picks the connections to capture, called with the id of the connection,
+/// its session and the tag of its outbound.
+pub type CaptureFilter = Arc<dyn Fn(u64, &Session, &str) -> bool + Send + Sync>;
+
+/// Captures the connections `filter` picks into `dir`, the file of a
+/// connection is named after its id, e.g. `42.cap`.
+#[derive(Clone)]
+pub struct CaptureRule {
+    pub dir: PathBuf,
+    pub filter: CaptureFilter,
+}
+
+impl CaptureRule {
+    /// Captures the connections dispatched to the outbound tagged `tag`.
+    pub fn outbound<P: Into<PathBuf>>(dir: P, tag: String) -> Self {
+        CaptureRule {
+            dir: dir.into(),
+            filter: Arc::new(move |_, _, outbound| outbound == tag),
+        }
+    }
+
+    /// Captures the connection with the id `conn` alone.
+    pub fn connection<P: Into<PathBuf>>(dir: P, conn: u64) -> Self {
+        CaptureRule {
+            dir: dir.into(),
+            filter: Arc::new(move |id, _, _| id == conn),
+        }
+    }
+
+    /// Returns the capture of connection `conn` if the rule picks it.
+    pub fn capture(&self, conn: u64, sess: &Session, outbound: &str) -> Option<Capture> {
+        if !(self.filter)(conn, sess, outbound) {
+            return None;
+        }
+        let path = self.dir.join(format!("{}.cap", conn));
+        match Capture::file(&path) {
+            Ok(capture) => {
+                debug!(
+                    "capturing tcp {} -> {} to {}",
+                    &sess.source,
+                    &sess.destination,
+                    path.display()
+                );
+                Some(capture)
+            }
+            Err(e) => {
+                warn!("create capture {} failed: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// The sending side of a capture file.
+pub struct Capture {
+    tx: Sender<Record>,
+}
+
+impl Capture {
+    /// Captures into the file at `path`, truncated if it exists.
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        let (tx, mut rx) = mpsc::channel::<Record>(CAPTURE_CAPACITY);
+        std::thread::spawn(move || {
+            while let Some(record) = futures::executor::block_on(rx.recv()) {
+                let mut buf = Vec::with_capacity(13 + record.data.len());
+                buf.extend_from_slice(&record.micros.to_be_bytes());
+                buf.push(match record.direction {
+                    Direction::Up => 0,
+                    Direction::Down => 1,
+                });
+                buf.extend_from_slice(&(record.data.len() as u32).to_be_bytes());
+                buf.extend_from_slice(&record.data);
+                if let Err(e) = file.write_all(&buf) {
+                    warn!("write capture failed: {}", e);
+                    return;
+                }
+            }
+        });
+        Ok(Capture { tx })
+    }
+
+    /// Records `data`, unless the capture is full or gone.
+    pub fn send(&mut self, direction: Direction, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let record = Record {
+            micros,
+            direction,
+            data: data.to_vec(),
+        };
+        match self.tx.try_send(record) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => trace!("capture full, record dropped"),
+            Err(TrySendError::Closed(_)) => (),
+        }
+    }
+}
+
+/// Reads the records of a capture file.
+pub fn read_records<R: Read>(mut r: R) -> io::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    let mut head = [0u8; 13];
+    loop {
+        match r.read_exact(&mut head) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(records),
+            Err(e) => return Err(e),
+        }
+        let mut micros = [0u8; 8];
+        micros.copy_from_slice(&head[..8]);
+        let direction = match head[8] {
+            0 => Direction::Up,
+            1 => Direction::Down,
+            d => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid direction {}", d),
+                ))
+            }
+        };
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&head[9..]);
+        let mut data = vec![0u8; u32::from_be_bytes(len) as usize];
+        r.read_exact(&mut data)?;
+        records.push(Record {
+            micros: u64::from_be_bytes(micros),
+            direction,
+            data,
+        });
+    }
+}
+
+/// Records the bytes written to and read from the outbound stream of a
+/// connection, as up and down.
+///
+/// The socket is never exposed to splice(2), which would bypass the capture.
+pub struct CaptureStream<S> {
+    inner: S,
+    capture: Capture,
+}
+
+impl<S> CaptureStream<S> {
+    pub fn new(inner: S, capture: Capture) -> Self {
+        CaptureStream { inner, capture }
+    }
+}
+
+impl<S: ProxyStream> ProxyStream for CaptureStream<S> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CaptureStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let res = AsyncRead::poll_read(Pin::new(&mut me.inner), cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            me.capture.send(Direction::Down, &buf[..n]);
+        }
+        res
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CaptureStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let res = AsyncWrite::poll_write(Pin::new(&mut me.inner), cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            me.capture.send(Direction::Up, &buf[..n]);
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::stream::SimpleStream;
+    use crate::session::SocksAddr;
+
+    #[tokio::test]
+    async fn test_capture_both_directions() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(b"\x00\x01world").await.unwrap();
+        });
+
+        let dir = std::env::temp_dir().join(format!("leaf-capture-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rule = CaptureRule::connection(&dir, 7);
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        assert!(rule.capture(6, &sess, "direct").is_none());
+        let capture = rule.capture(7, &sess, "direct").unwrap();
+
+        let stream = SimpleStream(TcpStream::connect(addr).await.unwrap());
+        let mut stream = CaptureStream::new(stream, capture);
+        stream.write_all(b"hello").await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        // Relayed as is.
+        assert_eq!(buf, b"\x00\x01world");
+        drop(stream);
+
+        let path = dir.join("7.cap");
+        let mut records = Vec::new();
+        for _ in 0..100 {
+            // The last record may be half written.
+            if let Ok(r) = read_records(File::open(&path).unwrap()) {
+                records = r;
+            }
+            let down: usize = records
+                .iter()
+                .filter(|r| r.direction == Direction::Down)
+                .map(|r| r.data.len())
+                .sum();
+            if down == 7 {
+                break;
+            }
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let bytes = |direction| -> Vec<u8> {
+            records
+                .iter()
+                .filter(|r| r.direction == direction)
+                .flat_map(|r| r.data.clone())
+                .collect()
+        };
+        assert_eq!(bytes(Direction::Up), b"hello");
+        assert_eq!(bytes(Direction::Down), b"\x00\x01world");
+        assert!(records.windows(2).all(|w| w[0].micros <= w[1].micros));
+        assert!(records[0].micros > 0);
+    }
+}
@@ -1,15 +1,17 @@
 use std::cmp::min;
 use std::io::{self, ErrorKind};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
 use byteorder::{BigEndian, ByteOrder};
 use bytes::BytesMut;
-use futures::future::{self, try_select, Either, Future, FutureExt, TryFutureExt};
+use futures::future::{try_select, BoxFuture, Either, Future};
 use futures::ready;
 use log::*;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::timeout;
@@ -18,13 +20,26 @@ use tokio::time::timeout;
 use colored::Colorize;
 
 use crate::{
+    common::buf_pool::{self, PooledBuf},
     option,
-    proxy::{stream::SimpleStream, ProxyDatagram, ProxyHandlerType, ProxyStream},
+    proxy::{
+        datagram,
+        error::FailureStage,
+        stream::{IdleTimeoutStream, SimpleStream},
+        ProxyHandlerType, ProxyStream,
+    },
     session::{Session, SocksAddr},
 };
 
+#[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+use crate::common::fake_dns::FakeDns;
+
+use super::capture::{CaptureRule, CaptureStream};
 use super::handler_manager::HandlerManager;
+use super::metrics::{self, CloseReason, ConnStats, CountingDatagram, CountingStream};
 use super::router::Router;
+#[cfg(all(target_os = "linux", feature = "splice"))]
+use super::splice::{IdleClock, Splice};
 
 struct SniffingStream<T> {
     inner: T,
@@ -33,8 +48,31 @@ struct SniffingStream<T> {
 
 impl<T> SniffingStream<T>
 where
-    T: AsyncRead + AsyncWrite + Unpin,
+    T: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
 {
+    // Unwraps a plain TCP stream so it can be relayed with splice(2), the
+    // data read while sniffing is returned along with it.
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn into_proxy_stream(self) -> (Box<dyn ProxyStream>, BytesMut) {
+        let SniffingStream { inner, buf } = self;
+        let inner: Box<dyn std::any::Any> = Box::new(inner);
+        match inner.downcast::<tokio::net::TcpStream>() {
+            Ok(stream) => (Box::new(SimpleStream(*stream)), buf),
+            Err(inner) => {
+                let inner = *inner.downcast::<T>().unwrap();
+                (
+                    Box::new(SimpleStream(SniffingStream { inner, buf })),
+                    BytesMut::new(),
+                )
+            }
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "splice")))]
+    fn into_proxy_stream(self) -> (Box<dyn ProxyStream>, BytesMut) {
+        (Box::new(SimpleStream(self)), BytesMut::new())
+    }
+
     pub fn new(inner: T) -> Self {
         SniffingStream {
             inner,
@@ -43,7 +81,7 @@ where
     }
 
     pub async fn sniff(&mut self) -> io::Result<Option<String>> {
-        let mut buf = vec![0u8; 2 * 1024];
+        let mut buf = buf_pool::get(2 * 1024);
         'outer: for _ in 0..2 {
             match timeout(Duration::from_millis(100), self.inner.read(&mut buf)).await {
                 Ok(res) => match res {
@@ -207,18 +245,31 @@ impl<T: AsyncWrite + Unpin> AsyncWrite for SniffingStream<T> {
     }
 }
 
-// The same as tokio::io::copy(), except it takes ownership of the reader and writer.
-struct Transfer<R, W> {
+// Consecutive reads filling the buffer before it's grown.
+const FULL_READS_TO_GROW: u32 = 4;
+
+/// The same as `tokio::io::copy()`, except it takes ownership of the reader
+/// and writer and tunes its buffer.
+///
+/// Reads filling the whole buffer mean more data is waiting than the buffer
+/// can take, on a link with a large bandwidth-delay product a small buffer
+/// caps the throughput. The buffer doubles after a few such reads in a row,
+/// up to a maximum.
+pub struct Transfer<R, W> {
     reader: R,
     read_done: bool,
     writer: W,
     pos: usize,
     cap: usize,
     amt: u64,
-    buf: Box<[u8]>,
+    buf: PooledBuf<'static>,
+    max_buf_size: usize,
+    full_reads: u32,
 }
 
-fn transfer<R, W>(reader: R, writer: W) -> Transfer<R, W>
+/// Copies `reader` to `writer` with a buffer of `buf_size` bytes, growing up
+/// to `max_buf_size` bytes.
+pub fn transfer<R, W>(reader: R, writer: W, buf_size: usize, max_buf_size: usize) -> Transfer<R, W>
 where
     R: AsyncRead + Unpin,
     W: AsyncWrite + Unpin,
@@ -230,7 +281,31 @@ where
         amt: 0,
         pos: 0,
         cap: 0,
-        buf: vec![0; 2048].into_boxed_slice(),
+        buf: buf_pool::get(buf_size),
+        max_buf_size,
+        full_reads: 0,
+    }
+}
+
+impl<R, W> Transfer<R, W> {
+    /// Returns the current size of the buffer.
+    pub fn buf_size(&self) -> usize {
+        self.buf.len()
+    }
+
+    // Called with the buffer drained, after a read of `n` bytes.
+    fn tune(&mut self, n: usize) {
+        if n < self.buf.len() {
+            self.full_reads = 0;
+            return;
+        }
+        self.full_reads += 1;
+        if self.full_reads >= FULL_READS_TO_GROW && self.buf.len() < self.max_buf_size {
+            let size = min(self.buf.len() * 2, self.max_buf_size);
+            trace!("relay buffer grown to {} bytes", size);
+            self.buf = buf_pool::get(size);
+            self.full_reads = 0;
+        }
     }
 }
 
@@ -270,18 +345,162 @@ where
                     self.amt += i as u64;
                 }
             }
+            // Drained, the buffer can be swapped for a larger one.
+            if self.cap > 0 {
+                let n = self.cap;
+                self.tune(n);
+                self.pos = 0;
+                self.cap = 0;
+            }
 
             // If we've written all the data and we've seen EOF, flush out the
-            // data and finish the transfer.
+            // data, pass the EOF on and finish the transfer. The other
+            // direction is left open, the peer may still be sending.
             if self.pos == self.cap && self.read_done {
                 let me = &mut *self;
                 ready!(Pin::new(&mut me.writer).poll_flush(cx))?;
+                ready!(Pin::new(&mut me.writer).poll_shutdown(cx))?;
                 return Poll::Ready(Ok(self.amt));
             }
         }
     }
 }
 
+type RelayHalf = Pin<Box<dyn Future<Output = io::Result<u64>> + Send>>;
+
+// Returns the uplink and downlink of the relay, `sniffed` is the data already
+// read from the inbound stream. Each half starts with a buffer of `buf_size`
+// bytes.
+// Bytes relayed with splice(2) are reported to `stats`, they don't go through
+// the counting stream.
+#[cfg_attr(
+    not(all(target_os = "linux", feature = "splice")),
+    allow(unused_variables)
+)]
+fn relay(
+    lhs: Box<dyn ProxyStream>,
+    rhs: Box<dyn ProxyStream>,
+    sniffed: BytesMut,
+    stats: Arc<ConnStats>,
+    buf_size: usize,
+) -> (RelayHalf, RelayHalf) {
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    {
+        if let (Some(lfd), Some(rfd)) = (lhs.tcp_fd(), rhs.tcp_fd()) {
+            match (Splice::new(lfd, rfd), Splice::new(rfd, lfd)) {
+                (Ok(mut l2r), Ok(mut r2l)) => {
+                    trace!("relaying with splice");
+                    // The streams aren't polled anymore, their idle timeout
+                    // moves to the splices, shared by both directions.
+                    let idle_timeout = match (lhs.idle_timeout(), rhs.idle_timeout()) {
+                        (Some(l), Some(r)) => Some(l.min(r)),
+                        (l, r) => l.or(r),
+                    };
+                    if let Some(timeout) = idle_timeout {
+                        let clock = IdleClock::new(timeout);
+                        l2r = l2r.idle_timeout(clock.clone());
+                        r2l = r2l.idle_timeout(clock);
+                    }
+                    // The relay holds its own copies of the sockets.
+                    drop(lhs);
+                    let up_stats = stats.clone();
+                    let l2r = async move {
+                        let mut rhs = rhs;
+                        rhs.write_all(&sniffed).await?;
+                        drop(rhs);
+                        let n = l2r.await?;
+                        up_stats.add_sent(n);
+                        Ok(sniffed.len() as u64 + n)
+                    };
+                    let r2l = async move {
+                        let n = r2l.await?;
+                        stats.add_received(n);
+                        Ok(n)
+                    };
+                    return (Box::pin(l2r), Box::pin(r2l));
+                }
+                (Err(e), _) | (_, Err(e)) => debug!("splice not available: {}", e),
+            }
+        }
+    }
+    let (lr, lw) = tokio::io::split(lhs);
+    let (rr, mut rw) = tokio::io::split(rhs);
+    let max_buf_size = buf_size.max(option::MAX_RELAY_BUFFER_SIZE);
+    let l2r = async move {
+        rw.write_all(&sniffed).await?;
+        Ok(sniffed.len() as u64 + transfer(lr, rw, buf_size, max_buf_size).await?)
+    };
+    (
+        Box::pin(l2r),
+        Box::pin(transfer(rr, lw, buf_size, max_buf_size)),
+    )
+}
+
+// Runs both halves of a relay. A half which reaches EOF half-closes its
+// destination, the other half goes on until its own EOF, half-closed
+// connections left idle are closed by the idle timeout of the streams. An
+// error on either half ends the relay.
+// Why the relay ended is recorded to `stats`: an error of either half, else
+// the side which closed first.
+async fn join_relay(
+    l2r: RelayHalf,
+    r2l: RelayHalf,
+    stats: Arc<ConnStats>,
+) -> io::Result<(io::Result<u64>, io::Result<u64>)> {
+    let stats_ref = &stats;
+    let finish = move |rest: RelayHalf, first: CloseReason| async move {
+        let res = rest.await;
+        match &res {
+            Ok(_) => stats_ref.set_close_reason(first),
+            Err(e) => stats_ref.set_close_reason(CloseReason::from_error(e)),
+        }
+        res
+    };
+    match try_select(l2r, r2l).await {
+        Ok(Either::Left((up_n, r2l))) => Ok((Ok(up_n), finish(r2l, CloseReason::ClientEof).await)),
+        Ok(Either::Right((down_n, l2r))) => {
+            Ok((finish(l2r, CloseReason::ServerEof).await, Ok(down_n)))
+        }
+        Err(Either::Left((up_e, _))) => {
+            stats.set_close_reason(CloseReason::from_error(&up_e));
+            Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!("uplink error: {}", up_e),
+            ))
+        }
+        Err(Either::Right((down_e, _))) => {
+            stats.set_close_reason(CloseReason::from_error(&down_e));
+            Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!("downlink error: {}", down_e),
+            ))
+        }
+    }
+}
+
+// Logs the end of a TCP connection once dropped. A connection without a
+// close reason never finished relaying, its dispatch was dropped.
+struct CloseLog {
+    stats: Arc<ConnStats>,
+    source: std::net::SocketAddr,
+    destination: SocksAddr,
+    tag: String,
+}
+
+impl Drop for CloseLog {
+    fn drop(&mut self) {
+        debug!(
+            "tcp {} <-> {} closed by {}, {} bytes sent, {} bytes received [{}]",
+            self.source,
+            self.destination,
+            self.stats.close_reason().unwrap_or(CloseReason::Forced),
+            self.stats.sent(),
+            self.stats.received(),
+            self.tag,
+        );
+    }
+}
+
 fn log_tcp(tag: &str, tag_color: colored::Color, handshake_time: u128, addr: &SocksAddr) {
     #[cfg(not(target_os = "ios"))]
     {
@@ -316,9 +535,28 @@ fn log_udp(tag: &str, tag_color: colored::Color, handshake_time: u128, addr: &So
     }
 }
 
+/// What to do with a connection, as decided by a `PreDispatchHook`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decision {
+    /// Dispatch to the outbound picked by the router.
+    Allow,
+    /// Refuse the connection.
+    Reject,
+    /// Dispatch to the outbound with this tag, bypassing the router.
+    RouteTo(String),
+}
+
+/// Called on every new TCP connection and UDP session before an outbound is
+/// picked, e.g. to check a quota or ask the user. For TCP, the destination
+/// is the sniffed domain if there's one.
+pub type PreDispatchHook = Arc<dyn Fn(&Session) -> BoxFuture<'static, Decision> + Send + Sync>;
+
 pub struct Dispatcher {
     handler_manager: HandlerManager,
     router: Router,
+    pre_dispatch: Option<PreDispatchHook>,
+    capture: Option<CaptureRule>,
+    next_conn: AtomicU64,
     endpoint_tcp_tx: TokioMutex<Sender<bool>>,
     endpoint_tcp_rx: TokioMutex<Receiver<bool>>,
     direct_tcp_tx: TokioMutex<Sender<bool>>,
@@ -334,6 +572,9 @@ impl Dispatcher {
         Dispatcher {
             handler_manager,
             router,
+            pre_dispatch: None,
+            capture: None,
+            next_conn: AtomicU64::new(1),
             endpoint_tcp_tx: TokioMutex::new(endpoint_tcp_tx),
             endpoint_tcp_rx: TokioMutex::new(endpoint_tcp_rx),
             direct_tcp_tx: TokioMutex::new(direct_tcp_tx),
@@ -343,6 +584,82 @@ impl Dispatcher {
         }
     }
 
+    /// The fake DNS shared with the outbounds, see `HandlerManager::fake_dns`.
+    #[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+    pub fn fake_dns(&self) -> Arc<TokioMutex<FakeDns>> {
+        self.handler_manager.fake_dns()
+    }
+
+    /// Runs `hook` ahead of the routing of every connection.
+    pub fn set_pre_dispatch_hook(&mut self, hook: Option<PreDispatchHook>) {
+        self.pre_dispatch = hook;
+    }
+
+    /// Captures the payload of the TCP connections `rule` picks. Connections
+    /// are numbered from 1 in the order they're dispatched, the id of a
+    /// connection is logged along with its outbound.
+    pub fn set_capture(&mut self, rule: Option<CaptureRule>) {
+        self.capture = rule;
+    }
+
+    // `udp` picks a route for a UDP session, which never has an SNI.
+    async fn pick_outbound(
+        &self,
+        sess: &Session,
+        sni: Option<&str>,
+        udp: bool,
+    ) -> io::Result<String> {
+        if let Some(hook) = &self.pre_dispatch {
+            match hook(sess).await {
+                Decision::Allow => (),
+                Decision::Reject => {
+                    debug!(
+                        "rejected {} -> {} by pre-dispatch hook",
+                        &sess.source, &sess.destination
+                    );
+                    return Err(io::Error::new(
+                        ErrorKind::PermissionDenied,
+                        "rejected by pre-dispatch hook",
+                    ));
+                }
+                Decision::RouteTo(tag) => {
+                    debug!(
+                        "hook picked route [{}] for {} -> {}",
+                        &tag, &sess.source, &sess.destination
+                    );
+                    return Ok(tag);
+                }
+            }
+        }
+
+        let route = if udp {
+            self.router.pick_udp_route(sess)
+        } else {
+            self.router.pick_route(sess, sni)
+        };
+        match route {
+            Ok(tag) => {
+                debug!(
+                    "picked route [{}] for {} -> {}",
+                    tag, &sess.source, &sess.destination
+                );
+                Ok(tag)
+            }
+            Err(err) => {
+                trace!("pick route failed: {}", err);
+                if let Some(tag) = self.handler_manager.default_handler() {
+                    debug!(
+                        "picked default route [{}] for {} -> {}",
+                        tag, &sess.source, &sess.destination
+                    );
+                    Ok(tag.to_owned())
+                } else {
+                    Err(io::Error::new(ErrorKind::Other, "no available handler"))
+                }
+            }
+        }
+    }
+
     async fn dispatch_endpoint_tcp_start(&self) {
         match self.endpoint_tcp_tx.lock().await.send(true).await {
             Ok(_) => (),
@@ -397,42 +714,30 @@ impl Dispatcher {
     where
         T: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
     {
-        let lhs: Box<dyn ProxyStream> =
-            if sess.destination.is_domain() && sess.destination.port() == 443 {
-                Box::new(SimpleStream(lhs))
-            } else {
-                let mut lhs = SniffingStream::new(lhs);
-                if let Some(domain) = lhs.sniff().await? {
-                    debug!("sniffed domain {}", &domain);
-                    sess.destination = SocksAddr::from((domain, sess.destination.port()));
-                }
-                Box::new(SimpleStream(lhs))
-            };
-
-        let outbound = match self.router.pick_route(&sess) {
-            Ok(tag) => {
-                debug!(
-                    "picked route [{}] for {} -> {}",
-                    tag, &sess.source, &sess.destination
-                );
-                tag
-            }
-            Err(err) => {
-                trace!("pick route failed: {}", err);
-                if let Some(tag) = self.handler_manager.default_handler() {
-                    debug!(
-                        "picked default route [{}] for {} -> {}",
-                        tag, &sess.source, &sess.destination
-                    );
-                    tag
-                } else {
-                    return Err(io::Error::new(ErrorKind::Other, "no available handler"));
+        // Domain destinations on 443 are sniffed only for SNI rules, and keep
+        // their destination.
+        let keep_domain = sess.destination.is_domain() && sess.destination.port() == 443;
+        let mut sni = None;
+        let (lhs, sniffed): (Box<dyn ProxyStream>, BytesMut) = if keep_domain
+            && !self.router.has_sni_rules()
+        {
+            (Box::new(SimpleStream(lhs)), BytesMut::new())
+        } else {
+            let mut lhs = SniffingStream::new(lhs);
+            if let Some(domain) = lhs.sniff().await? {
+                debug!("sniffed domain {}", &domain);
+                if !keep_domain {
+                    sess.destination = SocksAddr::from((domain.clone(), sess.destination.port()));
                 }
+                sni = Some(domain);
             }
+            lhs.into_proxy_stream()
         };
 
+        let outbound = self.pick_outbound(sess, sni.as_deref(), false).await?;
+
         let handshake_start = tokio::time::Instant::now();
-        if let Some(h) = self.handler_manager.get(outbound) {
+        if let Some(h) = self.handler_manager.get(&outbound) {
             match h.handler_type() {
                 ProxyHandlerType::Direct => self.dispatch_direct_tcp_start().await,
                 ProxyHandlerType::Endpoint | ProxyHandlerType::Ensemble => {
@@ -445,57 +750,44 @@ impl Dispatcher {
                     let elapsed = tokio::time::Instant::now().duration_since(handshake_start);
                     log_tcp(h.tag(), h.color(), elapsed.as_millis(), &sess.destination);
 
-                    let (lr, lw) = tokio::io::split(lhs);
-                    let (rr, rw) = tokio::io::split(rhs);
-
-                    let r2l = transfer(rr, lw);
-                    let l2r = transfer(lr, rw);
-
-                    type TransferResult = Box<
-                        dyn Future<Output = io::Result<(io::Result<u64>, io::Result<u64>)>>
-                            + Unpin
-                            + Send,
-                    >;
-                    let transfer = try_select(l2r, r2l).then(|res| -> TransferResult {
-                        match res {
-                            Ok(Either::Left((up_n, r2l))) => {
-                                let timed_r2l =
-                                    timeout(Duration::from_secs(option::TCP_DOWNLINK_TIMEOUT), r2l);
-                                let timed_r2l = timed_r2l.map_ok(move |down_res| match down_res {
-                                    Ok(down_n) => (Ok(up_n), Ok(down_n)),
-                                    Err(down_e) => (Ok(up_n), Err(down_e)),
-                                });
-                                let timed_r2l = timed_r2l.map_err(|_to| {
-                                    io::Error::new(io::ErrorKind::TimedOut, "downlink timeout")
-                                });
-                                Box::new(timed_r2l)
-                            }
-                            Ok(Either::Right((down_n, l2r))) => {
-                                let timed_l2r =
-                                    timeout(Duration::from_secs(option::TCP_UPLINK_TIMEOUT), l2r);
-                                let timed_l2r = timed_l2r.map_ok(move |up_res| match up_res {
-                                    Ok(up_n) => (Ok(up_n), Ok(down_n)),
-                                    Err(up_e) => (Err(up_e), Ok(down_n)),
-                                });
-                                let timed_l2r = timed_l2r.map_err(|_to| {
-                                    io::Error::new(io::ErrorKind::TimedOut, "uplink timeout")
-                                });
-                                Box::new(timed_l2r)
-                            }
-                            Err(Either::Left((up_e, _))) => Box::new(future::err(io::Error::new(
-                                io::ErrorKind::Interrupted,
-                                format!("uplink error: {}", up_e),
-                            ))),
-                            Err(Either::Right((down_e, _))) => {
-                                Box::new(future::err(io::Error::new(
-                                    io::ErrorKind::Interrupted,
-                                    format!("downlink error: {}", down_e),
-                                )))
-                            }
-                        }
-                    });
+                    let conn = self.next_conn.fetch_add(1, Ordering::Relaxed);
+                    debug!(
+                        "tcp {} -> {} is connection {} [{}]",
+                        &sess.source,
+                        &sess.destination,
+                        conn,
+                        h.tag()
+                    );
+                    let capture = self
+                        .capture
+                        .as_ref()
+                        .and_then(|rule| rule.capture(conn, sess, h.tag()));
+                    let rhs: Box<dyn ProxyStream> = match capture {
+                        Some(capture) => Box::new(CaptureStream::new(rhs, capture)),
+                        None => rhs,
+                    };
 
-                    match transfer.await {
+                    let registry = metrics::registry();
+                    registry.observe_tcp_handshake(elapsed);
+                    let stats = registry.tcp_connection();
+                    let idle_timeout = Duration::from_secs(option::TCP_IDLE_TIMEOUT);
+                    let lhs = Box::new(IdleTimeoutStream::new(lhs, idle_timeout));
+                    let rhs = IdleTimeoutStream::new(rhs, idle_timeout);
+                    let _close_log = CloseLog {
+                        stats: stats.clone(),
+                        source: sess.source,
+                        destination: sess.destination.clone(),
+                        tag: h.tag().clone(),
+                    };
+                    let rhs = Box::new(CountingStream::new(rhs, stats.clone()));
+                    let buf_size = self.handler_manager.relay_buffer_size(&outbound);
+                    let (l2r, r2l) = relay(lhs, rhs, sniffed, stats.clone(), buf_size);
+
+                    let relayed = join_relay(l2r, r2l, stats.clone()).await;
+                    if stats.received() == 0 && !matches!(relayed, Ok((_, Ok(_)))) {
+                        registry.tcp_failure(h.tag(), FailureStage::FirstByte);
+                    }
+                    match relayed {
                         Ok((up_res, down_res)) => {
                             match up_res {
                                 Ok(up_n) => {
@@ -566,6 +858,7 @@ impl Dispatcher {
                         &h.tag(),
                         e
                     );
+                    metrics::registry().tcp_failure(h.tag(), FailureStage::of(&e));
 
                     match h.handler_type() {
                         ProxyHandlerType::Direct => self.dispatch_direct_tcp_done().await,
@@ -583,37 +876,20 @@ impl Dispatcher {
         }
     }
 
-    pub async fn dispatch_udp(&self, sess: &Session) -> io::Result<Box<dyn ProxyDatagram>> {
-        let outbound = match self.router.pick_route(&sess) {
-            Ok(tag) => {
-                debug!(
-                    "picked route [{}] for {} -> {}",
-                    tag, &sess.source, &sess.destination
-                );
-                tag
-            }
-            Err(err) => {
-                trace!("pick route failed: {}", err);
-                if let Some(tag) = self.handler_manager.default_handler() {
-                    debug!(
-                        "picked default route [{}] for {} -> {}",
-                        tag, &sess.source, &sess.destination
-                    );
-                    tag
-                } else {
-                    return Err(io::Error::new(ErrorKind::Other, "no available handler"));
-                }
-            }
-        };
+    /// Connects a UDP session, the stats of the returned datagram are the
+    /// session's record, to which the caller reports why it ended.
+    pub async fn dispatch_udp(&self, sess: &Session) -> io::Result<CountingDatagram> {
+        let outbound = self.pick_outbound(sess, None, true).await?;
 
         let handshake_start = tokio::time::Instant::now();
 
-        if let Some(h) = self.handler_manager.get(outbound) {
-            match h.connect(sess, None, None).await {
+        if let Some(h) = self.handler_manager.get(&outbound) {
+            match datagram::connect_packets(&**h, sess).await {
                 Ok(c) => {
                     let elapsed = tokio::time::Instant::now().duration_since(handshake_start);
                     log_udp(h.tag(), h.color(), elapsed.as_millis(), &sess.destination);
-                    Ok(c)
+                    let stats = metrics::registry().udp_session();
+                    Ok(CountingDatagram::new(c, stats))
                 }
                 Err(e) => {
                     debug!(
@@ -631,3 +907,506 @@ impl Dispatcher {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (a, b) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (a.unwrap(), b.unwrap().0)
+    }
+
+    fn plain(s: TcpStream) -> Box<dyn ProxyStream> {
+        Box::new(SimpleStream(s))
+    }
+
+    // Hides the socket, so the relay can't splice.
+    fn wrapped(s: TcpStream) -> Box<dyn ProxyStream> {
+        Box::new(SimpleStream(SimpleStream(s)))
+    }
+
+    // Reads `chunk` bytes at a time.
+    struct Trickle {
+        left: usize,
+        chunk: usize,
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let n = min(min(self.left, self.chunk), buf.len());
+            buf[..n].iter_mut().for_each(|b| *b = 1);
+            self.left -= n;
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_buffer_tuning() {
+        // Reads filling the buffer grow it, up to the maximum.
+        let reader = Trickle {
+            left: 1024 * 1024,
+            chunk: usize::MAX,
+        };
+        let mut t = transfer(reader, tokio::io::sink(), 2048, 16 * 1024);
+        assert_eq!((&mut t).await.unwrap(), 1024 * 1024);
+        assert_eq!(t.buf_size(), 16 * 1024);
+
+        // Short reads don't.
+        let reader = Trickle {
+            left: 1024 * 1024,
+            chunk: 1000,
+        };
+        let mut t = transfer(reader, tokio::io::sink(), 2048, 16 * 1024);
+        assert_eq!((&mut t).await.unwrap(), 1024 * 1024);
+        assert_eq!(t.buf_size(), 2048);
+    }
+
+    // client -> (a, b) -> relay -> (c, d) -> server
+    async fn check_half_closed_upload(wrap: fn(TcpStream) -> Box<dyn ProxyStream>) {
+        let (mut client, a) = tcp_pair().await;
+        let (c, mut server) = tcp_pair().await;
+        let stats = metrics::Registry::new().tcp_connection();
+        let (l2r, r2l) = relay(
+            wrap(a),
+            wrap(c),
+            BytesMut::from(&b"GE"[..]),
+            stats.clone(),
+            option::RELAY_BUFFER_SIZE,
+        );
+        let relay = tokio::spawn(join_relay(l2r, r2l, stats.clone()));
+
+        let response: Vec<u8> = (0..4 * 1024 * 1024).map(|i| i as u8).collect();
+        let expected = response.clone();
+        let server = tokio::spawn(async move {
+            // The request ends with the EOF of the upload.
+            let mut request = Vec::new();
+            server.read_to_end(&mut request).await.unwrap();
+            assert_eq!(&request[..], b"GET /");
+            server.write_all(&response).await.unwrap();
+        });
+
+        client.write_all(b"T /").await.unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received.len(), expected.len());
+        assert!(received == expected);
+        server.await.unwrap();
+
+        let (up, down) = relay.await.unwrap().unwrap();
+        assert_eq!(up.unwrap(), 5);
+        assert_eq!(down.unwrap(), expected.len() as u64);
+        assert_eq!(stats.close_reason(), Some(CloseReason::ClientEof));
+    }
+
+    #[tokio::test]
+    async fn test_relay_half_close() {
+        check_half_closed_upload(plain).await;
+    }
+
+    #[tokio::test]
+    async fn test_relay_half_close_buffered() {
+        check_half_closed_upload(wrapped).await;
+    }
+
+    #[tokio::test]
+    async fn test_relay_error_close_reason() {
+        let (_client, a) = tcp_pair().await;
+        let (c, server) = tcp_pair().await;
+        let stats = metrics::Registry::new().tcp_connection();
+        let (l2r, r2l) = relay(
+            wrapped(a),
+            wrapped(c),
+            BytesMut::new(),
+            stats.clone(),
+            option::RELAY_BUFFER_SIZE,
+        );
+        // Resets the connection.
+        server.set_linger(Some(Duration::from_secs(0))).unwrap();
+        drop(server);
+
+        let res = timeout(Duration::from_secs(2), join_relay(l2r, r2l, stats.clone()))
+            .await
+            .unwrap();
+        assert!(res.is_err());
+        assert_eq!(stats.close_reason(), Some(CloseReason::Error));
+    }
+
+    fn idle(s: TcpStream) -> Box<dyn ProxyStream> {
+        Box::new(IdleTimeoutStream::new(
+            SimpleStream(s),
+            Duration::from_millis(300),
+        ))
+    }
+
+    fn idle_wrapped(s: TcpStream) -> Box<dyn ProxyStream> {
+        Box::new(IdleTimeoutStream::new(
+            SimpleStream(SimpleStream(s)),
+            Duration::from_millis(300),
+        ))
+    }
+
+    // Data going one way keeps the relay alive, which ends once idle, with
+    // splice(2) too when the feature is on.
+    async fn check_idle_timeout(wrap: fn(TcpStream) -> Box<dyn ProxyStream>) {
+        let (mut client, a) = tcp_pair().await;
+        let (c, mut server) = tcp_pair().await;
+        let stats = metrics::Registry::new().tcp_connection();
+        let (l2r, r2l) = relay(
+            wrap(a),
+            wrap(c),
+            BytesMut::new(),
+            stats.clone(),
+            option::RELAY_BUFFER_SIZE,
+        );
+        let start = tokio::time::Instant::now();
+        let relay = tokio::spawn(join_relay(l2r, r2l, stats.clone()));
+
+        let mut buf = [0u8; 4];
+        for _ in 0..4 {
+            client.write_all(b"ping").await.unwrap();
+            server.read_exact(&mut buf).await.unwrap();
+            tokio::time::delay_for(Duration::from_millis(150)).await;
+        }
+
+        let res = timeout(Duration::from_secs(2), relay)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(res.is_err());
+        assert!(start.elapsed() >= Duration::from_millis(800));
+        assert_eq!(stats.close_reason(), Some(CloseReason::IdleTimeout));
+    }
+
+    #[tokio::test]
+    async fn test_relay_idle_timeout() {
+        check_idle_timeout(idle).await;
+    }
+
+    #[tokio::test]
+    async fn test_relay_idle_timeout_buffered() {
+        check_idle_timeout(idle_wrapped).await;
+    }
+
+    #[cfg(all(feature = "outbound-direct", feature = "outbound-redirect"))]
+    mod hook {
+        use std::net::SocketAddr;
+
+        use protobuf::Message;
+        use tokio::net::UdpSocket;
+
+        use super::*;
+        use crate::app::{handler_manager::HandlerManager, router::Router};
+        use crate::common::shutdown::ShutdownToken;
+        use crate::config;
+        use crate::proxy::ProxyDatagram;
+
+        // Replies to every packet with `name`.
+        async fn named_echo(name: &'static [u8]) -> SocketAddr {
+            let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let addr = socket.local_addr().unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 64];
+                while let Ok((_, from)) = socket.recv_from(&mut buf).await {
+                    let _ = socket.send_to(name, &from).await;
+                }
+            });
+            addr
+        }
+
+        // Routes to "direct" by default, "redirect" sends to `redirect_to`.
+        fn dispatcher(redirect_to: SocketAddr, hook: PreDispatchHook) -> Dispatcher {
+            let mut direct = config::Outbound::new();
+            direct.tag = "direct".to_string();
+            direct.protocol = "direct".to_string();
+            direct.bind = "0.0.0.0".to_string();
+            let mut settings = config::RedirectOutboundSettings::new();
+            settings.address = redirect_to.ip().to_string();
+            settings.port = redirect_to.port() as u32;
+            let mut redirect = config::Outbound::new();
+            redirect.tag = "redirect".to_string();
+            redirect.protocol = "redirect".to_string();
+            redirect.bind = "0.0.0.0".to_string();
+            redirect.settings = settings.write_to_bytes().unwrap();
+            let mut dns = config::DNS::new();
+            dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+            dns.bind = "0.0.0.0".to_string();
+            let handler_manager = HandlerManager::new(
+                &protobuf::RepeatedField::from_vec(vec![direct, redirect]),
+                &dns,
+                ShutdownToken::never(),
+            );
+            let router = Router::new(&protobuf::RepeatedField::new());
+            let mut dispatcher = Dispatcher::new(handler_manager, router);
+            dispatcher.set_pre_dispatch_hook(Some(hook));
+            dispatcher
+        }
+
+        // Dispatches a UDP session to the "a" echo, returns the name of the
+        // echo which answered.
+        async fn dispatch(decision: Decision) -> io::Result<Vec<u8>> {
+            let a = named_echo(b"a").await;
+            let b = named_echo(b"b").await;
+            let dispatcher = dispatcher(
+                b,
+                Arc::new(move |_: &Session| {
+                    let decision = decision.clone();
+                    Box::pin(async move { decision }) as BoxFuture<'static, Decision>
+                }),
+            );
+            let sess = Session {
+                source: "127.0.0.1:10000".parse().unwrap(),
+                destination: SocksAddr::Ip(a),
+            };
+            let (mut recv, mut send) = Box::new(dispatcher.dispatch_udp(&sess).await?).split();
+            send.send_to(b"ping", &a).await?;
+            let mut buf = [0u8; 64];
+            let (n, _) = timeout(Duration::from_secs(2), recv.recv_from(&mut buf)).await??;
+            Ok(buf[..n].to_vec())
+        }
+
+        #[tokio::test]
+        async fn test_hook_allow() {
+            assert_eq!(dispatch(Decision::Allow).await.unwrap(), b"a");
+        }
+
+        #[tokio::test]
+        async fn test_hook_reject() {
+            let err = dispatch(Decision::Reject).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        }
+
+        #[tokio::test]
+        async fn test_hook_route_to() {
+            let res = dispatch(Decision::RouteTo("redirect".to_string())).await;
+            assert_eq!(res.unwrap(), b"b");
+        }
+    }
+
+    #[cfg(all(feature = "outbound-direct", feature = "outbound-redirect"))]
+    mod sni {
+        use std::net::SocketAddr;
+
+        use protobuf::Message;
+
+        use super::*;
+        use crate::app::{handler_manager::HandlerManager, router::Router};
+        use crate::common::shutdown::ShutdownToken;
+        use crate::config;
+
+        // A TLS record carrying a minimal ClientHello for `sni`.
+        fn client_hello(sni: &str) -> Vec<u8> {
+            let name = sni.as_bytes();
+            let mut server_name = Vec::new();
+            server_name.extend_from_slice(&(name.len() as u16 + 3).to_be_bytes());
+            server_name.push(0); // host_name
+            server_name.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            server_name.extend_from_slice(name);
+            let mut exts = vec![0, 0]; // server_name
+            exts.extend_from_slice(&(server_name.len() as u16).to_be_bytes());
+            exts.extend_from_slice(&server_name);
+
+            let mut body = vec![3, 3]; // TLS 1.2
+            body.extend_from_slice(&[0u8; 32]); // random
+            body.push(0); // session id
+            body.extend_from_slice(&[0, 2, 0x13, 0x01]); // cipher suites
+            body.extend_from_slice(&[1, 0]); // compression methods
+            body.extend_from_slice(&(exts.len() as u16).to_be_bytes());
+            body.extend_from_slice(&exts);
+
+            let mut record = vec![0x16, 3, 1];
+            record.extend_from_slice(&(body.len() as u16 + 4).to_be_bytes());
+            record.push(1); // client_hello
+            record.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+            record.extend_from_slice(&body);
+            record
+        }
+
+        // Accepts a connection, replies with `name` followed by the TLS record
+        // it received.
+        async fn named_server(name: &'static [u8]) -> SocketAddr {
+            let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut record = vec![0u8; 5];
+                stream.read_exact(&mut record).await.unwrap();
+                let len = BigEndian::read_u16(&record[3..5]) as usize;
+                record.resize(5 + len, 0);
+                stream.read_exact(&mut record[5..]).await.unwrap();
+                stream.write_all(name).await.unwrap();
+                stream.write_all(&record).await.unwrap();
+            });
+            addr
+        }
+
+        fn redirect(tag: &str, to: SocketAddr) -> config::Outbound {
+            let mut settings = config::RedirectOutboundSettings::new();
+            settings.address = to.ip().to_string();
+            settings.port = to.port() as u32;
+            let mut outbound = config::Outbound::new();
+            outbound.tag = tag.to_string();
+            outbound.protocol = "redirect".to_string();
+            outbound.bind = "0.0.0.0".to_string();
+            outbound.settings = settings.write_to_bytes().unwrap();
+            outbound
+        }
+
+        fn sni_rule(
+            target: &str,
+            field_type: config::RoutingRule_Domain_Type,
+            value: &str,
+        ) -> config::RoutingRule {
+            let mut domain = config::RoutingRule_Domain::new();
+            domain.field_type = field_type;
+            domain.value = value.to_string();
+            let mut rule = config::RoutingRule::new();
+            rule.target_tag = target.to_string();
+            rule.snis.push(domain);
+            rule
+        }
+
+        // Routes "a.example" to "a" and SNIs under "b.example" to "b",
+        // anything else goes "direct".
+        async fn dispatcher() -> Dispatcher {
+            let mut direct = config::Outbound::new();
+            direct.tag = "direct".to_string();
+            direct.protocol = "direct".to_string();
+            direct.bind = "0.0.0.0".to_string();
+            let a = redirect("a", named_server(b"a").await);
+            let b = redirect("b", named_server(b"b").await);
+            let mut dns = config::DNS::new();
+            dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+            dns.bind = "0.0.0.0".to_string();
+            let handler_manager = HandlerManager::new(
+                &protobuf::RepeatedField::from_vec(vec![direct, a, b]),
+                &dns,
+                ShutdownToken::never(),
+            );
+            let router = Router::new(&protobuf::RepeatedField::from_vec(vec![
+                sni_rule("a", config::RoutingRule_Domain_Type::FULL, "a.example"),
+                sni_rule("b", config::RoutingRule_Domain_Type::DOMAIN, "b.example"),
+            ]));
+            Dispatcher::new(handler_manager, router)
+        }
+
+        // Sends a ClientHello for `sni` to `destination`, returns what came
+        // back.
+        async fn route(destination: SocksAddr, sni: &str) -> Vec<u8> {
+            let dispatcher = dispatcher().await;
+            let (mut client, lhs) = tcp_pair().await;
+            let mut sess = Session {
+                source: client.local_addr().unwrap(),
+                destination,
+            };
+            let hello = client_hello(sni);
+            let received = async move {
+                client.write_all(&hello).await.unwrap();
+                let mut received = Vec::new();
+                client.read_to_end(&mut received).await.unwrap();
+                received
+            };
+            let (_, received) = tokio::join!(dispatcher.dispatch_tcp(&mut sess, lhs), received);
+            received
+        }
+
+        fn expected(name: &[u8], sni: &str) -> Vec<u8> {
+            let mut expected = name.to_vec();
+            expected.extend_from_slice(&client_hello(sni));
+            expected
+        }
+
+        #[tokio::test]
+        async fn test_route_by_sni() {
+            let destination = SocksAddr::Ip("127.0.0.1:443".parse().unwrap());
+            let received = route(destination.clone(), "a.example").await;
+            assert_eq!(received, expected(b"a", "a.example"));
+            let received = route(destination, "www.b.example").await;
+            assert_eq!(received, expected(b"b", "www.b.example"));
+        }
+
+        #[tokio::test]
+        async fn test_route_by_sni_over_domain() {
+            // The destination domain may be a fake one, the SNI still decides.
+            let destination = SocksAddr::Domain("a.example".to_string(), 443);
+            let received = route(destination, "b.example").await;
+            assert_eq!(received, expected(b"b", "b.example"));
+        }
+    }
+
+    #[cfg(all(feature = "outbound-direct", feature = "outbound-drop"))]
+    mod quic {
+        use tokio::net::UdpSocket;
+
+        use super::*;
+        use crate::app::{handler_manager::HandlerManager, router::Router};
+        use crate::common::shutdown::ShutdownToken;
+        use crate::config;
+        use crate::proxy::ProxyDatagram;
+
+        // Drops UDP to port 443, anything else goes "direct".
+        fn dispatcher() -> Dispatcher {
+            let mut direct = config::Outbound::new();
+            direct.tag = "direct".to_string();
+            direct.protocol = "direct".to_string();
+            direct.bind = "0.0.0.0".to_string();
+            let mut drop = config::Outbound::new();
+            drop.tag = "drop".to_string();
+            drop.protocol = "drop".to_string();
+            let mut dns = config::DNS::new();
+            dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+            dns.bind = "0.0.0.0".to_string();
+            let handler_manager = HandlerManager::new(
+                &protobuf::RepeatedField::from_vec(vec![direct, drop]),
+                &dns,
+                ShutdownToken::never(),
+            );
+            let mut rule = config::RoutingRule::new();
+            rule.target_tag = "drop".to_string();
+            rule.udp_ports = vec![443];
+            let router = Router::new(&protobuf::RepeatedField::from_vec(vec![rule]));
+            Dispatcher::new(handler_manager, router)
+        }
+
+        #[tokio::test]
+        async fn test_block_quic() {
+            let dispatcher = dispatcher();
+            let sess = |destination| Session {
+                source: "127.0.0.1:10000".parse().unwrap(),
+                destination: SocksAddr::Ip(destination),
+            };
+
+            assert!(dispatcher
+                .dispatch_udp(&sess("127.0.0.1:443".parse().unwrap()))
+                .await
+                .is_err());
+
+            // Other UDP passes.
+            let mut echo = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let addr = echo.local_addr().unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 64];
+                let (n, from) = echo.recv_from(&mut buf).await.unwrap();
+                let _ = echo.send_to(&buf[..n], &from).await;
+            });
+            let datagram = dispatcher.dispatch_udp(&sess(addr)).await.unwrap();
+            let (mut recv, mut send) = Box::new(datagram).split();
+            send.send_to(b"ping", &addr).await.unwrap();
+            let mut buf = [0u8; 64];
+            let (n, _) = timeout(Duration::from_secs(2), recv.recv_from(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(&buf[..n], b"ping");
+        }
+    }
+}
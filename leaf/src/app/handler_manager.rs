@@ -19,16 +19,40 @@ use crate::proxy::tryall;
 
 #[cfg(feature = "outbound-direct")]
 use crate::proxy::direct;
+#[cfg(feature = "outbound-dns")]
+use crate::proxy::dns;
 #[cfg(feature = "outbound-drop")]
 use crate::proxy::drop;
+#[cfg(feature = "outbound-fixed")]
+use crate::proxy::fixed;
+#[cfg(feature = "outbound-http")]
+use crate::proxy::http;
+#[cfg(feature = "outbound-limit")]
+use crate::proxy::limit;
+#[cfg(feature = "outbound-mux")]
+use crate::proxy::mux;
+#[cfg(feature = "outbound-obfs")]
+use crate::proxy::obfs;
+#[cfg(any(feature = "outbound-direct", feature = "outbound-redirect"))]
+use crate::proxy::proxy_protocol;
+#[cfg(feature = "outbound-quic")]
+use crate::proxy::quic;
 #[cfg(feature = "outbound-redirect")]
 use crate::proxy::redirect;
+#[cfg(feature = "outbound-reject")]
+use crate::proxy::reject;
+#[cfg(feature = "outbound-retry")]
+use crate::proxy::retry;
 #[cfg(feature = "outbound-shadowsocks")]
 use crate::proxy::shadowsocks;
 #[cfg(feature = "outbound-socks")]
 use crate::proxy::socks;
+#[cfg(feature = "outbound-tee")]
+use crate::proxy::tee;
 #[cfg(feature = "outbound-tls")]
 use crate::proxy::tls;
+#[cfg(feature = "outbound-tls")]
+use crate::proxy::transport::tls::{SessionCache, DEFAULT_SESSION_CACHE_SIZE};
 #[cfg(feature = "outbound-trojan")]
 use crate::proxy::trojan;
 #[cfg(feature = "outbound-vless")]
@@ -38,21 +62,136 @@ use crate::proxy::vmess;
 #[cfg(feature = "outbound-ws")]
 use crate::proxy::ws;
 
+#[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+use tokio::sync::Mutex as TokioMutex;
+
+#[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+use crate::common::fake_dns::FakeDns;
+#[cfg(feature = "outbound-direct")]
+use crate::common::happy_eyeballs::{Family, HappyEyeballs};
 use crate::{
-    common::dns_client::DnsClient,
-    config::{self, Outbound, DNS},
-    proxy::{self, ProxyHandler, ProxyHandlerType},
+    common::{
+        dns_client::{DnsClient, DomainRule},
+        health::HealthMap,
+        shutdown::ShutdownToken,
+    },
+    config::{self, Outbound, RoutingRule_Domain_Type, DNS},
+    option,
+    proxy::{self, limit::TokenBucket, BindAddrs, ProxyHandler, ProxyHandlerType},
 };
 
+#[cfg(any(
+    feature = "outbound-direct",
+    feature = "outbound-redirect",
+    feature = "outbound-socks"
+))]
+fn bind_interface(outbound: &Outbound) -> Option<String> {
+    if outbound.bind_interface.is_empty() {
+        None
+    } else {
+        Some(outbound.bind_interface.clone())
+    }
+}
+
+fn millis(ms: u32) -> Option<std::time::Duration> {
+    if ms > 0 {
+        Some(std::time::Duration::from_millis(ms as u64))
+    } else {
+        None
+    }
+}
+
+fn secs(s: u32) -> Option<std::time::Duration> {
+    if s > 0 {
+        Some(std::time::Duration::from_secs(s as u64))
+    } else {
+        None
+    }
+}
+
+// Whether the outbound resolves destination domains with the DNS client. By
+// default, proxies pass them on for their server to resolve, so the domain
+// resolves as seen from the server. Direct outbounds connect on their own,
+// the option doesn't apply to them. Redirect outbounds only pass the
+// destination on in their PROXY protocol header, an IP address if resolved
+// locally.
+fn resolves_locally(outbound: &Outbound) -> bool {
+    match outbound.protocol.as_str() {
+        "direct" | "drop" | "reject" => return false,
+        _ => (),
+    }
+    match outbound.resolve.as_str() {
+        "local" => true,
+        "remote" | "" => false,
+        v => {
+            warn!(
+                "invalid [{}] outbound resolve option {:?}, resolving remotely",
+                &outbound.tag, v
+            );
+            false
+        }
+    }
+}
+
+// Whether the connections of the outbound draw from the egress bucket. The
+// ensembles passing the connect on to their actors leave it to them, a chain
+// dials for its actors so it counts for them.
+fn draws_egress(outbound: &Outbound) -> bool {
+    !matches!(
+        outbound.protocol.as_str(),
+        "tryall" | "failover" | "random" | "retry" | "tee"
+    )
+}
+
+fn handler_options(
+    outbound: &Outbound,
+    dns_client: &Arc<DnsClient>,
+    egress: &Option<Arc<TokenBucket>>,
+) -> proxy::handler::Options {
+    proxy::handler::Options {
+        slow_threshold: millis(outbound.slow_threshold),
+        connect_deadline: millis(outbound.connect_deadline),
+        max_lifetime: secs(outbound.max_lifetime),
+        resolver: if resolves_locally(outbound) {
+            Some(dns_client.clone())
+        } else {
+            None
+        },
+        egress: if draws_egress(outbound) {
+            egress.clone()
+        } else {
+            None
+        },
+    }
+}
+
 pub struct HandlerManager {
     handlers: HashMap<String, Arc<dyn ProxyHandler>>,
     default_handler: Option<String>,
+    dns_client: Arc<DnsClient>,
+    #[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+    fake_dns: Arc<TokioMutex<FakeDns>>,
+    relay_buffer_sizes: HashMap<String, usize>,
 }
 
 impl HandlerManager {
-    pub fn new(outbounds: &protobuf::RepeatedField<Outbound>, dns: &DNS) -> Self {
-        let mut handlers: HashMap<String, Arc<dyn ProxyHandler>> = HashMap::new();
-        let mut default_handler: Option<String> = None;
+    /// Background tasks of the handlers stop once `shutdown` is signaled.
+    pub fn new(
+        outbounds: &protobuf::RepeatedField<Outbound>,
+        dns: &DNS,
+        shutdown: ShutdownToken,
+    ) -> Self {
+        Self::with_egress(outbounds, dns, shutdown, None)
+    }
+
+    /// Same as `new`, the writes of all outbound connections draw from
+    /// `egress`.
+    pub fn with_egress(
+        outbounds: &protobuf::RepeatedField<Outbound>,
+        dns: &DNS,
+        shutdown: ShutdownToken,
+        egress: Option<Arc<TokenBucket>>,
+    ) -> Self {
         let mut dns_servers = Vec::new();
         for dns_server in dns.servers.iter() {
             if let Ok(ip) = dns_server.parse::<IpAddr>() {
@@ -73,39 +212,124 @@ impl HandlerManager {
             };
             SocketAddr::from(addr)
         };
-        let dns_client = Arc::new(DnsClient::new(dns_servers, dns_bind_addr));
+        let mut dns_client = DnsClient::new(dns_servers, dns_bind_addr);
+        for rule in dns.rules.iter() {
+            let mut domains = Vec::new();
+            for domain in rule.domains.iter() {
+                let value = domain.value.clone();
+                domains.push(match domain.field_type {
+                    RoutingRule_Domain_Type::PLAIN => DomainRule::Keyword(value),
+                    RoutingRule_Domain_Type::DOMAIN => DomainRule::Suffix(value),
+                    RoutingRule_Domain_Type::FULL => DomainRule::Full(value),
+                });
+            }
+            let mut servers = Vec::new();
+            for server in rule.servers.iter() {
+                match server.parse::<IpAddr>() {
+                    Ok(ip) => servers.push(SocketAddr::new(ip, 53)),
+                    Err(e) => warn!("invalid server [{}] in dns rule: {}", server, e),
+                }
+            }
+            if servers.is_empty() {
+                warn!("ignoring dns rule without valid servers");
+                continue;
+            }
+            dns_client.add_rule(domains, servers);
+        }
+        Self::with_dns_client(outbounds, Arc::new(dns_client), shutdown, egress)
+    }
+
+    /// Same as `with_egress`, the handlers resolve domains on `dns_client`.
+    #[cfg_attr(not(feature = "outbound-failover"), allow(unused_variables))]
+    pub fn with_dns_client(
+        outbounds: &protobuf::RepeatedField<Outbound>,
+        dns_client: Arc<DnsClient>,
+        shutdown: ShutdownToken,
+        egress: Option<Arc<TokenBucket>>,
+    ) -> Self {
+        let mut handlers: HashMap<String, Arc<dyn ProxyHandler>> = HashMap::new();
+        let mut default_handler: Option<String> = None;
+        #[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+        let fake_dns = Arc::new(TokioMutex::new(FakeDns::new()));
+        let health = Arc::new(HealthMap::new());
 
         for outbound in outbounds.iter() {
             let tag = String::from(&outbound.tag);
+            let options = handler_options(outbound, &dns_client, &egress);
             if default_handler.is_none() {
                 default_handler = Some(String::from(&outbound.tag));
                 debug!("default handler [{}]", &outbound.tag);
             }
-            let bind_addr = {
-                let addr = format!("{}:0", &outbound.bind);
-                let addr = match SocketAddrV4::from_str(&addr) {
-                    Ok(a) => a,
-                    Err(e) => {
-                        error!(
-                            "invalid bind addr [{}] in outbound {}: {}",
-                            &outbound.bind, &outbound.tag, e
-                        );
-                        panic!("");
-                    }
-                };
-                SocketAddr::from(addr)
+            // Comma-separated, e.g. an IPv4 and an IPv6 address, direct
+            // outbounds dial from the one matching each destination, the
+            // others bind to the IPv4 one if any.
+            let binds = match BindAddrs::from_str(&outbound.bind) {
+                Ok(binds) => binds,
+                Err(e) => {
+                    error!(
+                        "invalid bind addr [{}] in outbound {}: {}",
+                        &outbound.bind, &outbound.tag, e
+                    );
+                    panic!("");
+                }
             };
+            let bind_addr = binds.any();
             match outbound.protocol.as_str() {
                 #[cfg(feature = "outbound-direct")]
                 "direct" => {
-                    let tcp = Box::new(direct::TcpHandler::new(bind_addr, dns_client.clone()));
-                    let udp = Box::new(direct::UdpHandler::new(bind_addr));
-                    let handler = proxy::Handler::new(
+                    let settings = match protobuf::parse_from_bytes::<config::DirectOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let proxy_protocol = match settings.proxy_protocol {
+                        0 => None,
+                        1 => Some(proxy_protocol::Version::V1),
+                        2 => Some(proxy_protocol::Version::V2),
+                        v => {
+                            warn!(
+                                "invalid [{}] outbound settings: unknown proxy protocol version {}",
+                                &tag, v
+                            );
+                            continue;
+                        }
+                    };
+                    let mut tcp =
+                        direct::TcpHandler::new(bind_addr, dns_client.clone(), proxy_protocol)
+                            .bind_addrs(binds)
+                            .sequential(settings.sequential_dial)
+                            .retry_on_reset(settings.retry_on_reset);
+                    if settings.dial_failure_window > 0 {
+                        tcp = tcp.dial_failure_window(std::time::Duration::from_secs(
+                            settings.dial_failure_window as u64,
+                        ));
+                    }
+                    let mut happy_eyeballs = HappyEyeballs::default();
+                    if settings.happy_eyeballs_delay > 0 {
+                        happy_eyeballs.delay =
+                            std::time::Duration::from_millis(settings.happy_eyeballs_delay as u64);
+                    }
+                    if settings.prefer_ipv4 {
+                        happy_eyeballs.first_family = Family::V4;
+                    }
+                    tcp = tcp.happy_eyeballs(happy_eyeballs);
+                    let tcp = Box::new(tcp);
+                    let udp = Box::new(
+                        direct::UdpHandler::new(bind_addr, bind_interface(outbound))
+                            .preserve_source_port(settings.preserve_source_port)
+                            .batching(settings.udp_batching),
+                    );
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::Green,
                         ProxyHandlerType::Direct,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
@@ -113,12 +337,106 @@ impl HandlerManager {
                 "drop" => {
                     let tcp = Box::new(drop::TcpHandler {});
                     let udp = Box::new(drop::UdpHandler {});
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::Red,
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-dns")]
+                "dns" => {
+                    let settings = match protobuf::parse_from_bytes::<config::DNSOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let fake_dns = if settings.fake_ip {
+                        Some(fake_dns.clone())
+                    } else {
+                        None
+                    };
+                    let responder = Arc::new(dns::Responder::new(dns_client.clone(), fake_dns));
+                    let tcp = Box::new(dns::TcpHandler {
+                        responder: responder.clone(),
+                    });
+                    let udp = Box::new(dns::UdpHandler { responder });
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::TrueColor {
+                            r: 252,
+                            g: 107,
+                            b: 3,
+                        },
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-reject")]
+                "reject" => {
+                    let settings = match protobuf::parse_from_bytes::<config::RejectOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let mode = match reject::Mode::from_name(&settings.mode) {
+                        Some(m) => m,
+                        None => {
+                            warn!("invalid [{}] reject mode: {}", &tag, &settings.mode);
+                            continue;
+                        }
+                    };
+                    let tcp = Box::new(reject::TcpHandler { mode });
+                    let udp = Box::new(reject::UdpHandler { mode });
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::Red,
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-fixed")]
+                "fixed" => {
+                    let settings = match protobuf::parse_from_bytes::<config::FixedOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let tcp = Box::new(fixed::TcpHandler {
+                        address: settings.address,
+                        port: settings.port as u16,
+                        bind_addr,
+                        dns_client: dns_client.clone(),
+                    });
+                    let udp = Box::new(fixed::UdpHandler {});
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::BrightYellow,
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
@@ -134,20 +452,36 @@ impl HandlerManager {
                             continue;
                         }
                     };
+                    let proxy_protocol = match settings.proxy_protocol {
+                        0 => None,
+                        1 => Some(proxy_protocol::Version::V1),
+                        2 => Some(proxy_protocol::Version::V2),
+                        v => {
+                            warn!(
+                                "invalid [{}] outbound settings: unknown proxy protocol version {}",
+                                &tag, v
+                            );
+                            continue;
+                        }
+                    };
                     let tcp = Box::new(redirect::TcpHandler {
                         address: settings.address.clone(),
                         port: settings.port as u16,
+                        proxy_protocol,
                     });
                     let udp = Box::new(redirect::UdpHandler {
                         address: settings.address,
                         port: settings.port as u16,
+                        bind_addr,
+                        bind_interface: bind_interface(outbound),
                     });
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::BrightYellow,
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
@@ -172,9 +506,20 @@ impl HandlerManager {
                         address: settings.address.clone(),
                         port: settings.port as u16,
                         bind_addr,
+                        bind_interface: bind_interface(outbound),
                         dns_client: dns_client.clone(),
+                        backoff: millis(settings.reassociate_base).map(|base| {
+                            socks::outbound::Backoff {
+                                base,
+                                max: millis(settings.reassociate_max).unwrap_or(base),
+                                jitter: settings.reassociate_jitter,
+                                give_up_after: std::time::Duration::from_secs(
+                                    settings.reassociate_timeout as u64,
+                                ),
+                            }
+                        }),
                     });
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::TrueColor {
                             r: 252,
@@ -184,6 +529,52 @@ impl HandlerManager {
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-http")]
+                "http" => {
+                    let settings = match protobuf::parse_from_bytes::<config::HTTPOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let tcp: Box<dyn proxy::ProxyTcpHandler> = if settings.forward {
+                        Box::new(http::outbound::ForwardHandler {
+                            address: settings.address.clone(),
+                            port: settings.port as u16,
+                            username: settings.username.clone(),
+                            password: settings.password.clone(),
+                            bind_addr,
+                            dns_client: dns_client.clone(),
+                        })
+                    } else {
+                        Box::new(http::outbound::TcpHandler {
+                            address: settings.address.clone(),
+                            port: settings.port as u16,
+                            username: settings.username.clone(),
+                            password: settings.password.clone(),
+                            bind_addr,
+                            dns_client: dns_client.clone(),
+                        })
+                    };
+                    let udp = Box::new(http::outbound::UdpHandler {});
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::TrueColor {
+                            r: 252,
+                            g: 107,
+                            b: 3,
+                        },
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
@@ -199,28 +590,41 @@ impl HandlerManager {
                             continue;
                         }
                     };
-                    let tcp = Box::new(shadowsocks::TcpHandler {
-                        address: settings.address.clone(),
-                        port: settings.port as u16,
-                        cipher: settings.method.clone(),
-                        password: settings.password.clone(),
+                    let tcp = match shadowsocks::TcpHandler::new(
+                        settings.address.clone(),
+                        settings.port as u16,
+                        settings.method.clone(),
+                        settings.password.clone(),
                         bind_addr,
-                        dns_client: dns_client.clone(),
-                    });
-                    let udp = Box::new(shadowsocks::UdpHandler {
-                        address: settings.address,
-                        port: settings.port as u16,
-                        cipher: settings.method,
-                        password: settings.password,
+                        dns_client.clone(),
+                    ) {
+                        Ok(h) => Box::new(h),
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let udp = match shadowsocks::UdpHandler::new(
+                        settings.address,
+                        settings.port as u16,
+                        settings.method,
+                        settings.password,
                         bind_addr,
-                        dns_client: dns_client.clone(),
-                    });
-                    let handler = proxy::Handler::new(
+                        dns_client.clone(),
+                    ) {
+                        Ok(h) => Box::new(h),
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::Blue,
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag, handler);
                 }
@@ -251,12 +655,13 @@ impl HandlerManager {
                         bind_addr,
                         dns_client: dns_client.clone(),
                     });
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::Cyan,
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag, handler);
                 }
@@ -272,28 +677,41 @@ impl HandlerManager {
                         }
                     };
 
-                    let tcp = Box::new(vmess::TcpHandler {
-                        address: settings.address.clone(),
-                        port: settings.port as u16,
-                        uuid: settings.uuid.clone(),
-                        security: settings.security.clone(),
+                    let tcp = match vmess::TcpHandler::new(
+                        settings.address.clone(),
+                        settings.port as u16,
+                        settings.uuid.clone(),
+                        settings.security.clone(),
                         bind_addr,
-                        dns_client: dns_client.clone(),
-                    });
-                    let udp = Box::new(vmess::UdpHandler {
-                        address: settings.address.clone(),
-                        port: settings.port as u16,
-                        uuid: settings.uuid.clone(),
-                        security: settings.security.clone(),
+                        dns_client.clone(),
+                    ) {
+                        Ok(h) => Box::new(h),
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let udp = match vmess::UdpHandler::new(
+                        settings.address.clone(),
+                        settings.port as u16,
+                        settings.uuid.clone(),
+                        settings.security.clone(),
                         bind_addr,
-                        dns_client: dns_client.clone(),
-                    });
-                    let handler = proxy::Handler::new(
+                        dns_client.clone(),
+                    ) {
+                        Ok(h) => Box::new(h),
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::Magenta,
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag, handler);
                     drop(settings); // TODO do this for all others
@@ -324,12 +742,13 @@ impl HandlerManager {
                         bind_addr,
                         dns_client: dns_client.clone(),
                     });
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::Magenta,
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag, handler);
                     drop(settings); // TODO do this for all others
@@ -349,15 +768,30 @@ impl HandlerManager {
                     for alpn in settings.alpn.iter() {
                         alpns.push(alpn.clone());
                     }
+                    let mut pinned_spki = Vec::new();
+                    for pin in settings.pinned_spki.iter() {
+                        match base64::decode(pin) {
+                            Ok(pin) if pin.len() == 32 => pinned_spki.push(pin),
+                            _ => warn!("invalid [{}] spki pin: {}", &tag, pin),
+                        }
+                    }
+                    let session_cache_size = if settings.session_cache_size > 0 {
+                        settings.session_cache_size as usize
+                    } else {
+                        DEFAULT_SESSION_CACHE_SIZE
+                    };
                     let tcp = Box::new(tls::TcpHandler {
                         server_name: settings.server_name.clone(),
                         alpns: alpns.clone(),
+                        insecure: settings.insecure,
+                        pinned_spki,
+                        session_cache: SessionCache::new(session_cache_size),
                     });
                     let udp = Box::new(tls::UdpHandler {
                         server_name: settings.server_name.clone(),
                         alpns: alpns.clone(),
                     });
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::TrueColor {
                             r: 252,
@@ -367,6 +801,7 @@ impl HandlerManager {
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
@@ -384,11 +819,15 @@ impl HandlerManager {
                     };
                     let tcp = Box::new(ws::TcpHandler {
                         path: settings.path.clone(),
+                        host: settings.host.clone(),
+                        max_early_data: settings.max_early_data as usize,
+                        early_data_header_name: settings.early_data_header_name.clone(),
                     });
                     let udp = Box::new(ws::UdpHandler {
                         path: settings.path.clone(),
+                        host: settings.host.clone(),
                     });
-                    let handler = proxy::Handler::new(
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::TrueColor {
                             r: 252,
@@ -398,6 +837,7 @@ impl HandlerManager {
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
@@ -412,15 +852,99 @@ impl HandlerManager {
                             continue;
                         }
                     };
-                    let tcp = Box::new(crate::proxy::h2::TcpHandler {
+                    let tcp = Box::new(crate::proxy::h2::TcpHandler::new(
+                        settings.path.clone(),
+                        settings.host.clone(),
+                    ));
+                    let udp = Box::new(crate::proxy::h2::UdpHandler {
                         path: settings.path.clone(),
                         host: settings.host.clone(),
                     });
-                    let udp = Box::new(crate::proxy::h2::UdpHandler {
-                        path: settings.path.clone(),
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::TrueColor {
+                            r: 252,
+                            g: 107,
+                            b: 3,
+                        },
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-mux")]
+                "mux" => {
+                    let settings = match protobuf::parse_from_bytes::<config::MuxOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let mut config = proxy::transport::mux::MuxConfig::default();
+                    if settings.receive_window > 0 {
+                        config.receive_window = settings.receive_window;
+                    }
+                    if settings.keepalive_interval > 0 {
+                        let interval =
+                            std::time::Duration::from_secs(settings.keepalive_interval as u64);
+                        let timeout = if settings.keepalive_timeout > 0 {
+                            std::time::Duration::from_secs(settings.keepalive_timeout as u64)
+                        } else {
+                            interval
+                        };
+                        config.keepalive =
+                            Some(proxy::transport::KeepaliveConfig { interval, timeout });
+                    }
+                    let mut pool = proxy::transport::pool::PoolConfig::default();
+                    if settings.max_streams > 0 {
+                        pool.max_streams = settings.max_streams as usize;
+                    }
+                    if settings.max_connections > 0 {
+                        pool.max_connections = settings.max_connections as usize;
+                    }
+                    if settings.idle_timeout > 0 {
+                        pool.idle_timeout =
+                            std::time::Duration::from_secs(settings.idle_timeout as u64);
+                    }
+                    let tcp = Box::new(mux::TcpHandler::new(config, pool));
+                    let udp = Box::new(mux::UdpHandler);
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::TrueColor {
+                            r: 252,
+                            g: 107,
+                            b: 3,
+                        },
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-obfs")]
+                "obfs" => {
+                    let settings = match protobuf::parse_from_bytes::<config::ObfsOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let tcp = Box::new(obfs::TcpHandler {
+                        method: settings.method.clone(),
                         host: settings.host.clone(),
+                        path: settings.path.clone(),
                     });
-                    let handler = proxy::Handler::new(
+                    let udp = Box::new(obfs::UdpHandler {});
+                    let handler = proxy::Handler::with_options(
                         tag.clone(),
                         colored::Color::TrueColor {
                             r: 252,
@@ -430,10 +954,97 @@ impl HandlerManager {
                         ProxyHandlerType::Endpoint,
                         tcp,
                         udp,
+                        options,
                     );
                     handlers.insert(tag.clone(), handler);
                 }
-                "tryall" | "failover" | "random" | "chain" => (),
+                #[cfg(feature = "outbound-quic")]
+                "quic" => {
+                    let settings = match protobuf::parse_from_bytes::<config::QuicOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let mut certificates = Vec::new();
+                    for path in settings.certificates.iter() {
+                        match std::fs::read(path) {
+                            Ok(cert) => certificates.push(cert),
+                            Err(e) => warn!("read certificate {} failed: {}", path, e),
+                        }
+                    }
+                    let server_name = if settings.server_name.is_empty() {
+                        settings.address.clone()
+                    } else {
+                        settings.server_name.clone()
+                    };
+                    let config = proxy::transport::quic::QuicConfig {
+                        server_name,
+                        alpns: settings.alpn.to_vec(),
+                        certificates,
+                        zero_rtt: settings.zero_rtt,
+                        congestion: proxy::transport::quic::CongestionConfig {
+                            initial_window: Some(settings.initial_window).filter(|w| *w > 0),
+                            minimum_window: Some(settings.minimum_window).filter(|w| *w > 0),
+                        },
+                    };
+                    let tcp = Box::new(quic::TcpHandler::new(
+                        settings.address.clone(),
+                        settings.port as u16,
+                        bind_addr,
+                        dns_client.clone(),
+                        config,
+                    ));
+                    let udp = Box::new(quic::UdpHandler);
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::TrueColor {
+                            r: 252,
+                            g: 107,
+                            b: 3,
+                        },
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                #[cfg(feature = "outbound-limit")]
+                "limit" => {
+                    let settings = match protobuf::parse_from_bytes::<config::LimitOutboundSettings>(
+                        &outbound.settings,
+                    ) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            warn!("invalid [{}] outbound settings: {}", &tag, e);
+                            continue;
+                        }
+                    };
+                    let nonzero = |r: u64| if r > 0 { Some(r) } else { None };
+                    let tcp = Box::new(limit::TcpHandler::new(
+                        nonzero(settings.rate),
+                        nonzero(settings.global_rate),
+                    ));
+                    let udp = Box::new(limit::UdpHandler {});
+                    let handler = proxy::Handler::with_options(
+                        tag.clone(),
+                        colored::Color::TrueColor {
+                            r: 252,
+                            g: 107,
+                            b: 3,
+                        },
+                        ProxyHandlerType::Endpoint,
+                        tcp,
+                        udp,
+                        options,
+                    );
+                    handlers.insert(tag.clone(), handler);
+                }
+                "tryall" | "failover" | "random" | "chain" | "retry" | "tee" => (),
                 _ => {
                     warn!("unknown outbound protocol {:?}", outbound.protocol);
                 }
@@ -444,6 +1055,7 @@ impl HandlerManager {
         for _i in 0..4 {
             for outbound in outbounds.iter() {
                 let tag = String::from(&outbound.tag);
+                let options = handler_options(outbound, &dns_client, &egress);
                 match outbound.protocol.as_str() {
                     #[cfg(feature = "outbound-tryall")]
                     "tryall" => {
@@ -466,15 +1078,30 @@ impl HandlerManager {
                         if actors.is_empty() {
                             continue;
                         }
+                        let until = match settings.until {
+                            config::TryAllOutboundSettings_Until::CONNECT => {
+                                tryall::RaceUntil::Connect
+                            }
+                            config::TryAllOutboundSettings_Until::WRITE => tryall::RaceUntil::Write,
+                            config::TryAllOutboundSettings_Until::RESPONSE => {
+                                tryall::RaceUntil::Response
+                            }
+                        };
                         let tcp = Box::new(tryall::TcpHandler {
                             actors: actors.clone(),
                             delay_base: settings.delay_base,
+                            until,
+                            health: if settings.skip_failing {
+                                Some(health.clone())
+                            } else {
+                                None
+                            },
                         });
                         let udp = Box::new(tryall::UdpHandler {
                             actors,
                             delay_base: settings.delay_base,
                         });
-                        let handler = proxy::Handler::new(
+                        let handler = proxy::Handler::with_options(
                             tag.clone(),
                             colored::Color::TrueColor {
                                 r: 182,
@@ -484,6 +1111,100 @@ impl HandlerManager {
                             ProxyHandlerType::Ensemble,
                             tcp,
                             udp,
+                            options,
+                        );
+                        handlers.insert(tag.clone(), handler);
+                    }
+                    #[cfg(feature = "outbound-retry")]
+                    "retry" => {
+                        let settings = match protobuf::parse_from_bytes::<
+                            config::RetryOutboundSettings,
+                        >(&outbound.settings)
+                        {
+                            Ok(s) => s,
+                            Err(e) => {
+                                warn!("invalid [{}] outbound settings: {}", &tag, e);
+                                continue;
+                            }
+                        };
+                        let actor = match handlers.get(&settings.actor) {
+                            Some(a) => a.clone(),
+                            None => continue,
+                        };
+                        let policy = retry::RetryPolicy {
+                            attempts: settings.attempts.max(1),
+                            delay_base: std::time::Duration::from_millis(
+                                settings.delay_base as u64,
+                            ),
+                            deadline: if settings.deadline > 0 {
+                                Some(std::time::Duration::from_secs(settings.deadline as u64))
+                            } else {
+                                None
+                            },
+                            ..Default::default()
+                        };
+                        let tcp = Box::new(retry::TcpHandler {
+                            actor: actor.clone(),
+                            policy: policy.clone(),
+                        });
+                        let udp = Box::new(retry::UdpHandler { actor, policy });
+                        let handler = proxy::Handler::with_options(
+                            tag.clone(),
+                            colored::Color::TrueColor {
+                                r: 182,
+                                g: 235,
+                                b: 250,
+                            },
+                            ProxyHandlerType::Ensemble,
+                            tcp,
+                            udp,
+                            options,
+                        );
+                        handlers.insert(tag.clone(), handler);
+                    }
+                    #[cfg(feature = "outbound-tee")]
+                    "tee" => {
+                        let settings = match protobuf::parse_from_bytes::<config::TeeOutboundSettings>(
+                            &outbound.settings,
+                        ) {
+                            Ok(s) => s,
+                            Err(e) => {
+                                warn!("invalid [{}] outbound settings: {}", &tag, e);
+                                continue;
+                            }
+                        };
+                        let actor = match handlers.get(&settings.actor) {
+                            Some(a) => a.clone(),
+                            None => continue,
+                        };
+                        if handlers.contains_key(&tag) {
+                            // Already built in a previous pass, don't open
+                            // the file again.
+                            continue;
+                        }
+                        let mirror = match tee::Mirror::file(&settings.path) {
+                            Ok(m) => m,
+                            Err(e) => {
+                                warn!("open tee file {} failed: {}", &settings.path, e);
+                                continue;
+                            }
+                        };
+                        let tcp = Box::new(tee::TcpHandler {
+                            actor: actor.clone(),
+                            mirror: mirror.clone(),
+                        });
+                        let udp = Box::new(tee::UdpHandler { actor, mirror });
+                        let handler = proxy::Handler::with_options(
+                            tag.clone(),
+                            colored::Color::TrueColor {
+                                r: 182,
+                                g: 235,
+                                b: 250,
+                            },
+                            ProxyHandlerType::Ensemble,
+                            tcp,
+                            udp,
+                            options,
                         );
                         handlers.insert(tag.clone(), handler);
                     }
@@ -508,11 +1229,14 @@ impl HandlerManager {
                         if actors.is_empty() {
                             continue;
                         }
+                        let affinity = secs(settings.affinity_ttl)
+                            .map(|ttl| Arc::new(random::Affinity::new(ttl)));
                         let tcp = Box::new(random::TcpHandler {
                             actors: actors.clone(),
+                            affinity: affinity.clone(),
                         });
-                        let udp = Box::new(random::UdpHandler { actors });
-                        let handler = proxy::Handler::new(
+                        let udp = Box::new(random::UdpHandler { actors, affinity });
+                        let handler = proxy::Handler::with_options(
                             tag.clone(),
                             colored::Color::TrueColor {
                                 r: 182,
@@ -522,6 +1246,7 @@ impl HandlerManager {
                             ProxyHandlerType::Ensemble,
                             tcp,
                             udp,
+                            options,
                         );
                         handlers.insert(tag.clone(), handler);
                     }
@@ -546,21 +1271,119 @@ impl HandlerManager {
                         if actors.is_empty() {
                             continue;
                         }
-                        let tcp = Box::new(failover::TcpHandler::new(
-                            actors.clone(),
-                            settings.fail_timeout,
-                            settings.health_check,
-                            settings.check_interval,
-                            settings.failover,
-                        ));
-                        let udp = Box::new(failover::UdpHandler::new(
-                            actors,
-                            settings.fail_timeout,
-                            settings.health_check,
-                            settings.check_interval,
-                            settings.failover,
-                        ));
-                        let handler = proxy::Handler::new(
+                        let switch_margin = if settings.switch_margin_ms > 0 {
+                            Some(failover::SwitchMargin::Millis(
+                                settings.switch_margin_ms as u64,
+                            ))
+                        } else if settings.switch_margin_percent > 0 {
+                            Some(failover::SwitchMargin::Percent(
+                                settings.switch_margin_percent as u64,
+                            ))
+                        } else {
+                            None
+                        };
+                        let throughput_probe = if settings.throughput_probe_url.is_empty() {
+                            None
+                        } else {
+                            let size = match settings.throughput_probe_size {
+                                0 => 1024 * 1024,
+                                n => n as u64,
+                            };
+                            let probe = failover::ThroughputProbe::from_url(
+                                &settings.throughput_probe_url,
+                                size,
+                            );
+                            if probe.is_none() {
+                                warn!(
+                                    "invalid throughput probe url {} for [{}]",
+                                    &settings.throughput_probe_url, &tag
+                                );
+                            }
+                            probe
+                        };
+                        let url_test = if settings.url_test.is_empty() {
+                            None
+                        } else {
+                            let url_test = failover::UrlTest::from_url(&settings.url_test);
+                            if url_test.is_none() {
+                                warn!("invalid url test url {} for [{}]", &settings.url_test, &tag);
+                            }
+                            url_test
+                        };
+                        let mut check_targets = Vec::new();
+                        for url in settings.check_urls.iter() {
+                            match failover::UrlTest::from_url(url) {
+                                Some(target) => check_targets.push(target),
+                                None => warn!("invalid check url {} for [{}]", url, &tag),
+                            }
+                        }
+                        let mut dns_probe = failover::DnsProbe::default();
+                        if !settings.dns_probe_server.is_empty() {
+                            let server = &settings.dns_probe_server;
+                            match server.parse::<IpAddr>() {
+                                Ok(ip) => dns_probe.server = SocketAddr::new(ip, 53),
+                                Err(_) => match server.parse::<SocketAddr>() {
+                                    Ok(addr) => dns_probe.server = addr,
+                                    Err(e) => warn!(
+                                        "invalid dns probe server {} for [{}]: {}",
+                                        server, &tag, e
+                                    ),
+                                },
+                            }
+                        }
+                        if !settings.dns_probe_domain.is_empty() {
+                            dns_probe.domain = settings.dns_probe_domain.clone();
+                        }
+                        for answer in settings.dns_probe_answers.iter() {
+                            match answer.parse::<IpAddr>() {
+                                Ok(ip) => dns_probe.expected.push(ip),
+                                Err(e) => warn!(
+                                    "invalid dns probe answer {} for [{}]: {}",
+                                    answer, &tag, e
+                                ),
+                            }
+                        }
+                        let mut unprobed = Vec::new();
+                        for u in settings.unprobed_actors.iter() {
+                            let rank = if u.last {
+                                failover::FixedRank::Last
+                            } else {
+                                failover::FixedRank::At(u.rank as usize)
+                            };
+                            for (i, a) in actors.iter().enumerate() {
+                                if a.tag() == &u.actor {
+                                    unprobed.push((i, rank));
+                                }
+                            }
+                        }
+                        let mut builder = failover::HandlerBuilder::default()
+                            .actors(actors)
+                            .fail_timeout(settings.fail_timeout)
+                            .health_check(settings.health_check)
+                            .check_interval(settings.check_interval)
+                            .failover(settings.failover)
+                            .switch_margin(switch_margin)
+                            .throughput_probe(throughput_probe)
+                            .url_test(url_test)
+                            .check_targets(check_targets)
+                            .quorum(settings.check_quorum as usize)
+                            .dns_probe(dns_probe)
+                            .health(Some(health.clone()))
+                            .shutdown(shutdown.clone());
+                        if !settings.user_agent.is_empty() {
+                            builder = builder.user_agent(settings.user_agent.clone());
+                        }
+                        for (i, rank) in unprobed {
+                            builder = builder.unprobed(i, rank);
+                        }
+                        if settings.prewarm_backups > 0 {
+                            builder = builder
+                                .prewarm(settings.prewarm_backups as usize, dns_client.clone());
+                        }
+                        let tcp: Box<failover::TcpHandler> = Box::new(builder.clone().build());
+                        let udp: Box<failover::UdpHandler> = Box::new(builder.build());
+                        failover::controls().register(&tag, tcp.control(), udp.control());
+                        let handler = proxy::Handler::with_options(
                             tag.clone(),
                             colored::Color::TrueColor {
                                 r: 182,
@@ -570,6 +1393,7 @@ impl HandlerManager {
                             ProxyHandlerType::Ensemble,
                             tcp,
                             udp,
+                            options,
                         );
                         handlers.insert(tag.clone(), handler);
                     }
@@ -602,7 +1426,7 @@ impl HandlerManager {
                             actors: actors.clone(),
                             dns_client: dns_client.clone(),
                         });
-                        let handler = proxy::Handler::new(
+                        let handler = proxy::Handler::with_options(
                             tag.clone(),
                             colored::Color::TrueColor {
                                 r: 226,
@@ -612,11 +1436,13 @@ impl HandlerManager {
                             ProxyHandlerType::Ensemble,
                             tcp,
                             udp,
+                            options,
                         );
                         handlers.insert(tag.clone(), handler);
                     }
-                    "direct" | "drop" | "redirect" | "socks" | "shadowsocks" | "trojan"
-                    | "vmess" | "vless" | "tls" | "ws" | "h2" => (),
+                    "direct" | "drop" | "reject" | "dns" | "fixed" | "redirect" | "socks"
+                    | "http" | "shadowsocks" | "trojan" | "vmess" | "vless" | "tls" | "ws"
+                    | "h2" | "obfs" | "limit" => (),
                     _ => {
                         warn!("unknown outbound protocol {:?}", outbound.protocol);
                     }
@@ -624,12 +1450,36 @@ impl HandlerManager {
             }
         }
 
+        let relay_buffer_sizes = outbounds
+            .iter()
+            .filter(|o| o.relay_buffer_size > 0)
+            .map(|o| (o.tag.clone(), o.relay_buffer_size as usize))
+            .collect();
+
         HandlerManager {
             handlers,
             default_handler,
+            dns_client,
+            #[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+            fake_dns,
+            relay_buffer_sizes,
         }
     }
 
+    /// The DNS client shared by the handlers.
+    pub fn dns_client(&self) -> Arc<DnsClient> {
+        self.dns_client.clone()
+    }
+
+    /// The fake DNS shared by the dns outbounds answering with fake IPs and
+    /// the tun inbound, so that the fake IPs handed out by either are mapped
+    /// back to their domains by both. The real IPs of the domains are looked
+    /// up once dialed, on the DNS client shared by the handlers.
+    #[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
+    pub fn fake_dns(&self) -> Arc<TokioMutex<FakeDns>> {
+        self.fake_dns.clone()
+    }
+
     pub fn add(&mut self, tag: String, handler: Arc<dyn ProxyHandler>) {
         self.handlers.insert(tag, handler);
     }
@@ -638,6 +1488,15 @@ impl HandlerManager {
         self.handlers.get(tag)
     }
 
+    /// Initial size of the buffers relaying TCP data through the outbound
+    /// `tag`.
+    pub fn relay_buffer_size(&self, tag: &str) -> usize {
+        self.relay_buffer_sizes
+            .get(tag)
+            .copied()
+            .unwrap_or(option::RELAY_BUFFER_SIZE)
+    }
+
     pub fn default_handler(&self) -> Option<&String> {
         self.default_handler.as_ref()
     }
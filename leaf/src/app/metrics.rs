@@ -0,0 +1,761 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::task::{Context, Poll};
+use lazy_static::lazy_static;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::proxy::error::FailureStage;
+use crate::proxy::{ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream};
+
+pub const TCP_CONNECTIONS_TOTAL: &str = "tcp_connections_total";
+pub const TCP_CONNECTIONS_ACTIVE: &str = "tcp_connections_active";
+pub const TCP_BYTES_SENT: &str = "tcp_bytes_sent_total";
+pub const TCP_BYTES_RECEIVED: &str = "tcp_bytes_received_total";
+pub const TCP_HANDSHAKE_SECONDS: &str = "tcp_handshake_seconds";
+pub const TCP_FAILURES_TOTAL: &str = "tcp_failures_total";
+pub const UDP_SESSIONS_TOTAL: &str = "udp_sessions_total";
+pub const UDP_SESSIONS_ACTIVE: &str = "udp_sessions_active";
+pub const UDP_BYTES_SENT: &str = "udp_bytes_sent_total";
+pub const UDP_BYTES_RECEIVED: &str = "udp_bytes_received_total";
+
+const HANDSHAKE_BUCKETS: &[f64] = &[0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::new();
+}
+
+/// Returns the registry the dispatcher reports to.
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
+/// Returns the current values of all metrics reported by the dispatcher.
+pub fn metrics() -> Snapshot {
+    REGISTRY.snapshot()
+}
+
+/// Returns the name of metric `name` with `labels`, e.g.
+/// `tcp_failures_total{outbound="proxy",stage="dns"}`.
+pub fn labeled(name: &str, labels: &[(&str, &str)]) -> String {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{}{{{}}}", name, labels.join(","))
+}
+
+// The name of a metric without its labels.
+#[cfg(feature = "metrics-prometheus")]
+fn base_name(name: &str) -> &str {
+    name.split('{').next().unwrap_or(name)
+}
+
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, v: i64) {
+        self.0.store(v, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts observations falling under each of a set of upper bounds.
+pub struct Histogram {
+    bounds: Vec<f64>,
+    // One more bucket than bounds, for observations above the last bound.
+    buckets: Vec<AtomicU64>,
+    sum: Mutex<f64>,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        Histogram {
+            bounds: bounds.to_vec(),
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum: Mutex::new(0.0),
+        }
+    }
+
+    pub fn observe(&self, v: f64) {
+        let i = self
+            .bounds
+            .iter()
+            .position(|b| v <= *b)
+            .unwrap_or(self.bounds.len());
+        self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        *self.sum.lock().unwrap() += v;
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            bounds: self.bounds.clone(),
+            counts: self
+                .buckets
+                .iter()
+                .map(|b| b.load(Ordering::Relaxed))
+                .collect(),
+            sum: *self.sum.lock().unwrap(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramSnapshot {
+    pub bounds: Vec<f64>,
+    /// Observations of each bucket, not cumulative, the last one counts
+    /// observations above all bounds.
+    pub counts: Vec<u64>,
+    pub sum: f64,
+}
+
+impl HistogramSnapshot {
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// Point-in-time values of the metrics of a registry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub counters: BTreeMap<String, u64>,
+    pub gauges: BTreeMap<String, i64>,
+    pub histograms: BTreeMap<String, HistogramSnapshot>,
+}
+
+impl Snapshot {
+    pub fn counter(&self, name: &str) -> u64 {
+        self.counters.get(name).cloned().unwrap_or(0)
+    }
+
+    pub fn gauge(&self, name: &str) -> i64 {
+        self.gauges.get(name).cloned().unwrap_or(0)
+    }
+
+    /// Serializes the snapshot in the Prometheus text exposition format,
+    /// metric names are prefixed with `leaf_`.
+    #[cfg(feature = "metrics-prometheus")]
+    pub fn to_prometheus(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        let mut last_base = None;
+        for (name, v) in self.counters.iter() {
+            // Labeled counters of the same metric sort next to each other
+            // and share a single type line.
+            let base = base_name(name);
+            if last_base != Some(base) {
+                let _ = writeln!(out, "# TYPE leaf_{} counter", base);
+                last_base = Some(base);
+            }
+            let _ = writeln!(out, "leaf_{} {}", name, v);
+        }
+        for (name, v) in self.gauges.iter() {
+            let _ = writeln!(out, "# TYPE leaf_{} gauge\nleaf_{} {}", name, name, v);
+        }
+        for (name, h) in self.histograms.iter() {
+            let _ = writeln!(out, "# TYPE leaf_{} histogram", name);
+            let mut cumulative = 0;
+            for (bound, n) in h.bounds.iter().zip(h.counts.iter()) {
+                cumulative += n;
+                let _ = writeln!(
+                    out,
+                    "leaf_{}_bucket{{le=\"{}\"}} {}",
+                    name, bound, cumulative
+                );
+            }
+            let _ = writeln!(out, "leaf_{}_bucket{{le=\"+Inf\"}} {}", name, h.count());
+            let _ = writeln!(out, "leaf_{}_sum {}", name, h.sum);
+            let _ = writeln!(out, "leaf_{}_count {}", name, h.count());
+        }
+        out
+    }
+}
+
+/// A set of named metrics, created on first use.
+#[derive(Default)]
+pub struct Registry {
+    counters: Mutex<BTreeMap<String, Arc<Counter>>>,
+    gauges: Mutex<BTreeMap<String, Arc<Gauge>>>,
+    histograms: Mutex<BTreeMap<String, Arc<Histogram>>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn counter(&self, name: &str) -> Arc<Counter> {
+        self.counters
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    pub fn gauge(&self, name: &str) -> Arc<Gauge> {
+        self.gauges
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Returns the histogram `name`, `bounds` is only used if it doesn't
+    /// exist yet.
+    pub fn histogram(&self, name: &str, bounds: &[f64]) -> Arc<Histogram> {
+        self.histograms
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Histogram::new(bounds)))
+            .clone()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            counters: self
+                .counters
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.get()))
+                .collect(),
+            gauges: self
+                .gauges
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.get()))
+                .collect(),
+            histograms: self
+                .histograms
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.snapshot()))
+                .collect(),
+        }
+    }
+
+    /// Starts tracking a TCP connection, it's counted as active until the
+    /// returned stats are dropped.
+    pub fn tcp_connection(&self) -> Arc<ConnStats> {
+        self.counter(TCP_CONNECTIONS_TOTAL).inc();
+        Arc::new(ConnStats::new(
+            self.gauge(TCP_CONNECTIONS_ACTIVE),
+            self.counter(TCP_BYTES_SENT),
+            self.counter(TCP_BYTES_RECEIVED),
+        ))
+    }
+
+    /// Starts tracking a UDP session, it's counted as active until the
+    /// returned stats are dropped.
+    pub fn udp_session(&self) -> Arc<ConnStats> {
+        self.counter(UDP_SESSIONS_TOTAL).inc();
+        Arc::new(ConnStats::new(
+            self.gauge(UDP_SESSIONS_ACTIVE),
+            self.counter(UDP_BYTES_SENT),
+            self.counter(UDP_BYTES_RECEIVED),
+        ))
+    }
+
+    pub fn observe_tcp_handshake(&self, elapsed: Duration) {
+        self.histogram(TCP_HANDSHAKE_SECONDS, HANDSHAKE_BUCKETS)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Counts a TCP connection through `outbound` failing at `stage`.
+    pub fn tcp_failure(&self, outbound: &str, stage: FailureStage) {
+        self.counter(&tcp_failures(outbound, stage)).inc();
+    }
+}
+
+/// Returns the name of the counter of TCP connections through `outbound`
+/// failing at `stage`.
+pub fn tcp_failures(outbound: &str, stage: FailureStage) -> String {
+    labeled(
+        TCP_FAILURES_TOTAL,
+        &[("outbound", outbound), ("stage", stage.as_str())],
+    )
+}
+
+/// Why a connection or UDP session ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CloseReason {
+    /// The client closed its side first.
+    ClientEof,
+    /// The server closed its side first.
+    ServerEof,
+    /// Reading or writing either side failed.
+    Error,
+    /// Nothing was relayed for too long.
+    IdleTimeout,
+    /// The relay was cancelled before it ended, e.g. on shutdown.
+    Forced,
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            CloseReason::ClientEof => "client eof",
+            CloseReason::ServerEof => "server eof",
+            CloseReason::Error => "error",
+            CloseReason::IdleTimeout => "idle timeout",
+            CloseReason::Forced => "forced close",
+        };
+        f.write_str(s)
+    }
+}
+
+impl CloseReason {
+    /// The reason for a relay ending with `e`.
+    pub fn from_error(e: &io::Error) -> Self {
+        if e.kind() == io::ErrorKind::TimedOut {
+            CloseReason::IdleTimeout
+        } else {
+            CloseReason::Error
+        }
+    }
+}
+
+/// Bytes moved by a single connection, also added to the totals of the
+/// registry it comes from, and why it ended.
+pub struct ConnStats {
+    sent: AtomicU64,
+    received: AtomicU64,
+    close_reason: Mutex<Option<CloseReason>>,
+    active: Arc<Gauge>,
+    total_sent: Arc<Counter>,
+    total_received: Arc<Counter>,
+}
+
+impl ConnStats {
+    fn new(active: Arc<Gauge>, total_sent: Arc<Counter>, total_received: Arc<Counter>) -> Self {
+        active.inc();
+        ConnStats {
+            sent: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            close_reason: Mutex::new(None),
+            active,
+            total_sent,
+            total_received,
+        }
+    }
+
+    pub fn add_sent(&self, n: u64) {
+        self.sent.fetch_add(n, Ordering::Relaxed);
+        self.total_sent.add(n);
+    }
+
+    pub fn add_received(&self, n: u64) {
+        self.received.fetch_add(n, Ordering::Relaxed);
+        self.total_received.add(n);
+    }
+
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Records why the connection ended, the first reason recorded sticks.
+    pub fn set_close_reason(&self, reason: CloseReason) {
+        self.close_reason.lock().unwrap().get_or_insert(reason);
+    }
+
+    pub fn close_reason(&self) -> Option<CloseReason> {
+        *self.close_reason.lock().unwrap()
+    }
+}
+
+impl Drop for ConnStats {
+    fn drop(&mut self) {
+        self.active.dec();
+    }
+}
+
+type OnDrop = Box<dyn FnOnce(&ConnStats) + Send + Sync>;
+
+/// Counts bytes written to and read from a stream, as sent and received.
+pub struct CountingStream<S> {
+    inner: S,
+    stats: Arc<ConnStats>,
+    on_drop: Option<OnDrop>,
+}
+
+impl<S> CountingStream<S> {
+    pub fn new(inner: S, stats: Arc<ConnStats>) -> Self {
+        CountingStream {
+            inner,
+            stats,
+            on_drop: None,
+        }
+    }
+
+    /// Calls `f` with the stats of the connection once the stream is
+    /// dropped, e.g. to log its byte totals.
+    pub fn on_drop<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&ConnStats) + Send + Sync + 'static,
+    {
+        self.on_drop = Some(Box::new(f));
+        self
+    }
+}
+
+impl<S> Drop for CountingStream<S> {
+    fn drop(&mut self) {
+        if let Some(f) = self.on_drop.take() {
+            f(&self.stats);
+        }
+    }
+}
+
+impl<S: ProxyStream> ProxyStream for CountingStream<S> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+
+    // Bytes relayed with splice(2) don't go through the stream, the relay
+    // reports them to the stats.
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn tcp_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        self.inner.tcp_fd()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn idle_timeout(&self) -> Option<Duration> {
+        self.inner.idle_timeout()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let res = Pin::new(&mut me.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            me.stats.add_received(n as u64);
+        }
+        res
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let res = Pin::new(&mut me.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            me.stats.add_sent(n as u64);
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Counts payload bytes of datagrams sent and received, the session stays
+/// active until both halves are dropped.
+pub struct CountingDatagram {
+    inner: Box<dyn ProxyDatagram>,
+    stats: Arc<ConnStats>,
+}
+
+impl CountingDatagram {
+    pub fn new(inner: Box<dyn ProxyDatagram>, stats: Arc<ConnStats>) -> Self {
+        CountingDatagram { inner, stats }
+    }
+
+    pub fn stats(&self) -> Arc<ConnStats> {
+        self.stats.clone()
+    }
+}
+
+impl ProxyDatagram for CountingDatagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        let (r, s) = self.inner.split();
+        (
+            Box::new(CountingRecvHalf(r, self.stats.clone())),
+            Box::new(CountingSendHalf(s, self.stats)),
+        )
+    }
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+struct CountingRecvHalf(Box<dyn ProxyDatagramRecvHalf>, Arc<ConnStats>);
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for CountingRecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (n, addr) = self.0.recv_from(buf).await?;
+        self.1.add_received(n as u64);
+        Ok((n, addr))
+    }
+}
+
+struct CountingSendHalf(Box<dyn ProxyDatagramSendHalf>, Arc<ConnStats>);
+
+#[async_trait]
+impl ProxyDatagramSendHalf for CountingSendHalf {
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        let n = self.0.send_to(buf, target).await?;
+        self.1.add_sent(n as u64);
+        Ok(n)
+    }
+
+    async fn send_batch(&mut self, pkts: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        let n = self.0.send_batch(pkts).await?;
+        let sent: usize = pkts[..n].iter().map(|(buf, _)| buf.len()).sum();
+        self.1.add_sent(sent as u64);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+    use super::*;
+    use crate::proxy::{datagram::SimpleDatagram, stream::SimpleStream};
+
+    #[tokio::test]
+    async fn test_stream_counts() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1000];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf[..300]).await.unwrap();
+        });
+
+        let registry = Registry::new();
+        let stats = registry.tcp_connection();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = CountingStream::new(SimpleStream(stream), stats.clone());
+        assert_eq!(registry.snapshot().gauge(TCP_CONNECTIONS_ACTIVE), 1);
+        stream.write_all(&[1u8; 1000]).await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(stats.sent(), 1000);
+        assert_eq!(stats.received(), 300);
+
+        drop(stream);
+        drop(stats);
+        let stats = registry.tcp_connection();
+        drop(stats);
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.counter(TCP_CONNECTIONS_TOTAL), 2);
+        assert_eq!(snapshot.gauge(TCP_CONNECTIONS_ACTIVE), 0);
+        assert_eq!(snapshot.counter(TCP_BYTES_SENT), 1000);
+        assert_eq!(snapshot.counter(TCP_BYTES_RECEIVED), 300);
+    }
+
+    #[tokio::test]
+    async fn test_stream_on_drop() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 100];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf[..40]).await.unwrap();
+        });
+
+        let registry = Registry::new();
+        let totals = Arc::new(Mutex::new(None));
+        let totals2 = totals.clone();
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = CountingStream::new(SimpleStream(stream), registry.tcp_connection())
+            .on_drop(move |stats| {
+                *totals2.lock().unwrap() = Some((stats.sent(), stats.received()));
+            });
+        stream.write_all(&[1u8; 100]).await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(*totals.lock().unwrap(), None);
+
+        drop(stream);
+        assert_eq!(*totals.lock().unwrap(), Some((100, 40)));
+    }
+
+    #[tokio::test]
+    async fn test_datagram_counts() {
+        let mut peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let peer_addr = peer.local_addr().unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let registry = Registry::new();
+        let datagram = Box::new(CountingDatagram::new(
+            Box::new(SimpleDatagram(socket)),
+            registry.udp_session(),
+        ));
+        let (mut recv, mut send) = datagram.split();
+        send.send_to(&[0u8; 100], &peer_addr).await.unwrap();
+        send.send_to(&[0u8; 50], &peer_addr).await.unwrap();
+        let mut buf = [0u8; 200];
+        let (n, src) = peer.recv_from(&mut buf).await.unwrap();
+        assert_eq!(n, 100);
+        peer.send_to(&buf[..20], &src).await.unwrap();
+        let (n, _) = recv.recv_from(&mut buf).await.unwrap();
+        assert_eq!(n, 20);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.counter(UDP_SESSIONS_TOTAL), 1);
+        assert_eq!(snapshot.gauge(UDP_SESSIONS_ACTIVE), 1);
+        assert_eq!(snapshot.counter(UDP_BYTES_SENT), 150);
+        assert_eq!(snapshot.counter(UDP_BYTES_RECEIVED), 20);
+        drop(recv);
+        assert_eq!(registry.snapshot().gauge(UDP_SESSIONS_ACTIVE), 1);
+        drop(send);
+        assert_eq!(registry.snapshot().gauge(UDP_SESSIONS_ACTIVE), 0);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let registry = Registry::new();
+        registry.observe_tcp_handshake(Duration::from_millis(5));
+        registry.observe_tcp_handshake(Duration::from_millis(200));
+        registry.observe_tcp_handshake(Duration::from_secs(20));
+        let snapshot = registry.snapshot();
+        let h = &snapshot.histograms[TCP_HANDSHAKE_SECONDS];
+        assert_eq!(h.count(), 3);
+        assert_eq!(h.counts[0], 1);
+        assert_eq!(h.counts[3], 1);
+        assert_eq!(h.counts[h.bounds.len()], 1);
+        assert!((h.sum - 20.205).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tcp_failure_stages() {
+        use crate::proxy::ProxyError;
+
+        let registry = Registry::new();
+        let dns: io::Error = ProxyError::Dns {
+            host: "example.com".to_string(),
+            source: "no record".into(),
+        }
+        .into();
+        registry.tcp_failure("proxy", FailureStage::of(&dns));
+        let handshake: io::Error = ProxyError::Handshake("unexpected reply".into()).into();
+        registry.tcp_failure("proxy", FailureStage::of(&handshake));
+        registry.tcp_failure("proxy", FailureStage::of(&handshake));
+
+        let snapshot = registry.snapshot();
+        assert_eq!(
+            snapshot.counter("tcp_failures_total{outbound=\"proxy\",stage=\"dns\"}"),
+            1
+        );
+        assert_eq!(
+            snapshot.counter(&tcp_failures("proxy", FailureStage::Handshake)),
+            2
+        );
+        assert_eq!(
+            snapshot.counter(&tcp_failures("proxy", FailureStage::Connect)),
+            0
+        );
+    }
+
+    #[cfg(feature = "metrics-prometheus")]
+    #[test]
+    fn test_prometheus_labels() {
+        let registry = Registry::new();
+        registry.tcp_failure("a", FailureStage::Dns);
+        registry.tcp_failure("b", FailureStage::Tls);
+        let text = registry.snapshot().to_prometheus();
+        assert_eq!(
+            text,
+            "# TYPE leaf_tcp_failures_total counter\n\
+             leaf_tcp_failures_total{outbound=\"a\",stage=\"dns\"} 1\n\
+             leaf_tcp_failures_total{outbound=\"b\",stage=\"tls\"} 1\n"
+        );
+    }
+
+    #[cfg(feature = "metrics-prometheus")]
+    #[test]
+    fn test_prometheus() {
+        let registry = Registry::new();
+        registry.counter("requests_total").add(3);
+        registry.gauge("active").set(-1);
+        let h = registry.histogram("latency", &[0.5, 1.0]);
+        h.observe(0.25);
+        h.observe(0.75);
+        h.observe(2.0);
+        let text = registry.snapshot().to_prometheus();
+        assert_eq!(
+            text,
+            "# TYPE leaf_requests_total counter\n\
+             leaf_requests_total 3\n\
+             # TYPE leaf_active gauge\n\
+             leaf_active -1\n\
+             # TYPE leaf_latency histogram\n\
+             leaf_latency_bucket{le=\"0.5\"} 1\n\
+             leaf_latency_bucket{le=\"1\"} 2\n\
+             leaf_latency_bucket{le=\"+Inf\"} 3\n\
+             leaf_latency_sum 3\n\
+             leaf_latency_count 3\n"
+        );
+    }
+}
@@ -1,4 +1,8 @@
+pub mod capture;
 pub mod dispatcher;
 pub mod handler_manager;
+pub mod metrics;
 pub mod nat_manager;
 pub mod router;
+#[cfg(all(target_os = "linux", feature = "splice"))]
+pub mod splice;
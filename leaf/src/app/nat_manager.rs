@@ -1,7 +1,8 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -11,45 +12,277 @@ use tokio::sync::{
     mpsc::{self, Sender},
     Mutex as TokioMutex,
 };
+use tokio::time::Instant;
 
 use crate::app::dispatcher::Dispatcher;
+use crate::app::metrics::{CloseReason, ConnStats};
+use crate::common::{fake_dns, shutdown::ShutdownToken};
+use crate::proxy::ProxyDatagram;
 use crate::session::{Session, SocksAddr};
 
 static UDP_SESSION_TIMEOUT: u64 = 30;
 static UDP_SESSION_TIMEOUT_CHECK_INTERVAL: u64 = 10;
+static DNS_QUERY_TIMEOUT: u64 = 5;
+// Maximum number of queued uplink packets sent together.
+const UDP_UPLINK_BATCH: usize = 16;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct UdpPacket {
     pub data: Vec<u8>,
     pub src_addr: Option<SocksAddr>,
     pub dst_addr: Option<SocksAddr>,
 }
 
-type SessionMap = Arc<TokioMutex<HashMap<SocketAddr, (Sender<UdpPacket>, AbortHandle, Instant)>>>;
+/// How the UDP sessions of a client are mapped to outbound sockets.
+///
+/// With `Cone`, the default, all packets from a client address go out
+/// through a single socket whatever their destination, so every peer sees
+/// the same public port. This is what NAT traversal in peer-to-peer
+/// applications relies on, but all destinations are routed by the first one
+/// the client sent to.
+///
+/// With `Symmetric`, each destination of a client gets a socket of its own
+/// and is routed on its own. It takes a socket per destination and peers see
+/// a different port each, which defeats hole punching.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NatType {
+    Cone,
+    Symmetric,
+}
+
+impl Default for NatType {
+    fn default() -> Self {
+        NatType::Cone
+    }
+}
+
+/// How long UDP sessions live without activity, by destination port, e.g.
+/// seconds for DNS and minutes for VoIP. With `NatType::Cone` a session
+/// carries all destinations of a client, the first one decides.
+#[derive(Clone, Debug)]
+pub struct SessionTimeouts {
+    pub default: Duration,
+    /// Timeouts of the sessions to these ports instead of the default.
+    pub ports: HashMap<u16, Duration>,
+    /// How long a DNS query waits for its response before the client is
+    /// answered SERVFAIL.
+    pub dns_query: Duration,
+}
+
+impl Default for SessionTimeouts {
+    fn default() -> Self {
+        SessionTimeouts {
+            default: Duration::from_secs(UDP_SESSION_TIMEOUT),
+            ports: HashMap::new(),
+            dns_query: Duration::from_secs(DNS_QUERY_TIMEOUT),
+        }
+    }
+}
+
+impl SessionTimeouts {
+    /// Returns the timeout of a session to `port`.
+    pub fn for_port(&self, port: u16) -> Duration {
+        self.ports.get(&port).copied().unwrap_or(self.default)
+    }
+
+    // Sessions are checked often enough for the shortest timeout to be
+    // honored within twice its length.
+    fn check_interval(&self) -> Duration {
+        self.ports
+            .values()
+            .chain(std::iter::once(&self.default))
+            .min()
+            .copied()
+            .unwrap_or(self.default)
+            .min(Duration::from_secs(UDP_SESSION_TIMEOUT_CHECK_INTERVAL))
+    }
+}
+
+/// Identifies a UDP session, see `NatManager::session_key`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    source: SocketAddr,
+    destination: Option<SocksAddr>,
+}
+
+impl fmt::Display for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.destination {
+            Some(destination) => write!(f, "{} -> {}", self.source, destination),
+            None => write!(f, "{}", self.source),
+        }
+    }
+}
+
+// The uplink channel, the downlink task, the last activity, the stats, the
+// timeout and the pending DNS queries of each session.
+type SessionMap = Arc<
+    TokioMutex<
+        HashMap<
+            SessionKey,
+            (
+                Sender<UdpPacket>,
+                AbortHandle,
+                Instant,
+                Arc<ConnStats>,
+                Duration,
+                Arc<PendingDns>,
+            ),
+        >,
+    >,
+>;
+
+fn dns_query_id(data: &[u8]) -> Option<[u8; 2]> {
+    if data.len() < 2 {
+        return None;
+    }
+    Some([data[0], data[1]])
+}
+
+// The DNS queries a session relayed and got no response for yet. They are
+// answered SERVFAIL when the upstream fails or stays silent, instead of
+// leaving the client waiting for its own timeout.
+struct PendingDns {
+    client: SocketAddr,
+    // The server and the query.
+    queries: std::sync::Mutex<Vec<(SocketAddr, Vec<u8>)>>,
+    client_ch_tx: std::sync::Mutex<Sender<UdpPacket>>,
+}
+
+impl PendingDns {
+    fn new(client: SocketAddr, client_ch_tx: Sender<UdpPacket>) -> Self {
+        PendingDns {
+            client,
+            queries: std::sync::Mutex::new(Vec::new()),
+            client_ch_tx: std::sync::Mutex::new(client_ch_tx),
+        }
+    }
+
+    // Records `pkt` if it's a DNS query, returns its server and id.
+    fn add(&self, pkt: &UdpPacket) -> Option<(SocketAddr, [u8; 2])> {
+        let server = match &pkt.dst_addr {
+            Some(SocksAddr::Ip(addr)) if addr.port() == 53 => *addr,
+            _ => return None,
+        };
+        let id = dns_query_id(&pkt.data)?;
+        self.queries
+            .lock()
+            .unwrap()
+            .push((server, pkt.data.clone()));
+        Some((server, id))
+    }
+
+    // Removes the query of `server` with the id `id`, returns it if any.
+    fn remove(&self, server: &SocketAddr, id: [u8; 2]) -> Option<Vec<u8>> {
+        let mut queries = self.queries.lock().unwrap();
+        let i = queries
+            .iter()
+            .position(|(s, q)| s == server && dns_query_id(q) == Some(id))?;
+        Some(queries.remove(i).1)
+    }
+
+    // A response from `server` answers its query.
+    fn answered(&self, server: &SocketAddr, response: &[u8]) {
+        if let Some(id) = dns_query_id(response) {
+            self.remove(server, id);
+        }
+    }
+
+    // Answers SERVFAIL to the query of `server` with the id `id`, if still
+    // pending.
+    fn fail(&self, server: &SocketAddr, id: [u8; 2]) {
+        if let Some(query) = self.remove(server, id) {
+            self.servfail(*server, &query);
+        }
+    }
+
+    // Answers SERVFAIL to all pending queries.
+    fn fail_all(&self) {
+        let queries: Vec<_> = self.queries.lock().unwrap().drain(..).collect();
+        for (server, query) in queries {
+            self.servfail(server, &query);
+        }
+    }
+
+    fn servfail(&self, server: SocketAddr, query: &[u8]) {
+        let data = match fake_dns::generate_servfail_response(query) {
+            Ok(data) => data,
+            Err(err) => {
+                debug!("generate servfail response failed: {}", err);
+                return;
+            }
+        };
+        debug!(
+            "dns query {} -> {} failed, answering servfail",
+            &self.client, &server
+        );
+        let pkt = UdpPacket {
+            data,
+            src_addr: Some(SocksAddr::Ip(server)),
+            dst_addr: Some(SocksAddr::Ip(self.client)),
+        };
+        if let Err(err) = self.client_ch_tx.lock().unwrap().try_send(pkt) {
+            debug!("send servfail response failed: {:?}", err);
+        }
+    }
+}
+
+// Records why a session ended and logs it, unless it already ended.
+fn session_ended(key: &SessionKey, stats: &ConnStats, reason: CloseReason) {
+    if stats.close_reason().is_some() {
+        return;
+    }
+    stats.set_close_reason(reason);
+    debug!(
+        "udp session {} ended by {}, {} bytes sent, {} bytes received",
+        key,
+        reason,
+        stats.sent(),
+        stats.received()
+    );
+}
 
 pub struct NatManager {
     sessions: SessionMap,
     dispatcher: Arc<Dispatcher>,
     timeout_check_task: TokioMutex<Option<BoxFuture<'static, ()>>>,
+    shutdown: ShutdownToken,
+    nat_type: NatType,
+    timeouts: SessionTimeouts,
 }
 
 impl NatManager {
-    pub fn new(dispatcher: Arc<Dispatcher>) -> Self {
+    /// All sessions are ended once `shutdown` is signaled.
+    pub fn new(dispatcher: Arc<Dispatcher>, shutdown: ShutdownToken, nat_type: NatType) -> Self {
+        Self::with_timeouts(dispatcher, shutdown, nat_type, SessionTimeouts::default())
+    }
+
+    /// Same as `new`, sessions expire after `timeouts`.
+    pub fn with_timeouts(
+        dispatcher: Arc<Dispatcher>,
+        shutdown: ShutdownToken,
+        nat_type: NatType,
+        timeouts: SessionTimeouts,
+    ) -> Self {
+        let check_interval = timeouts.check_interval();
         let sessions: SessionMap = Arc::new(TokioMutex::new(HashMap::new()));
         let sessions2 = sessions.clone();
+        let sessions3 = sessions.clone();
+        let shutdown2 = shutdown.clone();
 
         // The task is lazy, will not run until any sessions added.
-        let timeout_check_task: BoxFuture<'static, ()> = Box::pin(async move {
+        let timeout_check_task = async move {
             loop {
                 let mut sessions = sessions2.lock().await;
                 let n_total = sessions.len();
                 let now = Instant::now();
                 sessions.retain(|key, sess| {
-                    if now.duration_since(sess.2).as_secs() >= UDP_SESSION_TIMEOUT {
+                    if now.duration_since(sess.2) >= sess.4 {
                         // Abort downlink task, uplink task will end automatically
                         // when we drop the channel's tx side upon session removal.
                         sess.1.abort();
-                        debug!("udp session {} ended", key);
+                        sess.5.fail_all();
+                        session_ended(key, &sess.3, CloseReason::IdleTimeout);
                         false
                     } else {
                         true
@@ -65,27 +298,66 @@ impl NatManager {
                         n_remaining
                     );
                 }
-                tokio::time::delay_for(Duration::from_secs(UDP_SESSION_TIMEOUT_CHECK_INTERVAL))
-                    .await;
+                tokio::time::delay_for(check_interval).await;
+            }
+        };
+        let timeout_check_task: BoxFuture<'static, ()> = Box::pin(async move {
+            shutdown2.run_until(timeout_check_task).await;
+            // Same as a timeout, aborts the downlink tasks and drops the
+            // uplink channels.
+            let mut sessions = sessions3.lock().await;
+            for (key, sess) in sessions.iter() {
+                sess.1.abort();
+                sess.5.fail_all();
+                session_ended(key, &sess.3, CloseReason::Forced);
             }
+            sessions.clear();
+            debug!("nat manager stopped");
         });
 
         NatManager {
             sessions,
             dispatcher,
             timeout_check_task: TokioMutex::new(Some(timeout_check_task)),
+            shutdown,
+            nat_type,
+            timeouts,
         }
     }
 
-    pub async fn contains_key(&self, key: &SocketAddr) -> bool {
+    /// Returns the key of the session carrying packets from `source` to
+    /// `destination`.
+    pub fn session_key(&self, source: SocketAddr, destination: &SocksAddr) -> SessionKey {
+        let destination = match self.nat_type {
+            NatType::Cone => None,
+            NatType::Symmetric => Some(destination.clone()),
+        };
+        SessionKey {
+            source,
+            destination,
+        }
+    }
+
+    pub async fn contains_key(&self, key: &SessionKey) -> bool {
         self.sessions.lock().await.contains_key(key)
     }
 
-    pub async fn send(&self, key: &SocketAddr, pkt: UdpPacket) {
+    pub async fn send(&self, key: &SessionKey, pkt: UdpPacket) {
         let mut sessions = self.sessions.lock().await;
         if let Some(sess) = sessions.get_mut(key) {
+            let query = sess.5.add(&pkt);
             if let Err(err) = sess.0.try_send(pkt) {
                 debug!("send uplink packet failed {:?}", err);
+                if let Some((server, id)) = query {
+                    sess.5.fail(&server, id);
+                }
+            } else if let Some((server, id)) = query {
+                let pending = sess.5.clone();
+                let timeout = self.timeouts.dns_query;
+                tokio::spawn(async move {
+                    tokio::time::delay_for(timeout).await;
+                    pending.fail(&server, id);
+                });
             }
             sess.2 = Instant::now(); // activity update
         } else {
@@ -97,12 +369,11 @@ impl NatManager {
         self.sessions.lock().await.len()
     }
 
-    pub async fn add_session(
-        &self,
-        sess: &Session,
-        raddr: SocketAddr,
-        client_ch_tx: Sender<UdpPacket>,
-    ) -> Result<()> {
+    pub async fn add_session(&self, sess: &Session, client_ch_tx: Sender<UdpPacket>) -> Result<()> {
+        if self.shutdown.is_signaled() {
+            return Err(anyhow!("nat manager is shut down"));
+        }
+
         if self.timeout_check_task.lock().await.is_some() {
             if let Some(task) = self.timeout_check_task.lock().await.take() {
                 tokio::spawn(task);
@@ -116,29 +387,41 @@ impl NatManager {
                 return Err(anyhow!("dispatch udp failed: {}", e));
             }
         };
-        let (mut target_sock_recv, mut target_sock_send) = socket.split();
+        let stats = socket.stats();
+        let (mut target_sock_recv, mut target_sock_send) = Box::new(socket).split();
+        let raddr = sess.source;
+        let key = self.session_key(sess.source, &sess.destination);
 
         let (target_ch_tx, mut target_ch_rx) = mpsc::channel(100);
 
+        let pending_dns = Arc::new(PendingDns::new(raddr, client_ch_tx.clone()));
         let mut client_ch_tx = client_ch_tx.clone();
 
         // downlink
         let sessions = self.sessions.clone();
+        let key2 = key.clone();
+        let stats2 = stats.clone();
+        let pending_dns2 = pending_dns.clone();
         let downlink_task = async move {
             let mut buf = [0u8; 2 * 1024];
             loop {
                 match target_sock_recv.recv_from(&mut buf).await {
                     Err(err) => {
                         debug!("udp downlink error: {}", err);
-                        sessions.lock().await.remove(&raddr);
+                        sessions.lock().await.remove(&key2);
+                        pending_dns2.fail_all();
+                        session_ended(&key2, &stats2, CloseReason::from_error(&err));
                         break;
                     }
                     Ok((0, _)) => {
                         debug!("receive zero-len udp packet");
-                        sessions.lock().await.remove(&raddr);
+                        sessions.lock().await.remove(&key2);
+                        pending_dns2.fail_all();
+                        session_ended(&key2, &stats2, CloseReason::ServerEof);
                         break;
                     }
                     Ok((n, addr)) => {
+                        pending_dns2.answered(&addr, &buf[..n]);
                         let pkt = UdpPacket {
                             data: (&buf[..n]).to_vec(),
                             src_addr: Some(SocksAddr::from(addr)),
@@ -151,15 +434,19 @@ impl NatManager {
                             );
                         }
 
+                        // The DNS response ends the session, other queries
+                        // still pending won't get theirs.
                         if addr.port() == 53 {
-                            sessions.lock().await.remove(&raddr);
+                            sessions.lock().await.remove(&key2);
+                            pending_dns2.fail_all();
+                            session_ended(&key2, &stats2, CloseReason::ServerEof);
                             break;
                         }
 
                         // activity update
                         {
                             let mut sessions = sessions.lock().await;
-                            if let Some(sess) = sessions.get_mut(&raddr) {
+                            if let Some(sess) = sessions.get_mut(&key2) {
                                 if addr.port() == 53 {
                                     // If the destination port is 53, we assume it's a
                                     // DNS query and set a negative timeout so it will
@@ -178,45 +465,202 @@ impl NatManager {
         let (downlink_task, downlink_task_handle) = abortable(downlink_task);
         tokio::spawn(downlink_task);
 
-        self.sessions
-            .lock()
-            .await
-            .insert(raddr, (target_ch_tx, downlink_task_handle, Instant::now()));
+        let timeout = self.timeouts.for_port(sess.destination.port());
+        self.sessions.lock().await.insert(
+            key,
+            (
+                target_ch_tx,
+                downlink_task_handle,
+                Instant::now(),
+                stats,
+                timeout,
+                pending_dns.clone(),
+            ),
+        );
 
         // uplink
         tokio::spawn(async move {
+            let mut pkts = Vec::with_capacity(UDP_UPLINK_BATCH);
             while let Some(pkt) = target_ch_rx.recv().await {
-                if pkt.dst_addr.is_none() {
-                    warn!("unexpected none dst addr in uplink pkts");
-                    continue;
+                // Packets already queued go out together, in a single
+                // syscall for sockets batching sends.
+                pkts.push(pkt);
+                while pkts.len() < UDP_UPLINK_BATCH {
+                    match target_ch_rx.try_recv() {
+                        Ok(pkt) => pkts.push(pkt),
+                        Err(_) => break,
+                    }
                 }
-                let addr = match pkt.dst_addr {
-                    Some(a) => match a {
-                        SocksAddr::Ip(v) => v,
-                        _ => {
+                let batch: Vec<(&[u8], SocketAddr)> = pkts
+                    .iter()
+                    .filter_map(|pkt| match &pkt.dst_addr {
+                        Some(SocksAddr::Ip(addr)) => Some((&pkt.data[..], *addr)),
+                        Some(_) => {
                             warn!("unexpected domain addr");
-                            continue;
+                            None
+                        }
+                        None => {
+                            warn!("unexpected none dst addr in uplink pkts");
+                            None
+                        }
+                    })
+                    .collect();
+                let mut sent = 0;
+                while sent < batch.len() {
+                    match target_sock_send.send_batch(&batch[sent..]).await {
+                        Ok(0) => {
+                            debug!("uplink send zero packets");
+                            break;
+                        }
+                        Ok(n) => sent += n,
+                        Err(err) => {
+                            // Drops the packet failing to send, a DNS query
+                            // is answered SERVFAIL.
+                            debug!("uplink send error {:?}", err);
+                            let (data, target) = batch[sent];
+                            if let Some(id) = dns_query_id(data) {
+                                pending_dns.fail(&target, id);
+                            }
+                            sent += 1;
                         }
-                    },
-                    None => {
-                        warn!("unexpected none addr");
-                        continue;
-                    }
-                };
-                match target_sock_send.send_to(&pkt.data, &addr).await {
-                    Ok(0) => {
-                        debug!("uplink send zero bytes");
-                    }
-                    Ok(_) => {
-                        continue;
-                    }
-                    Err(err) => {
-                        debug!("uplink send error {:?}", err);
                     }
                 }
+                pkts.clear();
             }
         });
 
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "outbound-direct"))]
+mod tests {
+    use tokio::net::UdpSocket;
+    use tokio::time::timeout;
+
+    use super::*;
+    use crate::app::{handler_manager::HandlerManager, router::Router};
+    use crate::config;
+
+    fn dispatcher() -> Arc<Dispatcher> {
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "direct".to_string();
+        outbound.protocol = "direct".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let router = Router::new(&protobuf::RepeatedField::new());
+        Arc::new(Dispatcher::new(handler_manager, router))
+    }
+
+    // Replies to every packet with the port it came from.
+    async fn port_echo() -> SocketAddr {
+        let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            while let Ok((_, from)) = socket.recv_from(&mut buf).await {
+                let _ = socket.send_to(&from.port().to_be_bytes(), &from).await;
+            }
+        });
+        addr
+    }
+
+    // Sends a packet from the same client to two destinations, returns the
+    // outbound ports they see.
+    async fn outbound_ports(nat_type: NatType) -> Vec<u16> {
+        let nat_manager = NatManager::new(dispatcher(), ShutdownToken::never(), nat_type);
+        let (client_ch_tx, mut client_ch_rx) = mpsc::channel(10);
+        let source: SocketAddr = "127.0.0.1:10000".parse().unwrap();
+        let mut ports = Vec::new();
+        for _ in 0..2 {
+            let destination = SocksAddr::Ip(port_echo().await);
+            let key = nat_manager.session_key(source, &destination);
+            if !nat_manager.contains_key(&key).await {
+                let sess = Session {
+                    source,
+                    destination: destination.clone(),
+                };
+                nat_manager
+                    .add_session(&sess, client_ch_tx.clone())
+                    .await
+                    .unwrap();
+            }
+            let pkt = UdpPacket {
+                data: b"ping".to_vec(),
+                src_addr: Some(SocksAddr::Ip(source)),
+                dst_addr: Some(destination),
+            };
+            nat_manager.send(&key, pkt).await;
+            let pkt = timeout(Duration::from_secs(1), client_ch_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(pkt.dst_addr, Some(SocksAddr::Ip(source)));
+            ports.push(u16::from_be_bytes([pkt.data[0], pkt.data[1]]));
+        }
+        assert_eq!(
+            nat_manager.size().await,
+            if nat_type == NatType::Cone { 1 } else { 2 }
+        );
+        ports
+    }
+
+    #[tokio::test]
+    async fn test_cone_nat_reuses_port() {
+        let ports = outbound_ports(NatType::Cone).await;
+        assert_eq!(ports[0], ports[1]);
+    }
+
+    #[tokio::test]
+    async fn test_symmetric_nat_port_per_destination() {
+        let ports = outbound_ports(NatType::Symmetric).await;
+        assert_ne!(ports[0], ports[1]);
+    }
+
+    // Moves the paused clock forward and lets the timers due fire.
+    async fn advance(d: Duration) {
+        tokio::time::advance(d).await;
+        tokio::time::delay_for(Duration::from_millis(0)).await;
+    }
+
+    #[tokio::test]
+    async fn test_session_timeout_by_port() {
+        tokio::time::pause();
+        let mut timeouts = SessionTimeouts::default();
+        timeouts.ports.insert(53, Duration::from_millis(500));
+        let nat_manager = NatManager::with_timeouts(
+            dispatcher(),
+            ShutdownToken::never(),
+            NatType::Symmetric,
+            timeouts,
+        );
+        let (client_ch_tx, _client_ch_rx) = mpsc::channel(10);
+        let source: SocketAddr = "127.0.0.1:10000".parse().unwrap();
+        let mut keys = Vec::new();
+        for port in &[53, 3478] {
+            let sess = Session {
+                source,
+                destination: SocksAddr::Ip(SocketAddr::new([127, 0, 0, 1].into(), *port)),
+            };
+            nat_manager
+                .add_session(&sess, client_ch_tx.clone())
+                .await
+                .unwrap();
+            keys.push(nat_manager.session_key(source, &sess.destination));
+        }
+
+        advance(Duration::from_millis(400)).await;
+        assert!(nat_manager.contains_key(&keys[0]).await);
+        advance(Duration::from_millis(1100)).await;
+        // The DNS session is gone, the STUN one stays.
+        assert!(!nat_manager.contains_key(&keys[0]).await);
+        assert!(nat_manager.contains_key(&keys[1]).await);
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
 
 use anyhow::anyhow;
 use anyhow::Result;
@@ -9,7 +10,7 @@ use maxminddb::geoip2::Country;
 use memmap::Mmap;
 
 use crate::config::{self, RoutingRule};
-use crate::session::Session;
+use crate::session::{Session, SocksAddr};
 
 pub trait Condition: Send + Sync + Unpin {
     fn apply(&self, sess: &Session) -> bool;
@@ -18,16 +19,39 @@ pub trait Condition: Send + Sync + Unpin {
 struct Rule {
     target: String,
     condition: Box<dyn Condition>,
+    // Applied to the sniffed SNI instead of the destination.
+    sni: Option<DomainMatcher>,
+    // If any, the rule matches UDP sessions to these ports only.
+    udp_ports: Vec<u16>,
 }
 
 impl Rule {
-    fn new(target: String, condition: Box<dyn Condition>) -> Self {
-        Rule { target, condition }
+    fn new(
+        target: String,
+        condition: Box<dyn Condition>,
+        sni: Option<DomainMatcher>,
+        udp_ports: Vec<u16>,
+    ) -> Self {
+        Rule {
+            target,
+            condition,
+            sni,
+            udp_ports,
+        }
     }
-}
 
-impl Condition for Rule {
-    fn apply(&self, sess: &Session) -> bool {
+    // `sni_sess` is the session with the sniffed SNI as destination.
+    fn matches(&self, sess: &Session, sni_sess: Option<&Session>, udp: bool) -> bool {
+        if !self.udp_ports.is_empty() && !(udp && self.udp_ports.contains(&sess.destination.port()))
+        {
+            return false;
+        }
+        if let Some(sni) = &self.sni {
+            match sni_sess {
+                Some(sni_sess) if sni.apply(sni_sess) => (),
+                _ => return false,
+            }
+        }
         self.condition.apply(sess)
     }
 }
@@ -101,6 +125,83 @@ impl Condition for IpCidrMatcher {
     }
 }
 
+struct PrivateIpMatcher;
+
+// RFC 1918 and unique local addresses, loopback and link-local ones, IPv4
+// ones mapped to IPv6 too.
+fn is_private_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            if segments[..5].iter().all(|s| *s == 0) && segments[5] == 0xffff {
+                return is_private_ip(&IpAddr::V4(ip.to_ipv4().unwrap()));
+            }
+            ip.is_loopback() || segments[0] & 0xfe00 == 0xfc00 || segments[0] & 0xffc0 == 0xfe80
+        }
+    }
+}
+
+impl Condition for PrivateIpMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        if !sess.destination.is_domain() {
+            if let Some(ip) = sess.destination.ip() {
+                if is_private_ip(&ip) {
+                    debug!("[{}] matches private ip", ip);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+struct PortRangeMatcher {
+    values: Vec<(u16, u16)>,
+}
+
+impl PortRangeMatcher {
+    fn new(ranges: &protobuf::RepeatedField<String>) -> Self {
+        let mut values = Vec::new();
+        for range in ranges {
+            match parse_port_range(range) {
+                Some(range) => values.push(range),
+                None => {
+                    debug!("parsing port range {} failed", range);
+                }
+            }
+        }
+        PortRangeMatcher { values }
+    }
+}
+
+// Parses a single port, e.g. "22", or an inclusive range, e.g. "1000-2000".
+fn parse_port_range(s: &str) -> Option<(u16, u16)> {
+    let (lo, hi) = match s.find('-') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, s),
+    };
+    let lo = lo.trim().parse::<u16>().ok()?;
+    let hi = hi.trim().parse::<u16>().ok()?;
+    if lo > hi {
+        return None;
+    }
+    Some((lo, hi))
+}
+
+impl Condition for PortRangeMatcher {
+    fn apply(&self, sess: &Session) -> bool {
+        let port = sess.destination.port();
+        for (lo, hi) in &self.values {
+            if port >= *lo && port <= *hi {
+                debug!("[{}] matches port range [{}-{}]", port, lo, hi);
+                return true;
+            }
+        }
+        false
+    }
+}
+
 struct DomainKeywordMatcher {
     value: String,
 }
@@ -169,6 +270,197 @@ mod tests {
         let d2 = "gle.com".to_string();
         assert!(!is_sub_domain(&d1, &d2));
     }
+
+    fn udp_port_rule(target: &str, ports: Vec<u32>) -> RoutingRule {
+        let mut rr = RoutingRule::new();
+        rr.target_tag = target.to_string();
+        rr.udp_ports = ports;
+        rr
+    }
+
+    #[test]
+    fn test_udp_port_rule() {
+        let mut cidr = RoutingRule::new();
+        cidr.target_tag = "proxy".to_string();
+        cidr.ip_cidrs = protobuf::RepeatedField::from_vec(vec!["1.2.3.0/24".to_string()]);
+        let router = Router::new(&protobuf::RepeatedField::from_vec(vec![
+            udp_port_rule("drop", vec![443]),
+            cidr,
+        ]));
+        let sess = |dest: &str| Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip(dest.parse().unwrap()),
+        };
+
+        // QUIC is dropped, TCP to the same port isn't.
+        assert_eq!(router.pick_udp_route(&sess("1.2.3.4:443")).unwrap(), "drop");
+        assert_eq!(
+            router.pick_route(&sess("1.2.3.4:443"), None).unwrap(),
+            "proxy"
+        );
+        // Other UDP passes.
+        assert_eq!(router.pick_udp_route(&sess("1.2.3.4:53")).unwrap(), "proxy");
+        assert!(router.pick_udp_route(&sess("5.6.7.8:53")).is_err());
+    }
+
+    #[test]
+    fn test_private_ip_rule() {
+        let mut private = RoutingRule::new();
+        private.target_tag = "direct".to_string();
+        private.private_ip = true;
+        let router = Router::new(&protobuf::RepeatedField::from_vec(vec![private]));
+        let sess = |dest: &str| Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip(dest.parse().unwrap()),
+        };
+
+        for dest in &[
+            "192.168.1.1:80",
+            "10.0.0.1:80",
+            "172.16.0.1:80",
+            "127.0.0.1:80",
+            "169.254.1.1:80",
+            "[fc00::1]:80",
+            "[fd12::1]:80",
+            "[::1]:80",
+            "[fe80::1]:80",
+            "[::ffff:192.168.1.1]:80",
+        ] {
+            assert_eq!(
+                router.pick_route(&sess(dest), None).unwrap(),
+                "direct",
+                "{}",
+                dest
+            );
+        }
+        for dest in &[
+            "8.8.8.8:80",
+            "172.32.0.1:80",
+            "[2001:db8::1]:80",
+            "[::ffff:8.8.8.8]:80",
+        ] {
+            assert!(router.pick_route(&sess(dest), None).is_err(), "{}", dest);
+        }
+        // Domains aren't resolved for matching.
+        let domain = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("localhost".to_string(), 80),
+        };
+        assert!(router.pick_route(&domain, None).is_err());
+    }
+
+    fn port_rule(target: &str, ranges: Vec<&str>) -> RoutingRule {
+        let mut rr = RoutingRule::new();
+        rr.target_tag = target.to_string();
+        rr.port_ranges =
+            protobuf::RepeatedField::from_vec(ranges.into_iter().map(String::from).collect());
+        rr
+    }
+
+    #[test]
+    fn test_parse_port_range() {
+        assert_eq!(parse_port_range("22"), Some((22, 22)));
+        assert_eq!(parse_port_range("1000-2000"), Some((1000, 2000)));
+        assert_eq!(parse_port_range("2000-1000"), None);
+        assert_eq!(parse_port_range("70000"), None);
+        assert_eq!(parse_port_range("a-b"), None);
+    }
+
+    #[test]
+    fn test_port_rule() {
+        let mut domain = RoutingRule::new();
+        domain.target_tag = "proxy".to_string();
+        let mut d = config::RoutingRule_Domain::new();
+        d.field_type = config::RoutingRule_Domain_Type::DOMAIN;
+        d.value = "example.com".to_string();
+        domain.domains.push(d);
+        let router = Router::new(&protobuf::RepeatedField::from_vec(vec![
+            port_rule("secure", vec!["22", "3389"]),
+            port_rule("direct", vec!["1000-2000"]),
+            domain,
+        ]));
+        let sess = |host: &str, port: u16| Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain(host.to_string(), port),
+        };
+
+        // Single ports.
+        assert_eq!(
+            router.pick_route(&sess("example.com", 22), None).unwrap(),
+            "secure"
+        );
+        assert_eq!(
+            router.pick_route(&sess("other.com", 3389), None).unwrap(),
+            "secure"
+        );
+        // Ranges, bounds included.
+        assert_eq!(
+            router.pick_route(&sess("example.com", 1000), None).unwrap(),
+            "direct"
+        );
+        assert_eq!(
+            router.pick_route(&sess("other.com", 2000), None).unwrap(),
+            "direct"
+        );
+        // Other ports fall through to the next rules.
+        assert_eq!(
+            router.pick_route(&sess("example.com", 443), None).unwrap(),
+            "proxy"
+        );
+        assert_eq!(
+            router.pick_route(&sess("example.com", 2001), None).unwrap(),
+            "proxy"
+        );
+        assert!(router.pick_route(&sess("other.com", 443), None).is_err());
+    }
+
+    // Returns the rules of its current phase.
+    struct Phased {
+        phase: std::sync::atomic::AtomicUsize,
+        rules: Vec<Arc<protobuf::RepeatedField<RoutingRule>>>,
+    }
+
+    impl RuleProvider for Phased {
+        fn rules(&self) -> Arc<protobuf::RepeatedField<RoutingRule>> {
+            self.rules[self.phase.load(std::sync::atomic::Ordering::SeqCst)].clone()
+        }
+    }
+
+    #[test]
+    fn test_rule_provider() {
+        let phases = vec![
+            vec![port_rule("secure", vec!["22"])],
+            vec![port_rule("direct", vec!["1-1024"])],
+            vec![],
+        ];
+        let provider = Arc::new(Phased {
+            phase: std::sync::atomic::AtomicUsize::new(0),
+            rules: phases
+                .into_iter()
+                .map(|rules| Arc::new(protobuf::RepeatedField::from_vec(rules)))
+                .collect(),
+        });
+        let router = Router::with_provider(provider.clone());
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:22".parse().unwrap()),
+        };
+
+        assert_eq!(router.pick_route(&sess, None).unwrap(), "secure");
+        provider.phase.store(1, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(router.pick_route(&sess, None).unwrap(), "direct");
+        provider.phase.store(2, std::sync::atomic::Ordering::SeqCst);
+        assert!(router.pick_route(&sess, None).is_err());
+
+        let memory = Arc::new(MemoryRuleProvider::new(protobuf::RepeatedField::new()));
+        let router = Router::with_provider(memory.clone());
+        assert!(router.pick_route(&sess, None).is_err());
+        memory.set(protobuf::RepeatedField::from_vec(vec![port_rule(
+            "secure",
+            vec!["22"],
+        )]));
+        assert_eq!(router.pick_route(&sess, None).unwrap(), "secure");
+    }
 }
 
 impl Condition for DomainSuffixMatcher {
@@ -299,12 +591,76 @@ impl Condition for ConditionOr {
     }
 }
 
+/// Source of the ordered routing rules, e.g. the storage of an embedder.
+///
+/// The router asks for the rules on every route it picks and rebuilds its
+/// matchers when it gets another list than the last one, so a provider
+/// swaps its rules atomically by returning a new `Arc`.
+pub trait RuleProvider: Send + Sync {
+    fn rules(&self) -> Arc<protobuf::RepeatedField<RoutingRule>>;
+}
+
+/// Rules kept in memory, replaced as a whole.
+pub struct MemoryRuleProvider {
+    rules: RwLock<Arc<protobuf::RepeatedField<RoutingRule>>>,
+}
+
+impl MemoryRuleProvider {
+    pub fn new(rules: protobuf::RepeatedField<RoutingRule>) -> Self {
+        MemoryRuleProvider {
+            rules: RwLock::new(Arc::new(rules)),
+        }
+    }
+
+    /// Replaces the rules, routes picked from now on follow the new ones.
+    pub fn set(&self, rules: protobuf::RepeatedField<RoutingRule>) {
+        *self.rules.write().unwrap() = Arc::new(rules);
+    }
+}
+
+impl RuleProvider for MemoryRuleProvider {
+    fn rules(&self) -> Arc<protobuf::RepeatedField<RoutingRule>> {
+        self.rules.read().unwrap().clone()
+    }
+}
+
 pub struct Router {
-    rules: Vec<Rule>,
+    provider: Arc<dyn RuleProvider>,
+    // The rules last built, along with the list they were built from.
+    built: RwLock<(Arc<protobuf::RepeatedField<RoutingRule>>, Arc<Vec<Rule>>)>,
 }
 
 impl Router {
     pub fn new(routing_rules: &protobuf::RepeatedField<RoutingRule>) -> Self {
+        Self::with_provider(Arc::new(MemoryRuleProvider::new(routing_rules.clone())))
+    }
+
+    /// Routes by the rules `provider` returns at the time.
+    pub fn with_provider(provider: Arc<dyn RuleProvider>) -> Self {
+        let routing_rules = provider.rules();
+        let rules = Arc::new(Self::build(&routing_rules));
+        Router {
+            provider,
+            built: RwLock::new((routing_rules, rules)),
+        }
+    }
+
+    // The rules of the provider, built anew if they changed.
+    fn rules(&self) -> Arc<Vec<Rule>> {
+        let routing_rules = self.provider.rules();
+        {
+            let built = self.built.read().unwrap();
+            if Arc::ptr_eq(&built.0, &routing_rules) {
+                return built.1.clone();
+            }
+        }
+        debug!("routing rules changed, {} rules", routing_rules.len());
+        let rules = Arc::new(Self::build(&routing_rules));
+        *self.built.write().unwrap() = (routing_rules, rules.clone());
+        rules
+    }
+
+    fn build(routing_rules: &protobuf::RepeatedField<RoutingRule>) -> Vec<Rule> {
         let mut rules = Vec::new();
         let mut mmdb_readers: HashMap<String, Arc<maxminddb::Reader<Mmap>>> = HashMap::new();
         for rr in routing_rules.iter() {
@@ -315,6 +671,12 @@ impl Router {
             if rr.ip_cidrs.len() > 0 {
                 cond_and.add(Box::new(IpCidrMatcher::new(&rr.ip_cidrs)));
             }
+            if rr.private_ip {
+                cond_and.add(Box::new(PrivateIpMatcher));
+            }
+            if rr.port_ranges.len() > 0 {
+                cond_and.add(Box::new(PortRangeMatcher::new(&rr.port_ranges)));
+            }
             if rr.mmdbs.len() > 0 {
                 for mmdb in rr.mmdbs.iter() {
                     let reader = match mmdb_readers.get(&mmdb.file) {
@@ -336,19 +698,54 @@ impl Router {
                     )));
                 }
             }
-            if cond_and.is_empty() {
+            let sni = if rr.snis.len() > 0 {
+                Some(DomainMatcher::new(&rr.snis))
+            } else {
+                None
+            };
+            let udp_ports: Vec<u16> = rr.udp_ports.iter().map(|p| *p as u16).collect();
+            if cond_and.is_empty() && sni.is_none() && udp_ports.is_empty() {
                 warn!("empty rule at target {}", rr.target_tag);
                 continue;
             }
-            rules.push(Rule::new(rr.target_tag.clone(), Box::new(cond_and)));
+            rules.push(Rule::new(
+                rr.target_tag.clone(),
+                Box::new(cond_and),
+                sni,
+                udp_ports,
+            ));
         }
-        Router { rules }
+        rules
+    }
+
+    /// Whether any rule needs the SNI of the connection, the dispatcher
+    /// sniffs it only then for destinations already being domains.
+    pub fn has_sni_rules(&self) -> bool {
+        self.rules().iter().any(|r| r.sni.is_some())
+    }
+
+    /// Picks the target of the first matching rule, `sni` is the server name
+    /// sniffed from the TLS ClientHello, if any. Rules with SNI conditions
+    /// never match without it.
+    pub fn pick_route(&self, sess: &Session, sni: Option<&str>) -> Result<String> {
+        self.pick(sess, sni, false)
+    }
+
+    /// Picks the target of the first rule matching the UDP session `sess`,
+    /// rules with UDP ports match UDP sessions only, e.g. to send QUIC to
+    /// UDP 443 to a drop outbound so that clients fall back to TCP.
+    pub fn pick_udp_route(&self, sess: &Session) -> Result<String> {
+        self.pick(sess, None, true)
     }
 
-    pub fn pick_route(&self, sess: &Session) -> Result<&String> {
-        for rule in &self.rules {
-            if rule.apply(sess) {
-                return Ok(&rule.target);
+    fn pick(&self, sess: &Session, sni: Option<&str>, udp: bool) -> Result<String> {
+        let sni_sess = sni.map(|sni| Session {
+            source: sess.source,
+            destination: SocksAddr::Domain(sni.to_owned(), sess.destination.port()),
+        });
+        for rule in self.rules().iter() {
+            if rule.matches(sess, sni_sess.as_ref(), udp) {
+                return Ok(rule.target.clone());
             }
         }
         Err(anyhow!("no matching rules"))
@@ -0,0 +1,326 @@
+use std::future::Future;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use mio::{unix::EventedFd, Evented, PollOpt, Ready, Token};
+use tokio::io::PollEvented;
+use tokio::time::{delay_for, Delay, Instant};
+
+// Maximum number of bytes moved by a single splice call, the default
+// capacity of a pipe.
+const SPLICE_SIZE: usize = 64 * 1024;
+
+fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+// An owned file descriptor which can be registered to the reactor.
+struct OwnedFd(RawFd);
+
+impl OwnedFd {
+    fn dup(fd: RawFd) -> io::Result<Self> {
+        let fd = cvt(unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) })?;
+        Ok(OwnedFd(fd))
+    }
+}
+
+impl Drop for OwnedFd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+impl Evented for OwnedFd {
+    fn register(
+        &self,
+        poll: &mio::Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.0).register(poll, token, interest, opts)
+    }
+
+    fn reregister(
+        &self,
+        poll: &mio::Poll,
+        token: Token,
+        interest: Ready,
+        opts: PollOpt,
+    ) -> io::Result<()> {
+        EventedFd(&self.0).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &mio::Poll) -> io::Result<()> {
+        EventedFd(&self.0).deregister(poll)
+    }
+}
+
+struct Pipe {
+    r: OwnedFd,
+    w: OwnedFd,
+}
+
+impl Pipe {
+    fn new() -> io::Result<Self> {
+        let mut fds = [0 as libc::c_int; 2];
+        cvt(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) })?;
+        Ok(Pipe {
+            r: OwnedFd(fds[0]),
+            w: OwnedFd(fds[1]),
+        })
+    }
+}
+
+fn splice(from: RawFd, to: RawFd, len: usize) -> io::Result<usize> {
+    let n = unsafe {
+        libc::splice(
+            from,
+            std::ptr::null_mut(),
+            to,
+            std::ptr::null_mut(),
+            len,
+            libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK,
+        )
+    };
+    if n < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Copies data from one TCP socket to another with splice(2) through a pipe,
+/// the data never goes through userspace.
+///
+/// The sockets are duplicated, so the streams they come from can be dropped.
+/// The write side of the destination is shut down once the source reaches
+/// EOF. Resolves to the number of bytes copied.
+pub struct Splice {
+    src: PollEvented<OwnedFd>,
+    dst: PollEvented<OwnedFd>,
+    pipe: Pipe,
+    in_pipe: usize,
+    read_done: bool,
+    amt: u64,
+    idle: Option<(IdleClock, Delay)>,
+}
+
+/// Time of the last data moved by any of the splices sharing it, the
+/// directions of a relay share one so that either keeps both alive.
+#[derive(Clone)]
+pub struct IdleClock {
+    timeout: Duration,
+    last_activity: Arc<Mutex<Instant>>,
+}
+
+impl IdleClock {
+    pub fn new(timeout: Duration) -> Self {
+        IdleClock {
+            timeout,
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    fn deadline(&self) -> Instant {
+        *self.last_activity.lock().unwrap() + self.timeout
+    }
+}
+
+impl Splice {
+    pub fn new(src: RawFd, dst: RawFd) -> io::Result<Self> {
+        Ok(Splice {
+            src: PollEvented::new(OwnedFd::dup(src)?)?,
+            dst: PollEvented::new(OwnedFd::dup(dst)?)?,
+            pipe: Pipe::new()?,
+            in_pipe: 0,
+            read_done: false,
+            amt: 0,
+            idle: None,
+        })
+    }
+
+    /// Fails with `TimedOut` once no data was moved for the timeout of
+    /// `clock`, by this splice or any other sharing the clock.
+    pub fn idle_timeout(mut self, clock: IdleClock) -> Self {
+        let delay = delay_for(clock.timeout);
+        self.idle = Some((clock, delay));
+        self
+    }
+
+    fn on_activity(&mut self) {
+        if let Some((clock, _)) = &self.idle {
+            clock.touch();
+        }
+    }
+
+    fn poll_idle(&mut self, cx: &mut Context<'_>) -> io::Result<()> {
+        if let Some((clock, delay)) = &mut self.idle {
+            loop {
+                if Pin::new(&mut *delay).poll(cx).is_pending() {
+                    return Ok(());
+                }
+                // The other direction may have moved data meanwhile.
+                let deadline = clock.deadline();
+                if deadline <= Instant::now() {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "idle timeout"));
+                }
+                delay.reset(deadline);
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_splice(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        loop {
+            if self.in_pipe == 0 && !self.read_done {
+                ready!(self.src.poll_read_ready(cx, Ready::readable()))?;
+                match splice(self.src.get_ref().0, self.pipe.w.0, SPLICE_SIZE) {
+                    Ok(0) => self.read_done = true,
+                    Ok(n) => {
+                        self.in_pipe = n;
+                        self.on_activity();
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        self.src.clear_read_ready(cx, Ready::readable())?;
+                        continue;
+                    }
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+
+            while self.in_pipe > 0 {
+                ready!(self.dst.poll_write_ready(cx))?;
+                match splice(self.pipe.r.0, self.dst.get_ref().0, self.in_pipe) {
+                    Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    Ok(n) => {
+                        self.in_pipe -= n;
+                        self.amt += n as u64;
+                        self.on_activity();
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        self.dst.clear_write_ready(cx)?;
+                    }
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+
+            if self.read_done {
+                cvt(unsafe { libc::shutdown(self.dst.get_ref().0, libc::SHUT_WR) })?;
+                return Poll::Ready(Ok(self.amt));
+            }
+        }
+    }
+}
+
+impl Future for Splice {
+    type Output = io::Result<u64>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = &mut *self;
+        match me.poll_splice(cx) {
+            Poll::Pending => {
+                me.poll_idle(cx)?;
+                Poll::Pending
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::io::AsRawFd;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    async fn socket_pair() -> (TcpStream, TcpStream) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (a, b) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (a.unwrap(), b.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_splice() {
+        // client -> (a, b) -> relay -> (c, d) -> server
+        let (mut client, a) = socket_pair().await;
+        let (c, mut server) = socket_pair().await;
+        let relay = Splice::new(a.as_raw_fd(), c.as_raw_fd()).unwrap();
+        // The relay holds its own copies of the sockets.
+        drop(a);
+        drop(c);
+        let relay = tokio::spawn(relay);
+
+        let payload: Vec<u8> = (0..4 * 1024 * 1024).map(|i| i as u8).collect();
+        let expected = payload.clone();
+        let writer = tokio::spawn(async move {
+            client.write_all(&payload).await.unwrap();
+            client.shutdown(std::net::Shutdown::Write).unwrap();
+            client
+        });
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received.len(), expected.len());
+        assert!(received == expected);
+        assert_eq!(relay.await.unwrap().unwrap(), expected.len() as u64);
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_splice_eof() {
+        let pipe = Pipe::new().unwrap();
+        let (_client, a) = socket_pair().await;
+        let mut relay = Splice::new(pipe.r.0, a.as_raw_fd()).unwrap();
+        drop(pipe.w);
+        assert_eq!((&mut relay).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_splice_idle_timeout() {
+        let (mut client, a) = socket_pair().await;
+        let (c, mut server) = socket_pair().await;
+        let clock = IdleClock::new(Duration::from_millis(300));
+        let up = Splice::new(a.as_raw_fd(), c.as_raw_fd())
+            .unwrap()
+            .idle_timeout(clock.clone());
+        let down = Splice::new(c.as_raw_fd(), a.as_raw_fd())
+            .unwrap()
+            .idle_timeout(clock);
+        let start = Instant::now();
+        let up = tokio::spawn(up);
+        let down = tokio::spawn(down);
+
+        // Uploads keep the idle download alive.
+        let mut buf = [0u8; 4];
+        for _ in 0..4 {
+            client.write_all(b"ping").await.unwrap();
+            server.read_exact(&mut buf).await.unwrap();
+            tokio::time::delay_for(Duration::from_millis(150)).await;
+        }
+
+        let err = down.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(800));
+        let err = up.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,138 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use bytes::BytesMut;
+use lazy_static::lazy_static;
+
+// Large buffers are not kept around, they're rarely needed.
+const MAX_POOLED_CAPACITY: usize = 64 * 1024;
+const MAX_POOLED_BUFFERS: usize = 256;
+
+lazy_static! {
+    static ref POOL: BufferPool = BufferPool::new(MAX_POOLED_BUFFERS);
+}
+
+/// Takes a zero-filled buffer of `len` bytes from the shared pool.
+pub fn get(len: usize) -> PooledBuf<'static> {
+    POOL.get(len)
+}
+
+/// A pool of reusable byte buffers.
+///
+/// Buffers are cleared before going back to the pool and zero-filled when
+/// handed out, so data of one flow never shows up in another.
+pub struct BufferPool {
+    bufs: Mutex<Vec<BytesMut>>,
+    max_buffers: usize,
+}
+
+impl BufferPool {
+    pub fn new(max_buffers: usize) -> Self {
+        BufferPool {
+            bufs: Mutex::new(Vec::new()),
+            max_buffers,
+        }
+    }
+
+    /// Takes a zero-filled buffer of `len` bytes.
+    pub fn get(&self, len: usize) -> PooledBuf<'_> {
+        let mut buf = self
+            .bufs
+            .lock()
+            .unwrap()
+            .pop()
+            .unwrap_or_else(BytesMut::new);
+        buf.resize(len, 0);
+        PooledBuf {
+            buf: Some(buf),
+            pool: self,
+        }
+    }
+
+    /// Returns the number of idle buffers in the pool.
+    pub fn len(&self) -> usize {
+        self.bufs.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn put(&self, mut buf: BytesMut) {
+        if buf.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+        buf.clear();
+        let mut bufs = self.bufs.lock().unwrap();
+        if bufs.len() < self.max_buffers {
+            bufs.push(buf);
+        }
+    }
+}
+
+/// A buffer borrowed from a `BufferPool`, returned to the pool on drop.
+pub struct PooledBuf<'a> {
+    buf: Option<BytesMut>,
+    pool: &'a BufferPool,
+}
+
+impl Deref for PooledBuf<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.buf.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledBuf<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.buf.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledBuf<'_> {
+    fn drop(&mut self) {
+        if let Some(buf) = self.buf.take() {
+            self.pool.put(buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_reused() {
+        let pool = BufferPool::new(4);
+        let buf = pool.get(1024);
+        let ptr = buf.as_ptr();
+        drop(buf);
+        assert_eq!(pool.len(), 1);
+        let buf = pool.get(512);
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(buf.len(), 512);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_buffer_cleared() {
+        let pool = BufferPool::new(4);
+        let mut buf = pool.get(64);
+        buf.copy_from_slice(&[0xaa; 64]);
+        drop(buf);
+        let buf = pool.get(128);
+        assert!(buf.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_pool_bounded() {
+        let pool = BufferPool::new(2);
+        let bufs: Vec<_> = (0..4).map(|_| pool.get(16)).collect();
+        drop(bufs);
+        assert_eq!(pool.len(), 2);
+
+        drop(pool.get(MAX_POOLED_CAPACITY + 1));
+        assert_eq!(pool.len(), 1);
+    }
+}
@@ -40,6 +40,61 @@ pub trait NonceSequence: Sync + Send + Unpin {
     fn advance(&mut self) -> Result<Vec<u8>>;
 }
 
+/// Cipher method names of a protocol mapped to their implementation.
+///
+/// Each protocol keeps a registry of the methods it supports, so supporting a
+/// new cipher is a matter of registering it. Names are matched ignoring case.
+pub struct CipherRegistry<T> {
+    methods: Vec<(&'static str, T)>,
+}
+
+impl<T> Default for CipherRegistry<T> {
+    fn default() -> Self {
+        CipherRegistry {
+            methods: Vec::new(),
+        }
+    }
+}
+
+impl<T> CipherRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `method` under `name`, replacing any method of that name.
+    pub fn register(&mut self, name: &'static str, method: T) {
+        match self
+            .methods
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some(entry) => entry.1 = method,
+            None => self.methods.push((name, method)),
+        }
+    }
+
+    /// Returns the method registered under `name`, the error lists the
+    /// supported names.
+    pub fn get(&self, name: &str) -> Result<&T> {
+        self.methods
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, m)| m)
+            .ok_or_else(|| {
+                anyhow!(
+                    "unsupported cipher: {}, expected one of {}",
+                    name,
+                    self.names().join(", ")
+                )
+            })
+    }
+
+    /// Returns the registered names, in order of registration.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.methods.iter().map(|(n, _)| *n).collect()
+    }
+}
+
 #[cfg(feature = "openssl-aead")]
 pub mod aead {
     use openssl::symm;
@@ -348,6 +403,22 @@ pub mod aead {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cipher_registry() {
+        let mut registry = CipherRegistry::new();
+        registry.register("aes-128-gcm", 1);
+        registry.register("chacha20-poly1305", 2);
+        registry.register("AES-128-GCM", 3);
+        assert_eq!(*registry.get("aes-128-gcm").unwrap(), 3);
+        assert_eq!(*registry.get("ChaCha20-Poly1305").unwrap(), 2);
+        assert_eq!(registry.names(), vec!["aes-128-gcm", "chacha20-poly1305"]);
+        let err = registry.get("rc4-md5").unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "unsupported cipher: rc4-md5, expected one of aes-128-gcm, chacha20-poly1305"
+        );
+    }
+
     #[test]
     fn test_aead_enc_dec() {
         struct ShadowsocksNonceSequence(Vec<u8>);
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Addresses which recently failed to connect.
+///
+/// Dials try them after the other addresses of a host, so a dead IP among
+/// several A records doesn't cost a timeout on every connection. A failure
+/// is forgotten after `window`, or as soon as the address connects.
+pub struct DialFailures {
+    window: Duration,
+    failed: Mutex<HashMap<SocketAddr, Instant>>,
+}
+
+impl DialFailures {
+    pub fn new(window: Duration) -> Self {
+        DialFailures {
+            window,
+            failed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records the result of a dial to `addr`.
+    pub fn record(&self, addr: SocketAddr, ok: bool) {
+        let mut failed = self.failed.lock().unwrap();
+        if ok {
+            failed.remove(&addr);
+        } else {
+            failed.insert(addr, Instant::now());
+            // Drops the expired entries on the way.
+            let window = self.window;
+            failed.retain(|_, at| at.elapsed() < window);
+        }
+    }
+
+    /// Returns whether `addr` failed within the window.
+    pub fn is_failing(&self, addr: &SocketAddr) -> bool {
+        match self.failed.lock().unwrap().get(addr) {
+            Some(at) => at.elapsed() < self.window,
+            None => false,
+        }
+    }
+
+    /// Splits `addrs` into the ones to dial first and the recently failed
+    /// ones, keeping their order.
+    pub fn partition(&self, addrs: Vec<SocketAddr>) -> (Vec<SocketAddr>, Vec<SocketAddr>) {
+        addrs.into_iter().partition(|a| !self.is_failing(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dial_failures() {
+        let failures = DialFailures::new(Duration::from_millis(50));
+        let a: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let b: SocketAddr = "10.0.0.2:443".parse().unwrap();
+        let c: SocketAddr = "10.0.0.3:443".parse().unwrap();
+
+        failures.record(a, false);
+        failures.record(c, false);
+        failures.record(c, true);
+        assert!(failures.is_failing(&a));
+        assert!(!failures.is_failing(&c));
+        assert_eq!(failures.partition(vec![a, b, c]), (vec![b, c], vec![a]));
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!failures.is_failing(&a));
+        assert_eq!(failures.partition(vec![a, b, c]), (vec![a, b, c], vec![]));
+    }
+}
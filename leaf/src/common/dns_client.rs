@@ -1,10 +1,12 @@
+use std::cmp::max;
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use futures::future::select_ok;
+use futures::future::{join_all, select_ok, BoxFuture, FutureExt, Shared};
 use log::*;
 use lru::LruCache;
 use rand::{rngs::StdRng, Rng, SeedableRng};
@@ -20,10 +22,54 @@ use trust_dns_proto::{
 
 use crate::option;
 
+// Warmed domains are resolved again no sooner than this, whatever the TTL.
+const MIN_WARM_INTERVAL: Duration = Duration::from_secs(10);
+
+// Failing to warm a domain, it's retried after this.
+const WARM_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+// A lookup awaited by all the callers asking for the same domain meanwhile.
+type PendingLookup = Shared<BoxFuture<'static, Result<Vec<IpAddr>, String>>>;
+
+/// Matches the domain of a query.
+#[derive(Clone, Debug)]
+pub enum DomainRule {
+    /// The domain itself only.
+    Full(String),
+    /// The domain and its subdomains.
+    Suffix(String),
+    /// Any domain containing the keyword.
+    Keyword(String),
+}
+
+impl DomainRule {
+    fn matches(&self, domain: &str) -> bool {
+        match self {
+            DomainRule::Full(value) => domain == value,
+            DomainRule::Suffix(value) => {
+                domain == value
+                    || (domain.ends_with(value.as_str())
+                        && domain[..domain.len() - value.len()].ends_with('.'))
+            }
+            DomainRule::Keyword(value) => domain.contains(value.as_str()),
+        }
+    }
+}
+
+/// Resolves domains on the configured servers.
+///
+/// Answers are cached, and concurrent lookups of a domain share a single
+/// query, so a client shared by several components, e.g. the fake DNS and
+/// the outbounds, resolves each domain once.
+///
+/// Queries for a domain matching one of the rules go to the servers of the
+/// first such rule, the others to the default servers.
 pub struct DnsClient {
     bind_addr: SocketAddr,
     servers: Vec<SocketAddr>,
+    rules: Vec<(Vec<DomainRule>, Vec<SocketAddr>)>,
     cache: Arc<TokioMutex<LruCache<String, Vec<IpAddr>>>>,
+    pending: TokioMutex<HashMap<String, PendingLookup>>,
 }
 
 impl Default for DnsClient {
@@ -37,8 +83,10 @@ impl Default for DnsClient {
         )));
         DnsClient {
             servers: dns_servers,
+            rules: Vec::new(),
             bind_addr,
             cache,
+            pending: TokioMutex::new(HashMap::new()),
         }
     }
 }
@@ -50,17 +98,36 @@ impl DnsClient {
         )));
         DnsClient {
             servers,
+            rules: Vec::new(),
             bind_addr,
             cache,
+            pending: TokioMutex::new(HashMap::new()),
         }
     }
 
+    /// Sends queries for domains matching any of `domains` to `servers`.
+    ///
+    /// Rules are tried in the order they are added.
+    pub fn add_rule(&mut self, domains: Vec<DomainRule>, servers: Vec<SocketAddr>) {
+        self.rules.push((domains, servers));
+    }
+
+    fn servers_for(&self, domain: &str) -> &[SocketAddr] {
+        for (domains, servers) in &self.rules {
+            if domains.iter().any(|rule| rule.matches(domain)) {
+                trace!("{} matches a dns rule, querying {:?}", domain, servers);
+                return servers;
+            }
+        }
+        &self.servers
+    }
+
     async fn query_task(
         request: Box<[u8]>,
         domain: &str,
         server: &SocketAddr,
         bind_addr: &SocketAddr,
-    ) -> Result<Vec<IpAddr>> {
+    ) -> Result<(Vec<IpAddr>, u32)> {
         let mut socket = UdpSocket::bind(bind_addr).await?;
         let mut last_err = None;
         for _i in 0..4 {
@@ -90,10 +157,12 @@ impl DnsClient {
                                     break;
                                 }
                                 let mut addrs = Vec::new();
+                                let mut ttl = u32::MAX;
                                 for ans in resp.answers() {
                                     // TODO checks?
                                     if let RData::A(addr) = ans.rdata() {
                                         addrs.push(IpAddr::V4(addr.to_owned()));
+                                        ttl = ttl.min(ans.ttl());
                                     }
                                 }
                                 if !addrs.is_empty() {
@@ -106,7 +175,7 @@ impl DnsClient {
                                         elapsed.as_millis(),
                                     );
                                     trace!("ips for {}:\n{:#?}:", domain, &addrs);
-                                    return Ok(addrs);
+                                    return Ok((addrs, ttl));
                                 } else {
                                     // response with 0 records
                                     //
@@ -148,10 +217,79 @@ impl DnsClient {
             return Ok(vec![ip]);
         }
 
-        if let Some(ips) = self.cache.lock().await.get(&domain) {
-            return Ok(ips.to_vec());
+        let lookup = {
+            let mut pending = self.pending.lock().await;
+            if let Some(ips) = self.cache.lock().await.get(&domain) {
+                return Ok(ips.to_vec());
+            }
+            if let Some(lookup) = pending.get(&domain) {
+                trace!("joining pending lookup of {}", &domain);
+                lookup.clone()
+            } else {
+                let lookup = Self::query(
+                    self.servers_for(&domain).to_vec(),
+                    domain.clone(),
+                    *bind_addr,
+                )
+                .map(|res| res.map(|(ips, _)| ips).map_err(|e| e.to_string()))
+                .boxed()
+                .shared();
+                pending.insert(domain.clone(), lookup.clone());
+                lookup
+            }
+        };
+        let res = lookup.await;
+
+        // Cached before the lookup is forgotten, a lookup starting meanwhile
+        // either joins this one or hits the cache.
+        let mut pending = self.pending.lock().await;
+        if let Ok(ips) = &res {
+            self.cache.lock().await.put(domain.clone(), ips.clone());
         }
+        pending.remove(&domain);
+        res.map_err(|e| anyhow!(e))
+    }
+
+    /// Resolves `domains`, and again as their answers expire, so that lookups
+    /// of them are served from the cache. Runs forever.
+    pub async fn warm(self: Arc<Self>, domains: Vec<String>) {
+        let tasks = domains.into_iter().map(|domain| {
+            let client = self.clone();
+            async move {
+                loop {
+                    let interval = match client.refresh(&domain).await {
+                        Ok(ttl) => max(Duration::from_secs(ttl as u64), MIN_WARM_INTERVAL),
+                        Err(e) => {
+                            debug!("warming {} failed: {}", &domain, e);
+                            WARM_RETRY_INTERVAL
+                        }
+                    };
+                    tokio::time::delay_for(interval).await;
+                }
+            }
+        });
+        join_all(tasks).await;
+    }
 
+    // Resolves `domain` bypassing the cache, caches the answer and returns
+    // its TTL.
+    async fn refresh(&self, domain: &str) -> Result<u32> {
+        let (ips, ttl) = Self::query(
+            self.servers_for(domain).to_vec(),
+            domain.to_string(),
+            self.bind_addr,
+        )
+        .await?;
+        trace!("warmed {} with ttl {}s", domain, ttl);
+        self.cache.lock().await.put(domain.to_string(), ips);
+        Ok(ttl)
+    }
+
+    async fn query(
+        servers: Vec<SocketAddr>,
+        domain: String,
+        bind_addr: SocketAddr,
+    ) -> Result<(Vec<IpAddr>, u32)> {
         let mut msg = Message::new();
 
         let mut fqdn = domain.clone();
@@ -177,21 +315,131 @@ impl DnsClient {
         };
 
         let mut tasks = Vec::new();
-        for server in &self.servers {
+        for server in &servers {
             let t = Self::query_task(
                 msg_buf.clone().into_boxed_slice(),
                 &domain,
                 &server,
-                bind_addr,
+                &bind_addr,
             );
             tasks.push(Box::pin(t));
         }
         match select_ok(tasks.into_iter()).await {
-            Ok(v) => {
-                self.cache.lock().await.put(domain.to_owned(), v.0.clone());
-                Ok(v.0)
-            }
+            Ok(v) => Ok(v.0),
             Err(e) => Err(anyhow!("all dns servers failed, last error: {}", e)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use trust_dns_proto::rr::{dns_class::DNSClass, resource::Record};
+
+    use super::*;
+
+    // Starts a DNS server answering every A query with `ip`, also returns
+    // the number of queries received.
+    async fn upstream(ip: Ipv4Addr) -> (SocketAddr, Arc<AtomicUsize>) {
+        let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let queries = Arc::new(AtomicUsize::new(0));
+        let queries2 = queries.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (n, src) = socket.recv_from(&mut buf).await.unwrap();
+                queries2.fetch_add(1, Ordering::SeqCst);
+                let req = Message::from_vec(&buf[..n]).unwrap();
+                let query = req.queries()[0].clone();
+                let mut ans = Record::new();
+                ans.set_name(query.name().clone())
+                    .set_rr_type(RecordType::A)
+                    .set_ttl(1)
+                    .set_dns_class(DNSClass::IN)
+                    .set_rdata(RData::A(ip));
+                let mut resp = Message::new();
+                resp.set_id(req.id())
+                    .set_message_type(MessageType::Response)
+                    .set_op_code(OpCode::Query)
+                    .set_response_code(ResponseCode::NoError);
+                resp.add_query(query);
+                resp.add_answer(ans);
+                socket.send_to(&resp.to_vec().unwrap(), &src).await.unwrap();
+            }
+        });
+        (addr, queries)
+    }
+
+    #[test]
+    fn test_domain_rule() {
+        let suffix = DomainRule::Suffix("example.com".to_string());
+        assert!(suffix.matches("example.com"));
+        assert!(suffix.matches("www.example.com"));
+        assert!(!suffix.matches("myexample.com"));
+        let full = DomainRule::Full("example.com".to_string());
+        assert!(full.matches("example.com"));
+        assert!(!full.matches("www.example.com"));
+        let keyword = DomainRule::Keyword("example".to_string());
+        assert!(keyword.matches("myexample.org"));
+        assert!(!keyword.matches("exampel.org"));
+    }
+
+    #[tokio::test]
+    async fn test_rules() {
+        let (default, default_queries) = upstream(Ipv4Addr::new(1, 1, 1, 1)).await;
+        let (internal, internal_queries) = upstream(Ipv4Addr::new(10, 0, 0, 1)).await;
+        let (other, other_queries) = upstream(Ipv4Addr::new(10, 0, 0, 2)).await;
+        let mut client = DnsClient::new(vec![default], "127.0.0.1:0".parse().unwrap());
+        client.add_rule(
+            vec![DomainRule::Suffix("corp.example".to_string())],
+            vec![internal],
+        );
+        client.add_rule(
+            vec![
+                DomainRule::Full("intranet".to_string()),
+                DomainRule::Keyword("corp".to_string()),
+            ],
+            vec![other],
+        );
+
+        let ips = client.lookup("git.corp.example".to_string()).await.unwrap();
+        assert_eq!(ips, vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))]);
+        let ips = client.lookup("corp.example.org".to_string()).await.unwrap();
+        assert_eq!(ips, vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))]);
+        let ips = client.lookup("www.example.org".to_string()).await.unwrap();
+        assert_eq!(ips, vec![IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1))]);
+
+        assert_eq!(internal_queries.load(Ordering::SeqCst), 1);
+        assert_eq!(other_queries.load(Ordering::SeqCst), 1);
+        assert_eq!(default_queries.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm() {
+        let (upstream, queries) = upstream(Ipv4Addr::new(1, 1, 1, 1)).await;
+        let client = Arc::new(DnsClient::new(
+            vec![upstream],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        tokio::spawn(client.clone().warm(vec!["www.example.com".to_string()]));
+        for _ in 0..100 {
+            if client
+                .cache
+                .lock()
+                .await
+                .contains(&"www.example.com".to_string())
+            {
+                break;
+            }
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+
+        let ips = client.lookup("www.example.com".to_string()).await.unwrap();
+        assert_eq!(ips, vec![IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1))]);
+        // Served from the cache.
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+    }
+}
@@ -99,22 +99,7 @@ impl FakeDns {
         let ip = self.allocate_ip(&domain);
         debug!("allocate {} for {}", &ip, &domain);
 
-        let mut resp = Message::new();
-
-        // sets the response according to request
-        // https://github.com/miekg/dns/blob/f515aa579d28efa1af67d9a62cc57f2dfe59da76/defaults.go#L15
-        resp.set_id(req.id())
-            .set_message_type(MessageType::Response)
-            .set_op_code(req.op_code());
-
-        if resp.op_code() == OpCode::Query {
-            resp.set_recursion_desired(req.recursion_desired())
-                .set_checking_disabled(req.checking_disabled());
-        }
-        resp.set_response_code(ResponseCode::NoError);
-        if !req.queries().is_empty() {
-            resp.add_query(query.clone());
-        }
+        let mut resp = response_to(&req, ResponseCode::NoError);
 
         if query.query_type() == RecordType::A {
             let mut ans = Record::new();
@@ -147,6 +132,33 @@ impl FakeDns {
     }
 }
 
+/// Generates a SERVFAIL response to `request`, for queries which can't be
+/// answered at all, so that the client fails fast and retries rather than
+/// waiting for its timeout.
+pub fn generate_servfail_response(request: &[u8]) -> Result<Vec<u8>> {
+    let req = Message::from_vec(request)?;
+    Ok(response_to(&req, ResponseCode::ServFail).to_vec()?)
+}
+
+// Sets the response according to request
+// https://github.com/miekg/dns/blob/f515aa579d28efa1af67d9a62cc57f2dfe59da76/defaults.go#L15
+fn response_to(req: &Message, code: ResponseCode) -> Message {
+    let mut resp = Message::new();
+    resp.set_id(req.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(req.op_code());
+
+    if resp.op_code() == OpCode::Query {
+        resp.set_recursion_desired(req.recursion_desired())
+            .set_checking_disabled(req.checking_disabled());
+    }
+    resp.set_response_code(code);
+    if let Some(query) = req.queries().first() {
+        resp.add_query(query.clone());
+    }
+    resp
+}
+
 impl Default for FakeDns {
     fn default() -> Self {
         Self::new()
@@ -157,6 +169,10 @@ impl Default for FakeDns {
 mod tests {
     use super::*;
     use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use trust_dns_proto::op::query::Query;
+    use trust_dns_proto::rr::Name;
 
     #[test]
     fn test_u32_to_ip() {
@@ -165,6 +181,29 @@ mod tests {
         assert_eq!(ip1, ip2);
     }
 
+    #[test]
+    fn test_servfail_response() {
+        let mut req = Message::new();
+        req.set_id(42)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true);
+        req.add_query(Query::query(
+            Name::from_str("example.com.").unwrap(),
+            RecordType::A,
+        ));
+        let resp = generate_servfail_response(&req.to_vec().unwrap()).unwrap();
+        let resp = Message::from_vec(&resp).unwrap();
+        assert_eq!(resp.id(), 42);
+        assert_eq!(resp.message_type(), MessageType::Response);
+        assert_eq!(resp.response_code(), ResponseCode::ServFail);
+        assert!(resp.recursion_desired());
+        assert_eq!(resp.queries(), req.queries());
+        assert!(resp.answers().is_empty());
+
+        assert!(generate_servfail_response(b"garbage").is_err());
+    }
+
     #[test]
     fn test_ip_to_u32() {
         let ip = Ipv4Addr::new(127, 0, 0, 1);
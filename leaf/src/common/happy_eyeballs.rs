@@ -0,0 +1,191 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// The Connection Attempt Delay recommended by RFC 8305.
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Family {
+    V4,
+    V6,
+}
+
+impl Family {
+    fn of(addr: &SocketAddr) -> Self {
+        if addr.is_ipv4() {
+            Family::V4
+        } else {
+            Family::V6
+        }
+    }
+}
+
+/// Races the addresses of a host by family as in RFC 8305, the addresses of
+/// `first_family` are dialed first, the others `delay` later unless the
+/// first ones connected or failed by then.
+#[derive(Clone, Copy, Debug)]
+pub struct HappyEyeballs {
+    pub first_family: Family,
+    pub delay: Duration,
+}
+
+impl Default for HappyEyeballs {
+    fn default() -> Self {
+        HappyEyeballs {
+            first_family: Family::V6,
+            delay: DEFAULT_DELAY,
+        }
+    }
+}
+
+impl HappyEyeballs {
+    /// Splits `addrs` into the addresses of the first family and the others,
+    /// keeping their order.
+    pub fn partition(&self, addrs: Vec<SocketAddr>) -> (Vec<SocketAddr>, Vec<SocketAddr>) {
+        addrs
+            .into_iter()
+            .partition(|a| Family::of(a) == self.first_family)
+    }
+
+    /// Connects to `addrs` with `dial`, which is given the addresses of a
+    /// family at a time. Returns the first connection made, or the error of
+    /// the last family to fail.
+    pub async fn race<T, F, Fut>(&self, addrs: Vec<SocketAddr>, dial: F) -> io::Result<T>
+    where
+        F: Fn(Vec<SocketAddr>) -> Fut,
+        Fut: Future<Output = io::Result<T>>,
+    {
+        let (first, second) = self.partition(addrs);
+        if first.is_empty() {
+            return dial(second).await;
+        }
+        if second.is_empty() {
+            return dial(first).await;
+        }
+
+        let first = dial(first);
+        tokio::pin!(first);
+        let head_start = tokio::time::delay_for(self.delay);
+        tokio::pin!(head_start);
+        let first_failed = tokio::select! {
+            res = &mut first => match res {
+                Ok(conn) => return Ok(conn),
+                Err(_) => true,
+            },
+            _ = &mut head_start => false,
+        };
+
+        let second = dial(second);
+        if first_failed {
+            return second.await;
+        }
+        tokio::pin!(second);
+        tokio::select! {
+            res = &mut first => match res {
+                Ok(conn) => Ok(conn),
+                Err(_) => second.await,
+            },
+            res = &mut second => match res {
+                Ok(conn) => Ok(conn),
+                Err(_) => first.await,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::time::Instant;
+
+    use super::*;
+
+    const V4: &str = "127.0.0.1:443";
+    const V6: &str = "[::1]:443";
+
+    // Dials by connecting after `latency` to the addresses of `reachable`,
+    // never to the others. Returns the address connected to, records the
+    // addresses dialed.
+    fn dialer(
+        reachable: Family,
+        latency: Duration,
+        dialed: Arc<Mutex<Vec<SocketAddr>>>,
+    ) -> impl Fn(Vec<SocketAddr>) -> futures::future::BoxFuture<'static, io::Result<SocketAddr>>
+    {
+        move |addrs| {
+            dialed.lock().unwrap().extend_from_slice(&addrs);
+            Box::pin(async move {
+                if Family::of(&addrs[0]) == reachable {
+                    tokio::time::delay_for(latency).await;
+                    Ok(addrs[0])
+                } else {
+                    futures::future::pending().await
+                }
+            })
+        }
+    }
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    fn addrs() -> Vec<SocketAddr> {
+        vec![addr(V4), addr(V6)]
+    }
+
+    #[tokio::test]
+    async fn test_first_family_connects_first() {
+        for &(family, expected) in &[(Family::V6, V6), (Family::V4, V4)] {
+            let he = HappyEyeballs {
+                first_family: family,
+                ..Default::default()
+            };
+            let dialed = Arc::new(Mutex::new(Vec::new()));
+            // Both reachable, within the head start.
+            let dial = dialer(family, Duration::from_millis(50), dialed.clone());
+            let reachable = he.race(addrs(), dial).await.unwrap();
+            assert_eq!(reachable, addr(expected));
+            // The other family was never tried.
+            assert_eq!(*dialed.lock().unwrap(), vec![addr(expected)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delay_honored() {
+        let he = HappyEyeballs {
+            first_family: Family::V6,
+            delay: Duration::from_millis(300),
+        };
+        let dialed = Arc::new(Mutex::new(Vec::new()));
+        // IPv6 is broken, hangs.
+        let dial = dialer(Family::V4, Duration::from_millis(0), dialed.clone());
+        let start = Instant::now();
+        let reachable = he.race(addrs(), dial).await.unwrap();
+        assert_eq!(reachable, addr(V4));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+        assert_eq!(*dialed.lock().unwrap(), vec![addr(V6), addr(V4)]);
+    }
+
+    #[tokio::test]
+    async fn test_first_family_failure_skips_delay() {
+        let he = HappyEyeballs {
+            first_family: Family::V6,
+            delay: Duration::from_secs(10),
+        };
+        let dial = |addrs: Vec<SocketAddr>| async move {
+            if addrs[0].is_ipv6() {
+                Err(io::Error::new(io::ErrorKind::Other, "unreachable"))
+            } else {
+                Ok(addrs[0])
+            }
+        };
+        let start = Instant::now();
+        assert_eq!(he.race(addrs(), dial).await.unwrap(), addr(V4));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,31 @@
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+/// Outbounds known to be failing, by tag, as published by TCP health checks.
+///
+/// Shared among handlers so that ensembles can avoid actors found failing by
+/// another handler. Outbounds never checked are considered healthy.
+#[derive(Default)]
+pub struct HealthMap {
+    failing: RwLock<HashSet<String>>,
+}
+
+impl HealthMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the result of the last check of `tag`.
+    pub fn set_healthy(&self, tag: &str, healthy: bool) {
+        let mut failing = self.failing.write().unwrap();
+        if healthy {
+            failing.remove(tag);
+        } else {
+            failing.insert(tag.to_string());
+        }
+    }
+
+    pub fn is_healthy(&self, tag: &str) -> bool {
+        !self.failing.read().unwrap().contains(tag)
+    }
+}
@@ -0,0 +1,128 @@
+//! ASCII encoding of internationalized domain names.
+//!
+//! Only the punycode part of IDNA is done here: labels with non-ASCII
+//! characters are lowercased and encoded into `xn--` labels, ASCII labels
+//! are left as they are.
+
+use std::borrow::Cow;
+use std::io;
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+fn overflow() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "punycode overflow")
+}
+
+fn adapt(mut delta: u32, num_points: u32, first: bool) -> u32 {
+    delta /= if first { DAMP } else { 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn digit(d: u32) -> char {
+    match d {
+        0..=25 => (b'a' + d as u8) as char,
+        _ => (b'0' + (d - 26) as u8) as char,
+    }
+}
+
+/// Encodes `input` with punycode as in RFC 3492, without the `xn--` prefix.
+pub fn punycode(input: &str) -> io::Result<String> {
+    let input: Vec<u32> = input.chars().map(|c| c as u32).collect();
+    let mut output: String = input
+        .iter()
+        .filter(|&&c| c < INITIAL_N)
+        .map(|&c| c as u8 as char)
+        .collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut handled = basic;
+    while (handled as usize) < input.len() {
+        let m = input.iter().copied().filter(|&c| c >= n).min().unwrap();
+        delta = (m - n)
+            .checked_mul(handled + 1)
+            .and_then(|d| delta.checked_add(d))
+            .ok_or_else(overflow)?;
+        n = m;
+        for &c in &input {
+            if c < n {
+                delta = delta.checked_add(1).ok_or_else(overflow)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        T_MIN
+                    } else if k >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Ok(output)
+}
+
+/// Returns the ASCII form of `domain`, borrowed if it's ASCII already.
+pub fn to_ascii(domain: &str) -> io::Result<Cow<'_, str>> {
+    if domain.is_ascii() {
+        return Ok(Cow::Borrowed(domain));
+    }
+    let mut labels = Vec::new();
+    for label in domain.split('.') {
+        if label.is_ascii() {
+            labels.push(label.to_string());
+        } else {
+            labels.push(format!("xn--{}", punycode(&label.to_lowercase())?));
+        }
+    }
+    Ok(Cow::Owned(labels.join(".")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!(punycode("bücher").unwrap(), "bcher-kva");
+        assert_eq!(punycode("ü").unwrap(), "tda");
+        assert_eq!(punycode("例え").unwrap(), "r8jz45g");
+        assert_eq!(to_ascii("example.com").unwrap(), "example.com");
+        assert!(matches!(to_ascii("example.com").unwrap(), Cow::Borrowed(_)));
+        assert_eq!(to_ascii("Bücher.example").unwrap(), "xn--bcher-kva.example");
+        assert_eq!(to_ascii("münchen.de").unwrap(), "xn--mnchen-3ya.de");
+    }
+}
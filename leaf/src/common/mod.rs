@@ -1,9 +1,14 @@
+pub mod buf_pool;
 pub mod crypto;
+pub mod dial_failures;
 pub mod dns_client;
+pub mod happy_eyeballs;
+pub mod health;
+pub mod idna;
 pub mod log;
 pub mod mutex;
 pub mod resolver;
-pub mod tls;
+pub mod shutdown;
 
 #[cfg(any(target_os = "ios", target_os = "macos", target_os = "linux"))]
 pub mod fake_dns;
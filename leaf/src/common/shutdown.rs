@@ -0,0 +1,135 @@
+use std::future::Future;
+
+use futures::future::{self, Either};
+use tokio::sync::watch;
+
+/// The sending side of a shutdown signal, owned by whoever controls the
+/// lifetime of the proxy.
+pub struct Shutdown {
+    tx: watch::Sender<bool>,
+    rx: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (tx, rx) = watch::channel(false);
+        Shutdown { tx, rx }
+    }
+
+    /// Returns a token to hand to components running background tasks.
+    pub fn token(&self) -> ShutdownToken {
+        ShutdownToken {
+            rx: self.rx.clone(),
+        }
+    }
+
+    /// Signals all tokens, tasks watching them stop on their next poll.
+    pub fn signal(&self) {
+        let _ = self.tx.broadcast(true);
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The receiving side of a shutdown signal.
+///
+/// Dropping the `Shutdown` it comes from without signaling doesn't count as a
+/// signal.
+#[derive(Clone)]
+pub struct ShutdownToken {
+    rx: watch::Receiver<bool>,
+}
+
+impl ShutdownToken {
+    /// Returns a token which is never signaled.
+    pub fn never() -> Self {
+        let (_, rx) = watch::channel(false);
+        ShutdownToken { rx }
+    }
+
+    pub fn is_signaled(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolves once the shutdown is signaled.
+    pub async fn signaled(&mut self) {
+        loop {
+            if self.is_signaled() {
+                return;
+            }
+            match self.rx.recv().await {
+                Some(true) => return,
+                Some(false) => (),
+                None => future::pending::<()>().await,
+            }
+        }
+    }
+
+    /// Runs `task` until it completes or the shutdown is signaled, whichever
+    /// comes first. Returns `None` if the task was cut short.
+    pub async fn run_until<F>(mut self, task: F) -> Option<F::Output>
+    where
+        F: Future,
+    {
+        if self.is_signaled() {
+            return None;
+        }
+        let signaled = Box::pin(async move { self.signaled().await });
+        match future::select(Box::pin(task), signaled).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+}
+
+impl Default for ShutdownToken {
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::{delay_for, timeout};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_stops_task() {
+        let shutdown = Shutdown::new();
+        let token = shutdown.token();
+        assert!(!token.is_signaled());
+        let task = tokio::spawn(token.clone().run_until(future::pending::<()>()));
+        delay_for(Duration::from_millis(50)).await;
+        shutdown.signal();
+        assert!(token.is_signaled());
+        let res = timeout(Duration::from_secs(1), task)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(res.is_none());
+
+        // Tasks started after the signal don't run at all.
+        assert!(token.run_until(async { 1 }).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_task_completes() {
+        let shutdown = Shutdown::new();
+        assert_eq!(shutdown.token().run_until(async { 1 }).await, Some(1));
+
+        let token = ShutdownToken::never();
+        let res = timeout(
+            Duration::from_millis(50),
+            token.run_until(future::pending::<()>()),
+        )
+        .await;
+        assert!(res.is_err());
+    }
+}
@@ -55,6 +55,7 @@ pub struct Proxy {
     pub ws: Option<bool>,
     pub tls: Option<bool>,
     pub ws_path: Option<String>,
+    pub ws_host: Option<String>,
 
     // trojan
     pub sni: Option<String>,
@@ -74,6 +75,7 @@ impl Default for Proxy {
             ws: Some(false),
             tls: Some(false),
             ws_path: None,
+            ws_host: None,
             sni: None,
         }
     }
@@ -89,6 +91,8 @@ pub struct ProxyGroup {
     pub check_interval: Option<i32>,
     pub fail_timeout: Option<i32>,
     pub failover: Option<bool>,
+    pub url: Option<String>,
+    pub tolerance: Option<i32>,
 
     // tryall
     pub delay_base: Option<i32>,
@@ -104,6 +108,8 @@ impl Default for ProxyGroup {
             check_interval: Some(300),
             fail_timeout: Some(4),
             failover: Some(true),
+            url: None,
+            tolerance: None,
             delay_base: Some(0),
         }
     }
@@ -312,6 +318,9 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                 "ws-path" => {
                     proxy.ws_path = Some(v.to_string());
                 }
+                "ws-host" => {
+                    proxy.ws_host = Some(v.to_string());
+                }
                 "sni" => {
                     proxy.sni = Some(v.to_string());
                 }
@@ -443,6 +452,17 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
                     "failover" => {
                         group.failover = if v == "true" { Some(true) } else { Some(false) };
                     }
+                    "url" => {
+                        group.url = Some(v.to_string());
+                    }
+                    "tolerance" => {
+                        let i = if let Ok(i) = v.parse::<i32>() {
+                            Some(i)
+                        } else {
+                            None
+                        };
+                        group.tolerance = i;
+                    }
                     "delay-base" => {
                         let i = if let Ok(i) = v.parse::<i32>() {
                             Some(i)
@@ -458,14 +478,15 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
 
         // compat
         match group.protocol.as_str() {
-            // url-test group is just failover without failover
+            // url-test group is failover with the url-test policy
             "url-test" => {
                 group.protocol = "failover".to_string();
                 group.failover = Some(false);
             }
-            // fallback group is just failover
+            // fallback group is just failover, checked the usual way
             "fallback" => {
                 group.protocol = "failover".to_string();
+                group.url = None;
             }
             _ => {}
         }
@@ -502,7 +523,8 @@ pub fn from_lines(lines: Vec<io::Result<String>>) -> Result<Config> {
         rule.target = params[2].to_string();
 
         match rule.type_field.as_str() {
-            "IP-CIDR" | "DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "GEOIP" | "EXTERNAL" => {
+            "IP-CIDR" | "DOMAIN" | "DOMAIN-SUFFIX" | "DOMAIN-KEYWORD" | "GEOIP" | "EXTERNAL"
+            | "UDP-PORT" | "DEST-PORT" => {
                 rule.filter = Some(params[1].to_string());
             }
             _ => {}
@@ -636,6 +658,24 @@ pub fn to_internal(conf: Config) -> Result<internal::Config> {
                     outbound.settings = settings;
                     outbounds.push(outbound);
                 }
+                "http" => {
+                    let mut settings = internal::HTTPOutboundSettings::new();
+                    if let Some(ext_address) = &ext_proxy.address {
+                        settings.address = ext_address.clone();
+                    }
+                    if let Some(ext_port) = &ext_proxy.port {
+                        settings.port = *ext_port as u32;
+                    }
+                    if let Some(ext_username) = &ext_proxy.username {
+                        settings.username = ext_username.clone();
+                    }
+                    if let Some(ext_password) = &ext_proxy.password {
+                        settings.password = ext_password.clone();
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
                 "shadowsocks" => {
                     let mut settings = internal::ShadowsocksOutboundSettings::new();
                     if let Some(ext_address) = &ext_proxy.address {
@@ -724,6 +764,9 @@ pub fn to_internal(conf: Config) -> Result<internal::Config> {
                     } else {
                         ws_settings.path = "/".to_string();
                     }
+                    if let Some(ext_ws_host) = &ext_proxy.ws_host {
+                        ws_settings.host = ext_ws_host.clone();
+                    }
                     let ws_settings = ws_settings.write_to_bytes().unwrap();
                     ws_outbound.settings = ws_settings;
                     ws_outbound.tag = format!("{}_ws_xxx", ext_proxy.tag.clone());
@@ -804,6 +847,9 @@ pub fn to_internal(conf: Config) -> Result<internal::Config> {
                     } else {
                         ws_settings.path = "/".to_string();
                     }
+                    if let Some(ext_ws_host) = &ext_proxy.ws_host {
+                        ws_settings.host = ext_ws_host.clone();
+                    }
                     let ws_settings = ws_settings.write_to_bytes().unwrap();
                     ws_outbound.settings = ws_settings;
                     ws_outbound.tag = format!("{}_ws_xxx", ext_proxy.tag.clone());
@@ -922,6 +968,12 @@ pub fn to_internal(conf: Config) -> Result<internal::Config> {
                     } else {
                         settings.failover = true;
                     }
+                    if let Some(ext_url) = &ext_proxy_group.url {
+                        settings.url_test = ext_url.to_string();
+                    }
+                    if let Some(ext_tolerance) = ext_proxy_group.tolerance {
+                        settings.switch_margin_ms = ext_tolerance as u32;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -982,6 +1034,10 @@ pub fn to_internal(conf: Config) -> Result<internal::Config> {
                     domain.value = ext_filter;
                     rule.domains.push(domain);
                 }
+                // Private and local IPs rather than a country.
+                "GEOIP" if ext_filter.eq_ignore_ascii_case("LAN") => {
+                    rule.private_ip = true;
+                }
                 "GEOIP" => {
                     let mut mmdb = internal::RoutingRule_Mmdb::new();
                     let mut file = std::env::current_exe().unwrap();
@@ -991,6 +1047,13 @@ pub fn to_internal(conf: Config) -> Result<internal::Config> {
                     mmdb.country_code = ext_filter;
                     rule.mmdbs.push(mmdb)
                 }
+                "UDP-PORT" => match ext_filter.parse::<u16>() {
+                    Ok(port) => rule.udp_ports.push(port as u32),
+                    Err(_) => continue,
+                },
+                "DEST-PORT" => {
+                    rule.port_ranges.push(ext_filter);
+                }
                 "EXTERNAL" => {
                     match external_rule::add_external_rule(
                         &mut rule,
@@ -28,6 +28,8 @@ pub struct DNS {
     // message fields
     pub servers: ::protobuf::RepeatedField<::std::string::String>,
     pub bind: ::std::string::String,
+    pub rules: ::protobuf::RepeatedField<DNS_Rule>,
+    pub warm_domains: ::protobuf::RepeatedField<::std::string::String>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -94,10 +96,65 @@ impl DNS {
     pub fn take_bind(&mut self) -> ::std::string::String {
         ::std::mem::replace(&mut self.bind, ::std::string::String::new())
     }
+
+    // repeated .DNS.Rule rules = 3;
+
+
+    pub fn get_rules(&self) -> &[DNS_Rule] {
+        &self.rules
+    }
+    pub fn clear_rules(&mut self) {
+        self.rules.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_rules(&mut self, v: ::protobuf::RepeatedField<DNS_Rule>) {
+        self.rules = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_rules(&mut self) -> &mut ::protobuf::RepeatedField<DNS_Rule> {
+        &mut self.rules
+    }
+
+    // Take field
+    pub fn take_rules(&mut self) -> ::protobuf::RepeatedField<DNS_Rule> {
+        ::std::mem::replace(&mut self.rules, ::protobuf::RepeatedField::new())
+    }
+
+    // repeated string warm_domains = 4;
+
+
+    pub fn get_warm_domains(&self) -> &[::std::string::String] {
+        &self.warm_domains
+    }
+    pub fn clear_warm_domains(&mut self) {
+        self.warm_domains.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_warm_domains(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.warm_domains = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_warm_domains(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.warm_domains
+    }
+
+    // Take field
+    pub fn take_warm_domains(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.warm_domains, ::protobuf::RepeatedField::new())
+    }
 }
 
 impl ::protobuf::Message for DNS {
     fn is_initialized(&self) -> bool {
+        for v in &self.rules {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -111,6 +168,12 @@ impl ::protobuf::Message for DNS {
                 2 => {
                     ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bind)?;
                 },
+                3 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.rules)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.warm_domains)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -129,6 +192,13 @@ impl ::protobuf::Message for DNS {
         if !self.bind.is_empty() {
             my_size += ::protobuf::rt::string_size(2, &self.bind);
         }
+        for value in &self.rules {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        for value in &self.warm_domains {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -141,6 +211,14 @@ impl ::protobuf::Message for DNS {
         if !self.bind.is_empty() {
             os.write_string(2, &self.bind)?;
         }
+        for v in &self.rules {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        for v in &self.warm_domains {
+            os.write_string(4, &v)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -189,6 +267,16 @@ impl ::protobuf::Message for DNS {
                 |m: &DNS| { &m.bind },
                 |m: &mut DNS| { &mut m.bind },
             ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<DNS_Rule>>(
+                "rules",
+                |m: &DNS| { &m.rules },
+                |m: &mut DNS| { &mut m.rules },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "warm_domains",
+                |m: &DNS| { &m.warm_domains },
+                |m: &mut DNS| { &mut m.warm_domains },
+            ));
             ::protobuf::reflect::MessageDescriptor::new_pb_name::<DNS>(
                 "DNS",
                 fields,
@@ -207,6 +295,8 @@ impl ::protobuf::Clear for DNS {
     fn clear(&mut self) {
         self.servers.clear();
         self.bind.clear();
+        self.rules.clear();
+        self.warm_domains.clear();
         self.unknown_fields.clear();
     }
 }
@@ -223,6 +313,213 @@ impl ::protobuf::reflect::ProtobufValue for DNS {
     }
 }
 
+#[derive(PartialEq,Clone,Default)]
+pub struct DNS_Rule {
+    // message fields
+    pub domains: ::protobuf::RepeatedField<RoutingRule_Domain>,
+    pub servers: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a DNS_Rule {
+    fn default() -> &'a DNS_Rule {
+        <DNS_Rule as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DNS_Rule {
+    pub fn new() -> DNS_Rule {
+        ::std::default::Default::default()
+    }
+
+    // repeated .RoutingRule.Domain domains = 1;
+
+
+    pub fn get_domains(&self) -> &[RoutingRule_Domain] {
+        &self.domains
+    }
+    pub fn clear_domains(&mut self) {
+        self.domains.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_domains(&mut self, v: ::protobuf::RepeatedField<RoutingRule_Domain>) {
+        self.domains = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_domains(&mut self) -> &mut ::protobuf::RepeatedField<RoutingRule_Domain> {
+        &mut self.domains
+    }
+
+    // Take field
+    pub fn take_domains(&mut self) -> ::protobuf::RepeatedField<RoutingRule_Domain> {
+        ::std::mem::replace(&mut self.domains, ::protobuf::RepeatedField::new())
+    }
+
+    // repeated string servers = 2;
+
+
+    pub fn get_servers(&self) -> &[::std::string::String] {
+        &self.servers
+    }
+    pub fn clear_servers(&mut self) {
+        self.servers.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_servers(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.servers = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_servers(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.servers
+    }
+
+    // Take field
+    pub fn take_servers(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.servers, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for DNS_Rule {
+    fn is_initialized(&self) -> bool {
+        for v in &self.domains {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.domains)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.servers)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.domains {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        for value in &self.servers {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.domains {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        for v in &self.servers {
+            os.write_string(2, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> DNS_Rule {
+        DNS_Rule::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<RoutingRule_Domain>>(
+                "domains",
+                |m: &DNS_Rule| { &m.domains },
+                |m: &mut DNS_Rule| { &mut m.domains },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "servers",
+                |m: &DNS_Rule| { &m.servers },
+                |m: &mut DNS_Rule| { &mut m.servers },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<DNS_Rule>(
+                "DNS.Rule",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static DNS_Rule {
+        static instance: ::protobuf::rt::LazyV2<DNS_Rule> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(DNS_Rule::new)
+    }
+}
+
+impl ::protobuf::Clear for DNS_Rule {
+    fn clear(&mut self) {
+        self.domains.clear();
+        self.servers.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for DNS_Rule {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DNS_Rule {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
 #[derive(PartialEq,Clone,Default)]
 pub struct Log {
     // message fields
@@ -554,193 +851,90 @@ impl ::protobuf::reflect::ProtobufValue for Log_Output {
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct TUNInboundSettings {
+pub struct UDP {
     // message fields
-    pub fd: i32,
-    pub name: ::std::string::String,
-    pub address: ::std::string::String,
-    pub gateway: ::std::string::String,
-    pub netmask: ::std::string::String,
-    pub mtu: i32,
-    pub fake_dns_exclude: ::protobuf::RepeatedField<::std::string::String>,
+    pub nat_type: UDP_NatType,
+    pub session_timeout: u32,
+    pub port_timeouts: ::protobuf::RepeatedField<UDP_PortTimeout>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TUNInboundSettings {
-    fn default() -> &'a TUNInboundSettings {
-        <TUNInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a UDP {
+    fn default() -> &'a UDP {
+        <UDP as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TUNInboundSettings {
-    pub fn new() -> TUNInboundSettings {
+impl UDP {
+    pub fn new() -> UDP {
         ::std::default::Default::default()
     }
 
-    // int32 fd = 1;
+    // .UDP.NatType nat_type = 1;
 
 
-    pub fn get_fd(&self) -> i32 {
-        self.fd
+    pub fn get_nat_type(&self) -> UDP_NatType {
+        self.nat_type
     }
-    pub fn clear_fd(&mut self) {
-        self.fd = 0;
+    pub fn clear_nat_type(&mut self) {
+        self.nat_type = UDP_NatType::CONE;
     }
 
     // Param is passed by value, moved
-    pub fn set_fd(&mut self, v: i32) {
-        self.fd = v;
+    pub fn set_nat_type(&mut self, v: UDP_NatType) {
+        self.nat_type = v;
     }
 
-    // string name = 2;
+    // uint32 session_timeout = 2;
 
 
-    pub fn get_name(&self) -> &str {
-        &self.name
+    pub fn get_session_timeout(&self) -> u32 {
+        self.session_timeout
     }
-    pub fn clear_name(&mut self) {
-        self.name.clear();
-    }
-
-    // Param is passed by value, moved
-    pub fn set_name(&mut self, v: ::std::string::String) {
-        self.name = v;
-    }
-
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_name(&mut self) -> &mut ::std::string::String {
-        &mut self.name
-    }
-
-    // Take field
-    pub fn take_name(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.name, ::std::string::String::new())
-    }
-
-    // string address = 3;
-
-
-    pub fn get_address(&self) -> &str {
-        &self.address
-    }
-    pub fn clear_address(&mut self) {
-        self.address.clear();
-    }
-
-    // Param is passed by value, moved
-    pub fn set_address(&mut self, v: ::std::string::String) {
-        self.address = v;
-    }
-
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_address(&mut self) -> &mut ::std::string::String {
-        &mut self.address
-    }
-
-    // Take field
-    pub fn take_address(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.address, ::std::string::String::new())
-    }
-
-    // string gateway = 4;
-
-
-    pub fn get_gateway(&self) -> &str {
-        &self.gateway
-    }
-    pub fn clear_gateway(&mut self) {
-        self.gateway.clear();
-    }
-
-    // Param is passed by value, moved
-    pub fn set_gateway(&mut self, v: ::std::string::String) {
-        self.gateway = v;
-    }
-
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_gateway(&mut self) -> &mut ::std::string::String {
-        &mut self.gateway
-    }
-
-    // Take field
-    pub fn take_gateway(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.gateway, ::std::string::String::new())
-    }
-
-    // string netmask = 5;
-
-
-    pub fn get_netmask(&self) -> &str {
-        &self.netmask
-    }
-    pub fn clear_netmask(&mut self) {
-        self.netmask.clear();
-    }
-
-    // Param is passed by value, moved
-    pub fn set_netmask(&mut self, v: ::std::string::String) {
-        self.netmask = v;
-    }
-
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_netmask(&mut self) -> &mut ::std::string::String {
-        &mut self.netmask
-    }
-
-    // Take field
-    pub fn take_netmask(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.netmask, ::std::string::String::new())
-    }
-
-    // int32 mtu = 6;
-
-
-    pub fn get_mtu(&self) -> i32 {
-        self.mtu
-    }
-    pub fn clear_mtu(&mut self) {
-        self.mtu = 0;
+    pub fn clear_session_timeout(&mut self) {
+        self.session_timeout = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_mtu(&mut self, v: i32) {
-        self.mtu = v;
+    pub fn set_session_timeout(&mut self, v: u32) {
+        self.session_timeout = v;
     }
 
-    // repeated string fake_dns_exclude = 7;
+    // repeated .UDP.PortTimeout port_timeouts = 3;
 
 
-    pub fn get_fake_dns_exclude(&self) -> &[::std::string::String] {
-        &self.fake_dns_exclude
+    pub fn get_port_timeouts(&self) -> &[UDP_PortTimeout] {
+        &self.port_timeouts
     }
-    pub fn clear_fake_dns_exclude(&mut self) {
-        self.fake_dns_exclude.clear();
+    pub fn clear_port_timeouts(&mut self) {
+        self.port_timeouts.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_fake_dns_exclude(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
-        self.fake_dns_exclude = v;
+    pub fn set_port_timeouts(&mut self, v: ::protobuf::RepeatedField<UDP_PortTimeout>) {
+        self.port_timeouts = v;
     }
 
     // Mutable pointer to the field.
-    pub fn mut_fake_dns_exclude(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
-        &mut self.fake_dns_exclude
+    pub fn mut_port_timeouts(&mut self) -> &mut ::protobuf::RepeatedField<UDP_PortTimeout> {
+        &mut self.port_timeouts
     }
 
     // Take field
-    pub fn take_fake_dns_exclude(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
-        ::std::mem::replace(&mut self.fake_dns_exclude, ::protobuf::RepeatedField::new())
+    pub fn take_port_timeouts(&mut self) -> ::protobuf::RepeatedField<UDP_PortTimeout> {
+        ::std::mem::replace(&mut self.port_timeouts, ::protobuf::RepeatedField::new())
     }
 }
 
-impl ::protobuf::Message for TUNInboundSettings {
+impl ::protobuf::Message for UDP {
     fn is_initialized(&self) -> bool {
+        for v in &self.port_timeouts {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -749,33 +943,17 @@ impl ::protobuf::Message for TUNInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_int32()?;
-                    self.fd = tmp;
+                    ::protobuf::rt::read_proto3_enum_with_unknown_fields_into(wire_type, is, &mut self.nat_type, 1, &mut self.unknown_fields)?
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
-                },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
-                },
-                4 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.gateway)?;
-                },
-                5 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.netmask)?;
-                },
-                6 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
-                    let tmp = is.read_int32()?;
-                    self.mtu = tmp;
+                    let tmp = is.read_uint32()?;
+                    self.session_timeout = tmp;
                 },
-                7 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.fake_dns_exclude)?;
+                3 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.port_timeouts)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -789,26 +967,15 @@ impl ::protobuf::Message for TUNInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if self.fd != 0 {
-            my_size += ::protobuf::rt::value_size(1, self.fd, ::protobuf::wire_format::WireTypeVarint);
-        }
-        if !self.name.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.name);
-        }
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.address);
-        }
-        if !self.gateway.is_empty() {
-            my_size += ::protobuf::rt::string_size(4, &self.gateway);
-        }
-        if !self.netmask.is_empty() {
-            my_size += ::protobuf::rt::string_size(5, &self.netmask);
+        if self.nat_type != UDP_NatType::CONE {
+            my_size += ::protobuf::rt::enum_size(1, self.nat_type);
         }
-        if self.mtu != 0 {
-            my_size += ::protobuf::rt::value_size(6, self.mtu, ::protobuf::wire_format::WireTypeVarint);
+        if self.session_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.session_timeout, ::protobuf::wire_format::WireTypeVarint);
         }
-        for value in &self.fake_dns_exclude {
-            my_size += ::protobuf::rt::string_size(7, &value);
+        for value in &self.port_timeouts {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -816,26 +983,16 @@ impl ::protobuf::Message for TUNInboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if self.fd != 0 {
-            os.write_int32(1, self.fd)?;
-        }
-        if !self.name.is_empty() {
-            os.write_string(2, &self.name)?;
-        }
-        if !self.address.is_empty() {
-            os.write_string(3, &self.address)?;
-        }
-        if !self.gateway.is_empty() {
-            os.write_string(4, &self.gateway)?;
-        }
-        if !self.netmask.is_empty() {
-            os.write_string(5, &self.netmask)?;
+        if self.nat_type != UDP_NatType::CONE {
+            os.write_enum(1, ::protobuf::ProtobufEnum::value(&self.nat_type))?;
         }
-        if self.mtu != 0 {
-            os.write_int32(6, self.mtu)?;
+        if self.session_timeout != 0 {
+            os.write_uint32(2, self.session_timeout)?;
         }
-        for v in &self.fake_dns_exclude {
-            os.write_string(7, &v)?;
+        for v in &self.port_timeouts {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
         };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -867,136 +1024,117 @@ impl ::protobuf::Message for TUNInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TUNInboundSettings {
-        TUNInboundSettings::new()
+    fn new() -> UDP {
+        UDP::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
-                "fd",
-                |m: &TUNInboundSettings| { &m.fd },
-                |m: &mut TUNInboundSettings| { &mut m.fd },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "name",
-                |m: &TUNInboundSettings| { &m.name },
-                |m: &mut TUNInboundSettings| { &mut m.name },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "address",
-                |m: &TUNInboundSettings| { &m.address },
-                |m: &mut TUNInboundSettings| { &mut m.address },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "gateway",
-                |m: &TUNInboundSettings| { &m.gateway },
-                |m: &mut TUNInboundSettings| { &mut m.gateway },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeEnum<UDP_NatType>>(
+                "nat_type",
+                |m: &UDP| { &m.nat_type },
+                |m: &mut UDP| { &mut m.nat_type },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "netmask",
-                |m: &TUNInboundSettings| { &m.netmask },
-                |m: &mut TUNInboundSettings| { &mut m.netmask },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
-                "mtu",
-                |m: &TUNInboundSettings| { &m.mtu },
-                |m: &mut TUNInboundSettings| { &mut m.mtu },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "session_timeout",
+                |m: &UDP| { &m.session_timeout },
+                |m: &mut UDP| { &mut m.session_timeout },
             ));
-            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "fake_dns_exclude",
-                |m: &TUNInboundSettings| { &m.fake_dns_exclude },
-                |m: &mut TUNInboundSettings| { &mut m.fake_dns_exclude },
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<UDP_PortTimeout>>(
+                "port_timeouts",
+                |m: &UDP| { &m.port_timeouts },
+                |m: &mut UDP| { &mut m.port_timeouts },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TUNInboundSettings>(
-                "TUNInboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<UDP>(
+                "UDP",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static TUNInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TUNInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TUNInboundSettings::new)
+    fn default_instance() -> &'static UDP {
+        static instance: ::protobuf::rt::LazyV2<UDP> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(UDP::new)
     }
 }
 
-impl ::protobuf::Clear for TUNInboundSettings {
+impl ::protobuf::Clear for UDP {
     fn clear(&mut self) {
-        self.fd = 0;
-        self.name.clear();
-        self.address.clear();
-        self.gateway.clear();
-        self.netmask.clear();
-        self.mtu = 0;
-        self.fake_dns_exclude.clear();
+        self.nat_type = UDP_NatType::CONE;
+        self.session_timeout = 0;
+        self.port_timeouts.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for TUNInboundSettings {
+impl ::std::fmt::Debug for UDP {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TUNInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for UDP {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct SocksInboundSettings {
+pub struct UDP_PortTimeout {
     // message fields
-    pub bind: ::std::string::String,
+    pub port: u32,
+    pub timeout: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a SocksInboundSettings {
-    fn default() -> &'a SocksInboundSettings {
-        <SocksInboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a UDP_PortTimeout {
+    fn default() -> &'a UDP_PortTimeout {
+        <UDP_PortTimeout as ::protobuf::Message>::default_instance()
     }
 }
 
-impl SocksInboundSettings {
-    pub fn new() -> SocksInboundSettings {
+impl UDP_PortTimeout {
+    pub fn new() -> UDP_PortTimeout {
         ::std::default::Default::default()
     }
 
-    // string bind = 1;
+    // uint32 port = 1;
 
 
-    pub fn get_bind(&self) -> &str {
-        &self.bind
+    pub fn get_port(&self) -> u32 {
+        self.port
     }
-    pub fn clear_bind(&mut self) {
-        self.bind.clear();
+    pub fn clear_port(&mut self) {
+        self.port = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_bind(&mut self, v: ::std::string::String) {
-        self.bind = v;
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
     }
 
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_bind(&mut self) -> &mut ::std::string::String {
-        &mut self.bind
+    // uint32 timeout = 2;
+
+
+    pub fn get_timeout(&self) -> u32 {
+        self.timeout
+    }
+    pub fn clear_timeout(&mut self) {
+        self.timeout = 0;
     }
 
-    // Take field
-    pub fn take_bind(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.bind, ::std::string::String::new())
+    // Param is passed by value, moved
+    pub fn set_timeout(&mut self, v: u32) {
+        self.timeout = v;
     }
 }
 
-impl ::protobuf::Message for SocksInboundSettings {
+impl ::protobuf::Message for UDP_PortTimeout {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1006,7 +1144,18 @@ impl ::protobuf::Message for SocksInboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bind)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.timeout = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1020,8 +1169,11 @@ impl ::protobuf::Message for SocksInboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.bind.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.bind);
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.timeout != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.timeout, ::protobuf::wire_format::WireTypeVarint);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1029,8 +1181,11 @@ impl ::protobuf::Message for SocksInboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.bind.is_empty() {
-            os.write_string(1, &self.bind)?;
+        if self.port != 0 {
+            os.write_uint32(1, self.port)?;
+        }
+        if self.timeout != 0 {
+            os.write_uint32(2, self.timeout)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1062,223 +1217,354 @@ impl ::protobuf::Message for SocksInboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> SocksInboundSettings {
-        SocksInboundSettings::new()
+    fn new() -> UDP_PortTimeout {
+        UDP_PortTimeout::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "bind",
-                |m: &SocksInboundSettings| { &m.bind },
-                |m: &mut SocksInboundSettings| { &mut m.bind },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &UDP_PortTimeout| { &m.port },
+                |m: &mut UDP_PortTimeout| { &mut m.port },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SocksInboundSettings>(
-                "SocksInboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "timeout",
+                |m: &UDP_PortTimeout| { &m.timeout },
+                |m: &mut UDP_PortTimeout| { &mut m.timeout },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<UDP_PortTimeout>(
+                "UDP.PortTimeout",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static SocksInboundSettings {
-        static instance: ::protobuf::rt::LazyV2<SocksInboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(SocksInboundSettings::new)
+    fn default_instance() -> &'static UDP_PortTimeout {
+        static instance: ::protobuf::rt::LazyV2<UDP_PortTimeout> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(UDP_PortTimeout::new)
     }
 }
 
-impl ::protobuf::Clear for SocksInboundSettings {
+impl ::protobuf::Clear for UDP_PortTimeout {
     fn clear(&mut self) {
-        self.bind.clear();
+        self.port = 0;
+        self.timeout = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for SocksInboundSettings {
+impl ::std::fmt::Debug for UDP_PortTimeout {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for SocksInboundSettings {
+impl ::protobuf::reflect::ProtobufValue for UDP_PortTimeout {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum UDP_NatType {
+    CONE = 0,
+    SYMMETRIC = 1,
+}
+
+impl ::protobuf::ProtobufEnum for UDP_NatType {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<UDP_NatType> {
+        match value {
+            0 => ::std::option::Option::Some(UDP_NatType::CONE),
+            1 => ::std::option::Option::Some(UDP_NatType::SYMMETRIC),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [UDP_NatType] = &[
+            UDP_NatType::CONE,
+            UDP_NatType::SYMMETRIC,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            ::protobuf::reflect::EnumDescriptor::new_pb_name::<UDP_NatType>("UDP.NatType", file_descriptor_proto())
+        })
+    }
+}
+
+impl ::std::marker::Copy for UDP_NatType {
+}
+
+impl ::std::default::Default for UDP_NatType {
+    fn default() -> Self {
+        UDP_NatType::CONE
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UDP_NatType {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
 #[derive(PartialEq,Clone,Default)]
-pub struct Inbound {
+pub struct TUNInboundSettings {
     // message fields
-    pub tag: ::std::string::String,
-    pub protocol: ::std::string::String,
-    pub listen: ::std::string::String,
-    pub port: u32,
-    pub settings: ::std::vec::Vec<u8>,
+    pub fd: i32,
+    pub name: ::std::string::String,
+    pub address: ::std::string::String,
+    pub gateway: ::std::string::String,
+    pub netmask: ::std::string::String,
+    pub mtu: i32,
+    pub fake_dns_exclude: ::protobuf::RepeatedField<::std::string::String>,
+    pub max_flows: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a Inbound {
-    fn default() -> &'a Inbound {
-        <Inbound as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a TUNInboundSettings {
+    fn default() -> &'a TUNInboundSettings {
+        <TUNInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl Inbound {
-    pub fn new() -> Inbound {
+impl TUNInboundSettings {
+    pub fn new() -> TUNInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string tag = 1;
+    // int32 fd = 1;
 
 
-    pub fn get_tag(&self) -> &str {
-        &self.tag
+    pub fn get_fd(&self) -> i32 {
+        self.fd
     }
-    pub fn clear_tag(&mut self) {
-        self.tag.clear();
+    pub fn clear_fd(&mut self) {
+        self.fd = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_tag(&mut self, v: ::std::string::String) {
-        self.tag = v;
+    pub fn set_fd(&mut self, v: i32) {
+        self.fd = v;
+    }
+
+    // string name = 2;
+
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn clear_name(&mut self) {
+        self.name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_name(&mut self, v: ::std::string::String) {
+        self.name = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_tag(&mut self) -> &mut ::std::string::String {
-        &mut self.tag
+    pub fn mut_name(&mut self) -> &mut ::std::string::String {
+        &mut self.name
     }
 
     // Take field
-    pub fn take_tag(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.tag, ::std::string::String::new())
+    pub fn take_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.name, ::std::string::String::new())
     }
 
-    // string protocol = 2;
+    // string address = 3;
 
 
-    pub fn get_protocol(&self) -> &str {
-        &self.protocol
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
-    pub fn clear_protocol(&mut self) {
-        self.protocol.clear();
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_protocol(&mut self, v: ::std::string::String) {
-        self.protocol = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_protocol(&mut self) -> &mut ::std::string::String {
-        &mut self.protocol
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_protocol(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.protocol, ::std::string::String::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
     }
 
-    // string listen = 3;
+    // string gateway = 4;
 
 
-    pub fn get_listen(&self) -> &str {
-        &self.listen
+    pub fn get_gateway(&self) -> &str {
+        &self.gateway
     }
-    pub fn clear_listen(&mut self) {
-        self.listen.clear();
+    pub fn clear_gateway(&mut self) {
+        self.gateway.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_listen(&mut self, v: ::std::string::String) {
-        self.listen = v;
+    pub fn set_gateway(&mut self, v: ::std::string::String) {
+        self.gateway = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_listen(&mut self) -> &mut ::std::string::String {
-        &mut self.listen
+    pub fn mut_gateway(&mut self) -> &mut ::std::string::String {
+        &mut self.gateway
     }
 
     // Take field
-    pub fn take_listen(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.listen, ::std::string::String::new())
+    pub fn take_gateway(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.gateway, ::std::string::String::new())
     }
 
-    // uint32 port = 4;
+    // string netmask = 5;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_netmask(&self) -> &str {
+        &self.netmask
     }
-    pub fn clear_port(&mut self) {
-        self.port = 0;
+    pub fn clear_netmask(&mut self) {
+        self.netmask.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_port(&mut self, v: u32) {
-        self.port = v;
+    pub fn set_netmask(&mut self, v: ::std::string::String) {
+        self.netmask = v;
     }
 
-    // bytes settings = 5;
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_netmask(&mut self) -> &mut ::std::string::String {
+        &mut self.netmask
+    }
+
+    // Take field
+    pub fn take_netmask(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.netmask, ::std::string::String::new())
+    }
+
+    // int32 mtu = 6;
 
 
-    pub fn get_settings(&self) -> &[u8] {
-        &self.settings
+    pub fn get_mtu(&self) -> i32 {
+        self.mtu
     }
-    pub fn clear_settings(&mut self) {
-        self.settings.clear();
+    pub fn clear_mtu(&mut self) {
+        self.mtu = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_settings(&mut self, v: ::std::vec::Vec<u8>) {
-        self.settings = v;
+    pub fn set_mtu(&mut self, v: i32) {
+        self.mtu = v;
     }
 
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_settings(&mut self) -> &mut ::std::vec::Vec<u8> {
-        &mut self.settings
-    }
+    // repeated string fake_dns_exclude = 7;
 
-    // Take field
-    pub fn take_settings(&mut self) -> ::std::vec::Vec<u8> {
-        ::std::mem::replace(&mut self.settings, ::std::vec::Vec::new())
+
+    pub fn get_fake_dns_exclude(&self) -> &[::std::string::String] {
+        &self.fake_dns_exclude
+    }
+    pub fn clear_fake_dns_exclude(&mut self) {
+        self.fake_dns_exclude.clear();
     }
-}
 
-impl ::protobuf::Message for Inbound {
-    fn is_initialized(&self) -> bool {
-        true
+    // Param is passed by value, moved
+    pub fn set_fake_dns_exclude(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.fake_dns_exclude = v;
     }
 
-    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+    // Mutable pointer to the field.
+    pub fn mut_fake_dns_exclude(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.fake_dns_exclude
+    }
+
+    // Take field
+    pub fn take_fake_dns_exclude(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.fake_dns_exclude, ::protobuf::RepeatedField::new())
+    }
+
+    // uint32 max_flows = 8;
+
+
+    pub fn get_max_flows(&self) -> u32 {
+        self.max_flows
+    }
+    pub fn clear_max_flows(&mut self) {
+        self.max_flows = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_flows(&mut self, v: u32) {
+        self.max_flows = v;
+    }
+}
+
+impl ::protobuf::Message for TUNInboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
         while !is.eof()? {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.tag)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int32()?;
+                    self.fd = tmp;
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.protocol)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.name)?;
                 },
                 3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.listen)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
                 },
                 4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.gateway)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.netmask)?;
+                },
+                6 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
+                    let tmp = is.read_int32()?;
+                    self.mtu = tmp;
                 },
-                5 => {
-                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.settings)?;
+                7 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.fake_dns_exclude)?;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_flows = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1292,20 +1578,29 @@ impl ::protobuf::Message for Inbound {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.tag.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.tag);
+        if self.fd != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.fd, ::protobuf::wire_format::WireTypeVarint);
         }
-        if !self.protocol.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.protocol);
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.name);
         }
-        if !self.listen.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.listen);
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.address);
         }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(4, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if !self.gateway.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.gateway);
         }
-        if !self.settings.is_empty() {
-            my_size += ::protobuf::rt::bytes_size(5, &self.settings);
+        if !self.netmask.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.netmask);
+        }
+        if self.mtu != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.mtu, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.fake_dns_exclude {
+            my_size += ::protobuf::rt::string_size(7, &value);
+        };
+        if self.max_flows != 0 {
+            my_size += ::protobuf::rt::value_size(8, self.max_flows, ::protobuf::wire_format::WireTypeVarint);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1313,20 +1608,29 @@ impl ::protobuf::Message for Inbound {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.tag.is_empty() {
-            os.write_string(1, &self.tag)?;
+        if self.fd != 0 {
+            os.write_int32(1, self.fd)?;
         }
-        if !self.protocol.is_empty() {
-            os.write_string(2, &self.protocol)?;
+        if !self.name.is_empty() {
+            os.write_string(2, &self.name)?;
         }
-        if !self.listen.is_empty() {
-            os.write_string(3, &self.listen)?;
+        if !self.address.is_empty() {
+            os.write_string(3, &self.address)?;
         }
-        if self.port != 0 {
-            os.write_uint32(4, self.port)?;
+        if !self.gateway.is_empty() {
+            os.write_string(4, &self.gateway)?;
         }
-        if !self.settings.is_empty() {
-            os.write_bytes(5, &self.settings)?;
+        if !self.netmask.is_empty() {
+            os.write_string(5, &self.netmask)?;
+        }
+        if self.mtu != 0 {
+            os.write_int32(6, self.mtu)?;
+        }
+        for v in &self.fake_dns_exclude {
+            os.write_string(7, &v)?;
+        };
+        if self.max_flows != 0 {
+            os.write_uint32(8, self.max_flows)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1358,163 +1662,218 @@ impl ::protobuf::Message for Inbound {
         Self::descriptor_static()
     }
 
-    fn new() -> Inbound {
-        Inbound::new()
+    fn new() -> TUNInboundSettings {
+        TUNInboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                "fd",
+                |m: &TUNInboundSettings| { &m.fd },
+                |m: &mut TUNInboundSettings| { &mut m.fd },
+            ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "tag",
-                |m: &Inbound| { &m.tag },
-                |m: &mut Inbound| { &mut m.tag },
+                "name",
+                |m: &TUNInboundSettings| { &m.name },
+                |m: &mut TUNInboundSettings| { &mut m.name },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "protocol",
-                |m: &Inbound| { &m.protocol },
-                |m: &mut Inbound| { &mut m.protocol },
+                "address",
+                |m: &TUNInboundSettings| { &m.address },
+                |m: &mut TUNInboundSettings| { &mut m.address },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "listen",
-                |m: &Inbound| { &m.listen },
-                |m: &mut Inbound| { &mut m.listen },
+                "gateway",
+                |m: &TUNInboundSettings| { &m.gateway },
+                |m: &mut TUNInboundSettings| { &mut m.gateway },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "port",
-                |m: &Inbound| { &m.port },
-                |m: &mut Inbound| { &mut m.port },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "netmask",
+                |m: &TUNInboundSettings| { &m.netmask },
+                |m: &mut TUNInboundSettings| { &mut m.netmask },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
-                "settings",
-                |m: &Inbound| { &m.settings },
-                |m: &mut Inbound| { &mut m.settings },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt32>(
+                "mtu",
+                |m: &TUNInboundSettings| { &m.mtu },
+                |m: &mut TUNInboundSettings| { &mut m.mtu },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<Inbound>(
-                "Inbound",
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "fake_dns_exclude",
+                |m: &TUNInboundSettings| { &m.fake_dns_exclude },
+                |m: &mut TUNInboundSettings| { &mut m.fake_dns_exclude },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_flows",
+                |m: &TUNInboundSettings| { &m.max_flows },
+                |m: &mut TUNInboundSettings| { &mut m.max_flows },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TUNInboundSettings>(
+                "TUNInboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static Inbound {
-        static instance: ::protobuf::rt::LazyV2<Inbound> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(Inbound::new)
+    fn default_instance() -> &'static TUNInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TUNInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TUNInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for Inbound {
+impl ::protobuf::Clear for TUNInboundSettings {
     fn clear(&mut self) {
-        self.tag.clear();
-        self.protocol.clear();
-        self.listen.clear();
-        self.port = 0;
-        self.settings.clear();
+        self.fd = 0;
+        self.name.clear();
+        self.address.clear();
+        self.gateway.clear();
+        self.netmask.clear();
+        self.mtu = 0;
+        self.fake_dns_exclude.clear();
+        self.max_flows = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for Inbound {
+impl ::std::fmt::Debug for TUNInboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for Inbound {
+impl ::protobuf::reflect::ProtobufValue for TUNInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct RedirectOutboundSettings {
+pub struct SocksInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
+    pub bind: ::std::string::String,
+    pub username: ::std::string::String,
+    pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a RedirectOutboundSettings {
-    fn default() -> &'a RedirectOutboundSettings {
-        <RedirectOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a SocksInboundSettings {
+    fn default() -> &'a SocksInboundSettings {
+        <SocksInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl RedirectOutboundSettings {
-    pub fn new() -> RedirectOutboundSettings {
+impl SocksInboundSettings {
+    pub fn new() -> SocksInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string bind = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_bind(&self) -> &str {
+        &self.bind
     }
-    pub fn clear_address(&mut self) {
-        self.address.clear();
+    pub fn clear_bind(&mut self) {
+        self.bind.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_address(&mut self, v: ::std::string::String) {
-        self.address = v;
+    pub fn set_bind(&mut self, v: ::std::string::String) {
+        self.bind = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_address(&mut self) -> &mut ::std::string::String {
-        &mut self.address
+    pub fn mut_bind(&mut self) -> &mut ::std::string::String {
+        &mut self.bind
     }
 
     // Take field
-    pub fn take_address(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    pub fn take_bind(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bind, ::std::string::String::new())
     }
 
-    // uint32 port = 2;
+    // string username = 2;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_username(&self) -> &str {
+        &self.username
     }
-    pub fn clear_port(&mut self) {
-        self.port = 0;
+    pub fn clear_username(&mut self) {
+        self.username.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_port(&mut self, v: u32) {
-        self.port = v;
+    pub fn set_username(&mut self, v: ::std::string::String) {
+        self.username = v;
     }
-}
 
-impl ::protobuf::Message for RedirectOutboundSettings {
-    fn is_initialized(&self) -> bool {
-        true
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_username(&mut self) -> &mut ::std::string::String {
+        &mut self.username
     }
 
-    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        while !is.eof()? {
-            let (field_number, wire_type) = is.read_tag_unpack()?;
-            match field_number {
-                1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
-                },
-                2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
-                },
-                _ => {
-                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
-                },
-            };
-        }
+    // Take field
+    pub fn take_username(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.username, ::std::string::String::new())
+    }
+
+    // string password = 3;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+    pub fn clear_password(&mut self) {
+        self.password.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_password(&mut self, v: ::std::string::String) {
+        self.password = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_password(&mut self) -> &mut ::std::string::String {
+        &mut self.password
+    }
+
+    // Take field
+    pub fn take_password(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.password, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for SocksInboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bind)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
         ::std::result::Result::Ok(())
     }
 
@@ -1522,11 +1881,14 @@ impl ::protobuf::Message for RedirectOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
+        if !self.bind.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.bind);
         }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.username);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.password);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1534,11 +1896,14 @@ impl ::protobuf::Message for RedirectOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
+        if !self.bind.is_empty() {
+            os.write_string(1, &self.bind)?;
         }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+        if !self.username.is_empty() {
+            os.write_string(2, &self.username)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(3, &self.password)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1570,8 +1935,8 @@ impl ::protobuf::Message for RedirectOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> RedirectOutboundSettings {
-        RedirectOutboundSettings::new()
+    fn new() -> SocksInboundSettings {
+        SocksInboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -1579,113 +1944,130 @@ impl ::protobuf::Message for RedirectOutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "address",
-                |m: &RedirectOutboundSettings| { &m.address },
-                |m: &mut RedirectOutboundSettings| { &mut m.address },
+                "bind",
+                |m: &SocksInboundSettings| { &m.bind },
+                |m: &mut SocksInboundSettings| { &mut m.bind },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "port",
-                |m: &RedirectOutboundSettings| { &m.port },
-                |m: &mut RedirectOutboundSettings| { &mut m.port },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "username",
+                |m: &SocksInboundSettings| { &m.username },
+                |m: &mut SocksInboundSettings| { &mut m.username },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<RedirectOutboundSettings>(
-                "RedirectOutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "password",
+                |m: &SocksInboundSettings| { &m.password },
+                |m: &mut SocksInboundSettings| { &mut m.password },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SocksInboundSettings>(
+                "SocksInboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static RedirectOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<RedirectOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(RedirectOutboundSettings::new)
+    fn default_instance() -> &'static SocksInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<SocksInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SocksInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for RedirectOutboundSettings {
+impl ::protobuf::Clear for SocksInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
+        self.bind.clear();
+        self.username.clear();
+        self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for RedirectOutboundSettings {
+impl ::std::fmt::Debug for SocksInboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for RedirectOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for SocksInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct SocksOutboundSettings {
+pub struct HttpInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
+    pub username: ::std::string::String,
+    pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a SocksOutboundSettings {
-    fn default() -> &'a SocksOutboundSettings {
-        <SocksOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a HttpInboundSettings {
+    fn default() -> &'a HttpInboundSettings {
+        <HttpInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl SocksOutboundSettings {
-    pub fn new() -> SocksOutboundSettings {
+impl HttpInboundSettings {
+    pub fn new() -> HttpInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string username = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_username(&self) -> &str {
+        &self.username
     }
-    pub fn clear_address(&mut self) {
-        self.address.clear();
+    pub fn clear_username(&mut self) {
+        self.username.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_address(&mut self, v: ::std::string::String) {
-        self.address = v;
+    pub fn set_username(&mut self, v: ::std::string::String) {
+        self.username = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_address(&mut self) -> &mut ::std::string::String {
-        &mut self.address
+    pub fn mut_username(&mut self) -> &mut ::std::string::String {
+        &mut self.username
     }
 
     // Take field
-    pub fn take_address(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    pub fn take_username(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.username, ::std::string::String::new())
     }
 
-    // uint32 port = 2;
+    // string password = 2;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_password(&self) -> &str {
+        &self.password
     }
-    pub fn clear_port(&mut self) {
-        self.port = 0;
+    pub fn clear_password(&mut self) {
+        self.password.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_port(&mut self, v: u32) {
-        self.port = v;
+    pub fn set_password(&mut self, v: ::std::string::String) {
+        self.password = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_password(&mut self) -> &mut ::std::string::String {
+        &mut self.password
+    }
+
+    // Take field
+    pub fn take_password(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.password, ::std::string::String::new())
     }
 }
 
-impl ::protobuf::Message for SocksOutboundSettings {
+impl ::protobuf::Message for HttpInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1695,14 +2077,10 @@ impl ::protobuf::Message for SocksOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
                 },
                 2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -1716,11 +2094,11 @@ impl ::protobuf::Message for SocksOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.username);
         }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.password);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1728,11 +2106,11 @@ impl ::protobuf::Message for SocksOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
+        if !self.username.is_empty() {
+            os.write_string(1, &self.username)?;
         }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+        if !self.password.is_empty() {
+            os.write_string(2, &self.password)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -1764,8 +2142,8 @@ impl ::protobuf::Message for SocksOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> SocksOutboundSettings {
-        SocksOutboundSettings::new()
+    fn new() -> HttpInboundSettings {
+        HttpInboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -1773,140 +2151,124 @@ impl ::protobuf::Message for SocksOutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "address",
-                |m: &SocksOutboundSettings| { &m.address },
-                |m: &mut SocksOutboundSettings| { &mut m.address },
+                "username",
+                |m: &HttpInboundSettings| { &m.username },
+                |m: &mut HttpInboundSettings| { &mut m.username },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "port",
-                |m: &SocksOutboundSettings| { &m.port },
-                |m: &mut SocksOutboundSettings| { &mut m.port },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "password",
+                |m: &HttpInboundSettings| { &m.password },
+                |m: &mut HttpInboundSettings| { &mut m.password },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SocksOutboundSettings>(
-                "SocksOutboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<HttpInboundSettings>(
+                "HttpInboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static SocksOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<SocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(SocksOutboundSettings::new)
+    fn default_instance() -> &'static HttpInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<HttpInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(HttpInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for SocksOutboundSettings {
+impl ::protobuf::Clear for HttpInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
+        self.username.clear();
+        self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for SocksOutboundSettings {
+impl ::std::fmt::Debug for HttpInboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for SocksOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for HttpInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct ShadowsocksOutboundSettings {
+pub struct MixedInboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
-    pub method: ::std::string::String,
+    pub bind: ::std::string::String,
+    pub username: ::std::string::String,
     pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a ShadowsocksOutboundSettings {
-    fn default() -> &'a ShadowsocksOutboundSettings {
-        <ShadowsocksOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a MixedInboundSettings {
+    fn default() -> &'a MixedInboundSettings {
+        <MixedInboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ShadowsocksOutboundSettings {
-    pub fn new() -> ShadowsocksOutboundSettings {
+impl MixedInboundSettings {
+    pub fn new() -> MixedInboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string bind = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_bind(&self) -> &str {
+        &self.bind
     }
-    pub fn clear_address(&mut self) {
-        self.address.clear();
+    pub fn clear_bind(&mut self) {
+        self.bind.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_address(&mut self, v: ::std::string::String) {
-        self.address = v;
+    pub fn set_bind(&mut self, v: ::std::string::String) {
+        self.bind = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_address(&mut self) -> &mut ::std::string::String {
-        &mut self.address
+    pub fn mut_bind(&mut self) -> &mut ::std::string::String {
+        &mut self.bind
     }
 
     // Take field
-    pub fn take_address(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.address, ::std::string::String::new())
-    }
-
-    // uint32 port = 2;
-
-
-    pub fn get_port(&self) -> u32 {
-        self.port
-    }
-    pub fn clear_port(&mut self) {
-        self.port = 0;
-    }
-
-    // Param is passed by value, moved
-    pub fn set_port(&mut self, v: u32) {
-        self.port = v;
+    pub fn take_bind(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bind, ::std::string::String::new())
     }
 
-    // string method = 3;
+    // string username = 2;
 
 
-    pub fn get_method(&self) -> &str {
-        &self.method
+    pub fn get_username(&self) -> &str {
+        &self.username
     }
-    pub fn clear_method(&mut self) {
-        self.method.clear();
+    pub fn clear_username(&mut self) {
+        self.username.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_method(&mut self, v: ::std::string::String) {
-        self.method = v;
+    pub fn set_username(&mut self, v: ::std::string::String) {
+        self.username = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_method(&mut self) -> &mut ::std::string::String {
-        &mut self.method
+    pub fn mut_username(&mut self) -> &mut ::std::string::String {
+        &mut self.username
     }
 
     // Take field
-    pub fn take_method(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.method, ::std::string::String::new())
+    pub fn take_username(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.username, ::std::string::String::new())
     }
 
-    // string password = 4;
+    // string password = 3;
 
 
     pub fn get_password(&self) -> &str {
@@ -1933,7 +2295,7 @@ impl ShadowsocksOutboundSettings {
     }
 }
 
-impl ::protobuf::Message for ShadowsocksOutboundSettings {
+impl ::protobuf::Message for MixedInboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -1943,19 +2305,12 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bind)?;
                 },
                 2 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.port = tmp;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
                 },
                 3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
-                },
-                4 => {
                     ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
                 },
                 _ => {
@@ -1970,17 +2325,14 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
-        }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if !self.bind.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.bind);
         }
-        if !self.method.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.method);
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.username);
         }
         if !self.password.is_empty() {
-            my_size += ::protobuf::rt::string_size(4, &self.password);
+            my_size += ::protobuf::rt::string_size(3, &self.password);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -1988,17 +2340,14 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
-        }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+        if !self.bind.is_empty() {
+            os.write_string(1, &self.bind)?;
         }
-        if !self.method.is_empty() {
-            os.write_string(3, &self.method)?;
+        if !self.username.is_empty() {
+            os.write_string(2, &self.username)?;
         }
         if !self.password.is_empty() {
-            os.write_string(4, &self.password)?;
+            os.write_string(3, &self.password)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2030,8 +2379,8 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> ShadowsocksOutboundSettings {
-        ShadowsocksOutboundSettings::new()
+    fn new() -> MixedInboundSettings {
+        MixedInboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -2039,110 +2388,158 @@ impl ::protobuf::Message for ShadowsocksOutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "address",
-                |m: &ShadowsocksOutboundSettings| { &m.address },
-                |m: &mut ShadowsocksOutboundSettings| { &mut m.address },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "port",
-                |m: &ShadowsocksOutboundSettings| { &m.port },
-                |m: &mut ShadowsocksOutboundSettings| { &mut m.port },
+                "bind",
+                |m: &MixedInboundSettings| { &m.bind },
+                |m: &mut MixedInboundSettings| { &mut m.bind },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "method",
-                |m: &ShadowsocksOutboundSettings| { &m.method },
-                |m: &mut ShadowsocksOutboundSettings| { &mut m.method },
+                "username",
+                |m: &MixedInboundSettings| { &m.username },
+                |m: &mut MixedInboundSettings| { &mut m.username },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
                 "password",
-                |m: &ShadowsocksOutboundSettings| { &m.password },
-                |m: &mut ShadowsocksOutboundSettings| { &mut m.password },
+                |m: &MixedInboundSettings| { &m.password },
+                |m: &mut MixedInboundSettings| { &mut m.password },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ShadowsocksOutboundSettings>(
-                "ShadowsocksOutboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<MixedInboundSettings>(
+                "MixedInboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static ShadowsocksOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<ShadowsocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(ShadowsocksOutboundSettings::new)
+    fn default_instance() -> &'static MixedInboundSettings {
+        static instance: ::protobuf::rt::LazyV2<MixedInboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(MixedInboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for ShadowsocksOutboundSettings {
+impl ::protobuf::Clear for MixedInboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
-        self.method.clear();
+        self.bind.clear();
+        self.username.clear();
         self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for ShadowsocksOutboundSettings {
+impl ::std::fmt::Debug for MixedInboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for ShadowsocksOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for MixedInboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct TrojanOutboundSettings {
+pub struct Inbound {
     // message fields
-    pub address: ::std::string::String,
+    pub tag: ::std::string::String,
+    pub protocol: ::std::string::String,
+    pub listen: ::std::string::String,
     pub port: u32,
-    pub password: ::std::string::String,
+    pub settings: ::std::vec::Vec<u8>,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TrojanOutboundSettings {
-    fn default() -> &'a TrojanOutboundSettings {
-        <TrojanOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a Inbound {
+    fn default() -> &'a Inbound {
+        <Inbound as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TrojanOutboundSettings {
-    pub fn new() -> TrojanOutboundSettings {
+impl Inbound {
+    pub fn new() -> Inbound {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // string tag = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_tag(&self) -> &str {
+        &self.tag
     }
-    pub fn clear_address(&mut self) {
-        self.address.clear();
+    pub fn clear_tag(&mut self) {
+        self.tag.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_address(&mut self, v: ::std::string::String) {
-        self.address = v;
+    pub fn set_tag(&mut self, v: ::std::string::String) {
+        self.tag = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_address(&mut self) -> &mut ::std::string::String {
-        &mut self.address
+    pub fn mut_tag(&mut self) -> &mut ::std::string::String {
+        &mut self.tag
     }
 
     // Take field
-    pub fn take_address(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    pub fn take_tag(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.tag, ::std::string::String::new())
     }
 
-    // uint32 port = 2;
+    // string protocol = 2;
+
+
+    pub fn get_protocol(&self) -> &str {
+        &self.protocol
+    }
+    pub fn clear_protocol(&mut self) {
+        self.protocol.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_protocol(&mut self, v: ::std::string::String) {
+        self.protocol = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_protocol(&mut self) -> &mut ::std::string::String {
+        &mut self.protocol
+    }
+
+    // Take field
+    pub fn take_protocol(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.protocol, ::std::string::String::new())
+    }
+
+    // string listen = 3;
+
+
+    pub fn get_listen(&self) -> &str {
+        &self.listen
+    }
+    pub fn clear_listen(&mut self) {
+        self.listen.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_listen(&mut self, v: ::std::string::String) {
+        self.listen = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_listen(&mut self) -> &mut ::std::string::String {
+        &mut self.listen
+    }
+
+    // Take field
+    pub fn take_listen(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.listen, ::std::string::String::new())
+    }
+
+    // uint32 port = 4;
 
 
     pub fn get_port(&self) -> u32 {
@@ -2157,34 +2554,34 @@ impl TrojanOutboundSettings {
         self.port = v;
     }
 
-    // string password = 3;
+    // bytes settings = 5;
 
 
-    pub fn get_password(&self) -> &str {
-        &self.password
+    pub fn get_settings(&self) -> &[u8] {
+        &self.settings
     }
-    pub fn clear_password(&mut self) {
-        self.password.clear();
+    pub fn clear_settings(&mut self) {
+        self.settings.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_password(&mut self, v: ::std::string::String) {
-        self.password = v;
+    pub fn set_settings(&mut self, v: ::std::vec::Vec<u8>) {
+        self.settings = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_password(&mut self) -> &mut ::std::string::String {
-        &mut self.password
+    pub fn mut_settings(&mut self) -> &mut ::std::vec::Vec<u8> {
+        &mut self.settings
     }
 
     // Take field
-    pub fn take_password(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.password, ::std::string::String::new())
+    pub fn take_settings(&mut self) -> ::std::vec::Vec<u8> {
+        ::std::mem::replace(&mut self.settings, ::std::vec::Vec::new())
     }
 }
 
-impl ::protobuf::Message for TrojanOutboundSettings {
+impl ::protobuf::Message for Inbound {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2194,17 +2591,23 @@ impl ::protobuf::Message for TrojanOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.tag)?;
                 },
                 2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.protocol)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.listen)?;
+                },
+                4 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
                     self.port = tmp;
                 },
-                3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                5 => {
+                    ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.settings)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2218,14 +2621,20 @@ impl ::protobuf::Message for TrojanOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
+        if !self.tag.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.tag);
+        }
+        if !self.protocol.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.protocol);
+        }
+        if !self.listen.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.listen);
         }
         if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+            my_size += ::protobuf::rt::value_size(4, self.port, ::protobuf::wire_format::WireTypeVarint);
         }
-        if !self.password.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.password);
+        if !self.settings.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(5, &self.settings);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2233,14 +2642,20 @@ impl ::protobuf::Message for TrojanOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
+        if !self.tag.is_empty() {
+            os.write_string(1, &self.tag)?;
+        }
+        if !self.protocol.is_empty() {
+            os.write_string(2, &self.protocol)?;
+        }
+        if !self.listen.is_empty() {
+            os.write_string(3, &self.listen)?;
         }
         if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+            os.write_uint32(4, self.port)?;
         }
-        if !self.password.is_empty() {
-            os.write_string(3, &self.password)?;
+        if !self.settings.is_empty() {
+            os.write_bytes(5, &self.settings)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2272,8 +2687,8 @@ impl ::protobuf::Message for TrojanOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TrojanOutboundSettings {
-        TrojanOutboundSettings::new()
+    fn new() -> Inbound {
+        Inbound::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -2281,173 +2696,216 @@ impl ::protobuf::Message for TrojanOutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "address",
-                |m: &TrojanOutboundSettings| { &m.address },
-                |m: &mut TrojanOutboundSettings| { &mut m.address },
+                "tag",
+                |m: &Inbound| { &m.tag },
+                |m: &mut Inbound| { &mut m.tag },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "protocol",
+                |m: &Inbound| { &m.protocol },
+                |m: &mut Inbound| { &mut m.protocol },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "listen",
+                |m: &Inbound| { &m.listen },
+                |m: &mut Inbound| { &mut m.listen },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
                 "port",
-                |m: &TrojanOutboundSettings| { &m.port },
-                |m: &mut TrojanOutboundSettings| { &mut m.port },
+                |m: &Inbound| { &m.port },
+                |m: &mut Inbound| { &mut m.port },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "password",
-                |m: &TrojanOutboundSettings| { &m.password },
-                |m: &mut TrojanOutboundSettings| { &mut m.password },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBytes>(
+                "settings",
+                |m: &Inbound| { &m.settings },
+                |m: &mut Inbound| { &mut m.settings },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TrojanOutboundSettings>(
-                "TrojanOutboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<Inbound>(
+                "Inbound",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static TrojanOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TrojanOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TrojanOutboundSettings::new)
+    fn default_instance() -> &'static Inbound {
+        static instance: ::protobuf::rt::LazyV2<Inbound> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(Inbound::new)
     }
 }
 
-impl ::protobuf::Clear for TrojanOutboundSettings {
+impl ::protobuf::Clear for Inbound {
     fn clear(&mut self) {
-        self.address.clear();
+        self.tag.clear();
+        self.protocol.clear();
+        self.listen.clear();
         self.port = 0;
-        self.password.clear();
+        self.settings.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for TrojanOutboundSettings {
+impl ::std::fmt::Debug for Inbound {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TrojanOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for Inbound {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct VMessOutboundSettings {
+pub struct DirectOutboundSettings {
     // message fields
-    pub address: ::std::string::String,
-    pub port: u32,
-    pub uuid: ::std::string::String,
-    pub security: ::std::string::String,
+    pub proxy_protocol: u32,
+    pub dial_failure_window: u32,
+    pub sequential_dial: bool,
+    pub happy_eyeballs_delay: u32,
+    pub prefer_ipv4: bool,
+    pub retry_on_reset: bool,
+    pub preserve_source_port: bool,
+    pub udp_batching: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a VMessOutboundSettings {
-    fn default() -> &'a VMessOutboundSettings {
-        <VMessOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a DirectOutboundSettings {
+    fn default() -> &'a DirectOutboundSettings {
+        <DirectOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl VMessOutboundSettings {
-    pub fn new() -> VMessOutboundSettings {
+impl DirectOutboundSettings {
+    pub fn new() -> DirectOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // string address = 1;
+    // uint32 proxy_protocol = 1;
 
 
-    pub fn get_address(&self) -> &str {
-        &self.address
+    pub fn get_proxy_protocol(&self) -> u32 {
+        self.proxy_protocol
     }
-    pub fn clear_address(&mut self) {
-        self.address.clear();
+    pub fn clear_proxy_protocol(&mut self) {
+        self.proxy_protocol = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_address(&mut self, v: ::std::string::String) {
-        self.address = v;
+    pub fn set_proxy_protocol(&mut self, v: u32) {
+        self.proxy_protocol = v;
     }
 
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_address(&mut self) -> &mut ::std::string::String {
-        &mut self.address
+    // uint32 dial_failure_window = 2;
+
+
+    pub fn get_dial_failure_window(&self) -> u32 {
+        self.dial_failure_window
+    }
+    pub fn clear_dial_failure_window(&mut self) {
+        self.dial_failure_window = 0;
     }
 
-    // Take field
-    pub fn take_address(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    // Param is passed by value, moved
+    pub fn set_dial_failure_window(&mut self, v: u32) {
+        self.dial_failure_window = v;
     }
 
-    // uint32 port = 2;
+    // bool sequential_dial = 3;
 
 
-    pub fn get_port(&self) -> u32 {
-        self.port
+    pub fn get_sequential_dial(&self) -> bool {
+        self.sequential_dial
     }
-    pub fn clear_port(&mut self) {
-        self.port = 0;
+    pub fn clear_sequential_dial(&mut self) {
+        self.sequential_dial = false;
     }
 
     // Param is passed by value, moved
-    pub fn set_port(&mut self, v: u32) {
-        self.port = v;
+    pub fn set_sequential_dial(&mut self, v: bool) {
+        self.sequential_dial = v;
     }
 
-    // string uuid = 3;
+    // uint32 happy_eyeballs_delay = 4;
 
 
-    pub fn get_uuid(&self) -> &str {
-        &self.uuid
+    pub fn get_happy_eyeballs_delay(&self) -> u32 {
+        self.happy_eyeballs_delay
     }
-    pub fn clear_uuid(&mut self) {
-        self.uuid.clear();
+    pub fn clear_happy_eyeballs_delay(&mut self) {
+        self.happy_eyeballs_delay = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_uuid(&mut self, v: ::std::string::String) {
-        self.uuid = v;
+    pub fn set_happy_eyeballs_delay(&mut self, v: u32) {
+        self.happy_eyeballs_delay = v;
     }
 
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_uuid(&mut self) -> &mut ::std::string::String {
-        &mut self.uuid
+    // bool prefer_ipv4 = 5;
+
+
+    pub fn get_prefer_ipv4(&self) -> bool {
+        self.prefer_ipv4
+    }
+    pub fn clear_prefer_ipv4(&mut self) {
+        self.prefer_ipv4 = false;
     }
 
-    // Take field
-    pub fn take_uuid(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.uuid, ::std::string::String::new())
+    // Param is passed by value, moved
+    pub fn set_prefer_ipv4(&mut self, v: bool) {
+        self.prefer_ipv4 = v;
     }
 
-    // string security = 4;
+    // bool retry_on_reset = 6;
 
 
-    pub fn get_security(&self) -> &str {
-        &self.security
+    pub fn get_retry_on_reset(&self) -> bool {
+        self.retry_on_reset
     }
-    pub fn clear_security(&mut self) {
-        self.security.clear();
+    pub fn clear_retry_on_reset(&mut self) {
+        self.retry_on_reset = false;
     }
 
     // Param is passed by value, moved
-    pub fn set_security(&mut self, v: ::std::string::String) {
-        self.security = v;
+    pub fn set_retry_on_reset(&mut self, v: bool) {
+        self.retry_on_reset = v;
     }
 
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_security(&mut self) -> &mut ::std::string::String {
-        &mut self.security
+    // bool preserve_source_port = 7;
+
+
+    pub fn get_preserve_source_port(&self) -> bool {
+        self.preserve_source_port
+    }
+    pub fn clear_preserve_source_port(&mut self) {
+        self.preserve_source_port = false;
     }
 
-    // Take field
-    pub fn take_security(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.security, ::std::string::String::new())
+    // Param is passed by value, moved
+    pub fn set_preserve_source_port(&mut self, v: bool) {
+        self.preserve_source_port = v;
+    }
+
+    // bool udp_batching = 8;
+
+
+    pub fn get_udp_batching(&self) -> bool {
+        self.udp_batching
+    }
+    pub fn clear_udp_batching(&mut self) {
+        self.udp_batching = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_udp_batching(&mut self, v: bool) {
+        self.udp_batching = v;
     }
 }
 
-impl ::protobuf::Message for VMessOutboundSettings {
+impl ::protobuf::Message for DirectOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2457,20 +2915,60 @@ impl ::protobuf::Message for VMessOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.proxy_protocol = tmp;
                 },
                 2 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.port = tmp;
+                    self.dial_failure_window = tmp;
                 },
                 3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.uuid)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.sequential_dial = tmp;
                 },
                 4 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.security)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.happy_eyeballs_delay = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.prefer_ipv4 = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.retry_on_reset = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.preserve_source_port = tmp;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.udp_batching = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2484,17 +2982,29 @@ impl ::protobuf::Message for VMessOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.address.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.address);
+        if self.proxy_protocol != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.proxy_protocol, ::protobuf::wire_format::WireTypeVarint);
         }
-        if self.port != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        if self.dial_failure_window != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.dial_failure_window, ::protobuf::wire_format::WireTypeVarint);
         }
-        if !self.uuid.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.uuid);
+        if self.sequential_dial != false {
+            my_size += 2;
         }
-        if !self.security.is_empty() {
-            my_size += ::protobuf::rt::string_size(4, &self.security);
+        if self.happy_eyeballs_delay != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.happy_eyeballs_delay, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.prefer_ipv4 != false {
+            my_size += 2;
+        }
+        if self.retry_on_reset != false {
+            my_size += 2;
+        }
+        if self.preserve_source_port != false {
+            my_size += 2;
+        }
+        if self.udp_batching != false {
+            my_size += 2;
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2502,17 +3012,29 @@ impl ::protobuf::Message for VMessOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.address.is_empty() {
-            os.write_string(1, &self.address)?;
+        if self.proxy_protocol != 0 {
+            os.write_uint32(1, self.proxy_protocol)?;
         }
-        if self.port != 0 {
-            os.write_uint32(2, self.port)?;
+        if self.dial_failure_window != 0 {
+            os.write_uint32(2, self.dial_failure_window)?;
         }
-        if !self.uuid.is_empty() {
-            os.write_string(3, &self.uuid)?;
+        if self.sequential_dial != false {
+            os.write_bool(3, self.sequential_dial)?;
         }
-        if !self.security.is_empty() {
-            os.write_string(4, &self.security)?;
+        if self.happy_eyeballs_delay != 0 {
+            os.write_uint32(4, self.happy_eyeballs_delay)?;
+        }
+        if self.prefer_ipv4 != false {
+            os.write_bool(5, self.prefer_ipv4)?;
+        }
+        if self.retry_on_reset != false {
+            os.write_bool(6, self.retry_on_reset)?;
+        }
+        if self.preserve_source_port != false {
+            os.write_bool(7, self.preserve_source_port)?;
+        }
+        if self.udp_batching != false {
+            os.write_bool(8, self.udp_batching)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2544,89 +3066,113 @@ impl ::protobuf::Message for VMessOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> VMessOutboundSettings {
-        VMessOutboundSettings::new()
+    fn new() -> DirectOutboundSettings {
+        DirectOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "address",
-                |m: &VMessOutboundSettings| { &m.address },
-                |m: &mut VMessOutboundSettings| { &mut m.address },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "proxy_protocol",
+                |m: &DirectOutboundSettings| { &m.proxy_protocol },
+                |m: &mut DirectOutboundSettings| { &mut m.proxy_protocol },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "port",
-                |m: &VMessOutboundSettings| { &m.port },
-                |m: &mut VMessOutboundSettings| { &mut m.port },
+                "dial_failure_window",
+                |m: &DirectOutboundSettings| { &m.dial_failure_window },
+                |m: &mut DirectOutboundSettings| { &mut m.dial_failure_window },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "uuid",
-                |m: &VMessOutboundSettings| { &m.uuid },
-                |m: &mut VMessOutboundSettings| { &mut m.uuid },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "sequential_dial",
+                |m: &DirectOutboundSettings| { &m.sequential_dial },
+                |m: &mut DirectOutboundSettings| { &mut m.sequential_dial },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "security",
-                |m: &VMessOutboundSettings| { &m.security },
-                |m: &mut VMessOutboundSettings| { &mut m.security },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "happy_eyeballs_delay",
+                |m: &DirectOutboundSettings| { &m.happy_eyeballs_delay },
+                |m: &mut DirectOutboundSettings| { &mut m.happy_eyeballs_delay },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<VMessOutboundSettings>(
-                "VMessOutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "prefer_ipv4",
+                |m: &DirectOutboundSettings| { &m.prefer_ipv4 },
+                |m: &mut DirectOutboundSettings| { &mut m.prefer_ipv4 },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "retry_on_reset",
+                |m: &DirectOutboundSettings| { &m.retry_on_reset },
+                |m: &mut DirectOutboundSettings| { &mut m.retry_on_reset },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "preserve_source_port",
+                |m: &DirectOutboundSettings| { &m.preserve_source_port },
+                |m: &mut DirectOutboundSettings| { &mut m.preserve_source_port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "udp_batching",
+                |m: &DirectOutboundSettings| { &m.udp_batching },
+                |m: &mut DirectOutboundSettings| { &mut m.udp_batching },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<DirectOutboundSettings>(
+                "DirectOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static VMessOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<VMessOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(VMessOutboundSettings::new)
+    fn default_instance() -> &'static DirectOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<DirectOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(DirectOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for VMessOutboundSettings {
+impl ::protobuf::Clear for DirectOutboundSettings {
     fn clear(&mut self) {
-        self.address.clear();
-        self.port = 0;
-        self.uuid.clear();
-        self.security.clear();
+        self.proxy_protocol = 0;
+        self.dial_failure_window = 0;
+        self.sequential_dial = false;
+        self.happy_eyeballs_delay = 0;
+        self.prefer_ipv4 = false;
+        self.retry_on_reset = false;
+        self.preserve_source_port = false;
+        self.udp_batching = false;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for VMessOutboundSettings {
+impl ::std::fmt::Debug for DirectOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for VMessOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for DirectOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct VLessOutboundSettings {
+pub struct RedirectOutboundSettings {
     // message fields
     pub address: ::std::string::String,
     pub port: u32,
-    pub uuid: ::std::string::String,
+    pub proxy_protocol: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a VLessOutboundSettings {
-    fn default() -> &'a VLessOutboundSettings {
-        <VLessOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a RedirectOutboundSettings {
+    fn default() -> &'a RedirectOutboundSettings {
+        <RedirectOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl VLessOutboundSettings {
-    pub fn new() -> VLessOutboundSettings {
+impl RedirectOutboundSettings {
+    pub fn new() -> RedirectOutboundSettings {
         ::std::default::Default::default()
     }
 
@@ -2671,34 +3217,23 @@ impl VLessOutboundSettings {
         self.port = v;
     }
 
-    // string uuid = 3;
+    // uint32 proxy_protocol = 3;
 
 
-    pub fn get_uuid(&self) -> &str {
-        &self.uuid
+    pub fn get_proxy_protocol(&self) -> u32 {
+        self.proxy_protocol
     }
-    pub fn clear_uuid(&mut self) {
-        self.uuid.clear();
+    pub fn clear_proxy_protocol(&mut self) {
+        self.proxy_protocol = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_uuid(&mut self, v: ::std::string::String) {
-        self.uuid = v;
-    }
-
-    // Mutable pointer to the field.
-    // If field is not initialized, it is initialized with default value first.
-    pub fn mut_uuid(&mut self) -> &mut ::std::string::String {
-        &mut self.uuid
-    }
-
-    // Take field
-    pub fn take_uuid(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.uuid, ::std::string::String::new())
+    pub fn set_proxy_protocol(&mut self, v: u32) {
+        self.proxy_protocol = v;
     }
 }
 
-impl ::protobuf::Message for VLessOutboundSettings {
+impl ::protobuf::Message for RedirectOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2718,7 +3253,11 @@ impl ::protobuf::Message for VLessOutboundSettings {
                     self.port = tmp;
                 },
                 3 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.uuid)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.proxy_protocol = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2738,8 +3277,8 @@ impl ::protobuf::Message for VLessOutboundSettings {
         if self.port != 0 {
             my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
         }
-        if !self.uuid.is_empty() {
-            my_size += ::protobuf::rt::string_size(3, &self.uuid);
+        if self.proxy_protocol != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.proxy_protocol, ::protobuf::wire_format::WireTypeVarint);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -2753,8 +3292,8 @@ impl ::protobuf::Message for VLessOutboundSettings {
         if self.port != 0 {
             os.write_uint32(2, self.port)?;
         }
-        if !self.uuid.is_empty() {
-            os.write_string(3, &self.uuid)?;
+        if self.proxy_protocol != 0 {
+            os.write_uint32(3, self.proxy_protocol)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -2786,8 +3325,8 @@ impl ::protobuf::Message for VLessOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> VLessOutboundSettings {
-        VLessOutboundSettings::new()
+    fn new() -> RedirectOutboundSettings {
+        RedirectOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -2796,128 +3335,118 @@ impl ::protobuf::Message for VLessOutboundSettings {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
                 "address",
-                |m: &VLessOutboundSettings| { &m.address },
-                |m: &mut VLessOutboundSettings| { &mut m.address },
+                |m: &RedirectOutboundSettings| { &m.address },
+                |m: &mut RedirectOutboundSettings| { &mut m.address },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
                 "port",
-                |m: &VLessOutboundSettings| { &m.port },
-                |m: &mut VLessOutboundSettings| { &mut m.port },
+                |m: &RedirectOutboundSettings| { &m.port },
+                |m: &mut RedirectOutboundSettings| { &mut m.port },
             ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "uuid",
-                |m: &VLessOutboundSettings| { &m.uuid },
-                |m: &mut VLessOutboundSettings| { &mut m.uuid },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "proxy_protocol",
+                |m: &RedirectOutboundSettings| { &m.proxy_protocol },
+                |m: &mut RedirectOutboundSettings| { &mut m.proxy_protocol },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<VLessOutboundSettings>(
-                "VLessOutboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<RedirectOutboundSettings>(
+                "RedirectOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static VLessOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<VLessOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(VLessOutboundSettings::new)
+    fn default_instance() -> &'static RedirectOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RedirectOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RedirectOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for VLessOutboundSettings {
+impl ::protobuf::Clear for RedirectOutboundSettings {
     fn clear(&mut self) {
         self.address.clear();
         self.port = 0;
-        self.uuid.clear();
+        self.proxy_protocol = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for VLessOutboundSettings {
+impl ::std::fmt::Debug for RedirectOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for VLessOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for RedirectOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct TlsOutboundSettings {
+pub struct FixedOutboundSettings {
     // message fields
-    pub server_name: ::std::string::String,
-    pub alpn: ::protobuf::RepeatedField<::std::string::String>,
+    pub address: ::std::string::String,
+    pub port: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TlsOutboundSettings {
-    fn default() -> &'a TlsOutboundSettings {
-        <TlsOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a FixedOutboundSettings {
+    fn default() -> &'a FixedOutboundSettings {
+        <FixedOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TlsOutboundSettings {
-    pub fn new() -> TlsOutboundSettings {
+impl FixedOutboundSettings {
+    pub fn new() -> FixedOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // string server_name = 1;
+    // string address = 1;
 
 
-    pub fn get_server_name(&self) -> &str {
-        &self.server_name
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
-    pub fn clear_server_name(&mut self) {
-        self.server_name.clear();
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_server_name(&mut self, v: ::std::string::String) {
-        self.server_name = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_server_name(&mut self) -> &mut ::std::string::String {
-        &mut self.server_name
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_server_name(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.server_name, ::std::string::String::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
     }
 
-    // repeated string alpn = 2;
+    // uint32 port = 2;
 
 
-    pub fn get_alpn(&self) -> &[::std::string::String] {
-        &self.alpn
+    pub fn get_port(&self) -> u32 {
+        self.port
     }
-    pub fn clear_alpn(&mut self) {
-        self.alpn.clear();
+    pub fn clear_port(&mut self) {
+        self.port = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_alpn(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
-        self.alpn = v;
-    }
-
-    // Mutable pointer to the field.
-    pub fn mut_alpn(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
-        &mut self.alpn
-    }
-
-    // Take field
-    pub fn take_alpn(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
-        ::std::mem::replace(&mut self.alpn, ::protobuf::RepeatedField::new())
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
     }
 }
 
-impl ::protobuf::Message for TlsOutboundSettings {
+impl ::protobuf::Message for FixedOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -2927,10 +3456,14 @@ impl ::protobuf::Message for TlsOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
                 },
                 2 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.alpn)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -2944,24 +3477,24 @@ impl ::protobuf::Message for TlsOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.server_name.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.server_name);
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
         }
-        for value in &self.alpn {
-            my_size += ::protobuf::rt::string_size(2, &value);
-        };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.server_name.is_empty() {
-            os.write_string(1, &self.server_name)?;
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
         }
-        for v in &self.alpn {
-            os.write_string(2, &v)?;
-        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -2992,8 +3525,8 @@ impl ::protobuf::Message for TlsOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TlsOutboundSettings {
-        TlsOutboundSettings::new()
+    fn new() -> FixedOutboundSettings {
+        FixedOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -3001,97 +3534,177 @@ impl ::protobuf::Message for TlsOutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "server_name",
-                |m: &TlsOutboundSettings| { &m.server_name },
-                |m: &mut TlsOutboundSettings| { &mut m.server_name },
+                "address",
+                |m: &FixedOutboundSettings| { &m.address },
+                |m: &mut FixedOutboundSettings| { &mut m.address },
             ));
-            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "alpn",
-                |m: &TlsOutboundSettings| { &m.alpn },
-                |m: &mut TlsOutboundSettings| { &mut m.alpn },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &FixedOutboundSettings| { &m.port },
+                |m: &mut FixedOutboundSettings| { &mut m.port },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TlsOutboundSettings>(
-                "TlsOutboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<FixedOutboundSettings>(
+                "FixedOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static TlsOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TlsOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TlsOutboundSettings::new)
+    fn default_instance() -> &'static FixedOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<FixedOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(FixedOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for TlsOutboundSettings {
+impl ::protobuf::Clear for FixedOutboundSettings {
     fn clear(&mut self) {
-        self.server_name.clear();
-        self.alpn.clear();
+        self.address.clear();
+        self.port = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for TlsOutboundSettings {
+impl ::std::fmt::Debug for FixedOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TlsOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for FixedOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct WebSocketOutboundSettings {
+pub struct SocksOutboundSettings {
     // message fields
-    pub path: ::std::string::String,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub reassociate_base: u32,
+    pub reassociate_max: u32,
+    pub reassociate_jitter: u32,
+    pub reassociate_timeout: u32,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a WebSocketOutboundSettings {
-    fn default() -> &'a WebSocketOutboundSettings {
-        <WebSocketOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a SocksOutboundSettings {
+    fn default() -> &'a SocksOutboundSettings {
+        <SocksOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl WebSocketOutboundSettings {
-    pub fn new() -> WebSocketOutboundSettings {
+impl SocksOutboundSettings {
+    pub fn new() -> SocksOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // string path = 1;
+    // string address = 1;
 
 
-    pub fn get_path(&self) -> &str {
-        &self.path
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
-    pub fn clear_path(&mut self) {
-        self.path.clear();
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_path(&mut self, v: ::std::string::String) {
-        self.path = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_path(&mut self) -> &mut ::std::string::String {
-        &mut self.path
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_path(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.path, ::std::string::String::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+    pub fn clear_port(&mut self) {
+        self.port = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // uint32 reassociate_base = 3;
+
+
+    pub fn get_reassociate_base(&self) -> u32 {
+        self.reassociate_base
+    }
+    pub fn clear_reassociate_base(&mut self) {
+        self.reassociate_base = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_reassociate_base(&mut self, v: u32) {
+        self.reassociate_base = v;
+    }
+
+    // uint32 reassociate_max = 4;
+
+
+    pub fn get_reassociate_max(&self) -> u32 {
+        self.reassociate_max
+    }
+    pub fn clear_reassociate_max(&mut self) {
+        self.reassociate_max = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_reassociate_max(&mut self, v: u32) {
+        self.reassociate_max = v;
+    }
+
+    // uint32 reassociate_jitter = 5;
+
+
+    pub fn get_reassociate_jitter(&self) -> u32 {
+        self.reassociate_jitter
+    }
+    pub fn clear_reassociate_jitter(&mut self) {
+        self.reassociate_jitter = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_reassociate_jitter(&mut self, v: u32) {
+        self.reassociate_jitter = v;
+    }
+
+    // uint32 reassociate_timeout = 6;
+
+
+    pub fn get_reassociate_timeout(&self) -> u32 {
+        self.reassociate_timeout
+    }
+    pub fn clear_reassociate_timeout(&mut self) {
+        self.reassociate_timeout = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_reassociate_timeout(&mut self, v: u32) {
+        self.reassociate_timeout = v;
     }
 }
 
-impl ::protobuf::Message for WebSocketOutboundSettings {
+impl ::protobuf::Message for SocksOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3101,7 +3714,42 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.reassociate_base = tmp;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.reassociate_max = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.reassociate_jitter = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.reassociate_timeout = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3115,17 +3763,47 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.path.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.path);
-        }
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.reassociate_base != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.reassociate_base, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.reassociate_max != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.reassociate_max, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.reassociate_jitter != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.reassociate_jitter, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.reassociate_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.reassociate_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.path.is_empty() {
-            os.write_string(1, &self.path)?;
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if self.reassociate_base != 0 {
+            os.write_uint32(3, self.reassociate_base)?;
+        }
+        if self.reassociate_max != 0 {
+            os.write_uint32(4, self.reassociate_max)?;
+        }
+        if self.reassociate_jitter != 0 {
+            os.write_uint32(5, self.reassociate_jitter)?;
+        }
+        if self.reassociate_timeout != 0 {
+            os.write_uint32(6, self.reassociate_timeout)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -3157,8 +3835,8 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> WebSocketOutboundSettings {
-        WebSocketOutboundSettings::new()
+    fn new() -> SocksOutboundSettings {
+        SocksOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -3166,118 +3844,207 @@ impl ::protobuf::Message for WebSocketOutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "path",
-                |m: &WebSocketOutboundSettings| { &m.path },
-                |m: &mut WebSocketOutboundSettings| { &mut m.path },
+                "address",
+                |m: &SocksOutboundSettings| { &m.address },
+                |m: &mut SocksOutboundSettings| { &mut m.address },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<WebSocketOutboundSettings>(
-                "WebSocketOutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &SocksOutboundSettings| { &m.port },
+                |m: &mut SocksOutboundSettings| { &mut m.port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "reassociate_base",
+                |m: &SocksOutboundSettings| { &m.reassociate_base },
+                |m: &mut SocksOutboundSettings| { &mut m.reassociate_base },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "reassociate_max",
+                |m: &SocksOutboundSettings| { &m.reassociate_max },
+                |m: &mut SocksOutboundSettings| { &mut m.reassociate_max },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "reassociate_jitter",
+                |m: &SocksOutboundSettings| { &m.reassociate_jitter },
+                |m: &mut SocksOutboundSettings| { &mut m.reassociate_jitter },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "reassociate_timeout",
+                |m: &SocksOutboundSettings| { &m.reassociate_timeout },
+                |m: &mut SocksOutboundSettings| { &mut m.reassociate_timeout },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<SocksOutboundSettings>(
+                "SocksOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static WebSocketOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<WebSocketOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(WebSocketOutboundSettings::new)
+    fn default_instance() -> &'static SocksOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<SocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(SocksOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for WebSocketOutboundSettings {
+impl ::protobuf::Clear for SocksOutboundSettings {
     fn clear(&mut self) {
-        self.path.clear();
+        self.address.clear();
+        self.port = 0;
+        self.reassociate_base = 0;
+        self.reassociate_max = 0;
+        self.reassociate_jitter = 0;
+        self.reassociate_timeout = 0;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for WebSocketOutboundSettings {
+impl ::std::fmt::Debug for SocksOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for WebSocketOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for SocksOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct HTTP2OutboundSettings {
+pub struct HTTPOutboundSettings {
     // message fields
-    pub path: ::std::string::String,
-    pub host: ::std::string::String,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub username: ::std::string::String,
+    pub password: ::std::string::String,
+    pub forward: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a HTTP2OutboundSettings {
-    fn default() -> &'a HTTP2OutboundSettings {
-        <HTTP2OutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a HTTPOutboundSettings {
+    fn default() -> &'a HTTPOutboundSettings {
+        <HTTPOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl HTTP2OutboundSettings {
-    pub fn new() -> HTTP2OutboundSettings {
+impl HTTPOutboundSettings {
+    pub fn new() -> HTTPOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // string path = 1;
+    // string address = 1;
 
 
-    pub fn get_path(&self) -> &str {
-        &self.path
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
-    pub fn clear_path(&mut self) {
-        self.path.clear();
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_path(&mut self, v: ::std::string::String) {
-        self.path = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_path(&mut self) -> &mut ::std::string::String {
-        &mut self.path
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_path(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.path, ::std::string::String::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
     }
 
-    // string host = 2;
+    // uint32 port = 2;
 
 
-    pub fn get_host(&self) -> &str {
-        &self.host
+    pub fn get_port(&self) -> u32 {
+        self.port
     }
-    pub fn clear_host(&mut self) {
-        self.host.clear();
+    pub fn clear_port(&mut self) {
+        self.port = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_host(&mut self, v: ::std::string::String) {
-        self.host = v;
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // string username = 3;
+
+
+    pub fn get_username(&self) -> &str {
+        &self.username
+    }
+    pub fn clear_username(&mut self) {
+        self.username.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_username(&mut self, v: ::std::string::String) {
+        self.username = v;
     }
 
     // Mutable pointer to the field.
     // If field is not initialized, it is initialized with default value first.
-    pub fn mut_host(&mut self) -> &mut ::std::string::String {
-        &mut self.host
+    pub fn mut_username(&mut self) -> &mut ::std::string::String {
+        &mut self.username
     }
 
     // Take field
-    pub fn take_host(&mut self) -> ::std::string::String {
-        ::std::mem::replace(&mut self.host, ::std::string::String::new())
+    pub fn take_username(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.username, ::std::string::String::new())
+    }
+
+    // string password = 4;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+    pub fn clear_password(&mut self) {
+        self.password.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_password(&mut self, v: ::std::string::String) {
+        self.password = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_password(&mut self) -> &mut ::std::string::String {
+        &mut self.password
+    }
+
+    // Take field
+    pub fn take_password(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.password, ::std::string::String::new())
+    }
+
+    // bool forward = 5;
+
+
+    pub fn get_forward(&self) -> bool {
+        self.forward
+    }
+    pub fn clear_forward(&mut self) {
+        self.forward = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_forward(&mut self, v: bool) {
+        self.forward = v;
     }
 }
 
-impl ::protobuf::Message for HTTP2OutboundSettings {
+impl ::protobuf::Message for HTTPOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3287,10 +4054,27 @@ impl ::protobuf::Message for HTTP2OutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
                 },
                 2 => {
-                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.username)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.forward = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3304,11 +4088,20 @@ impl ::protobuf::Message for HTTP2OutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        if !self.path.is_empty() {
-            my_size += ::protobuf::rt::string_size(1, &self.path);
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
         }
-        if !self.host.is_empty() {
-            my_size += ::protobuf::rt::string_size(2, &self.host);
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.username.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.username);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.password);
+        }
+        if self.forward != false {
+            my_size += 2;
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -3316,11 +4109,20 @@ impl ::protobuf::Message for HTTP2OutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        if !self.path.is_empty() {
-            os.write_string(1, &self.path)?;
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
         }
-        if !self.host.is_empty() {
-            os.write_string(2, &self.host)?;
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.username.is_empty() {
+            os.write_string(3, &self.username)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(4, &self.password)?;
+        }
+        if self.forward != false {
+            os.write_bool(5, self.forward)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -3352,8 +4154,8 @@ impl ::protobuf::Message for HTTP2OutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> HTTP2OutboundSettings {
-        HTTP2OutboundSettings::new()
+    fn new() -> HTTPOutboundSettings {
+        HTTPOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -3361,112 +4163,185 @@ impl ::protobuf::Message for HTTP2OutboundSettings {
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "path",
-                |m: &HTTP2OutboundSettings| { &m.path },
-                |m: &mut HTTP2OutboundSettings| { &mut m.path },
+                "address",
+                |m: &HTTPOutboundSettings| { &m.address },
+                |m: &mut HTTPOutboundSettings| { &mut m.address },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &HTTPOutboundSettings| { &m.port },
+                |m: &mut HTTPOutboundSettings| { &mut m.port },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "host",
-                |m: &HTTP2OutboundSettings| { &m.host },
-                |m: &mut HTTP2OutboundSettings| { &mut m.host },
+                "username",
+                |m: &HTTPOutboundSettings| { &m.username },
+                |m: &mut HTTPOutboundSettings| { &mut m.username },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<HTTP2OutboundSettings>(
-                "HTTP2OutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "password",
+                |m: &HTTPOutboundSettings| { &m.password },
+                |m: &mut HTTPOutboundSettings| { &mut m.password },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "forward",
+                |m: &HTTPOutboundSettings| { &m.forward },
+                |m: &mut HTTPOutboundSettings| { &mut m.forward },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<HTTPOutboundSettings>(
+                "HTTPOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static HTTP2OutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<HTTP2OutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(HTTP2OutboundSettings::new)
+    fn default_instance() -> &'static HTTPOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<HTTPOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(HTTPOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for HTTP2OutboundSettings {
+impl ::protobuf::Clear for HTTPOutboundSettings {
     fn clear(&mut self) {
-        self.path.clear();
-        self.host.clear();
+        self.address.clear();
+        self.port = 0;
+        self.username.clear();
+        self.password.clear();
+        self.forward = false;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for HTTP2OutboundSettings {
+impl ::std::fmt::Debug for HTTPOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for HTTP2OutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for HTTPOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct TryAllOutboundSettings {
+pub struct ShadowsocksOutboundSettings {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
-    pub delay_base: u32,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub method: ::std::string::String,
+    pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a TryAllOutboundSettings {
-    fn default() -> &'a TryAllOutboundSettings {
-        <TryAllOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a ShadowsocksOutboundSettings {
+    fn default() -> &'a ShadowsocksOutboundSettings {
+        <ShadowsocksOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl TryAllOutboundSettings {
-    pub fn new() -> TryAllOutboundSettings {
+impl ShadowsocksOutboundSettings {
+    pub fn new() -> ShadowsocksOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string address = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
-    }
-    pub fn clear_actors(&mut self) {
-        self.actors.clear();
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
-        self.actors = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
-    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
-        &mut self.actors
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
-        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
     }
 
-    // uint32 delay_base = 2;
+    // uint32 port = 2;
 
 
-    pub fn get_delay_base(&self) -> u32 {
-        self.delay_base
+    pub fn get_port(&self) -> u32 {
+        self.port
     }
-    pub fn clear_delay_base(&mut self) {
-        self.delay_base = 0;
+    pub fn clear_port(&mut self) {
+        self.port = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_delay_base(&mut self, v: u32) {
-        self.delay_base = v;
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // string method = 3;
+
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+    pub fn clear_method(&mut self) {
+        self.method.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_method(&mut self, v: ::std::string::String) {
+        self.method = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_method(&mut self) -> &mut ::std::string::String {
+        &mut self.method
+    }
+
+    // Take field
+    pub fn take_method(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.method, ::std::string::String::new())
+    }
+
+    // string password = 4;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+    pub fn clear_password(&mut self) {
+        self.password.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_password(&mut self, v: ::std::string::String) {
+        self.password = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_password(&mut self) -> &mut ::std::string::String {
+        &mut self.password
+    }
+
+    // Take field
+    pub fn take_password(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.password, ::std::string::String::new())
     }
 }
 
-impl ::protobuf::Message for TryAllOutboundSettings {
+impl ::protobuf::Message for ShadowsocksOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3476,14 +4351,20 @@ impl ::protobuf::Message for TryAllOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
                 },
                 2 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.delay_base = tmp;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -3497,11 +4378,17 @@ impl ::protobuf::Message for TryAllOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
-        };
-        if self.delay_base != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.delay_base, ::protobuf::wire_format::WireTypeVarint);
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.method.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.method);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.password);
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -3509,11 +4396,17 @@ impl ::protobuf::Message for TryAllOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
-        };
-        if self.delay_base != 0 {
-            os.write_uint32(2, self.delay_base)?;
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.method.is_empty() {
+            os.write_string(3, &self.method)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(4, &self.password)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -3545,140 +4438,218 @@ impl ::protobuf::Message for TryAllOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> TryAllOutboundSettings {
-        TryAllOutboundSettings::new()
+    fn new() -> ShadowsocksOutboundSettings {
+        ShadowsocksOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "actors",
-                |m: &TryAllOutboundSettings| { &m.actors },
-                |m: &mut TryAllOutboundSettings| { &mut m.actors },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "address",
+                |m: &ShadowsocksOutboundSettings| { &m.address },
+                |m: &mut ShadowsocksOutboundSettings| { &mut m.address },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "delay_base",
-                |m: &TryAllOutboundSettings| { &m.delay_base },
-                |m: &mut TryAllOutboundSettings| { &mut m.delay_base },
+                "port",
+                |m: &ShadowsocksOutboundSettings| { &m.port },
+                |m: &mut ShadowsocksOutboundSettings| { &mut m.port },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TryAllOutboundSettings>(
-                "TryAllOutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "method",
+                |m: &ShadowsocksOutboundSettings| { &m.method },
+                |m: &mut ShadowsocksOutboundSettings| { &mut m.method },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "password",
+                |m: &ShadowsocksOutboundSettings| { &m.password },
+                |m: &mut ShadowsocksOutboundSettings| { &mut m.password },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ShadowsocksOutboundSettings>(
+                "ShadowsocksOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static TryAllOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<TryAllOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(TryAllOutboundSettings::new)
+    fn default_instance() -> &'static ShadowsocksOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ShadowsocksOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ShadowsocksOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for TryAllOutboundSettings {
+impl ::protobuf::Clear for ShadowsocksOutboundSettings {
     fn clear(&mut self) {
-        self.actors.clear();
-        self.delay_base = 0;
+        self.address.clear();
+        self.port = 0;
+        self.method.clear();
+        self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for TryAllOutboundSettings {
+impl ::std::fmt::Debug for ShadowsocksOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for TryAllOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for ShadowsocksOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct RandomOutboundSettings {
+pub struct TrojanOutboundSettings {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub password: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a RandomOutboundSettings {
-    fn default() -> &'a RandomOutboundSettings {
-        <RandomOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a TrojanOutboundSettings {
+    fn default() -> &'a TrojanOutboundSettings {
+        <TrojanOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl RandomOutboundSettings {
-    pub fn new() -> RandomOutboundSettings {
+impl TrojanOutboundSettings {
+    pub fn new() -> TrojanOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string address = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
-    pub fn clear_actors(&mut self) {
-        self.actors.clear();
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
-        self.actors = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
-    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
-        &mut self.actors
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
-        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
     }
-}
 
-impl ::protobuf::Message for RandomOutboundSettings {
-    fn is_initialized(&self) -> bool {
-        true
-    }
+    // uint32 port = 2;
 
-    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        while !is.eof()? {
-            let (field_number, wire_type) = is.read_tag_unpack()?;
-            match field_number {
-                1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
-                },
-                _ => {
-                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
-                },
-            };
-        }
-        ::std::result::Result::Ok(())
-    }
 
-    // Compute sizes of nested messages
-    #[allow(unused_variables)]
-    fn compute_size(&self) -> u32 {
-        let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
-        };
-        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
-        self.cached_size.set(my_size);
-        my_size
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+    pub fn clear_port(&mut self) {
+        self.port = 0;
     }
 
-    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
-        };
+    // Param is passed by value, moved
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // string password = 3;
+
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+    pub fn clear_password(&mut self) {
+        self.password.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_password(&mut self, v: ::std::string::String) {
+        self.password = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_password(&mut self) -> &mut ::std::string::String {
+        &mut self.password
+    }
+
+    // Take field
+    pub fn take_password(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.password, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for TrojanOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.password)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.password.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.password);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.password.is_empty() {
+            os.write_string(3, &self.password)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -3709,99 +4680,182 @@ impl ::protobuf::Message for RandomOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> RandomOutboundSettings {
-        RandomOutboundSettings::new()
+    fn new() -> TrojanOutboundSettings {
+        TrojanOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "actors",
-                |m: &RandomOutboundSettings| { &m.actors },
-                |m: &mut RandomOutboundSettings| { &mut m.actors },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "address",
+                |m: &TrojanOutboundSettings| { &m.address },
+                |m: &mut TrojanOutboundSettings| { &mut m.address },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<RandomOutboundSettings>(
-                "RandomOutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &TrojanOutboundSettings| { &m.port },
+                |m: &mut TrojanOutboundSettings| { &mut m.port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "password",
+                |m: &TrojanOutboundSettings| { &m.password },
+                |m: &mut TrojanOutboundSettings| { &mut m.password },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TrojanOutboundSettings>(
+                "TrojanOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static RandomOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<RandomOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(RandomOutboundSettings::new)
+    fn default_instance() -> &'static TrojanOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TrojanOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TrojanOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for RandomOutboundSettings {
+impl ::protobuf::Clear for TrojanOutboundSettings {
     fn clear(&mut self) {
-        self.actors.clear();
+        self.address.clear();
+        self.port = 0;
+        self.password.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for RandomOutboundSettings {
+impl ::std::fmt::Debug for TrojanOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for RandomOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for TrojanOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct ChainOutboundSettings {
+pub struct VMessOutboundSettings {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub uuid: ::std::string::String,
+    pub security: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a ChainOutboundSettings {
-    fn default() -> &'a ChainOutboundSettings {
-        <ChainOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a VMessOutboundSettings {
+    fn default() -> &'a VMessOutboundSettings {
+        <VMessOutboundSettings as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ChainOutboundSettings {
-    pub fn new() -> ChainOutboundSettings {
+impl VMessOutboundSettings {
+    pub fn new() -> VMessOutboundSettings {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string address = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_address(&self) -> &str {
+        &self.address
     }
-    pub fn clear_actors(&mut self) {
-        self.actors.clear();
+    pub fn clear_address(&mut self) {
+        self.address.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
-        self.actors = v;
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
     }
 
     // Mutable pointer to the field.
-    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
-        &mut self.actors
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
     }
 
     // Take field
-    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
-        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+    pub fn clear_port(&mut self) {
+        self.port = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // string uuid = 3;
+
+
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+    pub fn clear_uuid(&mut self) {
+        self.uuid.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_uuid(&mut self, v: ::std::string::String) {
+        self.uuid = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_uuid(&mut self) -> &mut ::std::string::String {
+        &mut self.uuid
+    }
+
+    // Take field
+    pub fn take_uuid(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.uuid, ::std::string::String::new())
+    }
+
+    // string security = 4;
+
+
+    pub fn get_security(&self) -> &str {
+        &self.security
+    }
+    pub fn clear_security(&mut self) {
+        self.security.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_security(&mut self, v: ::std::string::String) {
+        self.security = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_security(&mut self) -> &mut ::std::string::String {
+        &mut self.security
+    }
+
+    // Take field
+    pub fn take_security(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.security, ::std::string::String::new())
     }
 }
 
-impl ::protobuf::Message for ChainOutboundSettings {
+impl ::protobuf::Message for VMessOutboundSettings {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -3811,22 +4865,4375 @@ impl ::protobuf::Message for ChainOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
                 },
-                _ => {
-                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
                 },
-            };
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.uuid)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.security)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.uuid.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.uuid);
+        }
+        if !self.security.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.security);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.uuid.is_empty() {
+            os.write_string(3, &self.uuid)?;
+        }
+        if !self.security.is_empty() {
+            os.write_string(4, &self.security)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> VMessOutboundSettings {
+        VMessOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "address",
+                |m: &VMessOutboundSettings| { &m.address },
+                |m: &mut VMessOutboundSettings| { &mut m.address },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &VMessOutboundSettings| { &m.port },
+                |m: &mut VMessOutboundSettings| { &mut m.port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "uuid",
+                |m: &VMessOutboundSettings| { &m.uuid },
+                |m: &mut VMessOutboundSettings| { &mut m.uuid },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "security",
+                |m: &VMessOutboundSettings| { &m.security },
+                |m: &mut VMessOutboundSettings| { &mut m.security },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<VMessOutboundSettings>(
+                "VMessOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static VMessOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<VMessOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(VMessOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for VMessOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.uuid.clear();
+        self.security.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for VMessOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for VMessOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct VLessOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub uuid: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a VLessOutboundSettings {
+    fn default() -> &'a VLessOutboundSettings {
+        <VLessOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl VLessOutboundSettings {
+    pub fn new() -> VLessOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+    pub fn clear_address(&mut self) {
+        self.address.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
+    }
+
+    // Take field
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+    pub fn clear_port(&mut self) {
+        self.port = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // string uuid = 3;
+
+
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+    pub fn clear_uuid(&mut self) {
+        self.uuid.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_uuid(&mut self, v: ::std::string::String) {
+        self.uuid = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_uuid(&mut self) -> &mut ::std::string::String {
+        &mut self.uuid
+    }
+
+    // Take field
+    pub fn take_uuid(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.uuid, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for VLessOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.uuid)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.uuid.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.uuid);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.uuid.is_empty() {
+            os.write_string(3, &self.uuid)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> VLessOutboundSettings {
+        VLessOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "address",
+                |m: &VLessOutboundSettings| { &m.address },
+                |m: &mut VLessOutboundSettings| { &mut m.address },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &VLessOutboundSettings| { &m.port },
+                |m: &mut VLessOutboundSettings| { &mut m.port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "uuid",
+                |m: &VLessOutboundSettings| { &m.uuid },
+                |m: &mut VLessOutboundSettings| { &mut m.uuid },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<VLessOutboundSettings>(
+                "VLessOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static VLessOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<VLessOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(VLessOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for VLessOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.uuid.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for VLessOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for VLessOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct TlsOutboundSettings {
+    // message fields
+    pub server_name: ::std::string::String,
+    pub alpn: ::protobuf::RepeatedField<::std::string::String>,
+    pub insecure: bool,
+    pub pinned_spki: ::protobuf::RepeatedField<::std::string::String>,
+    pub session_cache_size: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a TlsOutboundSettings {
+    fn default() -> &'a TlsOutboundSettings {
+        <TlsOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TlsOutboundSettings {
+    pub fn new() -> TlsOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string server_name = 1;
+
+
+    pub fn get_server_name(&self) -> &str {
+        &self.server_name
+    }
+    pub fn clear_server_name(&mut self) {
+        self.server_name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_server_name(&mut self, v: ::std::string::String) {
+        self.server_name = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_server_name(&mut self) -> &mut ::std::string::String {
+        &mut self.server_name
+    }
+
+    // Take field
+    pub fn take_server_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.server_name, ::std::string::String::new())
+    }
+
+    // repeated string alpn = 2;
+
+
+    pub fn get_alpn(&self) -> &[::std::string::String] {
+        &self.alpn
+    }
+    pub fn clear_alpn(&mut self) {
+        self.alpn.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_alpn(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.alpn = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_alpn(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.alpn
+    }
+
+    // Take field
+    pub fn take_alpn(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.alpn, ::protobuf::RepeatedField::new())
+    }
+
+    // bool insecure = 3;
+
+
+    pub fn get_insecure(&self) -> bool {
+        self.insecure
+    }
+    pub fn clear_insecure(&mut self) {
+        self.insecure = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_insecure(&mut self, v: bool) {
+        self.insecure = v;
+    }
+
+    // repeated string pinned_spki = 4;
+
+
+    pub fn get_pinned_spki(&self) -> &[::std::string::String] {
+        &self.pinned_spki
+    }
+    pub fn clear_pinned_spki(&mut self) {
+        self.pinned_spki.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_pinned_spki(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.pinned_spki = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_pinned_spki(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.pinned_spki
+    }
+
+    // Take field
+    pub fn take_pinned_spki(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.pinned_spki, ::protobuf::RepeatedField::new())
+    }
+
+    // uint32 session_cache_size = 5;
+
+
+    pub fn get_session_cache_size(&self) -> u32 {
+        self.session_cache_size
+    }
+    pub fn clear_session_cache_size(&mut self) {
+        self.session_cache_size = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_session_cache_size(&mut self, v: u32) {
+        self.session_cache_size = v;
+    }
+}
+
+impl ::protobuf::Message for TlsOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.alpn)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.insecure = tmp;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.pinned_spki)?;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.session_cache_size = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.server_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.server_name);
+        }
+        for value in &self.alpn {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        if self.insecure != false {
+            my_size += 2;
+        }
+        for value in &self.pinned_spki {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        if self.session_cache_size != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.session_cache_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.server_name.is_empty() {
+            os.write_string(1, &self.server_name)?;
+        }
+        for v in &self.alpn {
+            os.write_string(2, &v)?;
+        };
+        if self.insecure != false {
+            os.write_bool(3, self.insecure)?;
+        }
+        for v in &self.pinned_spki {
+            os.write_string(4, &v)?;
+        };
+        if self.session_cache_size != 0 {
+            os.write_uint32(5, self.session_cache_size)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TlsOutboundSettings {
+        TlsOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "server_name",
+                |m: &TlsOutboundSettings| { &m.server_name },
+                |m: &mut TlsOutboundSettings| { &mut m.server_name },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "alpn",
+                |m: &TlsOutboundSettings| { &m.alpn },
+                |m: &mut TlsOutboundSettings| { &mut m.alpn },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "insecure",
+                |m: &TlsOutboundSettings| { &m.insecure },
+                |m: &mut TlsOutboundSettings| { &mut m.insecure },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "pinned_spki",
+                |m: &TlsOutboundSettings| { &m.pinned_spki },
+                |m: &mut TlsOutboundSettings| { &mut m.pinned_spki },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "session_cache_size",
+                |m: &TlsOutboundSettings| { &m.session_cache_size },
+                |m: &mut TlsOutboundSettings| { &mut m.session_cache_size },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TlsOutboundSettings>(
+                "TlsOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static TlsOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TlsOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TlsOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for TlsOutboundSettings {
+    fn clear(&mut self) {
+        self.server_name.clear();
+        self.alpn.clear();
+        self.insecure = false;
+        self.pinned_spki.clear();
+        self.session_cache_size = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for TlsOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TlsOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct WebSocketOutboundSettings {
+    // message fields
+    pub path: ::std::string::String,
+    pub host: ::std::string::String,
+    pub max_early_data: u32,
+    pub early_data_header_name: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a WebSocketOutboundSettings {
+    fn default() -> &'a WebSocketOutboundSettings {
+        <WebSocketOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl WebSocketOutboundSettings {
+    pub fn new() -> WebSocketOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string path = 1;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    pub fn clear_path(&mut self) {
+        self.path.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_path(&mut self, v: ::std::string::String) {
+        self.path = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_path(&mut self) -> &mut ::std::string::String {
+        &mut self.path
+    }
+
+    // Take field
+    pub fn take_path(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.path, ::std::string::String::new())
+    }
+
+    // string host = 2;
+
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+    pub fn clear_host(&mut self) {
+        self.host.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_host(&mut self, v: ::std::string::String) {
+        self.host = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_host(&mut self) -> &mut ::std::string::String {
+        &mut self.host
+    }
+
+    // Take field
+    pub fn take_host(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.host, ::std::string::String::new())
+    }
+
+    // uint32 max_early_data = 3;
+
+
+    pub fn get_max_early_data(&self) -> u32 {
+        self.max_early_data
+    }
+    pub fn clear_max_early_data(&mut self) {
+        self.max_early_data = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_early_data(&mut self, v: u32) {
+        self.max_early_data = v;
+    }
+
+    // string early_data_header_name = 4;
+
+
+    pub fn get_early_data_header_name(&self) -> &str {
+        &self.early_data_header_name
+    }
+    pub fn clear_early_data_header_name(&mut self) {
+        self.early_data_header_name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_early_data_header_name(&mut self, v: ::std::string::String) {
+        self.early_data_header_name = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_early_data_header_name(&mut self) -> &mut ::std::string::String {
+        &mut self.early_data_header_name
+    }
+
+    // Take field
+    pub fn take_early_data_header_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.early_data_header_name, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for WebSocketOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_early_data = tmp;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.early_data_header_name)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.path);
+        }
+        if !self.host.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.host);
+        }
+        if self.max_early_data != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.max_early_data, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.early_data_header_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.early_data_header_name);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.path.is_empty() {
+            os.write_string(1, &self.path)?;
+        }
+        if !self.host.is_empty() {
+            os.write_string(2, &self.host)?;
+        }
+        if self.max_early_data != 0 {
+            os.write_uint32(3, self.max_early_data)?;
+        }
+        if !self.early_data_header_name.is_empty() {
+            os.write_string(4, &self.early_data_header_name)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> WebSocketOutboundSettings {
+        WebSocketOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "path",
+                |m: &WebSocketOutboundSettings| { &m.path },
+                |m: &mut WebSocketOutboundSettings| { &mut m.path },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "host",
+                |m: &WebSocketOutboundSettings| { &m.host },
+                |m: &mut WebSocketOutboundSettings| { &mut m.host },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_early_data",
+                |m: &WebSocketOutboundSettings| { &m.max_early_data },
+                |m: &mut WebSocketOutboundSettings| { &mut m.max_early_data },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "early_data_header_name",
+                |m: &WebSocketOutboundSettings| { &m.early_data_header_name },
+                |m: &mut WebSocketOutboundSettings| { &mut m.early_data_header_name },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<WebSocketOutboundSettings>(
+                "WebSocketOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static WebSocketOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<WebSocketOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(WebSocketOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for WebSocketOutboundSettings {
+    fn clear(&mut self) {
+        self.path.clear();
+        self.host.clear();
+        self.max_early_data = 0;
+        self.early_data_header_name.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for WebSocketOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for WebSocketOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct HTTP2OutboundSettings {
+    // message fields
+    pub path: ::std::string::String,
+    pub host: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a HTTP2OutboundSettings {
+    fn default() -> &'a HTTP2OutboundSettings {
+        <HTTP2OutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HTTP2OutboundSettings {
+    pub fn new() -> HTTP2OutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string path = 1;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    pub fn clear_path(&mut self) {
+        self.path.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_path(&mut self, v: ::std::string::String) {
+        self.path = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_path(&mut self) -> &mut ::std::string::String {
+        &mut self.path
+    }
+
+    // Take field
+    pub fn take_path(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.path, ::std::string::String::new())
+    }
+
+    // string host = 2;
+
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+    pub fn clear_host(&mut self) {
+        self.host.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_host(&mut self, v: ::std::string::String) {
+        self.host = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_host(&mut self) -> &mut ::std::string::String {
+        &mut self.host
+    }
+
+    // Take field
+    pub fn take_host(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.host, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for HTTP2OutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.path);
+        }
+        if !self.host.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.host);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.path.is_empty() {
+            os.write_string(1, &self.path)?;
+        }
+        if !self.host.is_empty() {
+            os.write_string(2, &self.host)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> HTTP2OutboundSettings {
+        HTTP2OutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "path",
+                |m: &HTTP2OutboundSettings| { &m.path },
+                |m: &mut HTTP2OutboundSettings| { &mut m.path },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "host",
+                |m: &HTTP2OutboundSettings| { &m.host },
+                |m: &mut HTTP2OutboundSettings| { &mut m.host },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<HTTP2OutboundSettings>(
+                "HTTP2OutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static HTTP2OutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<HTTP2OutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(HTTP2OutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for HTTP2OutboundSettings {
+    fn clear(&mut self) {
+        self.path.clear();
+        self.host.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for HTTP2OutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HTTP2OutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct MuxOutboundSettings {
+    // message fields
+    pub max_streams: u32,
+    pub max_connections: u32,
+    pub receive_window: u32,
+    pub keepalive_interval: u32,
+    pub keepalive_timeout: u32,
+    pub idle_timeout: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a MuxOutboundSettings {
+    fn default() -> &'a MuxOutboundSettings {
+        <MuxOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl MuxOutboundSettings {
+    pub fn new() -> MuxOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // uint32 max_streams = 1;
+
+
+    pub fn get_max_streams(&self) -> u32 {
+        self.max_streams
+    }
+    pub fn clear_max_streams(&mut self) {
+        self.max_streams = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_streams(&mut self, v: u32) {
+        self.max_streams = v;
+    }
+
+    // uint32 max_connections = 2;
+
+
+    pub fn get_max_connections(&self) -> u32 {
+        self.max_connections
+    }
+    pub fn clear_max_connections(&mut self) {
+        self.max_connections = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_connections(&mut self, v: u32) {
+        self.max_connections = v;
+    }
+
+    // uint32 receive_window = 3;
+
+
+    pub fn get_receive_window(&self) -> u32 {
+        self.receive_window
+    }
+    pub fn clear_receive_window(&mut self) {
+        self.receive_window = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_receive_window(&mut self, v: u32) {
+        self.receive_window = v;
+    }
+
+    // uint32 keepalive_interval = 4;
+
+
+    pub fn get_keepalive_interval(&self) -> u32 {
+        self.keepalive_interval
+    }
+    pub fn clear_keepalive_interval(&mut self) {
+        self.keepalive_interval = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_keepalive_interval(&mut self, v: u32) {
+        self.keepalive_interval = v;
+    }
+
+    // uint32 keepalive_timeout = 5;
+
+
+    pub fn get_keepalive_timeout(&self) -> u32 {
+        self.keepalive_timeout
+    }
+    pub fn clear_keepalive_timeout(&mut self) {
+        self.keepalive_timeout = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_keepalive_timeout(&mut self, v: u32) {
+        self.keepalive_timeout = v;
+    }
+
+    // uint32 idle_timeout = 6;
+
+
+    pub fn get_idle_timeout(&self) -> u32 {
+        self.idle_timeout
+    }
+    pub fn clear_idle_timeout(&mut self) {
+        self.idle_timeout = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_idle_timeout(&mut self, v: u32) {
+        self.idle_timeout = v;
+    }
+}
+
+impl ::protobuf::Message for MuxOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_streams = tmp;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_connections = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.receive_window = tmp;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.keepalive_interval = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.keepalive_timeout = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.idle_timeout = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.max_streams != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.max_streams, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.max_connections != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.max_connections, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.receive_window != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.receive_window, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.keepalive_interval != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.keepalive_interval, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.keepalive_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.keepalive_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.idle_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.idle_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.max_streams != 0 {
+            os.write_uint32(1, self.max_streams)?;
+        }
+        if self.max_connections != 0 {
+            os.write_uint32(2, self.max_connections)?;
+        }
+        if self.receive_window != 0 {
+            os.write_uint32(3, self.receive_window)?;
+        }
+        if self.keepalive_interval != 0 {
+            os.write_uint32(4, self.keepalive_interval)?;
+        }
+        if self.keepalive_timeout != 0 {
+            os.write_uint32(5, self.keepalive_timeout)?;
+        }
+        if self.idle_timeout != 0 {
+            os.write_uint32(6, self.idle_timeout)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> MuxOutboundSettings {
+        MuxOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_streams",
+                |m: &MuxOutboundSettings| { &m.max_streams },
+                |m: &mut MuxOutboundSettings| { &mut m.max_streams },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_connections",
+                |m: &MuxOutboundSettings| { &m.max_connections },
+                |m: &mut MuxOutboundSettings| { &mut m.max_connections },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "receive_window",
+                |m: &MuxOutboundSettings| { &m.receive_window },
+                |m: &mut MuxOutboundSettings| { &mut m.receive_window },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "keepalive_interval",
+                |m: &MuxOutboundSettings| { &m.keepalive_interval },
+                |m: &mut MuxOutboundSettings| { &mut m.keepalive_interval },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "keepalive_timeout",
+                |m: &MuxOutboundSettings| { &m.keepalive_timeout },
+                |m: &mut MuxOutboundSettings| { &mut m.keepalive_timeout },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "idle_timeout",
+                |m: &MuxOutboundSettings| { &m.idle_timeout },
+                |m: &mut MuxOutboundSettings| { &mut m.idle_timeout },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<MuxOutboundSettings>(
+                "MuxOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static MuxOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<MuxOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(MuxOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for MuxOutboundSettings {
+    fn clear(&mut self) {
+        self.max_streams = 0;
+        self.max_connections = 0;
+        self.receive_window = 0;
+        self.keepalive_interval = 0;
+        self.keepalive_timeout = 0;
+        self.idle_timeout = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for MuxOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for MuxOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct QuicOutboundSettings {
+    // message fields
+    pub address: ::std::string::String,
+    pub port: u32,
+    pub server_name: ::std::string::String,
+    pub alpn: ::protobuf::RepeatedField<::std::string::String>,
+    pub certificates: ::protobuf::RepeatedField<::std::string::String>,
+    pub zero_rtt: bool,
+    pub initial_window: u64,
+    pub minimum_window: u64,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a QuicOutboundSettings {
+    fn default() -> &'a QuicOutboundSettings {
+        <QuicOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl QuicOutboundSettings {
+    pub fn new() -> QuicOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string address = 1;
+
+
+    pub fn get_address(&self) -> &str {
+        &self.address
+    }
+    pub fn clear_address(&mut self) {
+        self.address.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_address(&mut self, v: ::std::string::String) {
+        self.address = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_address(&mut self) -> &mut ::std::string::String {
+        &mut self.address
+    }
+
+    // Take field
+    pub fn take_address(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.address, ::std::string::String::new())
+    }
+
+    // uint32 port = 2;
+
+
+    pub fn get_port(&self) -> u32 {
+        self.port
+    }
+    pub fn clear_port(&mut self) {
+        self.port = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_port(&mut self, v: u32) {
+        self.port = v;
+    }
+
+    // string server_name = 3;
+
+
+    pub fn get_server_name(&self) -> &str {
+        &self.server_name
+    }
+    pub fn clear_server_name(&mut self) {
+        self.server_name.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_server_name(&mut self, v: ::std::string::String) {
+        self.server_name = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_server_name(&mut self) -> &mut ::std::string::String {
+        &mut self.server_name
+    }
+
+    // Take field
+    pub fn take_server_name(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.server_name, ::std::string::String::new())
+    }
+
+    // repeated string alpn = 4;
+
+
+    pub fn get_alpn(&self) -> &[::std::string::String] {
+        &self.alpn
+    }
+    pub fn clear_alpn(&mut self) {
+        self.alpn.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_alpn(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.alpn = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_alpn(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.alpn
+    }
+
+    // Take field
+    pub fn take_alpn(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.alpn, ::protobuf::RepeatedField::new())
+    }
+
+    // repeated string certificates = 5;
+
+
+    pub fn get_certificates(&self) -> &[::std::string::String] {
+        &self.certificates
+    }
+    pub fn clear_certificates(&mut self) {
+        self.certificates.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_certificates(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.certificates = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_certificates(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.certificates
+    }
+
+    // Take field
+    pub fn take_certificates(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.certificates, ::protobuf::RepeatedField::new())
+    }
+
+    // bool zero_rtt = 6;
+
+
+    pub fn get_zero_rtt(&self) -> bool {
+        self.zero_rtt
+    }
+    pub fn clear_zero_rtt(&mut self) {
+        self.zero_rtt = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_zero_rtt(&mut self, v: bool) {
+        self.zero_rtt = v;
+    }
+
+    // uint64 initial_window = 7;
+
+
+    pub fn get_initial_window(&self) -> u64 {
+        self.initial_window
+    }
+    pub fn clear_initial_window(&mut self) {
+        self.initial_window = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_initial_window(&mut self, v: u64) {
+        self.initial_window = v;
+    }
+
+    // uint64 minimum_window = 8;
+
+
+    pub fn get_minimum_window(&self) -> u64 {
+        self.minimum_window
+    }
+    pub fn clear_minimum_window(&mut self) {
+        self.minimum_window = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_minimum_window(&mut self, v: u64) {
+        self.minimum_window = v;
+    }
+}
+
+impl ::protobuf::Message for QuicOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.address)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.port = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.server_name)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.alpn)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.certificates)?;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.zero_rtt = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.initial_window = tmp;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.minimum_window = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.address.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.address);
+        }
+        if self.port != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.port, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.server_name.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.server_name);
+        }
+        for value in &self.alpn {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        for value in &self.certificates {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
+        if self.zero_rtt != false {
+            my_size += 2;
+        }
+        if self.initial_window != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.initial_window, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.minimum_window != 0 {
+            my_size += ::protobuf::rt::value_size(8, self.minimum_window, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.address.is_empty() {
+            os.write_string(1, &self.address)?;
+        }
+        if self.port != 0 {
+            os.write_uint32(2, self.port)?;
+        }
+        if !self.server_name.is_empty() {
+            os.write_string(3, &self.server_name)?;
+        }
+        for v in &self.alpn {
+            os.write_string(4, &v)?;
+        };
+        for v in &self.certificates {
+            os.write_string(5, &v)?;
+        };
+        if self.zero_rtt != false {
+            os.write_bool(6, self.zero_rtt)?;
+        }
+        if self.initial_window != 0 {
+            os.write_uint64(7, self.initial_window)?;
+        }
+        if self.minimum_window != 0 {
+            os.write_uint64(8, self.minimum_window)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> QuicOutboundSettings {
+        QuicOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "address",
+                |m: &QuicOutboundSettings| { &m.address },
+                |m: &mut QuicOutboundSettings| { &mut m.address },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "port",
+                |m: &QuicOutboundSettings| { &m.port },
+                |m: &mut QuicOutboundSettings| { &mut m.port },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "server_name",
+                |m: &QuicOutboundSettings| { &m.server_name },
+                |m: &mut QuicOutboundSettings| { &mut m.server_name },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "alpn",
+                |m: &QuicOutboundSettings| { &m.alpn },
+                |m: &mut QuicOutboundSettings| { &mut m.alpn },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "certificates",
+                |m: &QuicOutboundSettings| { &m.certificates },
+                |m: &mut QuicOutboundSettings| { &mut m.certificates },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "zero_rtt",
+                |m: &QuicOutboundSettings| { &m.zero_rtt },
+                |m: &mut QuicOutboundSettings| { &mut m.zero_rtt },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "initial_window",
+                |m: &QuicOutboundSettings| { &m.initial_window },
+                |m: &mut QuicOutboundSettings| { &mut m.initial_window },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "minimum_window",
+                |m: &QuicOutboundSettings| { &m.minimum_window },
+                |m: &mut QuicOutboundSettings| { &mut m.minimum_window },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<QuicOutboundSettings>(
+                "QuicOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static QuicOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<QuicOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(QuicOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for QuicOutboundSettings {
+    fn clear(&mut self) {
+        self.address.clear();
+        self.port = 0;
+        self.server_name.clear();
+        self.alpn.clear();
+        self.certificates.clear();
+        self.zero_rtt = false;
+        self.initial_window = 0;
+        self.minimum_window = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for QuicOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for QuicOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct RejectOutboundSettings {
+    // message fields
+    pub mode: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a RejectOutboundSettings {
+    fn default() -> &'a RejectOutboundSettings {
+        <RejectOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RejectOutboundSettings {
+    pub fn new() -> RejectOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string mode = 1;
+
+
+    pub fn get_mode(&self) -> &str {
+        &self.mode
+    }
+    pub fn clear_mode(&mut self) {
+        self.mode.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_mode(&mut self, v: ::std::string::String) {
+        self.mode = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_mode(&mut self) -> &mut ::std::string::String {
+        &mut self.mode
+    }
+
+    // Take field
+    pub fn take_mode(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.mode, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for RejectOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.mode)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.mode.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.mode);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.mode.is_empty() {
+            os.write_string(1, &self.mode)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> RejectOutboundSettings {
+        RejectOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "mode",
+                |m: &RejectOutboundSettings| { &m.mode },
+                |m: &mut RejectOutboundSettings| { &mut m.mode },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<RejectOutboundSettings>(
+                "RejectOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static RejectOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RejectOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RejectOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for RejectOutboundSettings {
+    fn clear(&mut self) {
+        self.mode.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for RejectOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RejectOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct DNSOutboundSettings {
+    // message fields
+    pub fake_ip: bool,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a DNSOutboundSettings {
+    fn default() -> &'a DNSOutboundSettings {
+        <DNSOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DNSOutboundSettings {
+    pub fn new() -> DNSOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // bool fake_ip = 1;
+
+
+    pub fn get_fake_ip(&self) -> bool {
+        self.fake_ip
+    }
+    pub fn clear_fake_ip(&mut self) {
+        self.fake_ip = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_fake_ip(&mut self, v: bool) {
+        self.fake_ip = v;
+    }
+}
+
+impl ::protobuf::Message for DNSOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.fake_ip = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.fake_ip != false {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.fake_ip != false {
+            os.write_bool(1, self.fake_ip)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> DNSOutboundSettings {
+        DNSOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "fake_ip",
+                |m: &DNSOutboundSettings| { &m.fake_ip },
+                |m: &mut DNSOutboundSettings| { &mut m.fake_ip },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<DNSOutboundSettings>(
+                "DNSOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static DNSOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<DNSOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(DNSOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for DNSOutboundSettings {
+    fn clear(&mut self) {
+        self.fake_ip = false;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for DNSOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DNSOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct ObfsOutboundSettings {
+    // message fields
+    pub method: ::std::string::String,
+    pub host: ::std::string::String,
+    pub path: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ObfsOutboundSettings {
+    fn default() -> &'a ObfsOutboundSettings {
+        <ObfsOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ObfsOutboundSettings {
+    pub fn new() -> ObfsOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string method = 1;
+
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+    pub fn clear_method(&mut self) {
+        self.method.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_method(&mut self, v: ::std::string::String) {
+        self.method = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_method(&mut self) -> &mut ::std::string::String {
+        &mut self.method
+    }
+
+    // Take field
+    pub fn take_method(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.method, ::std::string::String::new())
+    }
+
+    // string host = 2;
+
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+    pub fn clear_host(&mut self) {
+        self.host.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_host(&mut self, v: ::std::string::String) {
+        self.host = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_host(&mut self) -> &mut ::std::string::String {
+        &mut self.host
+    }
+
+    // Take field
+    pub fn take_host(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.host, ::std::string::String::new())
+    }
+
+    // string path = 3;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    pub fn clear_path(&mut self) {
+        self.path.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_path(&mut self, v: ::std::string::String) {
+        self.path = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_path(&mut self) -> &mut ::std::string::String {
+        &mut self.path
+    }
+
+    // Take field
+    pub fn take_path(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.path, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for ObfsOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.method)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.host)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.method.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.method);
+        }
+        if !self.host.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.host);
+        }
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.path);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.method.is_empty() {
+            os.write_string(1, &self.method)?;
+        }
+        if !self.host.is_empty() {
+            os.write_string(2, &self.host)?;
+        }
+        if !self.path.is_empty() {
+            os.write_string(3, &self.path)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ObfsOutboundSettings {
+        ObfsOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "method",
+                |m: &ObfsOutboundSettings| { &m.method },
+                |m: &mut ObfsOutboundSettings| { &mut m.method },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "host",
+                |m: &ObfsOutboundSettings| { &m.host },
+                |m: &mut ObfsOutboundSettings| { &mut m.host },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "path",
+                |m: &ObfsOutboundSettings| { &m.path },
+                |m: &mut ObfsOutboundSettings| { &mut m.path },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ObfsOutboundSettings>(
+                "ObfsOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static ObfsOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ObfsOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ObfsOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for ObfsOutboundSettings {
+    fn clear(&mut self) {
+        self.method.clear();
+        self.host.clear();
+        self.path.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for ObfsOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ObfsOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct LimitOutboundSettings {
+    // message fields
+    pub rate: u64,
+    pub global_rate: u64,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a LimitOutboundSettings {
+    fn default() -> &'a LimitOutboundSettings {
+        <LimitOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl LimitOutboundSettings {
+    pub fn new() -> LimitOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // uint64 rate = 1;
+
+
+    pub fn get_rate(&self) -> u64 {
+        self.rate
+    }
+    pub fn clear_rate(&mut self) {
+        self.rate = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_rate(&mut self, v: u64) {
+        self.rate = v;
+    }
+
+    // uint64 global_rate = 2;
+
+
+    pub fn get_global_rate(&self) -> u64 {
+        self.global_rate
+    }
+    pub fn clear_global_rate(&mut self) {
+        self.global_rate = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_global_rate(&mut self, v: u64) {
+        self.global_rate = v;
+    }
+}
+
+impl ::protobuf::Message for LimitOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.rate = tmp;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.global_rate = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.rate != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.rate, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.global_rate != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.global_rate, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if self.rate != 0 {
+            os.write_uint64(1, self.rate)?;
+        }
+        if self.global_rate != 0 {
+            os.write_uint64(2, self.global_rate)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> LimitOutboundSettings {
+        LimitOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "rate",
+                |m: &LimitOutboundSettings| { &m.rate },
+                |m: &mut LimitOutboundSettings| { &mut m.rate },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "global_rate",
+                |m: &LimitOutboundSettings| { &m.global_rate },
+                |m: &mut LimitOutboundSettings| { &mut m.global_rate },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<LimitOutboundSettings>(
+                "LimitOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static LimitOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<LimitOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(LimitOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for LimitOutboundSettings {
+    fn clear(&mut self) {
+        self.rate = 0;
+        self.global_rate = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for LimitOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for LimitOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct RetryOutboundSettings {
+    // message fields
+    pub actor: ::std::string::String,
+    pub attempts: u32,
+    pub delay_base: u32,
+    pub deadline: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a RetryOutboundSettings {
+    fn default() -> &'a RetryOutboundSettings {
+        <RetryOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RetryOutboundSettings {
+    pub fn new() -> RetryOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string actor = 1;
+
+
+    pub fn get_actor(&self) -> &str {
+        &self.actor
+    }
+    pub fn clear_actor(&mut self) {
+        self.actor.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_actor(&mut self, v: ::std::string::String) {
+        self.actor = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_actor(&mut self) -> &mut ::std::string::String {
+        &mut self.actor
+    }
+
+    // Take field
+    pub fn take_actor(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.actor, ::std::string::String::new())
+    }
+
+    // uint32 attempts = 2;
+
+
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+    pub fn clear_attempts(&mut self) {
+        self.attempts = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_attempts(&mut self, v: u32) {
+        self.attempts = v;
+    }
+
+    // uint32 delay_base = 3;
+
+
+    pub fn get_delay_base(&self) -> u32 {
+        self.delay_base
+    }
+    pub fn clear_delay_base(&mut self) {
+        self.delay_base = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_delay_base(&mut self, v: u32) {
+        self.delay_base = v;
+    }
+
+    // uint32 deadline = 4;
+
+
+    pub fn get_deadline(&self) -> u32 {
+        self.deadline
+    }
+    pub fn clear_deadline(&mut self) {
+        self.deadline = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_deadline(&mut self, v: u32) {
+        self.deadline = v;
+    }
+}
+
+impl ::protobuf::Message for RetryOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.actor)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.attempts = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.delay_base = tmp;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.deadline = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.actor.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.actor);
+        }
+        if self.attempts != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.attempts, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.delay_base != 0 {
+            my_size += ::protobuf::rt::value_size(3, self.delay_base, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.deadline != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.deadline, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.actor.is_empty() {
+            os.write_string(1, &self.actor)?;
+        }
+        if self.attempts != 0 {
+            os.write_uint32(2, self.attempts)?;
+        }
+        if self.delay_base != 0 {
+            os.write_uint32(3, self.delay_base)?;
+        }
+        if self.deadline != 0 {
+            os.write_uint32(4, self.deadline)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> RetryOutboundSettings {
+        RetryOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "actor",
+                |m: &RetryOutboundSettings| { &m.actor },
+                |m: &mut RetryOutboundSettings| { &mut m.actor },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "attempts",
+                |m: &RetryOutboundSettings| { &m.attempts },
+                |m: &mut RetryOutboundSettings| { &mut m.attempts },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "delay_base",
+                |m: &RetryOutboundSettings| { &m.delay_base },
+                |m: &mut RetryOutboundSettings| { &mut m.delay_base },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "deadline",
+                |m: &RetryOutboundSettings| { &m.deadline },
+                |m: &mut RetryOutboundSettings| { &mut m.deadline },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<RetryOutboundSettings>(
+                "RetryOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static RetryOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RetryOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RetryOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for RetryOutboundSettings {
+    fn clear(&mut self) {
+        self.actor.clear();
+        self.attempts = 0;
+        self.delay_base = 0;
+        self.deadline = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for RetryOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RetryOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct TeeOutboundSettings {
+    // message fields
+    pub actor: ::std::string::String,
+    pub path: ::std::string::String,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a TeeOutboundSettings {
+    fn default() -> &'a TeeOutboundSettings {
+        <TeeOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TeeOutboundSettings {
+    pub fn new() -> TeeOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // string actor = 1;
+
+
+    pub fn get_actor(&self) -> &str {
+        &self.actor
+    }
+    pub fn clear_actor(&mut self) {
+        self.actor.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_actor(&mut self, v: ::std::string::String) {
+        self.actor = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_actor(&mut self) -> &mut ::std::string::String {
+        &mut self.actor
+    }
+
+    // Take field
+    pub fn take_actor(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.actor, ::std::string::String::new())
+    }
+
+    // string path = 2;
+
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+    pub fn clear_path(&mut self) {
+        self.path.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_path(&mut self, v: ::std::string::String) {
+        self.path = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_path(&mut self) -> &mut ::std::string::String {
+        &mut self.path
+    }
+
+    // Take field
+    pub fn take_path(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.path, ::std::string::String::new())
+    }
+}
+
+impl ::protobuf::Message for TeeOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.actor)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.path)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.actor.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.actor);
+        }
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.path);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        if !self.actor.is_empty() {
+            os.write_string(1, &self.actor)?;
+        }
+        if !self.path.is_empty() {
+            os.write_string(2, &self.path)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TeeOutboundSettings {
+        TeeOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "actor",
+                |m: &TeeOutboundSettings| { &m.actor },
+                |m: &mut TeeOutboundSettings| { &mut m.actor },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "path",
+                |m: &TeeOutboundSettings| { &m.path },
+                |m: &mut TeeOutboundSettings| { &mut m.path },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TeeOutboundSettings>(
+                "TeeOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static TeeOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TeeOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TeeOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for TeeOutboundSettings {
+    fn clear(&mut self) {
+        self.actor.clear();
+        self.path.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for TeeOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TeeOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct TryAllOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub delay_base: u32,
+    pub until: TryAllOutboundSettings_Until,
+    pub skip_failing: bool,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a TryAllOutboundSettings {
+    fn default() -> &'a TryAllOutboundSettings {
+        <TryAllOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl TryAllOutboundSettings {
+    pub fn new() -> TryAllOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+    pub fn clear_actors(&mut self) {
+        self.actors.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.actors = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.actors
+    }
+
+    // Take field
+    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    }
+
+    // uint32 delay_base = 2;
+
+
+    pub fn get_delay_base(&self) -> u32 {
+        self.delay_base
+    }
+    pub fn clear_delay_base(&mut self) {
+        self.delay_base = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_delay_base(&mut self, v: u32) {
+        self.delay_base = v;
+    }
+
+    // .TryAllOutboundSettings.Until until = 3;
+
+
+    pub fn get_until(&self) -> TryAllOutboundSettings_Until {
+        self.until
+    }
+    pub fn clear_until(&mut self) {
+        self.until = TryAllOutboundSettings_Until::CONNECT;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_until(&mut self, v: TryAllOutboundSettings_Until) {
+        self.until = v;
+    }
+
+    // bool skip_failing = 4;
+
+
+    pub fn get_skip_failing(&self) -> bool {
+        self.skip_failing
+    }
+    pub fn clear_skip_failing(&mut self) {
+        self.skip_failing = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_skip_failing(&mut self, v: bool) {
+        self.skip_failing = v;
+    }
+}
+
+impl ::protobuf::Message for TryAllOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.delay_base = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_proto3_enum_with_unknown_fields_into(wire_type, is, &mut self.until, 3, &mut self.unknown_fields)?
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.skip_failing = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if self.delay_base != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.delay_base, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.until != TryAllOutboundSettings_Until::CONNECT {
+            my_size += ::protobuf::rt::enum_size(3, self.until);
+        }
+        if self.skip_failing != false {
+            my_size += 2;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        if self.delay_base != 0 {
+            os.write_uint32(2, self.delay_base)?;
+        }
+        if self.until != TryAllOutboundSettings_Until::CONNECT {
+            os.write_enum(3, ::protobuf::ProtobufEnum::value(&self.until))?;
+        }
+        if self.skip_failing != false {
+            os.write_bool(4, self.skip_failing)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TryAllOutboundSettings {
+        TryAllOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "actors",
+                |m: &TryAllOutboundSettings| { &m.actors },
+                |m: &mut TryAllOutboundSettings| { &mut m.actors },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "delay_base",
+                |m: &TryAllOutboundSettings| { &m.delay_base },
+                |m: &mut TryAllOutboundSettings| { &mut m.delay_base },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeEnum<TryAllOutboundSettings_Until>>(
+                "until",
+                |m: &TryAllOutboundSettings| { &m.until },
+                |m: &mut TryAllOutboundSettings| { &mut m.until },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "skip_failing",
+                |m: &TryAllOutboundSettings| { &m.skip_failing },
+                |m: &mut TryAllOutboundSettings| { &mut m.skip_failing },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<TryAllOutboundSettings>(
+                "TryAllOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static TryAllOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<TryAllOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(TryAllOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for TryAllOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.delay_base = 0;
+        self.until = TryAllOutboundSettings_Until::CONNECT;
+        self.skip_failing = false;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for TryAllOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TryAllOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(Clone,PartialEq,Eq,Debug,Hash)]
+pub enum TryAllOutboundSettings_Until {
+    CONNECT = 0,
+    WRITE = 1,
+    RESPONSE = 2,
+}
+
+impl ::protobuf::ProtobufEnum for TryAllOutboundSettings_Until {
+    fn value(&self) -> i32 {
+        *self as i32
+    }
+
+    fn from_i32(value: i32) -> ::std::option::Option<TryAllOutboundSettings_Until> {
+        match value {
+            0 => ::std::option::Option::Some(TryAllOutboundSettings_Until::CONNECT),
+            1 => ::std::option::Option::Some(TryAllOutboundSettings_Until::WRITE),
+            2 => ::std::option::Option::Some(TryAllOutboundSettings_Until::RESPONSE),
+            _ => ::std::option::Option::None
+        }
+    }
+
+    fn values() -> &'static [Self] {
+        static values: &'static [TryAllOutboundSettings_Until] = &[
+            TryAllOutboundSettings_Until::CONNECT,
+            TryAllOutboundSettings_Until::WRITE,
+            TryAllOutboundSettings_Until::RESPONSE,
+        ];
+        values
+    }
+
+    fn enum_descriptor_static() -> &'static ::protobuf::reflect::EnumDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::EnumDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            ::protobuf::reflect::EnumDescriptor::new_pb_name::<TryAllOutboundSettings_Until>("TryAllOutboundSettings.Until", file_descriptor_proto())
+        })
+    }
+}
+
+impl ::std::marker::Copy for TryAllOutboundSettings_Until {
+}
+
+impl ::std::default::Default for TryAllOutboundSettings_Until {
+    fn default() -> Self {
+        TryAllOutboundSettings_Until::CONNECT
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TryAllOutboundSettings_Until {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Enum(::protobuf::ProtobufEnum::descriptor(self))
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct RandomOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub affinity_ttl: u32,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a RandomOutboundSettings {
+    fn default() -> &'a RandomOutboundSettings {
+        <RandomOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RandomOutboundSettings {
+    pub fn new() -> RandomOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+    pub fn clear_actors(&mut self) {
+        self.actors.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.actors = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.actors
+    }
+
+    // Take field
+    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    }
+
+    // uint32 affinity_ttl = 2;
+
+
+    pub fn get_affinity_ttl(&self) -> u32 {
+        self.affinity_ttl
+    }
+    pub fn clear_affinity_ttl(&mut self) {
+        self.affinity_ttl = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_affinity_ttl(&mut self, v: u32) {
+        self.affinity_ttl = v;
+    }
+}
+
+impl ::protobuf::Message for RandomOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.affinity_ttl = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if self.affinity_ttl != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.affinity_ttl, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        if self.affinity_ttl != 0 {
+            os.write_uint32(2, self.affinity_ttl)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> RandomOutboundSettings {
+        RandomOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "actors",
+                |m: &RandomOutboundSettings| { &m.actors },
+                |m: &mut RandomOutboundSettings| { &mut m.actors },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "affinity_ttl",
+                |m: &RandomOutboundSettings| { &m.affinity_ttl },
+                |m: &mut RandomOutboundSettings| { &mut m.affinity_ttl },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<RandomOutboundSettings>(
+                "RandomOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static RandomOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<RandomOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(RandomOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for RandomOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.affinity_ttl = 0;
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for RandomOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RandomOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct ChainOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a ChainOutboundSettings {
+    fn default() -> &'a ChainOutboundSettings {
+        <ChainOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ChainOutboundSettings {
+    pub fn new() -> ChainOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+    pub fn clear_actors(&mut self) {
+        self.actors.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.actors = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.actors
+    }
+
+    // Take field
+    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for ChainOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.actors {
+            os.write_string(1, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &dyn (::std::any::Any) {
+        self as &dyn (::std::any::Any)
+    }
+    fn as_any_mut(&mut self) -> &mut dyn (::std::any::Any) {
+        self as &mut dyn (::std::any::Any)
+    }
+    fn into_any(self: ::std::boxed::Box<Self>) -> ::std::boxed::Box<dyn (::std::any::Any)> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> ChainOutboundSettings {
+        ChainOutboundSettings::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
+        descriptor.get(|| {
+            let mut fields = ::std::vec::Vec::new();
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "actors",
+                |m: &ChainOutboundSettings| { &m.actors },
+                |m: &mut ChainOutboundSettings| { &mut m.actors },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ChainOutboundSettings>(
+                "ChainOutboundSettings",
+                fields,
+                file_descriptor_proto()
+            )
+        })
+    }
+
+    fn default_instance() -> &'static ChainOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<ChainOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(ChainOutboundSettings::new)
+    }
+}
+
+impl ::protobuf::Clear for ChainOutboundSettings {
+    fn clear(&mut self) {
+        self.actors.clear();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for ChainOutboundSettings {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ChainOutboundSettings {
+    fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
+        ::protobuf::reflect::ReflectValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct FailOverOutboundSettings {
+    // message fields
+    pub actors: ::protobuf::RepeatedField<::std::string::String>,
+    pub fail_timeout: u32,
+    pub health_check: bool,
+    pub check_interval: u32,
+    pub failover: bool,
+    pub switch_margin_ms: u32,
+    pub switch_margin_percent: u32,
+    pub throughput_probe_url: ::std::string::String,
+    pub throughput_probe_size: u32,
+    pub url_test: ::std::string::String,
+    pub dns_probe_server: ::std::string::String,
+    pub dns_probe_domain: ::std::string::String,
+    pub dns_probe_answers: ::protobuf::RepeatedField<::std::string::String>,
+    pub user_agent: ::std::string::String,
+    pub check_urls: ::protobuf::RepeatedField<::std::string::String>,
+    pub check_quorum: u32,
+    pub prewarm_backups: u32,
+    pub unprobed_actors: ::protobuf::RepeatedField<FailOverOutboundSettings_UnprobedActor>,
+    // special fields
+    pub unknown_fields: ::protobuf::UnknownFields,
+    pub cached_size: ::protobuf::CachedSize,
+}
+
+impl<'a> ::std::default::Default for &'a FailOverOutboundSettings {
+    fn default() -> &'a FailOverOutboundSettings {
+        <FailOverOutboundSettings as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl FailOverOutboundSettings {
+    pub fn new() -> FailOverOutboundSettings {
+        ::std::default::Default::default()
+    }
+
+    // repeated string actors = 1;
+
+
+    pub fn get_actors(&self) -> &[::std::string::String] {
+        &self.actors
+    }
+    pub fn clear_actors(&mut self) {
+        self.actors.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.actors = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.actors
+    }
+
+    // Take field
+    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
+    }
+
+    // uint32 fail_timeout = 2;
+
+
+    pub fn get_fail_timeout(&self) -> u32 {
+        self.fail_timeout
+    }
+    pub fn clear_fail_timeout(&mut self) {
+        self.fail_timeout = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_fail_timeout(&mut self, v: u32) {
+        self.fail_timeout = v;
+    }
+
+    // bool health_check = 3;
+
+
+    pub fn get_health_check(&self) -> bool {
+        self.health_check
+    }
+    pub fn clear_health_check(&mut self) {
+        self.health_check = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_health_check(&mut self, v: bool) {
+        self.health_check = v;
+    }
+
+    // uint32 check_interval = 4;
+
+
+    pub fn get_check_interval(&self) -> u32 {
+        self.check_interval
+    }
+    pub fn clear_check_interval(&mut self) {
+        self.check_interval = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_check_interval(&mut self, v: u32) {
+        self.check_interval = v;
+    }
+
+    // bool failover = 5;
+
+
+    pub fn get_failover(&self) -> bool {
+        self.failover
+    }
+    pub fn clear_failover(&mut self) {
+        self.failover = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_failover(&mut self, v: bool) {
+        self.failover = v;
+    }
+
+    // uint32 switch_margin_ms = 6;
+
+
+    pub fn get_switch_margin_ms(&self) -> u32 {
+        self.switch_margin_ms
+    }
+    pub fn clear_switch_margin_ms(&mut self) {
+        self.switch_margin_ms = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_switch_margin_ms(&mut self, v: u32) {
+        self.switch_margin_ms = v;
+    }
+
+    // uint32 switch_margin_percent = 7;
+
+
+    pub fn get_switch_margin_percent(&self) -> u32 {
+        self.switch_margin_percent
+    }
+    pub fn clear_switch_margin_percent(&mut self) {
+        self.switch_margin_percent = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_switch_margin_percent(&mut self, v: u32) {
+        self.switch_margin_percent = v;
+    }
+
+    // string throughput_probe_url = 8;
+
+
+    pub fn get_throughput_probe_url(&self) -> &str {
+        &self.throughput_probe_url
+    }
+    pub fn clear_throughput_probe_url(&mut self) {
+        self.throughput_probe_url.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_throughput_probe_url(&mut self, v: ::std::string::String) {
+        self.throughput_probe_url = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_throughput_probe_url(&mut self) -> &mut ::std::string::String {
+        &mut self.throughput_probe_url
+    }
+
+    // Take field
+    pub fn take_throughput_probe_url(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.throughput_probe_url, ::std::string::String::new())
+    }
+
+    // uint32 throughput_probe_size = 9;
+
+
+    pub fn get_throughput_probe_size(&self) -> u32 {
+        self.throughput_probe_size
+    }
+    pub fn clear_throughput_probe_size(&mut self) {
+        self.throughput_probe_size = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_throughput_probe_size(&mut self, v: u32) {
+        self.throughput_probe_size = v;
+    }
+
+    // string url_test = 10;
+
+
+    pub fn get_url_test(&self) -> &str {
+        &self.url_test
+    }
+    pub fn clear_url_test(&mut self) {
+        self.url_test.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_url_test(&mut self, v: ::std::string::String) {
+        self.url_test = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_url_test(&mut self) -> &mut ::std::string::String {
+        &mut self.url_test
+    }
+
+    // Take field
+    pub fn take_url_test(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.url_test, ::std::string::String::new())
+    }
+
+    // string dns_probe_server = 11;
+
+
+    pub fn get_dns_probe_server(&self) -> &str {
+        &self.dns_probe_server
+    }
+    pub fn clear_dns_probe_server(&mut self) {
+        self.dns_probe_server.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_dns_probe_server(&mut self, v: ::std::string::String) {
+        self.dns_probe_server = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_dns_probe_server(&mut self) -> &mut ::std::string::String {
+        &mut self.dns_probe_server
+    }
+
+    // Take field
+    pub fn take_dns_probe_server(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.dns_probe_server, ::std::string::String::new())
+    }
+
+    // string dns_probe_domain = 12;
+
+
+    pub fn get_dns_probe_domain(&self) -> &str {
+        &self.dns_probe_domain
+    }
+    pub fn clear_dns_probe_domain(&mut self) {
+        self.dns_probe_domain.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_dns_probe_domain(&mut self, v: ::std::string::String) {
+        self.dns_probe_domain = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_dns_probe_domain(&mut self) -> &mut ::std::string::String {
+        &mut self.dns_probe_domain
+    }
+
+    // Take field
+    pub fn take_dns_probe_domain(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.dns_probe_domain, ::std::string::String::new())
+    }
+
+    // repeated string dns_probe_answers = 13;
+
+
+    pub fn get_dns_probe_answers(&self) -> &[::std::string::String] {
+        &self.dns_probe_answers
+    }
+    pub fn clear_dns_probe_answers(&mut self) {
+        self.dns_probe_answers.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_dns_probe_answers(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.dns_probe_answers = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_dns_probe_answers(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.dns_probe_answers
+    }
+
+    // Take field
+    pub fn take_dns_probe_answers(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.dns_probe_answers, ::protobuf::RepeatedField::new())
+    }
+
+    // string user_agent = 14;
+
+
+    pub fn get_user_agent(&self) -> &str {
+        &self.user_agent
+    }
+    pub fn clear_user_agent(&mut self) {
+        self.user_agent.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_user_agent(&mut self, v: ::std::string::String) {
+        self.user_agent = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_user_agent(&mut self) -> &mut ::std::string::String {
+        &mut self.user_agent
+    }
+
+    // Take field
+    pub fn take_user_agent(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.user_agent, ::std::string::String::new())
+    }
+
+    // repeated string check_urls = 15;
+
+
+    pub fn get_check_urls(&self) -> &[::std::string::String] {
+        &self.check_urls
+    }
+    pub fn clear_check_urls(&mut self) {
+        self.check_urls.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_check_urls(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.check_urls = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_check_urls(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.check_urls
+    }
+
+    // Take field
+    pub fn take_check_urls(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.check_urls, ::protobuf::RepeatedField::new())
+    }
+
+    // uint32 check_quorum = 16;
+
+
+    pub fn get_check_quorum(&self) -> u32 {
+        self.check_quorum
+    }
+    pub fn clear_check_quorum(&mut self) {
+        self.check_quorum = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_check_quorum(&mut self, v: u32) {
+        self.check_quorum = v;
+    }
+
+    // uint32 prewarm_backups = 17;
+
+
+    pub fn get_prewarm_backups(&self) -> u32 {
+        self.prewarm_backups
+    }
+    pub fn clear_prewarm_backups(&mut self) {
+        self.prewarm_backups = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_prewarm_backups(&mut self, v: u32) {
+        self.prewarm_backups = v;
+    }
+
+    // repeated .FailOverOutboundSettings.UnprobedActor unprobed_actors = 18;
+
+
+    pub fn get_unprobed_actors(&self) -> &[FailOverOutboundSettings_UnprobedActor] {
+        &self.unprobed_actors
+    }
+    pub fn clear_unprobed_actors(&mut self) {
+        self.unprobed_actors.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_unprobed_actors(&mut self, v: ::protobuf::RepeatedField<FailOverOutboundSettings_UnprobedActor>) {
+        self.unprobed_actors = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_unprobed_actors(&mut self) -> &mut ::protobuf::RepeatedField<FailOverOutboundSettings_UnprobedActor> {
+        &mut self.unprobed_actors
+    }
+
+    // Take field
+    pub fn take_unprobed_actors(&mut self) -> ::protobuf::RepeatedField<FailOverOutboundSettings_UnprobedActor> {
+        ::std::mem::replace(&mut self.unprobed_actors, ::protobuf::RepeatedField::new())
+    }
+}
+
+impl ::protobuf::Message for FailOverOutboundSettings {
+    fn is_initialized(&self) -> bool {
+        for v in &self.unprobed_actors {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.fail_timeout = tmp;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.health_check = tmp;
+                },
+                4 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.check_interval = tmp;
+                },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.failover = tmp;
+                },
+                6 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.switch_margin_ms = tmp;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.switch_margin_percent = tmp;
+                },
+                8 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.throughput_probe_url)?;
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.throughput_probe_size = tmp;
+                },
+                10 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.url_test)?;
+                },
+                11 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.dns_probe_server)?;
+                },
+                12 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.dns_probe_domain)?;
+                },
+                13 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.dns_probe_answers)?;
+                },
+                14 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.user_agent)?;
+                },
+                15 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.check_urls)?;
+                },
+                16 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.check_quorum = tmp;
+                },
+                17 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.prewarm_backups = tmp;
+                },
+                18 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.unprobed_actors)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.actors {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        if self.fail_timeout != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.fail_timeout, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.health_check != false {
+            my_size += 2;
+        }
+        if self.check_interval != 0 {
+            my_size += ::protobuf::rt::value_size(4, self.check_interval, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.failover != false {
+            my_size += 2;
+        }
+        if self.switch_margin_ms != 0 {
+            my_size += ::protobuf::rt::value_size(6, self.switch_margin_ms, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.switch_margin_percent != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.switch_margin_percent, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.throughput_probe_url.is_empty() {
+            my_size += ::protobuf::rt::string_size(8, &self.throughput_probe_url);
         }
-        ::std::result::Result::Ok(())
-    }
-
-    // Compute sizes of nested messages
-    #[allow(unused_variables)]
-    fn compute_size(&self) -> u32 {
-        let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
+        if self.throughput_probe_size != 0 {
+            my_size += ::protobuf::rt::value_size(9, self.throughput_probe_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.url_test.is_empty() {
+            my_size += ::protobuf::rt::string_size(10, &self.url_test);
+        }
+        if !self.dns_probe_server.is_empty() {
+            my_size += ::protobuf::rt::string_size(11, &self.dns_probe_server);
+        }
+        if !self.dns_probe_domain.is_empty() {
+            my_size += ::protobuf::rt::string_size(12, &self.dns_probe_domain);
+        }
+        for value in &self.dns_probe_answers {
+            my_size += ::protobuf::rt::string_size(13, &value);
+        };
+        if !self.user_agent.is_empty() {
+            my_size += ::protobuf::rt::string_size(14, &self.user_agent);
+        }
+        for value in &self.check_urls {
+            my_size += ::protobuf::rt::string_size(15, &value);
+        };
+        if self.check_quorum != 0 {
+            my_size += ::protobuf::rt::value_size(16, self.check_quorum, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.prewarm_backups != 0 {
+            my_size += ::protobuf::rt::value_size(17, self.prewarm_backups, ::protobuf::wire_format::WireTypeVarint);
+        }
+        for value in &self.unprobed_actors {
+            let len = value.compute_size();
+            my_size += 2 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         };
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
@@ -3837,6 +9244,59 @@ impl ::protobuf::Message for ChainOutboundSettings {
         for v in &self.actors {
             os.write_string(1, &v)?;
         };
+        if self.fail_timeout != 0 {
+            os.write_uint32(2, self.fail_timeout)?;
+        }
+        if self.health_check != false {
+            os.write_bool(3, self.health_check)?;
+        }
+        if self.check_interval != 0 {
+            os.write_uint32(4, self.check_interval)?;
+        }
+        if self.failover != false {
+            os.write_bool(5, self.failover)?;
+        }
+        if self.switch_margin_ms != 0 {
+            os.write_uint32(6, self.switch_margin_ms)?;
+        }
+        if self.switch_margin_percent != 0 {
+            os.write_uint32(7, self.switch_margin_percent)?;
+        }
+        if !self.throughput_probe_url.is_empty() {
+            os.write_string(8, &self.throughput_probe_url)?;
+        }
+        if self.throughput_probe_size != 0 {
+            os.write_uint32(9, self.throughput_probe_size)?;
+        }
+        if !self.url_test.is_empty() {
+            os.write_string(10, &self.url_test)?;
+        }
+        if !self.dns_probe_server.is_empty() {
+            os.write_string(11, &self.dns_probe_server)?;
+        }
+        if !self.dns_probe_domain.is_empty() {
+            os.write_string(12, &self.dns_probe_domain)?;
+        }
+        for v in &self.dns_probe_answers {
+            os.write_string(13, &v)?;
+        };
+        if !self.user_agent.is_empty() {
+            os.write_string(14, &self.user_agent)?;
+        }
+        for v in &self.check_urls {
+            os.write_string(15, &v)?;
+        };
+        if self.check_quorum != 0 {
+            os.write_uint32(16, self.check_quorum)?;
+        }
+        if self.prewarm_backups != 0 {
+            os.write_uint32(17, self.prewarm_backups)?;
+        }
+        for v in &self.unprobed_actors {
+            os.write_tag(18, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -3867,8 +9327,8 @@ impl ::protobuf::Message for ChainOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> ChainOutboundSettings {
-        ChainOutboundSettings::new()
+    fn new() -> FailOverOutboundSettings {
+        FailOverOutboundSettings::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
@@ -3877,153 +9337,224 @@ impl ::protobuf::Message for ChainOutboundSettings {
             let mut fields = ::std::vec::Vec::new();
             fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
                 "actors",
-                |m: &ChainOutboundSettings| { &m.actors },
-                |m: &mut ChainOutboundSettings| { &mut m.actors },
+                |m: &FailOverOutboundSettings| { &m.actors },
+                |m: &mut FailOverOutboundSettings| { &mut m.actors },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<ChainOutboundSettings>(
-                "ChainOutboundSettings",
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "fail_timeout",
+                |m: &FailOverOutboundSettings| { &m.fail_timeout },
+                |m: &mut FailOverOutboundSettings| { &mut m.fail_timeout },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "health_check",
+                |m: &FailOverOutboundSettings| { &m.health_check },
+                |m: &mut FailOverOutboundSettings| { &mut m.health_check },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "check_interval",
+                |m: &FailOverOutboundSettings| { &m.check_interval },
+                |m: &mut FailOverOutboundSettings| { &mut m.check_interval },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "failover",
+                |m: &FailOverOutboundSettings| { &m.failover },
+                |m: &mut FailOverOutboundSettings| { &mut m.failover },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "switch_margin_ms",
+                |m: &FailOverOutboundSettings| { &m.switch_margin_ms },
+                |m: &mut FailOverOutboundSettings| { &mut m.switch_margin_ms },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "switch_margin_percent",
+                |m: &FailOverOutboundSettings| { &m.switch_margin_percent },
+                |m: &mut FailOverOutboundSettings| { &mut m.switch_margin_percent },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "throughput_probe_url",
+                |m: &FailOverOutboundSettings| { &m.throughput_probe_url },
+                |m: &mut FailOverOutboundSettings| { &mut m.throughput_probe_url },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "throughput_probe_size",
+                |m: &FailOverOutboundSettings| { &m.throughput_probe_size },
+                |m: &mut FailOverOutboundSettings| { &mut m.throughput_probe_size },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "url_test",
+                |m: &FailOverOutboundSettings| { &m.url_test },
+                |m: &mut FailOverOutboundSettings| { &mut m.url_test },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "dns_probe_server",
+                |m: &FailOverOutboundSettings| { &m.dns_probe_server },
+                |m: &mut FailOverOutboundSettings| { &mut m.dns_probe_server },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "dns_probe_domain",
+                |m: &FailOverOutboundSettings| { &m.dns_probe_domain },
+                |m: &mut FailOverOutboundSettings| { &mut m.dns_probe_domain },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "dns_probe_answers",
+                |m: &FailOverOutboundSettings| { &m.dns_probe_answers },
+                |m: &mut FailOverOutboundSettings| { &mut m.dns_probe_answers },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "user_agent",
+                |m: &FailOverOutboundSettings| { &m.user_agent },
+                |m: &mut FailOverOutboundSettings| { &mut m.user_agent },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "check_urls",
+                |m: &FailOverOutboundSettings| { &m.check_urls },
+                |m: &mut FailOverOutboundSettings| { &mut m.check_urls },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "check_quorum",
+                |m: &FailOverOutboundSettings| { &m.check_quorum },
+                |m: &mut FailOverOutboundSettings| { &mut m.check_quorum },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "prewarm_backups",
+                |m: &FailOverOutboundSettings| { &m.prewarm_backups },
+                |m: &mut FailOverOutboundSettings| { &mut m.prewarm_backups },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<FailOverOutboundSettings_UnprobedActor>>(
+                "unprobed_actors",
+                |m: &FailOverOutboundSettings| { &m.unprobed_actors },
+                |m: &mut FailOverOutboundSettings| { &mut m.unprobed_actors },
+            ));
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<FailOverOutboundSettings>(
+                "FailOverOutboundSettings",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
 
-    fn default_instance() -> &'static ChainOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<ChainOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(ChainOutboundSettings::new)
+    fn default_instance() -> &'static FailOverOutboundSettings {
+        static instance: ::protobuf::rt::LazyV2<FailOverOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(FailOverOutboundSettings::new)
     }
 }
 
-impl ::protobuf::Clear for ChainOutboundSettings {
+impl ::protobuf::Clear for FailOverOutboundSettings {
     fn clear(&mut self) {
         self.actors.clear();
+        self.fail_timeout = 0;
+        self.health_check = false;
+        self.check_interval = 0;
+        self.failover = false;
+        self.switch_margin_ms = 0;
+        self.switch_margin_percent = 0;
+        self.throughput_probe_url.clear();
+        self.throughput_probe_size = 0;
+        self.url_test.clear();
+        self.dns_probe_server.clear();
+        self.dns_probe_domain.clear();
+        self.dns_probe_answers.clear();
+        self.user_agent.clear();
+        self.check_urls.clear();
+        self.check_quorum = 0;
+        self.prewarm_backups = 0;
+        self.unprobed_actors.clear();
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for ChainOutboundSettings {
+impl ::std::fmt::Debug for FailOverOutboundSettings {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for ChainOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for FailOverOutboundSettings {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
 }
 
 #[derive(PartialEq,Clone,Default)]
-pub struct FailOverOutboundSettings {
+pub struct FailOverOutboundSettings_UnprobedActor {
     // message fields
-    pub actors: ::protobuf::RepeatedField<::std::string::String>,
-    pub fail_timeout: u32,
-    pub health_check: bool,
-    pub check_interval: u32,
-    pub failover: bool,
+    pub actor: ::std::string::String,
+    pub rank: u32,
+    pub last: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
 }
 
-impl<'a> ::std::default::Default for &'a FailOverOutboundSettings {
-    fn default() -> &'a FailOverOutboundSettings {
-        <FailOverOutboundSettings as ::protobuf::Message>::default_instance()
+impl<'a> ::std::default::Default for &'a FailOverOutboundSettings_UnprobedActor {
+    fn default() -> &'a FailOverOutboundSettings_UnprobedActor {
+        <FailOverOutboundSettings_UnprobedActor as ::protobuf::Message>::default_instance()
     }
 }
 
-impl FailOverOutboundSettings {
-    pub fn new() -> FailOverOutboundSettings {
+impl FailOverOutboundSettings_UnprobedActor {
+    pub fn new() -> FailOverOutboundSettings_UnprobedActor {
         ::std::default::Default::default()
     }
 
-    // repeated string actors = 1;
+    // string actor = 1;
 
 
-    pub fn get_actors(&self) -> &[::std::string::String] {
-        &self.actors
+    pub fn get_actor(&self) -> &str {
+        &self.actor
     }
-    pub fn clear_actors(&mut self) {
-        self.actors.clear();
+    pub fn clear_actor(&mut self) {
+        self.actor.clear();
     }
 
     // Param is passed by value, moved
-    pub fn set_actors(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
-        self.actors = v;
+    pub fn set_actor(&mut self, v: ::std::string::String) {
+        self.actor = v;
     }
 
     // Mutable pointer to the field.
-    pub fn mut_actors(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
-        &mut self.actors
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_actor(&mut self) -> &mut ::std::string::String {
+        &mut self.actor
     }
 
     // Take field
-    pub fn take_actors(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
-        ::std::mem::replace(&mut self.actors, ::protobuf::RepeatedField::new())
-    }
-
-    // uint32 fail_timeout = 2;
-
-
-    pub fn get_fail_timeout(&self) -> u32 {
-        self.fail_timeout
-    }
-    pub fn clear_fail_timeout(&mut self) {
-        self.fail_timeout = 0;
-    }
-
-    // Param is passed by value, moved
-    pub fn set_fail_timeout(&mut self, v: u32) {
-        self.fail_timeout = v;
-    }
-
-    // bool health_check = 3;
-
-
-    pub fn get_health_check(&self) -> bool {
-        self.health_check
-    }
-    pub fn clear_health_check(&mut self) {
-        self.health_check = false;
-    }
-
-    // Param is passed by value, moved
-    pub fn set_health_check(&mut self, v: bool) {
-        self.health_check = v;
+    pub fn take_actor(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.actor, ::std::string::String::new())
     }
 
-    // uint32 check_interval = 4;
+    // uint32 rank = 2;
 
 
-    pub fn get_check_interval(&self) -> u32 {
-        self.check_interval
+    pub fn get_rank(&self) -> u32 {
+        self.rank
     }
-    pub fn clear_check_interval(&mut self) {
-        self.check_interval = 0;
+    pub fn clear_rank(&mut self) {
+        self.rank = 0;
     }
 
     // Param is passed by value, moved
-    pub fn set_check_interval(&mut self, v: u32) {
-        self.check_interval = v;
+    pub fn set_rank(&mut self, v: u32) {
+        self.rank = v;
     }
 
-    // bool failover = 5;
+    // bool last = 3;
 
 
-    pub fn get_failover(&self) -> bool {
-        self.failover
+    pub fn get_last(&self) -> bool {
+        self.last
     }
-    pub fn clear_failover(&mut self) {
-        self.failover = false;
+    pub fn clear_last(&mut self) {
+        self.last = false;
     }
 
     // Param is passed by value, moved
-    pub fn set_failover(&mut self, v: bool) {
-        self.failover = v;
+    pub fn set_last(&mut self, v: bool) {
+        self.last = v;
     }
 }
 
-impl ::protobuf::Message for FailOverOutboundSettings {
+impl ::protobuf::Message for FailOverOutboundSettings_UnprobedActor {
     fn is_initialized(&self) -> bool {
         true
     }
@@ -4033,35 +9564,21 @@ impl ::protobuf::Message for FailOverOutboundSettings {
             let (field_number, wire_type) = is.read_tag_unpack()?;
             match field_number {
                 1 => {
-                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.actors)?;
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.actor)?;
                 },
                 2 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_uint32()?;
-                    self.fail_timeout = tmp;
+                    self.rank = tmp;
                 },
                 3 => {
                     if wire_type != ::protobuf::wire_format::WireTypeVarint {
                         return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
                     }
                     let tmp = is.read_bool()?;
-                    self.health_check = tmp;
-                },
-                4 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_uint32()?;
-                    self.check_interval = tmp;
-                },
-                5 => {
-                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
-                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
-                    }
-                    let tmp = is.read_bool()?;
-                    self.failover = tmp;
+                    self.last = tmp;
                 },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
@@ -4075,19 +9592,13 @@ impl ::protobuf::Message for FailOverOutboundSettings {
     #[allow(unused_variables)]
     fn compute_size(&self) -> u32 {
         let mut my_size = 0;
-        for value in &self.actors {
-            my_size += ::protobuf::rt::string_size(1, &value);
-        };
-        if self.fail_timeout != 0 {
-            my_size += ::protobuf::rt::value_size(2, self.fail_timeout, ::protobuf::wire_format::WireTypeVarint);
-        }
-        if self.health_check != false {
-            my_size += 2;
+        if !self.actor.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.actor);
         }
-        if self.check_interval != 0 {
-            my_size += ::protobuf::rt::value_size(4, self.check_interval, ::protobuf::wire_format::WireTypeVarint);
+        if self.rank != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.rank, ::protobuf::wire_format::WireTypeVarint);
         }
-        if self.failover != false {
+        if self.last != false {
             my_size += 2;
         }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
@@ -4096,20 +9607,14 @@ impl ::protobuf::Message for FailOverOutboundSettings {
     }
 
     fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::ProtobufResult<()> {
-        for v in &self.actors {
-            os.write_string(1, &v)?;
-        };
-        if self.fail_timeout != 0 {
-            os.write_uint32(2, self.fail_timeout)?;
+        if !self.actor.is_empty() {
+            os.write_string(1, &self.actor)?;
         }
-        if self.health_check != false {
-            os.write_bool(3, self.health_check)?;
-        }
-        if self.check_interval != 0 {
-            os.write_uint32(4, self.check_interval)?;
+        if self.rank != 0 {
+            os.write_uint32(2, self.rank)?;
         }
-        if self.failover != false {
-            os.write_bool(5, self.failover)?;
+        if self.last != false {
+            os.write_bool(3, self.last)?;
         }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
@@ -4141,71 +9646,59 @@ impl ::protobuf::Message for FailOverOutboundSettings {
         Self::descriptor_static()
     }
 
-    fn new() -> FailOverOutboundSettings {
-        FailOverOutboundSettings::new()
+    fn new() -> FailOverOutboundSettings_UnprobedActor {
+        FailOverOutboundSettings_UnprobedActor::new()
     }
 
     fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
         static descriptor: ::protobuf::rt::LazyV2<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::LazyV2::INIT;
         descriptor.get(|| {
             let mut fields = ::std::vec::Vec::new();
-            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
-                "actors",
-                |m: &FailOverOutboundSettings| { &m.actors },
-                |m: &mut FailOverOutboundSettings| { &mut m.actors },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "fail_timeout",
-                |m: &FailOverOutboundSettings| { &m.fail_timeout },
-                |m: &mut FailOverOutboundSettings| { &mut m.fail_timeout },
-            ));
-            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
-                "health_check",
-                |m: &FailOverOutboundSettings| { &m.health_check },
-                |m: &mut FailOverOutboundSettings| { &mut m.health_check },
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "actor",
+                |m: &FailOverOutboundSettings_UnprobedActor| { &m.actor },
+                |m: &mut FailOverOutboundSettings_UnprobedActor| { &mut m.actor },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
-                "check_interval",
-                |m: &FailOverOutboundSettings| { &m.check_interval },
-                |m: &mut FailOverOutboundSettings| { &mut m.check_interval },
+                "rank",
+                |m: &FailOverOutboundSettings_UnprobedActor| { &m.rank },
+                |m: &mut FailOverOutboundSettings_UnprobedActor| { &mut m.rank },
             ));
             fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
-                "failover",
-                |m: &FailOverOutboundSettings| { &m.failover },
-                |m: &mut FailOverOutboundSettings| { &mut m.failover },
+                "last",
+                |m: &FailOverOutboundSettings_UnprobedActor| { &m.last },
+                |m: &mut FailOverOutboundSettings_UnprobedActor| { &mut m.last },
             ));
-            ::protobuf::reflect::MessageDescriptor::new_pb_name::<FailOverOutboundSettings>(
-                "FailOverOutboundSettings",
+            ::protobuf::reflect::MessageDescriptor::new_pb_name::<FailOverOutboundSettings_UnprobedActor>(
+                "FailOverOutboundSettings.UnprobedActor",
                 fields,
                 file_descriptor_proto()
             )
         })
     }
-
-    fn default_instance() -> &'static FailOverOutboundSettings {
-        static instance: ::protobuf::rt::LazyV2<FailOverOutboundSettings> = ::protobuf::rt::LazyV2::INIT;
-        instance.get(FailOverOutboundSettings::new)
+
+    fn default_instance() -> &'static FailOverOutboundSettings_UnprobedActor {
+        static instance: ::protobuf::rt::LazyV2<FailOverOutboundSettings_UnprobedActor> = ::protobuf::rt::LazyV2::INIT;
+        instance.get(FailOverOutboundSettings_UnprobedActor::new)
     }
 }
 
-impl ::protobuf::Clear for FailOverOutboundSettings {
+impl ::protobuf::Clear for FailOverOutboundSettings_UnprobedActor {
     fn clear(&mut self) {
-        self.actors.clear();
-        self.fail_timeout = 0;
-        self.health_check = false;
-        self.check_interval = 0;
-        self.failover = false;
+        self.actor.clear();
+        self.rank = 0;
+        self.last = false;
         self.unknown_fields.clear();
     }
 }
 
-impl ::std::fmt::Debug for FailOverOutboundSettings {
+impl ::std::fmt::Debug for FailOverOutboundSettings_UnprobedActor {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
         ::protobuf::text_format::fmt(self, f)
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for FailOverOutboundSettings {
+impl ::protobuf::reflect::ProtobufValue for FailOverOutboundSettings_UnprobedActor {
     fn as_ref(&self) -> ::protobuf::reflect::ReflectValueRef {
         ::protobuf::reflect::ReflectValueRef::Message(self)
     }
@@ -4218,6 +9711,12 @@ pub struct Outbound {
     pub protocol: ::std::string::String,
     pub bind: ::std::string::String,
     pub settings: ::std::vec::Vec<u8>,
+    pub slow_threshold: u32,
+    pub bind_interface: ::std::string::String,
+    pub connect_deadline: u32,
+    pub relay_buffer_size: u32,
+    pub max_lifetime: u32,
+    pub resolve: ::std::string::String,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4337,6 +9836,118 @@ impl Outbound {
     pub fn take_settings(&mut self) -> ::std::vec::Vec<u8> {
         ::std::mem::replace(&mut self.settings, ::std::vec::Vec::new())
     }
+
+    // uint32 slow_threshold = 5;
+
+
+    pub fn get_slow_threshold(&self) -> u32 {
+        self.slow_threshold
+    }
+    pub fn clear_slow_threshold(&mut self) {
+        self.slow_threshold = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_slow_threshold(&mut self, v: u32) {
+        self.slow_threshold = v;
+    }
+
+    // string bind_interface = 6;
+
+
+    pub fn get_bind_interface(&self) -> &str {
+        &self.bind_interface
+    }
+    pub fn clear_bind_interface(&mut self) {
+        self.bind_interface.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_bind_interface(&mut self, v: ::std::string::String) {
+        self.bind_interface = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_bind_interface(&mut self) -> &mut ::std::string::String {
+        &mut self.bind_interface
+    }
+
+    // Take field
+    pub fn take_bind_interface(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.bind_interface, ::std::string::String::new())
+    }
+
+    // uint32 connect_deadline = 7;
+
+
+    pub fn get_connect_deadline(&self) -> u32 {
+        self.connect_deadline
+    }
+    pub fn clear_connect_deadline(&mut self) {
+        self.connect_deadline = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_connect_deadline(&mut self, v: u32) {
+        self.connect_deadline = v;
+    }
+
+    // uint32 relay_buffer_size = 8;
+
+
+    pub fn get_relay_buffer_size(&self) -> u32 {
+        self.relay_buffer_size
+    }
+    pub fn clear_relay_buffer_size(&mut self) {
+        self.relay_buffer_size = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_relay_buffer_size(&mut self, v: u32) {
+        self.relay_buffer_size = v;
+    }
+
+    // uint32 max_lifetime = 9;
+
+
+    pub fn get_max_lifetime(&self) -> u32 {
+        self.max_lifetime
+    }
+    pub fn clear_max_lifetime(&mut self) {
+        self.max_lifetime = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_lifetime(&mut self, v: u32) {
+        self.max_lifetime = v;
+    }
+
+    // string resolve = 10;
+
+
+    pub fn get_resolve(&self) -> &str {
+        &self.resolve
+    }
+    pub fn clear_resolve(&mut self) {
+        self.resolve.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_resolve(&mut self, v: ::std::string::String) {
+        self.resolve = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_resolve(&mut self) -> &mut ::std::string::String {
+        &mut self.resolve
+    }
+
+    // Take field
+    pub fn take_resolve(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.resolve, ::std::string::String::new())
+    }
 }
 
 impl ::protobuf::Message for Outbound {
@@ -4360,6 +9971,40 @@ impl ::protobuf::Message for Outbound {
                 4 => {
                     ::protobuf::rt::read_singular_proto3_bytes_into(wire_type, is, &mut self.settings)?;
                 },
+                5 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.slow_threshold = tmp;
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.bind_interface)?;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.connect_deadline = tmp;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.relay_buffer_size = tmp;
+                },
+                9 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.max_lifetime = tmp;
+                },
+                10 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.resolve)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -4384,6 +10029,24 @@ impl ::protobuf::Message for Outbound {
         if !self.settings.is_empty() {
             my_size += ::protobuf::rt::bytes_size(4, &self.settings);
         }
+        if self.slow_threshold != 0 {
+            my_size += ::protobuf::rt::value_size(5, self.slow_threshold, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.bind_interface.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.bind_interface);
+        }
+        if self.connect_deadline != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.connect_deadline, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.relay_buffer_size != 0 {
+            my_size += ::protobuf::rt::value_size(8, self.relay_buffer_size, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.max_lifetime != 0 {
+            my_size += ::protobuf::rt::value_size(9, self.max_lifetime, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.resolve.is_empty() {
+            my_size += ::protobuf::rt::string_size(10, &self.resolve);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -4402,6 +10065,24 @@ impl ::protobuf::Message for Outbound {
         if !self.settings.is_empty() {
             os.write_bytes(4, &self.settings)?;
         }
+        if self.slow_threshold != 0 {
+            os.write_uint32(5, self.slow_threshold)?;
+        }
+        if !self.bind_interface.is_empty() {
+            os.write_string(6, &self.bind_interface)?;
+        }
+        if self.connect_deadline != 0 {
+            os.write_uint32(7, self.connect_deadline)?;
+        }
+        if self.relay_buffer_size != 0 {
+            os.write_uint32(8, self.relay_buffer_size)?;
+        }
+        if self.max_lifetime != 0 {
+            os.write_uint32(9, self.max_lifetime)?;
+        }
+        if !self.resolve.is_empty() {
+            os.write_string(10, &self.resolve)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -4460,6 +10141,36 @@ impl ::protobuf::Message for Outbound {
                 |m: &Outbound| { &m.settings },
                 |m: &mut Outbound| { &mut m.settings },
             ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "slow_threshold",
+                |m: &Outbound| { &m.slow_threshold },
+                |m: &mut Outbound| { &mut m.slow_threshold },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "bind_interface",
+                |m: &Outbound| { &m.bind_interface },
+                |m: &mut Outbound| { &mut m.bind_interface },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "connect_deadline",
+                |m: &Outbound| { &m.connect_deadline },
+                |m: &mut Outbound| { &mut m.connect_deadline },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "relay_buffer_size",
+                |m: &Outbound| { &m.relay_buffer_size },
+                |m: &mut Outbound| { &mut m.relay_buffer_size },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "max_lifetime",
+                |m: &Outbound| { &m.max_lifetime },
+                |m: &mut Outbound| { &mut m.max_lifetime },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "resolve",
+                |m: &Outbound| { &m.resolve },
+                |m: &mut Outbound| { &mut m.resolve },
+            ));
             ::protobuf::reflect::MessageDescriptor::new_pb_name::<Outbound>(
                 "Outbound",
                 fields,
@@ -4480,6 +10191,12 @@ impl ::protobuf::Clear for Outbound {
         self.protocol.clear();
         self.bind.clear();
         self.settings.clear();
+        self.slow_threshold = 0;
+        self.bind_interface.clear();
+        self.connect_deadline = 0;
+        self.relay_buffer_size = 0;
+        self.max_lifetime = 0;
+        self.resolve.clear();
         self.unknown_fields.clear();
     }
 }
@@ -4503,6 +10220,10 @@ pub struct RoutingRule {
     pub domains: ::protobuf::RepeatedField<RoutingRule_Domain>,
     pub ip_cidrs: ::protobuf::RepeatedField<::std::string::String>,
     pub mmdbs: ::protobuf::RepeatedField<RoutingRule_Mmdb>,
+    pub snis: ::protobuf::RepeatedField<RoutingRule_Domain>,
+    pub udp_ports: ::std::vec::Vec<u32>,
+    pub port_ranges: ::protobuf::RepeatedField<::std::string::String>,
+    pub private_ip: bool,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -4619,6 +10340,96 @@ impl RoutingRule {
     pub fn take_mmdbs(&mut self) -> ::protobuf::RepeatedField<RoutingRule_Mmdb> {
         ::std::mem::replace(&mut self.mmdbs, ::protobuf::RepeatedField::new())
     }
+
+    // repeated .RoutingRule.Domain snis = 5;
+
+
+    pub fn get_snis(&self) -> &[RoutingRule_Domain] {
+        &self.snis
+    }
+    pub fn clear_snis(&mut self) {
+        self.snis.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_snis(&mut self, v: ::protobuf::RepeatedField<RoutingRule_Domain>) {
+        self.snis = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_snis(&mut self) -> &mut ::protobuf::RepeatedField<RoutingRule_Domain> {
+        &mut self.snis
+    }
+
+    // Take field
+    pub fn take_snis(&mut self) -> ::protobuf::RepeatedField<RoutingRule_Domain> {
+        ::std::mem::replace(&mut self.snis, ::protobuf::RepeatedField::new())
+    }
+
+    // repeated uint32 udp_ports = 6;
+
+
+    pub fn get_udp_ports(&self) -> &[u32] {
+        &self.udp_ports
+    }
+    pub fn clear_udp_ports(&mut self) {
+        self.udp_ports.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_udp_ports(&mut self, v: ::std::vec::Vec<u32>) {
+        self.udp_ports = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_udp_ports(&mut self) -> &mut ::std::vec::Vec<u32> {
+        &mut self.udp_ports
+    }
+
+    // Take field
+    pub fn take_udp_ports(&mut self) -> ::std::vec::Vec<u32> {
+        ::std::mem::replace(&mut self.udp_ports, ::std::vec::Vec::new())
+    }
+
+    // repeated string port_ranges = 7;
+
+
+    pub fn get_port_ranges(&self) -> &[::std::string::String] {
+        &self.port_ranges
+    }
+    pub fn clear_port_ranges(&mut self) {
+        self.port_ranges.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_port_ranges(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.port_ranges = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_port_ranges(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.port_ranges
+    }
+
+    // Take field
+    pub fn take_port_ranges(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.port_ranges, ::protobuf::RepeatedField::new())
+    }
+
+    // bool private_ip = 8;
+
+
+    pub fn get_private_ip(&self) -> bool {
+        self.private_ip
+    }
+    pub fn clear_private_ip(&mut self) {
+        self.private_ip = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_private_ip(&mut self, v: bool) {
+        self.private_ip = v;
+    }
 }
 
 impl ::protobuf::Message for RoutingRule {
@@ -4633,6 +10444,11 @@ impl ::protobuf::Message for RoutingRule {
                 return false;
             }
         };
+        for v in &self.snis {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -4652,6 +10468,22 @@ impl ::protobuf::Message for RoutingRule {
                 4 => {
                     ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.mmdbs)?;
                 },
+                5 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.snis)?;
+                },
+                6 => {
+                    ::protobuf::rt::read_repeated_uint32_into(wire_type, is, &mut self.udp_ports)?;
+                },
+                7 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.port_ranges)?;
+                },
+                8 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.private_ip = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -4678,6 +10510,19 @@ impl ::protobuf::Message for RoutingRule {
             let len = value.compute_size();
             my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         };
+        for value in &self.snis {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if !self.udp_ports.is_empty() {
+            my_size += ::protobuf::rt::vec_packed_varint_size(6, &self.udp_ports);
+        }
+        for value in &self.port_ranges {
+            my_size += ::protobuf::rt::string_size(7, &value);
+        };
+        if self.private_ip != false {
+            my_size += 2;
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -4700,6 +10545,25 @@ impl ::protobuf::Message for RoutingRule {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         };
+        for v in &self.snis {
+            os.write_tag(5, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if !self.udp_ports.is_empty() {
+            os.write_tag(6, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            // TODO: Data size is computed again, it should be cached
+            os.write_raw_varint32(::protobuf::rt::vec_packed_varint_data_size(&self.udp_ports))?;
+            for v in &self.udp_ports {
+                os.write_uint32_no_tag(*v)?;
+            };
+        }
+        for v in &self.port_ranges {
+            os.write_string(7, &v)?;
+        };
+        if self.private_ip != false {
+            os.write_bool(8, self.private_ip)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -4758,6 +10622,26 @@ impl ::protobuf::Message for RoutingRule {
                 |m: &RoutingRule| { &m.mmdbs },
                 |m: &mut RoutingRule| { &mut m.mmdbs },
             ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<RoutingRule_Domain>>(
+                "snis",
+                |m: &RoutingRule| { &m.snis },
+                |m: &mut RoutingRule| { &mut m.snis },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_vec_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                "udp_ports",
+                |m: &RoutingRule| { &m.udp_ports },
+                |m: &mut RoutingRule| { &mut m.udp_ports },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                "port_ranges",
+                |m: &RoutingRule| { &m.port_ranges },
+                |m: &mut RoutingRule| { &mut m.port_ranges },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                "private_ip",
+                |m: &RoutingRule| { &m.private_ip },
+                |m: &mut RoutingRule| { &mut m.private_ip },
+            ));
             ::protobuf::reflect::MessageDescriptor::new_pb_name::<RoutingRule>(
                 "RoutingRule",
                 fields,
@@ -4778,6 +10662,10 @@ impl ::protobuf::Clear for RoutingRule {
         self.domains.clear();
         self.ip_cidrs.clear();
         self.mmdbs.clear();
+        self.snis.clear();
+        self.udp_ports.clear();
+        self.port_ranges.clear();
+        self.private_ip = false;
         self.unknown_fields.clear();
     }
 }
@@ -5246,6 +11134,8 @@ pub struct Config {
     pub outbounds: ::protobuf::RepeatedField<Outbound>,
     pub routing_rules: ::protobuf::RepeatedField<RoutingRule>,
     pub dns: ::protobuf::SingularPtrField<DNS>,
+    pub udp: ::protobuf::SingularPtrField<UDP>,
+    pub egress_rate: u64,
     // special fields
     pub unknown_fields: ::protobuf::UnknownFields,
     pub cached_size: ::protobuf::CachedSize,
@@ -5402,6 +11292,54 @@ impl Config {
     pub fn take_dns(&mut self) -> DNS {
         self.dns.take().unwrap_or_else(|| DNS::new())
     }
+
+    // .UDP udp = 6;
+
+
+    pub fn get_udp(&self) -> &UDP {
+        self.udp.as_ref().unwrap_or_else(|| <UDP as ::protobuf::Message>::default_instance())
+    }
+    pub fn clear_udp(&mut self) {
+        self.udp.clear();
+    }
+
+    pub fn has_udp(&self) -> bool {
+        self.udp.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_udp(&mut self, v: UDP) {
+        self.udp = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_udp(&mut self) -> &mut UDP {
+        if self.udp.is_none() {
+            self.udp.set_default();
+        }
+        self.udp.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_udp(&mut self) -> UDP {
+        self.udp.take().unwrap_or_else(|| UDP::new())
+    }
+
+    // uint64 egress_rate = 7;
+
+
+    pub fn get_egress_rate(&self) -> u64 {
+        self.egress_rate
+    }
+    pub fn clear_egress_rate(&mut self) {
+        self.egress_rate = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_egress_rate(&mut self, v: u64) {
+        self.egress_rate = v;
+    }
 }
 
 impl ::protobuf::Message for Config {
@@ -5431,6 +11369,11 @@ impl ::protobuf::Message for Config {
                 return false;
             }
         };
+        for v in &self.udp {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -5453,6 +11396,16 @@ impl ::protobuf::Message for Config {
                 5 => {
                     ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.dns)?;
                 },
+                6 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.udp)?;
+                },
+                7 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint64()?;
+                    self.egress_rate = tmp;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -5485,6 +11438,13 @@ impl ::protobuf::Message for Config {
             let len = v.compute_size();
             my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         }
+        if let Some(ref v) = self.udp.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if self.egress_rate != 0 {
+            my_size += ::protobuf::rt::value_size(7, self.egress_rate, ::protobuf::wire_format::WireTypeVarint);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -5516,6 +11476,14 @@ impl ::protobuf::Message for Config {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         }
+        if let Some(ref v) = self.udp.as_ref() {
+            os.write_tag(6, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if self.egress_rate != 0 {
+            os.write_uint64(7, self.egress_rate)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -5579,6 +11547,16 @@ impl ::protobuf::Message for Config {
                 |m: &Config| { &m.dns },
                 |m: &mut Config| { &mut m.dns },
             ));
+            fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<UDP>>(
+                "udp",
+                |m: &Config| { &m.udp },
+                |m: &mut Config| { &mut m.udp },
+            ));
+            fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint64>(
+                "egress_rate",
+                |m: &Config| { &m.egress_rate },
+                |m: &mut Config| { &mut m.egress_rate },
+            ));
             ::protobuf::reflect::MessageDescriptor::new_pb_name::<Config>(
                 "Config",
                 fields,
@@ -5600,6 +11578,8 @@ impl ::protobuf::Clear for Config {
         self.outbounds.clear();
         self.routing_rules.clear();
         self.dns.clear();
+        self.udp.clear();
+        self.egress_rate = 0;
         self.unknown_fields.clear();
     }
 }
@@ -5617,70 +11597,163 @@ impl ::protobuf::reflect::ProtobufValue for Config {
 }
 
 static file_descriptor_proto_data: &'static [u8] = b"\
-    \n\x20src/config/internal/config.proto\"3\n\x03DNS\x12\x18\n\x07servers\
-    \x18\x01\x20\x03(\tR\x07servers\x12\x12\n\x04bind\x18\x02\x20\x01(\tR\
-    \x04bind\"\xcc\x01\n\x03Log\x12\x20\n\x05level\x18\x01\x20\x01(\x0e2\n.L\
-    og.LevelR\x05level\x12#\n\x06output\x18\x02\x20\x01(\x0e2\x0b.Log.Output\
-    R\x06output\x12\x1f\n\x0boutput_file\x18\x03\x20\x01(\tR\noutputFile\"<\
-    \n\x05Level\x12\t\n\x05TRACE\x10\0\x12\t\n\x05DEBUG\x10\x01\x12\x08\n\
-    \x04INFO\x10\x02\x12\x08\n\x04WARN\x10\x03\x12\t\n\x05ERROR\x10\x04\"\
-    \x1f\n\x06Output\x12\x0b\n\x07CONSOLE\x10\0\x12\x08\n\x04FILE\x10\x01\"\
-    \xc2\x01\n\x12TUNInboundSettings\x12\x0e\n\x02fd\x18\x01\x20\x01(\x05R\
-    \x02fd\x12\x12\n\x04name\x18\x02\x20\x01(\tR\x04name\x12\x18\n\x07addres\
-    s\x18\x03\x20\x01(\tR\x07address\x12\x18\n\x07gateway\x18\x04\x20\x01(\t\
-    R\x07gateway\x12\x18\n\x07netmask\x18\x05\x20\x01(\tR\x07netmask\x12\x10\
-    \n\x03mtu\x18\x06\x20\x01(\x05R\x03mtu\x12(\n\x10fake_dns_exclude\x18\
-    \x07\x20\x03(\tR\x0efakeDnsExclude\"*\n\x14SocksInboundSettings\x12\x12\
-    \n\x04bind\x18\x01\x20\x01(\tR\x04bind\"\x7f\n\x07Inbound\x12\x10\n\x03t\
-    ag\x18\x01\x20\x01(\tR\x03tag\x12\x1a\n\x08protocol\x18\x02\x20\x01(\tR\
-    \x08protocol\x12\x16\n\x06listen\x18\x03\x20\x01(\tR\x06listen\x12\x12\n\
-    \x04port\x18\x04\x20\x01(\rR\x04port\x12\x1a\n\x08settings\x18\x05\x20\
-    \x01(\x0cR\x08settings\"H\n\x18RedirectOutboundSettings\x12\x18\n\x07add\
-    ress\x18\x01\x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\r\
-    R\x04port\"E\n\x15SocksOutboundSettings\x12\x18\n\x07address\x18\x01\x20\
-    \x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\"\x7f\
-    \n\x1bShadowsocksOutboundSettings\x12\x18\n\x07address\x18\x01\x20\x01(\
-    \tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\x12\x16\n\
-    \x06method\x18\x03\x20\x01(\tR\x06method\x12\x1a\n\x08password\x18\x04\
-    \x20\x01(\tR\x08password\"b\n\x16TrojanOutboundSettings\x12\x18\n\x07add\
-    ress\x18\x01\x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\r\
-    R\x04port\x12\x1a\n\x08password\x18\x03\x20\x01(\tR\x08password\"u\n\x15\
-    VMessOutboundSettings\x12\x18\n\x07address\x18\x01\x20\x01(\tR\x07addres\
-    s\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\x12\x12\n\x04uuid\x18\
-    \x03\x20\x01(\tR\x04uuid\x12\x1a\n\x08security\x18\x04\x20\x01(\tR\x08se\
-    curity\"Y\n\x15VLessOutboundSettings\x12\x18\n\x07address\x18\x01\x20\
+    \n\x20src/config/internal/config.proto\"\xc8\x01\n\x03DNS\x12\x18\n\x07s\
+    ervers\x18\x01\x20\x03(\tR\x07servers\x12\x12\n\x04bind\x18\x02\x20\x01(\
+    \tR\x04bind\x12\x1f\n\x05rules\x18\x03\x20\x03(\x0b2\t.DNS.RuleR\x05rule\
+    s\x12!\n\x0cwarm_domains\x18\x04\x20\x03(\tR\x0bwarmDomains\x1aO\n\x04Ru\
+    le\x12-\n\x07domains\x18\x01\x20\x03(\x0b2\x13.RoutingRule.DomainR\x07do\
+    mains\x12\x18\n\x07servers\x18\x02\x20\x03(\tR\x07servers\"\xcc\x01\n\
+    \x03Log\x12\x20\n\x05level\x18\x01\x20\x01(\x0e2\n.Log.LevelR\x05level\
+    \x12#\n\x06output\x18\x02\x20\x01(\x0e2\x0b.Log.OutputR\x06output\x12\
+    \x1f\n\x0boutput_file\x18\x03\x20\x01(\tR\noutputFile\"<\n\x05Level\x12\
+    \t\n\x05TRACE\x10\0\x12\t\n\x05DEBUG\x10\x01\x12\x08\n\x04INFO\x10\x02\
+    \x12\x08\n\x04WARN\x10\x03\x12\t\n\x05ERROR\x10\x04\"\x1f\n\x06Output\
+    \x12\x0b\n\x07CONSOLE\x10\0\x12\x08\n\x04FILE\x10\x01\"\xef\x01\n\x03UDP\
+    \x12'\n\x08nat_type\x18\x01\x20\x01(\x0e2\x0c.UDP.NatTypeR\x07natType\
+    \x12'\n\x0fsession_timeout\x18\x02\x20\x01(\rR\x0esessionTimeout\x125\n\
+    \rport_timeouts\x18\x03\x20\x03(\x0b2\x10.UDP.PortTimeoutR\x0cportTimeou\
+    ts\x1a;\n\x0bPortTimeout\x12\x12\n\x04port\x18\x01\x20\x01(\rR\x04port\
+    \x12\x18\n\x07timeout\x18\x02\x20\x01(\rR\x07timeout\"\"\n\x07NatType\
+    \x12\x08\n\x04CONE\x10\0\x12\r\n\tSYMMETRIC\x10\x01\"\xdf\x01\n\x12TUNIn\
+    boundSettings\x12\x0e\n\x02fd\x18\x01\x20\x01(\x05R\x02fd\x12\x12\n\x04n\
+    ame\x18\x02\x20\x01(\tR\x04name\x12\x18\n\x07address\x18\x03\x20\x01(\tR\
+    \x07address\x12\x18\n\x07gateway\x18\x04\x20\x01(\tR\x07gateway\x12\x18\
+    \n\x07netmask\x18\x05\x20\x01(\tR\x07netmask\x12\x10\n\x03mtu\x18\x06\
+    \x20\x01(\x05R\x03mtu\x12(\n\x10fake_dns_exclude\x18\x07\x20\x03(\tR\x0e\
+    fakeDnsExclude\x12\x1b\n\tmax_flows\x18\x08\x20\x01(\rR\x08maxFlows\"b\n\
+    \x14SocksInboundSettings\x12\x12\n\x04bind\x18\x01\x20\x01(\tR\x04bind\
+    \x12\x1a\n\x08username\x18\x02\x20\x01(\tR\x08username\x12\x1a\n\x08pass\
+    word\x18\x03\x20\x01(\tR\x08password\"M\n\x13HttpInboundSettings\x12\x1a\
+    \n\x08username\x18\x01\x20\x01(\tR\x08username\x12\x1a\n\x08password\x18\
+    \x02\x20\x01(\tR\x08password\"b\n\x14MixedInboundSettings\x12\x12\n\x04b\
+    ind\x18\x01\x20\x01(\tR\x04bind\x12\x1a\n\x08username\x18\x02\x20\x01(\t\
+    R\x08username\x12\x1a\n\x08password\x18\x03\x20\x01(\tR\x08password\"\
+    \x7f\n\x07Inbound\x12\x10\n\x03tag\x18\x01\x20\x01(\tR\x03tag\x12\x1a\n\
+    \x08protocol\x18\x02\x20\x01(\tR\x08protocol\x12\x16\n\x06listen\x18\x03\
+    \x20\x01(\tR\x06listen\x12\x12\n\x04port\x18\x04\x20\x01(\rR\x04port\x12\
+    \x1a\n\x08settings\x18\x05\x20\x01(\x0cR\x08settings\"\xe6\x02\n\x16Dire\
+    ctOutboundSettings\x12%\n\x0eproxy_protocol\x18\x01\x20\x01(\rR\rproxyPr\
+    otocol\x12.\n\x13dial_failure_window\x18\x02\x20\x01(\rR\x11dialFailureW\
+    indow\x12'\n\x0fsequential_dial\x18\x03\x20\x01(\x08R\x0esequentialDial\
+    \x120\n\x14happy_eyeballs_delay\x18\x04\x20\x01(\rR\x12happyEyeballsDela\
+    y\x12\x1f\n\x0bprefer_ipv4\x18\x05\x20\x01(\x08R\npreferIpv4\x12$\n\x0er\
+    etry_on_reset\x18\x06\x20\x01(\x08R\x0cretryOnReset\x120\n\x14preserve_s\
+    ource_port\x18\x07\x20\x01(\x08R\x12preserveSourcePort\x12!\n\x0cudp_bat\
+    ching\x18\x08\x20\x01(\x08R\x0budpBatching\"o\n\x18RedirectOutboundSetti\
+    ngs\x12\x18\n\x07address\x18\x01\x20\x01(\tR\x07address\x12\x12\n\x04por\
+    t\x18\x02\x20\x01(\rR\x04port\x12%\n\x0eproxy_protocol\x18\x03\x20\x01(\
+    \rR\rproxyProtocol\"E\n\x15FixedOutboundSettings\x12\x18\n\x07address\
+    \x18\x01\x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\
+    \x04port\"\xf9\x01\n\x15SocksOutboundSettings\x12\x18\n\x07address\x18\
+    \x01\x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04por\
+    t\x12)\n\x10reassociate_base\x18\x03\x20\x01(\rR\x0freassociateBase\x12'\
+    \n\x0freassociate_max\x18\x04\x20\x01(\rR\x0ereassociateMax\x12-\n\x12re\
+    associate_jitter\x18\x05\x20\x01(\rR\x11reassociateJitter\x12/\n\x13reas\
+    sociate_timeout\x18\x06\x20\x01(\rR\x12reassociateTimeout\"\x96\x01\n\
+    \x14HTTPOutboundSettings\x12\x18\n\x07address\x18\x01\x20\x01(\tR\x07add\
+    ress\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\x12\x1a\n\x08username\
+    \x18\x03\x20\x01(\tR\x08username\x12\x1a\n\x08password\x18\x04\x20\x01(\
+    \tR\x08password\x12\x18\n\x07forward\x18\x05\x20\x01(\x08R\x07forward\"\
+    \x7f\n\x1bShadowsocksOutboundSettings\x12\x18\n\x07address\x18\x01\x20\
     \x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\x12\
-    \x12\n\x04uuid\x18\x03\x20\x01(\tR\x04uuid\"J\n\x13TlsOutboundSettings\
-    \x12\x1f\n\x0bserver_name\x18\x01\x20\x01(\tR\nserverName\x12\x12\n\x04a\
-    lpn\x18\x02\x20\x03(\tR\x04alpn\"/\n\x19WebSocketOutboundSettings\x12\
-    \x12\n\x04path\x18\x01\x20\x01(\tR\x04path\"?\n\x15HTTP2OutboundSettings\
-    \x12\x12\n\x04path\x18\x01\x20\x01(\tR\x04path\x12\x12\n\x04host\x18\x02\
-    \x20\x01(\tR\x04host\"O\n\x16TryAllOutboundSettings\x12\x16\n\x06actors\
-    \x18\x01\x20\x03(\tR\x06actors\x12\x1d\n\ndelay_base\x18\x02\x20\x01(\rR\
-    \tdelayBase\"0\n\x16RandomOutboundSettings\x12\x16\n\x06actors\x18\x01\
-    \x20\x03(\tR\x06actors\"/\n\x15ChainOutboundSettings\x12\x16\n\x06actors\
-    \x18\x01\x20\x03(\tR\x06actors\"\xbb\x01\n\x18FailOverOutboundSettings\
-    \x12\x16\n\x06actors\x18\x01\x20\x03(\tR\x06actors\x12!\n\x0cfail_timeou\
-    t\x18\x02\x20\x01(\rR\x0bfailTimeout\x12!\n\x0chealth_check\x18\x03\x20\
-    \x01(\x08R\x0bhealthCheck\x12%\n\x0echeck_interval\x18\x04\x20\x01(\rR\r\
-    checkInterval\x12\x1a\n\x08failover\x18\x05\x20\x01(\x08R\x08failover\"h\
-    \n\x08Outbound\x12\x10\n\x03tag\x18\x01\x20\x01(\tR\x03tag\x12\x1a\n\x08\
-    protocol\x18\x02\x20\x01(\tR\x08protocol\x12\x12\n\x04bind\x18\x03\x20\
-    \x01(\tR\x04bind\x12\x1a\n\x08settings\x18\x04\x20\x01(\x0cR\x08settings\
-    \"\xd5\x02\n\x0bRoutingRule\x12\x1d\n\ntarget_tag\x18\x01\x20\x01(\tR\tt\
-    argetTag\x12-\n\x07domains\x18\x02\x20\x03(\x0b2\x13.RoutingRule.DomainR\
-    \x07domains\x12\x19\n\x08ip_cidrs\x18\x03\x20\x03(\tR\x07ipCidrs\x12'\n\
-    \x05mmdbs\x18\x04\x20\x03(\x0b2\x11.RoutingRule.MmdbR\x05mmdbs\x1au\n\
-    \x06Domain\x12,\n\x04type\x18\x01\x20\x01(\x0e2\x18.RoutingRule.Domain.T\
-    ypeR\x04type\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value\"'\n\x04Typ\
-    e\x12\t\n\x05PLAIN\x10\0\x12\n\n\x06DOMAIN\x10\x01\x12\x08\n\x04FULL\x10\
-    \x02\x1a=\n\x04Mmdb\x12\x12\n\x04file\x18\x01\x20\x01(\tR\x04file\x12!\n\
-    \x0ccountry_code\x18\x02\x20\x01(\tR\x0bcountryCode\"\xba\x01\n\x06Confi\
-    g\x12\x16\n\x03log\x18\x01\x20\x01(\x0b2\x04.LogR\x03log\x12$\n\x08inbou\
-    nds\x18\x02\x20\x03(\x0b2\x08.InboundR\x08inbounds\x12'\n\toutbounds\x18\
-    \x03\x20\x03(\x0b2\t.OutboundR\toutbounds\x121\n\rrouting_rules\x18\x04\
-    \x20\x03(\x0b2\x0c.RoutingRuleR\x0croutingRules\x12\x16\n\x03dns\x18\x05\
-    \x20\x01(\x0b2\x04.DNSR\x03dnsb\x06proto3\
+    \x16\n\x06method\x18\x03\x20\x01(\tR\x06method\x12\x1a\n\x08password\x18\
+    \x04\x20\x01(\tR\x08password\"b\n\x16TrojanOutboundSettings\x12\x18\n\
+    \x07address\x18\x01\x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\
+    \x01(\rR\x04port\x12\x1a\n\x08password\x18\x03\x20\x01(\tR\x08password\"\
+    u\n\x15VMessOutboundSettings\x12\x18\n\x07address\x18\x01\x20\x01(\tR\
+    \x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\x12\x12\n\x04u\
+    uid\x18\x03\x20\x01(\tR\x04uuid\x12\x1a\n\x08security\x18\x04\x20\x01(\t\
+    R\x08security\"Y\n\x15VLessOutboundSettings\x12\x18\n\x07address\x18\x01\
+    \x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\x20\x01(\rR\x04port\
+    \x12\x12\n\x04uuid\x18\x03\x20\x01(\tR\x04uuid\"\xb5\x01\n\x13TlsOutboun\
+    dSettings\x12\x1f\n\x0bserver_name\x18\x01\x20\x01(\tR\nserverName\x12\
+    \x12\n\x04alpn\x18\x02\x20\x03(\tR\x04alpn\x12\x1a\n\x08insecure\x18\x03\
+    \x20\x01(\x08R\x08insecure\x12\x1f\n\x0bpinned_spki\x18\x04\x20\x03(\tR\
+    \npinnedSpki\x12,\n\x12session_cache_size\x18\x05\x20\x01(\rR\x10session\
+    CacheSize\"\x9e\x01\n\x19WebSocketOutboundSettings\x12\x12\n\x04path\x18\
+    \x01\x20\x01(\tR\x04path\x12\x12\n\x04host\x18\x02\x20\x01(\tR\x04host\
+    \x12$\n\x0emax_early_data\x18\x03\x20\x01(\rR\x0cmaxEarlyData\x123\n\x16\
+    early_data_header_name\x18\x04\x20\x01(\tR\x13earlyDataHeaderName\"?\n\
+    \x15HTTP2OutboundSettings\x12\x12\n\x04path\x18\x01\x20\x01(\tR\x04path\
+    \x12\x12\n\x04host\x18\x02\x20\x01(\tR\x04host\"\x85\x02\n\x13MuxOutboun\
+    dSettings\x12\x1f\n\x0bmax_streams\x18\x01\x20\x01(\rR\nmaxStreams\x12'\
+    \n\x0fmax_connections\x18\x02\x20\x01(\rR\x0emaxConnections\x12%\n\x0ere\
+    ceive_window\x18\x03\x20\x01(\rR\rreceiveWindow\x12-\n\x12keepalive_inte\
+    rval\x18\x04\x20\x01(\rR\x11keepaliveInterval\x12+\n\x11keepalive_timeou\
+    t\x18\x05\x20\x01(\rR\x10keepaliveTimeout\x12!\n\x0cidle_timeout\x18\x06\
+    \x20\x01(\rR\x0bidleTimeout\"\x86\x02\n\x14QuicOutboundSettings\x12\x18\
+    \n\x07address\x18\x01\x20\x01(\tR\x07address\x12\x12\n\x04port\x18\x02\
+    \x20\x01(\rR\x04port\x12\x1f\n\x0bserver_name\x18\x03\x20\x01(\tR\nserve\
+    rName\x12\x12\n\x04alpn\x18\x04\x20\x03(\tR\x04alpn\x12\"\n\x0ccertifica\
+    tes\x18\x05\x20\x03(\tR\x0ccertificates\x12\x19\n\x08zero_rtt\x18\x06\
+    \x20\x01(\x08R\x07zeroRtt\x12%\n\x0einitial_window\x18\x07\x20\x01(\x04R\
+    \rinitialWindow\x12%\n\x0eminimum_window\x18\x08\x20\x01(\x04R\rminimumW\
+    indow\",\n\x16RejectOutboundSettings\x12\x12\n\x04mode\x18\x01\x20\x01(\
+    \tR\x04mode\".\n\x13DNSOutboundSettings\x12\x17\n\x07fake_ip\x18\x01\x20\
+    \x01(\x08R\x06fakeIp\"V\n\x14ObfsOutboundSettings\x12\x16\n\x06method\
+    \x18\x01\x20\x01(\tR\x06method\x12\x12\n\x04host\x18\x02\x20\x01(\tR\x04\
+    host\x12\x12\n\x04path\x18\x03\x20\x01(\tR\x04path\"L\n\x15LimitOutbound\
+    Settings\x12\x12\n\x04rate\x18\x01\x20\x01(\x04R\x04rate\x12\x1f\n\x0bgl\
+    obal_rate\x18\x02\x20\x01(\x04R\nglobalRate\"\x84\x01\n\x15RetryOutbound\
+    Settings\x12\x14\n\x05actor\x18\x01\x20\x01(\tR\x05actor\x12\x1a\n\x08at\
+    tempts\x18\x02\x20\x01(\rR\x08attempts\x12\x1d\n\ndelay_base\x18\x03\x20\
+    \x01(\rR\tdelayBase\x12\x1a\n\x08deadline\x18\x04\x20\x01(\rR\x08deadlin\
+    e\"?\n\x13TeeOutboundSettings\x12\x14\n\x05actor\x18\x01\x20\x01(\tR\x05\
+    actor\x12\x12\n\x04path\x18\x02\x20\x01(\tR\x04path\"\xd6\x01\n\x16TryAl\
+    lOutboundSettings\x12\x16\n\x06actors\x18\x01\x20\x03(\tR\x06actors\x12\
+    \x1d\n\ndelay_base\x18\x02\x20\x01(\rR\tdelayBase\x123\n\x05until\x18\
+    \x03\x20\x01(\x0e2\x1d.TryAllOutboundSettings.UntilR\x05until\x12!\n\x0c\
+    skip_failing\x18\x04\x20\x01(\x08R\x0bskipFailing\"-\n\x05Until\x12\x0b\
+    \n\x07CONNECT\x10\0\x12\t\n\x05WRITE\x10\x01\x12\x0c\n\x08RESPONSE\x10\
+    \x02\"S\n\x16RandomOutboundSettings\x12\x16\n\x06actors\x18\x01\x20\x03(\
+    \tR\x06actors\x12!\n\x0caffinity_ttl\x18\x02\x20\x01(\rR\x0baffinityTtl\
+    \"/\n\x15ChainOutboundSettings\x12\x16\n\x06actors\x18\x01\x20\x03(\tR\
+    \x06actors\"\xc5\x06\n\x18FailOverOutboundSettings\x12\x16\n\x06actors\
+    \x18\x01\x20\x03(\tR\x06actors\x12!\n\x0cfail_timeout\x18\x02\x20\x01(\r\
+    R\x0bfailTimeout\x12!\n\x0chealth_check\x18\x03\x20\x01(\x08R\x0bhealthC\
+    heck\x12%\n\x0echeck_interval\x18\x04\x20\x01(\rR\rcheckInterval\x12\x1a\
+    \n\x08failover\x18\x05\x20\x01(\x08R\x08failover\x12(\n\x10switch_margin\
+    _ms\x18\x06\x20\x01(\rR\x0eswitchMarginMs\x122\n\x15switch_margin_percen\
+    t\x18\x07\x20\x01(\rR\x13switchMarginPercent\x120\n\x14throughput_probe_\
+    url\x18\x08\x20\x01(\tR\x12throughputProbeUrl\x122\n\x15throughput_probe\
+    _size\x18\t\x20\x01(\rR\x13throughputProbeSize\x12\x19\n\x08url_test\x18\
+    \n\x20\x01(\tR\x07urlTest\x12(\n\x10dns_probe_server\x18\x0b\x20\x01(\tR\
+    \x0ednsProbeServer\x12(\n\x10dns_probe_domain\x18\x0c\x20\x01(\tR\x0edns\
+    ProbeDomain\x12*\n\x11dns_probe_answers\x18\r\x20\x03(\tR\x0fdnsProbeAns\
+    wers\x12\x1d\n\nuser_agent\x18\x0e\x20\x01(\tR\tuserAgent\x12\x1d\n\nche\
+    ck_urls\x18\x0f\x20\x03(\tR\tcheckUrls\x12!\n\x0ccheck_quorum\x18\x10\
+    \x20\x01(\rR\x0bcheckQuorum\x12'\n\x0fprewarm_backups\x18\x11\x20\x01(\r\
+    R\x0eprewarmBackups\x12P\n\x0funprobed_actors\x18\x12\x20\x03(\x0b2'.Fai\
+    lOverOutboundSettings.UnprobedActorR\x0eunprobedActors\x1aM\n\rUnprobedA\
+    ctor\x12\x14\n\x05actor\x18\x01\x20\x01(\tR\x05actor\x12\x12\n\x04rank\
+    \x18\x02\x20\x01(\rR\x04rank\x12\x12\n\x04last\x18\x03\x20\x01(\x08R\x04\
+    last\"\xca\x02\n\x08Outbound\x12\x10\n\x03tag\x18\x01\x20\x01(\tR\x03tag\
+    \x12\x1a\n\x08protocol\x18\x02\x20\x01(\tR\x08protocol\x12\x12\n\x04bind\
+    \x18\x03\x20\x01(\tR\x04bind\x12\x1a\n\x08settings\x18\x04\x20\x01(\x0cR\
+    \x08settings\x12%\n\x0eslow_threshold\x18\x05\x20\x01(\rR\rslowThreshold\
+    \x12%\n\x0ebind_interface\x18\x06\x20\x01(\tR\rbindInterface\x12)\n\x10c\
+    onnect_deadline\x18\x07\x20\x01(\rR\x0fconnectDeadline\x12*\n\x11relay_b\
+    uffer_size\x18\x08\x20\x01(\rR\x0frelayBufferSize\x12!\n\x0cmax_lifetime\
+    \x18\t\x20\x01(\rR\x0bmaxLifetime\x12\x18\n\x07resolve\x18\n\x20\x01(\tR\
+    \x07resolve\"\xdb\x03\n\x0bRoutingRule\x12\x1d\n\ntarget_tag\x18\x01\x20\
+    \x01(\tR\ttargetTag\x12-\n\x07domains\x18\x02\x20\x03(\x0b2\x13.RoutingR\
+    ule.DomainR\x07domains\x12\x19\n\x08ip_cidrs\x18\x03\x20\x03(\tR\x07ipCi\
+    drs\x12'\n\x05mmdbs\x18\x04\x20\x03(\x0b2\x11.RoutingRule.MmdbR\x05mmdbs\
+    \x12'\n\x04snis\x18\x05\x20\x03(\x0b2\x13.RoutingRule.DomainR\x04snis\
+    \x12\x1b\n\tudp_ports\x18\x06\x20\x03(\rR\x08udpPorts\x12\x1f\n\x0bport_\
+    ranges\x18\x07\x20\x03(\tR\nportRanges\x12\x1d\n\nprivate_ip\x18\x08\x20\
+    \x01(\x08R\tprivateIp\x1au\n\x06Domain\x12,\n\x04type\x18\x01\x20\x01(\
+    \x0e2\x18.RoutingRule.Domain.TypeR\x04type\x12\x14\n\x05value\x18\x02\
+    \x20\x01(\tR\x05value\"'\n\x04Type\x12\t\n\x05PLAIN\x10\0\x12\n\n\x06DOM\
+    AIN\x10\x01\x12\x08\n\x04FULL\x10\x02\x1a=\n\x04Mmdb\x12\x12\n\x04file\
+    \x18\x01\x20\x01(\tR\x04file\x12!\n\x0ccountry_code\x18\x02\x20\x01(\tR\
+    \x0bcountryCode\"\xf3\x01\n\x06Config\x12\x16\n\x03log\x18\x01\x20\x01(\
+    \x0b2\x04.LogR\x03log\x12$\n\x08inbounds\x18\x02\x20\x03(\x0b2\x08.Inbou\
+    ndR\x08inbounds\x12'\n\toutbounds\x18\x03\x20\x03(\x0b2\t.OutboundR\tout\
+    bounds\x121\n\rrouting_rules\x18\x04\x20\x03(\x0b2\x0c.RoutingRuleR\x0cr\
+    outingRules\x12\x16\n\x03dns\x18\x05\x20\x01(\x0b2\x04.DNSR\x03dns\x12\
+    \x16\n\x03udp\x18\x06\x20\x01(\x0b2\x04.UDPR\x03udp\x12\x1f\n\x0begress_\
+    rate\x18\x07\x20\x01(\x04R\negressRateb\x06proto3\
 ";
 
 static file_descriptor_proto_lazy: ::protobuf::rt::LazyV2<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::rt::LazyV2::INIT;
@@ -13,6 +13,29 @@ use crate::config::{external_rule, geosite, internal};
 pub struct DNS {
     pub servers: Option<Vec<String>>,
     pub bind: Option<String>,
+    pub rules: Option<Vec<DNSRule>>,
+    #[serde(rename = "warmDomains")]
+    pub warm_domains: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DNSRule {
+    pub domain: Option<Vec<String>>,
+    #[serde(rename = "domainKeyword")]
+    pub domain_keyword: Option<Vec<String>>,
+    #[serde(rename = "domainSuffix")]
+    pub domain_suffix: Option<Vec<String>>,
+    pub servers: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UDP {
+    #[serde(rename = "natType")]
+    pub nat_type: Option<String>,
+    #[serde(rename = "sessionTimeout")]
+    pub session_timeout: Option<u32>,
+    #[serde(rename = "portTimeouts")]
+    pub port_timeouts: Option<HashMap<String, u32>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -21,9 +44,24 @@ pub struct Log {
     pub output: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HttpInboundSettings {
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SocksInboundSettings {
     pub bind: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MixedInboundSettings {
+    pub bind: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -36,6 +74,8 @@ pub struct TUNInboundSettings {
     pub mtu: Option<i32>,
     #[serde(rename = "fakeDnsExclude")]
     pub fake_dns_exclude: Option<Vec<String>>,
+    #[serde(rename = "maxFlows")]
+    pub max_flows: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -47,16 +87,61 @@ pub struct Inbound {
     pub settings: Option<Box<RawValue>>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DirectOutboundSettings {
+    #[serde(rename = "proxyProtocol")]
+    pub proxy_protocol: Option<u32>,
+    #[serde(rename = "dialFailureWindow")]
+    pub dial_failure_window: Option<u32>,
+    #[serde(rename = "sequentialDial")]
+    pub sequential_dial: Option<bool>,
+    #[serde(rename = "happyEyeballsDelay")]
+    pub happy_eyeballs_delay: Option<u32>,
+    #[serde(rename = "preferIpv4")]
+    pub prefer_ipv4: Option<bool>,
+    #[serde(rename = "retryOnReset")]
+    pub retry_on_reset: Option<bool>,
+    #[serde(rename = "preserveSourcePort")]
+    pub preserve_source_port: Option<bool>,
+    #[serde(rename = "udpBatching")]
+    pub udp_batching: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FixedOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RedirectOutboundSettings {
     pub address: Option<String>,
     pub port: Option<u16>,
+    #[serde(rename = "proxyProtocol")]
+    pub proxy_protocol: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SocksOutboundSettings {
     pub address: Option<String>,
     pub port: Option<u16>,
+    #[serde(rename = "reassociateBase")]
+    pub reassociate_base: Option<u32>,
+    #[serde(rename = "reassociateMax")]
+    pub reassociate_max: Option<u32>,
+    #[serde(rename = "reassociateJitter")]
+    pub reassociate_jitter: Option<u32>,
+    #[serde(rename = "reassociateTimeout")]
+    pub reassociate_timeout: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HTTPOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub forward: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -89,28 +174,58 @@ pub struct VLessOutboundSettings {
     pub uuid: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RetryOutboundSettings {
+    pub actor: Option<String>,
+    pub attempts: Option<u32>,
+    #[serde(rename = "delayBase")]
+    pub delay_base: Option<u32>,
+    pub deadline: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TeeOutboundSettings {
+    pub actor: Option<String>,
+    pub path: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TryAllOutboundSettings {
     pub actors: Option<Vec<String>>,
     #[serde(rename = "delayBase")]
     pub delay_base: Option<u32>,
+    pub until: Option<String>,
+    #[serde(rename = "skipFailing")]
+    pub skip_failing: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RandomOutboundSettings {
     pub actors: Option<Vec<String>>,
+    #[serde(rename = "affinityTtl")]
+    pub affinity_ttl: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TlsOutboundSettings {
-    #[serde(rename = "serverName")]
+    #[serde(rename = "serverName", alias = "sni")]
     pub server_name: Option<String>,
     pub alpn: Option<Vec<String>>,
+    pub insecure: Option<bool>,
+    #[serde(rename = "pinnedSpki")]
+    pub pinned_spki: Option<Vec<String>>,
+    #[serde(rename = "sessionCacheSize")]
+    pub session_cache_size: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WebSocketOutboundSettings {
     pub path: Option<String>,
+    pub host: Option<String>,
+    #[serde(rename = "maxEarlyData")]
+    pub max_early_data: Option<u32>,
+    #[serde(rename = "earlyDataHeaderName")]
+    pub early_data_header_name: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -119,6 +234,63 @@ pub struct HTTP2OutboundSettings {
     pub host: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MuxOutboundSettings {
+    #[serde(rename = "maxStreams")]
+    pub max_streams: Option<u32>,
+    #[serde(rename = "maxConnections")]
+    pub max_connections: Option<u32>,
+    #[serde(rename = "receiveWindow")]
+    pub receive_window: Option<u32>,
+    #[serde(rename = "keepaliveInterval")]
+    pub keepalive_interval: Option<u32>,
+    #[serde(rename = "keepaliveTimeout")]
+    pub keepalive_timeout: Option<u32>,
+    #[serde(rename = "idleTimeout")]
+    pub idle_timeout: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuicOutboundSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    #[serde(rename = "serverName")]
+    pub server_name: Option<String>,
+    pub alpn: Option<Vec<String>>,
+    pub certificates: Option<Vec<String>>,
+    #[serde(rename = "zeroRtt")]
+    pub zero_rtt: Option<bool>,
+    #[serde(rename = "initialWindow")]
+    pub initial_window: Option<u64>,
+    #[serde(rename = "minimumWindow")]
+    pub minimum_window: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RejectOutboundSettings {
+    pub mode: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DNSOutboundSettings {
+    #[serde(rename = "fakeIp")]
+    pub fake_ip: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ObfsOutboundSettings {
+    pub method: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LimitOutboundSettings {
+    pub rate: Option<u64>,
+    #[serde(rename = "globalRate")]
+    pub global_rate: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ChainOutboundSettings {
     pub actors: Option<Vec<String>>,
@@ -134,6 +306,33 @@ pub struct FailOverOutboundSettings {
     #[serde(rename = "checkInterval")]
     pub check_interval: Option<u32>,
     pub failover: Option<bool>,
+    /// Either milliseconds, e.g. "30ms", or a percentage, e.g. "10%".
+    #[serde(rename = "switchMargin")]
+    pub switch_margin: Option<String>,
+    #[serde(rename = "throughputProbeUrl")]
+    pub throughput_probe_url: Option<String>,
+    #[serde(rename = "throughputProbeSize")]
+    pub throughput_probe_size: Option<u32>,
+    #[serde(rename = "urlTest")]
+    pub url_test: Option<String>,
+    #[serde(rename = "dnsProbeServer")]
+    pub dns_probe_server: Option<String>,
+    #[serde(rename = "dnsProbeDomain")]
+    pub dns_probe_domain: Option<String>,
+    #[serde(rename = "dnsProbeAnswers")]
+    pub dns_probe_answers: Option<Vec<String>>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    #[serde(rename = "checkUrls")]
+    pub check_urls: Option<Vec<String>>,
+    #[serde(rename = "checkQuorum")]
+    pub check_quorum: Option<u32>,
+    #[serde(rename = "prewarmBackups")]
+    pub prewarm_backups: Option<u32>,
+    /// Actors left out of health checks, to their fixed rank in the
+    /// schedule: a position from 0, "first" or "last".
+    #[serde(rename = "unprobedActors")]
+    pub unprobed_actors: Option<HashMap<String, String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -141,6 +340,17 @@ pub struct Outbound {
     pub protocol: String,
     pub tag: Option<String>,
     pub bind: Option<String>,
+    #[serde(rename = "slowThreshold")]
+    pub slow_threshold: Option<u32>,
+    #[serde(rename = "bindInterface")]
+    pub bind_interface: Option<String>,
+    #[serde(rename = "connectDeadline")]
+    pub connect_deadline: Option<u32>,
+    #[serde(rename = "relayBufferSize")]
+    pub relay_buffer_size: Option<u32>,
+    #[serde(rename = "maxLifetime")]
+    pub max_lifetime: Option<u32>,
+    pub resolve: Option<String>,
     pub settings: Option<Box<RawValue>>,
 }
 
@@ -152,8 +362,21 @@ pub struct Rule {
     pub domain_keyword: Option<Vec<String>>,
     #[serde(rename = "domainSuffix")]
     pub domain_suffix: Option<Vec<String>>,
+    pub sni: Option<Vec<String>>,
+    #[serde(rename = "sniKeyword")]
+    pub sni_keyword: Option<Vec<String>>,
+    #[serde(rename = "sniSuffix")]
+    pub sni_suffix: Option<Vec<String>>,
     pub geoip: Option<Vec<String>>,
     pub external: Option<Vec<String>>,
+    #[serde(rename = "udpPort")]
+    pub udp_port: Option<Vec<u16>>,
+    /// Single ports or ranges, e.g. "22" or "1000-2000".
+    pub port: Option<Vec<String>>,
+    /// Matches private and local destination IPs, e.g. to send LAN traffic
+    /// direct.
+    #[serde(rename = "privateIp")]
+    pub private_ip: Option<bool>,
     pub target: String,
 }
 
@@ -164,6 +387,9 @@ pub struct Config {
     pub outbounds: Option<Vec<Outbound>>,
     pub rules: Option<Vec<Rule>>,
     pub dns: Option<DNS>,
+    pub udp: Option<UDP>,
+    #[serde(rename = "egressRate")]
+    pub egress_rate: Option<u64>,
 }
 
 pub fn to_internal(json: Config) -> Result<internal::Config> {
@@ -233,6 +459,9 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     if fake_dns_exclude.len() > 0 {
                         settings.fake_dns_exclude = fake_dns_exclude;
                     }
+                    if let Some(ext_max_flows) = ext_settings.max_flows {
+                        settings.max_flows = ext_max_flows;
+                    }
 
                     if let Some(ext_fd) = ext_settings.fd {
                         settings.fd = ext_fd;
@@ -261,6 +490,19 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     inbounds.push(inbound);
                 }
                 "http" => {
+                    if let Some(ext_settings) = ext_inbound.settings {
+                        let mut settings = internal::HttpInboundSettings::new();
+                        let ext_settings: HttpInboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_username) = ext_settings.username {
+                            settings.username = ext_username;
+                        }
+                        if let Some(ext_password) = ext_settings.password {
+                            settings.password = ext_password;
+                        }
+                        let settings = settings.write_to_bytes().unwrap();
+                        inbound.settings = settings;
+                    }
                     inbounds.push(inbound);
                 }
                 "socks" => {
@@ -272,6 +514,31 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     } else {
                         settings.bind = "".to_string();
                     }
+                    if let Some(ext_username) = ext_settings.username {
+                        settings.username = ext_username;
+                    }
+                    if let Some(ext_password) = ext_settings.password {
+                        settings.password = ext_password;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    inbound.settings = settings;
+                    inbounds.push(inbound);
+                }
+                "mixed" => {
+                    let mut settings = internal::MixedInboundSettings::new();
+                    if let Some(ext_settings) = ext_inbound.settings {
+                        let ext_settings: MixedInboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_bind) = ext_settings.bind {
+                            settings.bind = ext_bind;
+                        }
+                        if let Some(ext_username) = ext_settings.username {
+                            settings.username = ext_username;
+                        }
+                        if let Some(ext_password) = ext_settings.password {
+                            settings.password = ext_password;
+                        }
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     inbound.settings = settings;
                     inbounds.push(inbound);
@@ -296,8 +563,77 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
             } else {
                 outbound.bind = "0.0.0.0".to_string();
             }
+            if let Some(ext_slow_threshold) = ext_outbound.slow_threshold {
+                outbound.slow_threshold = ext_slow_threshold;
+            }
+            if let Some(ext_bind_interface) = ext_outbound.bind_interface {
+                outbound.bind_interface = ext_bind_interface;
+            }
+            if let Some(ext_connect_deadline) = ext_outbound.connect_deadline {
+                outbound.connect_deadline = ext_connect_deadline;
+            }
+            if let Some(ext_relay_buffer_size) = ext_outbound.relay_buffer_size {
+                outbound.relay_buffer_size = ext_relay_buffer_size;
+            }
+            if let Some(ext_max_lifetime) = ext_outbound.max_lifetime {
+                outbound.max_lifetime = ext_max_lifetime;
+            }
+            if let Some(ext_resolve) = ext_outbound.resolve {
+                outbound.resolve = ext_resolve;
+            }
             match outbound.protocol.as_str() {
-                "direct" | "drop" => {
+                "direct" => {
+                    if let Some(ext_settings) = ext_outbound.settings {
+                        let mut settings = internal::DirectOutboundSettings::new();
+                        let ext_settings: DirectOutboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_proxy_protocol) = ext_settings.proxy_protocol {
+                            settings.proxy_protocol = ext_proxy_protocol;
+                        }
+                        if let Some(ext_dial_failure_window) = ext_settings.dial_failure_window {
+                            settings.dial_failure_window = ext_dial_failure_window;
+                        }
+                        if let Some(ext_sequential_dial) = ext_settings.sequential_dial {
+                            settings.sequential_dial = ext_sequential_dial;
+                        }
+                        if let Some(ext_happy_eyeballs_delay) = ext_settings.happy_eyeballs_delay {
+                            settings.happy_eyeballs_delay = ext_happy_eyeballs_delay;
+                        }
+                        if let Some(ext_prefer_ipv4) = ext_settings.prefer_ipv4 {
+                            settings.prefer_ipv4 = ext_prefer_ipv4;
+                        }
+                        if let Some(ext_retry_on_reset) = ext_settings.retry_on_reset {
+                            settings.retry_on_reset = ext_retry_on_reset;
+                        }
+                        if let Some(ext_preserve_source_port) = ext_settings.preserve_source_port {
+                            settings.preserve_source_port = ext_preserve_source_port;
+                        }
+                        if let Some(ext_udp_batching) = ext_settings.udp_batching {
+                            settings.udp_batching = ext_udp_batching;
+                        }
+                        let settings = settings.write_to_bytes().unwrap();
+                        outbound.settings = settings;
+                    }
+                    outbounds.push(outbound);
+                }
+                "drop" => {
+                    outbounds.push(outbound);
+                }
+                "fixed" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid fixed outbound settings"));
+                    }
+                    let mut settings = internal::FixedOutboundSettings::new();
+                    let ext_settings: FixedOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.unwrap().get()).unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address;
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
                     outbounds.push(outbound);
                 }
                 "redirect" => {
@@ -313,6 +649,9 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     if let Some(ext_port) = ext_settings.port {
                         settings.port = ext_port as u32;
                     }
+                    if let Some(ext_proxy_protocol) = ext_settings.proxy_protocol {
+                        settings.proxy_protocol = ext_proxy_protocol;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -330,6 +669,50 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     if let Some(ext_port) = ext_settings.port {
                         settings.port = ext_port as u32; // TODO checks
                     }
+                    if let Some(ext_reassociate_base) = ext_settings.reassociate_base {
+                        settings.reassociate_base = ext_reassociate_base;
+                    }
+                    if let Some(ext_reassociate_max) = ext_settings.reassociate_max {
+                        settings.reassociate_max = ext_reassociate_max;
+                    } else {
+                        settings.reassociate_max = 10000;
+                    }
+                    if let Some(ext_reassociate_jitter) = ext_settings.reassociate_jitter {
+                        settings.reassociate_jitter = ext_reassociate_jitter;
+                    } else {
+                        settings.reassociate_jitter = 50;
+                    }
+                    if let Some(ext_reassociate_timeout) = ext_settings.reassociate_timeout {
+                        settings.reassociate_timeout = ext_reassociate_timeout;
+                    } else {
+                        settings.reassociate_timeout = 30;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "http" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid http outbound settings"));
+                    }
+                    let mut settings = internal::HTTPOutboundSettings::new();
+                    let ext_settings: HTTPOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.unwrap().get()).unwrap();
+                    if let Some(ext_address) = ext_settings.address {
+                        settings.address = ext_address; // TODO checks
+                    }
+                    if let Some(ext_port) = ext_settings.port {
+                        settings.port = ext_port as u32; // TODO checks
+                    }
+                    if let Some(ext_username) = ext_settings.username {
+                        settings.username = ext_username;
+                    }
+                    if let Some(ext_password) = ext_settings.password {
+                        settings.password = ext_password;
+                    }
+                    if let Some(ext_forward) = ext_settings.forward {
+                        settings.forward = ext_forward;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -441,6 +824,16 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                         if alpns.len() > 0 {
                             settings.alpn = alpns;
                         }
+                        if let Some(ext_insecure) = ext_settings.insecure {
+                            settings.insecure = ext_insecure;
+                        }
+                        if let Some(ext_pinned_spki) = ext_settings.pinned_spki {
+                            settings.pinned_spki =
+                                protobuf::RepeatedField::from_vec(ext_pinned_spki);
+                        }
+                        if let Some(ext_session_cache_size) = ext_settings.session_cache_size {
+                            settings.session_cache_size = ext_session_cache_size;
+                        }
                     }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
@@ -457,6 +850,15 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     if let Some(ext_path) = ext_settings.path {
                         settings.path = ext_path; // TODO checks
                     }
+                    if let Some(ext_host) = ext_settings.host {
+                        settings.host = ext_host;
+                    }
+                    if let Some(ext_max_early_data) = ext_settings.max_early_data {
+                        settings.max_early_data = ext_max_early_data;
+                    }
+                    if let Some(ext_header_name) = ext_settings.early_data_header_name {
+                        settings.early_data_header_name = ext_header_name;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -479,6 +881,134 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     outbound.settings = settings;
                     outbounds.push(outbound);
                 }
+                "mux" => {
+                    let mut settings = internal::MuxOutboundSettings::new();
+                    if let Some(ext_settings) = ext_outbound.settings {
+                        let ext_settings: MuxOutboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_max_streams) = ext_settings.max_streams {
+                            settings.max_streams = ext_max_streams;
+                        }
+                        if let Some(ext_max_connections) = ext_settings.max_connections {
+                            settings.max_connections = ext_max_connections;
+                        }
+                        if let Some(ext_receive_window) = ext_settings.receive_window {
+                            settings.receive_window = ext_receive_window;
+                        }
+                        if let Some(ext_keepalive_interval) = ext_settings.keepalive_interval {
+                            settings.keepalive_interval = ext_keepalive_interval;
+                        }
+                        if let Some(ext_keepalive_timeout) = ext_settings.keepalive_timeout {
+                            settings.keepalive_timeout = ext_keepalive_timeout;
+                        }
+                        if let Some(ext_idle_timeout) = ext_settings.idle_timeout {
+                            settings.idle_timeout = ext_idle_timeout;
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "quic" => {
+                    let mut settings = internal::QuicOutboundSettings::new();
+                    if let Some(ext_settings) = ext_outbound.settings {
+                        let ext_settings: QuicOutboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_address) = ext_settings.address {
+                            settings.address = ext_address; // TODO checks
+                        }
+                        if let Some(ext_port) = ext_settings.port {
+                            settings.port = ext_port as u32;
+                        }
+                        if let Some(ext_server_name) = ext_settings.server_name {
+                            settings.server_name = ext_server_name;
+                        }
+                        if let Some(ext_alpn) = ext_settings.alpn {
+                            settings.alpn = protobuf::RepeatedField::from_vec(ext_alpn);
+                        }
+                        if let Some(ext_certificates) = ext_settings.certificates {
+                            settings.certificates =
+                                protobuf::RepeatedField::from_vec(ext_certificates);
+                        }
+                        if let Some(ext_zero_rtt) = ext_settings.zero_rtt {
+                            settings.zero_rtt = ext_zero_rtt;
+                        }
+                        if let Some(ext_initial_window) = ext_settings.initial_window {
+                            settings.initial_window = ext_initial_window;
+                        }
+                        if let Some(ext_minimum_window) = ext_settings.minimum_window {
+                            settings.minimum_window = ext_minimum_window;
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "reject" => {
+                    let mut settings = internal::RejectOutboundSettings::new();
+                    if let Some(ext_settings) = ext_outbound.settings {
+                        let ext_settings: RejectOutboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_mode) = ext_settings.mode {
+                            settings.mode = ext_mode;
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "dns" => {
+                    let mut settings = internal::DNSOutboundSettings::new();
+                    if let Some(ext_settings) = ext_outbound.settings {
+                        let ext_settings: DNSOutboundSettings =
+                            serde_json::from_str(ext_settings.get()).unwrap();
+                        if let Some(ext_fake_ip) = ext_settings.fake_ip {
+                            settings.fake_ip = ext_fake_ip;
+                        }
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "obfs" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid obfs outbound settings"));
+                    }
+                    let mut settings = internal::ObfsOutboundSettings::new();
+                    let ext_settings: ObfsOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.unwrap().get()).unwrap();
+                    if let Some(ext_method) = ext_settings.method {
+                        settings.method = ext_method;
+                    } else {
+                        settings.method = "http".to_string();
+                    }
+                    if let Some(ext_host) = ext_settings.host {
+                        settings.host = ext_host;
+                    }
+                    if let Some(ext_path) = ext_settings.path {
+                        settings.path = ext_path;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "limit" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid limit outbound settings"));
+                    }
+                    let mut settings = internal::LimitOutboundSettings::new();
+                    let ext_settings: LimitOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.unwrap().get()).unwrap();
+                    if let Some(ext_rate) = ext_settings.rate {
+                        settings.rate = ext_rate;
+                    }
+                    if let Some(ext_global_rate) = ext_settings.global_rate {
+                        settings.global_rate = ext_global_rate;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
                 "tryall" => {
                     if ext_outbound.settings.is_none() {
                         return Err(anyhow!("invalid tryall outbound settings"));
@@ -496,6 +1026,51 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     } else {
                         settings.delay_base = 0;
                     }
+                    if let Some(ext_until) = ext_settings.until {
+                        settings.until = match ext_until.as_str() {
+                            "connect" => internal::TryAllOutboundSettings_Until::CONNECT,
+                            "write" => internal::TryAllOutboundSettings_Until::WRITE,
+                            "response" => internal::TryAllOutboundSettings_Until::RESPONSE,
+                            _ => return Err(anyhow!("invalid tryall until {}", ext_until)),
+                        };
+                    }
+                    if let Some(ext_skip_failing) = ext_settings.skip_failing {
+                        settings.skip_failing = ext_skip_failing;
+                    }
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "retry" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid retry outbound settings"));
+                    }
+                    let mut settings = internal::RetryOutboundSettings::new();
+                    let ext_settings: RetryOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.unwrap().get()).unwrap();
+                    if let Some(ext_actor) = ext_settings.actor {
+                        settings.actor = ext_actor;
+                    }
+                    settings.attempts = ext_settings.attempts.unwrap_or(3);
+                    settings.delay_base = ext_settings.delay_base.unwrap_or(100);
+                    settings.deadline = ext_settings.deadline.unwrap_or(0);
+                    let settings = settings.write_to_bytes().unwrap();
+                    outbound.settings = settings;
+                    outbounds.push(outbound);
+                }
+                "tee" => {
+                    if ext_outbound.settings.is_none() {
+                        return Err(anyhow!("invalid tee outbound settings"));
+                    }
+                    let mut settings = internal::TeeOutboundSettings::new();
+                    let ext_settings: TeeOutboundSettings =
+                        serde_json::from_str(ext_outbound.settings.unwrap().get()).unwrap();
+                    if let Some(ext_actor) = ext_settings.actor {
+                        settings.actor = ext_actor;
+                    }
+                    if let Some(ext_path) = ext_settings.path {
+                        settings.path = ext_path;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -512,6 +1087,9 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                             settings.actors.push(ext_actor);
                         }
                     }
+                    if let Some(ext_affinity_ttl) = ext_settings.affinity_ttl {
+                        settings.affinity_ttl = ext_affinity_ttl;
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -548,6 +1126,76 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     } else {
                         settings.failover = true;
                     }
+                    if let Some(ext_switch_margin) = ext_settings.switch_margin {
+                        let margin = ext_switch_margin.trim();
+                        let (value, percent) = match margin.strip_suffix('%') {
+                            Some(v) => (v, true),
+                            None => (margin.trim_end_matches("ms"), false),
+                        };
+                        let value: u32 = value
+                            .trim()
+                            .parse()
+                            .map_err(|_| anyhow!("invalid switch margin {}", &ext_switch_margin))?;
+                        if percent {
+                            settings.switch_margin_percent = value;
+                        } else {
+                            settings.switch_margin_ms = value;
+                        }
+                    }
+                    if let Some(ext_url) = ext_settings.throughput_probe_url {
+                        settings.throughput_probe_url = ext_url;
+                    }
+                    if let Some(ext_size) = ext_settings.throughput_probe_size {
+                        settings.throughput_probe_size = ext_size;
+                    }
+                    if let Some(ext_url_test) = ext_settings.url_test {
+                        settings.url_test = ext_url_test;
+                    }
+                    if let Some(ext_server) = ext_settings.dns_probe_server {
+                        settings.dns_probe_server = ext_server;
+                    }
+                    if let Some(ext_domain) = ext_settings.dns_probe_domain {
+                        settings.dns_probe_domain = ext_domain;
+                    }
+                    if let Some(ext_answers) = ext_settings.dns_probe_answers {
+                        for ext_answer in ext_answers {
+                            settings.dns_probe_answers.push(ext_answer);
+                        }
+                    }
+                    if let Some(ext_user_agent) = ext_settings.user_agent {
+                        settings.user_agent = ext_user_agent;
+                    }
+                    if let Some(ext_check_urls) = ext_settings.check_urls {
+                        for ext_check_url in ext_check_urls {
+                            settings.check_urls.push(ext_check_url);
+                        }
+                    }
+                    if let Some(ext_check_quorum) = ext_settings.check_quorum {
+                        settings.check_quorum = ext_check_quorum;
+                    }
+                    if let Some(ext_prewarm_backups) = ext_settings.prewarm_backups {
+                        settings.prewarm_backups = ext_prewarm_backups;
+                    }
+                    if let Some(ext_unprobed_actors) = ext_settings.unprobed_actors {
+                        let mut ext_unprobed_actors: Vec<(String, String)> =
+                            ext_unprobed_actors.into_iter().collect();
+                        ext_unprobed_actors.sort();
+                        for (actor, rank) in ext_unprobed_actors {
+                            let mut unprobed =
+                                internal::FailOverOutboundSettings_UnprobedActor::new();
+                            match rank.trim() {
+                                "first" => unprobed.rank = 0,
+                                "last" => unprobed.last = true,
+                                pos => {
+                                    unprobed.rank = pos.parse().map_err(|_| {
+                                        anyhow!("invalid rank {} of actor {}", &rank, &actor)
+                                    })?
+                                }
+                            }
+                            unprobed.actor = actor;
+                            settings.unprobed_actors.push(unprobed);
+                        }
+                    }
                     let settings = settings.write_to_bytes().unwrap();
                     outbound.settings = settings;
                     outbounds.push(outbound);
@@ -612,6 +1260,30 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     rule.domains.push(domain);
                 }
             }
+            if let Some(ext_snis) = ext_rule.sni {
+                for ext_sni in ext_snis {
+                    let mut domain = internal::RoutingRule_Domain::new();
+                    domain.field_type = internal::RoutingRule_Domain_Type::FULL;
+                    domain.value = ext_sni;
+                    rule.snis.push(domain);
+                }
+            }
+            if let Some(ext_sni_keywords) = ext_rule.sni_keyword {
+                for ext_sni_keyword in ext_sni_keywords {
+                    let mut domain = internal::RoutingRule_Domain::new();
+                    domain.field_type = internal::RoutingRule_Domain_Type::PLAIN;
+                    domain.value = ext_sni_keyword;
+                    rule.snis.push(domain);
+                }
+            }
+            if let Some(ext_sni_suffixes) = ext_rule.sni_suffix {
+                for ext_sni_suffix in ext_sni_suffixes {
+                    let mut domain = internal::RoutingRule_Domain::new();
+                    domain.field_type = internal::RoutingRule_Domain_Type::DOMAIN;
+                    domain.value = ext_sni_suffix;
+                    rule.snis.push(domain);
+                }
+            }
             if let Some(ext_geoips) = ext_rule.geoip {
                 for ext_geoip in ext_geoips {
                     let mut mmdb = internal::RoutingRule_Mmdb::new();
@@ -637,6 +1309,19 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                     }
                 }
             }
+            if let Some(ext_udp_ports) = ext_rule.udp_port {
+                for ext_udp_port in ext_udp_ports {
+                    rule.udp_ports.push(ext_udp_port as u32);
+                }
+            }
+            if let Some(ext_ports) = ext_rule.port {
+                for ext_port in ext_ports {
+                    rule.port_ranges.push(ext_port);
+                }
+            }
+            if let Some(ext_private_ip) = ext_rule.private_ip {
+                rule.private_ip = ext_private_ip;
+            }
             rules.push(rule);
         }
         drop(site_group_lists); // make sure it's released
@@ -653,6 +1338,47 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
                 servers.push(ext_server);
             }
         }
+        if let Some(ext_rules) = ext_dns.rules {
+            for ext_rule in ext_rules {
+                let mut rule = internal::DNS_Rule::new();
+                if let Some(ext_domains) = ext_rule.domain {
+                    for ext_domain in ext_domains {
+                        let mut domain = internal::RoutingRule_Domain::new();
+                        domain.field_type = internal::RoutingRule_Domain_Type::FULL;
+                        domain.value = ext_domain;
+                        rule.domains.push(domain);
+                    }
+                }
+                if let Some(ext_domain_keywords) = ext_rule.domain_keyword {
+                    for ext_domain_keyword in ext_domain_keywords {
+                        let mut domain = internal::RoutingRule_Domain::new();
+                        domain.field_type = internal::RoutingRule_Domain_Type::PLAIN;
+                        domain.value = ext_domain_keyword;
+                        rule.domains.push(domain);
+                    }
+                }
+                if let Some(ext_domain_suffixes) = ext_rule.domain_suffix {
+                    for ext_domain_suffix in ext_domain_suffixes {
+                        let mut domain = internal::RoutingRule_Domain::new();
+                        domain.field_type = internal::RoutingRule_Domain_Type::DOMAIN;
+                        domain.value = ext_domain_suffix;
+                        rule.domains.push(domain);
+                    }
+                }
+                if ext_rule.servers.is_empty() {
+                    return Err(anyhow!("no servers in dns rule"));
+                }
+                for ext_server in ext_rule.servers {
+                    rule.servers.push(ext_server);
+                }
+                dns.rules.push(rule);
+            }
+        }
+        if let Some(ext_warm_domains) = ext_dns.warm_domains {
+            for ext_warm_domain in ext_warm_domains {
+                dns.warm_domains.push(ext_warm_domain);
+            }
+        }
     }
     if dns.bind.is_empty() {
         dns.bind = "0.0.0.0".to_string();
@@ -663,12 +1389,44 @@ pub fn to_internal(json: Config) -> Result<internal::Config> {
     }
     dns.servers = servers;
 
+    let mut udp = internal::UDP::new();
+    if let Some(ext_udp) = json.udp {
+        if let Some(ext_nat_type) = ext_udp.nat_type {
+            match ext_nat_type.as_str() {
+                "cone" => udp.nat_type = internal::UDP_NatType::CONE,
+                "symmetric" => udp.nat_type = internal::UDP_NatType::SYMMETRIC,
+                _ => return Err(anyhow!("invalid udp nat type {}", ext_nat_type)),
+            }
+        }
+        if let Some(ext_session_timeout) = ext_udp.session_timeout {
+            udp.session_timeout = ext_session_timeout;
+        }
+        if let Some(ext_port_timeouts) = ext_udp.port_timeouts {
+            for (port, timeout) in ext_port_timeouts {
+                let mut port_timeout = internal::UDP_PortTimeout::new();
+                port_timeout.port = port
+                    .parse::<u16>()
+                    .map_err(|e| anyhow!("invalid udp port {}: {}", port, e))?
+                    as u32;
+                if timeout == 0 {
+                    return Err(anyhow!("invalid udp timeout 0 for port {}", port));
+                }
+                port_timeout.timeout = timeout;
+                udp.port_timeouts.push(port_timeout);
+            }
+        }
+    }
+
     let mut config = internal::Config::new();
     config.log = protobuf::SingularPtrField::some(log);
     config.inbounds = inbounds;
     config.outbounds = outbounds;
     config.routing_rules = rules;
     config.dns = protobuf::SingularPtrField::some(dns);
+    config.udp = protobuf::SingularPtrField::some(udp);
+    if let Some(ext_egress_rate) = json.egress_rate {
+        config.egress_rate = ext_egress_rate;
+    }
     Ok(config)
 }
 
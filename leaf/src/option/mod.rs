@@ -16,8 +16,12 @@ mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::*;
 
-/// Uplink timeout after downlink EOF.
-pub static TCP_UPLINK_TIMEOUT: u64 = 2;
+/// Time after which a relayed TCP connection with no traffic in either
+/// direction is closed, half-closed connections included.
+pub static TCP_IDLE_TIMEOUT: u64 = 300;
 
-/// Downlink timeout after uplink EOF.
-pub static TCP_DOWNLINK_TIMEOUT: u64 = 4;
+/// Initial size of the buffers relaying TCP data, unless set by the outbound.
+pub static RELAY_BUFFER_SIZE: usize = 16 * 1024;
+
+/// Size the buffers relaying TCP data may grow to on fast links.
+pub static MAX_RELAY_BUFFER_SIZE: usize = 256 * 1024;
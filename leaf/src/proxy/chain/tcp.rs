@@ -3,6 +3,7 @@ use std::net::SocketAddr;
 use std::{io, sync::Arc};
 
 use async_trait::async_trait;
+use log::*;
 
 use crate::{
     common::dns_client::DnsClient,
@@ -15,6 +16,37 @@ pub struct Handler {
     pub dns_client: Arc<DnsClient>,
 }
 
+impl Handler {
+    // The session seen by the i-th actor, targeting the next actor which
+    // connects to a proxy server, or the original destination if there's none.
+    fn next_session(&self, sess: &Session, i: usize) -> Session {
+        let mut new_sess = sess.clone();
+        for a in self.actors[i + 1..].iter() {
+            if let Some((connect_addr, port, _)) = a.tcp_connect_addr() {
+                if let Ok(addr) = SocksAddr::try_from(format!("{}:{}", connect_addr, port)) {
+                    new_sess.destination = addr;
+                    break;
+                }
+            }
+        }
+        new_sess
+    }
+
+    // Each actor from the `from`-th one consumes the stream produced by the
+    // previous one.
+    async fn handle_stream(
+        &self,
+        sess: &Session,
+        mut stream: Box<dyn ProxyStream>,
+        from: usize,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        for (i, a) in self.actors.iter().enumerate().skip(from) {
+            stream = a.handle(&self.next_session(sess, i), Some(stream)).await?;
+        }
+        Ok(Box::new(SimpleStream(stream)))
+    }
+}
+
 #[async_trait]
 impl ProxyTcpHandler for Handler {
     fn name(&self) -> &str {
@@ -30,52 +62,316 @@ impl ProxyTcpHandler for Handler {
         None
     }
 
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.actors
+            .iter()
+            .enumerate()
+            .any(|(i, a)| a.is_warm(&self.next_session(sess, i)))
+    }
+
     async fn handle<'a>(
         &'a self,
         sess: &'a Session,
         stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>> {
-        if let Some(mut stream) = stream {
-            for (i, a) in self.actors.iter().enumerate() {
-                let mut new_sess = sess.clone();
-                for j in (i + 1)..self.actors.len() {
-                    if let Some((connect_addr, port, _)) = self.actors[j].tcp_connect_addr() {
-                        if let Ok(addr) = SocksAddr::try_from(format!("{}:{}", connect_addr, port))
-                        {
-                            new_sess.destination = addr;
-                        }
-                    }
+        if let Some(stream) = stream {
+            return self.handle_stream(sess, stream, 0).await;
+        }
+
+        // The last warm actor opens a stream on its pooled connection, the
+        // dial and the actors below it are skipped.
+        for (i, a) in self.actors.iter().enumerate().rev() {
+            let next_sess = self.next_session(sess, i);
+            if a.is_warm(&next_sess) {
+                match a.handle(&next_sess, None).await {
+                    Ok(stream) => return self.handle_stream(sess, stream, i + 1).await,
+                    Err(e) => debug!("pooled connection of [{}] failed: {}", a.tag(), e),
                 }
-                stream = a.handle(&new_sess, Some(stream)).await?;
+                break;
             }
-
-            return Ok(Box::new(SimpleStream(stream)));
         }
 
         for a in self.actors.iter() {
             if let Some((connect_addr, port, bind_addr)) = a.tcp_connect_addr() {
-                let mut stream = self
-                    .dial_tcp_stream(self.dns_client.clone(), &bind_addr, &connect_addr, &port)
-                    .await?;
-
-                for (i, a) in self.actors.iter().enumerate() {
-                    let mut new_sess = sess.clone();
-                    for j in (i + 1)..self.actors.len() {
-                        if let Some((connect_addr, port, _)) = self.actors[j].tcp_connect_addr() {
-                            if let Ok(addr) =
-                                SocksAddr::try_from(format!("{}:{}", connect_addr, port))
-                            {
-                                new_sess.destination = addr;
-                                break;
-                            }
-                        }
-                    }
-                    stream = a.handle(&new_sess, Some(stream)).await?;
-                }
-
-                return Ok(Box::new(SimpleStream(stream)));
+                // Dialed by the actor, which may not connect over TCP.
+                let stream = ProxyTcpHandler::dial_tcp_stream(
+                    &**a,
+                    self.dns_client.clone(),
+                    &bind_addr,
+                    &connect_addr,
+                    &port,
+                )
+                .await?;
+                return self.handle_stream(sess, stream, 0).await;
             }
         }
         Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid chain"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::{ProxyDatagram, ProxyHandlerType, ProxyUdpHandler, UdpTransportType};
+
+    // Writes its marker to the stream it's given and records the destination
+    // it's asked to connect to.
+    struct Marker {
+        marker: u8,
+        connect_addr: Option<(String, u16, SocketAddr)>,
+        seen: Arc<Mutex<Vec<(u8, String)>>>,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for Marker {
+        fn name(&self) -> &str {
+            "marker"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            self.connect_addr.clone()
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            sess: &'a Session,
+            stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            let mut stream = stream.unwrap();
+            self.seen
+                .lock()
+                .unwrap()
+                .push((self.marker, sess.destination.to_string()));
+            stream.write_all(&[self.marker]).await?;
+            Ok(stream)
+        }
+    }
+
+    // Opens its streams on its own connection to `addr` if it's warm, like a
+    // pooled transport.
+    struct Pooled {
+        addr: SocketAddr,
+        warm: bool,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for Pooled {
+        fn name(&self) -> &str {
+            "pooled"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn is_warm(&self, _sess: &Session) -> bool {
+            self.warm
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            match stream {
+                Some(stream) => Ok(stream),
+                None => Ok(Box::new(SimpleStream(TcpStream::connect(self.addr).await?))),
+            }
+        }
+    }
+
+    struct NoUdp;
+
+    #[async_trait]
+    impl ProxyUdpHandler for NoUdp {
+        fn name(&self) -> &str {
+            "marker"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Err(io::Error::new(io::ErrorKind::Other, "not supported"))
+        }
+    }
+
+    fn marker(
+        marker: u8,
+        connect_addr: Option<(String, u16, SocketAddr)>,
+        seen: &Arc<Mutex<Vec<(u8, String)>>>,
+    ) -> Arc<dyn ProxyHandler> {
+        crate::proxy::Handler::new(
+            format!("marker-{}", marker),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Marker {
+                marker,
+                connect_addr,
+                seen: seen.clone(),
+            }),
+            Box::new(NoUdp),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_chain_order() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 3];
+            stream.read_exact(&mut buf).await.unwrap();
+            buf
+        });
+
+        let bind_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let handler = Handler {
+            actors: vec![
+                marker(
+                    b'a',
+                    Some((server_addr.ip().to_string(), server_addr.port(), bind_addr)),
+                    &seen,
+                ),
+                marker(b'b', Some(("1.2.3.4".to_string(), 8388, bind_addr)), &seen),
+                marker(b'c', None, &seen),
+            ],
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        stream.flush().await.unwrap();
+
+        assert_eq!(&server.await.unwrap(), b"abc");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (b'a', "1.2.3.4:8388".to_string()),
+                (b'b', "example.com:443".to_string()),
+                (b'c', "example.com:443".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chain_skips_dial_when_warm() {
+        let mut dialed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dialed_addr = dialed.local_addr().unwrap();
+        let mut pooled = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let pooled_addr = pooled.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = pooled.accept().await.unwrap();
+            let mut buf = [0u8; 1];
+            stream.read_exact(&mut buf).await.unwrap();
+            buf
+        });
+
+        let bind_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let warm = crate::proxy::Handler::new(
+            "pooled".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Pooled {
+                addr: pooled_addr,
+                warm: true,
+            }),
+            Box::new(NoUdp),
+        );
+        let handler = Handler {
+            actors: vec![
+                marker(
+                    b'a',
+                    Some((dialed_addr.ip().to_string(), dialed_addr.port(), bind_addr)),
+                    &seen,
+                ),
+                warm,
+                marker(b'c', None, &seen),
+            ],
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        assert!(handler.is_warm(&sess));
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        stream.flush().await.unwrap();
+
+        // Only the actors above the warm one ran, nothing was dialed.
+        assert_eq!(&server.await.unwrap(), b"c");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(b'c', "example.com:443".to_string())]
+        );
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(100), dialed.accept())
+                .await
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "outbound-redirect")]
+    #[tokio::test]
+    async fn test_chain_dials_redirect() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            // Nothing else connected.
+            let next =
+                tokio::time::timeout(std::time::Duration::from_millis(100), listener.accept())
+                    .await;
+            (buf, next.is_err())
+        });
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let redirect = crate::proxy::Handler::new(
+            "redirect".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(crate::proxy::redirect::TcpHandler {
+                address: server_addr.ip().to_string(),
+                port: server_addr.port(),
+                proxy_protocol: None,
+            }),
+            Box::new(NoUdp),
+        );
+        let handler = Handler {
+            actors: vec![marker(b'a', None, &seen), redirect],
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        // The chain dials the redirect target, the actors before it target it.
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        stream.write_all(b"payload").await.unwrap();
+        drop(stream);
+
+        assert_eq!(server.await.unwrap(), (b"apayload".to_vec(), true));
+        assert_eq!(*seen.lock().unwrap(), vec![(b'a', server_addr.to_string())]);
+    }
+}
@@ -1,71 +1,90 @@
 use std::convert::TryFrom;
 use std::io;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::pin::Pin;
 use std::sync::Arc;
-use std::task::{Context, Poll};
 
 use async_trait::async_trait;
-use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::UdpSocket;
 
 use crate::{
     common::dns_client::DnsClient,
     proxy::{
-        stream::SimpleStream, ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf,
-        ProxyHandler, ProxyStream, ProxyUdpHandler, SimpleDatagram, UdpTransportType,
+        ProxyDatagram, ProxyHandler, ProxyStream, ProxyUdpHandler, SimpleDatagram, UdpTransportType,
     },
     session::{Session, SocksAddr},
 };
 
-struct DatagramToStream {
-    recv: Box<dyn ProxyDatagramRecvHalf>,
-    send: Box<dyn ProxyDatagramSendHalf>,
-    target: SocketAddr,
+pub struct Handler {
+    pub actors: Vec<Arc<dyn ProxyHandler>>,
+    pub dns_client: Arc<DnsClient>,
 }
 
-impl AsyncRead for DatagramToStream {
-    fn poll_read(
-        self: Pin<&mut Self>,
-        cx: &mut Context,
-        buf: &mut [u8],
-    ) -> Poll<io::Result<usize>> {
-        // FIXME use a large internal buffor recv?
-        let mut recv = self.get_mut().recv.recv_from(buf);
-        match recv.as_mut().poll(cx) {
-            Poll::Ready(res) => match res {
-                Ok((n, _)) => Poll::Ready(Ok(n)),
-                Err(e) => Poll::Ready(Err(e)),
-            },
-
-            Poll::Pending => Poll::Pending,
+impl Handler {
+    // The session seen by the i-th actor, targeting the next actor which
+    // connects to a proxy server, or the original destination if there's none.
+    fn next_session(&self, sess: &Session, i: usize) -> Session {
+        let mut new_sess = sess.clone();
+        for a in self.actors[i + 1..].iter() {
+            if let Some((connect_addr, port, _)) = a.udp_connect_addr() {
+                if let Ok(addr) = SocksAddr::try_from(format!("{}:{}", connect_addr, port)) {
+                    new_sess.destination = addr;
+                    break;
+                }
+            }
         }
+        new_sess
     }
-}
 
-impl AsyncWrite for DatagramToStream {
-    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
-        let me = self.get_mut();
-        let mut send = me.send.send_to(buf, &me.target);
-        match send.as_mut().poll(cx) {
-            Poll::Ready(res) => match res {
-                Ok(n) => Poll::Ready(Ok(n)),
-                Err(e) => Poll::Ready(Err(e)),
-            },
-            Poll::Pending => Poll::Pending,
-        }
+    // Can only transport unreliable upon reliable, not the reverse, if any
+    // actor starting from the i-th one requires reliable transport, reliable
+    // transport must be used up to there.
+    fn needs_stream(&self, i: usize) -> bool {
+        self.actors[i..]
+            .iter()
+            .any(|a| a.udp_transport_type() == UdpTransportType::Stream)
     }
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
-    }
-    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
+
+    // Chains the datagram through the actors starting from the i-th one, each
+    // wraps the datagram of the previous one. Actors needing a control
+    // connection, e.g. socks, dial it directly.
+    async fn chain_datagram(
+        &self,
+        sess: &Session,
+        i: usize,
+        mut dgram: Box<dyn ProxyDatagram>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        for (j, a) in self.actors.iter().enumerate().skip(i) {
+            dgram = a
+                .connect(&self.next_session(sess, j), Some(dgram), None)
+                .await?;
+        }
+        Ok(dgram)
     }
-}
 
-pub struct Handler {
-    pub actors: Vec<Arc<dyn ProxyHandler>>,
-    pub dns_client: Arc<DnsClient>,
+    // Sets up the actors on a stream connected to the first actor.
+    async fn connect_over_stream(
+        &self,
+        sess: &Session,
+        mut stream: Box<dyn ProxyStream>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        for (i, a) in self.actors.iter().enumerate() {
+            let new_sess = self.next_session(sess, i);
+            if i == self.actors.len() - 1 && a.udp_transport_type() == UdpTransportType::Stream {
+                return a.connect(&new_sess, None, Some(stream)).await;
+            }
+            if !self.needs_stream(i) {
+                // The following actors are all Packet type, they send their
+                // packets as they are, addressed to their server, which a
+                // stream can't carry.
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "can not chain datagram transports over a stream",
+                ));
+            }
+            stream = a.handle(&new_sess, Some(stream)).await?;
+        }
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid chain"))
+    }
 }
 
 #[async_trait]
@@ -95,65 +114,26 @@ impl ProxyUdpHandler for Handler {
     async fn connect<'a>(
         &'a self,
         sess: &'a Session,
-        _datagram: Option<Box<dyn ProxyDatagram>>,
+        datagram: Option<Box<dyn ProxyDatagram>>,
         stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyDatagram>> {
-        if let Some(mut stream) = stream {
-            for (i, a) in self.actors.iter().enumerate() {
-                let mut new_sess = sess.clone();
-                for j in (i + 1)..self.actors.len() {
-                    if let Some((connect_addr, port, _)) = self.actors[j].udp_connect_addr() {
-                        if let Ok(addr) = SocksAddr::try_from(format!("{}:{}", connect_addr, port))
-                        {
-                            new_sess.destination = addr;
-                            break;
-                        }
-                    }
-                }
-
-                if i == self.actors.len() - 1 {
-                    let dgram = a.connect(&new_sess, None, Some(stream)).await?;
-                    return Ok(dgram);
-                } else {
-                    let mut transport_type: UdpTransportType = UdpTransportType::Packet;
-                    for k in i..self.actors.len() {
-                        if self.actors[k].udp_transport_type() == UdpTransportType::Stream {
-                            transport_type = UdpTransportType::Stream;
-                        }
-                    }
-                    match transport_type {
-                        UdpTransportType::Stream => {
-                            stream = a.handle(&new_sess, Some(stream)).await?;
-                        }
-                        UdpTransportType::Packet => {
-                            let dgram = a.connect(&new_sess, None, Some(stream)).await?;
-                            let (r, s) = dgram.split();
-                            stream = Box::new(SimpleStream(DatagramToStream {
-                                recv: r,
-                                send: s,
-                                target: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0),
-                                // target: new_sess.destination.must_ip(), // FIXME
-                            }));
-                        }
-                        UdpTransportType::Unknown => {
-                            return Err(io::Error::new(
-                                io::ErrorKind::Other,
-                                "unknown transport type",
-                            ));
-                        }
-                    }
-                }
-            }
+        if let Some(stream) = stream {
+            return self.connect_over_stream(sess, stream).await;
         }
 
-        let mut transport_type: UdpTransportType = UdpTransportType::Packet;
-        for a in self.actors.iter() {
-            if a.udp_transport_type() == UdpTransportType::Stream {
-                transport_type = UdpTransportType::Stream;
+        // Chained itself, the first actor wraps the datagram given.
+        if let Some(dgram) = datagram {
+            if self.needs_stream(0) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "can not chain reliable transports over a datagram",
+                ));
             }
+            return self.chain_datagram(sess, 0, dgram).await;
         }
+
         // if all actors are Packet transports, simply chaining the datagrams.
-        if let UdpTransportType::Packet = transport_type {
+        if !self.needs_stream(0) {
             let mut bind_addr: SocketAddr =
                 SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0);
             for a in self.actors.iter() {
@@ -162,107 +142,266 @@ impl ProxyUdpHandler for Handler {
                 }
             }
             let socket = UdpSocket::bind(bind_addr).await?;
-            let mut dgram: Box<dyn ProxyDatagram> = Box::new(SimpleDatagram(socket));
-
-            for (i, a) in self.actors.iter().enumerate() {
-                let mut new_sess = sess.clone();
-                for j in (i + 1)..self.actors.len() {
-                    if let Some((connect_addr, port, _)) = self.actors[j].udp_connect_addr() {
-                        if let Ok(addr) = SocksAddr::try_from(format!("{}:{}", connect_addr, port))
-                        {
-                            new_sess.destination = addr;
-                            break;
-                        }
-                    }
-                }
-                dgram = a.connect(&new_sess, Some(dgram), None).await?;
-            }
-            return Ok(dgram);
+            let dgram: Box<dyn ProxyDatagram> = Box::new(SimpleDatagram(socket));
+            return self.chain_datagram(sess, 0, dgram).await;
         }
 
         for a in self.actors.iter() {
             if let Some((connect_addr, port, bind_addr)) = a.udp_connect_addr() {
-                let mut stream = self
+                let stream = self
                     .dial_tcp_stream(self.dns_client.clone(), &bind_addr, &connect_addr, &port)
                     .await?;
-                let mut datagram: Option<Box<dyn ProxyDatagram>> = None;
-
-                let mut last_index = 0;
-
-                for (i, a) in self.actors.iter().enumerate() {
-                    last_index = i;
-                    let mut new_sess = sess.clone();
-                    for j in (i + 1)..self.actors.len() {
-                        if let Some((connect_addr, port, _)) = self.actors[j].udp_connect_addr() {
-                            if let Ok(addr) =
-                                SocksAddr::try_from(format!("{}:{}", connect_addr, port))
-                            {
-                                new_sess.destination = addr;
-                                break;
-                            }
-                        }
-                    }
-
-                    if i == self.actors.len() - 1 {
-                        let dgram = a.connect(&new_sess, datagram, Some(stream)).await?;
-                        return Ok(dgram);
-                    } else {
-                        let mut transport_type: UdpTransportType = UdpTransportType::Packet;
-
-                        // can only transport unreliable upon reliable, not the
-                        // reverse, if any following actor requires reliable transport,
-                        // we must also use reliable transport here.
-                        for k in i..self.actors.len() {
-                            if self.actors[k].udp_transport_type() == UdpTransportType::Stream {
-                                transport_type = UdpTransportType::Stream;
-                            }
-                        }
-
-                        match transport_type {
-                            UdpTransportType::Stream => {
-                                stream = a.handle(&new_sess, Some(stream)).await?;
-                            }
-                            UdpTransportType::Packet => {
-                                // once a Packet type is encountered, it's guaranteed all
-                                // following actors are Packet type.
-                                datagram =
-                                    Some(a.connect(&new_sess, datagram, Some(stream)).await?);
-                                break;
-                            }
-                            UdpTransportType::Unknown => {
-                                return Err(io::Error::new(
-                                    io::ErrorKind::Other,
-                                    "unknown transport type",
-                                ));
-                            }
-                        }
-                    }
-                }
-
-                // catch up the last actor index, and treats all the remaining
-                // actors are Packet transports, and they should be
-                for i in (last_index + 1)..self.actors.len() {
-                    let mut new_sess = sess.clone();
-                    for j in (i + 1)..self.actors.len() {
-                        if let Some((connect_addr, port, _)) = self.actors[j].udp_connect_addr() {
-                            if let Ok(addr) =
-                                SocksAddr::try_from(format!("{}:{}", connect_addr, port))
-                            {
-                                new_sess.destination = addr;
-                                break;
-                            }
-                        }
-                    }
-
-                    if i == self.actors.len() - 1 {
-                        let dgram = self.actors[i].connect(&new_sess, datagram, None).await?;
-                        return Ok(dgram);
-                    } else {
-                        datagram = Some(self.actors[i].connect(&new_sess, datagram, None).await?);
-                    }
-                }
+                return self.connect_over_stream(sess, stream).await;
             }
         }
         Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid chain"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::proxy::{
+        ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyHandlerType, ProxyTcpHandler,
+    };
+
+    struct NoTcp;
+
+    #[async_trait]
+    impl ProxyTcpHandler for NoTcp {
+        fn name(&self) -> &str {
+            "marker"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            Err(io::Error::new(io::ErrorKind::Other, "not supported"))
+        }
+    }
+
+    // Prefixes the packets it sends with its marker, strips it from the ones
+    // it receives.
+    struct Marker(u8);
+
+    #[async_trait]
+    impl ProxyUdpHandler for Marker {
+        fn name(&self) -> &str {
+            "marker"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Packet
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            let dgram = datagram
+                .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no datagram to wrap"))?;
+            Ok(Box::new(Marked(self.0, dgram)))
+        }
+    }
+
+    struct Marked(u8, Box<dyn ProxyDatagram>);
+
+    impl ProxyDatagram for Marked {
+        fn split(
+            self: Box<Self>,
+        ) -> (
+            Box<dyn ProxyDatagramRecvHalf>,
+            Box<dyn ProxyDatagramSendHalf>,
+        ) {
+            let (r, s) = self.1.split();
+            (
+                Box::new(MarkedRecvHalf(self.0, r)),
+                Box::new(MarkedSendHalf(self.0, s)),
+            )
+        }
+    }
+
+    struct MarkedRecvHalf(u8, Box<dyn ProxyDatagramRecvHalf>);
+
+    #[async_trait]
+    impl ProxyDatagramRecvHalf for MarkedRecvHalf {
+        async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+            let mut buf2 = vec![0u8; buf.len() + 1];
+            let (n, addr) = self.1.recv_from(&mut buf2).await?;
+            if n == 0 || buf2[0] != self.0 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "missing marker"));
+            }
+            buf[..n - 1].copy_from_slice(&buf2[1..n]);
+            Ok((n - 1, addr))
+        }
+    }
+
+    struct MarkedSendHalf(u8, Box<dyn ProxyDatagramSendHalf>);
+
+    #[async_trait]
+    impl ProxyDatagramSendHalf for MarkedSendHalf {
+        async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+            let mut buf2 = vec![self.0];
+            buf2.extend_from_slice(buf);
+            self.1.send_to(&buf2, target).await?;
+            Ok(buf.len())
+        }
+    }
+
+    // A stream transport passing the stream it wraps through.
+    struct Wrap;
+
+    #[async_trait]
+    impl ProxyTcpHandler for Wrap {
+        fn name(&self) -> &str {
+            "wrap"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no stream to wrap"))
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Wrap {
+        fn name(&self) -> &str {
+            "wrap"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Stream
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Err(io::Error::new(io::ErrorKind::Other, "not supported"))
+        }
+    }
+
+    fn marker(marker: u8) -> Arc<dyn ProxyHandler> {
+        crate::proxy::Handler::new(
+            format!("marker-{}", marker),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(NoTcp),
+            Box::new(Marker(marker)),
+        )
+    }
+
+    fn chain(markers: &[u8]) -> Handler {
+        Handler {
+            actors: markers.iter().map(|m| marker(*m)).collect(),
+            dns_client: Arc::new(DnsClient::default()),
+        }
+    }
+
+    // Echoes a packet, returns it.
+    async fn echo_once() -> (SocketAddr, tokio::task::JoinHandle<Vec<u8>>) {
+        let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (n, from) = socket.recv_from(&mut buf).await.unwrap();
+            socket.send_to(&buf[..n], &from).await.unwrap();
+            buf[..n].to_vec()
+        });
+        (addr, server)
+    }
+
+    async fn ping(dgram: Box<dyn ProxyDatagram>, server: SocketAddr) -> Vec<u8> {
+        let (mut r, mut s) = dgram.split();
+        s.send_to(b"ping", &server).await.unwrap();
+        let mut buf = [0u8; 64];
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(from, server);
+        buf[..n].to_vec()
+    }
+
+    fn session(server: SocketAddr) -> Session {
+        Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Ip(server),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_datagrams() {
+        let (addr, server) = echo_once().await;
+        let dgram = chain(b"ab")
+            .connect(&session(addr), None, None)
+            .await
+            .unwrap();
+        assert_eq!(ping(dgram, addr).await, b"ping");
+        // The first actor is the outermost on the wire.
+        assert_eq!(server.await.unwrap(), b"abping");
+    }
+
+    #[tokio::test]
+    async fn test_chain_over_datagram() {
+        let (addr, server) = echo_once().await;
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let outer: Box<dyn ProxyDatagram> =
+            Box::new(Marked(b'a', Box::new(SimpleDatagram(socket))));
+        let dgram = chain(b"bc")
+            .connect(&session(addr), Some(outer), None)
+            .await
+            .unwrap();
+        assert_eq!(ping(dgram, addr).await, b"ping");
+        assert_eq!(server.await.unwrap(), b"abcping");
+    }
+
+    #[tokio::test]
+    async fn test_chain_datagrams_over_stream_rejected() {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stream, _) = tokio::join!(tokio::net::TcpStream::connect(addr), listener.accept());
+        let wrap = crate::proxy::Handler::new(
+            "wrap".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Wrap),
+            Box::new(Wrap),
+        );
+        let handler = Handler {
+            actors: vec![wrap, marker(b'a')],
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let stream: Box<dyn ProxyStream> = Box::new(crate::proxy::SimpleStream(stream.unwrap()));
+        let err = handler
+            .connect(&session(addr), None, Some(stream))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
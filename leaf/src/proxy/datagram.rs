@@ -1,12 +1,19 @@
-use std::{io, net::SocketAddr};
+use std::{cmp::min, convert::TryFrom, io, net::SocketAddr};
 
 use async_trait::async_trait;
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{Buf, BufMut, BytesMut};
+use log::*;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio::net::{
     udp::{RecvHalf, SendHalf},
     UdpSocket,
 };
 
-use super::{ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf};
+use super::{
+    ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyHandler, UdpTransportType,
+};
+use crate::session::{Session, SocksAddr, SocksAddrWireType};
 
 pub struct SimpleDatagramRecvHalf(RecvHalf);
 
@@ -41,4 +48,522 @@ impl ProxyDatagram for SimpleDatagram {
             Box::new(SimpleDatagramSendHalf(s)),
         )
     }
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+}
+
+/// Frames datagrams carried over a reliable stream.
+///
+/// Each frame is the SOCKS address of the remote peer, followed by a 2-byte
+/// payload length, optionally a CRLF, and the payload.
+#[derive(Clone, Copy, Default)]
+pub struct DatagramCodec {
+    crlf: bool,
+}
+
+impl DatagramCodec {
+    /// The trojan framing, with a CRLF after the payload length.
+    pub fn with_crlf() -> Self {
+        DatagramCodec { crlf: true }
+    }
+
+    fn header_size(&self) -> usize {
+        if self.crlf {
+            2 + 2
+        } else {
+            2
+        }
+    }
+
+    /// Appends the frame of `payload` to `dst`.
+    pub fn encode(&self, addr: &SocksAddr, payload: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        if payload.len() > u16::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "udp payload too large",
+            ));
+        }
+        dst.reserve(addr.size() + self.header_size() + payload.len());
+        addr.write_buf(dst, SocksAddrWireType::PortLast)?;
+        dst.put_u16(payload.len() as u16);
+        if self.crlf {
+            dst.put_slice(b"\r\n");
+        }
+        dst.put_slice(payload);
+        Ok(())
+    }
+
+    /// Takes the first frame off `src`, or returns `None` and leaves `src`
+    /// untouched if it's not complete yet.
+    pub fn decode(&self, src: &mut BytesMut) -> io::Result<Option<(SocksAddr, BytesMut)>> {
+        let addr_size = match SocksAddr::peek_size(src)? {
+            Some(n) => n,
+            None => return Ok(None),
+        };
+        let header_size = addr_size + self.header_size();
+        if src.len() < header_size {
+            return Ok(None);
+        }
+        let payload_len = BigEndian::read_u16(&src[addr_size..addr_size + 2]) as usize;
+        if self.crlf && &src[addr_size + 2..header_size] != b"\r\n" {
+            return Err(io::Error::new(io::ErrorKind::Other, "expected CRLF"));
+        }
+        if src.len() < header_size + payload_len {
+            return Ok(None);
+        }
+        let addr = SocksAddr::try_from((&src[..addr_size], SocksAddrWireType::PortLast))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        src.advance(header_size);
+        Ok(Some((addr, src.split_to(payload_len))))
+    }
+}
+
+/// Reads framed datagrams from a stream.
+///
+/// A frame may arrive split across several reads, or together with the
+/// following frames in a single read, what's read past a frame is kept for
+/// the next one.
+pub struct DatagramReader<R> {
+    inner: R,
+    codec: DatagramCodec,
+    buf: BytesMut,
+}
+
+impl<R: AsyncRead + Unpin> DatagramReader<R> {
+    pub fn new(inner: R, codec: DatagramCodec) -> Self {
+        DatagramReader {
+            inner,
+            codec,
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Reads the next whole datagram along with the address of the remote
+    /// peer.
+    pub async fn read_frame(&mut self) -> io::Result<(SocksAddr, BytesMut)> {
+        loop {
+            if let Some(frame) = self.codec.decode(&mut self.buf)? {
+                return Ok(frame);
+            }
+            let len = self.buf.len();
+            self.buf.resize(len + READ_CHUNK_SIZE, 0);
+            let res = self.inner.read(&mut self.buf[len..]).await;
+            self.buf.truncate(len + *res.as_ref().unwrap_or(&0));
+            if res? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+    }
+
+    /// Reads the next datagram into `buf`, truncating it if `buf` is too
+    /// small. Only IP addresses are accepted for the remote peer.
+    pub async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (addr, payload) = self.read_frame().await?;
+        let addr = match addr {
+            SocksAddr::Ip(a) => a,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "unexpected domain address",
+                ))
+            }
+        };
+        let n = min(payload.len(), buf.len());
+        if n < payload.len() {
+            warn!(
+                "truncated udp payload, buf size too small: {} < {}",
+                buf.len(),
+                payload.len()
+            );
+        }
+        buf[..n].copy_from_slice(&payload[..n]);
+        Ok((n, addr))
+    }
+}
+
+const READ_CHUNK_SIZE: usize = 2 * 1024;
+
+/// Carries datagrams over a reliable stream, for UDP transports of the
+/// `UdpTransportType::Stream` kind.
+///
+/// Datagrams are framed with the default `DatagramCodec`.
+pub struct StreamDatagram<S>(pub S);
+
+impl<S> ProxyDatagram for StreamDatagram<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Send + Sync + Unpin,
+{
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        let (r, w) = tokio::io::split(self.0);
+        (
+            Box::new(StreamDatagramRecvHalf(DatagramReader::new(
+                r,
+                DatagramCodec::default(),
+            ))),
+            Box::new(StreamDatagramSendHalf(w)),
+        )
+    }
+}
+
+pub struct StreamDatagramRecvHalf<S>(DatagramReader<ReadHalf<S>>);
+
+#[async_trait]
+impl<S> ProxyDatagramRecvHalf for StreamDatagramRecvHalf<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.0.recv_from(buf).await
+    }
+}
+
+pub struct StreamDatagramSendHalf<S>(WriteHalf<S>);
+
+#[async_trait]
+impl<S> ProxyDatagramSendHalf for StreamDatagramSendHalf<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync,
+{
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        let mut data = BytesMut::new();
+        DatagramCodec::default().encode(&SocksAddr::from(*target), buf, &mut data)?;
+        // A frame must be written as a whole, or the stream is out of sync.
+        self.0.write_all(&data).await?;
+        Ok(buf.len())
+    }
+}
+
+/// Connects the UDP side of `handler` for a caller exchanging single
+/// packets, e.g. the NAT manager, according to the transport it reports.
+///
+/// Packet transports carry the packets as they are, and so do stream
+/// transports dialing their own connection, e.g. trojan, which frame the
+/// packets over it. The other stream transports only wrap a stream, e.g. tls
+/// or ws, there's no server for them to carry the packets to.
+pub async fn connect_packets(
+    handler: &dyn ProxyHandler,
+    sess: &Session,
+) -> io::Result<Box<dyn ProxyDatagram>> {
+    match handler.udp_transport_type() {
+        UdpTransportType::Stream if handler.udp_connect_addr().is_none() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("[{}] can't carry packets without a server", handler.tag()),
+        )),
+        _ => handler.connect(sess, None, None).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use std::sync::{atomic::Ordering, Arc};
+
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::testing::{udp_actor, Behavior, MockUdpHandler};
+    use crate::proxy::{Handler, ProxyHandlerType, ProxyStream, ProxyTcpHandler, ProxyUdpHandler};
+
+    // Returns the given chunks one per read.
+    struct Chunks(Vec<Vec<u8>>);
+
+    impl AsyncRead for Chunks {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.0.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            let chunk = self.0.remove(0);
+            assert!(chunk.len() <= buf.len());
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Poll::Ready(Ok(chunk.len()))
+        }
+    }
+
+    fn frame(codec: DatagramCodec, addr: SocketAddr, payload: &[u8]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        codec
+            .encode(&SocksAddr::from(addr), payload, &mut buf)
+            .unwrap();
+        buf.to_vec()
+    }
+
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (client.unwrap(), server.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_stream_datagram_framing() {
+        let (client, mut server) = tcp_pair().await;
+        let (_, mut s) = Box::new(StreamDatagram(client)).split();
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 53);
+        assert_eq!(s.send_to(b"hello", &target).await.unwrap(), 5);
+        let mut buf = [0u8; 1 + 4 + 2 + 2 + 5];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(
+            &buf[..],
+            &[1, 1, 2, 3, 4, 0, 53, 0, 5, b'h', b'e', b'l', b'l', b'o'][..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_datagram_round_trip() {
+        let (client, server) = tcp_pair().await;
+        let (mut client_r, mut client_s) = Box::new(StreamDatagram(client)).split();
+        let (mut server_r, mut server_s) = Box::new(StreamDatagram(server)).split();
+
+        let targets = vec![
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 443),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 9),
+        ];
+        let payloads: Vec<Vec<u8>> = vec![b"a".to_vec(), vec![7u8; 1400], Vec::new()];
+        for (target, payload) in targets.iter().zip(payloads.iter()) {
+            client_s.send_to(payload, target).await.unwrap();
+        }
+
+        // Echoes every datagram back with the peer address as the source,
+        // boundaries must be preserved.
+        let mut buf = [0u8; 2048];
+        for (target, payload) in targets.iter().zip(payloads.iter()) {
+            let (n, addr) = server_r.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&addr, target);
+            assert_eq!(&buf[..n], &payload[..]);
+            server_s.send_to(&buf[..n], &addr).await.unwrap();
+        }
+        for (target, payload) in targets.iter().zip(payloads.iter()) {
+            let (n, addr) = client_r.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&addr, target);
+            assert_eq!(&buf[..n], &payload[..]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_datagram_truncated() {
+        let (client, server) = tcp_pair().await;
+        let (mut r, _) = Box::new(StreamDatagram(client)).split();
+        let (_, mut s) = Box::new(StreamDatagram(server)).split();
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 53);
+        s.send_to(b"0123456789", &target).await.unwrap();
+        s.send_to(b"next", &target).await.unwrap();
+
+        let mut buf = [0u8; 4];
+        let (n, _) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"0123");
+        // The rest of the truncated datagram is discarded.
+        let (n, _) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"next");
+    }
+
+    #[tokio::test]
+    async fn test_datagram_reader_split_frames() {
+        let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 53);
+        for codec in vec![DatagramCodec::default(), DatagramCodec::with_crlf()] {
+            let data = frame(codec, addr, b"hello world");
+            // One byte per read, every part of the frame arrives split.
+            let chunks = data.iter().map(|b| vec![*b]).collect();
+            let mut r = DatagramReader::new(Chunks(chunks), codec);
+            let mut buf = [0u8; 64];
+            let (n, from) = r.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], b"hello world");
+            assert_eq!(from, addr);
+            // Nothing more to read.
+            let err = r.recv_from(&mut buf).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_datagram_reader_back_to_back_frames() {
+        let codec = DatagramCodec::default();
+        let a = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 53);
+        let b = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53);
+        let mut first = frame(codec, a, b"first");
+        first.extend(frame(codec, b, b""));
+        // The third frame starts in the first read and ends in the second.
+        let third = frame(codec, a, b"third");
+        first.extend(&third[..3]);
+        let chunks = vec![first, third[3..].to_vec()];
+        let mut r = DatagramReader::new(Chunks(chunks), codec);
+
+        let mut buf = [0u8; 64];
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!((&buf[..n], from), (&b"first"[..], a));
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!((&buf[..n], from), (&b""[..], b));
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!((&buf[..n], from), (&b"third"[..], a));
+    }
+
+    #[test]
+    fn test_datagram_codec_incomplete() {
+        let codec = DatagramCodec::default();
+        let data = frame(
+            codec,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 53),
+            b"hello",
+        );
+        for i in 0..data.len() {
+            let mut src = BytesMut::from(&data[..i]);
+            assert!(codec.decode(&mut src).unwrap().is_none());
+            assert_eq!(src.len(), i);
+        }
+        let mut src = BytesMut::from(&data[..]);
+        let (_, payload) = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(&payload[..], b"hello");
+        assert!(src.is_empty());
+
+        let mut src = BytesMut::from(&[9u8, 0, 0][..]);
+        assert!(codec.decode(&mut src).is_err());
+    }
+
+    // A stream transport, dialing the server at its address if it has one,
+    // otherwise passing the stream it wraps through.
+    struct StreamTransport(Option<SocketAddr>);
+
+    #[async_trait]
+    impl ProxyTcpHandler for StreamTransport {
+        fn name(&self) -> &str {
+            "stream"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no stream to wrap"))
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for StreamTransport {
+        fn name(&self) -> &str {
+            "stream"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            self.0.map(|addr| {
+                (
+                    addr.ip().to_string(),
+                    addr.port(),
+                    "127.0.0.1:0".parse().unwrap(),
+                )
+            })
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Stream
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            match (stream, self.0) {
+                (Some(stream), _) => Ok(Box::new(StreamDatagram(stream))),
+                (None, Some(addr)) => Ok(Box::new(StreamDatagram(TcpStream::connect(addr).await?))),
+                (None, None) => Err(io::Error::new(io::ErrorKind::Other, "no stream to wrap")),
+            }
+        }
+    }
+
+    fn stream_transport(addr: Option<SocketAddr>) -> Arc<Handler> {
+        Handler::new(
+            "stream".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(StreamTransport(addr)),
+            Box::new(StreamTransport(addr)),
+        )
+    }
+
+    fn udp_session() -> Session {
+        Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_packets_over_stream() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Echoes the framed packets back.
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (mut r, mut s) = Box::new(StreamDatagram(stream)).split();
+            let mut buf = [0u8; 64];
+            while let Ok((n, from)) = r.recv_from(&mut buf).await {
+                s.send_to(&buf[..n], &from).await.unwrap();
+            }
+        });
+        let handler = stream_transport(Some(addr));
+
+        let datagram = connect_packets(&*handler, &udp_session())
+            .await
+            .ok()
+            .unwrap();
+        let (mut r, mut s) = datagram.split();
+        let target: SocketAddr = "1.2.3.4:53".parse().unwrap();
+        s.send_to(b"first", &target).await.unwrap();
+        s.send_to(b"second", &target).await.unwrap();
+        // Packet boundaries are kept over the stream.
+        let mut buf = [0u8; 64];
+        assert_eq!(r.recv_from(&mut buf).await.unwrap(), (5, target));
+        assert_eq!(&buf[..5], b"first");
+        assert_eq!(r.recv_from(&mut buf).await.unwrap(), (6, target));
+        assert_eq!(&buf[..6], b"second");
+    }
+
+    #[tokio::test]
+    async fn test_connect_packets_without_server() {
+        // Nothing is dialed for a transport only wrapping a stream.
+        let handler = stream_transport(None);
+        let err = connect_packets(&*handler, &udp_session())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_connect_packets_as_packets() {
+        let udp = MockUdpHandler::new(Behavior::Echo);
+        let connects = udp.connects();
+        let handler = udp_actor("packet", udp);
+
+        let datagram = connect_packets(&*handler, &udp_session())
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(connects.load(Ordering::SeqCst), 1);
+        let (mut r, mut s) = datagram.split();
+        let target: SocketAddr = "1.2.3.4:53".parse().unwrap();
+        s.send_to(b"ping", &target).await.unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(r.recv_from(&mut buf).await.unwrap(), (4, target));
+        assert_eq!(&buf[..4], b"ping");
+    }
 }
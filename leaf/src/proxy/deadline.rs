@@ -0,0 +1,148 @@
+//! Deadline of a connect across the whole handler chain.
+//!
+//! A handler with a connect deadline runs the handlers below it in `scope`,
+//! those check the time left with `remaining` or `check` before starting
+//! another attempt, so retrying or failing over never outlives the deadline.
+//! The deadline only applies to the futures polled inside the scope, tasks
+//! spawned from it aren't bounded.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::time::{timeout_at, Instant};
+
+use super::ProxyError;
+
+thread_local! {
+    // The deadline and the budget it was set with.
+    static DEADLINE: Cell<Option<(Instant, Duration)>> = Cell::new(None);
+}
+
+// Restores the previous deadline when dropped, even on panic.
+struct Restore(Option<(Instant, Duration)>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        DEADLINE.with(|d| d.set(self.0));
+    }
+}
+
+// Makes the deadline visible while polling the inner future.
+struct Scoped<F> {
+    deadline: (Instant, Duration),
+    inner: F,
+}
+
+impl<F: Future + Unpin> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let _restore = Restore(DEADLINE.with(|d| d.replace(Some(self.deadline))));
+        Pin::new(&mut self.inner).poll(cx)
+    }
+}
+
+fn current() -> Option<(Instant, Duration)> {
+    DEADLINE.with(|d| d.get())
+}
+
+/// Runs `f` with a deadline `budget` from now, or with the deadline already
+/// in scope if it's earlier. Fails with `ProxyError::Timeout` once the
+/// deadline is reached.
+pub async fn scope<T, F>(budget: Duration, f: F) -> io::Result<T>
+where
+    F: Future<Output = io::Result<T>> + Unpin,
+{
+    let own = (Instant::now() + budget, budget);
+    let deadline = match current() {
+        Some(outer) if outer.0 < own.0 => outer,
+        _ => own,
+    };
+    match timeout_at(deadline.0, Scoped { deadline, inner: f }).await {
+        Ok(res) => res,
+        Err(_) => Err(ProxyError::Timeout(deadline.1).into()),
+    }
+}
+
+/// Returns the deadline in scope, if any.
+pub fn deadline() -> Option<Instant> {
+    current().map(|(at, _)| at)
+}
+
+/// Returns the time left before the deadline, `None` without a deadline.
+pub fn remaining() -> Option<Duration> {
+    deadline().map(|at| at.saturating_duration_since(Instant::now()))
+}
+
+/// Bounds `timeout` by the time left before the deadline.
+pub fn cap(timeout: Duration) -> Duration {
+    match remaining() {
+        Some(left) => left.min(timeout),
+        None => timeout,
+    }
+}
+
+/// Fails with `ProxyError::Timeout` if the deadline has passed.
+pub fn check() -> io::Result<()> {
+    match current() {
+        Some((at, budget)) if Instant::now() >= at => Err(ProxyError::Timeout(budget).into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+    use tokio::time::delay_for;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope() {
+        assert_eq!(remaining(), None);
+        let res = scope(
+            Duration::from_millis(100),
+            async {
+                let left = remaining().unwrap();
+                assert!(left <= Duration::from_millis(100));
+                assert!(cap(Duration::from_secs(5)) <= left);
+                // An inner scope can't extend the deadline.
+                scope(
+                    Duration::from_secs(5),
+                    async { Ok::<_, io::Error>(remaining().unwrap()) }.boxed(),
+                )
+                .await
+            }
+            .boxed(),
+        )
+        .await
+        .unwrap();
+        assert!(res <= Duration::from_millis(100));
+        assert_eq!(remaining(), None);
+
+        let start = Instant::now();
+        let res: io::Result<()> = scope(
+            Duration::from_millis(50),
+            async {
+                loop {
+                    if let Err(e) = check() {
+                        return Err(e);
+                    }
+                    delay_for(Duration::from_millis(10)).await;
+                }
+            }
+            .boxed(),
+        )
+        .await;
+        let err = res.unwrap_err();
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Timeout(_))
+        ));
+    }
+}
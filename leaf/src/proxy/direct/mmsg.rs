@@ -0,0 +1,259 @@
+use std::io;
+use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::ptr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{
+    future::poll_fn,
+    ready,
+    task::{Context, Poll},
+};
+use mio::Ready;
+use tokio::io::PollEvented;
+
+use crate::proxy::{ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf};
+
+/// Maximum number of datagrams sent or received by a single syscall.
+pub const BATCH_SIZE: usize = 16;
+
+/// Size of the buffers datagrams are received into, the largest UDP
+/// payload, so that no datagram is truncated.
+pub const MAX_DATAGRAM_SIZE: usize = 65535;
+
+fn to_sockaddr(addr: &SocketAddr, storage: &mut libc::sockaddr_storage) -> libc::socklen_t {
+    match addr {
+        SocketAddr::V4(addr) => {
+            let sin = unsafe { &mut *(storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr.s_addr = u32::from(*addr.ip()).to_be();
+            mem::size_of::<libc::sockaddr_in>() as libc::socklen_t
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = unsafe { &mut *(storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = addr.port().to_be();
+            sin6.sin6_flowinfo = addr.flowinfo();
+            sin6.sin6_addr.s6_addr = addr.ip().octets();
+            sin6.sin6_scope_id = addr.scope_id();
+            mem::size_of::<libc::sockaddr_in6>() as libc::socklen_t
+        }
+    }
+}
+
+fn from_sockaddr(storage: &libc::sockaddr_storage) -> io::Result<SocketAddr> {
+    match storage.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let sin = unsafe { &*(storage as *const _ as *const libc::sockaddr_in) };
+            Ok(SocketAddr::V4(SocketAddrV4::new(
+                Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr)),
+                u16::from_be(sin.sin_port),
+            )))
+        }
+        libc::AF_INET6 => {
+            let sin6 = unsafe { &*(storage as *const _ as *const libc::sockaddr_in6) };
+            Ok(SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::from(sin6.sin6_addr.s6_addr),
+                u16::from_be(sin6.sin6_port),
+                sin6.sin6_flowinfo,
+                sin6.sin6_scope_id,
+            )))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unsupported address family",
+        )),
+    }
+}
+
+/// Sends `pkts` with a single sendmmsg(2), returns how many were sent.
+pub fn send_mmsg(fd: RawFd, pkts: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+    let mut storages: Vec<libc::sockaddr_storage> = vec![unsafe { mem::zeroed() }; pkts.len()];
+    let mut iovs: Vec<libc::iovec> = pkts
+        .iter()
+        .map(|(buf, _)| libc::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        })
+        .collect();
+    let mut msgs: Vec<libc::mmsghdr> = Vec::with_capacity(pkts.len());
+    for ((storage, iov), (_, target)) in storages.iter_mut().zip(iovs.iter_mut()).zip(pkts) {
+        let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+        msg.msg_hdr.msg_namelen = to_sockaddr(target, storage);
+        msg.msg_hdr.msg_name = storage as *mut _ as *mut libc::c_void;
+        msg.msg_hdr.msg_iov = iov;
+        msg.msg_hdr.msg_iovlen = 1;
+        msgs.push(msg);
+    }
+    let n = unsafe { libc::sendmmsg(fd, msgs.as_mut_ptr(), msgs.len() as _, 0) };
+    if n < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Receives into `bufs` with a single recvmmsg(2), the length and source
+/// of each datagram received replace the contents of `received`. Blocking
+/// sockets only wait for the first datagram.
+pub fn recv_mmsg(
+    fd: RawFd,
+    bufs: &mut [Vec<u8>],
+    received: &mut Vec<(usize, SocketAddr)>,
+) -> io::Result<usize> {
+    let mut storages: Vec<libc::sockaddr_storage> = vec![unsafe { mem::zeroed() }; bufs.len()];
+    let mut iovs: Vec<libc::iovec> = bufs
+        .iter_mut()
+        .map(|buf| libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        })
+        .collect();
+    let mut msgs: Vec<libc::mmsghdr> = Vec::with_capacity(bufs.len());
+    for (storage, iov) in storages.iter_mut().zip(iovs.iter_mut()) {
+        let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+        msg.msg_hdr.msg_name = storage as *mut _ as *mut libc::c_void;
+        msg.msg_hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_hdr.msg_iov = iov;
+        msg.msg_hdr.msg_iovlen = 1;
+        msgs.push(msg);
+    }
+    let n = unsafe {
+        libc::recvmmsg(
+            fd,
+            msgs.as_mut_ptr(),
+            msgs.len() as _,
+            libc::MSG_WAITFORONE as _,
+            ptr::null_mut(),
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    received.clear();
+    for (msg, storage) in msgs.iter().zip(storages.iter()).take(n as usize) {
+        received.push((msg.msg_len as usize, from_sockaddr(storage)?));
+    }
+    Ok(n as usize)
+}
+
+type Socket = Arc<PollEvented<mio::net::UdpSocket>>;
+
+/// A UDP socket sending and receiving up to `BATCH_SIZE` datagrams per
+/// syscall, with sendmmsg(2) and recvmmsg(2).
+pub struct Datagram {
+    socket: Socket,
+    local_addr: SocketAddr,
+}
+
+impl Datagram {
+    pub fn new(socket: std::net::UdpSocket) -> io::Result<Self> {
+        let local_addr = socket.local_addr()?;
+        Ok(Datagram {
+            socket: Arc::new(PollEvented::new(mio::net::UdpSocket::from_socket(socket)?)?),
+            local_addr,
+        })
+    }
+}
+
+impl ProxyDatagram for Datagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        (
+            Box::new(DatagramRecvHalf {
+                socket: self.socket.clone(),
+                bufs: vec![vec![0u8; MAX_DATAGRAM_SIZE]; BATCH_SIZE],
+                received: Vec::with_capacity(BATCH_SIZE),
+                next: 0,
+            }),
+            Box::new(DatagramSendHalf(self.socket)),
+        )
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+}
+
+/// Receives datagrams a batch at a time, handing them out one by one.
+pub struct DatagramRecvHalf {
+    socket: Socket,
+    bufs: Vec<Vec<u8>>,
+    received: Vec<(usize, SocketAddr)>,
+    // The next datagram of `received` to hand out.
+    next: usize,
+}
+
+impl DatagramRecvHalf {
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            ready!(self.socket.poll_read_ready(cx, Ready::readable()))?;
+            let fd = self.socket.get_ref().as_raw_fd();
+            match recv_mmsg(fd, &mut self.bufs, &mut self.received) {
+                Ok(_) => {
+                    self.next = 0;
+                    return Poll::Ready(Ok(()));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    self.socket.clear_read_ready(cx, Ready::readable())?;
+                }
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for DatagramRecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        if self.next >= self.received.len() {
+            poll_fn(|cx| self.poll_fill(cx)).await?;
+        }
+        let (n, addr) = self.received[self.next];
+        let n = n.min(buf.len());
+        buf[..n].copy_from_slice(&self.bufs[self.next][..n]);
+        self.next += 1;
+        Ok((n, addr))
+    }
+}
+
+pub struct DatagramSendHalf(Socket);
+
+impl DatagramSendHalf {
+    fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        pkts: &[(&[u8], SocketAddr)],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            ready!(self.0.poll_write_ready(cx))?;
+            match send_mmsg(self.0.get_ref().as_raw_fd(), pkts) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    self.0.clear_write_ready(cx)?;
+                }
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ProxyDatagramSendHalf for DatagramSendHalf {
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        let pkts = [(buf, *target)];
+        poll_fn(|cx| self.poll_send(cx, &pkts)).await?;
+        Ok(buf.len())
+    }
+
+    async fn send_batch(&mut self, pkts: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        let pkts = &pkts[..pkts.len().min(BATCH_SIZE)];
+        poll_fn(|cx| self.poll_send(cx, pkts)).await
+    }
+}
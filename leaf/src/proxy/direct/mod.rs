@@ -1,3 +1,6 @@
+#[cfg(all(target_os = "linux", feature = "udp-mmsg"))]
+pub mod mmsg;
+pub mod stream;
 pub mod tcp;
 pub mod udp;
 
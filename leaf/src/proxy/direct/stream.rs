@@ -0,0 +1,170 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+use futures::{
+    future::{BoxFuture, FutureExt},
+    ready,
+};
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::proxy::ProxyStream;
+
+/// Connects anew, once.
+pub type Redial = Box<dyn FnOnce() -> BoxFuture<'static, io::Result<Box<dyn ProxyStream>>> + Send>;
+
+/// Whether `e` means the peer reset the connection.
+pub fn is_reset(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionReset | io::ErrorKind::BrokenPipe
+    )
+}
+
+struct Retry {
+    redial: Option<Redial>,
+    redialing: Option<BoxFuture<'static, io::Result<Box<dyn ProxyStream>>>>,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+}
+
+/// A stream connecting anew once if reset before any byte went through
+/// either way, e.g. by a flaky path tearing down fresh connections.
+///
+/// Nothing of the payload has been delivered when the first write fails, so
+/// retrying keeps it at most once. The first byte written or read closes
+/// the window, later resets are returned as is.
+pub struct ResetRetryStream {
+    inner: Box<dyn ProxyStream>,
+    // Behind a lock only to make the stream `Sync`, it's never contended.
+    retry: Option<Mutex<Retry>>,
+}
+
+impl ResetRetryStream {
+    pub fn new(inner: Box<dyn ProxyStream>, redial: Redial) -> Self {
+        ResetRetryStream {
+            inner,
+            retry: Some(Mutex::new(Retry {
+                redial: Some(redial),
+                redialing: None,
+                read_waker: None,
+                write_waker: None,
+            })),
+        }
+    }
+
+    fn retry(&mut self) -> Option<&mut Retry> {
+        self.retry.as_mut().map(|r| r.get_mut().unwrap())
+    }
+
+    // Starts connecting anew if `e` is a reset within the window, returns
+    // whether a redial is under way.
+    fn start_redial(&mut self, e: &io::Error) -> bool {
+        if !is_reset(e) {
+            return false;
+        }
+        match self.retry() {
+            Some(retry) => {
+                if let Some(redial) = retry.redial.take() {
+                    debug!("connection reset before any data, redialing");
+                    retry.redialing = Some(redial());
+                }
+                retry.redialing.is_some()
+            }
+            None => false,
+        }
+    }
+
+    // Waits for a redial under way, if any, `reading` tells which half is
+    // waiting.
+    fn poll_redial(&mut self, cx: &mut Context, reading: bool) -> Poll<io::Result<()>> {
+        let retry = match self.retry() {
+            Some(retry) => retry,
+            None => return Poll::Ready(Ok(())),
+        };
+        let res = match retry.redialing.as_mut() {
+            Some(redialing) => match redialing.poll_unpin(cx) {
+                Poll::Ready(res) => res,
+                Poll::Pending => {
+                    if reading {
+                        retry.read_waker = Some(cx.waker().clone());
+                    } else {
+                        retry.write_waker = Some(cx.waker().clone());
+                    }
+                    return Poll::Pending;
+                }
+            },
+            None => return Poll::Ready(Ok(())),
+        };
+        // Either half may be waiting on the other to redial.
+        if let Some(waker) = retry.read_waker.take() {
+            waker.wake();
+        }
+        if let Some(waker) = retry.write_waker.take() {
+            waker.wake();
+        }
+        // Retried once, whatever the outcome.
+        self.retry = None;
+        self.inner = res?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl ProxyStream for ResetRetryStream {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+}
+
+impl AsyncRead for ResetRetryStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            ready!(self.poll_redial(cx, true))?;
+            let res = ready!(Pin::new(&mut self.inner).poll_read(cx, buf));
+            match &res {
+                Ok(n) if *n > 0 => self.retry = None,
+                Err(e) if self.start_redial(e) => continue,
+                _ => (),
+            }
+            return Poll::Ready(res);
+        }
+    }
+}
+
+impl AsyncWrite for ResetRetryStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            ready!(self.poll_redial(cx, false))?;
+            let res = ready!(Pin::new(&mut self.inner).poll_write(cx, buf));
+            match &res {
+                Ok(n) if *n > 0 => self.retry = None,
+                Err(e) if self.start_redial(e) => continue,
+                _ => (),
+            }
+            return Poll::Ready(res);
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_redial(cx, false))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_redial(cx, false))?;
+        self.retry = None;
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -1,25 +1,124 @@
-use std::{io, net::SocketAddr, sync::Arc};
+use std::{io, net::SocketAddr, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::future::FutureExt;
+use log::*;
+use tokio::io::AsyncWriteExt;
 
 use crate::{
-    common::dns_client::DnsClient,
-    proxy::{ProxyStream, ProxyTcpHandler},
-    session::Session,
+    common::{dial_failures::DialFailures, dns_client::DnsClient, happy_eyeballs::HappyEyeballs},
+    proxy::{
+        dial_tcp_stream_with,
+        proxy_protocol::{self, Version},
+        BindAddrs, ProxyStream, ProxyTcpHandler, DIAL_CONCURRENCY,
+    },
+    session::{Session, SocksAddr},
 };
 
+use super::stream::{is_reset, ResetRetryStream};
+
+/// How long a failed address is dialed after the others of its host.
+pub const DEFAULT_DIAL_FAILURE_WINDOW: Duration = Duration::from_secs(30);
+
 pub struct Handler {
-    bind_addr: SocketAddr,
+    binds: BindAddrs,
     dns_client: Arc<DnsClient>,
+    proxy_protocol: Option<Version>,
+    dial_failures: Arc<DialFailures>,
+    sequential: bool,
+    happy_eyeballs: HappyEyeballs,
+    retry_on_reset: bool,
 }
 
 impl Handler {
-    pub fn new(bind_addr: SocketAddr, dns_client: Arc<DnsClient>) -> Self {
+    /// `proxy_protocol` is the version of the PROXY protocol header sent
+    /// ahead of the payload, if any.
+    pub fn new(
+        bind_addr: SocketAddr,
+        dns_client: Arc<DnsClient>,
+        proxy_protocol: Option<Version>,
+    ) -> Self {
         Handler {
-            bind_addr,
+            binds: BindAddrs::from(bind_addr),
             dns_client,
+            proxy_protocol,
+            dial_failures: Arc::new(DialFailures::new(DEFAULT_DIAL_FAILURE_WINDOW)),
+            sequential: false,
+            happy_eyeballs: HappyEyeballs::default(),
+            retry_on_reset: false,
         }
     }
+
+    /// Addresses failing to connect are dialed after the other addresses of
+    /// their host for `window`.
+    pub fn dial_failure_window(mut self, window: Duration) -> Self {
+        self.dial_failures = Arc::new(DialFailures::new(window));
+        self
+    }
+
+    /// Dials the addresses of a host one at a time instead of a few at once.
+    pub fn sequential(mut self, sequential: bool) -> Self {
+        self.sequential = sequential;
+        self
+    }
+
+    /// Dials from the bind address of the family of each address, instead
+    /// of the one given to `new`, e.g. to reach both IPv4 and IPv6
+    /// destinations from specific addresses. Dials of a family without a
+    /// bind address fail.
+    pub fn bind_addrs(mut self, binds: BindAddrs) -> Self {
+        self.binds = binds;
+        self
+    }
+
+    /// Races the IPv4 and IPv6 addresses of a host with `happy_eyeballs`,
+    /// IPv6 first with the RFC 8305 delay by default.
+    pub fn happy_eyeballs(mut self, happy_eyeballs: HappyEyeballs) -> Self {
+        self.happy_eyeballs = happy_eyeballs;
+        self
+    }
+
+    /// Connects anew once if the connection is reset before any data went
+    /// through, see `ResetRetryStream`. Only for destinations where a
+    /// reconnect is harmless, the PROXY protocol header is sent again.
+    pub fn retry_on_reset(mut self, retry_on_reset: bool) -> Self {
+        self.retry_on_reset = retry_on_reset;
+        self
+    }
+}
+
+// Connects to a destination, the direct way.
+#[derive(Clone)]
+struct Dial {
+    dns_client: Arc<DnsClient>,
+    binds: BindAddrs,
+    host: String,
+    port: u16,
+    concurrency: usize,
+    dial_failures: Arc<DialFailures>,
+    happy_eyeballs: HappyEyeballs,
+    // The PROXY protocol header sent ahead of the payload.
+    header: Option<Bytes>,
+}
+
+impl Dial {
+    async fn connect(self) -> io::Result<Box<dyn ProxyStream>> {
+        let mut stream = dial_tcp_stream_with(
+            self.dns_client,
+            &self.binds,
+            &self.host,
+            &self.port,
+            self.concurrency,
+            Some(&*self.dial_failures),
+            Some(&self.happy_eyeballs),
+        )
+        .await?;
+        if let Some(header) = &self.header {
+            stream.write_all(header).await?;
+        }
+        Ok(stream)
+    }
 }
 
 #[async_trait]
@@ -35,15 +134,258 @@ impl ProxyTcpHandler for Handler {
     async fn handle<'a>(
         &'a self,
         sess: &'a Session,
-        _stream: Option<Box<dyn ProxyStream>>,
+        stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>> {
-        Ok(self
-            .dial_tcp_stream(
-                self.dns_client.clone(),
-                &self.bind_addr,
-                &sess.destination.host(),
-                &sess.destination.port(),
-            )
-            .await?)
+        let header = self.proxy_protocol.map(|version| {
+            let dst = match &sess.destination {
+                SocksAddr::Ip(a) => Some(a),
+                SocksAddr::Domain(..) => None,
+            };
+            let mut header = BytesMut::new();
+            proxy_protocol::write_header(version, &sess.source, dst, &mut header);
+            header.freeze()
+        });
+        // At the tail of a chain, the previous proxy has already connected
+        // to the destination.
+        if let Some(mut stream) = stream {
+            if let Some(header) = &header {
+                stream.write_all(header).await?;
+            }
+            return Ok(stream);
+        }
+        let dial = Dial {
+            dns_client: self.dns_client.clone(),
+            binds: self.binds,
+            host: sess.destination.host(),
+            port: sess.destination.port(),
+            concurrency: if self.sequential { 1 } else { DIAL_CONCURRENCY },
+            dial_failures: self.dial_failures.clone(),
+            happy_eyeballs: self.happy_eyeballs,
+            header,
+        };
+        if !self.retry_on_reset {
+            return dial.connect().await;
+        }
+        match dial.clone().connect().await {
+            Ok(stream) => Ok(Box::new(ResetRetryStream::new(
+                stream,
+                Box::new(move || dial.connect().boxed()),
+            ))),
+            Err(e) if is_reset(&e) => {
+                debug!("connection to {} reset, redialing", &sess.destination);
+                dial.connect().await
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_direct_proxy_protocol() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            buf
+        });
+
+        let handler = Handler::new(
+            "127.0.0.1:0".parse().unwrap(),
+            Arc::new(DnsClient::default()),
+            Some(Version::V1),
+        );
+        let sess = Session {
+            source: "192.168.0.1:56324".parse().unwrap(),
+            destination: SocksAddr::Ip(addr),
+        };
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        stream.write_all(b"hello").await.unwrap();
+        stream.shutdown().await.unwrap();
+
+        let expected = format!(
+            "PROXY TCP4 192.168.0.1 127.0.0.1 56324 {}\r\nhello",
+            addr.port()
+        );
+        assert_eq!(server.await.unwrap(), expected.into_bytes());
+    }
+
+    #[cfg(feature = "outbound-dns")]
+    #[tokio::test]
+    async fn test_direct_resolves_faked_domain_on_dial() {
+        use std::net::Ipv4Addr;
+        use std::sync::atomic::Ordering;
+
+        use trust_dns_proto::rr::record_type::RecordType;
+
+        use crate::common::fake_dns::FakeDns;
+        use crate::proxy::dns::test_utils::{counting_upstream, query};
+
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let _ = listener.accept().await;
+        });
+        let (upstream, queries) = counting_upstream(Ipv4Addr::new(127, 0, 0, 1)).await;
+        let dns_client = Arc::new(DnsClient::new(
+            vec![upstream],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+
+        // The fake answer doesn't resolve the real IPs, only the dial does.
+        let mut fake_dns = FakeDns::new();
+        fake_dns
+            .generate_fake_response(&query("example.com.", 1, RecordType::A))
+            .unwrap();
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        assert_eq!(queries.load(Ordering::SeqCst), 0);
+        let handler = Handler::new("127.0.0.1:0".parse().unwrap(), dns_client, None);
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), port),
+        };
+        assert!(handler.handle(&sess, None).await.is_ok());
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "outbound-dns")]
+    #[tokio::test]
+    async fn test_direct_skips_failed_addr() {
+        use std::net::Ipv4Addr;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::proxy::dns::test_utils::counting_upstream_with;
+
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let _ = listener.accept().await;
+            }
+        });
+        // The first address is dead.
+        let (upstream, _) = counting_upstream_with(vec![
+            Ipv4Addr::new(127, 0, 0, 2),
+            Ipv4Addr::new(127, 0, 0, 1),
+        ])
+        .await;
+        let dns_client = Arc::new(DnsClient::new(
+            vec![upstream],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        let handler = Handler::new("127.0.0.1:0".parse().unwrap(), dns_client, None)
+            .dial_failure_window(Duration::from_secs(10))
+            .sequential(true);
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), port),
+        };
+        assert!(handler.handle(&sess, None).await.is_ok());
+
+        // Within the window the failed address is dialed last, even if it
+        // came back.
+        let mut dead = match TcpListener::bind(("127.0.0.2", port)).await {
+            Ok(dead) => dead,
+            Err(_) => return,
+        };
+        let accepts = Arc::new(AtomicUsize::new(0));
+        let accepts2 = accepts.clone();
+        tokio::spawn(async move {
+            loop {
+                if dead.accept().await.is_ok() {
+                    accepts2.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+        assert!(handler.handle(&sess, None).await.is_ok());
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        assert_eq!(accepts.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_direct_binds_by_family() {
+        let mut v4 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut v6 = match TcpListener::bind("[::1]:0").await {
+            Ok(v6) => v6,
+            Err(_) => return, // no IPv6
+        };
+        let (v4_addr, v6_addr) = (v4.local_addr().unwrap(), v6.local_addr().unwrap());
+        let v4_peer = tokio::spawn(async move { v4.accept().await.unwrap().1 });
+        let v6_peer = tokio::spawn(async move { v6.accept().await.unwrap().1 });
+
+        let binds: BindAddrs = "127.0.0.1, ::1".parse().unwrap();
+        let handler = Handler::new(
+            "0.0.0.0:0".parse().unwrap(),
+            Arc::new(DnsClient::default()),
+            None,
+        )
+        .bind_addrs(binds);
+        for addr in &[v4_addr, v6_addr] {
+            let sess = Session {
+                source: "127.0.0.1:10000".parse().unwrap(),
+                destination: SocksAddr::Ip(*addr),
+            };
+            assert!(handler.handle(&sess, None).await.is_ok());
+        }
+        assert_eq!(v4_peer.await.unwrap().ip(), binds.v4.unwrap().ip());
+        assert_eq!(v6_peer.await.unwrap().ip(), binds.v6.unwrap().ip());
+
+        // No IPv6 bind address.
+        let handler = handler.bind_addrs("127.0.0.1".parse().unwrap());
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip(v6_addr),
+        };
+        let err = handler.handle(&sess, None).await.err().unwrap();
+        assert!(err.to_string().contains("no ipv6 bind address"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_direct_retries_reset() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepts = Arc::new(AtomicUsize::new(0));
+        let accepts2 = accepts.clone();
+        tokio::spawn(async move {
+            // Resets the first connection.
+            let (stream, _) = listener.accept().await.unwrap();
+            accepts2.fetch_add(1, Ordering::SeqCst);
+            stream.set_linger(Some(Duration::from_secs(0))).unwrap();
+            drop(stream);
+            let (mut stream, _) = listener.accept().await.unwrap();
+            accepts2.fetch_add(1, Ordering::SeqCst);
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let handler = Handler::new(
+            "127.0.0.1:0".parse().unwrap(),
+            Arc::new(DnsClient::default()),
+            None,
+        )
+        .retry_on_reset(true);
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip(addr),
+        };
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        // The reset arrives before the first write.
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        stream.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+        assert_eq!(accepts.load(Ordering::SeqCst), 2);
     }
 }
@@ -1,26 +1,68 @@
 use std::{io, net::SocketAddr};
 
 use async_trait::async_trait;
-use tokio::net::{
-    udp::{RecvHalf, SendHalf},
-    UdpSocket,
-};
+use log::*;
+use tokio::net::udp::{RecvHalf, SendHalf};
 
 use crate::{
     proxy::{
-        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
-        UdpTransportType,
+        bind_std_udp_socket, ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf,
+        ProxyStream, ProxyUdpHandler, UdpTransportType,
     },
     session::Session,
 };
 
 pub struct Handler {
     bind_addr: SocketAddr,
+    bind_interface: Option<String>,
+    preserve_source_port: bool,
+    batching: bool,
 }
 
 impl Handler {
-    pub fn new(bind_addr: SocketAddr) -> Self {
-        Handler { bind_addr }
+    /// Sessions are bound to `bind_addr`, and to the network interface
+    /// `bind_interface` if any.
+    pub fn new(bind_addr: SocketAddr, bind_interface: Option<String>) -> Self {
+        Handler {
+            bind_addr,
+            bind_interface,
+            preserve_source_port: false,
+            batching: false,
+        }
+    }
+
+    /// Binds sessions to the source port of the client when free, for
+    /// peers checking the port, e.g. in some STUN flows. Sessions get an
+    /// ephemeral port otherwise.
+    pub fn preserve_source_port(mut self, enabled: bool) -> Self {
+        self.preserve_source_port = enabled;
+        self
+    }
+
+    /// Sends and receives several datagrams per syscall, with sendmmsg(2)
+    /// and recvmmsg(2), on Linux builds with the `udp-mmsg` feature. Ignored
+    /// elsewhere, datagrams are sent and received one at a time. Batching
+    /// sessions hold 1 MiB of receive buffers each.
+    pub fn batching(mut self, enabled: bool) -> Self {
+        self.batching = enabled;
+        self
+    }
+
+    fn bind(&self, sess: &Session) -> io::Result<std::net::UdpSocket> {
+        let interface = self.bind_interface.as_deref();
+        if self.preserve_source_port && self.bind_addr.port() == 0 {
+            let mut bind_addr = self.bind_addr;
+            bind_addr.set_port(sess.source.port());
+            match bind_std_udp_socket(&bind_addr, interface) {
+                Ok(socket) => return Ok(socket),
+                Err(e) => debug!(
+                    "source port {} unavailable, binding an ephemeral one: {}",
+                    sess.source.port(),
+                    e
+                ),
+            }
+        }
+        bind_std_udp_socket(&self.bind_addr, interface)
     }
 }
 
@@ -40,15 +82,26 @@ impl ProxyUdpHandler for Handler {
 
     async fn connect<'a>(
         &'a self,
-        _sess: &'a Session,
+        sess: &'a Session,
         _datagram: Option<Box<dyn ProxyDatagram>>,
         _stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyDatagram>> {
-        let socket = UdpSocket::bind(&self.bind_addr).await?;
+        let socket = self.bind(sess)?;
+        if self.batching {
+            #[cfg(all(target_os = "linux", feature = "udp-mmsg"))]
+            {
+                return Ok(Box::new(super::mmsg::Datagram::new(socket)?));
+            }
+            #[cfg(not(all(target_os = "linux", feature = "udp-mmsg")))]
+            trace!("udp batching not available, sending datagrams one at a time");
+        }
+        let socket = tokio::net::UdpSocket::from_std(socket)?;
+        let local_addr = socket.local_addr()?;
         let (rh, sh) = socket.split();
         Ok(Box::new(Datagram {
             recv_half: rh,
             send_half: sh,
+            local_addr,
         }))
     }
 }
@@ -56,6 +109,7 @@ impl ProxyUdpHandler for Handler {
 pub struct Datagram {
     pub recv_half: RecvHalf,
     pub send_half: SendHalf,
+    pub local_addr: SocketAddr,
 }
 
 impl ProxyDatagram for Datagram {
@@ -70,6 +124,9 @@ impl ProxyDatagram for Datagram {
             Box::new(DatagramSendHalf(self.send_half)),
         )
     }
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
 }
 
 pub struct DatagramRecvHalf(RecvHalf);
@@ -89,3 +146,138 @@ impl ProxyDatagramSendHalf for DatagramSendHalf {
         self.0.send_to(buf, target).await
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use tokio::net::UdpSocket;
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    #[tokio::test]
+    async fn test_direct_udp_bind_interface() {
+        let mut echo = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let echo_addr = echo.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (n, from) = echo.recv_from(&mut buf).await.unwrap();
+            echo.send_to(&buf[..n], &from).await.unwrap();
+        });
+
+        let handler = Handler::new("127.0.0.1:0".parse().unwrap(), Some("lo".to_string()));
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip(echo_addr),
+        };
+        let (mut recv, mut send) = handler.connect(&sess, None, None).await.unwrap().split();
+        send.send_to(b"hello", &echo_addr).await.unwrap();
+        let mut buf = [0u8; 64];
+        let (n, from) = recv.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert_eq!(from, echo_addr);
+    }
+
+    #[tokio::test]
+    async fn test_direct_udp_bind_unknown_interface() {
+        let handler = Handler::new(
+            "127.0.0.1:0".parse().unwrap(),
+            Some("leaf-no-such-if".to_string()),
+        );
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("127.0.0.1:53".parse().unwrap()),
+        };
+        assert!(handler.connect(&sess, None, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_direct_udp_preserve_source_port() {
+        let handler = Handler::new("127.0.0.1:0".parse().unwrap(), None).preserve_source_port(true);
+        let sess = |port: u16| Session {
+            source: SocketAddr::from(([127, 0, 0, 1], port)),
+            destination: SocksAddr::Ip("127.0.0.1:53".parse().unwrap()),
+        };
+
+        // A free port is kept.
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let datagram = handler.connect(&sess(port), None, None).await.unwrap();
+        assert_eq!(datagram.local_addr().unwrap().port(), port);
+
+        // A taken one isn't.
+        let taken = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let datagram = handler.connect(&sess(port), None, None).await.unwrap();
+        assert_ne!(datagram.local_addr().unwrap().port(), port);
+    }
+
+    // Sends datagrams of varied lengths, up to 6000 bytes, in batches,
+    // alternately to two peers echoing them back, returns the datagrams
+    // received back with the index of the peer they came from.
+    async fn exchange(handler: Handler) -> Vec<(Vec<u8>, usize)> {
+        const COUNT: usize = 40;
+
+        let mut peers = Vec::new();
+        for _ in 0..2 {
+            peers.push(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        }
+        let addrs: Vec<SocketAddr> = peers.iter().map(|p| p.local_addr().unwrap()).collect();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip(addrs[0]),
+        };
+        let (mut recv, mut send) = handler.connect(&sess, None, None).await.unwrap().split();
+
+        let payloads: Vec<Vec<u8>> = (0..COUNT)
+            .map(|i| vec![i as u8; 1 + i * 997 % 6000])
+            .collect();
+        let pkts: Vec<(&[u8], SocketAddr)> = payloads
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (&p[..], addrs[i % 2]))
+            .collect();
+        let mut sent = 0;
+        while sent < pkts.len() {
+            sent += send.send_batch(&pkts[sent..]).await.unwrap();
+        }
+
+        // One peer echoes all its datagrams before the other.
+        for peer in peers.iter_mut() {
+            let mut buf = vec![0u8; 65535];
+            for _ in 0..COUNT / 2 {
+                let (n, from) = peer.recv_from(&mut buf).await.unwrap();
+                peer.send_to(&buf[..n], &from).await.unwrap();
+            }
+        }
+
+        let mut echoed = Vec::new();
+        let mut buf = vec![0u8; 65535];
+        for _ in 0..COUNT {
+            let (n, from) = recv.recv_from(&mut buf).await.unwrap();
+            let peer = addrs.iter().position(|a| *a == from).unwrap();
+            echoed.push((buf[..n].to_vec(), peer));
+        }
+        echoed
+    }
+
+    #[tokio::test]
+    async fn test_direct_udp_batching() {
+        let unbatched = exchange(Handler::new("127.0.0.1:0".parse().unwrap(), None)).await;
+        let batched =
+            exchange(Handler::new("127.0.0.1:0".parse().unwrap(), None).batching(true)).await;
+        assert_eq!(batched, unbatched);
+
+        // Each datagram went to, and came back from, the peer it was sent
+        // to, in order.
+        let expected: Vec<(Vec<u8>, usize)> = (0..40)
+            .step_by(2)
+            .chain((1..40).step_by(2))
+            .map(|i| (vec![i as u8; 1 + i * 997 % 6000], i % 2))
+            .collect();
+        assert!(expected.iter().any(|(data, _)| data.len() > 4096));
+        assert_eq!(batched, expected);
+    }
+}
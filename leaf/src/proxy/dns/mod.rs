@@ -0,0 +1,206 @@
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use log::*;
+use tokio::sync::Mutex as TokioMutex;
+use trust_dns_proto::op::{
+    header::MessageType, op_code::OpCode, response_code::ResponseCode, Message,
+};
+use trust_dns_proto::rr::{
+    dns_class::DNSClass, record_data::RData, record_type::RecordType, resource::Record,
+};
+
+use crate::common::{dns_client::DnsClient, fake_dns::FakeDns};
+
+pub mod tcp;
+pub mod udp;
+
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
+
+pub static NAME: &str = "dns";
+
+// TTL of the answers resolved by the DnsClient.
+const TTL: u32 = 60;
+
+/// Answers DNS queries in process.
+pub struct Responder {
+    dns_client: Arc<DnsClient>,
+    fake_dns: Option<Arc<TokioMutex<FakeDns>>>,
+}
+
+impl Responder {
+    /// Answers with fake IPs allocated from `fake_dns` if it's set, domains
+    /// excluded from the fake DNS are resolved by `dns_client`.
+    pub fn new(dns_client: Arc<DnsClient>, fake_dns: Option<Arc<TokioMutex<FakeDns>>>) -> Self {
+        Responder {
+            dns_client,
+            fake_dns,
+        }
+    }
+
+    /// Returns the encoded response to an encoded DNS request.
+    pub async fn answer(&self, request: &[u8]) -> io::Result<Vec<u8>> {
+        if let Some(fake_dns) = &self.fake_dns {
+            match fake_dns.lock().await.generate_fake_response(request) {
+                Ok(resp) => return Ok(resp),
+                Err(e) => debug!("generate fake response failed: {}", e),
+            }
+        }
+
+        let req = Message::from_vec(request).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid dns request: {}", e),
+            )
+        })?;
+
+        let mut resp = Message::new();
+        resp.set_id(req.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(req.op_code());
+        if resp.op_code() == OpCode::Query {
+            resp.set_recursion_desired(req.recursion_desired())
+                .set_recursion_available(true)
+                .set_checking_disabled(req.checking_disabled());
+        }
+
+        let query = match req.queries().first() {
+            Some(q) => q.clone(),
+            None => {
+                resp.set_response_code(ResponseCode::FormErr);
+                return encode(&resp);
+            }
+        };
+        resp.add_query(query.clone());
+
+        let t = query.query_type();
+        if query.query_class() != DNSClass::IN || (t != RecordType::A && t != RecordType::AAAA) {
+            resp.set_response_code(ResponseCode::NotImp);
+            return encode(&resp);
+        }
+
+        let raw_name = query.name();
+        let domain = if raw_name.is_fqdn() {
+            let fqdn = raw_name.to_ascii();
+            fqdn[..fqdn.len() - 1].to_string()
+        } else {
+            raw_name.to_ascii()
+        };
+        match self.dns_client.lookup(domain.clone()).await {
+            Ok(ips) => {
+                resp.set_response_code(ResponseCode::NoError);
+                for ip in ips {
+                    let rdata = match ip {
+                        IpAddr::V4(ip) if t == RecordType::A => RData::A(ip),
+                        IpAddr::V6(ip) if t == RecordType::AAAA => RData::AAAA(ip),
+                        _ => continue,
+                    };
+                    let mut ans = Record::new();
+                    ans.set_name(raw_name.clone())
+                        .set_rr_type(t)
+                        .set_ttl(TTL)
+                        .set_dns_class(DNSClass::IN)
+                        .set_rdata(rdata);
+                    resp.add_answer(ans);
+                }
+            }
+            Err(e) => {
+                debug!("lookup {} failed: {}", &domain, e);
+                resp.set_response_code(ResponseCode::ServFail);
+            }
+        }
+        encode(&resp)
+    }
+}
+
+fn encode(msg: &Message) -> io::Result<Vec<u8>> {
+    msg.to_vec().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("encode dns response failed: {}", e),
+        )
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::net::UdpSocket;
+    use trust_dns_proto::op::query::Query;
+    use trust_dns_proto::rr::Name;
+
+    use super::*;
+
+    /// Starts an upstream DNS server answering every A query with `ip`.
+    pub async fn upstream(ip: Ipv4Addr) -> SocketAddr {
+        counting_upstream(ip).await.0
+    }
+
+    /// Like `upstream`, also returns the number of queries received.
+    pub async fn counting_upstream(ip: Ipv4Addr) -> (SocketAddr, Arc<AtomicUsize>) {
+        counting_upstream_with(vec![ip]).await
+    }
+
+    /// Like `counting_upstream`, answering with all of `ips` in order.
+    pub async fn counting_upstream_with(ips: Vec<Ipv4Addr>) -> (SocketAddr, Arc<AtomicUsize>) {
+        let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let queries = Arc::new(AtomicUsize::new(0));
+        let queries2 = queries.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (n, src) = socket.recv_from(&mut buf).await.unwrap();
+                queries2.fetch_add(1, Ordering::SeqCst);
+                let req = Message::from_vec(&buf[..n]).unwrap();
+                let mut resp = Message::new();
+                resp.set_id(req.id())
+                    .set_message_type(MessageType::Response)
+                    .set_op_code(OpCode::Query)
+                    .set_response_code(ResponseCode::NoError);
+                let query = req.queries()[0].clone();
+                for ip in &ips {
+                    let mut ans = Record::new();
+                    ans.set_name(query.name().clone())
+                        .set_rr_type(RecordType::A)
+                        .set_ttl(1)
+                        .set_dns_class(DNSClass::IN)
+                        .set_rdata(RData::A(*ip));
+                    resp.add_answer(ans);
+                }
+                resp.add_query(query);
+                socket.send_to(&resp.to_vec().unwrap(), &src).await.unwrap();
+            }
+        });
+        (addr, queries)
+    }
+
+    pub fn query(domain: &str, id: u16, t: RecordType) -> Vec<u8> {
+        let mut msg = Message::new();
+        msg.add_query(Query::query(Name::from_str(domain).unwrap(), t));
+        msg.set_id(id)
+            .set_op_code(OpCode::Query)
+            .set_message_type(MessageType::Query)
+            .set_recursion_desired(true);
+        msg.to_vec().unwrap()
+    }
+
+    pub fn answers(resp: &[u8]) -> (u16, ResponseCode, Vec<IpAddr>) {
+        let resp = Message::from_vec(resp).unwrap();
+        let ips = resp
+            .answers()
+            .iter()
+            .filter_map(|ans| match ans.rdata() {
+                RData::A(ip) => Some(IpAddr::V4(*ip)),
+                RData::AAAA(ip) => Some(IpAddr::V6(*ip)),
+                _ => None,
+            })
+            .collect();
+        (resp.id(), resp.response_code(), ips)
+    }
+}
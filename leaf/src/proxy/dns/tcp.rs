@@ -0,0 +1,219 @@
+use std::cmp::min;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::Responder;
+use crate::{
+    proxy::{ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+pub struct Handler {
+    pub responder: Arc<Responder>,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        _sess: &'a Session,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        Ok(Box::new(Stream::new(self.responder.clone())))
+    }
+}
+
+/// DNS over TCP, every query written is answered with a response readable
+/// from the stream, both are prefixed with a 2-byte length.
+pub struct Stream {
+    responder: Arc<Responder>,
+    tx: Option<UnboundedSender<Vec<u8>>>,
+    rx: UnboundedReceiver<Vec<u8>>,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+}
+
+impl Stream {
+    fn new(responder: Arc<Responder>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Stream {
+            responder,
+            tx: Some(tx),
+            rx,
+            read_buf: BytesMut::new(),
+            write_buf: BytesMut::new(),
+        }
+    }
+
+    fn answer(&self, req: Vec<u8>) {
+        let tx = match &self.tx {
+            Some(tx) => tx.clone(),
+            None => return,
+        };
+        let responder = self.responder.clone();
+        tokio::spawn(async move {
+            match responder.answer(&req).await {
+                Ok(resp) => {
+                    let _ = tx.send(resp);
+                }
+                Err(e) => debug!("answer dns query failed: {}", e),
+            }
+        });
+    }
+}
+
+impl ProxyStream for Stream {}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.read_buf.is_empty() {
+            // Ends once the write side is shut down and every pending query
+            // is answered.
+            match ready!(self.rx.poll_recv(cx)) {
+                Some(resp) => {
+                    self.read_buf.reserve(2 + resp.len());
+                    self.read_buf.put_u16(resp.len() as u16);
+                    self.read_buf.put_slice(&resp);
+                }
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+        let to_read = min(buf.len(), self.read_buf.len());
+        let data = self.read_buf.split_to(to_read);
+        (&mut buf[..to_read]).copy_from_slice(&data);
+        Poll::Ready(Ok(to_read))
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.tx.is_none() {
+            return Poll::Ready(Err(io::Error::from(io::ErrorKind::BrokenPipe)));
+        }
+        self.write_buf.put_slice(buf);
+        while self.write_buf.len() >= 2 {
+            let len = BigEndian::read_u16(&self.write_buf[..2]) as usize;
+            if self.write_buf.len() < 2 + len {
+                break;
+            }
+            self.write_buf.advance(2);
+            let req = self.write_buf.split_to(len).to_vec();
+            self.answer(req);
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        self.tx.take();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::Mutex as TokioMutex;
+    use trust_dns_proto::op::response_code::ResponseCode;
+    use trust_dns_proto::rr::record_type::RecordType;
+
+    use super::super::test_utils::*;
+    use super::*;
+    use crate::common::{dns_client::DnsClient, fake_dns::FakeDns};
+    use crate::session::SocksAddr;
+
+    fn sess() -> Session {
+        Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Ip("1.1.1.1:53".parse().unwrap()),
+        }
+    }
+
+    async fn read_response(stream: &mut Box<dyn ProxyStream>) -> (u16, ResponseCode, Vec<IpAddr>) {
+        let len = stream.read_u16().await.unwrap() as usize;
+        let mut resp = vec![0u8; len];
+        stream.read_exact(&mut resp).await.unwrap();
+        answers(&resp)
+    }
+
+    #[tokio::test]
+    async fn test_dns_tcp_a_query() {
+        let upstream = upstream(Ipv4Addr::new(5, 6, 7, 8)).await;
+        let dns_client = DnsClient::new(vec![upstream], "127.0.0.1:0".parse().unwrap());
+        let handler = Handler {
+            responder: Arc::new(Responder::new(Arc::new(dns_client), None)),
+        };
+        let mut stream = handler.handle(&sess(), None).await.unwrap();
+
+        // The query is written in two pieces.
+        let req = query("example.com.", 3, RecordType::A);
+        let mut framed = (req.len() as u16).to_be_bytes().to_vec();
+        framed.extend_from_slice(&req);
+        stream.write_all(&framed[..5]).await.unwrap();
+        stream.write_all(&framed[5..]).await.unwrap();
+        let (id, code, ips) = read_response(&mut stream).await;
+        assert_eq!(id, 3);
+        assert_eq!(code, ResponseCode::NoError);
+        assert_eq!(ips, vec![IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8))]);
+
+        stream.shutdown().await.unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(stream.read_to_end(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dns_tcp_fake_ip() {
+        let fake_dns = Arc::new(TokioMutex::new(FakeDns::new()));
+        let handler = Handler {
+            responder: Arc::new(Responder::new(
+                Arc::new(DnsClient::default()),
+                Some(fake_dns.clone()),
+            )),
+        };
+        let mut stream = handler.handle(&sess(), None).await.unwrap();
+        for id in 0..2u16 {
+            let domain = format!("host{}.example.com.", id);
+            let req = query(&domain, id, RecordType::A);
+            stream.write_u16(req.len() as u16).await.unwrap();
+            stream.write_all(&req).await.unwrap();
+            let (resp_id, code, ips) = read_response(&mut stream).await;
+            assert_eq!(resp_id, id);
+            assert_eq!(code, ResponseCode::NoError);
+            assert!(fake_dns.lock().await.is_fake_ip(&ips[0]));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::*;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+
+use super::Responder;
+use crate::{
+    proxy::{
+        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
+        UdpTransportType,
+    },
+    session::Session,
+};
+
+pub struct Handler {
+    pub responder: Arc<Responder>,
+}
+
+#[async_trait]
+impl ProxyUdpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn udp_transport_type(&self) -> UdpTransportType {
+        UdpTransportType::Packet
+    }
+
+    async fn connect<'a>(
+        &'a self,
+        _sess: &'a Session,
+        _datagram: Option<Box<dyn ProxyDatagram>>,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        let (tx, rx) = mpsc::channel(64);
+        Ok(Box::new(Datagram {
+            responder: self.responder.clone(),
+            tx,
+            rx,
+        }))
+    }
+}
+
+/// Every packet sent is taken as a DNS query, the response is received as
+/// if it came from the address the query was sent to.
+pub struct Datagram {
+    responder: Arc<Responder>,
+    tx: Sender<(Vec<u8>, SocketAddr)>,
+    rx: Receiver<(Vec<u8>, SocketAddr)>,
+}
+
+impl ProxyDatagram for Datagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        (
+            Box::new(DatagramRecvHalf(self.rx)),
+            Box::new(DatagramSendHalf {
+                responder: self.responder,
+                tx: self.tx,
+            }),
+        )
+    }
+}
+
+pub struct DatagramRecvHalf(Receiver<(Vec<u8>, SocketAddr)>);
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for DatagramRecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.0.recv().await {
+            Some((resp, src)) => {
+                if resp.len() > buf.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "buffer too small for dns response",
+                    ));
+                }
+                (&mut buf[..resp.len()]).copy_from_slice(&resp);
+                Ok((resp.len(), src))
+            }
+            None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "dns closed")),
+        }
+    }
+}
+
+pub struct DatagramSendHalf {
+    responder: Arc<Responder>,
+    tx: Sender<(Vec<u8>, SocketAddr)>,
+}
+
+#[async_trait]
+impl ProxyDatagramSendHalf for DatagramSendHalf {
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        // Answers asynchronously so that a slow lookup doesn't hold up the
+        // following queries.
+        let responder = self.responder.clone();
+        let mut tx = self.tx.clone();
+        let req = buf.to_vec();
+        let target = *target;
+        tokio::spawn(async move {
+            match responder.answer(&req).await {
+                Ok(resp) => {
+                    let _ = tx.send((resp, target)).await;
+                }
+                Err(e) => debug!("answer dns query failed: {}", e),
+            }
+        });
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use tokio::sync::Mutex as TokioMutex;
+    use trust_dns_proto::op::response_code::ResponseCode;
+    use trust_dns_proto::rr::record_type::RecordType;
+
+    use super::super::test_utils::*;
+    use super::*;
+    use crate::common::{dns_client::DnsClient, fake_dns::FakeDns};
+    use crate::session::SocksAddr;
+
+    fn sess() -> Session {
+        Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Ip("1.1.1.1:53".parse().unwrap()),
+        }
+    }
+
+    async fn exchange(handler: &Handler, req: Vec<u8>) -> (u16, ResponseCode, Vec<IpAddr>) {
+        let datagram = handler.connect(&sess(), None, None).await.unwrap();
+        let (mut r, mut s) = datagram.split();
+        let target = "1.1.1.1:53".parse().unwrap();
+        s.send_to(&req, &target).await.unwrap();
+        let mut buf = [0u8; 512];
+        let (n, src) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(src, target);
+        answers(&buf[..n])
+    }
+
+    #[tokio::test]
+    async fn test_dns_udp_a_query() {
+        let upstream = upstream(Ipv4Addr::new(1, 2, 3, 4)).await;
+        let dns_client = DnsClient::new(vec![upstream], "127.0.0.1:0".parse().unwrap());
+        let handler = Handler {
+            responder: Arc::new(Responder::new(Arc::new(dns_client), None)),
+        };
+
+        let (id, code, ips) = exchange(&handler, query("example.com.", 7, RecordType::A)).await;
+        assert_eq!(id, 7);
+        assert_eq!(code, ResponseCode::NoError);
+        assert_eq!(ips, vec![IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))]);
+
+        let (_, code, _) = exchange(&handler, query("example.com.", 8, RecordType::MX)).await;
+        assert_eq!(code, ResponseCode::NotImp);
+    }
+
+    #[tokio::test]
+    async fn test_dns_udp_fake_ip() {
+        let fake_dns = Arc::new(TokioMutex::new(FakeDns::new()));
+        let handler = Handler {
+            responder: Arc::new(Responder::new(
+                Arc::new(DnsClient::default()),
+                Some(fake_dns.clone()),
+            )),
+        };
+
+        let (id, code, ips) = exchange(&handler, query("example.com.", 9, RecordType::A)).await;
+        assert_eq!(id, 9);
+        assert_eq!(code, ResponseCode::NoError);
+        assert_eq!(ips.len(), 1);
+        let mut fake_dns = fake_dns.lock().await;
+        assert!(fake_dns.is_fake_ip(&ips[0]));
+        assert_eq!(
+            fake_dns.query_domain(&ips[0]).as_deref(),
+            Some("example.com")
+        );
+    }
+}
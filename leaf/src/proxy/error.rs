@@ -0,0 +1,183 @@
+use std::io;
+use std::time::Duration;
+
+use thiserror::Error;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors of proxy handlers.
+///
+/// Handler traits return `io::Error`, a `ProxyError` is carried as the inner
+/// error of the `io::Error` and can be recovered with `ProxyError::downcast_ref`.
+#[derive(Debug, Error)]
+pub enum ProxyError {
+    #[error("resolve {host} failed: {source}")]
+    Dns {
+        host: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("connect failed: {0}")]
+    Connect(#[source] io::Error),
+    #[error("tls handshake failed: {0}")]
+    Tls(#[source] io::Error),
+    #[error("handshake failed: {0}")]
+    Handshake(#[source] BoxError),
+    #[error("authentication failed: {0}")]
+    Auth(String),
+    #[error("timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("protocol error: {0}")]
+    Protocol(#[source] BoxError),
+    #[error("all outbound attempts failed, last error: {0}")]
+    AllFailed(#[source] io::Error),
+    #[error("no outbound available")]
+    NoOutbound,
+    #[error("connection closed after its max lifetime of {0:?}")]
+    MaxLifetime(Duration),
+}
+
+impl ProxyError {
+    /// Returns the `ProxyError` wrapped in `err`, if any.
+    pub fn downcast_ref(err: &io::Error) -> Option<&ProxyError> {
+        err.get_ref().and_then(|e| e.downcast_ref::<ProxyError>())
+    }
+
+    /// Returns the stage of the outbound connect the error happened at, for
+    /// `AllFailed` the stage the last attempt failed at.
+    pub fn stage(&self) -> FailureStage {
+        match self {
+            ProxyError::Dns { .. } => FailureStage::Dns,
+            ProxyError::Connect(_) => FailureStage::Connect,
+            ProxyError::Tls(_) => FailureStage::Tls,
+            ProxyError::Handshake(_) | ProxyError::Auth(_) => FailureStage::Handshake,
+            ProxyError::AllFailed(e) => FailureStage::of(e),
+            _ => FailureStage::Other,
+        }
+    }
+
+    fn kind(&self) -> io::ErrorKind {
+        match self {
+            ProxyError::Connect(e) | ProxyError::Tls(e) | ProxyError::AllFailed(e) => e.kind(),
+            ProxyError::Auth(_) => io::ErrorKind::PermissionDenied,
+            ProxyError::Timeout(_) => io::ErrorKind::TimedOut,
+            ProxyError::Protocol(_) => io::ErrorKind::InvalidData,
+            ProxyError::MaxLifetime(_) => io::ErrorKind::ConnectionAborted,
+            _ => io::ErrorKind::Other,
+        }
+    }
+}
+
+/// Stages of connecting to an outbound, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureStage {
+    Dns,
+    Connect,
+    Tls,
+    Handshake,
+    /// The connection was made but closed or failed before the first byte
+    /// of the response.
+    FirstByte,
+    /// Errors not labeled with a stage, e.g. timeouts.
+    Other,
+}
+
+impl FailureStage {
+    /// Returns the stage `err` happened at, `Other` if it doesn't carry a
+    /// `ProxyError`.
+    pub fn of(err: &io::Error) -> Self {
+        ProxyError::downcast_ref(err).map_or(FailureStage::Other, ProxyError::stage)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureStage::Dns => "dns",
+            FailureStage::Connect => "connect",
+            FailureStage::Tls => "tls",
+            FailureStage::Handshake => "handshake",
+            FailureStage::FirstByte => "first_byte",
+            FailureStage::Other => "other",
+        }
+    }
+}
+
+impl From<ProxyError> for io::Error {
+    fn from(e: ProxyError) -> Self {
+        io::Error::new(e.kind(), e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_error_survives_io_error() {
+        let err: io::Error = ProxyError::Auth("bad password".to_string()).into();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Auth(msg)) if msg == "bad password"
+        ));
+        assert_eq!(err.to_string(), "authentication failed: bad password");
+
+        let err: io::Error = ProxyError::Timeout(Duration::from_secs(3)).into();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Timeout(_))
+        ));
+
+        let err: io::Error = ProxyError::Dns {
+            host: "example.com".to_string(),
+            source: "no record".into(),
+        }
+        .into();
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Dns { host, .. }) if host == "example.com"
+        ));
+    }
+
+    #[test]
+    fn test_proxy_error_keeps_source() {
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        let err: io::Error = ProxyError::AllFailed(ProxyError::Connect(refused).into()).into();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        let last = match ProxyError::downcast_ref(&err) {
+            Some(ProxyError::AllFailed(last)) => last,
+            e => panic!("unexpected error {:?}", e),
+        };
+        assert!(matches!(
+            ProxyError::downcast_ref(last),
+            Some(ProxyError::Connect(_))
+        ));
+
+        let err: io::Error = ProxyError::Handshake("unexpected reply".into()).into();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(ProxyError::downcast_ref(&err).is_some());
+        assert!(ProxyError::downcast_ref(&io::Error::new(io::ErrorKind::Other, "plain")).is_none());
+    }
+
+    #[test]
+    fn test_failure_stage() {
+        let err: io::Error = ProxyError::Dns {
+            host: "example.com".to_string(),
+            source: "no record".into(),
+        }
+        .into();
+        assert_eq!(FailureStage::of(&err), FailureStage::Dns);
+
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        let err: io::Error = ProxyError::AllFailed(ProxyError::Tls(refused).into()).into();
+        assert_eq!(FailureStage::of(&err), FailureStage::Tls);
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+
+        let err: io::Error = ProxyError::Auth("bad password".to_string()).into();
+        assert_eq!(FailureStage::of(&err), FailureStage::Handshake);
+        let err: io::Error = ProxyError::Timeout(Duration::from_secs(3)).into();
+        assert_eq!(FailureStage::of(&err), FailureStage::Other);
+        let err = io::Error::new(io::ErrorKind::Other, "plain");
+        assert_eq!(FailureStage::of(&err), FailureStage::Other);
+    }
+}
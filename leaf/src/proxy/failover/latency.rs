@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Number of health check results kept per actor.
+pub const WINDOW_SIZE: usize = 512;
+
+/// Percentiles of the response times of an actor, in milliseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencySummary {
+    /// Number of health checks the percentiles are computed from.
+    pub samples: usize,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+/// Response times of the last successful health checks of an actor, the
+/// oldest ones are dropped once the window is full.
+pub struct LatencyWindow {
+    samples: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl LatencyWindow {
+    pub fn new(capacity: usize) -> Self {
+        LatencyWindow {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn record(&mut self, millis: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(millis);
+    }
+
+    /// Returns the smallest response time greater than or equal to `p`
+    /// percent of the samples, `None` without samples.
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let mut sorted: Vec<u64> = self.samples.iter().cloned().collect();
+        sorted.sort_unstable();
+        percentile(&sorted, p)
+    }
+
+    pub fn summary(&self) -> Option<LatencySummary> {
+        let mut sorted: Vec<u64> = self.samples.iter().cloned().collect();
+        sorted.sort_unstable();
+        Some(LatencySummary {
+            samples: sorted.len(),
+            p50: percentile(&sorted, 50.0)?,
+            p95: percentile(&sorted, 95.0)?,
+            p99: percentile(&sorted, 99.0)?,
+        })
+    }
+}
+
+// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len() as f64 / 100.0).ceil() as usize;
+    Some(sorted[rank.max(1).min(sorted.len()) - 1])
+}
+
+/// Latency windows of the actors of a failover handler, by actor index,
+/// shared with its health check task.
+#[derive(Clone)]
+pub struct Latencies(Arc<Mutex<Vec<LatencyWindow>>>);
+
+impl Latencies {
+    pub fn new(actors: usize) -> Self {
+        let windows = (0..actors)
+            .map(|_| LatencyWindow::new(WINDOW_SIZE))
+            .collect();
+        Latencies(Arc::new(Mutex::new(windows)))
+    }
+
+    pub fn record(&self, actor: usize, millis: u64) {
+        if let Some(w) = self.0.lock().unwrap().get_mut(actor) {
+            w.record(millis);
+        }
+    }
+
+    pub fn summary(&self, actor: usize) -> Option<LatencySummary> {
+        self.0.lock().unwrap().get(actor)?.summary()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut w = LatencyWindow::new(1000);
+        assert_eq!(w.summary(), None);
+        // Shuffled so the order of arrival doesn't matter.
+        for i in (1..=100).rev().step_by(2).chain((1..=100).step_by(2)) {
+            w.record(i);
+        }
+        assert_eq!(
+            w.summary(),
+            Some(LatencySummary {
+                samples: 100,
+                p50: 50,
+                p95: 95,
+                p99: 99,
+            })
+        );
+        assert_eq!(w.percentile(0.0), Some(1));
+        assert_eq!(w.percentile(100.0), Some(100));
+
+        // A few slow checks show in the tail only.
+        let mut w = LatencyWindow::new(1000);
+        for _ in 0..980 {
+            w.record(20);
+        }
+        for _ in 0..20 {
+            w.record(3000);
+        }
+        let s = w.summary().unwrap();
+        assert_eq!((s.p50, s.p95, s.p99), (20, 20, 3000));
+    }
+
+    #[test]
+    fn test_latency_window_rolls() {
+        let mut w = LatencyWindow::new(100);
+        for i in 1..=300 {
+            w.record(i);
+        }
+        // Only 201..=300 are left.
+        let s = w.summary().unwrap();
+        assert_eq!(s.samples, 100);
+        assert_eq!((s.p50, s.p95, s.p99), (250, 295, 299));
+        assert_eq!(w.percentile(0.0), Some(201));
+    }
+
+    #[test]
+    fn test_latencies_by_actor() {
+        let latencies = Latencies::new(2);
+        latencies.record(1, 10);
+        latencies.record(5, 10); // no such actor
+        assert_eq!(latencies.summary(0), None);
+        assert_eq!(latencies.summary(1).unwrap().p99, 10);
+    }
+}
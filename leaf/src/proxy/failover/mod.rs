@@ -1,7 +1,886 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::*;
+use tokio::sync::Notify;
+
+use super::ProxyHandler;
+use crate::common::{dns_client::DnsClient, health::HealthMap, shutdown::ShutdownToken};
+use crate::session::SocksAddr;
+
+pub mod latency;
+pub mod prewarm;
 pub mod tcp;
 pub mod udp;
 
+pub use super::self_test::DEFAULT_USER_AGENT;
+pub use latency::LatencySummary;
 pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
 
 pub static NAME: &str = "failover";
+
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct Measure(usize, u128); // (index, duration in millis)
+
+// Scores of failed checks, above any response time, the earlier the check
+// failed the higher the score.
+const HANDSHAKE_FAILED: u128 = u128::MAX;
+const TIMED_OUT: u128 = u128::MAX - 1;
+// Nothing of the request could be written.
+const WRITE_FAILED: u128 = u128::MAX - 2;
+// The connection was reset while writing the request.
+const WRITE_RESET: u128 = u128::MAX - 3;
+// Part of the request was written before the write failed.
+const WRITE_PARTIAL: u128 = u128::MAX - 4;
+const READ_FAILED: u128 = u128::MAX - 5;
+
+impl Measure {
+    fn is_ok(&self) -> bool {
+        self.1 < READ_FAILED
+    }
+
+    // Combines the scores of actor `i` with several check targets. With at
+    // least `quorum` targets reached, the score is their average response
+    // time, otherwise that of the check which got the furthest.
+    fn quorum(i: usize, scores: &[u128], quorum: usize) -> Self {
+        let ok: Vec<u128> = scores
+            .iter()
+            .cloned()
+            .filter(|s| *s < READ_FAILED)
+            .collect();
+        if !ok.is_empty() && ok.len() >= quorum {
+            return Measure(i, ok.iter().sum::<u128>() / ok.len() as u128);
+        }
+        let failed = scores.iter().cloned().filter(|s| *s >= READ_FAILED).min();
+        Measure(i, failed.unwrap_or(HANDSHAKE_FAILED))
+    }
+}
+
+/// How much faster than the primary actor a challenger must be to replace
+/// it after a health check.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SwitchMargin {
+    /// Faster by more than this many milliseconds.
+    Millis(u64),
+    /// Faster by more than this percentage of the primary's response time.
+    Percent(u64),
+}
+
+impl SwitchMargin {
+    fn exceeded(&self, challenger: u128, primary: u128) -> bool {
+        let gain = primary.saturating_sub(challenger);
+        match *self {
+            SwitchMargin::Millis(ms) => gain > ms as u128,
+            SwitchMargin::Percent(p) => gain * 100 > primary * p as u128,
+        }
+    }
+}
+
+/// Where an actor left out of health checks goes in the schedule, whatever
+/// the results of the checked ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FixedRank {
+    /// At this position, 0 being the first, or last if there are fewer
+    /// actors.
+    At(usize),
+    /// After all checked actors.
+    Last,
+}
+
+// Places the `unprobed` actors at their ranks among the checked ones, given
+// in order.
+fn place_unprobed(mut order: Vec<usize>, unprobed: &[(usize, FixedRank)]) -> Vec<usize> {
+    let mut unprobed = unprobed.to_vec();
+    unprobed.sort_by_key(|(_, rank)| match rank {
+        FixedRank::At(pos) => *pos,
+        FixedRank::Last => usize::MAX,
+    });
+    for (i, rank) in unprobed {
+        match rank {
+            FixedRank::At(pos) => order.insert(pos.min(order.len()), i),
+            FixedRank::Last => order.push(i),
+        }
+    }
+    order
+}
+
+// The initial schedule, the checked actors in order of preference.
+fn initial_schedule(n: usize, unprobed: &[(usize, FixedRank)]) -> Vec<usize> {
+    let checked = (0..n)
+        .filter(|i| !unprobed.iter().any(|(j, _)| j == i))
+        .collect();
+    place_unprobed(checked, unprobed)
+}
+
+// Whether each actor is in service. Actors taken out of service are skipped
+// by connects and health checks.
+#[derive(Clone)]
+struct Enabled(Arc<Vec<AtomicBool>>);
+
+impl Enabled {
+    fn new(n: usize) -> Self {
+        Enabled(Arc::new((0..n).map(|_| AtomicBool::new(true)).collect()))
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0.get(i).map_or(false, |e| e.load(Ordering::Relaxed))
+    }
+
+    // Sets the actors tagged `tag`, returns whether there was any.
+    fn set(&self, actors: &[Arc<dyn ProxyHandler>], tag: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for (i, a) in actors.iter().enumerate() {
+            if a.tag() == tag {
+                self.0[i].store(enabled, Ordering::Relaxed);
+                found = true;
+            }
+        }
+        if !found {
+            warn!(
+                "no actor [{}] to {}",
+                tag,
+                if enabled { "enable" } else { "disable" }
+            );
+        }
+        found
+    }
+}
+
+/// The response time percentiles of the actors of a failover handler, by
+/// actor tag.
+pub type ActorLatencies = Vec<(String, Option<LatencySummary>)>;
+
+/// The runtime controls of a failover handler, shared with its health check
+/// task. Clones control the same handler.
+#[derive(Clone)]
+pub struct Control {
+    actors: Vec<Arc<dyn ProxyHandler>>,
+    latencies: latency::Latencies,
+    recheck: Arc<Notify>,
+    enabled: Enabled,
+}
+
+impl Control {
+    fn new(actors: Vec<Arc<dyn ProxyHandler>>) -> Self {
+        Control {
+            latencies: latency::Latencies::new(actors.len()),
+            recheck: Arc::new(Notify::new()),
+            enabled: Enabled::new(actors.len()),
+            actors,
+        }
+    }
+
+    /// Returns the response time percentiles of each actor over its last
+    /// health checks, along with its tag. Without successful checks there
+    /// are no percentiles.
+    pub fn latencies(&self) -> ActorLatencies {
+        self.actors
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.tag().to_owned(), self.latencies.summary(i)))
+            .collect()
+    }
+
+    /// Runs a health check right away instead of waiting for the check
+    /// interval, which then starts over. Meant for platform code to call on
+    /// network changes, e.g. from the path update handler of an
+    /// `NWPathMonitor` on iOS or a `ConnectivityManager.NetworkCallback` on
+    /// Android, as the actors ranked on the previous network may no longer
+    /// be the best ones. Triggers during a check run one more check after it.
+    pub fn trigger_recheck(&self) {
+        self.recheck.notify();
+    }
+
+    /// Takes the actors tagged `tag` out of service, or puts them back, e.g.
+    /// to drain an upstream for maintenance without a restart. Disabled
+    /// actors are skipped by connects and health checks. A health check runs
+    /// right away to rank the remaining actors. Returns whether there was
+    /// any actor tagged `tag`.
+    pub fn set_actor_enabled(&self, tag: &str, enabled: bool) -> bool {
+        if self.enabled.set(&self.actors, tag, enabled) {
+            self.recheck.notify();
+            return true;
+        }
+        false
+    }
+}
+
+lazy_static! {
+    static ref CONTROLS: Controls = Controls::default();
+}
+
+/// Returns the controls of the failover outbounds, by tag, for platform
+/// code to reach them at runtime.
+pub fn controls() -> &'static Controls {
+    &CONTROLS
+}
+
+/// The controls of the TCP and UDP handlers of the failover outbounds, by
+/// outbound tag. Outbounds created later replace the ones of the same tag.
+#[derive(Default)]
+pub struct Controls(Mutex<HashMap<String, (Control, Control)>>);
+
+impl Controls {
+    /// Registers the controls of the TCP and UDP handlers of the failover
+    /// outbound tagged `outbound`.
+    pub fn register(&self, outbound: &str, tcp: Control, udp: Control) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(outbound.to_string(), (tcp, udp));
+    }
+
+    /// Runs a health check of all failover outbounds right away, see
+    /// `Control::trigger_recheck`.
+    pub fn trigger_recheck(&self) {
+        for (tcp, udp) in self.0.lock().unwrap().values() {
+            tcp.trigger_recheck();
+            udp.trigger_recheck();
+        }
+    }
+
+    /// Takes the actors tagged `actor` of the failover outbound tagged
+    /// `outbound` out of service, or puts them back, see
+    /// `Control::set_actor_enabled`. Returns whether there was any.
+    pub fn set_actor_enabled(&self, outbound: &str, actor: &str, enabled: bool) -> bool {
+        match self.0.lock().unwrap().get(outbound) {
+            Some((tcp, udp)) => {
+                let found = tcp.set_actor_enabled(actor, enabled);
+                udp.set_actor_enabled(actor, enabled) || found
+            }
+            None => {
+                warn!("no failover outbound [{}]", outbound);
+                false
+            }
+        }
+    }
+
+    /// Returns the latencies of the actors of the failover outbound tagged
+    /// `outbound`, from its TCP and UDP health checks, see
+    /// `Control::latencies`.
+    pub fn latencies(&self, outbound: &str) -> Option<(ActorLatencies, ActorLatencies)> {
+        let controls = self.0.lock().unwrap();
+        let (tcp, udp) = controls.get(outbound)?;
+        Some((tcp.latencies(), udp.latencies()))
+    }
+}
+
+// Remembers the primary actor across health checks, so that actors with
+// about the same response times don't take turns.
+struct Sticky {
+    margin: Option<SwitchMargin>,
+    primary: Option<usize>,
+}
+
+impl Sticky {
+    fn new(margin: Option<SwitchMargin>) -> Self {
+        Sticky {
+            margin,
+            primary: None,
+        }
+    }
+
+    // Takes the measures sorted fastest first, moves the primary back to
+    // the front unless it failed or the fastest actor beats it by more
+    // than the margin.
+    fn rank(&mut self, measures: &mut Vec<Measure>) {
+        if let (Some(margin), Some(primary)) = (self.margin, self.primary) {
+            if let Some(pos) = measures.iter().position(|m| m.0 == primary) {
+                if pos > 0
+                    && measures[pos].is_ok()
+                    && !margin.exceeded(measures[0].1, measures[pos].1)
+                {
+                    let m = measures.remove(pos);
+                    measures.insert(0, m);
+                }
+            }
+        }
+        self.primary = measures.first().map(|m| m.0);
+    }
+}
+
+// Parses a `http://host[:port]/path` URL into the address to connect to and
+// the path.
+fn parse_url(url: &str) -> Option<(SocksAddr, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let authority = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    Some((SocksAddr::try_from(authority).ok()?, path.to_string()))
+}
+
+/// The url-test policy of common proxy clients. Actors are checked by
+/// requesting a URL and reading the status line of the response, the
+/// fastest actor is used alone and only replaced when it fails a check or
+/// another one is faster by more than the switch margin.
+#[derive(Clone, Debug)]
+pub struct UrlTest {
+    pub destination: SocksAddr,
+    pub path: String,
+}
+
+impl UrlTest {
+    /// Parses a `http://host[:port]/path` URL.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (destination, path) = parse_url(url)?;
+        Some(UrlTest { destination, path })
+    }
+}
+
+/// An object downloaded through every actor during TCP health checks, so
+/// that actors are ranked by how long a download of that size takes rather
+/// than by their response time alone. Each check costs up to `size` bytes
+/// per actor.
+#[derive(Clone, Debug)]
+pub struct ThroughputProbe {
+    pub destination: SocksAddr,
+    /// Path of the object, requested with a plain `GET`.
+    pub path: String,
+    /// Bytes to download, the response head included.
+    pub size: u64,
+}
+
+impl ThroughputProbe {
+    /// Parses a `http://host[:port]/path` URL.
+    pub fn from_url(url: &str, size: u64) -> Option<Self> {
+        let (destination, path) = parse_url(url)?;
+        Some(ThroughputProbe {
+            destination,
+            path,
+            size,
+        })
+    }
+
+    // Scores an actor in milliseconds: its response time plus the time the
+    // whole object takes at the throughput measured over `received` bytes.
+    fn score(&self, latency: u128, received: u64, elapsed: Duration) -> Option<u128> {
+        if received == 0 {
+            return None;
+        }
+        Some(latency + elapsed.as_millis() * self.size as u128 / received as u128)
+    }
+}
+
+/// The DNS query sent through every actor in UDP health checks. Only a
+/// well-formed answer to the query counts as a response, so that garbage
+/// returned by e.g. a captive portal doesn't pass for a working actor.
+#[derive(Clone, Debug)]
+pub struct DnsProbe {
+    /// The resolver queried.
+    pub server: SocketAddr,
+    /// The domain looked up, for A records.
+    pub domain: String,
+    /// Addresses the answer must include one of, any address if empty.
+    pub expected: Vec<IpAddr>,
+    /// Time to wait for the answer, the handshake of the actor included.
+    pub timeout: Duration,
+}
+
+impl Default for DnsProbe {
+    fn default() -> Self {
+        DnsProbe {
+            server: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53),
+            domain: "www.google.com".to_string(),
+            expected: Vec::new(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+fn mbps(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 * 8.0 / 1_000_000.0 / elapsed.as_secs_f64().max(0.001)
+}
+
+/// Builds failover handlers, unset options take the same defaults as the
+/// config files.
+///
+/// ```ignore
+/// let tcp: failover::TcpHandler = HandlerBuilder::default()
+///     .actors(actors)
+///     .fail_timeout(4)
+///     .build();
+/// ```
+#[derive(Clone)]
+pub struct HandlerBuilder {
+    actors: Vec<Arc<dyn ProxyHandler>>,
+    fail_timeout: u32,
+    health_check: bool,
+    check_interval: u32,
+    failover: bool,
+    switch_margin: Option<SwitchMargin>,
+    throughput_probe: Option<ThroughputProbe>,
+    url_test: Option<UrlTest>,
+    check_targets: Vec<UrlTest>,
+    quorum: usize,
+    dns_probe: DnsProbe,
+    user_agent: String,
+    health: Option<Arc<HealthMap>>,
+    prewarm: Option<(usize, Arc<DnsClient>)>,
+    unprobed: Vec<(usize, FixedRank)>,
+    shutdown: ShutdownToken,
+}
+
+impl Default for HandlerBuilder {
+    fn default() -> Self {
+        HandlerBuilder {
+            actors: Vec::new(),
+            fail_timeout: 4,
+            health_check: true,
+            check_interval: 300,
+            failover: true,
+            switch_margin: None,
+            throughput_probe: None,
+            url_test: None,
+            check_targets: Vec::new(),
+            quorum: 0,
+            dns_probe: DnsProbe::default(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            health: None,
+            prewarm: None,
+            unprobed: Vec::new(),
+            shutdown: ShutdownToken::never(),
+        }
+    }
+}
+
+impl HandlerBuilder {
+    /// Outbounds to try, in order of preference.
+    pub fn actors(mut self, actors: Vec<Arc<dyn ProxyHandler>>) -> Self {
+        self.actors = actors;
+        self
+    }
+
+    /// Time to wait for an actor before trying the next one, in seconds.
+    pub fn fail_timeout(mut self, secs: u32) -> Self {
+        self.fail_timeout = secs;
+        self
+    }
+
+    /// Whether to periodically reorder actors by their latency.
+    pub fn health_check(mut self, enabled: bool) -> Self {
+        self.health_check = enabled;
+        self
+    }
+
+    /// Interval between health checks, in seconds.
+    pub fn check_interval(mut self, secs: u32) -> Self {
+        self.check_interval = secs;
+        self
+    }
+
+    /// Whether to fall through to the next actor on failure, otherwise only
+    /// the best actor of the last health check is used.
+    pub fn failover(mut self, enabled: bool) -> Self {
+        self.failover = enabled;
+        self
+    }
+
+    /// Keeps the best actor of the previous health check first unless
+    /// another one is faster by more than `margin`. Without a margin, the
+    /// fastest actor always goes first.
+    pub fn switch_margin(mut self, margin: Option<SwitchMargin>) -> Self {
+        self.switch_margin = margin;
+        self
+    }
+
+    /// Also measures the throughput of actors in TCP health checks, off by
+    /// default as it costs a download per actor and check.
+    pub fn throughput_probe(mut self, probe: Option<ThroughputProbe>) -> Self {
+        self.throughput_probe = probe;
+        self
+    }
+
+    /// Selects actors with the url-test policy, which never falls through
+    /// to the next actor whatever `failover` is set to.
+    pub fn url_test(mut self, test: Option<UrlTest>) -> Self {
+        self.url_test = test;
+        self
+    }
+
+    /// URLs probed through every actor in TCP health checks instead of the
+    /// url-test URL or the default `HEAD` to `www.google.com`. The response
+    /// time of an actor is the average over the targets it reached.
+    pub fn check_targets(mut self, targets: Vec<UrlTest>) -> Self {
+        self.check_targets = targets;
+        self
+    }
+
+    /// Number of check targets an actor must reach to pass a TCP health
+    /// check, a majority of them if 0.
+    pub fn quorum(mut self, quorum: usize) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    /// The query of UDP health checks, an A query for `www.google.com` on
+    /// 8.8.8.8 by default.
+    pub fn dns_probe(mut self, probe: DnsProbe) -> Self {
+        self.dns_probe = probe;
+        self
+    }
+
+    /// The User-Agent of the requests of TCP health checks, some servers
+    /// reject requests without one.
+    pub fn user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    /// Publishes the results of TCP health checks to `health`, so that other
+    /// handlers can avoid failing actors.
+    pub fn health(mut self, health: Option<Arc<HealthMap>>) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Keeps a connection to the servers of the first `backups` actors
+    /// after the primary, resolved with `dns_client`, so that TCP connects
+    /// falling through to them skip the connect to the server. Connections
+    /// to actors failing health checks are closed.
+    pub fn prewarm(mut self, backups: usize, dns_client: Arc<DnsClient>) -> Self {
+        self.prewarm = if backups > 0 {
+            Some((backups, dns_client))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Leaves actor `i` out of health checks and keeps it at `rank` in the
+    /// schedule, e.g. a direct fallback which would always pass checks, or
+    /// an actor too costly to probe.
+    pub fn unprobed(mut self, i: usize, rank: FixedRank) -> Self {
+        self.unprobed.retain(|(j, _)| *j != i);
+        self.unprobed.push((i, rank));
+        self
+    }
+
+    /// Stops the health check once `shutdown` is signaled.
+    pub fn shutdown(mut self, shutdown: ShutdownToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Builds a TCP or UDP failover handler.
+    pub fn build<H: From<HandlerBuilder>>(self) -> H {
+        H::from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio::time::delay_for;
+
+    use super::*;
+    use crate::common::shutdown::Shutdown;
+    use crate::proxy::{
+        Handler, ProxyDatagram, ProxyError, ProxyHandlerType, ProxyStream, ProxyTcpHandler,
+        ProxyUdpHandler, UdpTransportType,
+    };
+    use crate::session::{Session, SocksAddr};
+
+    // An actor counting how many times it's been used.
+    struct Counting(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handler_builder() {
+        let builder = HandlerBuilder::default()
+            .fail_timeout(2)
+            .health_check(false)
+            .check_interval(60)
+            .failover(false);
+        let tcp: TcpHandler = builder.clone().build();
+        assert_eq!(tcp.fail_timeout, 2);
+        assert!(tcp.actors.is_empty());
+        assert!(tcp.health_check_task.lock().await.is_none());
+        let udp: UdpHandler = builder.build();
+        assert_eq!(udp.fail_timeout, 2);
+
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        let err = match tcp.handle(&sess, None).await {
+            Ok(_) => panic!("handled without actors"),
+            Err(e) => e,
+        };
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::NoOutbound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_handler_builder_defaults() {
+        let tcp: TcpHandler = HandlerBuilder::default().build();
+        assert_eq!(tcp.fail_timeout, 4);
+        // The health check task is started along with the first flow.
+        assert!(tcp.health_check_task.lock().await.is_some());
+    }
+
+    fn rank(sticky: &mut Sticky, latencies: &[(usize, u128)]) -> usize {
+        let mut measures: Vec<Measure> = latencies.iter().map(|(i, t)| Measure(*i, *t)).collect();
+        measures.sort_by(|a, b| a.1.cmp(&b.1));
+        sticky.rank(&mut measures);
+        measures[0].0
+    }
+
+    #[test]
+    fn test_sticky_within_margin() {
+        let mut sticky = Sticky::new(Some(SwitchMargin::Millis(20)));
+        assert_eq!(rank(&mut sticky, &[(0, 100), (1, 110)]), 0);
+        // The two actors keep overtaking each other by less than 20ms.
+        for _ in 0..5 {
+            assert_eq!(rank(&mut sticky, &[(0, 105), (1, 95)]), 0);
+            assert_eq!(rank(&mut sticky, &[(0, 95), (1, 112)]), 0);
+        }
+        // Way faster.
+        assert_eq!(rank(&mut sticky, &[(0, 100), (1, 50)]), 1);
+        assert_eq!(rank(&mut sticky, &[(0, 90), (1, 100)]), 1);
+    }
+
+    #[test]
+    fn test_sticky_percent_margin() {
+        let mut sticky = Sticky::new(Some(SwitchMargin::Percent(10)));
+        assert_eq!(rank(&mut sticky, &[(0, 200), (1, 210)]), 0);
+        assert_eq!(rank(&mut sticky, &[(0, 200), (1, 185)]), 0);
+        assert_eq!(rank(&mut sticky, &[(0, 200), (1, 170)]), 1);
+    }
+
+    #[test]
+    fn test_sticky_primary_failed() {
+        let mut sticky = Sticky::new(Some(SwitchMargin::Millis(1000)));
+        assert_eq!(rank(&mut sticky, &[(0, 100), (1, 110)]), 0);
+        assert_eq!(rank(&mut sticky, &[(0, u128::MAX - 1), (1, 110)]), 1);
+    }
+
+    #[test]
+    fn test_place_unprobed() {
+        let unprobed = vec![(4, FixedRank::Last), (1, FixedRank::At(0))];
+        assert_eq!(initial_schedule(5, &unprobed), vec![1, 0, 2, 3, 4]);
+        assert_eq!(
+            place_unprobed(vec![3, 2, 0], &unprobed),
+            vec![1, 3, 2, 0, 4]
+        );
+        // Beyond the checked actors.
+        assert_eq!(
+            place_unprobed(vec![2, 0], &[(1, FixedRank::At(5))]),
+            vec![2, 0, 1]
+        );
+        assert_eq!(
+            place_unprobed(Vec::new(), &[(1, FixedRank::Last), (0, FixedRank::At(1))]),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn test_quorum_measure() {
+        let scores = vec![100, 300, HANDSHAKE_FAILED];
+        assert_eq!(Measure::quorum(0, &scores, 2), Measure(0, 200));
+        assert_eq!(Measure::quorum(0, &scores, 3), Measure(0, HANDSHAKE_FAILED));
+        let scores = vec![100, TIMED_OUT, READ_FAILED];
+        assert_eq!(Measure::quorum(1, &scores, 2), Measure(1, READ_FAILED));
+        assert_eq!(Measure::quorum(1, &[], 1), Measure(1, HANDSHAKE_FAILED));
+    }
+
+    #[test]
+    fn test_sticky_without_margin() {
+        let mut sticky = Sticky::new(None);
+        assert_eq!(rank(&mut sticky, &[(0, 100), (1, 110)]), 0);
+        assert_eq!(rank(&mut sticky, &[(0, 105), (1, 104)]), 1);
+    }
+
+    #[test]
+    fn test_throughput_probe() {
+        let probe = ThroughputProbe::from_url("http://example.com:8080/1m", 1_000_000).unwrap();
+        assert_eq!(
+            probe.destination,
+            SocksAddr::Domain("example.com".to_string(), 8080)
+        );
+        assert_eq!(probe.path, "/1m");
+        let probe = ThroughputProbe::from_url("http://127.0.0.1", 1_000_000).unwrap();
+        assert_eq!(
+            probe.destination,
+            SocksAddr::Ip("127.0.0.1:80".parse().unwrap())
+        );
+        assert_eq!(probe.path, "/");
+        assert!(ThroughputProbe::from_url("https://example.com/", 1).is_none());
+
+        // Half the object in 400ms, the whole of it would take 800ms.
+        assert_eq!(
+            probe.score(100, 500_000, Duration::from_millis(400)),
+            Some(900)
+        );
+        assert_eq!(probe.score(100, 0, Duration::from_millis(400)), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_health_check() {
+        let tcp_count = Arc::new(AtomicUsize::new(0));
+        let udp_count = Arc::new(AtomicUsize::new(0));
+        let actor: Arc<dyn ProxyHandler> = Handler::new(
+            "counting".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Counting(tcp_count.clone())),
+            Box::new(Counting(udp_count.clone())),
+        );
+        let shutdown = Shutdown::new();
+        // Checks continuously.
+        let builder = HandlerBuilder::default()
+            .actors(vec![actor.clone()])
+            .check_interval(0)
+            .shutdown(shutdown.token());
+        let tcp: TcpHandler = builder.clone().build();
+        let udp: UdpHandler = builder.build();
+
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // Starts the health checks.
+        assert!(tcp.handle(&sess, None).await.is_err());
+        assert!(udp.connect(&sess, None, None).await.is_err());
+        delay_for(Duration::from_millis(100)).await;
+        assert!(tcp_count.load(Ordering::SeqCst) > 1);
+        assert!(udp_count.load(Ordering::SeqCst) > 1);
+
+        shutdown.signal();
+        delay_for(Duration::from_millis(100)).await;
+        let tcp_checks = tcp_count.load(Ordering::SeqCst);
+        let udp_checks = udp_count.load(Ordering::SeqCst);
+        delay_for(Duration::from_millis(100)).await;
+        assert_eq!(tcp_count.load(Ordering::SeqCst), tcp_checks);
+        assert_eq!(udp_count.load(Ordering::SeqCst), udp_checks);
+        // The stopped tasks no longer hold the actor, only this test and the
+        // two handlers do.
+        assert_eq!(Arc::strong_count(&actor), 3);
+    }
+
+    #[tokio::test]
+    async fn test_disable_actor() {
+        let counts: Vec<_> = (0..2).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let actors: Vec<Arc<dyn ProxyHandler>> = counts
+            .iter()
+            .zip(&["a", "b"])
+            .map(|(count, tag)| {
+                Handler::new(
+                    tag.to_string(),
+                    colored::Color::White,
+                    ProxyHandlerType::Endpoint,
+                    Box::new(Counting(count.clone())),
+                    Box::new(Counting(count.clone())),
+                ) as Arc<dyn ProxyHandler>
+            })
+            .collect();
+        let used = || -> Vec<usize> { counts.iter().map(|c| c.load(Ordering::SeqCst)).collect() };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+
+        let builder = HandlerBuilder::default()
+            .actors(actors.clone())
+            .health_check(false);
+        let tcp: TcpHandler = builder.clone().build();
+        let udp: UdpHandler = builder.build();
+        // Through the controls registered for the outbound, as platform code
+        // does.
+        controls().register("test_disable_actor", tcp.control(), udp.control());
+        assert!(!controls().set_actor_enabled("test_disable_actor", "c", false));
+        assert!(!controls().set_actor_enabled("unknown", "a", false));
+        assert!(controls().set_actor_enabled("test_disable_actor", "a", false));
+        assert!(tcp.handle(&sess, None).await.is_err());
+        assert!(udp.connect(&sess, None, None).await.is_err());
+        assert_eq!(used(), vec![0, 2]);
+        assert!(controls().set_actor_enabled("test_disable_actor", "a", true));
+        assert!(tcp.handle(&sess, None).await.is_err());
+        assert!(udp.connect(&sess, None, None).await.is_err());
+        assert_eq!(used(), vec![2, 4]);
+
+        // Health checks skip the disabled actor too.
+        let shutdown = Shutdown::new();
+        let builder = HandlerBuilder::default()
+            .actors(actors)
+            .check_interval(0)
+            .shutdown(shutdown.token());
+        let tcp: TcpHandler = builder.clone().build();
+        let udp: UdpHandler = builder.build();
+        tcp.set_actor_enabled("a", false);
+        udp.set_actor_enabled("a", false);
+        let before = used();
+        assert!(tcp.handle(&sess, None).await.is_err());
+        assert!(udp.connect(&sess, None, None).await.is_err());
+        delay_for(Duration::from_millis(100)).await;
+        assert_eq!(used()[0], before[0]);
+        assert!(used()[1] > before[1] + 2);
+        tcp.set_actor_enabled("a", true);
+        udp.set_actor_enabled("a", true);
+        delay_for(Duration::from_millis(100)).await;
+        assert!(used()[0] > before[0]);
+        shutdown.signal();
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::*;
+use tokio::sync::{Mutex as TokioMutex, Notify};
+use tokio::time::{timeout, Instant};
+
+use super::Enabled;
+use crate::{
+    common::dns_client::DnsClient,
+    proxy::{ProxyHandler, ProxyStream, ProxyTcpHandler},
+};
+
+/// Warm connections older than this are replaced, before servers drop them
+/// for being idle.
+pub const WARM_MAX_IDLE: Duration = Duration::from_secs(30);
+
+/// Time allowed to connect to the server of an actor.
+const WARM_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Idle connections to the servers of the first backup actors, handed over
+/// to connects falling through to them so that they skip the connect to the
+/// server. Only actors connecting to a server of their own, see
+/// `tcp_connect_addr`, get one.
+pub struct WarmPool {
+    backups: usize,
+    dns_client: Arc<DnsClient>,
+    // The connection of each actor, with the time it was made.
+    conns: Mutex<HashMap<usize, (Box<dyn ProxyStream>, Instant)>>,
+    // Actors which failed their last health check.
+    unhealthy: Mutex<HashSet<usize>>,
+    refill: Notify,
+}
+
+impl WarmPool {
+    /// Keeps connections to at most `backups` actors.
+    pub fn new(backups: usize, dns_client: Arc<DnsClient>) -> Self {
+        WarmPool {
+            backups,
+            dns_client,
+            conns: Mutex::new(HashMap::new()),
+            unhealthy: Mutex::new(HashSet::new()),
+            refill: Notify::new(),
+        }
+    }
+
+    /// Takes the connection to the server of actor `i`, if any, another one
+    /// is made in its place.
+    pub fn take(&self, i: usize) -> Option<Box<dyn ProxyStream>> {
+        let (conn, made) = self.conns.lock().unwrap().remove(&i)?;
+        self.refill.notify();
+        if made.elapsed() >= WARM_MAX_IDLE {
+            return None;
+        }
+        Some(conn)
+    }
+
+    /// Records the outcome of the health check of actor `i`, the connection
+    /// to an unhealthy actor is closed.
+    pub fn set_healthy(&self, i: usize, healthy: bool) {
+        if healthy {
+            self.unhealthy.lock().unwrap().remove(&i);
+        } else {
+            self.unhealthy.lock().unwrap().insert(i);
+            if self.conns.lock().unwrap().remove(&i).is_some() {
+                debug!("closed warm connection to unhealthy actor {}", i);
+            }
+        }
+    }
+
+    /// Returns the actors holding a connection.
+    pub fn warm(&self) -> Vec<usize> {
+        let mut warm: Vec<usize> = self.conns.lock().unwrap().keys().cloned().collect();
+        warm.sort_unstable();
+        warm
+    }
+
+    // Connects to the backups after the first actor of `schedule` which
+    // have no fresh connection, closes the connections of the others.
+    async fn fill(&self, actors: &[Arc<dyn ProxyHandler>], schedule: &[usize], enabled: &Enabled) {
+        let backups: Vec<usize> = {
+            let unhealthy = self.unhealthy.lock().unwrap();
+            schedule
+                .iter()
+                .skip(1)
+                .cloned()
+                .filter(|i| {
+                    enabled.get(*i)
+                        && !unhealthy.contains(i)
+                        && actors
+                            .get(*i)
+                            .map_or(false, |a| a.tcp_connect_addr().is_some())
+                })
+                .take(self.backups)
+                .collect()
+        };
+        self.conns
+            .lock()
+            .unwrap()
+            .retain(|i, (_, made)| backups.contains(i) && made.elapsed() < WARM_MAX_IDLE);
+        for i in backups {
+            if self.conns.lock().unwrap().contains_key(&i) {
+                continue;
+            }
+            let a = &actors[i];
+            let (address, port, bind_addr) = match a.tcp_connect_addr() {
+                Some(addr) => addr,
+                None => continue,
+            };
+            let dial = ProxyTcpHandler::dial_tcp_stream(
+                &**a,
+                self.dns_client.clone(),
+                &bind_addr,
+                &address,
+                &port,
+            );
+            match timeout(WARM_CONNECT_TIMEOUT, dial).await {
+                Ok(Ok(conn)) => {
+                    trace!("warm connection to [{}] made", a.tag());
+                    self.conns.lock().unwrap().insert(i, (conn, Instant::now()));
+                }
+                Ok(Err(e)) => debug!("warm connection to [{}] failed: {}", a.tag(), e),
+                Err(_) => debug!("warm connection to [{}] timed out", a.tag()),
+            }
+        }
+    }
+
+    /// Keeps the connections to the backups of `schedule` ready, refreshed
+    /// as they age and made anew once taken.
+    pub async fn run(
+        self: Arc<Self>,
+        actors: Vec<Arc<dyn ProxyHandler>>,
+        schedule: Arc<TokioMutex<Vec<usize>>>,
+        enabled: Enabled,
+    ) {
+        loop {
+            let current = schedule.lock().await.clone();
+            self.fill(&actors, &current, &enabled).await;
+            tokio::select! {
+                _ = tokio::time::delay_for(WARM_MAX_IDLE / 2) => (),
+                _ = self.refill.notified() => (),
+            }
+        }
+    }
+}
@@ -4,12 +4,21 @@ use std::{io, sync::Arc, time};
 use async_trait::async_trait;
 use futures::future::BoxFuture;
 use log::*;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::timeout;
 
+use super::{
+    initial_schedule, mbps, place_unprobed, prewarm::WarmPool, ActorLatencies, Control,
+    HandlerBuilder, Measure, Sticky, ThroughputProbe, HANDSHAKE_FAILED, READ_FAILED, TIMED_OUT,
+    WRITE_FAILED, WRITE_PARTIAL, WRITE_RESET,
+};
 use crate::{
-    proxy::{ProxyHandler, ProxyStream, ProxyTcpHandler},
+    proxy::{
+        deadline,
+        self_test::{self, http_request, is_reachable, read_status_line, TestResult},
+        ProxyError, ProxyHandler, ProxyStream, ProxyTcpHandler,
+    },
     session::{Session, SocksAddr},
 };
 
@@ -18,11 +27,11 @@ pub struct Handler {
     pub fail_timeout: u32,
     pub schedule: Arc<TokioMutex<Vec<usize>>>,
     pub health_check_task: TokioMutex<Option<BoxFuture<'static, ()>>>,
+    pub prewarm_task: TokioMutex<Option<BoxFuture<'static, ()>>>,
+    control: Control,
+    warm: Option<Arc<WarmPool>>,
 }
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct Measure(usize, u128); // (index, duration in millis)
-
 impl Handler {
     pub fn new(
         actors: Vec<Arc<dyn ProxyHandler>>,
@@ -31,58 +40,142 @@ impl Handler {
         check_interval: u32,
         failover: bool,
     ) -> Self {
-        let mut schedule = Vec::new();
-        for i in 0..actors.len() {
-            schedule.push(i);
-        }
+        HandlerBuilder::default()
+            .actors(actors)
+            .fail_timeout(fail_timeout)
+            .health_check(health_check)
+            .check_interval(check_interval)
+            .failover(failover)
+            .build()
+    }
+
+    /// Returns the runtime controls of the handler, see `Control`.
+    pub fn control(&self) -> Control {
+        self.control.clone()
+    }
+
+    /// See `Control::latencies`.
+    pub fn latencies(&self) -> ActorLatencies {
+        self.control.latencies()
+    }
+
+    /// See `Control::trigger_recheck`.
+    pub fn trigger_recheck(&self) {
+        self.control.trigger_recheck();
+    }
+
+    /// See `Control::set_actor_enabled`.
+    pub fn set_actor_enabled(&self, tag: &str, enabled: bool) -> bool {
+        self.control.set_actor_enabled(tag, enabled)
+    }
+}
+
+impl From<HandlerBuilder> for Handler {
+    fn from(b: HandlerBuilder) -> Self {
+        let HandlerBuilder {
+            actors,
+            fail_timeout,
+            health_check,
+            check_interval,
+            failover,
+            switch_margin,
+            throughput_probe,
+            url_test,
+            check_targets,
+            quorum,
+            user_agent,
+            health,
+            prewarm,
+            unprobed,
+            shutdown,
+            ..
+        } = b;
+        let failover = failover && url_test.is_none();
+        let schedule = initial_schedule(actors.len(), &unprobed);
         let schedule = Arc::new(TokioMutex::new(schedule));
 
+        let control = Control::new(actors.clone());
+
         let schedule2 = schedule.clone();
         let actors2 = actors.clone();
+        let latencies2 = control.latencies.clone();
+        let recheck2 = control.recheck.clone();
+        let enabled = control.enabled.clone();
+        let enabled2 = enabled.clone();
+        let warm =
+            prewarm.map(|(backups, dns_client)| Arc::new(WarmPool::new(backups, dns_client)));
+        let prewarm_task = warm.as_ref().map(|warm| {
+            let run = warm
+                .clone()
+                .run(actors.clone(), schedule.clone(), enabled.clone());
+            let shutdown = shutdown.clone();
+            let task: BoxFuture<'static, ()> = Box::pin(async move {
+                shutdown.run_until(run).await;
+            });
+            task
+        });
+        let warm2 = warm.clone();
         let task = if health_check {
-            let health_check_task: BoxFuture<'static, ()> = Box::pin(async move {
+            let health_check_task = async move {
+                let user_agent = user_agent.as_str();
+                let targets: Vec<(SocksAddr, String)> = if !check_targets.is_empty() {
+                    check_targets
+                        .iter()
+                        .map(|t| {
+                            let request = http_request("GET", &t.destination, &t.path, user_agent);
+                            (t.destination.clone(), request)
+                        })
+                        .collect()
+                } else if let Some(t) = url_test.as_ref() {
+                    let request = http_request("GET", &t.destination, &t.path, user_agent);
+                    vec![(t.destination.clone(), request)]
+                } else {
+                    let destination = self_test::tcp_target();
+                    let request = http_request("HEAD", &destination, "/", user_agent);
+                    vec![(destination, request)]
+                };
+                let quorum = match quorum {
+                    0 => targets.len() / 2 + 1,
+                    n => n.min(targets.len()),
+                };
+                let mut sticky = Sticky::new(switch_margin);
                 loop {
                     let mut measures: Vec<Measure> = Vec::new();
                     for (i, a) in (&actors2).iter().enumerate() {
+                        if !enabled2.get(i) || unprobed.iter().any(|(j, _)| *j == i) {
+                            continue;
+                        }
                         debug!("health checking tcp for [{}] index [{}]", a.tag(), i);
-                        let single_measure = async move {
-                            let sess = Session {
-                                source: "0.0.0.0:0".parse().unwrap(),
-                                destination: SocksAddr::Domain("www.google.com".to_string(), 80),
-                            };
-                            let start = tokio::time::Instant::now();
-                            match a.handle(&sess, None).await {
-                                Ok(mut stream) => {
-                                    if stream.write_all(b"HEAD / HTTP/1.1\r\n\r\n").await.is_err() {
-                                        return Measure(i, u128::MAX - 2); // handshake is ok
-                                    }
-                                    let mut buf = vec![0u8; 1];
-                                    match stream.read_exact(&mut buf).await {
-                                        // handshake, write and read are ok
-                                        Ok(_) => {
-                                            let elapsed =
-                                                tokio::time::Instant::now().duration_since(start);
-                                            Measure(i, elapsed.as_millis())
-                                        }
-                                        // handshake and write are ok
-                                        Err(_) => Measure(i, u128::MAX - 3),
-                                    }
-                                }
-                                // handshake not ok
-                                Err(_) => Measure(i, u128::MAX),
+                        let mut scores = Vec::with_capacity(targets.len());
+                        for (destination, request) in targets.iter() {
+                            scores.push(check_target(a, destination, request).await);
+                        }
+                        let m = Measure::quorum(i, &scores, quorum);
+                        if m.is_ok() {
+                            latencies2.record(m.0, m.1 as u64);
+                        }
+                        let m = match &throughput_probe {
+                            Some(probe) if m.is_ok() => {
+                                measure_throughput(a, probe, user_agent, m).await
                             }
+                            _ => m,
                         };
-                        match timeout(time::Duration::from_secs(10), single_measure).await {
-                            Ok(m) => {
-                                measures.push(m);
-                            }
-                            Err(_) => {
-                                measures.push(Measure(i, u128::MAX - 1)); // timeout, better than handshake error
-                            }
+                        measures.push(m);
+                    }
+
+                    if let Some(health) = &health {
+                        for m in measures.iter() {
+                            health.set_healthy(actors2[m.0].tag(), m.is_ok());
+                        }
+                    }
+                    if let Some(warm) = &warm2 {
+                        for m in measures.iter() {
+                            warm.set_healthy(m.0, m.is_ok());
                         }
                     }
 
                     measures.sort_by(|a, b| a.1.cmp(&b.1));
+                    sticky.rank(&mut measures);
                     trace!("sorted tcp health check results:\n{:#?}", measures);
 
                     let priorities: Vec<String> = measures
@@ -102,23 +195,34 @@ impl Handler {
                         priorities.join(" > ")
                     );
 
+                    let order = place_unprobed(measures.iter().map(|m| m.0).collect(), &unprobed);
                     let mut schedule = schedule2.lock().await;
                     schedule.clear();
                     if !failover {
                         // if failover is disabled, put only 1 actor in schedule
-                        schedule.push(measures[0].0);
-                        trace!("put {} in schedule", measures[0].0);
+                        if let Some(i) = order.first() {
+                            schedule.push(*i);
+                            trace!("put {} in schedule", i);
+                        }
                     } else {
-                        for m in measures {
-                            schedule.push(m.0);
-                            trace!("put {} in schedule", m.0);
+                        for i in order {
+                            schedule.push(i);
+                            trace!("put {} in schedule", i);
                         }
                     }
 
                     drop(schedule); // drop the guard, to release the lock
 
-                    tokio::time::delay_for(time::Duration::from_secs(check_interval as u64)).await;
+                    let interval = time::Duration::from_secs(check_interval as u64);
+                    tokio::select! {
+                        _ = tokio::time::delay_for(interval) => (),
+                        _ = recheck2.notified() => debug!("tcp health check triggered"),
+                    }
                 }
+            };
+            let health_check_task: BoxFuture<'static, ()> = Box::pin(async move {
+                shutdown.run_until(health_check_task).await;
+                debug!("health check stopped");
             });
             Some(health_check_task)
         } else {
@@ -130,7 +234,136 @@ impl Handler {
             fail_timeout,
             schedule,
             health_check_task: TokioMutex::new(task),
+            prewarm_task: TokioMutex::new(prewarm_task),
+            control,
+            warm,
+        }
+    }
+}
+
+// Sends `request` to `destination` through `actor`, returns the response
+// time in milliseconds or the score of the failed check.
+async fn check_target(
+    actor: &Arc<dyn ProxyHandler>,
+    destination: &SocksAddr,
+    request: &str,
+) -> u128 {
+    let sess = Session {
+        source: "0.0.0.0:0".parse().unwrap(),
+        destination: destination.clone(),
+    };
+    let single_measure = async {
+        let start = tokio::time::Instant::now();
+        match actor.handle(&sess, None).await {
+            Ok(mut stream) => {
+                if let Err(score) = write_request(&mut stream, request.as_bytes()).await {
+                    return score; // handshake is ok
+                }
+                match read_status_line(&mut stream).await {
+                    // handshake, write and read are ok
+                    Ok(code) if is_reachable(code) => tokio::time::Instant::now()
+                        .duration_since(start)
+                        .as_millis(),
+                    // handshake and write are ok
+                    _ => READ_FAILED,
+                }
+            }
+            // handshake not ok
+            Err(_) => HANDSHAKE_FAILED,
         }
+    };
+    match timeout(time::Duration::from_secs(10), single_measure).await {
+        Ok(score) => score,
+        Err(_) => TIMED_OUT, // timeout, better than handshake error
+    }
+}
+
+// Writes the whole request, a short write is followed by another one. On
+// failure returns the score of the check: a reset connection, a request cut
+// short, or nothing written at all.
+async fn write_request<S: AsyncWrite + Unpin>(stream: &mut S, buf: &[u8]) -> Result<(), u128> {
+    let mut written = 0;
+    while written < buf.len() {
+        let e = match stream.write(&buf[written..]).await {
+            Ok(0) => io::Error::from(io::ErrorKind::WriteZero),
+            Ok(n) => {
+                written += n;
+                continue;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => e,
+        };
+        return Err(match e.kind() {
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => WRITE_RESET,
+            _ if written > 0 => WRITE_PARTIAL,
+            _ => WRITE_FAILED,
+        });
+    }
+    Ok(())
+}
+
+// Downloads the probe object through `actor`, returns the bytes read and
+// the time it took, or the score of the failed download. The download
+// stops at the probe size, on EOF or once `limit` is up.
+async fn download(
+    actor: &Arc<dyn ProxyHandler>,
+    probe: &ThroughputProbe,
+    user_agent: &str,
+    limit: time::Duration,
+) -> Result<(u64, time::Duration), u128> {
+    let sess = Session {
+        source: "0.0.0.0:0".parse().unwrap(),
+        destination: probe.destination.clone(),
+    };
+    let mut stream = actor
+        .handle(&sess, None)
+        .await
+        .map_err(|_| HANDSHAKE_FAILED)?;
+    let request = http_request("GET", &probe.destination, &probe.path, user_agent);
+    let start = tokio::time::Instant::now();
+    write_request(&mut stream, request.as_bytes()).await?;
+    let mut received = 0u64;
+    let mut buf = vec![0u8; 16 * 1024];
+    let read = async {
+        while received < probe.size {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            received += n as u64;
+        }
+        Ok::<_, io::Error>(())
+    };
+    // A slow download still counts with the bytes received so far.
+    if let Ok(res) = timeout(limit, read).await {
+        res.map_err(|_| READ_FAILED)?;
+    }
+    Ok((received, start.elapsed()))
+}
+
+// Turns the response time of an actor into its score with the probe.
+async fn measure_throughput(
+    actor: &Arc<dyn ProxyHandler>,
+    probe: &ThroughputProbe,
+    user_agent: &str,
+    latency: Measure,
+) -> Measure {
+    let Measure(i, latency) = latency;
+    match download(actor, probe, user_agent, time::Duration::from_secs(30)).await {
+        Ok((received, elapsed)) => match probe.score(latency, received, elapsed) {
+            Some(score) => {
+                debug!(
+                    "throughput of [{}] {:.2} Mbps",
+                    actor.tag(),
+                    mbps(received, elapsed)
+                );
+                Measure(i, score)
+            }
+            None => Measure(i, READ_FAILED),
+        },
+        Err(score) => Measure(i, score),
     }
 }
 
@@ -144,6 +377,18 @@ impl ProxyTcpHandler for Handler {
         None
     }
 
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.actors.iter().any(|a| a.is_warm(sess))
+    }
+
+    async fn self_test(&self) -> TestResult {
+        let tests = self
+            .actors
+            .iter()
+            .map(|a| async move { (a.tag().to_owned(), ProxyTcpHandler::self_test(&**a).await) });
+        TestResult::aggregate(futures::future::join_all(tests).await)
+    }
+
     async fn handle<'a>(
         &'a self,
         sess: &'a Session,
@@ -154,34 +399,825 @@ impl ProxyTcpHandler for Handler {
                 tokio::spawn(task);
             }
         }
+        if self.prewarm_task.lock().await.is_some() {
+            if let Some(task) = self.prewarm_task.lock().await.take() {
+                tokio::spawn(task);
+            }
+        }
 
-        let schedule = self.schedule.lock().await.clone();
+        let mut schedule = self.schedule.lock().await.clone();
+        // Warm actors go first, the order among them is kept.
+        schedule.sort_by_key(|i| !self.actors.get(*i).map_or(false, |a| a.is_warm(sess)));
+        let fail_timeout = time::Duration::from_secs(self.fail_timeout as u64);
+        let mut last_err = None;
 
         for i in schedule {
             if i >= self.actors.len() {
                 return Err(io::Error::new(io::ErrorKind::Other, "invalid actor index"));
             }
+            if !self.control.enabled.get(i) {
+                continue;
+            }
 
-            match timeout(
-                time::Duration::from_secs(self.fail_timeout as u64),
-                (&self.actors[i]).handle(sess, None),
-            )
-            .await
-            {
+            // No time left for another actor.
+            deadline::check()?;
+            let fail_timeout = deadline::cap(fail_timeout);
+            let a = &self.actors[i];
+            let warm = self.warm.as_ref().and_then(|warm| warm.take(i));
+            let task = async move {
+                if let Some(stream) = warm {
+                    match a.handle(sess, Some(stream)).await {
+                        Ok(v) => return Ok(v),
+                        // The server may have dropped it meanwhile.
+                        Err(e) => debug!("warm connection to [{}] failed: {}", a.tag(), e),
+                    }
+                }
+                a.handle(sess, None).await
+            };
+            match timeout(fail_timeout, task).await {
                 // return before timeout
                 Ok(t) => match t {
                     // return ok
                     Ok(v) => return Ok(v),
                     // return err
-                    Err(_) => continue,
+                    Err(e) => last_err = Some(e),
                 },
                 // after timeout
-                Err(_) => continue,
+                Err(_) => last_err = Some(ProxyError::Timeout(fail_timeout).into()),
             }
         }
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "all outbound attempts failed",
-        ))
+        Err(match last_err {
+            Some(e) => ProxyError::AllFailed(e),
+            None => ProxyError::NoOutbound,
+        }
+        .into())
+    }
+}
+
+#[cfg(all(test, feature = "outbound-redirect"))]
+mod tests {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::pin::Pin;
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    };
+
+    use futures::task::{Context, Poll};
+    use tokio::io::AsyncRead;
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+    use crate::proxy::failover::{FixedRank, SwitchMargin, UrlTest, DEFAULT_USER_AGENT};
+    use crate::proxy::{handler, redirect, ProxyHandlerType};
+
+    #[derive(Default)]
+    struct SpanData {
+        parent: Option<u64>,
+        fields: HashMap<String, String>,
+    }
+
+    impl Visit for SpanData {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.fields
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    // Records all spans along with their fields and parents.
+    #[derive(Clone, Default)]
+    struct Collector {
+        next_id: Arc<AtomicU64>,
+        spans: Arc<Mutex<Vec<SpanData>>>,
+        stack: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+            let parent = if let Some(parent) = attrs.parent() {
+                Some(parent.into_u64())
+            } else if attrs.is_contextual() {
+                self.stack.lock().unwrap().last().cloned()
+            } else {
+                None
+            };
+            let mut data = SpanData {
+                parent,
+                ..Default::default()
+            };
+            attrs.record(&mut data);
+            self.spans.lock().unwrap().push(data);
+            span::Id::from_u64(id)
+        }
+
+        fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut spans[id.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, id: &span::Id) {
+            self.stack.lock().unwrap().push(id.into_u64());
+        }
+
+        fn exit(&self, _: &span::Id) {
+            self.stack.lock().unwrap().pop();
+        }
+    }
+
+    fn redirect_actor(tag: &str, port: u16) -> Arc<dyn ProxyHandler> {
+        handler::Handler::new(
+            tag.to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(redirect::TcpHandler {
+                address: "127.0.0.1".to_string(),
+                port,
+                proxy_protocol: None,
+            }),
+            Box::new(redirect::UdpHandler {
+                address: "127.0.0.1".to_string(),
+                port,
+                bind_addr: "0.0.0.0:0".parse().unwrap(),
+                bind_interface: None,
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_failover_spans() {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let _ = listener.accept().await;
+        });
+        // A port nobody listens on.
+        let closed_port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let failover = Handler::new(
+            vec![redirect_actor("a", closed_port), redirect_actor("b", port)],
+            4,
+            false,
+            300,
+            true,
+        );
+        let failover = handler::Handler::new(
+            "failover".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Ensemble,
+            Box::new(failover),
+            Box::new(redirect::UdpHandler {
+                address: "127.0.0.1".to_string(),
+                port,
+                bind_addr: "0.0.0.0:0".parse().unwrap(),
+                bind_interface: None,
+            }),
+        );
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+
+        let collector = Collector::default();
+        let guard = tracing::subscriber::set_default(collector.clone());
+        assert!(failover.handle(&sess, None).await.is_ok());
+        drop(guard);
+
+        let spans = collector.spans.lock().unwrap();
+        let tags: Vec<&str> = spans.iter().map(|s| s.fields["tag"].as_str()).collect();
+        assert_eq!(tags, vec!["failover", "a", "b"]);
+        for span in spans.iter() {
+            assert_eq!(span.fields["destination"], "example.com:443");
+        }
+        assert_eq!(spans[0].fields["outcome"], "ok");
+        assert_ne!(spans[1].fields["outcome"], "ok");
+        assert_eq!(spans[2].fields["outcome"], "ok");
+        // The actors are followed from the failover span.
+        assert_eq!(spans[0].parent, None);
+        assert_eq!(spans[1].parent, Some(1));
+        assert_eq!(spans[2].parent, Some(1));
+    }
+
+    // Answers every connection with a status line and `chunks` chunks of
+    // 4KB, pausing between them.
+    async fn server(chunks: usize, pause: time::Duration) -> u16 {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf).await;
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\n\r\n").await.is_err() {
+                        return;
+                    }
+                    for _ in 0..chunks {
+                        if stream.write_all(&[0u8; 4096]).await.is_err() {
+                            return;
+                        }
+                        tokio::time::delay_for(pause).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_throughput_ranking() {
+        // Both respond right away, one of them trickles the rest.
+        let slow = server(8, time::Duration::from_millis(50)).await;
+        let fast = server(8, time::Duration::from_millis(0)).await;
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![
+                redirect_actor("slow", slow),
+                redirect_actor("fast", fast),
+            ])
+            .throughput_probe(ThroughputProbe::from_url(
+                "http://example.com/32k",
+                32 * 1024,
+            ))
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // Starts the health check.
+        assert!(failover.handle(&sess, None).await.is_ok());
+        for _ in 0..100 {
+            if failover.schedule.lock().await[0] == 1 {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(*failover.schedule.lock().await, vec![1, 0]);
+    }
+
+    // Answers every connection with `response` after `delay`, keeps the
+    // requests received.
+    async fn http_server(
+        response: &'static [u8],
+        delay: time::Duration,
+    ) -> (u16, Arc<Mutex<Vec<String>>>) {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let requests2 = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let requests = requests2.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    requests
+                        .lock()
+                        .unwrap()
+                        .push(String::from_utf8_lossy(&buf[..n]).to_string());
+                    tokio::time::delay_for(delay).await;
+                    let _ = stream.write_all(response).await;
+                });
+            }
+        });
+        (port, requests)
+    }
+
+    #[tokio::test]
+    async fn test_url_test() {
+        let no_content = b"HTTP/1.1 204 No Content\r\n\r\n";
+        let (broken, _) =
+            http_server(b"SSH-2.0-OpenSSH_8.4\r\n", time::Duration::from_millis(0)).await;
+        let (slow, _) = http_server(no_content, time::Duration::from_millis(300)).await;
+        let (fast, requests) = http_server(no_content, time::Duration::from_millis(0)).await;
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![
+                redirect_actor("broken", broken),
+                redirect_actor("slow", slow),
+                redirect_actor("fast", fast),
+            ])
+            .url_test(UrlTest::from_url("http://example.com/generate_204"))
+            .switch_margin(Some(SwitchMargin::Millis(50)))
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // Starts the health check.
+        assert!(failover.handle(&sess, None).await.is_ok());
+        for _ in 0..100 {
+            if failover.schedule.lock().await.len() == 1 {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        }
+        // The fastest actor alone, a response without a status line doesn't
+        // count.
+        assert_eq!(*failover.schedule.lock().await, vec![2]);
+        assert_eq!(
+            requests.lock().unwrap()[0],
+            format!(
+                "GET /generate_204 HTTP/1.1\r\nHost: example.com\r\nUser-Agent: {}\r\nConnection: close\r\n\r\n",
+                DEFAULT_USER_AGENT
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unprobed_actor_keeps_rank() {
+        let no_content = b"HTTP/1.1 204 No Content\r\n\r\n";
+        let (slow, _) = http_server(no_content, time::Duration::from_millis(300)).await;
+        let (fallback, probes) = http_server(no_content, time::Duration::from_millis(0)).await;
+        let (fast, _) = http_server(no_content, time::Duration::from_millis(0)).await;
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![
+                redirect_actor("slow", slow),
+                redirect_actor("fallback", fallback),
+                redirect_actor("fast", fast),
+            ])
+            .unprobed(1, FixedRank::Last)
+            .build();
+        assert_eq!(*failover.schedule.lock().await, vec![0, 2, 1]);
+
+        let task = failover.health_check_task.lock().await.take().unwrap();
+        tokio::spawn(task);
+        for _ in 0..100 {
+            if failover.schedule.lock().await[0] == 2 {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        }
+        // The checked actors swapped, the fallback stays last though it's as
+        // fast as the fastest, and it was never probed.
+        assert_eq!(*failover.schedule.lock().await, vec![2, 0, 1]);
+        assert!(probes.lock().unwrap().is_empty());
+        assert!(failover.latencies()[1].1.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_probe_request() {
+        let (bad_gateway, _) = http_server(
+            b"HTTP/1.1 502 Bad Gateway\r\n\r\n",
+            time::Duration::from_millis(0),
+        )
+        .await;
+        let (forbidden, requests) = http_server(
+            b"HTTP/1.1 403 Forbidden\r\n\r\n",
+            time::Duration::from_millis(0),
+        )
+        .await;
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![
+                redirect_actor("bad-gateway", bad_gateway),
+                redirect_actor("forbidden", forbidden),
+            ])
+            .user_agent("probe/1.0".to_string())
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // Starts the health check.
+        assert!(failover.handle(&sess, None).await.is_ok());
+        for _ in 0..100 {
+            if failover.schedule.lock().await[0] == 1 {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        }
+        // The destination was reached through the forbidden one only.
+        assert_eq!(*failover.schedule.lock().await, vec![1, 0]);
+        assert_eq!(
+            requests.lock().unwrap()[0],
+            "HEAD / HTTP/1.1\r\nHost: www.google.com\r\nUser-Agent: probe/1.0\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_trigger_recheck() {
+        let (port, requests) =
+            http_server(b"HTTP/1.1 200 OK\r\n\r\n", time::Duration::from_millis(0)).await;
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![redirect_actor("ok", port)])
+            .check_interval(300)
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        let checks = || {
+            requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|r| r.starts_with("HEAD "))
+                .count()
+        };
+        let wait_for_checks = |n: usize| async move {
+            for _ in 0..100 {
+                if checks() >= n {
+                    break;
+                }
+                tokio::time::delay_for(time::Duration::from_millis(20)).await;
+            }
+        };
+        // Starts the health check.
+        assert!(failover.handle(&sess, None).await.is_ok());
+        wait_for_checks(1).await;
+        assert_eq!(checks(), 1);
+
+        failover.trigger_recheck();
+        wait_for_checks(2).await;
+        assert_eq!(checks(), 2);
+        // Back to the interval.
+        tokio::time::delay_for(time::Duration::from_millis(200)).await;
+        assert_eq!(checks(), 2);
+    }
+
+    // Takes up to 4 bytes a write, fails writes with `kind` once it's taken
+    // `accept` bytes. Reads hit EOF.
+    struct Flaky {
+        accept: usize,
+        kind: io::ErrorKind,
+    }
+
+    impl AsyncRead for Flaky {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Ok(0))
+        }
+    }
+
+    impl AsyncWrite for Flaky {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.accept == 0 {
+                return Poll::Ready(Err(self.kind.into()));
+            }
+            let n = self.accept.min(buf.len()).min(4);
+            self.accept -= n;
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl ProxyStream for Flaky {}
+
+    async fn write(accept: usize, kind: io::ErrorKind) -> Result<(), u128> {
+        let mut stream = Flaky { accept, kind };
+        write_request(&mut stream, b"HEAD / HTTP/1.1\r\n\r\n").await
+    }
+
+    #[tokio::test]
+    async fn test_write_request() {
+        assert_eq!(write(64, io::ErrorKind::Other).await, Ok(()));
+        assert_eq!(write(0, io::ErrorKind::Other).await, Err(WRITE_FAILED));
+        assert_eq!(write(6, io::ErrorKind::Other).await, Err(WRITE_PARTIAL));
+        assert_eq!(
+            write(6, io::ErrorKind::ConnectionReset).await,
+            Err(WRITE_RESET)
+        );
+        assert_eq!(write(0, io::ErrorKind::BrokenPipe).await, Err(WRITE_RESET));
+    }
+
+    // Returns `Flaky` streams.
+    struct FlakyActor {
+        accept: usize,
+        kind: io::ErrorKind,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for FlakyActor {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            Ok(Box::new(Flaky {
+                accept: self.accept,
+                kind: self.kind,
+            }))
+        }
+    }
+
+    fn flaky_actor(tag: &str, accept: usize, kind: io::ErrorKind) -> Arc<dyn ProxyHandler> {
+        handler::Handler::new(
+            tag.to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(FlakyActor { accept, kind }),
+            Box::new(redirect::UdpHandler {
+                address: "127.0.0.1".to_string(),
+                port: 53,
+                bind_addr: "0.0.0.0:0".parse().unwrap(),
+                bind_interface: None,
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reset_during_write() {
+        let (port, _) =
+            http_server(b"HTTP/1.1 200 OK\r\n\r\n", time::Duration::from_millis(0)).await;
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![
+                flaky_actor("reset", 6, io::ErrorKind::ConnectionReset),
+                // Takes the whole request, then closes without a response.
+                flaky_actor("mute", 1024, io::ErrorKind::Other),
+                redirect_actor("ok", port),
+            ])
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // Starts the health check.
+        assert!(failover.handle(&sess, None).await.is_ok());
+        for _ in 0..100 {
+            if failover.schedule.lock().await[0] == 2 {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        }
+        // A failed read got further than a reset write.
+        assert_eq!(*failover.schedule.lock().await, vec![2, 1, 0]);
+        let latencies = failover.latencies();
+        assert!(latencies[0].1.is_none());
+        assert!(latencies[1].1.is_none());
+        assert!(latencies[2].1.is_some());
+    }
+
+    // Takes a while to fail.
+    struct Stall(time::Duration);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Stall {
+        fn name(&self) -> &str {
+            "stall"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            tokio::time::delay_for(self.0).await;
+            Err(io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_deadline() {
+        let actors = (0..10)
+            .map(|i| {
+                handler::Handler::new(
+                    format!("stall{}", i),
+                    colored::Color::White,
+                    ProxyHandlerType::Endpoint,
+                    Box::new(Stall(time::Duration::from_millis(200))),
+                    Box::new(redirect::UdpHandler {
+                        address: "127.0.0.1".to_string(),
+                        port: 53,
+                        bind_addr: "0.0.0.0:0".parse().unwrap(),
+                        bind_interface: None,
+                    }),
+                ) as Arc<dyn ProxyHandler>
+            })
+            .collect();
+        let failover: Handler = HandlerBuilder::default()
+            .actors(actors)
+            .health_check(false)
+            .build();
+        let failover = handler::Handler::with_options(
+            "failover".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Ensemble,
+            Box::new(failover),
+            Box::new(redirect::UdpHandler {
+                address: "127.0.0.1".to_string(),
+                port: 53,
+                bind_addr: "0.0.0.0:0".parse().unwrap(),
+                bind_interface: None,
+            }),
+            handler::Options {
+                connect_deadline: Some(time::Duration::from_millis(500)),
+                ..Default::default()
+            },
+        );
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+
+        // Trying all the actors would take 2s.
+        let start = tokio::time::Instant::now();
+        let err = failover.handle(&sess, None).await.err().unwrap();
+        assert!(start.elapsed() < time::Duration::from_millis(1000));
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Timeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_quorum() {
+        // Slow on /slow, closes the connection on /down.
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    if request.starts_with("GET /down ") {
+                        return;
+                    }
+                    if request.starts_with("GET /slow ") {
+                        tokio::time::delay_for(time::Duration::from_millis(200)).await;
+                    }
+                    let _ = stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await;
+                });
+            }
+        });
+        let (steady, _) = http_server(
+            b"HTTP/1.1 204 No Content\r\n\r\n",
+            time::Duration::from_millis(300),
+        )
+        .await;
+        let targets = vec![
+            "http://a.example.com/fast",
+            "http://b.example.com/slow",
+            "http://c.example.com/down",
+        ];
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![
+                redirect_actor("steady", steady),
+                redirect_actor("quorum", port),
+            ])
+            .check_targets(
+                targets
+                    .into_iter()
+                    .map(|url| UrlTest::from_url(url).unwrap())
+                    .collect(),
+            )
+            .quorum(2)
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // Starts the health check.
+        assert!(failover.handle(&sess, None).await.is_ok());
+        for _ in 0..100 {
+            if failover.schedule.lock().await[0] == 1 {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(50)).await;
+        }
+        // Two of the three targets reached, ranked by their average.
+        assert_eq!(*failover.schedule.lock().await, vec![1, 0]);
+        let summary = failover.latencies()[1].1.clone().unwrap();
+        assert!(summary.p50 >= 100 && summary.p50 < 200, "{:?}", summary);
+    }
+
+    // Connects to a server at `port`, counting the connects made anew.
+    struct Backup {
+        port: u16,
+        dials: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for Backup {
+        fn name(&self) -> &str {
+            "backup"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            Some((
+                "127.0.0.1".to_string(),
+                self.port,
+                "0.0.0.0:0".parse().unwrap(),
+            ))
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            if let Some(stream) = stream {
+                return Ok(stream);
+            }
+            self.dials.fetch_add(1, Ordering::SeqCst);
+            let stream = tokio::net::TcpStream::connect(("127.0.0.1", self.port)).await?;
+            Ok(Box::new(crate::proxy::stream::SimpleStream(stream)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_hands_over_warm_connection() {
+        // Echoes on every connection.
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut r, mut w) = conn.split();
+                    let _ = tokio::io::copy(&mut r, &mut w).await;
+                });
+            }
+        });
+        let closed_port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let dials = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let backup = handler::Handler::new(
+            "backup".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Backup {
+                port,
+                dials: dials.clone(),
+            }),
+            Box::new(redirect::UdpHandler {
+                address: "127.0.0.1".to_string(),
+                port,
+                bind_addr: "0.0.0.0:0".parse().unwrap(),
+                bind_interface: None,
+            }),
+        );
+        let failover: Handler = HandlerBuilder::default()
+            .actors(vec![redirect_actor("primary", closed_port), backup])
+            .health_check(false)
+            .prewarm(1, Arc::new(crate::common::dns_client::DnsClient::default()))
+            .build();
+        tokio::spawn(failover.prewarm_task.lock().await.take().unwrap());
+        let warm = failover.warm.clone().unwrap();
+        for _ in 0..100 {
+            if warm.warm() == vec![1] {
+                break;
+            }
+            tokio::time::delay_for(time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(warm.warm(), vec![1]);
+
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+        // The primary refuses, the backup serves on its warm connection.
+        let mut stream = failover.handle(&sess, None).await.unwrap();
+        assert_eq!(dials.load(Ordering::SeqCst), 0);
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
     }
 }
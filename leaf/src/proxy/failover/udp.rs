@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::time;
 use std::{
     io,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, SocketAddr},
 };
 
 use async_trait::async_trait;
@@ -13,12 +13,21 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use tokio::sync::Mutex as TokioMutex;
 use tokio::time::timeout;
 use trust_dns_proto::{
-    op::{header::MessageType, op_code::OpCode, query::Query, Message},
-    rr::{record_type::RecordType, Name},
+    op::{
+        header::MessageType, op_code::OpCode, query::Query, response_code::ResponseCode, Message,
+    },
+    rr::{record_data::RData, record_type::RecordType, Name},
 };
 
+use super::{
+    initial_schedule, place_unprobed, ActorLatencies, Control, DnsProbe, HandlerBuilder, Measure,
+    Sticky, HANDSHAKE_FAILED, READ_FAILED, TIMED_OUT, WRITE_FAILED,
+};
 use crate::{
-    proxy::{ProxyDatagram, ProxyHandler, ProxyStream, ProxyUdpHandler, UdpTransportType},
+    proxy::{
+        deadline, ProxyDatagram, ProxyError, ProxyHandler, ProxyStream, ProxyUdpHandler,
+        TestResult, UdpTransportType,
+    },
     session::{Session, SocksAddr},
 };
 
@@ -27,11 +36,9 @@ pub struct Handler {
     pub fail_timeout: u32,
     pub schedule: Arc<TokioMutex<Vec<usize>>>,
     pub health_check_task: TokioMutex<Option<BoxFuture<'static, ()>>>,
+    control: Control,
 }
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-struct Measure(usize, u128); // (index, duration in millis)
-
 impl Handler {
     pub fn new(
         actors: Vec<Arc<dyn ProxyHandler>>,
@@ -40,89 +47,195 @@ impl Handler {
         check_interval: u32,
         failover: bool,
     ) -> Self {
-        let mut schedule = Vec::new();
-        for i in 0..actors.len() {
-            schedule.push(i);
-        }
+        HandlerBuilder::default()
+            .actors(actors)
+            .fail_timeout(fail_timeout)
+            .health_check(health_check)
+            .check_interval(check_interval)
+            .failover(failover)
+            .build()
+    }
+
+    /// Returns the runtime controls of the handler, see `Control`.
+    pub fn control(&self) -> Control {
+        self.control.clone()
+    }
+
+    /// See `Control::latencies`.
+    pub fn latencies(&self) -> ActorLatencies {
+        self.control.latencies()
+    }
+
+    /// See `Control::trigger_recheck`.
+    pub fn trigger_recheck(&self) {
+        self.control.trigger_recheck();
+    }
+
+    /// See `Control::set_actor_enabled`.
+    pub fn set_actor_enabled(&self, tag: &str, enabled: bool) -> bool {
+        self.control.set_actor_enabled(tag, enabled)
+    }
+}
+
+fn probe_name(probe: &DnsProbe) -> Result<Name, String> {
+    let mut fqdn = probe.domain.clone();
+    if !fqdn.ends_with('.') {
+        fqdn.push('.');
+    }
+    Name::from_str(&fqdn).map_err(|e| format!("invalid domain name {}: {}", &probe.domain, e))
+}
+
+// The A query of `probe`, with `id`.
+fn dns_query(probe: &DnsProbe, id: u16) -> Result<Vec<u8>, String> {
+    let mut msg = Message::new();
+    msg.add_query(Query::query(probe_name(probe)?, RecordType::A));
+    msg.set_id(id);
+    msg.set_op_code(OpCode::Query);
+    msg.set_message_type(MessageType::Query);
+    msg.set_recursion_desired(true);
+    msg.to_vec()
+        .map_err(|e| format!("encode message to buffer failed: {}", e))
+}
+
+// Checks `buf` answers the query `id` of `probe`, the response may come
+// from anything on the path of the actor.
+fn check_response(probe: &DnsProbe, id: u16, buf: &[u8]) -> Result<(), String> {
+    let resp = Message::from_vec(buf).map_err(|e| format!("parse message failed: {}", e))?;
+    if resp.id() != id || resp.message_type() != MessageType::Response {
+        return Err("not a response to the query".to_string());
+    }
+    if resp.response_code() != ResponseCode::NoError {
+        return Err(format!("response error {}", resp.response_code()));
+    }
+    let name = probe_name(probe)?;
+    if !resp
+        .queries()
+        .iter()
+        .any(|q| q.name() == &name && q.query_type() == RecordType::A)
+    {
+        return Err("response for another question".to_string());
+    }
+    let addrs: Vec<IpAddr> = resp
+        .answers()
+        .iter()
+        .filter_map(|ans| match ans.rdata() {
+            RData::A(ip) => Some(IpAddr::V4(*ip)),
+            _ => None,
+        })
+        .collect();
+    if addrs.is_empty() {
+        return Err("no records".to_string());
+    }
+    if !probe.expected.is_empty() && !addrs.iter().any(|ip| probe.expected.contains(ip)) {
+        return Err(format!("unexpected answer {:?}", addrs));
+    }
+    Ok(())
+}
+
+impl From<HandlerBuilder> for Handler {
+    fn from(b: HandlerBuilder) -> Self {
+        let HandlerBuilder {
+            actors,
+            fail_timeout,
+            health_check,
+            check_interval,
+            failover,
+            switch_margin,
+            url_test,
+            dns_probe,
+            unprobed,
+            shutdown,
+            ..
+        } = b;
+        let failover = failover && url_test.is_none();
+        let schedule = initial_schedule(actors.len(), &unprobed);
         let schedule = Arc::new(TokioMutex::new(schedule));
 
+        let control = Control::new(actors.clone());
+
         let schedule2 = schedule.clone();
         let actors2 = actors.clone();
+        let latencies2 = control.latencies.clone();
+        let recheck2 = control.recheck.clone();
+        let enabled2 = control.enabled.clone();
         let task = if health_check {
-            let health_check_task: BoxFuture<'static, ()> = Box::pin(async move {
+            let health_check_task = async move {
+                let mut sticky = Sticky::new(switch_margin);
                 loop {
                     let mut measures: Vec<Measure> = Vec::new();
                     for (i, a) in (&actors2).iter().enumerate() {
+                        if !enabled2.get(i) || unprobed.iter().any(|(j, _)| *j == i) {
+                            continue;
+                        }
+                        let probe = &dns_probe;
                         debug!("health checking udp for [{}] index [{}]", a.tag(), i);
                         let single_measure = async move {
                             let sess = Session {
                                 source: "0.0.0.0:0".parse().unwrap(),
-                                destination: SocksAddr::Ip(SocketAddr::new(
-                                    IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)),
-                                    53,
-                                )),
+                                destination: SocksAddr::Ip(probe.server),
                             };
                             let start = tokio::time::Instant::now();
                             match a.connect(&sess, None, None).await {
                                 Ok(socket) => {
-                                    let addr =
-                                        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53);
-                                    let mut msg = Message::new();
-                                    let name = match Name::from_str("www.google.com.") {
-                                        Ok(n) => n,
-                                        Err(e) => {
-                                            warn!("invalid domain name: {}", e);
-                                            return Measure(i, u128::MAX);
-                                        }
-                                    };
-                                    let query = Query::query(name, RecordType::A);
-                                    msg.add_query(query);
                                     let mut rng = StdRng::from_entropy();
                                     let id: u16 = rng.gen();
-                                    msg.set_id(id);
-                                    msg.set_op_code(OpCode::Query);
-                                    msg.set_message_type(MessageType::Query);
-                                    msg.set_recursion_desired(true);
-                                    let msg_buf = match msg.to_vec() {
+                                    let msg_buf = match dns_query(probe, id) {
                                         Ok(b) => b,
                                         Err(e) => {
-                                            warn!("encode message to buffer failed: {}", e);
-                                            return Measure(i, u128::MAX);
+                                            warn!("invalid dns probe: {}", e);
+                                            return Measure(i, HANDSHAKE_FAILED);
                                         }
                                     };
 
                                     let (mut recv, mut send) = socket.split();
 
-                                    if send.send_to(&msg_buf, &addr).await.is_err() {
-                                        return Measure(i, u128::MAX - 2); // handshake is ok
+                                    if send.send_to(&msg_buf, &probe.server).await.is_err() {
+                                        return Measure(i, WRITE_FAILED); // handshake is ok
                                     }
                                     let mut buf = [0u8; 1500];
                                     match recv.recv_from(&mut buf).await {
                                         // handshake, write and read are ok
-                                        Ok(_) => {
-                                            let elapsed =
-                                                tokio::time::Instant::now().duration_since(start);
-                                            Measure(i, elapsed.as_millis())
-                                        }
+                                        Ok((n, _)) => match check_response(probe, id, &buf[..n]) {
+                                            Ok(()) => {
+                                                let elapsed = tokio::time::Instant::now()
+                                                    .duration_since(start);
+                                                Measure(i, elapsed.as_millis())
+                                            }
+                                            Err(e) => {
+                                                debug!(
+                                                    "invalid dns response through [{}]: {}",
+                                                    a.tag(),
+                                                    e
+                                                );
+                                                Measure(i, READ_FAILED)
+                                            }
+                                        },
                                         // handshake and write are ok
-                                        Err(_) => Measure(i, u128::MAX - 3),
+                                        Err(_) => Measure(i, READ_FAILED),
                                     }
                                 }
                                 // handshake not ok
-                                Err(_) => Measure(i, u128::MAX),
+                                Err(_) => Measure(i, HANDSHAKE_FAILED),
                             }
                         };
-                        match timeout(time::Duration::from_secs(10), single_measure).await {
+                        match timeout(probe.timeout, single_measure).await {
                             Ok(m) => {
                                 measures.push(m);
                             }
                             Err(_) => {
-                                measures.push(Measure(i, u128::MAX - 1)); // timeout, better than handshake error
+                                measures.push(Measure(i, TIMED_OUT)); // timeout, better than handshake error
                             }
                         }
                     }
 
+                    for m in measures.iter() {
+                        if m.is_ok() {
+                            latencies2.record(m.0, m.1 as u64);
+                        }
+                    }
+
                     measures.sort_by(|a, b| a.1.cmp(&b.1));
+                    sticky.rank(&mut measures);
                     trace!("sorted udp health check results:\n{:#?}", measures);
 
                     let priorities: Vec<String> = measures
@@ -141,23 +254,35 @@ impl Handler {
                         priorities.join(" > ")
                     );
 
+                    let order =
+                        place_unprobed(measures.iter().map(|m| m.0).collect(), &unprobed);
                     let mut schedule = schedule2.lock().await;
                     schedule.clear();
                     if !failover {
                         // if failover is disabled, put only 1 actor in schedule
-                        schedule.push(measures[0].0);
-                        trace!("put {} in schedule", measures[0].0);
+                        if let Some(i) = order.first() {
+                            schedule.push(*i);
+                            trace!("put {} in schedule", i);
+                        }
                     } else {
-                        for m in measures {
-                            schedule.push(m.0);
-                            trace!("put {} in schedule", m.0);
+                        for i in order {
+                            schedule.push(i);
+                            trace!("put {} in schedule", i);
                         }
                     }
 
                     drop(schedule); // drop the guard, to release the lock
 
-                    tokio::time::delay_for(time::Duration::from_secs(check_interval as u64)).await;
+                    let interval = time::Duration::from_secs(check_interval as u64);
+                    tokio::select! {
+                        _ = tokio::time::delay_for(interval) => (),
+                        _ = recheck2.notified() => debug!("udp health check triggered"),
+                    }
                 }
+            };
+            let health_check_task: BoxFuture<'static, ()> = Box::pin(async move {
+                shutdown.run_until(health_check_task).await;
+                debug!("health check stopped");
             });
             Some(health_check_task)
         } else {
@@ -169,6 +294,7 @@ impl Handler {
             fail_timeout,
             schedule,
             health_check_task: TokioMutex::new(task),
+            control,
         }
     }
 }
@@ -187,6 +313,14 @@ impl ProxyUdpHandler for Handler {
         UdpTransportType::Unknown
     }
 
+    async fn self_test(&self) -> TestResult {
+        let tests = self
+            .actors
+            .iter()
+            .map(|a| async move { (a.tag().to_owned(), ProxyUdpHandler::self_test(&**a).await) });
+        TestResult::aggregate(futures::future::join_all(tests).await)
+    }
+
     async fn connect<'a>(
         &'a self,
         sess: &'a Session,
@@ -200,32 +334,316 @@ impl ProxyUdpHandler for Handler {
         }
 
         let schedule = self.schedule.lock().await.clone();
+        let fail_timeout = time::Duration::from_secs(self.fail_timeout as u64);
+        let mut last_err = None;
 
         for i in schedule {
             if i >= self.actors.len() {
                 return Err(io::Error::new(io::ErrorKind::Other, "invalid actor index"));
             }
+            if !self.control.enabled.get(i) {
+                continue;
+            }
 
-            match timeout(
-                time::Duration::from_secs(self.fail_timeout as u64),
-                (&self.actors[i]).connect(sess, None, None),
-            )
-            .await
-            {
+            // No time left for another actor.
+            deadline::check()?;
+            let fail_timeout = deadline::cap(fail_timeout);
+            match timeout(fail_timeout, (&self.actors[i]).connect(sess, None, None)).await {
                 // return before timeout
                 Ok(t) => match t {
                     // return ok
                     Ok(v) => return Ok(v),
                     // return err
-                    Err(_) => continue,
+                    Err(e) => last_err = Some(e),
                 },
                 // after timeout
-                Err(_) => continue,
+                Err(_) => last_err = Some(ProxyError::Timeout(fail_timeout).into()),
+            }
+        }
+        Err(match last_err {
+            Some(e) => ProxyError::AllFailed(e),
+            None => ProxyError::NoOutbound,
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use tokio::net::UdpSocket;
+    use tokio::time::delay_for;
+    use trust_dns_proto::rr::{dns_class::DNSClass, resource::Record};
+
+    use super::*;
+    use crate::proxy::{
+        datagram::SimpleDatagram, Handler as ProxyHandlerImpl, ProxyDatagramRecvHalf,
+        ProxyDatagramSendHalf, ProxyHandlerType, ProxyTcpHandler,
+    };
+
+    const PORTAL_REPLY: &[u8] = b"HTTP/1.1 302 Found\r\nLocation: http://portal/\r\n\r\n";
+
+    // Answers `query` with `ip`.
+    fn answer(query: &[u8], ip: Ipv4Addr) -> Vec<u8> {
+        let req = Message::from_vec(query).unwrap();
+        let query = req.queries()[0].clone();
+        let mut ans = Record::new();
+        ans.set_name(query.name().clone())
+            .set_rr_type(RecordType::A)
+            .set_ttl(1)
+            .set_dns_class(DNSClass::IN)
+            .set_rdata(RData::A(ip));
+        let mut resp = Message::new();
+        resp.set_id(req.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(OpCode::Query)
+            .set_response_code(ResponseCode::NoError);
+        resp.add_query(query);
+        resp.add_answer(ans);
+        resp.to_vec().unwrap()
+    }
+
+    // Starts a resolver answering every query with `ip`.
+    async fn resolver(ip: Ipv4Addr) -> SocketAddr {
+        let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (n, src) = socket.recv_from(&mut buf).await.unwrap();
+                let resp = answer(&buf[..n], ip);
+                socket.send_to(&resp, &src).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    // Swallows what's sent, receives `reply` once if any, nothing otherwise.
+    struct Swallowing(Option<Vec<u8>>);
+
+    impl ProxyDatagram for Swallowing {
+        fn split(
+            self: Box<Self>,
+        ) -> (
+            Box<dyn ProxyDatagramRecvHalf>,
+            Box<dyn ProxyDatagramSendHalf>,
+        ) {
+            (
+                Box::new(SwallowingRecvHalf(self.0)),
+                Box::new(SwallowingSendHalf),
+            )
+        }
+    }
+
+    struct SwallowingRecvHalf(Option<Vec<u8>>);
+
+    #[async_trait]
+    impl ProxyDatagramRecvHalf for SwallowingRecvHalf {
+        async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+            match self.0.take() {
+                Some(reply) => {
+                    buf[..reply.len()].copy_from_slice(&reply);
+                    Ok((reply.len(), "10.0.0.1:53".parse().unwrap()))
+                }
+                None => futures::future::pending().await,
+            }
+        }
+    }
+
+    struct SwallowingSendHalf;
+
+    #[async_trait]
+    impl ProxyDatagramSendHalf for SwallowingSendHalf {
+        async fn send_to(&mut self, buf: &[u8], _target: &SocketAddr) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    enum Path {
+        Direct,
+        BlackHole,
+        Portal,
+    }
+
+    struct Actor(Path);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Actor {
+        fn name(&self) -> &str {
+            "actor"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            Err(io::ErrorKind::Other.into())
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Actor {
+        fn name(&self) -> &str {
+            "actor"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Ok(match self.0 {
+                Path::Direct => Box::new(SimpleDatagram(UdpSocket::bind("127.0.0.1:0").await?)),
+                Path::BlackHole => Box::new(Swallowing(None)),
+                Path::Portal => Box::new(Swallowing(Some(PORTAL_REPLY.to_vec()))),
+            })
+        }
+    }
+
+    fn actor(tag: &str, path: Path) -> Arc<dyn ProxyHandler> {
+        ProxyHandlerImpl::new(
+            tag.to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Actor(path)),
+            Box::new(Actor(path)),
+        )
+    }
+
+    fn probe(server: SocketAddr, expected: Vec<IpAddr>) -> DnsProbe {
+        DnsProbe {
+            server,
+            domain: "example.com".to_string(),
+            expected,
+            timeout: Duration::from_millis(500),
+        }
+    }
+
+    #[test]
+    fn test_check_response() {
+        let ip = Ipv4Addr::new(93, 184, 216, 34);
+        let probe = probe("127.0.0.1:53".parse().unwrap(), vec![]);
+        let query = dns_query(&probe, 1234).unwrap();
+        let resp = answer(&query, ip);
+        assert!(check_response(&probe, 1234, &resp).is_ok());
+        assert!(check_response(&probe, 4321, &resp).is_err());
+        assert!(check_response(&probe, 1234, PORTAL_REPLY).is_err());
+
+        let expected = DnsProbe {
+            expected: vec![IpAddr::V4(ip)],
+            ..probe.clone()
+        };
+        assert!(check_response(&expected, 1234, &resp).is_ok());
+        let resp = answer(&query, Ipv4Addr::new(10, 0, 0, 1));
+        assert!(check_response(&expected, 1234, &resp).is_err());
+
+        let other = DnsProbe {
+            domain: "example.org".to_string(),
+            ..probe
+        };
+        assert!(check_response(&other, 1234, &resp).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dns_probe_health_check() {
+        let ip = Ipv4Addr::new(93, 184, 216, 34);
+        let server = resolver(ip).await;
+        let udp: Handler = HandlerBuilder::default()
+            .actors(vec![
+                actor("black-hole", Path::BlackHole),
+                actor("portal", Path::Portal),
+                actor("direct", Path::Direct),
+            ])
+            .dns_probe(probe(server, vec![IpAddr::V4(ip)]))
+            .build();
+        let task = udp.health_check_task.lock().await.take().unwrap();
+        tokio::spawn(task);
+
+        let mut schedule = Vec::new();
+        for _ in 0..50 {
+            delay_for(Duration::from_millis(100)).await;
+            schedule = udp.schedule.lock().await.clone();
+            if schedule != vec![0, 1, 2] {
+                break;
             }
         }
-        Err(io::Error::new(
-            io::ErrorKind::Other,
-            "all outbound attempts failed",
-        ))
+        // The garbage of the portal is worth no more than a failure, though
+        // it's faster than the timeout of the black hole.
+        assert_eq!(schedule, vec![2, 1, 0]);
+        let latencies = udp.latencies();
+        assert!(latencies[0].1.is_none());
+        assert!(latencies[1].1.is_none());
+        assert!(latencies[2].1.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failover_to_next_actor() {
+        use std::sync::atomic::Ordering;
+
+        use crate::proxy::testing::{udp_actor, Behavior, MockUdpHandler};
+
+        let refusing = MockUdpHandler::refusing();
+        let echo = MockUdpHandler::new(Behavior::Echo);
+        let (refused, echoed) = (refusing.connects(), echo.connects());
+        let udp: Handler = HandlerBuilder::default()
+            .actors(vec![udp_actor("a", refusing), udp_actor("b", echo)])
+            .health_check(false)
+            .build();
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        };
+
+        let (mut r, mut s) = udp.connect(&sess, None, None).await.ok().unwrap().split();
+        let target = "1.2.3.4:53".parse().unwrap();
+        s.send_to(b"ping", &target).await.unwrap();
+        let mut buf = [0u8; 16];
+        let (n, _) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(refused.load(Ordering::SeqCst), 1);
+        assert_eq!(echoed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_self_test_aggregates_actors() {
+        use crate::proxy::testing::{udp_actor, Behavior, MockUdpHandler};
+
+        let udp: Handler = HandlerBuilder::default()
+            .actors(vec![
+                udp_actor("a", MockUdpHandler::refusing()),
+                udp_actor("b", MockUdpHandler::new(Behavior::Echo)),
+            ])
+            .health_check(false)
+            .build();
+        let res = udp.self_test().await;
+        assert!(res.is_ok(), "{:?}", res);
+        assert_eq!(res.actors[0].0, "a");
+        assert!(!res.actors[0].1.is_ok());
+        assert_eq!(res.actors[1].0, "b");
+        assert!(res.actors[1].1.is_ok());
+
+        let udp: Handler = HandlerBuilder::default()
+            .actors(vec![udp_actor("a", MockUdpHandler::refusing())])
+            .health_check(false)
+            .build();
+        let res = udp.self_test().await;
+        assert_eq!(res.outcome.unwrap_err(), "[a] connection refused");
     }
 }
@@ -0,0 +1,103 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{
+    common::dns_client::DnsClient,
+    proxy::{ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+/// Connects to a fixed address regardless of the session destination, the
+/// address can be either a domain or an IP.
+pub struct Handler {
+    pub address: String,
+    pub port: u16,
+    pub bind_addr: SocketAddr,
+    pub dns_client: Arc<DnsClient>,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        Some((self.address.clone(), self.port, self.bind_addr))
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        _sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        // In a chain, the stream is already connected to the fixed address.
+        if let Some(stream) = stream {
+            return Ok(stream);
+        }
+        self.dial_tcp_stream(
+            self.dns_client.clone(),
+            &self.bind_addr,
+            &self.address,
+            &self.port,
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    #[tokio::test]
+    async fn test_fixed_ignores_destination() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            for i in 0..2u8 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1];
+                stream.read_exact(&mut buf).await.unwrap();
+                assert_eq!(buf[0], i);
+            }
+        });
+
+        let handler = Handler {
+            address: addr.ip().to_string(),
+            port: addr.port(),
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let destinations = vec![
+            SocksAddr::Domain("example.com".to_string(), 443),
+            SocksAddr::Ip("1.2.3.4:80".parse().unwrap()),
+        ];
+        for (i, destination) in destinations.into_iter().enumerate() {
+            let sess = Session {
+                source: "127.0.0.1:0".parse().unwrap(),
+                destination,
+            };
+            let mut stream = handler.handle(&sess, None).await.unwrap();
+            stream.write_all(&[i as u8]).await.unwrap();
+        }
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fixed_connect_addr() {
+        let handler = Handler {
+            address: "relay.example.com".to_string(),
+            port: 8443,
+            bind_addr: "0.0.0.0:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let (address, port, _) = handler.tcp_connect_addr().unwrap();
+        assert_eq!(address, "relay.example.com");
+        assert_eq!(port, 8443);
+    }
+}
@@ -1,96 +1,44 @@
-use std::cmp::min;
 use std::io;
 use std::net::SocketAddr;
-use std::pin::Pin;
-use std::task::{Context, Poll};
 
 use async_trait::async_trait;
-use bytes::{Bytes, BytesMut};
-use futures::stream::Stream;
-use futures::TryFutureExt;
-use log::*;
-use tokio::io::{AsyncRead, AsyncWrite};
-use url::Url;
 
 use crate::{
-    proxy::{ProxyStream, ProxyTcpHandler, SimpleStream},
+    proxy::{
+        transport::{
+            h2::{H2Config, H2Connection},
+            pool::{ConnectionPool, PoolConfig, PoolKey},
+        },
+        ProxyStream, ProxyTcpHandler,
+    },
     session::Session,
 };
 
-struct Adapter {
-    send_stream: h2::SendStream<Bytes>,
-    recv_stream: h2::RecvStream,
-    recv_buf: BytesMut,
+pub struct Handler {
+    pub path: String,
+    pub host: String,
+    // Connections by server, each flow is a stream on one of them.
+    pool: ConnectionPool<H2Connection>,
 }
 
-impl AsyncRead for Adapter {
-    fn poll_read(
-        mut self: Pin<&mut Self>,
-        cx: &mut Context,
-        buf: &mut [u8],
-    ) -> Poll<io::Result<usize>> {
-        if !self.recv_buf.is_empty() {
-            let to_read = min(buf.len(), self.recv_buf.len());
-            let for_read = self.recv_buf.split_to(to_read);
-            (&mut buf[..to_read]).copy_from_slice(&for_read[..to_read]);
-            return Poll::Ready(Ok(to_read));
-        }
-        if self.recv_stream.is_end_stream() {
-            return Poll::Ready(Ok(0));
-        }
-        let item = match Pin::new(&mut self.recv_stream).poll_next(cx) {
-            Poll::Ready(item) => item,
-            Poll::Pending => return Poll::Pending,
-        };
-        match item {
-            Some(res) => match res {
-                Ok(data) => {
-                    let to_read = min(buf.len(), data.len());
-                    (&mut buf[..to_read]).copy_from_slice(&data[..to_read]);
-                    if data.len() > to_read {
-                        self.recv_buf.extend_from_slice(&data[to_read..]);
-                    }
-                    Poll::Ready(Ok(to_read))
-                }
-                Err(e) => Poll::Ready(Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("receive data failed: {}", e),
-                ))),
-            },
-            None => {
-                panic!("could never happend, we already checked stream end");
-            }
+impl Handler {
+    pub fn new(path: String, host: String) -> Self {
+        Handler {
+            path,
+            host,
+            pool: ConnectionPool::new(PoolConfig::default()),
         }
     }
-}
 
-impl AsyncWrite for Adapter {
-    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
-        let me = self.get_mut();
-        // FIXME reserve capacity before sending to avoid memory issue
-        let mut buf2 = BytesMut::new();
-        buf2.extend_from_slice(buf);
-        match me.send_stream.send_data(buf2.freeze(), false) {
-            Ok(_) => Poll::Ready(Ok(buf.len())),
-            Err(e) => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("send data failed: {}", e),
-            ))),
+    // The server is the destination of the session, the one the stream
+    // given to the handler is connected to.
+    fn pool_key(&self, sess: &Session) -> PoolKey {
+        PoolKey {
+            host: sess.destination.host(),
+            port: sess.destination.port(),
+            transport: super::NAME.to_string(),
         }
     }
-
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
-    }
-
-    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
-        Poll::Ready(Ok(()))
-    }
-}
-
-pub struct Handler {
-    pub path: String,
-    pub host: String,
 }
 
 #[async_trait]
@@ -103,60 +51,141 @@ impl ProxyTcpHandler for Handler {
         None
     }
 
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.pool.is_warm(&self.pool_key(sess))
+    }
+
     async fn handle<'a>(
         &'a self,
-        _sess: &'a Session,
+        sess: &'a Session,
         stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>> {
-        match stream {
+        // stream is aussumed to be a connection ready for h2 handshake,
+        // e.g. a TLS connection negotiated with alpn h2.
+        if let Some(proto) = stream.as_ref().and_then(|s| s.negotiated_protocol()) {
+            if proto != b"h2" {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("h2 not negotiated, got {}", String::from_utf8_lossy(&proto)),
+                ));
+            }
+        }
+        let config = H2Config {
+            host: self.host.clone(),
+            path: self.path.clone(),
+            ..Default::default()
+        };
+        let key = self.pool_key(sess);
+        let h2_stream = match stream {
+            // A stream dialed for the flow, e.g. by a chain, is always used,
+            // it's a new connection to the server.
             Some(stream) => {
-                // stream is aussumed to be a connection ready for h2 handshake,
-                // e.g. a TLS connection negotiated with alpn h2.
-                let (client, conn) = h2::client::handshake(stream)
-                    .map_err(|e| {
-                        io::Error::new(io::ErrorKind::Other, format!("handshake failed: {}", e))
+                let conn = H2Connection::handshake(stream, config).await?;
+                self.pool.add_connection(&key, conn).await?
+            }
+            None => {
+                self.pool
+                    .open_stream(&key, || async {
+                        Err(io::Error::new(io::ErrorKind::Other, "invalid h2 input"))
                     })
-                    .await?;
-                let mut url = Url::parse(&format!("https://{}", self.host)).unwrap();
-                url = url.join(self.path.as_str()).unwrap();
-                let req = http::Request::builder()
-                    .method(http::Method::PUT)
-                    .uri(&url.to_string())
-                    .body(())
-                    .unwrap();
-
-                let mut client = client
-                    .ready()
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("h2 error: {}", e)))
-                    .await?;
-                let (resp, send_stream) = client.send_request(req, false).map_err(|e| {
-                    io::Error::new(io::ErrorKind::Other, format!("h2 error: {}", e))
-                })?;
-
-                tokio::spawn(async move {
-                    if let Err(e) = conn.await {
-                        debug!("connection failed: {}", e);
-                    }
-                });
-
-                let (parts, recv_stream) = resp
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("h2 error: {}", e)))
                     .await?
-                    .into_parts();
-                if parts.status != http::status::StatusCode::OK {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("h2 failed with status code: {}", parts.status),
-                    ));
-                }
-                let h2_stream = Adapter {
-                    send_stream,
-                    recv_stream,
-                    recv_buf: BytesMut::new(),
-                };
-                Ok(Box::new(SimpleStream(h2_stream)))
             }
-            None => Err(io::Error::new(io::ErrorKind::Other, "invalid h2 input")),
+        };
+        Ok(Box::new(h2_stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::stream::SimpleStream;
+    use crate::session::SocksAddr;
+
+    // Echoes the streams of every connection accepted, counts the
+    // connections.
+    async fn serve(mut listener: TcpListener, accepted: Arc<AtomicUsize>) {
+        while let Ok((stream, _)) = listener.accept().await {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut conn = match h2::server::handshake(stream).await {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                while let Some(Ok((req, mut respond))) = conn.accept().await {
+                    tokio::spawn(async move {
+                        let resp = http::Response::builder().status(200).body(()).unwrap();
+                        let mut send = respond.send_response(resp, false).unwrap();
+                        let mut body = req.into_body();
+                        while let Some(Ok(data)) = body.data().await {
+                            let _ = body.flow_control().release_capacity(data.len());
+                            if send.send_data(data, false).is_err() {
+                                return;
+                            }
+                        }
+                        let _ = send.send_data(Bytes::new(), true);
+                    });
+                }
+            });
         }
     }
+
+    async fn echo(stream: &mut Box<dyn ProxyStream>, payload: &[u8]) {
+        stream.write_all(payload).await.unwrap();
+        let mut buf = vec![0u8; payload.len()];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, payload);
+    }
+
+    #[tokio::test]
+    async fn test_h2_reuses_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(serve(listener, accepted.clone()));
+
+        let handler = Handler::new("h2".to_string(), "example.com".to_string());
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Ip(addr),
+        };
+        assert!(!handler.is_warm(&sess));
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut s1 = handler
+            .handle(&sess, Some(Box::new(SimpleStream(stream))))
+            .await
+            .unwrap();
+        echo(&mut s1, b"first").await;
+
+        // The second flow is a stream on the same connection, it needs no
+        // stream of its own.
+        assert!(handler.is_warm(&sess));
+        let mut s2 = handler.handle(&sess, None).await.unwrap();
+        echo(&mut s2, b"second").await;
+        echo(&mut s1, b"first again").await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // A stream given while warm is used rather than dropped.
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut s3 = handler
+            .handle(&sess, Some(Box::new(SimpleStream(stream))))
+            .await
+            .unwrap();
+        echo(&mut s3, b"third").await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+        // Other servers don't share it.
+        let other = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("other.example.com".to_string(), 443),
+        };
+        assert!(!handler.is_warm(&other));
+        assert!(handler.handle(&other, None).await.is_err());
+    }
 }
@@ -1,24 +1,63 @@
 use std::io::Result;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use futures::future::FutureExt;
+use tokio::time::Instant;
+use tracing::{debug_span, field, warn};
+use tracing_futures::Instrument;
 
-use crate::session::Session;
+use crate::common::dns_client::DnsClient;
+use crate::session::{Session, SocksAddr};
 
 use super::{
-    Color, HandlerTyped, ProxyDatagram, ProxyHandler, ProxyHandlerType, ProxyStream,
-    ProxyTcpHandler, ProxyUdpHandler, Tag, UdpTransportType,
+    deadline,
+    limit::{LimitedStream, TokenBucket},
+    self_test,
+    stream::MaxLifetimeStream,
+    Color, HandlerTyped, ProxyDatagram, ProxyError, ProxyHandler, ProxyHandlerType, ProxyStream,
+    ProxyTcpHandler, ProxyUdpHandler, Tag, TestResult, UdpTransportType,
 };
 
 pub static NAME: &str = "handler";
 
+fn record_outcome<T>(span: &tracing::Span, res: &Result<T>) {
+    match res {
+        Ok(_) => span.record("outcome", &"ok"),
+        Err(e) => span.record("outcome", &field::display(e)),
+    };
+}
+
+/// Options common to all outbounds.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// Connects taking longer than this are logged as warnings, whether they
+    /// succeed or not.
+    pub slow_threshold: Option<Duration>,
+    /// Time allowed for a connect, including every attempt made by the
+    /// handlers below, see `deadline`.
+    pub connect_deadline: Option<Duration>,
+    /// TCP connections are closed once this old, see `MaxLifetimeStream`.
+    pub max_lifetime: Option<Duration>,
+    /// Destination domains are resolved with this client before connecting,
+    /// instead of being passed on to the outbound for its server to resolve.
+    pub resolver: Option<Arc<DnsClient>>,
+    /// Bucket shared by the outbounds which the writes of the connections
+    /// they dial draw from, so all of them together stay under a rate.
+    /// Only applies to connects not given a stream, ensembles which don't
+    /// dial themselves should go without it, their actors count the bytes.
+    pub egress: Option<Arc<TokenBucket>>,
+}
+
 pub struct Handler {
     tag: String,
     color: colored::Color,
     handler_type: ProxyHandlerType,
     tcp_handler: Box<dyn ProxyTcpHandler>,
     udp_handler: Box<dyn ProxyUdpHandler>,
+    options: Options,
 }
 
 impl Handler {
@@ -28,6 +67,34 @@ impl Handler {
         handler_type: ProxyHandlerType,
         tcp: Box<dyn ProxyTcpHandler>,
         udp: Box<dyn ProxyUdpHandler>,
+    ) -> Arc<Self> {
+        Self::with_slow_threshold(tag, color, handler_type, tcp, udp, None)
+    }
+
+    /// Same as `new`, connects taking longer than `slow_threshold` are
+    /// logged as warnings, whether they succeed or not.
+    pub fn with_slow_threshold(
+        tag: String,
+        color: colored::Color,
+        handler_type: ProxyHandlerType,
+        tcp: Box<dyn ProxyTcpHandler>,
+        udp: Box<dyn ProxyUdpHandler>,
+        slow_threshold: Option<Duration>,
+    ) -> Arc<Self> {
+        let options = Options {
+            slow_threshold,
+            ..Default::default()
+        };
+        Self::with_options(tag, color, handler_type, tcp, udp, options)
+    }
+
+    pub fn with_options(
+        tag: String,
+        color: colored::Color,
+        handler_type: ProxyHandlerType,
+        tcp: Box<dyn ProxyTcpHandler>,
+        udp: Box<dyn ProxyUdpHandler>,
+        options: Options,
     ) -> Arc<Self> {
         Arc::new(Handler {
             tag,
@@ -35,8 +102,57 @@ impl Handler {
             handler_type,
             tcp_handler: tcp,
             udp_handler: udp,
+            options,
         })
     }
+
+    // Returns the session with its destination domain resolved, if
+    // destinations are resolved locally.
+    async fn resolve(&self, sess: &Session) -> Result<Option<Session>> {
+        let dns_client = match &self.options.resolver {
+            Some(dns_client) => dns_client,
+            None => return Ok(None),
+        };
+        let (domain, port) = match &sess.destination {
+            SocksAddr::Domain(domain, port) => (domain, *port),
+            SocksAddr::Ip(_) => return Ok(None),
+        };
+        let ips = dns_client
+            .lookup(domain.clone())
+            .await
+            .map_err(|e| ProxyError::Dns {
+                host: domain.clone(),
+                source: e.into(),
+            })?;
+        match ips.first() {
+            Some(ip) => {
+                let mut sess = sess.clone();
+                sess.destination = SocksAddr::Ip(SocketAddr::new(*ip, port));
+                Ok(Some(sess))
+            }
+            None => Err(ProxyError::Dns {
+                host: domain.clone(),
+                source: "could not resolve to any address".into(),
+            }
+            .into()),
+        }
+    }
+
+    fn check_slow(&self, network: &str, sess: &Session, start: Instant) {
+        if let Some(threshold) = self.options.slow_threshold {
+            let elapsed = start.elapsed();
+            if elapsed > threshold {
+                warn!(
+                    "[{}] slow {} connect to {}: {}ms, threshold {}ms",
+                    self.tag,
+                    network,
+                    sess.destination,
+                    elapsed.as_millis(),
+                    threshold.as_millis()
+                );
+            }
+        }
+    }
 }
 
 impl ProxyHandler for Handler {}
@@ -73,12 +189,73 @@ impl ProxyTcpHandler for Handler {
         self.tcp_handler.tcp_connect_addr()
     }
 
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.tcp_handler.is_warm(sess)
+    }
+
+    async fn dial_tcp_stream(
+        &self,
+        dns_client: Arc<DnsClient>,
+        bind_addr: &SocketAddr,
+        address: &str,
+        port: &u16,
+    ) -> Result<Box<dyn ProxyStream>> {
+        self.tcp_handler
+            .dial_tcp_stream(dns_client, bind_addr, address, port)
+            .await
+    }
+
+    async fn self_test(&self) -> TestResult {
+        // Ensembles test their actors, the others go through the options
+        // of the outbound like any connection.
+        match self.handler_type {
+            ProxyHandlerType::Ensemble => self.tcp_handler.self_test().await,
+            _ => self_test::test_tcp(self, &self_test::tcp_target()).await,
+        }
+    }
+
     async fn handle<'a>(
         &'a self,
         sess: &'a Session,
         stream: Option<Box<dyn ProxyStream>>,
     ) -> Result<Box<dyn ProxyStream>> {
-        self.tcp_handler.handle(sess, stream).await
+        let span = debug_span!(
+            "handle",
+            tag = %self.tag,
+            destination = %sess.destination,
+            outcome = field::Empty,
+        );
+        let start = Instant::now();
+        let dials = stream.is_none();
+        let handle = async move {
+            let resolved = self.resolve(sess).await?;
+            let sess = resolved.as_ref().unwrap_or(sess);
+            self.tcp_handler.handle(sess, stream).await
+        }
+        .boxed();
+        let res = match self.options.connect_deadline {
+            Some(budget) => {
+                deadline::scope(budget, handle)
+                    .instrument(span.clone())
+                    .await
+            }
+            None => handle.instrument(span.clone()).await,
+        };
+        record_outcome(&span, &res);
+        self.check_slow("tcp", sess, start);
+        let res = match &self.options.egress {
+            Some(egress) if dials => res.map(|stream| {
+                Box::new(LimitedStream::new(stream, None, None, Some(egress.clone())))
+                    as Box<dyn ProxyStream>
+            }),
+            _ => res,
+        };
+        match self.options.max_lifetime {
+            Some(max_lifetime) => res.map(|stream| {
+                Box::new(MaxLifetimeStream::new(stream, max_lifetime)) as Box<dyn ProxyStream>
+            }),
+            None => res,
+        }
     }
 }
 
@@ -96,12 +273,352 @@ impl ProxyUdpHandler for Handler {
         self.udp_handler.udp_transport_type()
     }
 
+    async fn self_test(&self) -> TestResult {
+        match self.handler_type {
+            ProxyHandlerType::Ensemble => self.udp_handler.self_test().await,
+            _ => self_test::test_udp(self, &self_test::udp_target()).await,
+        }
+    }
+
     async fn connect<'a>(
         &'a self,
         sess: &'a Session,
         datagram: Option<Box<dyn ProxyDatagram>>,
         stream: Option<Box<dyn ProxyStream>>,
     ) -> Result<Box<dyn ProxyDatagram>> {
-        self.udp_handler.connect(sess, datagram, stream).await
+        let span = debug_span!(
+            "connect",
+            tag = %self.tag,
+            destination = %sess.destination,
+            outcome = field::Empty,
+        );
+        let start = Instant::now();
+        let connect = async move {
+            let resolved = self.resolve(sess).await?;
+            let sess = resolved.as_ref().unwrap_or(sess);
+            self.udp_handler.connect(sess, datagram, stream).await
+        }
+        .boxed();
+        let res = match self.options.connect_deadline {
+            Some(budget) => {
+                deadline::scope(budget, connect)
+                    .instrument(span.clone())
+                    .await
+            }
+            None => connect.instrument(span.clone()).await,
+        };
+        record_outcome(&span, &res);
+        self.check_slow("udp", sess, start);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+    use std::pin::Pin;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use futures::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncWrite};
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Level, Metadata, Subscriber,
+    };
+
+    use super::*;
+
+    // Takes a while to connect.
+    struct Slow(Duration);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Slow {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> Result<Box<dyn ProxyStream>> {
+            tokio::time::delay_for(self.0).await;
+            Err(std::io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Slow {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> Result<Box<dyn ProxyDatagram>> {
+            tokio::time::delay_for(self.0).await;
+            Err(std::io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    struct Message(String);
+
+    impl Visit for Message {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                self.0 = format!("{:?}", value);
+            }
+        }
+    }
+
+    // Records warning events.
+    #[derive(Clone, Default)]
+    struct Warnings(Arc<Mutex<Vec<String>>>);
+
+    impl Subscriber for Warnings {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            if *event.metadata().level() == Level::WARN {
+                let mut msg = Message(String::new());
+                event.record(&mut msg);
+                self.0.lock().unwrap().push(msg.0);
+            }
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    fn handler(delay: Duration) -> Arc<Handler> {
+        Handler::with_slow_threshold(
+            "slow".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Slow(delay)),
+            Box::new(Slow(delay)),
+            Some(Duration::from_millis(50)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_slow_connect_warning() {
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let warnings = Warnings::default();
+        let _guard = tracing::subscriber::set_default(warnings.clone());
+
+        let fast = handler(Duration::from_millis(0));
+        assert!(fast.handle(&sess, None).await.is_err());
+        assert!(fast.connect(&sess, None, None).await.is_err());
+        assert!(warnings.0.lock().unwrap().is_empty());
+
+        let slow = handler(Duration::from_millis(100));
+        assert!(slow.handle(&sess, None).await.is_err());
+        assert!(slow.connect(&sess, None, None).await.is_err());
+        let warnings = warnings.0.lock().unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("[slow] slow tcp connect to example.com:443"));
+        assert!(warnings[1].starts_with("[slow] slow udp connect to example.com:443"));
+    }
+
+    // Fails to connect, recording the destination it was asked for.
+    #[derive(Clone, Default)]
+    struct Recording(Arc<Mutex<Vec<SocksAddr>>>);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Recording {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> Result<Box<dyn ProxyStream>> {
+            self.0.lock().unwrap().push(sess.destination.clone());
+            Err(std::io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    #[cfg(feature = "outbound-dns")]
+    #[tokio::test]
+    async fn test_resolve_destination() {
+        use std::net::Ipv4Addr;
+
+        use crate::proxy::dns::test_utils::upstream;
+
+        let upstream = upstream(Ipv4Addr::new(10, 0, 0, 1)).await;
+        let dns_client = Arc::new(DnsClient::new(
+            vec![upstream],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+
+        let handler = |resolver: Option<Arc<DnsClient>>| {
+            let recording = Recording::default();
+            let handler = Handler::with_options(
+                "recording".to_string(),
+                colored::Color::White,
+                ProxyHandlerType::Endpoint,
+                Box::new(recording.clone()),
+                Box::new(Slow(Duration::from_millis(0))),
+                Options {
+                    resolver,
+                    ..Default::default()
+                },
+            );
+            (handler, recording)
+        };
+
+        // Passed on as a domain by default.
+        let (remote, recording) = handler(None);
+        assert!(remote.handle(&sess, None).await.is_err());
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            vec![SocksAddr::Domain("example.com".to_string(), 443)]
+        );
+
+        let (local, recording) = handler(Some(dns_client));
+        assert!(local.handle(&sess, None).await.is_err());
+        assert_eq!(
+            *recording.0.lock().unwrap(),
+            vec![SocksAddr::Ip("10.0.0.1:443".parse().unwrap())]
+        );
+    }
+
+    // Connects to a stream discarding everything written to it, counting
+    // the bytes.
+    struct Discard(Arc<AtomicUsize>);
+
+    impl AsyncRead for Discard {
+        fn poll_read(self: Pin<&mut Self>, _: &mut Context, _: &mut [u8]) -> Poll<Result<usize>> {
+            Poll::Ready(Ok(0))
+        }
+    }
+
+    impl AsyncWrite for Discard {
+        fn poll_write(self: Pin<&mut Self>, _: &mut Context, buf: &[u8]) -> Poll<Result<usize>> {
+            self.0.fetch_add(buf.len(), Ordering::SeqCst);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl ProxyStream for Discard {}
+
+    #[async_trait]
+    impl ProxyTcpHandler for Discard {
+        fn name(&self) -> &str {
+            "discard"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> Result<Box<dyn ProxyStream>> {
+            Ok(Box::new(Discard(self.0.clone())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shared_egress_rate() {
+        use tokio::io::AsyncWriteExt;
+
+        const RATE: u64 = 256 * 1024;
+
+        // The bucket isn't refilled until the clock is advanced.
+        tokio::time::pause();
+        let egress = Arc::new(TokenBucket::new(RATE));
+        let written = Arc::new(AtomicUsize::new(0));
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let mut tasks = Vec::new();
+        for tag in &["a", "b"] {
+            let handler = Handler::with_options(
+                tag.to_string(),
+                colored::Color::White,
+                ProxyHandlerType::Endpoint,
+                Box::new(Discard(written.clone())),
+                Box::new(Slow(Duration::from_millis(0))),
+                Options {
+                    egress: Some(egress.clone()),
+                    ..Default::default()
+                },
+            );
+            let sess = sess.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut stream = handler.handle(&sess, None).await.unwrap();
+                stream.write_all(&vec![0u8; RATE as usize]).await.unwrap();
+            }));
+        }
+        // Lets both streams write until they wait for tokens.
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        // Either stream alone would be done with the bucket starting full,
+        // together they only get one second worth of tokens.
+        assert_eq!(written.load(Ordering::SeqCst), RATE as usize);
+
+        // The rest goes through a second later.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(written.load(Ordering::SeqCst), 2 * RATE as usize);
     }
 }
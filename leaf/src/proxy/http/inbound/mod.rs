@@ -0,0 +1,431 @@
+mod stream;
+
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use bytes::{Buf, BytesMut};
+use log::*;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+    stream::StreamExt,
+};
+
+use crate::{
+    app::dispatcher::Dispatcher,
+    config::{HttpInboundSettings, Inbound},
+    proxy::http::request::{invalid_request, RequestHead, MAX_HEADER_SIZE},
+    session::{Session, SocksAddr},
+    Runner,
+};
+
+pub use stream::ClientStream;
+
+const CONNECTION_ESTABLISHED: &[u8] = b"HTTP/1.1 200 Connection established\r\n\r\n";
+const PROXY_AUTHENTICATION_REQUIRED: &[u8] = b"HTTP/1.1 407 Proxy Authentication Required\r\n\
+    Proxy-Authenticate: Basic realm=\"leaf\"\r\n\
+    Content-Length: 0\r\n\r\n";
+const BAD_REQUEST: &[u8] = b"HTTP/1.1 400 Bad Request\r\n\
+    Content-Length: 0\r\n\
+    Connection: close\r\n\r\n";
+
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    // Checks the Basic credentials in the `Proxy-Authorization` header.
+    fn verify(&self, head: &RequestHead) -> bool {
+        let value = match head.header("proxy-authorization") {
+            Some(v) => v,
+            None => return false,
+        };
+        let mut parts = value.splitn(2, ' ');
+        match (parts.next(), parts.next()) {
+            (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("basic") => {
+                match base64::decode(token.trim()) {
+                    Ok(decoded) => {
+                        decoded == format!("{}:{}", self.username, self.password).as_bytes()
+                    }
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+// Splits an absolute URI into its authority and the origin-form path.
+fn split_absolute_uri(uri: &str) -> Option<(&str, &str)> {
+    match uri.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("http://") => (),
+        _ => return None,
+    }
+    let rest = &uri[7..];
+    match rest.find('/') {
+        Some(i) => Some((&rest[..i], &rest[i..])),
+        None => Some((rest, "/")),
+    }
+}
+
+// Parses `host[:port]`, IPv6 hosts are enclosed in brackets.
+fn parse_authority(authority: &str, default_port: u16) -> Option<SocksAddr> {
+    let (host, port) = if authority.starts_with('[') {
+        let end = authority.find(']')?;
+        let port = match &authority[end + 1..] {
+            "" => default_port,
+            rest if rest.starts_with(':') => rest[1..].parse().ok()?,
+            _ => return None,
+        };
+        (&authority[1..end], port)
+    } else {
+        match authority.rfind(':') {
+            Some(i) => (&authority[..i], authority[i + 1..].parse().ok()?),
+            None => (authority, default_port),
+        }
+    };
+    if host.is_empty() {
+        return None;
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        Some(SocksAddr::from((ip, port)))
+    } else {
+        Some(SocksAddr::from((host, port)))
+    }
+}
+
+enum Action {
+    // Asks for credentials, the connection is kept open if the request has
+    // no body to skip.
+    Authenticate(bool),
+    Connect(SocksAddr),
+    Forward(SocksAddr),
+    Reject,
+}
+
+fn route(head: &[u8], credentials: Option<&Credentials>) -> Action {
+    let head = match RequestHead::parse(head) {
+        Ok(head) => head,
+        Err(_) => return Action::Reject,
+    };
+    if let Some(credentials) = credentials {
+        if !credentials.verify(&head) {
+            return Action::Authenticate(matches!(head.has_body(), Ok(false)));
+        }
+    }
+    if head.method.eq_ignore_ascii_case("CONNECT") {
+        return match parse_authority(head.uri, 443) {
+            Some(destination) => Action::Connect(destination),
+            None => Action::Reject,
+        };
+    }
+    match split_absolute_uri(head.uri).and_then(|(authority, _)| parse_authority(authority, 80)) {
+        Some(destination) => Action::Forward(destination),
+        None => Action::Reject,
+    }
+}
+
+// Reads until a request head is buffered, returns the length of the head
+// including the empty line ending it.
+async fn read_head<T: AsyncRead + Unpin>(stream: &mut T, buf: &mut BytesMut) -> io::Result<usize> {
+    loop {
+        // Tolerate stray line breaks between requests.
+        while buf.first().map_or(false, |b| *b == b'\r' || *b == b'\n') {
+            buf.advance(1);
+        }
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(i + 4);
+        }
+        if buf.len() > MAX_HEADER_SIZE {
+            return Err(invalid_request("header too large"));
+        }
+        let mut chunk = [0u8; 2 * 1024];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Serves an HTTP proxy client connection.
+pub async fn serve<S>(
+    mut stream: S,
+    source: SocketAddr,
+    credentials: Option<Arc<Credentials>>,
+    dispatcher: Arc<Dispatcher>,
+) -> io::Result<()>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    let mut buf = BytesMut::new();
+    loop {
+        let n = read_head(&mut stream, &mut buf).await?;
+        match route(&buf[..n - 4], credentials.as_deref()) {
+            Action::Authenticate(keep_alive) => {
+                stream.write_all(PROXY_AUTHENTICATION_REQUIRED).await?;
+                if !keep_alive {
+                    return Ok(());
+                }
+                buf.advance(n);
+            }
+            Action::Connect(destination) => {
+                stream.write_all(CONNECTION_ESTABLISHED).await?;
+                buf.advance(n);
+                let mut sess = Session {
+                    source,
+                    destination,
+                };
+                return dispatcher
+                    .dispatch_tcp(&mut sess, ClientStream::tunnel(stream, buf))
+                    .await;
+            }
+            Action::Forward(destination) => {
+                // The head is left in the buffer, it's rewritten along with
+                // the requests following it.
+                let mut sess = Session {
+                    source,
+                    destination: destination.clone(),
+                };
+                return dispatcher
+                    .dispatch_tcp(&mut sess, ClientStream::forward(stream, destination, buf))
+                    .await;
+            }
+            Action::Reject => {
+                stream.write_all(BAD_REQUEST).await?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Serves `CONNECT` tunnels and forwards requests in absolute-URI form.
+///
+/// A forwarded connection is relayed to the origin of its first request,
+/// keep-alive requests for other origins close the connection.
+pub fn new(inbound: Inbound, dispatcher: Arc<Dispatcher>) -> Result<Runner> {
+    let settings = protobuf::parse_from_bytes::<HttpInboundSettings>(&inbound.settings)?;
+    let credentials = if settings.username.is_empty() {
+        None
+    } else {
+        Some(Arc::new(Credentials {
+            username: settings.username,
+            password: settings.password,
+        }))
+    };
+    let t = async move {
+        let mut listener =
+            TcpListener::bind(format!("{}:{}", inbound.listen, inbound.port).as_str())
+                .await
+                .unwrap();
+        info!(
+            "http inbound listening tcp {}:{}",
+            inbound.listen, inbound.port
+        );
+        while let Some(stream) = listener.next().await {
+            if let Ok(stream) = stream {
+                let credentials = credentials.clone();
+                let dispatcher = dispatcher.clone();
+                tokio::spawn(async move {
+                    let source = stream
+                        .peer_addr()
+                        .unwrap_or_else(|_| "0.0.0.0:0".parse().unwrap());
+                    // dispatch err logging was handled in dispatcher
+                    if let Err(err) = serve(stream, source, credentials, dispatcher).await {
+                        debug!("http inbound: {}", err);
+                    }
+                });
+            }
+        }
+    };
+
+    Ok(Box::pin(t))
+}
+
+#[cfg(all(test, feature = "outbound-direct"))]
+mod tests {
+    use protobuf::Message;
+    use tokio::net::TcpStream;
+
+    use super::*;
+    use crate::app::{handler_manager::HandlerManager, router::Router};
+    use crate::common::shutdown::ShutdownToken;
+    use crate::config;
+
+    fn dispatcher() -> Arc<Dispatcher> {
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "direct".to_string();
+        outbound.protocol = "direct".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let router = Router::new(&protobuf::RepeatedField::new());
+        Arc::new(Dispatcher::new(handler_manager, router))
+    }
+
+    // Serves every request it reads with a two-byte body, returns the
+    // request heads through the handle.
+    async fn origin(num_requests: usize) -> (SocketAddr, tokio::task::JoinHandle<String>) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = BytesMut::new();
+            let mut heads = String::new();
+            for _ in 0..num_requests {
+                let n = read_head(&mut stream, &mut buf).await.unwrap();
+                heads.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+                buf.advance(n);
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                    .await
+                    .unwrap();
+            }
+            heads
+        });
+        (addr, server)
+    }
+
+    // Runs the inbound until `client` is done with a connection to it.
+    async fn run<F, Fut>(settings: HttpInboundSettings, client: F)
+    where
+        F: FnOnce(TcpStream) -> Fut + 'static,
+        Fut: std::future::Future<Output = ()> + 'static,
+    {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut inbound = Inbound::new();
+        inbound.protocol = "http".to_string();
+        inbound.listen = "127.0.0.1".to_string();
+        inbound.port = port as u32;
+        inbound.settings = settings.write_to_bytes().unwrap();
+        let runner = new(inbound, dispatcher()).unwrap();
+        let client = async move {
+            let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            client(stream).await;
+        };
+        let res = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            futures::future::select(runner, Box::pin(client)),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(res, futures::future::Either::Right(_)));
+    }
+
+    const RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+    async fn expect(stream: &mut TcpStream, expected: &[u8]) {
+        let mut buf = vec![0u8; expected.len()];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, expected);
+    }
+
+    #[tokio::test]
+    async fn test_connect() {
+        let (addr, server) = origin(1).await;
+        run(HttpInboundSettings::new(), move |mut stream| async move {
+            stream
+                .write_all(
+                    format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n\r\n", addr, addr).as_bytes(),
+                )
+                .await
+                .unwrap();
+            expect(&mut stream, CONNECTION_ESTABLISHED).await;
+            // Requests in the tunnel are relayed untouched.
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+                .await
+                .unwrap();
+            expect(&mut stream, RESPONSE).await;
+        })
+        .await;
+        assert_eq!(
+            server.await.unwrap(),
+            "GET / HTTP/1.1\r\nHost: example.com\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_get() {
+        let (addr, server) = origin(2).await;
+        run(HttpInboundSettings::new(), move |mut stream| async move {
+            let request = format!(
+                "GET http://{}/index.html HTTP/1.1\r\n\
+                Host: {}\r\n\
+                Proxy-Connection: keep-alive\r\n\r\n",
+                addr, addr
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            expect(&mut stream, RESPONSE).await;
+            let request = format!("GET http://{}/a?b=1 HTTP/1.1\r\n\r\n", addr);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            expect(&mut stream, RESPONSE).await;
+        })
+        .await;
+        let expected = format!(
+            "GET /index.html HTTP/1.1\r\n\
+            Host: {}\r\n\r\n\
+            GET /a?b=1 HTTP/1.1\r\n\
+            Host: {}\r\n\r\n",
+            addr, addr
+        );
+        assert_eq!(server.await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_authorization() {
+        let (addr, server) = origin(1).await;
+        let mut settings = HttpInboundSettings::new();
+        settings.username = "user".to_string();
+        settings.password = "pass".to_string();
+        run(settings, move |mut stream| async move {
+            let connect = format!("CONNECT {} HTTP/1.1\r\n", addr);
+            for authorization in &["", "Proxy-Authorization: Basic dXNlcjp3cm9uZw==\r\n"] {
+                let request = format!("{}{}\r\n", connect, authorization);
+                stream.write_all(request.as_bytes()).await.unwrap();
+                expect(&mut stream, PROXY_AUTHENTICATION_REQUIRED).await;
+            }
+            let request = format!("{}Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n", connect);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            expect(&mut stream, CONNECTION_ESTABLISHED).await;
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n")
+                .await
+                .unwrap();
+            expect(&mut stream, RESPONSE).await;
+        })
+        .await;
+        assert!(server.await.is_ok());
+    }
+
+    #[test]
+    fn test_route() {
+        assert!(matches!(
+            route(b"GET / HTTP/1.1\r\nHost: example.com", None),
+            Action::Reject
+        ));
+        assert!(matches!(
+            route(b"CONNECT [::1]:8443 HTTP/1.1", None),
+            Action::Connect(SocksAddr::Ip(addr)) if addr == "[::1]:8443".parse::<SocketAddr>().unwrap()
+        ));
+        assert!(matches!(
+            route(b"GET http://example.com/ HTTP/1.1", None),
+            Action::Forward(SocksAddr::Domain(ref host, 80)) if host == "example.com"
+        ));
+    }
+}
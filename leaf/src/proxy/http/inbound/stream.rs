@@ -0,0 +1,213 @@
+use std::{cmp::min, io, pin::Pin};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    proxy::http::request::{invalid_request, RequestFramer, RequestHead},
+    session::SocksAddr,
+};
+
+struct Origin {
+    destination: SocksAddr,
+    framer: RequestFramer,
+}
+
+/// A client connection accepted by the HTTP proxy.
+///
+/// For a forwarded request, request lines in absolute-URI form
+/// (`GET http://host/path HTTP/1.1`) are rewritten into origin-form
+/// (`GET /path HTTP/1.1`) and the proxy headers are dropped. The connection
+/// is relayed to a single destination, so a keep-alive request for another
+/// origin fails the stream. Responses are relayed untouched.
+pub struct ClientStream<T> {
+    inner: T,
+    origin: Option<Origin>,
+    // Bytes read from the client ahead of time, not decoded yet.
+    pending: BytesMut,
+    read_buf: BytesMut,
+}
+
+impl<T> ClientStream<T> {
+    /// A tunnel established by `CONNECT`, `pending` holds the bytes already
+    /// read past the request head.
+    pub fn tunnel(inner: T, pending: BytesMut) -> Self {
+        ClientStream {
+            inner,
+            origin: None,
+            pending,
+            read_buf: BytesMut::new(),
+        }
+    }
+
+    /// Requests forwarded to `destination`, `pending` holds the bytes
+    /// already read starting at the first request head.
+    pub fn forward(inner: T, destination: SocksAddr, pending: BytesMut) -> Self {
+        ClientStream {
+            inner,
+            origin: Some(Origin {
+                destination,
+                framer: RequestFramer::default(),
+            }),
+            pending,
+            read_buf: BytesMut::new(),
+        }
+    }
+
+    // Feeds bytes read from the client through the request parser,
+    // appending the bytes to be relayed to the read buffer.
+    fn decode(&mut self, buf: &[u8]) -> io::Result<()> {
+        match &mut self.origin {
+            Some(origin) => {
+                let Origin {
+                    destination,
+                    framer,
+                } = origin;
+                framer.feed(buf, &mut self.read_buf, |head, out| {
+                    rewrite_head(head, destination, out)
+                })
+            }
+            None => {
+                self.read_buf.put_slice(buf);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn rewrite_head(head: &RequestHead, destination: &SocksAddr, out: &mut BytesMut) -> io::Result<()> {
+    let (authority, path) = match super::split_absolute_uri(head.uri) {
+        Some((authority, path)) => {
+            if super::parse_authority(authority, 80).as_ref() != Some(destination) {
+                return Err(invalid_request("request for another origin"));
+            }
+            (Some(authority), path)
+        }
+        None if head.uri.starts_with('/') => (None, head.uri),
+        None => return Err(invalid_request("bad request uri")),
+    };
+    out.put_slice(format!("{} {} {}\r\n", head.method, path, head.version).as_bytes());
+    for header in &head.headers {
+        if header.name.eq_ignore_ascii_case("proxy-authorization")
+            || header.name.eq_ignore_ascii_case("proxy-connection")
+        {
+            continue;
+        }
+        out.put_slice(header.line.as_bytes());
+        out.put_slice(b"\r\n");
+    }
+    if head.header("host").is_none() {
+        if let Some(authority) = authority {
+            out.put_slice(format!("Host: {}\r\n", authority).as_bytes());
+        }
+    }
+    out.put_slice(b"\r\n");
+    Ok(())
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ClientStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            if !self.read_buf.is_empty() {
+                let n = min(buf.len(), self.read_buf.len());
+                buf[..n].copy_from_slice(&self.read_buf[..n]);
+                self.read_buf.advance(n);
+                return Poll::Ready(Ok(n));
+            }
+            if !self.pending.is_empty() {
+                let pending = self.pending.split();
+                self.decode(&pending)?;
+                continue;
+            }
+            let n = ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+            if n == 0 {
+                return Poll::Ready(Ok(0));
+            }
+            // Decoded into the read buffer, copied back on the next turn.
+            self.decode(&buf[..n])?;
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ClientStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    async fn read_all(mut stream: ClientStream<&[u8]>) -> io::Result<String> {
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await?;
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_forward_keep_alive() {
+        let pending = BytesMut::from(
+            &b"POST http://example.com/submit HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\
+            Content-Length: 5\r\n\r\nhel"[..],
+        );
+        let rest: &[u8] = b"lo\
+            GET http://example.com:80/index.html HTTP/1.1\r\n\
+            Proxy-Connection: keep-alive\r\n\r\n";
+        let stream = ClientStream::forward(
+            rest,
+            SocksAddr::Domain("example.com".to_string(), 80),
+            pending,
+        );
+        assert_eq!(
+            read_all(stream).await.unwrap(),
+            "POST /submit HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            Content-Length: 5\r\n\r\nhello\
+            GET /index.html HTTP/1.1\r\n\
+            Host: example.com:80\r\n\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forward_other_origin() {
+        let rest: &[u8] = b"GET http://example.com/ HTTP/1.1\r\n\r\n\
+            GET http://example.org/ HTTP/1.1\r\n\r\n";
+        let stream = ClientStream::forward(
+            rest,
+            SocksAddr::Domain("example.com".to_string(), 80),
+            BytesMut::new(),
+        );
+        assert!(read_all(stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_pending() {
+        let stream = ClientStream::tunnel(&b" world"[..], BytesMut::from(&b"hello"[..]));
+        assert_eq!(read_all(stream).await.unwrap(), "hello world");
+    }
+}
@@ -1,3 +1,7 @@
+#[cfg(feature = "inbound-http")]
 pub mod inbound;
+#[cfg(feature = "outbound-http")]
+pub mod outbound;
+pub mod request;
 
 pub static NAME: &str = "http";
@@ -0,0 +1,343 @@
+use std::{io, net::SocketAddr, pin::Pin, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::{BufMut, BytesMut};
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    common::dns_client::DnsClient,
+    proxy::{
+        http::request::{RequestFramer, RequestHead},
+        stream::SimpleStream,
+        ProxyStream, ProxyTcpHandler,
+    },
+    session::Session,
+};
+
+enum WriteState {
+    Waiting,
+    Pending(usize),
+}
+
+/// A stream relaying plain HTTP requests to a forwarding proxy.
+///
+/// Request lines in origin-form (`GET /path HTTP/1.1`) are rewritten into
+/// absolute-URI form (`GET http://host/path HTTP/1.1`). Request bodies are
+/// tracked by `Content-Length` or chunked encoding so that every request on
+/// a keep-alive connection is rewritten. Responses are relayed untouched.
+pub struct ForwardStream<T> {
+    inner: T,
+    target: String,
+    authorization: Option<String>,
+    framer: RequestFramer,
+    write_buf: BytesMut,
+    write_state: WriteState,
+}
+
+impl<T> ForwardStream<T> {
+    /// `target` is the authority used for requests without a `Host` header.
+    pub fn new(inner: T, target: String, authorization: Option<String>) -> Self {
+        ForwardStream {
+            inner,
+            target,
+            authorization,
+            framer: RequestFramer::default(),
+            write_buf: BytesMut::new(),
+            write_state: WriteState::Waiting,
+        }
+    }
+
+    // Feeds outgoing bytes through the request parser, appending the bytes
+    // to be sent upstream to the write buffer.
+    fn encode(&mut self, buf: &[u8]) -> io::Result<()> {
+        let ForwardStream {
+            target,
+            authorization,
+            framer,
+            write_buf,
+            ..
+        } = self;
+        framer.feed(buf, write_buf, |head, out| {
+            rewrite_head(head, target, authorization.as_deref(), out)
+        })
+    }
+}
+
+fn rewrite_head(
+    head: &RequestHead,
+    target: &str,
+    authorization: Option<&str>,
+    out: &mut BytesMut,
+) -> io::Result<()> {
+    if head.uri.starts_with('/') {
+        let authority = head.header("host").unwrap_or(target);
+        out.put_slice(
+            format!(
+                "{} http://{}{} {}\r\n",
+                head.method, authority, head.uri, head.version
+            )
+            .as_bytes(),
+        );
+    } else {
+        out.put_slice(head.request_line.as_bytes());
+        out.put_slice(b"\r\n");
+    }
+    for header in &head.headers {
+        out.put_slice(header.line.as_bytes());
+        out.put_slice(b"\r\n");
+    }
+    if head.header("proxy-authorization").is_none() {
+        if let Some(auth) = authorization {
+            out.put_slice(format!("Proxy-Authorization: {}\r\n", auth).as_bytes());
+        }
+    }
+    out.put_slice(b"\r\n");
+    Ok(())
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ForwardStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ForwardStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match self.write_state {
+                WriteState::Waiting => {
+                    if buf.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    self.encode(buf)?;
+                    if self.write_buf.is_empty() {
+                        // Nothing to send yet, e.g. a partial header.
+                        return Poll::Ready(Ok(buf.len()));
+                    }
+                    self.write_state = WriteState::Pending(buf.len());
+                }
+                WriteState::Pending(consumed) => {
+                    let me = &mut *self;
+                    // Same as other stream wrappers, the caller is expected to
+                    // retry with the same buffer upon pending.
+                    let nw = ready!(Pin::new(&mut me.inner).poll_write_buf(cx, &mut me.write_buf))?;
+                    if nw == 0 {
+                        return Err(io::Error::from(io::ErrorKind::WriteZero)).into();
+                    }
+                    if me.write_buf.is_empty() {
+                        me.write_state = WriteState::Waiting;
+                        return Poll::Ready(Ok(consumed));
+                    }
+                }
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+pub struct Handler {
+    pub address: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub bind_addr: SocketAddr,
+    pub dns_client: Arc<DnsClient>,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        Some((self.address.clone(), self.port, self.bind_addr))
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        let stream = if let Some(stream) = stream {
+            stream
+        } else {
+            self.dial_tcp_stream(
+                self.dns_client.clone(),
+                &self.bind_addr,
+                &self.address,
+                &self.port,
+            )
+            .await?
+        };
+        let mut target = sess.destination.to_string();
+        if sess.destination.port() == 80 {
+            target.truncate(target.len() - 3);
+        }
+        let authorization = super::basic_authorization(&self.username, &self.password);
+        Ok(Box::new(SimpleStream(ForwardStream::new(
+            stream,
+            target,
+            authorization,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    // Runs a fake forwarding proxy expecting `expected` and answering every
+    // request with a short response, then writes `requests` through the
+    // handler in chunks of `chunk_size` bytes.
+    async fn relay(
+        destination: SocksAddr,
+        username: &str,
+        requests: &[u8],
+        chunk_size: usize,
+        expected: &'static str,
+        num_requests: usize,
+    ) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; expected.len()];
+            stream.read_exact(&mut buf).await.unwrap();
+            for _ in 0..num_requests {
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                    .await
+                    .unwrap();
+            }
+            String::from_utf8(buf).unwrap()
+        });
+        let handler = Handler {
+            address: "127.0.0.1".to_string(),
+            port,
+            username: username.to_string(),
+            password: "pass".to_string(),
+            bind_addr: "0.0.0.0:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination,
+        };
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        for chunk in requests.chunks(chunk_size) {
+            stream.write_all(chunk).await.unwrap();
+        }
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let mut buf = vec![0u8; response.len() * num_requests];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, response.repeat(num_requests));
+        assert_eq!(server.await.unwrap(), expected);
+    }
+
+    const KEEP_ALIVE_REQUESTS: &[u8] = b"POST /submit?a=1 HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        Content-Length: 5\r\n\r\n\
+        hello\
+        POST /upload HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        Transfer-Encoding: chunked\r\n\r\n\
+        3\r\nGET\r\n0\r\n\r\n\
+        GET /index.html HTTP/1.1\r\n\
+        Host: example.com\r\n\r\n";
+
+    const KEEP_ALIVE_EXPECTED: &str = "POST http://example.com/submit?a=1 HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        Content-Length: 5\r\n\
+        Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n\
+        hello\
+        POST http://example.com/upload HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        Transfer-Encoding: chunked\r\n\
+        Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n\
+        3\r\nGET\r\n0\r\n\r\n\
+        GET http://example.com/index.html HTTP/1.1\r\n\
+        Host: example.com\r\n\
+        Proxy-Authorization: Basic dXNlcjpwYXNz\r\n\r\n";
+
+    #[tokio::test]
+    async fn test_forward_keep_alive() {
+        relay(
+            SocksAddr::Domain("example.com".to_string(), 80),
+            "user",
+            KEEP_ALIVE_REQUESTS,
+            KEEP_ALIVE_REQUESTS.len(),
+            KEEP_ALIVE_EXPECTED,
+            3,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_forward_split_writes() {
+        relay(
+            SocksAddr::Domain("example.com".to_string(), 80),
+            "user",
+            KEEP_ALIVE_REQUESTS,
+            1,
+            KEEP_ALIVE_EXPECTED,
+            3,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_forward_without_host_header() {
+        relay(
+            SocksAddr::Domain("example.com".to_string(), 8080),
+            "",
+            b"GET / HTTP/1.0\r\n\r\n",
+            64,
+            "GET http://example.com:8080/ HTTP/1.0\r\n\r\n",
+            1,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_forward_absolute_uri_untouched() {
+        relay(
+            SocksAddr::Domain("example.com".to_string(), 80),
+            "",
+            b"GET http://example.com/a HTTP/1.1\r\nHost: example.com\r\n\r\n",
+            64,
+            "GET http://example.com/a HTTP/1.1\r\nHost: example.com\r\n\r\n",
+            1,
+        )
+        .await;
+    }
+
+    #[test]
+    fn test_forward_bad_request_line() {
+        let mut s = ForwardStream::new(Vec::<u8>::new(), "example.com".to_string(), None);
+        assert!(s.encode(b"\x16\x03\x01\x02\x00\r\n\r\n").is_err());
+    }
+}
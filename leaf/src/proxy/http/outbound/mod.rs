@@ -0,0 +1,19 @@
+mod forward;
+mod tcp;
+mod udp;
+
+pub use forward::{ForwardStream, Handler as ForwardHandler};
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
+
+pub use super::NAME;
+
+fn basic_authorization(username: &str, password: &str) -> Option<String> {
+    if username.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Basic {}",
+        base64::encode(format!("{}:{}", username, password))
+    ))
+}
@@ -0,0 +1,236 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::{
+    common::dns_client::DnsClient,
+    proxy::{ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+// Upper bound on the size of the response header from the proxy.
+const MAX_RESPONSE_HEADER_SIZE: usize = 8 * 1024;
+
+pub struct Handler {
+    pub address: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub bind_addr: SocketAddr,
+    pub dns_client: Arc<DnsClient>,
+}
+
+impl Handler {
+    fn connect_request(&self, sess: &Session) -> String {
+        let target = sess.destination.to_string();
+        let mut req = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", target, target);
+        if let Some(auth) = super::basic_authorization(&self.username, &self.password) {
+            req.push_str(&format!("Proxy-Authorization: {}\r\n", auth));
+        }
+        req.push_str("\r\n");
+        req
+    }
+}
+
+// Reads the response header byte by byte so that no tunneled data
+// following the header is consumed.
+async fn read_response_header<R: AsyncRead + Unpin>(r: &mut R) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(256);
+    loop {
+        let b = r.read_u8().await?;
+        buf.push(b);
+        if buf.ends_with(b"\r\n\r\n") {
+            return Ok(buf);
+        }
+        if buf.len() >= MAX_RESPONSE_HEADER_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "http proxy response header too large",
+            ));
+        }
+    }
+}
+
+fn invalid_response() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid http proxy response")
+}
+
+fn status_line(header: &[u8]) -> io::Result<&str> {
+    let line = header
+        .split(|b| *b == b'\n')
+        .next()
+        .ok_or_else(invalid_response)?;
+    let line = std::str::from_utf8(line).map_err(|_| invalid_response())?;
+    Ok(line.trim_end())
+}
+
+fn parse_status_code(header: &[u8]) -> io::Result<u16> {
+    let invalid = invalid_response;
+    let line = status_line(header)?;
+    let mut parts = line.splitn(3, ' ');
+    let version = parts.next().ok_or_else(invalid)?;
+    if !version.starts_with("HTTP/1.") {
+        return Err(invalid());
+    }
+    let code = parts.next().ok_or_else(invalid)?;
+    if code.len() != 3 {
+        return Err(invalid());
+    }
+    code.parse::<u16>().map_err(|_| invalid())
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        Some((self.address.clone(), self.port, self.bind_addr))
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        let mut stream = if let Some(stream) = stream {
+            stream
+        } else {
+            self.dial_tcp_stream(
+                self.dns_client.clone(),
+                &self.bind_addr,
+                &self.address,
+                &self.port,
+            )
+            .await?
+        };
+        stream
+            .write_all(self.connect_request(sess).as_bytes())
+            .await?;
+        let header = read_response_header(&mut stream).await?;
+        match parse_status_code(&header)? {
+            // Only 200 establishes the tunnel.
+            200 => Ok(stream),
+            407 => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "http proxy authentication required",
+            )),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("http proxy connect failed: {}", status_line(&header)?),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    async fn run(
+        username: &str,
+        password: &str,
+        response: &'static [u8],
+    ) -> (io::Result<Box<dyn ProxyStream>>, String) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let header = read_response_header(&mut stream).await.unwrap();
+            stream.write_all(response).await.unwrap();
+            let mut buf = [0u8; 4];
+            if stream.read_exact(&mut buf).await.is_ok() {
+                stream.write_all(&buf).await.unwrap();
+            }
+            String::from_utf8(header).unwrap()
+        });
+        let handler = Handler {
+            address: "127.0.0.1".to_string(),
+            port,
+            username: username.to_string(),
+            password: password.to_string(),
+            bind_addr: "0.0.0.0:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let res = handler.handle(&sess, None).await;
+        let res = match res {
+            Ok(mut stream) => {
+                stream.write_all(b"ping").await.unwrap();
+                let mut buf = [0u8; 4];
+                stream.read_exact(&mut buf).await.unwrap();
+                assert_eq!(&buf, b"ping");
+                Ok(stream)
+            }
+            Err(e) => Err(e),
+        };
+        let request = server.await.unwrap();
+        (res, request)
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_success() {
+        let (res, request) = run(
+            "user",
+            "pass",
+            b"HTTP/1.1 200 Connection established\r\nProxy-Agent: test\r\n\r\n",
+        )
+        .await;
+        assert!(res.is_ok());
+        assert!(request.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Host: example.com:443\r\n"));
+        // base64("user:pass")
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_no_auth() {
+        let (res, request) = run("", "", b"HTTP/1.0 200 OK\r\n\r\n").await;
+        assert!(res.is_ok());
+        assert!(!request.contains("Proxy-Authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_auth_required() {
+        let (res, _) = run(
+            "user",
+            "wrong",
+            b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n",
+        )
+        .await;
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(err.to_string().contains("authentication required"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_other_success_status() {
+        let (res, _) = run("", "", b"HTTP/1.1 204 No Content\r\n\r\n").await;
+        let err = res.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(err.to_string().contains("HTTP/1.1 204 No Content"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_malformed_response() {
+        let (res, _) = run("", "", b"SSH-2.0-OpenSSH_8.2\r\n\r\n").await;
+        assert_eq!(res.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_status_code() {
+        assert_eq!(parse_status_code(b"HTTP/1.1 200 OK\r\n\r\n").unwrap(), 200);
+        assert_eq!(parse_status_code(b"HTTP/1.1 502\r\n\r\n").unwrap(), 502);
+        assert!(parse_status_code(b"HTTP/1.1 2000 OK\r\n\r\n").is_err());
+        assert!(parse_status_code(b"HTTP/2 200 OK\r\n\r\n").is_err());
+        assert!(parse_status_code(b"\r\n\r\n").is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use std::{cmp::min, io};
+
+use bytes::{BufMut, BytesMut};
+
+// Upper bound on the size of a single request header or chunk-size line.
+pub const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+pub fn invalid_request(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid http request: {}", msg),
+    )
+}
+
+pub struct HeaderLine<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    /// The header line as received, without the line break.
+    pub line: &'a str,
+}
+
+/// The head of an HTTP/1.x request.
+pub struct RequestHead<'a> {
+    pub request_line: &'a str,
+    pub method: &'a str,
+    pub uri: &'a str,
+    pub version: &'a str,
+    pub headers: Vec<HeaderLine<'a>>,
+}
+
+impl<'a> RequestHead<'a> {
+    /// Parses a request head, `buf` excludes the empty line ending it.
+    pub fn parse(buf: &'a [u8]) -> io::Result<Self> {
+        let head = std::str::from_utf8(buf).map_err(|_| invalid_request("non-utf8 header"))?;
+        let mut lines = head.split("\r\n");
+        let request_line = lines.next().unwrap_or_default();
+        let mut parts = request_line.splitn(3, ' ');
+        let (method, uri, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(m), Some(u), Some(v)) if v.starts_with("HTTP/1.") => (m, u, v),
+            _ => return Err(invalid_request("bad request line")),
+        };
+        let mut headers = Vec::new();
+        for line in lines {
+            let mut kv = line.splitn(2, ':');
+            let name = kv.next().unwrap_or_default().trim();
+            let value = match kv.next() {
+                Some(v) => v.trim(),
+                None => return Err(invalid_request("bad header line")),
+            };
+            headers.push(HeaderLine { name, value, line });
+        }
+        Ok(RequestHead {
+            request_line,
+            method,
+            uri,
+            version,
+            headers,
+        })
+    }
+
+    /// Returns the value of the first header named `name`.
+    pub fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value)
+    }
+
+    fn body_state(&self) -> io::Result<BodyState> {
+        if let Some(te) = self.header("transfer-encoding") {
+            if te.to_ascii_lowercase().contains("chunked") {
+                return Ok(BodyState::ChunkSize);
+            }
+        }
+        match self.header("content-length") {
+            Some(v) => match v.parse::<u64>() {
+                Ok(0) => Ok(BodyState::Header),
+                Ok(n) => Ok(BodyState::Fixed(n)),
+                Err(_) => Err(invalid_request("bad content length")),
+            },
+            None => Ok(BodyState::Header),
+        }
+    }
+
+    /// Whether a body follows the head.
+    pub fn has_body(&self) -> io::Result<bool> {
+        Ok(!matches!(self.body_state()?, BodyState::Header))
+    }
+}
+
+enum BodyState {
+    Header,
+    Fixed(u64),
+    ChunkSize,
+    ChunkData(u64),
+    ChunkDataEnd(usize),
+    Trailer,
+}
+
+/// Splits a stream of requests on a keep-alive connection.
+///
+/// Request bodies are tracked by `Content-Length` or chunked encoding, so
+/// that the head of every request can be rewritten, bodies are relayed
+/// untouched.
+pub struct RequestFramer {
+    body_state: BodyState,
+    line_buf: BytesMut,
+}
+
+impl Default for RequestFramer {
+    fn default() -> Self {
+        RequestFramer {
+            body_state: BodyState::Header,
+            line_buf: BytesMut::new(),
+        }
+    }
+}
+
+impl RequestFramer {
+    fn push_line_byte(&mut self, b: u8) -> io::Result<()> {
+        self.line_buf.put_u8(b);
+        if self.line_buf.len() > MAX_HEADER_SIZE {
+            return Err(invalid_request("header too large"));
+        }
+        Ok(())
+    }
+
+    /// Feeds `buf` through the parser, appending the bytes to be relayed to
+    /// `out`. Every complete request head is handed to `rewrite`, which
+    /// appends the head to be relayed in its place.
+    pub fn feed<F>(&mut self, buf: &[u8], out: &mut BytesMut, mut rewrite: F) -> io::Result<()>
+    where
+        F: FnMut(&RequestHead, &mut BytesMut) -> io::Result<()>,
+    {
+        let mut pos = 0;
+        while pos < buf.len() {
+            match self.body_state {
+                BodyState::Header => {
+                    let b = buf[pos];
+                    pos += 1;
+                    // Tolerate stray line breaks between requests.
+                    if self.line_buf.is_empty() && (b == b'\r' || b == b'\n') {
+                        continue;
+                    }
+                    self.push_line_byte(b)?;
+                    if self.line_buf.ends_with(b"\r\n\r\n") {
+                        let head = RequestHead::parse(&self.line_buf[..self.line_buf.len() - 4])?;
+                        let body_state = head.body_state()?;
+                        rewrite(&head, out)?;
+                        self.body_state = body_state;
+                        self.line_buf.clear();
+                    }
+                }
+                BodyState::Fixed(remaining) | BodyState::ChunkData(remaining) => {
+                    let n = min(remaining, (buf.len() - pos) as u64) as usize;
+                    out.put_slice(&buf[pos..pos + n]);
+                    pos += n;
+                    let remaining = remaining - n as u64;
+                    self.body_state = match self.body_state {
+                        BodyState::Fixed(_) if remaining == 0 => BodyState::Header,
+                        BodyState::Fixed(_) => BodyState::Fixed(remaining),
+                        _ if remaining == 0 => BodyState::ChunkDataEnd(2),
+                        _ => BodyState::ChunkData(remaining),
+                    };
+                }
+                BodyState::ChunkDataEnd(remaining) => {
+                    out.put_u8(buf[pos]);
+                    pos += 1;
+                    self.body_state = if remaining == 1 {
+                        BodyState::ChunkSize
+                    } else {
+                        BodyState::ChunkDataEnd(remaining - 1)
+                    };
+                }
+                BodyState::ChunkSize => {
+                    let b = buf[pos];
+                    pos += 1;
+                    out.put_u8(b);
+                    self.push_line_byte(b)?;
+                    if self.line_buf.ends_with(b"\r\n") {
+                        let line = std::str::from_utf8(&self.line_buf[..self.line_buf.len() - 2])
+                            .map_err(|_| invalid_request("bad chunk size"))?;
+                        let size = line.split(';').next().unwrap_or_default().trim();
+                        let size = u64::from_str_radix(size, 16)
+                            .map_err(|_| invalid_request("bad chunk size"))?;
+                        self.line_buf.clear();
+                        self.body_state = if size == 0 {
+                            BodyState::Trailer
+                        } else {
+                            BodyState::ChunkData(size)
+                        };
+                    }
+                }
+                BodyState::Trailer => {
+                    let b = buf[pos];
+                    pos += 1;
+                    out.put_u8(b);
+                    self.push_line_byte(b)?;
+                    if self.line_buf.ends_with(b"\r\n") {
+                        if self.line_buf.len() == 2 {
+                            self.body_state = BodyState::Header;
+                        }
+                        self.line_buf.clear();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
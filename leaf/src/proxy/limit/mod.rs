@@ -0,0 +1,13 @@
+pub mod stream;
+#[cfg(feature = "outbound-limit")]
+pub mod tcp;
+#[cfg(feature = "outbound-limit")]
+pub mod udp;
+
+pub use stream::{LimitedStream, TokenBucket};
+#[cfg(feature = "outbound-limit")]
+pub use tcp::Handler as TcpHandler;
+#[cfg(feature = "outbound-limit")]
+pub use udp::Handler as UdpHandler;
+
+pub static NAME: &str = "limit";
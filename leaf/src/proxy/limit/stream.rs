@@ -0,0 +1,308 @@
+use std::cmp::min;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::{delay_for, Delay, Instant};
+
+use crate::proxy::ProxyStream;
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+/// A token bucket refilled at a constant rate, holding at most one second
+/// worth of tokens.
+pub struct TokenBucket {
+    rate: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket refilled at `rate` bytes per second.
+    pub fn new(rate: u64) -> Self {
+        TokenBucket {
+            rate: rate as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: rate as f64,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes up to `want` tokens, or returns the time to wait for them if
+    /// the bucket is empty.
+    fn take(&self, want: usize) -> Result<usize, Duration> {
+        let mut b = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(b.last).as_secs_f64();
+        b.tokens = (b.tokens + elapsed * self.rate).min(self.rate);
+        b.last = now;
+        if b.tokens >= 1.0 {
+            let n = min(want, b.tokens as usize);
+            b.tokens -= n as f64;
+            Ok(n)
+        } else {
+            // Waits for enough tokens so the caller doesn't end up moving
+            // data byte by byte.
+            let need = (want as f64).min(self.rate) - b.tokens;
+            Err(Duration::from_secs_f64(need / self.rate))
+        }
+    }
+
+    /// Returns tokens taken but not used.
+    fn refund(&self, n: usize) {
+        if n > 0 {
+            let mut b = self.bucket.lock().unwrap();
+            b.tokens = (b.tokens + n as f64).min(self.rate);
+        }
+    }
+}
+
+/// Throttles one direction of a stream, through a per-connection bucket and
+/// optionally a bucket shared by many connections.
+struct Limiter {
+    buckets: Vec<Arc<TokenBucket>>,
+    delay: Option<Delay>,
+}
+
+impl Limiter {
+    fn new(rate: Option<u64>, shared: Option<Arc<TokenBucket>>) -> Self {
+        let mut buckets = Vec::new();
+        if let Some(rate) = rate {
+            buckets.push(Arc::new(TokenBucket::new(rate)));
+        }
+        buckets.extend(shared);
+        Limiter {
+            buckets,
+            delay: None,
+        }
+    }
+
+    // Resolves to the number of bytes allowed to move, at most `want`.
+    fn poll_acquire(&mut self, cx: &mut Context, want: usize) -> Poll<usize> {
+        loop {
+            if let Some(delay) = self.delay.as_mut() {
+                ready!(Pin::new(delay).poll(cx));
+                self.delay = None;
+            }
+            let mut allowed = want;
+            let mut wait = None;
+            for (i, bucket) in self.buckets.iter().enumerate() {
+                match bucket.take(allowed) {
+                    Ok(n) => {
+                        // Gives back what the previous buckets granted in
+                        // excess.
+                        for b in &self.buckets[..i] {
+                            b.refund(allowed - n);
+                        }
+                        allowed = n;
+                    }
+                    Err(d) => {
+                        for b in &self.buckets[..i] {
+                            b.refund(allowed);
+                        }
+                        wait = Some(d);
+                        break;
+                    }
+                }
+            }
+            match wait {
+                Some(d) => self.delay = Some(delay_for(d)),
+                None => return Poll::Ready(allowed),
+            }
+        }
+    }
+
+    fn release(&self, n: usize) {
+        for b in self.buckets.iter() {
+            b.refund(n);
+        }
+    }
+}
+
+/// Limits the throughput of both directions of a stream.
+pub struct LimitedStream<S> {
+    inner: S,
+    read: Limiter,
+    write: Limiter,
+}
+
+impl<S> LimitedStream<S> {
+    /// `rate` is the limit of each direction of this stream, in bytes per
+    /// second, `shared_read` and `shared_write` are buckets shared with
+    /// other streams.
+    pub fn new(
+        inner: S,
+        rate: Option<u64>,
+        shared_read: Option<Arc<TokenBucket>>,
+        shared_write: Option<Arc<TokenBucket>>,
+    ) -> Self {
+        LimitedStream {
+            inner,
+            read: Limiter::new(rate, shared_read),
+            write: Limiter::new(rate, shared_write),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Send + Sync + Unpin> ProxyStream for LimitedStream<S> {}
+
+impl<S: AsyncRead + Unpin> AsyncRead for LimitedStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let me = &mut *self;
+        let allowed = ready!(me.read.poll_acquire(cx, buf.len()));
+        match Pin::new(&mut me.inner).poll_read(cx, &mut buf[..allowed]) {
+            Poll::Ready(Ok(n)) => {
+                me.read.release(allowed - n);
+                Poll::Ready(Ok(n))
+            }
+            res => {
+                me.read.release(allowed);
+                res
+            }
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for LimitedStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let me = &mut *self;
+        let allowed = ready!(me.write.poll_acquire(cx, buf.len()));
+        match Pin::new(&mut me.inner).poll_write(cx, &buf[..allowed]) {
+            Poll::Ready(Ok(n)) => {
+                me.write.release(allowed - n);
+                Poll::Ready(Ok(n))
+            }
+            res => {
+                me.write.release(allowed);
+                res
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    const RATE: u64 = 256 * 1024;
+
+    // Accepts connections and discards everything received.
+    async fn sink() -> std::net::SocketAddr {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 16 * 1024];
+                    while let Ok(n) = stream.read(&mut buf).await {
+                        if n == 0 {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    // The bucket starts full, so the first second worth of data goes out
+    // right away.
+    fn expected_secs(bytes: u64, rate: u64) -> f64 {
+        (bytes - rate) as f64 / rate as f64
+    }
+
+    fn assert_within(elapsed: Duration, expected: f64) {
+        let elapsed = elapsed.as_secs_f64();
+        assert!(
+            elapsed > expected * 0.9 && elapsed < expected * 1.2,
+            "took {}s, expected {}s",
+            elapsed,
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_limit_write_rate() {
+        let addr = sink().await;
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = LimitedStream::new(stream, Some(RATE), None, None);
+        let payload = vec![0u8; 3 * RATE as usize];
+        let start = Instant::now();
+        stream.write_all(&payload).await.unwrap();
+        assert_within(start.elapsed(), expected_secs(3 * RATE, RATE));
+    }
+
+    #[tokio::test]
+    async fn test_limit_read_rate() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.write_all(&vec![0u8; 3 * RATE as usize]).await;
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = LimitedStream::new(stream, Some(RATE), None, None);
+        let mut buf = Vec::new();
+        let start = Instant::now();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf.len(), 3 * RATE as usize);
+        assert_within(start.elapsed(), expected_secs(3 * RATE, RATE));
+    }
+
+    #[tokio::test]
+    async fn test_limit_shared_rate() {
+        let addr = sink().await;
+        let shared = Arc::new(TokenBucket::new(RATE));
+        let start = Instant::now();
+        let mut tasks = Vec::new();
+        // Each stream alone would be allowed the full rate.
+        for _ in 0..3 {
+            let shared = shared.clone();
+            tasks.push(tokio::spawn(async move {
+                let stream = TcpStream::connect(addr).await.unwrap();
+                let mut stream = LimitedStream::new(stream, Some(RATE), None, Some(shared));
+                stream.write_all(&vec![0u8; RATE as usize]).await.unwrap();
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_within(start.elapsed(), expected_secs(3 * RATE, RATE));
+    }
+}
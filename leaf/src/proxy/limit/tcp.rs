@@ -0,0 +1,56 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    proxy::{ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+use super::{LimitedStream, TokenBucket};
+
+pub struct Handler {
+    /// Limit of each connection in bytes per second.
+    pub rate: Option<u64>,
+    /// Limits shared by all connections of this handler.
+    pub global_read: Option<Arc<TokenBucket>>,
+    pub global_write: Option<Arc<TokenBucket>>,
+}
+
+impl Handler {
+    pub fn new(rate: Option<u64>, global_rate: Option<u64>) -> Self {
+        Handler {
+            rate,
+            global_read: global_rate.map(|r| Arc::new(TokenBucket::new(r))),
+            global_write: global_rate.map(|r| Arc::new(TokenBucket::new(r))),
+        }
+    }
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        _sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        let stream =
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid limit input"))?;
+        Ok(Box::new(LimitedStream::new(
+            stream,
+            self.rate,
+            self.global_read.clone(),
+            self.global_write.clone(),
+        )))
+    }
+}
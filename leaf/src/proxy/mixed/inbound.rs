@@ -0,0 +1,227 @@
+use std::{net::IpAddr, sync::Arc};
+
+use anyhow::Result;
+use bytes::BytesMut;
+use log::*;
+use tokio::{
+    io::AsyncReadExt,
+    net::{TcpListener, TcpStream},
+    stream::StreamExt,
+};
+
+use crate::{
+    app::{dispatcher::Dispatcher, nat_manager::NatManager},
+    config::{Inbound, MixedInboundSettings},
+    proxy::{http, socks, stream::ReplayStream},
+    session::SocksAddr,
+    Runner,
+};
+
+async fn serve(
+    mut stream: TcpStream,
+    socks_credentials: Option<socks::inbound::Credentials>,
+    http_credentials: Option<Arc<http::inbound::Credentials>>,
+    udp_addr: SocksAddr,
+    dispatcher: Arc<Dispatcher>,
+) {
+    let source = stream
+        .peer_addr()
+        .unwrap_or_else(|_| "0.0.0.0:0".parse().unwrap());
+    let mut buf = [0u8; 1];
+    match stream.read(&mut buf).await {
+        Ok(1) => (),
+        _ => return,
+    }
+    let stream = ReplayStream::new(stream, BytesMut::from(&buf[..]));
+    match buf[0] {
+        // A SOCKS greeting starts with the version, SOCKS4 is turned down by
+        // the SOCKS5 handshake.
+        0x04 | 0x05 => {
+            socks::inbound::tcp::serve(
+                stream,
+                source,
+                socks_credentials.as_ref(),
+                &udp_addr,
+                dispatcher,
+            )
+            .await
+        }
+        _ => {
+            if let Err(err) =
+                http::inbound::serve(stream, source, http_credentials, dispatcher).await
+            {
+                debug!("mixed inbound: {}", err);
+            }
+        }
+    }
+}
+
+/// Serves SOCKS5 and HTTP proxy clients on a single port, telling them apart
+/// by the first byte on a connection.
+pub fn new(
+    inbound: &Inbound,
+    dispatcher: Arc<Dispatcher>,
+    nat_manager: Arc<NatManager>,
+) -> Result<Runner> {
+    let listen = inbound.listen.clone();
+    let port = inbound.port as u16;
+    let settings = protobuf::parse_from_bytes::<MixedInboundSettings>(&inbound.settings)?;
+    let (socks_credentials, http_credentials) = if settings.username.is_empty() {
+        (None, None)
+    } else {
+        (
+            Some(socks::inbound::Credentials {
+                username: settings.username.clone(),
+                password: settings.password.clone(),
+            }),
+            Some(Arc::new(http::inbound::Credentials {
+                username: settings.username,
+                password: settings.password,
+            })),
+        )
+    };
+
+    let mut runners: Vec<Runner> = Vec::new();
+    let bind = if settings.bind.is_empty() {
+        "0.0.0.0".to_string()
+    } else {
+        runners.push(socks::inbound::udp::new(listen.clone(), port, nat_manager)?);
+        settings.bind
+    };
+    let udp_addr = SocksAddr::from((bind.parse::<IpAddr>()?, port));
+
+    runners.push(Box::pin(async move {
+        let mut listener = TcpListener::bind(format!("{}:{}", listen, port).as_str())
+            .await
+            .unwrap();
+        info!("mixed inbound listening tcp {}:{}", listen, port);
+        while let Some(stream) = listener.next().await {
+            if let Ok(stream) = stream {
+                tokio::spawn(serve(
+                    stream,
+                    socks_credentials.clone(),
+                    http_credentials.clone(),
+                    udp_addr.clone(),
+                    dispatcher.clone(),
+                ));
+            }
+        }
+    }));
+
+    Ok(Box::pin(async move {
+        futures::future::join_all(runners).await;
+    }))
+}
+
+#[cfg(all(test, feature = "outbound-direct"))]
+mod tests {
+    use std::net::SocketAddr;
+
+    use protobuf::Message;
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::app::{handler_manager::HandlerManager, nat_manager::NatType, router::Router};
+    use crate::common::shutdown::ShutdownToken;
+    use crate::config;
+
+    fn dispatcher() -> Arc<Dispatcher> {
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "direct".to_string();
+        outbound.protocol = "direct".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let router = Router::new(&protobuf::RepeatedField::new());
+        Arc::new(Dispatcher::new(handler_manager, router))
+    }
+
+    // Echoes back whatever it reads on every connection.
+    async fn echo() -> SocketAddr {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut r, mut w) = stream.split();
+                    let _ = tokio::io::copy(&mut r, &mut w).await;
+                });
+            }
+        });
+        addr
+    }
+
+    async fn ping(stream: &mut TcpStream, msg: &[u8]) {
+        stream.write_all(msg).await.unwrap();
+        let mut buf = vec![0u8; msg.len()];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, msg);
+    }
+
+    #[tokio::test]
+    async fn test_socks_and_http() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut inbound = Inbound::new();
+        inbound.protocol = "mixed".to_string();
+        inbound.listen = "127.0.0.1".to_string();
+        inbound.port = port as u32;
+        inbound.settings = MixedInboundSettings::new().write_to_bytes().unwrap();
+        let dispatcher = dispatcher();
+        let nat_manager = Arc::new(NatManager::new(
+            dispatcher.clone(),
+            ShutdownToken::never(),
+            NatType::Cone,
+        ));
+        let runner = new(&inbound, dispatcher, nat_manager).unwrap();
+
+        let echo = echo().await;
+        let client = async move {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            stream.write_all(&[5, 1, 0]).await.unwrap();
+            let mut buf = [0u8; 2];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [5, 0]);
+            let mut request = vec![5, 1, 0];
+            let mut addr = BytesMut::new();
+            SocksAddr::from(echo)
+                .write_buf(&mut addr, crate::session::SocksAddrWireType::PortLast)
+                .unwrap();
+            request.extend_from_slice(&addr);
+            stream.write_all(&request).await.unwrap();
+            let mut reply = [0u8; 10];
+            stream.read_exact(&mut reply).await.unwrap();
+            assert_eq!(reply[1], 0);
+            ping(&mut stream, b"socks").await;
+
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            stream
+                .write_all(
+                    format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n\r\n", echo, echo).as_bytes(),
+                )
+                .await
+                .unwrap();
+            let established = b"HTTP/1.1 200 Connection established\r\n\r\n";
+            let mut buf = vec![0u8; established.len()];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, &established[..]);
+            ping(&mut stream, b"http").await;
+        };
+        let res = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            futures::future::select(runner, Box::pin(client)),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(res, futures::future::Either::Right(_)));
+    }
+}
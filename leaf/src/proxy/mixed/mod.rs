@@ -0,0 +1,3 @@
+pub mod inbound;
+
+pub static NAME: &str = "mixed";
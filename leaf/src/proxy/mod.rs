@@ -7,16 +7,28 @@ use futures::TryFutureExt;
 use log::*;
 use socket2::{Domain, Socket, Type};
 use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::TcpStream;
+use tokio::net::{TcpStream, UdpSocket};
 
-use crate::{common::dns_client::DnsClient, common::resolver::Resolver, session::Session};
+use crate::{
+    common::dial_failures::DialFailures, common::dns_client::DnsClient,
+    common::happy_eyeballs::HappyEyeballs, common::resolver::Resolver, session::Session,
+};
 
 pub mod datagram;
+pub mod deadline;
+pub mod error;
 pub mod handler;
+pub mod proxy_protocol;
+pub mod self_test;
 pub mod stream;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod transport;
 
-#[cfg(feature = "inbound-http")]
+#[cfg(any(feature = "inbound-http", feature = "outbound-http"))]
 pub mod http;
+#[cfg(feature = "inbound-mixed")]
+pub mod mixed;
 #[cfg(all(
     feature = "inbound-tun",
     any(target_os = "ios", target_os = "macos", target_os = "linux")
@@ -25,16 +37,33 @@ pub mod tun;
 
 #[cfg(feature = "outbound-direct")]
 pub mod direct;
+#[cfg(feature = "outbound-dns")]
+pub mod dns;
 #[cfg(feature = "outbound-drop")]
 pub mod drop;
+#[cfg(feature = "outbound-fixed")]
+pub mod fixed;
 #[cfg(feature = "outbound-h2")]
 pub mod h2;
+pub mod limit;
+#[cfg(feature = "outbound-mux")]
+pub mod mux;
+#[cfg(feature = "outbound-obfs")]
+pub mod obfs;
+#[cfg(feature = "outbound-quic")]
+pub mod quic;
 #[cfg(feature = "outbound-redirect")]
 pub mod redirect;
+#[cfg(feature = "outbound-reject")]
+pub mod reject;
+#[cfg(feature = "outbound-retry")]
+pub mod retry;
 #[cfg(feature = "outbound-shadowsocks")]
 pub mod shadowsocks;
 #[cfg(any(feature = "inbound-socks", feature = "outbound-socks"))]
 pub mod socks;
+#[cfg(feature = "outbound-tee")]
+pub mod tee;
 #[cfg(feature = "outbound-tls")]
 pub mod tls;
 #[cfg(feature = "outbound-trojan")]
@@ -55,8 +84,13 @@ pub mod random;
 #[cfg(feature = "outbound-tryall")]
 pub mod tryall;
 
-pub use datagram::{SimpleDatagram, SimpleDatagramRecvHalf, SimpleDatagramSendHalf};
+pub use datagram::{
+    DatagramCodec, DatagramReader, SimpleDatagram, SimpleDatagramRecvHalf, SimpleDatagramSendHalf,
+    StreamDatagram,
+};
+pub use error::ProxyError;
 pub use handler::Handler;
+pub use self_test::TestResult;
 pub use stream::SimpleStream;
 
 #[derive(Clone)]
@@ -78,7 +112,43 @@ pub trait ProxyHandler:
 {
 }
 
-pub trait ProxyStream: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
+pub trait ProxyStream: AsyncRead + AsyncWrite + Send + Sync + Unpin {
+    /// Returns the application protocol negotiated on this stream, e.g. by
+    /// TLS ALPN, if any.
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Returns the socket if this is a plain TCP stream, which can be
+    /// relayed with splice(2).
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn tcp_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        None
+    }
+
+    /// Returns how long the stream may stay idle, for relays bypassing it
+    /// with splice(2) to enforce.
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn idle_timeout(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+impl<S: ProxyStream + ?Sized> ProxyStream for Box<S> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        (**self).negotiated_protocol()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn tcp_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        (**self).tcp_fd()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn idle_timeout(&self) -> Option<std::time::Duration> {
+        (**self).idle_timeout()
+    }
+}
 
 pub trait Tag {
     fn tag(&self) -> &String;
@@ -92,23 +162,156 @@ pub trait HandlerTyped {
     fn handler_type(&self) -> ProxyHandlerType;
 }
 
-async fn dial_task(
-    dial_addr: SocketAddr,
-    bind_addr: &SocketAddr,
-) -> io::Result<Box<dyn ProxyStream>> {
-    let socket = Socket::new(Domain::ipv4(), Type::stream(), None)?;
-    socket.bind(&bind_addr.clone().into())?;
+/// The addresses to bind to for dialing, at most one per family. A single
+/// unspecified address binds the dials of the other family to its
+/// unspecified address too.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BindAddrs {
+    pub v4: Option<SocketAddr>,
+    pub v6: Option<SocketAddr>,
+}
+
+impl From<SocketAddr> for BindAddrs {
+    fn from(addr: SocketAddr) -> Self {
+        if addr.is_ipv4() {
+            BindAddrs {
+                v4: Some(addr),
+                v6: None,
+            }
+        } else {
+            BindAddrs {
+                v4: None,
+                v6: Some(addr),
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for BindAddrs {
+    type Err = io::Error;
+
+    /// Parses comma-separated IPs, e.g. `192.168.1.2,2001:db8::2`, one per
+    /// family.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let mut binds = BindAddrs::default();
+        for ip in s.split(',').map(str::trim) {
+            let ip: std::net::IpAddr = ip
+                .parse()
+                .map_err(|e| invalid(format!("invalid bind address [{}]: {}", ip, e)))?;
+            let slot = if ip.is_ipv4() {
+                &mut binds.v4
+            } else {
+                &mut binds.v6
+            };
+            if slot.is_some() {
+                return Err(invalid(format!(
+                    "more than one bind address of the family of [{}]",
+                    ip
+                )));
+            }
+            *slot = Some(SocketAddr::new(ip, 0));
+        }
+        Ok(binds)
+    }
+}
+
+impl BindAddrs {
+    /// Returns an address to bind to for any dial, e.g. for DNS lookups.
+    pub fn any(&self) -> SocketAddr {
+        self.v4
+            .or(self.v6)
+            .unwrap_or_else(|| SocketAddr::new(std::net::Ipv4Addr::UNSPECIFIED.into(), 0))
+    }
+
+    /// Returns the address to bind to for dialing `dial_addr`, the one of
+    /// its family, or the unspecified address of its family if the other
+    /// family alone is bound to an unspecified address.
+    pub fn for_dial(&self, dial_addr: &SocketAddr) -> io::Result<SocketAddr> {
+        let (same, other) = if dial_addr.is_ipv4() {
+            (self.v4, self.v6)
+        } else {
+            (self.v6, self.v4)
+        };
+        match (same, other) {
+            (Some(addr), _) => Ok(addr),
+            (None, Some(other)) if other.ip().is_unspecified() => {
+                let ip: std::net::IpAddr = if dial_addr.is_ipv4() {
+                    std::net::Ipv4Addr::UNSPECIFIED.into()
+                } else {
+                    std::net::Ipv6Addr::UNSPECIFIED.into()
+                };
+                Ok(SocketAddr::new(ip, other.port()))
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "no {} bind address to dial {}",
+                    if dial_addr.is_ipv4() { "ipv4" } else { "ipv6" },
+                    dial_addr
+                ),
+            )),
+        }
+    }
+}
+
+async fn dial_task(dial_addr: SocketAddr, binds: &BindAddrs) -> io::Result<Box<dyn ProxyStream>> {
+    let socket = if dial_addr.is_ipv4() {
+        Socket::new(Domain::ipv4(), Type::stream(), None)?
+    } else {
+        Socket::new(Domain::ipv6(), Type::stream(), None)?
+    };
+    socket.bind(&binds.for_dial(&dial_addr)?.into())?;
     trace!("dialing tcp {}", &dial_addr);
     match TcpStream::connect_std(socket.into_tcp_stream(), &dial_addr).await {
         Ok(stream) => {
             trace!("connected tcp {}", &dial_addr);
             Ok(Box::new(SimpleStream(stream)))
         }
-        Err(e) => Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("connect failed: {}", e),
-        )),
+        Err(e) => Err(ProxyError::Connect(e).into()),
+    }
+}
+
+/// Binds a UDP socket to `bind_addr`, and to the network interface named
+/// `interface` if any, which is only supported on Linux.
+pub fn bind_udp_socket(bind_addr: &SocketAddr, interface: Option<&str>) -> io::Result<UdpSocket> {
+    UdpSocket::from_std(bind_std_udp_socket(bind_addr, interface)?)
+}
+
+/// As `bind_udp_socket`, returns a non-blocking std socket, for sockets not
+/// driven by tokio's own `UdpSocket`.
+pub fn bind_std_udp_socket(
+    bind_addr: &SocketAddr,
+    interface: Option<&str>,
+) -> io::Result<std::net::UdpSocket> {
+    let domain = if bind_addr.is_ipv4() {
+        Domain::ipv4()
+    } else {
+        Domain::ipv6()
+    };
+    let socket = Socket::new(domain, Type::dgram(), None)?;
+    if let Some(interface) = interface {
+        bind_device(&socket, interface)?;
     }
+    socket.bind(&bind_addr.clone().into())?;
+    let socket = socket.into_udp_socket();
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn bind_device(socket: &Socket, interface: &str) -> io::Result<()> {
+    let interface = std::ffi::CString::new(interface)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    socket.bind_device(Some(&interface))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn bind_device(_socket: &Socket, interface: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        format!("binding to interface {} is not supported", interface),
+    ))
 }
 
 async fn dial_tcp_stream(
@@ -117,58 +320,109 @@ async fn dial_tcp_stream(
     address: &str,
     port: &u16,
 ) -> io::Result<Box<dyn ProxyStream>> {
-    let mut resolver = Resolver::new(dns_client, bind_addr, address, port)
-        .map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("resolve address failed: {}", e),
-            )
+    dial_tcp_stream_with(
+        dns_client,
+        &BindAddrs::from(*bind_addr),
+        address,
+        port,
+        DIAL_CONCURRENCY,
+        None,
+        None,
+    )
+    .await
+}
+
+// Addresses of a host dialed at the same time.
+// TODO make configurable
+const DIAL_CONCURRENCY: usize = 3;
+
+// Same as `dial_tcp_stream`, dialing `concurrency` addresses at a time.
+// Addresses which recently failed are dialed after the others, if
+// `failures` is given. The address families are raced with
+// `happy_eyeballs`, if given. Each address is dialed from the bind address
+// of its family.
+async fn dial_tcp_stream_with(
+    dns_client: Arc<DnsClient>,
+    binds: &BindAddrs,
+    address: &str,
+    port: &u16,
+    concurrency: usize,
+    failures: Option<&DialFailures>,
+    happy_eyeballs: Option<&HappyEyeballs>,
+) -> io::Result<Box<dyn ProxyStream>> {
+    let resolver = Resolver::new(dns_client, &binds.any(), address, port)
+        .map_err(|e| ProxyError::Dns {
+            host: address.to_string(),
+            source: e.into(),
         })
         .await?;
+    let addrs: Vec<SocketAddr> = resolver.collect();
+    if addrs.is_empty() {
+        return Err(ProxyError::Dns {
+            host: address.to_string(),
+            source: "could not resolve to any address".into(),
+        }
+        .into());
+    }
+    match happy_eyeballs {
+        Some(he) => {
+            he.race(addrs, |addrs| {
+                dial_addrs(addrs, binds, concurrency, failures)
+            })
+            .await
+        }
+        None => dial_addrs(addrs, binds, concurrency, failures).await,
+    }
+}
+
+async fn dial_addrs(
+    addrs: Vec<SocketAddr>,
+    binds: &BindAddrs,
+    concurrency: usize,
+    failures: Option<&DialFailures>,
+) -> io::Result<Box<dyn ProxyStream>> {
+    let (fresh, failing) = match failures {
+        Some(failures) => failures.partition(addrs),
+        None => (addrs, Vec::new()),
+    };
 
     let mut last_err = None;
 
-    // TODO make configurable
-    let dial_concurrency = 3;
-    let mut done = false;
-
-    while !done {
-        let mut tasks = Vec::new();
-        for _ in 0..dial_concurrency {
-            let dial_addr = match resolver.next() {
-                Some(a) => a,
-                None => {
-                    done = true; // run out
-                    break; // break and execute tasks if there're any
-                }
-            };
-            let t = dial_task(dial_addr, bind_addr);
-            tasks.push(Box::pin(t));
-        }
-        if !tasks.is_empty() {
-            match select_ok(tasks.into_iter()).await {
+    for group in [fresh, failing].iter() {
+        for batch in group.chunks(concurrency.max(1)) {
+            let tasks = batch.iter().map(|dial_addr| {
+                Box::pin(async move {
+                    let res = dial_task(*dial_addr, binds).await;
+                    if let Some(failures) = failures {
+                        failures.record(*dial_addr, res.is_ok());
+                    }
+                    res
+                })
+            });
+            match select_ok(tasks).await {
                 Ok(v) => return Ok(v.0),
                 Err(e) => {
-                    last_err = Some(io::Error::new(
-                        io::ErrorKind::Other,
-                        format!("all attempts failed, last error: {}", e),
-                    ));
+                    last_err = Some(ProxyError::AllFailed(e).into());
                 }
             }
         }
     }
 
-    Err(last_err.unwrap_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "could not resolve to any address",
-        )
-    }))
+    Err(last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "no address to dial")))
 }
 
 #[async_trait]
 pub trait ProxyTcpHandler: Send + Sync + Unpin {
     fn name(&self) -> &str;
+
+    /// The address of the server of the handler, and the address to bind
+    /// to, if it connects to a fixed one, e.g. a proxy server.
+    ///
+    /// Given no stream, a handler returning one dials it itself, given a
+    /// stream, it takes it as connected there, e.g. by the previous actors
+    /// of a chain, which are given this address as destination. Handlers
+    /// connecting to the destination of the session, wrapping the stream of
+    /// another handler, or delegating to actors, return `None`.
     fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)>;
     async fn handle<'a>(
         &'a self,
@@ -176,6 +430,22 @@ pub trait ProxyTcpHandler: Send + Sync + Unpin {
         stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>>;
 
+    /// Returns true if `sess` can be served without a new handshake, e.g. by
+    /// opening a stream on a pooled connection. Ensemble handlers prefer warm
+    /// actors.
+    fn is_warm(&self, _sess: &Session) -> bool {
+        false
+    }
+
+    /// Tries a connection to a well-known site, for a diagnostic of the
+    /// handler. Ensemble handlers report the results of their actors too.
+    async fn self_test(&self) -> TestResult {
+        self_test::test_tcp(self, &self_test::tcp_target()).await
+    }
+
+    /// Dials a stream to `address`, a TCP connection unless the handler
+    /// connects to its server otherwise, e.g. over QUIC. Chains dial the
+    /// server of their connecting actor through it.
     async fn dial_tcp_stream(
         &self,
         dns_client: Arc<DnsClient>,
@@ -194,6 +464,17 @@ pub trait ProxyDatagram: Send + Unpin {
         Box<dyn ProxyDatagramRecvHalf>,
         Box<dyn ProxyDatagramSendHalf>,
     );
+
+    /// Returns the address the local socket of this datagram is bound to,
+    /// with the port assigned by the system, e.g. for hole punching. Fails
+    /// for datagrams not sent through a local UDP socket.
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Err(no_local_addr())
+    }
+}
+
+pub(crate) fn no_local_addr() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "no local udp socket")
 }
 
 #[async_trait]
@@ -204,13 +485,36 @@ pub trait ProxyDatagramRecvHalf: Sync + Send + Unpin {
 #[async_trait]
 pub trait ProxyDatagramSendHalf: Sync + Send + Unpin {
     async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize>;
+
+    /// Sends each of `pkts` to its target, in order, and returns how many
+    /// were sent. Fails only if none was, the caller sends the rest again.
+    /// Sockets able to send several datagrams per syscall override it.
+    async fn send_batch(&mut self, pkts: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        for (i, (buf, target)) in pkts.iter().enumerate() {
+            if let Err(e) = self.send_to(buf, target).await {
+                if i == 0 {
+                    return Err(e);
+                }
+                return Ok(i);
+            }
+        }
+        Ok(pkts.len())
+    }
 }
 
 #[async_trait]
 pub trait ProxyUdpHandler: Send + Sync + Unpin {
     fn name(&self) -> &str;
+
+    /// As `ProxyTcpHandler::tcp_connect_addr`, for the server the packets, or
+    /// the stream they are framed over, are sent to.
     fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)>;
     fn udp_transport_type(&self) -> UdpTransportType;
+
+    /// Connects for `sess`. Chained after other actors, a Packet transport is
+    /// given the `datagram` of the previous actor and must send its packets,
+    /// addressed to its server, through it, a Stream transport is given the
+    /// `stream` to frame them over instead.
     async fn connect<'a>(
         &'a self,
         sess: &'a Session,
@@ -218,6 +522,12 @@ pub trait ProxyUdpHandler: Send + Sync + Unpin {
         stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyDatagram>>;
 
+    /// Tries a DNS query to a public server, for a diagnostic of the
+    /// handler. Ensemble handlers report the results of their actors too.
+    async fn self_test(&self) -> TestResult {
+        self_test::test_udp(self, &self_test::udp_target()).await
+    }
+
     async fn dial_tcp_stream(
         &self,
         dns_client: Arc<DnsClient>,
@@ -0,0 +1,159 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+
+use crate::{
+    proxy::{
+        transport::{
+            mux::{MuxConfig, MuxConnection},
+            pool::{ConnectionPool, PoolConfig, PoolKey},
+        },
+        ProxyStream, ProxyTcpHandler,
+    },
+    session::Session,
+};
+
+pub struct Handler {
+    config: MuxConfig,
+    // Connections by server, each flow is a stream on one of them.
+    pool: ConnectionPool<MuxConnection>,
+}
+
+impl Handler {
+    pub fn new(config: MuxConfig, pool: PoolConfig) -> Self {
+        Handler {
+            config,
+            pool: ConnectionPool::new(pool),
+        }
+    }
+
+    // The server is the destination of the session, the one the stream
+    // given to the handler is connected to.
+    fn pool_key(&self, sess: &Session) -> PoolKey {
+        PoolKey {
+            host: sess.destination.host(),
+            port: sess.destination.port(),
+            transport: super::NAME.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.pool.is_warm(&self.pool_key(sess))
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        let key = self.pool_key(sess);
+        let mux_stream = match stream {
+            // A stream dialed for the flow, e.g. by a chain, is always used,
+            // it's a new connection to the server.
+            Some(stream) => {
+                let conn = MuxConnection::new(stream, &self.config);
+                self.pool.add_connection(&key, conn).await?
+            }
+            None => {
+                self.pool
+                    .open_stream(&key, || async {
+                        Err(io::Error::new(io::ErrorKind::Other, "invalid mux input"))
+                    })
+                    .await?
+            }
+        };
+        Ok(Box::new(mux_stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_util::compat::{FuturesAsyncReadCompatExt, Tokio02AsyncReadCompatExt};
+
+    use super::*;
+    use crate::proxy::stream::SimpleStream;
+    use crate::session::SocksAddr;
+
+    // Echoes the streams of every connection accepted, counts the
+    // connections.
+    async fn serve(mut listener: TcpListener, accepted: Arc<AtomicUsize>) {
+        while let Ok((stream, _)) = listener.accept().await {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut conn = yamux::Connection::new(
+                    stream.compat(),
+                    yamux::Config::default(),
+                    yamux::Mode::Server,
+                );
+                while let Ok(Some(stream)) = conn.next_stream().await {
+                    tokio::spawn(async move {
+                        let (mut r, mut w) = tokio::io::split(stream.compat());
+                        let _ = tokio::io::copy(&mut r, &mut w).await;
+                    });
+                }
+            });
+        }
+    }
+
+    async fn echo(stream: &mut Box<dyn ProxyStream>, payload: &[u8]) {
+        stream.write_all(payload).await.unwrap();
+        let mut buf = vec![0u8; payload.len()];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, payload);
+    }
+
+    #[tokio::test]
+    async fn test_mux_reuses_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(serve(listener, accepted.clone()));
+
+        let handler = Handler::new(MuxConfig::default(), PoolConfig::default());
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Ip(addr),
+        };
+        assert!(!handler.is_warm(&sess));
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut s1 = handler
+            .handle(&sess, Some(Box::new(SimpleStream(stream))))
+            .await
+            .unwrap();
+        echo(&mut s1, b"first").await;
+
+        // The second flow is a stream on the same connection, it needs no
+        // stream of its own.
+        assert!(handler.is_warm(&sess));
+        let mut s2 = handler.handle(&sess, None).await.unwrap();
+        echo(&mut s2, b"second").await;
+        echo(&mut s1, b"first again").await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // A stream given while warm is used rather than dropped.
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut s3 = handler
+            .handle(&sess, Some(Box::new(SimpleStream(stream))))
+            .await
+            .unwrap();
+        echo(&mut s3, b"third").await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    }
+}
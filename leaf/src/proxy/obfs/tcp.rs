@@ -0,0 +1,54 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+
+use crate::{
+    proxy::{
+        transport::obfs::{HttpObfsStream, ObfsConfig, TlsObfsStream},
+        ProxyStream, ProxyTcpHandler,
+    },
+    session::Session,
+};
+
+pub struct Handler {
+    pub method: String,
+    pub host: String,
+    pub path: String,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        let stream =
+            stream.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid obfs input"))?;
+        let config = ObfsConfig {
+            host: if self.host.is_empty() {
+                sess.destination.host()
+            } else {
+                self.host.clone()
+            },
+            path: self.path.clone(),
+        };
+        match self.method.as_str() {
+            "http" => Ok(Box::new(HttpObfsStream::new(stream, &config))),
+            "tls" => Ok(Box::new(TlsObfsStream::new(stream, &config))),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unsupported obfs method: {}", &self.method),
+            )),
+        }
+    }
+}
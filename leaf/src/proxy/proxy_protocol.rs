@@ -0,0 +1,221 @@
+//! Headers of the PROXY protocol, which pass the address of the original
+//! client on to servers behind a proxy.
+//!
+//! https://www.haproxy.org/download/2.3/doc/proxy-protocol.txt
+
+use std::net::{IpAddr, SocketAddr};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::session::SocksAddr;
+
+// Type of the TLV holding the host name the client asked for.
+const PP2_TYPE_AUTHORITY: u8 = 0x02;
+
+const V2_SIGNATURE: [u8; 12] = [
+    0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54, 0x0a,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Version {
+    /// The human-readable header.
+    V1,
+    /// The binary header.
+    V2,
+}
+
+// Both addresses of a header must be of the same family, IPv4 addresses are
+// mapped to IPv6 when they aren't.
+fn same_family(src: SocketAddr, dst: SocketAddr) -> (SocketAddr, SocketAddr) {
+    let to_v6 = |a: SocketAddr| match a {
+        SocketAddr::V4(v4) => SocketAddr::new(IpAddr::V6(v4.ip().to_ipv6_mapped()), v4.port()),
+        a => a,
+    };
+    if src.is_ipv4() == dst.is_ipv4() {
+        (src, dst)
+    } else {
+        (to_v6(src), to_v6(dst))
+    }
+}
+
+/// Appends the header of a TCP connection from `src` to `dst` to `buf`.
+///
+/// Without a `dst` IP address, e.g. when the destination is a domain, the
+/// header tells the addresses are unknown, and the server uses the ones of
+/// the connection itself.
+pub fn write_header(
+    version: Version,
+    src: &SocketAddr,
+    dst: Option<&SocketAddr>,
+    buf: &mut BytesMut,
+) {
+    let addrs = dst.map(|dst| same_family(*src, *dst));
+    match version {
+        Version::V1 => match addrs {
+            Some((src, dst)) => {
+                let family = if src.is_ipv4() { "TCP4" } else { "TCP6" };
+                let header = format!(
+                    "PROXY {} {} {} {} {}\r\n",
+                    family,
+                    src.ip(),
+                    dst.ip(),
+                    src.port(),
+                    dst.port()
+                );
+                buf.put_slice(header.as_bytes());
+            }
+            None => buf.put_slice(b"PROXY UNKNOWN\r\n"),
+        },
+        Version::V2 => {
+            buf.put_slice(&V2_SIGNATURE);
+            match addrs {
+                Some((SocketAddr::V4(src), SocketAddr::V4(dst))) => {
+                    buf.put_u8(0x21); // version 2, PROXY
+                    buf.put_u8(0x11); // TCP over IPv4
+                    buf.put_u16(4 + 4 + 2 + 2);
+                    buf.put_slice(&src.ip().octets());
+                    buf.put_slice(&dst.ip().octets());
+                    buf.put_u16(src.port());
+                    buf.put_u16(dst.port());
+                }
+                Some((SocketAddr::V6(src), SocketAddr::V6(dst))) => {
+                    buf.put_u8(0x21); // version 2, PROXY
+                    buf.put_u8(0x21); // TCP over IPv6
+                    buf.put_u16(16 + 16 + 2 + 2);
+                    buf.put_slice(&src.ip().octets());
+                    buf.put_slice(&dst.ip().octets());
+                    buf.put_u16(src.port());
+                    buf.put_u16(dst.port());
+                }
+                _ => {
+                    buf.put_u8(0x20); // version 2, LOCAL
+                    buf.put_u8(0x00); // unspecified
+                    buf.put_u16(0);
+                }
+            }
+        }
+    }
+}
+
+/// Appends the header of a TCP connection from `src` to the destination
+/// `dst` the client asked for, e.g. for a server which connects on behalf of
+/// the client.
+///
+/// A domain destination is passed on as `host:port` in an authority TLV of a
+/// version 2 header, along with unknown addresses. The version 1 header has
+/// no room for it and tells the addresses are unknown.
+pub fn write_destination_header(
+    version: Version,
+    src: &SocketAddr,
+    dst: &SocksAddr,
+    buf: &mut BytesMut,
+) {
+    match (version, dst) {
+        (_, SocksAddr::Ip(dst)) => write_header(version, src, Some(dst), buf),
+        (Version::V1, SocksAddr::Domain(..)) => write_header(version, src, None, buf),
+        (Version::V2, SocksAddr::Domain(domain, port)) => {
+            let authority = format!("{}:{}", domain, port);
+            // The length of a TLV value takes 2 bytes.
+            let authority = &authority.as_bytes()[..authority.len().min(0xffff - 3)];
+            buf.put_slice(&V2_SIGNATURE);
+            buf.put_u8(0x21); // version 2, PROXY
+            buf.put_u8(0x00); // unspecified
+            buf.put_u16(3 + authority.len() as u16);
+            buf.put_u8(PP2_TYPE_AUTHORITY);
+            buf.put_u16(authority.len() as u16);
+            buf.put_slice(authority);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(version: Version, src: &str, dst: Option<&str>) -> Vec<u8> {
+        let src: SocketAddr = src.parse().unwrap();
+        let dst: Option<SocketAddr> = dst.map(|d| d.parse().unwrap());
+        let mut buf = BytesMut::new();
+        write_header(version, &src, dst.as_ref(), &mut buf);
+        buf.to_vec()
+    }
+
+    #[test]
+    fn test_proxy_protocol_v1() {
+        assert_eq!(
+            header(Version::V1, "192.168.0.1:56324", Some("10.0.0.1:443")),
+            b"PROXY TCP4 192.168.0.1 10.0.0.1 56324 443\r\n".to_vec()
+        );
+        assert_eq!(
+            header(Version::V1, "[2001:db8::1]:56324", Some("[::1]:443")),
+            b"PROXY TCP6 2001:db8::1 ::1 56324 443\r\n".to_vec()
+        );
+        assert_eq!(
+            header(Version::V1, "192.168.0.1:56324", Some("[::1]:443")),
+            b"PROXY TCP6 ::ffff:192.168.0.1 ::1 56324 443\r\n".to_vec()
+        );
+        assert_eq!(
+            header(Version::V1, "192.168.0.1:56324", None),
+            b"PROXY UNKNOWN\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_proxy_protocol_v2() {
+        let mut expected = V2_SIGNATURE.to_vec();
+        expected.extend_from_slice(&[
+            0x21, 0x11, 0x00, 0x0c, // PROXY, TCP4, 12 bytes
+            192, 168, 0, 1, // source
+            10, 0, 0, 1, // destination
+            0xdc, 0x04, // source port 56324
+            0x01, 0xbb, // destination port 443
+        ]);
+        assert_eq!(
+            header(Version::V2, "192.168.0.1:56324", Some("10.0.0.1:443")),
+            expected
+        );
+
+        let mut expected = V2_SIGNATURE.to_vec();
+        expected.extend_from_slice(&[0x21, 0x21, 0x00, 0x24]); // PROXY, TCP6, 36 bytes
+        expected.extend_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+        expected.extend_from_slice(&[0; 11]);
+        expected.push(1); // source 2001:db8::1
+        expected.extend_from_slice(&[0; 15]);
+        expected.push(1); // destination ::1
+        expected.extend_from_slice(&[0xdc, 0x04, 0x01, 0xbb]);
+        assert_eq!(
+            header(Version::V2, "[2001:db8::1]:56324", Some("[::1]:443")),
+            expected
+        );
+
+        let mut expected = V2_SIGNATURE.to_vec();
+        expected.extend_from_slice(&[0x20, 0x00, 0x00, 0x00]); // LOCAL, unspecified
+        assert_eq!(header(Version::V2, "192.168.0.1:56324", None), expected);
+    }
+
+    #[test]
+    fn test_destination_header() {
+        let src: SocketAddr = "192.168.0.1:56324".parse().unwrap();
+        let dest_header = |version: Version, dst: SocksAddr| {
+            let mut buf = BytesMut::new();
+            write_destination_header(version, &src, &dst, &mut buf);
+            buf.to_vec()
+        };
+        let ip = SocksAddr::Ip("10.0.0.1:443".parse().unwrap());
+        assert_eq!(
+            dest_header(Version::V2, ip),
+            header(Version::V2, "192.168.0.1:56324", Some("10.0.0.1:443"))
+        );
+
+        let domain = SocksAddr::Domain("example.com".to_string(), 443);
+        assert_eq!(
+            dest_header(Version::V1, domain.clone()),
+            b"PROXY UNKNOWN\r\n".to_vec()
+        );
+        let mut expected = V2_SIGNATURE.to_vec();
+        expected.extend_from_slice(&[0x21, 0x00, 0x00, 0x12]); // PROXY, unspecified, 18 bytes
+        expected.extend_from_slice(&[0x02, 0x00, 0x0f]); // authority, 15 bytes
+        expected.extend_from_slice(b"example.com:443");
+        assert_eq!(dest_header(Version::V2, domain), expected);
+    }
+}
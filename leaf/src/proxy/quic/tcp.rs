@@ -0,0 +1,248 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::*;
+use tokio::sync::Mutex;
+
+use crate::{
+    common::dns_client::DnsClient,
+    proxy::{
+        transport::quic::{QuicConfig, QuicConnection, QuicStream},
+        ProxyStream, ProxyTcpHandler,
+    },
+    session::Session,
+};
+
+pub struct Handler {
+    address: String,
+    port: u16,
+    bind_addr: SocketAddr,
+    dns_client: Arc<DnsClient>,
+    config: QuicConfig,
+    // The connection every flow is a stream of, and the number of times it
+    // has been established.
+    conn: Mutex<(u64, Option<QuicConnection>)>,
+}
+
+impl Handler {
+    pub fn new(
+        address: String,
+        port: u16,
+        bind_addr: SocketAddr,
+        dns_client: Arc<DnsClient>,
+        config: QuicConfig,
+    ) -> Self {
+        Handler {
+            address,
+            port,
+            bind_addr,
+            dns_client,
+            config,
+            conn: Mutex::new((0, None)),
+        }
+    }
+
+    async fn connect(&self) -> io::Result<QuicConnection> {
+        let ips = self
+            .dns_client
+            .lookup_with_bind(self.address.clone(), &self.bind_addr)
+            .await
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("lookup {} failed: {}", &self.address, e),
+                )
+            })?;
+        let ip = ips.first().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("no address for {}", &self.address),
+            )
+        })?;
+        QuicConnection::connect(
+            &SocketAddr::new(*ip, self.port),
+            &self.bind_addr,
+            &self.config,
+        )
+        .await
+    }
+
+    // Opens a stream on the connection, connecting again if it has failed.
+    async fn open_stream(&self) -> io::Result<QuicStream> {
+        let (generation, conn) = self.conn.lock().await.clone();
+        if let Some(conn) = conn {
+            match conn.open_stream().await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => debug!(
+                    "quic connection to {}:{} failed: {}",
+                    &self.address, self.port, e
+                ),
+            }
+        }
+        // Connects again, unless another flow already has.
+        let conn = {
+            let mut cached = self.conn.lock().await;
+            match cached.1.clone() {
+                Some(conn) if cached.0 != generation => conn,
+                _ => {
+                    let conn = self.connect().await?;
+                    *cached = (cached.0 + 1, Some(conn.clone()));
+                    conn
+                }
+            }
+        };
+        conn.open_stream().await
+    }
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        Some((self.address.clone(), self.port, self.bind_addr))
+    }
+
+    fn is_warm(&self, _sess: &Session) -> bool {
+        match self.conn.try_lock() {
+            Ok(conn) => conn.1.is_some(),
+            Err(_) => false,
+        }
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        _sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        match stream {
+            // Dialed with dial_tcp_stream, e.g. by a chain.
+            Some(stream) => Ok(stream),
+            None => Ok(Box::new(self.open_stream().await?)),
+        }
+    }
+
+    // The server is always the one of the handler, the streams dialed to it
+    // are streams of the QUIC connection.
+    async fn dial_tcp_stream(
+        &self,
+        _dns_client: Arc<DnsClient>,
+        _bind_addr: &SocketAddr,
+        _address: &str,
+        _port: &u16,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        Ok(Box::new(self.open_stream().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::proxy::{Handler as ProxyHandlerImpl, ProxyHandlerType};
+    use crate::session::SocksAddr;
+
+    const CERT: &[u8] = include_bytes!("../transport/testdata/cert.der");
+    const KEY: &[u8] = include_bytes!("../transport/testdata/key.der");
+
+    // Echoes the streams of every connection accepted, counts the
+    // connections.
+    fn serve(accepted: Arc<AtomicUsize>) -> SocketAddr {
+        let mut server_config = quinn::ServerConfigBuilder::default();
+        server_config
+            .certificate(
+                quinn::CertificateChain::from_certs(vec![
+                    quinn::Certificate::from_der(CERT).unwrap()
+                ]),
+                quinn::PrivateKey::from_der(KEY).unwrap(),
+            )
+            .unwrap();
+        server_config.protocols(&[b"leaf"]);
+        let mut builder = quinn::Endpoint::builder();
+        builder.listen(server_config.build());
+        let (endpoint, mut incoming) = builder.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _endpoint = endpoint;
+            while let Some(connecting) = incoming.next().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut bi_streams = match connecting.await {
+                        Ok(new_conn) => new_conn.bi_streams,
+                        Err(_) => return,
+                    };
+                    while let Some(Ok((mut send, mut recv))) = bi_streams.next().await {
+                        tokio::spawn(async move {
+                            let _ = tokio::io::copy(&mut recv, &mut send).await;
+                        });
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    async fn echo(stream: &mut Box<dyn ProxyStream>, payload: &[u8]) {
+        stream.write_all(payload).await.unwrap();
+        let mut buf = vec![0u8; payload.len()];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, payload);
+    }
+
+    #[tokio::test]
+    async fn test_quic_flows_share_connection() {
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let addr = serve(accepted.clone());
+        let handler = ProxyHandlerImpl::new(
+            "quic".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Handler::new(
+                addr.ip().to_string(),
+                addr.port(),
+                "127.0.0.1:0".parse().unwrap(),
+                Arc::new(DnsClient::default()),
+                QuicConfig {
+                    server_name: "localhost".to_string(),
+                    alpns: vec!["leaf".to_string()],
+                    certificates: vec![CERT.to_vec()],
+                    ..Default::default()
+                },
+            )),
+            Box::new(super::super::UdpHandler),
+        );
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        assert!(!handler.is_warm(&sess));
+
+        // Dialing the server of the handler, as a chain does, opens a stream.
+        let (address, port, bind_addr) = handler.tcp_connect_addr().unwrap();
+        let stream = ProxyTcpHandler::dial_tcp_stream(
+            &*handler,
+            Arc::new(DnsClient::default()),
+            &bind_addr,
+            &address,
+            &port,
+        )
+        .await
+        .unwrap();
+        let mut s1 = handler.handle(&sess, Some(stream)).await.unwrap();
+        echo(&mut s1, b"first").await;
+
+        assert!(handler.is_warm(&sess));
+        let mut s2 = handler.handle(&sess, None).await.unwrap();
+        echo(&mut s2, b"second").await;
+        echo(&mut s1, b"first again").await;
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+}
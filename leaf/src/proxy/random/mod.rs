@@ -1,3 +1,10 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
 pub mod tcp;
 pub mod udp;
 
@@ -5,3 +12,156 @@ pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
 
 pub static NAME: &str = "random";
+
+/// The actors recently chosen for the flows of each source IP.
+///
+/// Shared by the TCP and UDP handlers of a `random`, so a UDP flow goes
+/// through the actor a recent TCP flow from the same source went through,
+/// and the other way round. Keeps protocols pairing a TCP control channel
+/// with UDP media behind a single NAT. A choice is forgotten once it hasn't
+/// been used for `ttl`.
+pub struct Affinity {
+    ttl: Duration,
+    chosen: Mutex<HashMap<IpAddr, (usize, Instant)>>,
+}
+
+impl Affinity {
+    pub fn new(ttl: Duration) -> Self {
+        Affinity {
+            ttl,
+            chosen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the actor chosen for `ip` within the TTL.
+    pub fn get(&self, ip: &IpAddr) -> Option<usize> {
+        match self.chosen.lock().unwrap().get(ip) {
+            Some((i, at)) if at.elapsed() < self.ttl => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Records `i` as the actor chosen for `ip`.
+    pub fn set(&self, ip: IpAddr, i: usize) {
+        let mut chosen = self.chosen.lock().unwrap();
+        chosen.insert(ip, (i, Instant::now()));
+        // Drops the expired entries on the way.
+        let ttl = self.ttl;
+        chosen.retain(|_, (_, at)| at.elapsed() < ttl);
+    }
+}
+
+// Picks the actor for a flow from `ip`, the one of its recent flows if
+// there's an affinity.
+fn pick(n: usize, affinity: Option<&Affinity>, ip: &IpAddr) -> usize {
+    if let Some(i) = affinity.and_then(|a| a.get(ip)) {
+        if i < n {
+            return i;
+        }
+    }
+    let mut rng = StdRng::from_entropy();
+    rng.gen_range(0, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::testing::{Behavior, MockUdpHandler};
+    use crate::proxy::{
+        stream::SimpleStream, Handler, ProxyHandler, ProxyHandlerType, ProxyStream,
+        ProxyTcpHandler, ProxyUdpHandler,
+    };
+    use crate::session::{Session, SocksAddr};
+
+    // Connects to a listener, counting its connects.
+    struct Counting(SocketAddr, Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> std::io::Result<Box<dyn ProxyStream>> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(SimpleStream(TcpStream::connect(self.0).await?)))
+        }
+    }
+
+    fn used(counts: &[Arc<AtomicUsize>]) -> Vec<usize> {
+        counts.iter().map(|c| c.load(Ordering::SeqCst)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_udp_follows_tcp() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut accepted = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                accepted.push(stream);
+            }
+        });
+
+        let mut actors: Vec<Arc<dyn ProxyHandler>> = Vec::new();
+        let mut tcp_counts = Vec::new();
+        let mut udp_counts = Vec::new();
+        for i in 0..8 {
+            let tcp_count = Arc::new(AtomicUsize::new(0));
+            let udp = MockUdpHandler::new(Behavior::Echo);
+            tcp_counts.push(tcp_count.clone());
+            udp_counts.push(udp.connects());
+            actors.push(Handler::new(
+                i.to_string(),
+                colored::Color::White,
+                ProxyHandlerType::Endpoint,
+                Box::new(Counting(addr, tcp_count)),
+                Box::new(udp),
+            ));
+        }
+        let affinity = Some(Arc::new(Affinity::new(Duration::from_secs(60))));
+        let tcp = TcpHandler {
+            actors: actors.clone(),
+            affinity: affinity.clone(),
+        };
+        let udp = UdpHandler { actors, affinity };
+
+        for n in 1..=4 {
+            let tcp_sess = Session {
+                source: format!("10.0.0.{}:10000", n).parse().unwrap(),
+                destination: SocksAddr::Domain("example.com".to_string(), 443),
+            };
+            let udp_sess = Session {
+                source: format!("10.0.0.{}:20000", n).parse().unwrap(),
+                destination: SocksAddr::Domain("example.com".to_string(), 3478),
+            };
+            let before = used(&tcp_counts);
+            tcp.handle(&tcp_sess, None).await.ok().unwrap();
+            let chosen = (0..8).find(|&i| used(&tcp_counts)[i] > before[i]).unwrap();
+
+            let before = used(&udp_counts);
+            udp.connect(&udp_sess, None, None).await.ok().unwrap();
+            let after = used(&udp_counts);
+            assert_eq!(after[chosen], before[chosen] + 1);
+            assert_eq!(
+                after.iter().sum::<usize>(),
+                before.iter().sum::<usize>() + 1
+            );
+        }
+    }
+}
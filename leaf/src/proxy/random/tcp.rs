@@ -2,15 +2,19 @@ use std::net::SocketAddr;
 use std::{io, sync::Arc};
 
 use async_trait::async_trait;
-use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
     proxy::{ProxyHandler, ProxyStream, ProxyTcpHandler},
     session::Session,
 };
 
+use super::Affinity;
+
 pub struct Handler {
     pub actors: Vec<Arc<dyn ProxyHandler>>,
+    /// Shared with the UDP handler, flows from a source stick to an actor
+    /// if set.
+    pub affinity: Option<Arc<Affinity>>,
 }
 
 #[async_trait]
@@ -28,8 +32,12 @@ impl ProxyTcpHandler for Handler {
         sess: &'a Session,
         _stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>> {
-        let mut rng = StdRng::from_entropy();
-        let i: usize = rng.gen_range(0, self.actors.len());
-        self.actors[i].handle(sess, None).await
+        let ip = sess.source.ip();
+        let i = super::pick(self.actors.len(), self.affinity.as_deref(), &ip);
+        let stream = self.actors[i].handle(sess, None).await?;
+        if let Some(affinity) = &self.affinity {
+            affinity.set(ip, i);
+        }
+        Ok(stream)
     }
 }
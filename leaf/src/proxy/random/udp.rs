@@ -3,15 +3,19 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
     proxy::{ProxyDatagram, ProxyHandler, ProxyStream, ProxyUdpHandler, UdpTransportType},
     session::Session,
 };
 
+use super::Affinity;
+
 pub struct Handler {
     pub actors: Vec<Arc<dyn ProxyHandler>>,
+    /// Shared with the TCP handler, flows from a source stick to an actor
+    /// if set.
+    pub affinity: Option<Arc<Affinity>>,
 }
 
 #[async_trait]
@@ -34,8 +38,12 @@ impl ProxyUdpHandler for Handler {
         _datagram: Option<Box<dyn ProxyDatagram>>,
         _stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyDatagram>> {
-        let mut rng = StdRng::from_entropy();
-        let i: usize = rng.gen_range(0, self.actors.len());
-        self.actors[i].connect(sess, None, None).await
+        let ip = sess.source.ip();
+        let i = super::pick(self.actors.len(), self.affinity.as_deref(), &ip);
+        let datagram = self.actors[i].connect(sess, None, None).await?;
+        if let Some(affinity) = &self.affinity {
+            affinity.set(ip, i);
+        }
+        Ok(datagram)
     }
 }
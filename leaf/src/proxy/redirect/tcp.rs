@@ -2,17 +2,29 @@ use std::io::Result;
 use std::net::SocketAddr;
 
 use async_trait::async_trait;
+use bytes::BytesMut;
+use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 
 use crate::{
-    proxy::{stream::SimpleStream, ProxyStream, ProxyTcpHandler},
+    proxy::{
+        proxy_protocol::{self, Version},
+        stream::SimpleStream,
+        ProxyError, ProxyStream, ProxyTcpHandler,
+    },
     session::Session,
 };
 
 /// Handler with a redirect target address.
+///
+/// The destination of the session isn't resolved nor connected to, the
+/// target gets it in a PROXY protocol header if `proxy_protocol` is set,
+/// a domain unless it was resolved before reaching the handler. A stream
+/// given, e.g. in a chain, is taken as connected to the target.
 pub struct Handler {
     pub address: String,
     pub port: u16,
+    pub proxy_protocol: Option<Version>,
 }
 
 #[async_trait]
@@ -22,15 +34,142 @@ impl ProxyTcpHandler for Handler {
     }
 
     fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
-        None
+        // Connects from any address.
+        Some((
+            self.address.clone(),
+            self.port,
+            SocketAddr::from(([0, 0, 0, 0], 0)),
+        ))
     }
 
     async fn handle<'a>(
         &'a self,
-        _sess: &'a Session,
-        _stream: Option<Box<dyn ProxyStream>>,
+        sess: &'a Session,
+        stream: Option<Box<dyn ProxyStream>>,
     ) -> Result<Box<dyn ProxyStream>> {
-        let stream = TcpStream::connect(format!("{}:{}", self.address, self.port)).await?;
-        Ok(Box::new(SimpleStream(stream)))
+        let mut stream: Box<dyn ProxyStream> = match stream {
+            Some(stream) => stream,
+            None => Box::new(SimpleStream(
+                TcpStream::connect(format!("{}:{}", self.address, self.port))
+                    .await
+                    .map_err(ProxyError::Connect)?,
+            )),
+        };
+        if let Some(version) = self.proxy_protocol {
+            let mut header = BytesMut::new();
+            proxy_protocol::write_destination_header(
+                version,
+                &sess.source,
+                &sess.destination,
+                &mut header,
+            );
+            stream.write_all(&header).await?;
+        }
+        Ok(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use crate::proxy::testing::check_tcp_connect_addr;
+    use crate::session::SocksAddr;
+
+    #[tokio::test]
+    async fn test_redirect_connect_error() {
+        // A port nobody listens on.
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let handler = Handler {
+            address: "127.0.0.1".to_string(),
+            port,
+            proxy_protocol: None,
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: "127.0.0.1:80".parse::<SocketAddr>().unwrap().into(),
+        };
+        let err = match handler.handle(&sess, None).await {
+            Ok(_) => panic!("connected to a closed port"),
+            Err(e) => e,
+        };
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Connect(_))
+        ));
+    }
+
+    // Accepts a single connection, returns what it received.
+    async fn target() -> (u16, tokio::task::JoinHandle<Vec<u8>>) {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            buf
+        });
+        (port, received)
+    }
+
+    async fn forward(version: Option<Version>, destination: SocksAddr) -> Vec<u8> {
+        let (port, received) = target().await;
+        let handler = Handler {
+            address: "127.0.0.1".to_string(),
+            port,
+            proxy_protocol: version,
+        };
+        let sess = Session {
+            source: "192.168.0.1:56324".parse().unwrap(),
+            destination,
+        };
+        let mut stream = handler.handle(&sess, None).await.ok().unwrap();
+        stream.write_all(b"payload").await.unwrap();
+        drop(stream);
+        received.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forward_destination() {
+        let ip = SocksAddr::Ip("10.0.0.1:443".parse().unwrap());
+        let domain = SocksAddr::Domain("example.com".to_string(), 443);
+
+        assert_eq!(forward(None, domain.clone()).await, b"payload".to_vec());
+        assert_eq!(
+            forward(Some(Version::V1), ip.clone()).await,
+            b"PROXY TCP4 192.168.0.1 10.0.0.1 56324 443\r\npayload".to_vec()
+        );
+
+        let mut expected = BytesMut::new();
+        proxy_protocol::write_destination_header(
+            Version::V2,
+            &"192.168.0.1:56324".parse().unwrap(),
+            &domain,
+            &mut expected,
+        );
+        let received = forward(Some(Version::V2), domain).await;
+        assert_eq!(&received[..expected.len()], &expected[..]);
+        assert!(String::from_utf8_lossy(&received).contains("example.com:443"));
+        assert_eq!(&received[expected.len()..], b"payload");
+    }
+
+    #[tokio::test]
+    async fn test_redirect_connect_addr() {
+        let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let handler = Handler {
+            address: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+            proxy_protocol: None,
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        check_tcp_connect_addr(&handler, &mut listener, &sess).await;
     }
 }
@@ -5,15 +5,11 @@ use std::{
 
 use async_trait::async_trait;
 use futures::TryFutureExt;
-use tokio::net::{
-    udp::{RecvHalf, SendHalf},
-    UdpSocket,
-};
 
 use crate::{
     proxy::{
-        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
-        UdpTransportType,
+        bind_udp_socket, no_local_addr, ProxyDatagram, ProxyDatagramRecvHalf,
+        ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler, SimpleDatagram, UdpTransportType,
     },
     session::Session,
 };
@@ -22,6 +18,8 @@ use crate::{
 pub struct Handler {
     pub address: String,
     pub port: u16,
+    pub bind_addr: SocketAddr,
+    pub bind_interface: Option<String>,
 }
 
 #[async_trait]
@@ -31,7 +29,7 @@ impl ProxyUdpHandler for Handler {
     }
 
     fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
-        None
+        Some((self.address.clone(), self.port, self.bind_addr))
     }
 
     fn udp_transport_type(&self) -> UdpTransportType {
@@ -41,24 +39,35 @@ impl ProxyUdpHandler for Handler {
     async fn connect<'a>(
         &'a self,
         _sess: &'a Session,
-        _datagram: Option<Box<dyn ProxyDatagram>>,
+        datagram: Option<Box<dyn ProxyDatagram>>,
         _stream: Option<Box<dyn ProxyStream>>,
     ) -> Result<Box<dyn ProxyDatagram>> {
-        let socket = UdpSocket::bind("0.0.0.0:0").await?;
-        let (rh, sh) = socket.split();
+        // Chained after another actor, the packets go through its datagram.
+        let datagram: Box<dyn ProxyDatagram> = match datagram {
+            Some(datagram) => datagram,
+            None => Box::new(SimpleDatagram(bind_udp_socket(
+                &self.bind_addr,
+                self.bind_interface.as_deref(),
+            )?)),
+        };
+        let local_addr = datagram.local_addr().ok();
+        let (rh, sh) = datagram.split();
         let addr = SocketAddr::new(self.address.parse::<IpAddr>().unwrap(), self.port);
         Ok(Box::new(Datagram {
             recv_half: rh,
             send_half: sh,
             target: addr,
+            local_addr,
         }))
     }
 }
 
 pub struct Datagram {
-    pub recv_half: RecvHalf,
-    pub send_half: SendHalf,
+    pub recv_half: Box<dyn ProxyDatagramRecvHalf>,
+    pub send_half: Box<dyn ProxyDatagramSendHalf>,
     pub target: SocketAddr,
+    /// The local address of the datagram the halves come from, if any.
+    pub local_addr: Option<SocketAddr>,
 }
 
 impl ProxyDatagram for Datagram {
@@ -73,9 +82,12 @@ impl ProxyDatagram for Datagram {
             Box::new(DatagramSendHalf(self.send_half, self.target)),
         )
     }
+    fn local_addr(&self) -> Result<SocketAddr> {
+        self.local_addr.ok_or_else(no_local_addr)
+    }
 }
 
-pub struct DatagramRecvHalf(RecvHalf, SocketAddr);
+pub struct DatagramRecvHalf(Box<dyn ProxyDatagramRecvHalf>, SocketAddr);
 
 #[async_trait]
 impl ProxyDatagramRecvHalf for DatagramRecvHalf {
@@ -85,7 +97,7 @@ impl ProxyDatagramRecvHalf for DatagramRecvHalf {
     }
 }
 
-pub struct DatagramSendHalf(SendHalf, SocketAddr);
+pub struct DatagramSendHalf(Box<dyn ProxyDatagramSendHalf>, SocketAddr);
 
 #[async_trait]
 impl ProxyDatagramSendHalf for DatagramSendHalf {
@@ -93,3 +105,62 @@ impl ProxyDatagramSendHalf for DatagramSendHalf {
         self.0.send_to(buf, &self.1).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::testing::{Behavior, MockDatagram};
+    use crate::session::SocksAddr;
+
+    #[tokio::test]
+    async fn test_redirect_through_datagram() {
+        let handler = Handler {
+            address: "10.0.0.1".to_string(),
+            port: 5353,
+            bind_addr: "0.0.0.0:0".parse().unwrap(),
+            bind_interface: None,
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        };
+        let datagram = MockDatagram::new(Behavior::Echo);
+        let sent = datagram.sent();
+        let datagram = handler
+            .connect(&sess, Some(Box::new(datagram)), None)
+            .await
+            .ok()
+            .unwrap();
+        // No socket of its own.
+        assert!(datagram.local_addr().is_err());
+        let (mut r, mut s) = datagram.split();
+        s.send_to(b"ping", &"1.2.3.4:53".parse().unwrap())
+            .await
+            .unwrap();
+
+        let target: SocketAddr = "10.0.0.1:5353".parse().unwrap();
+        assert_eq!(*sent.lock().unwrap(), vec![(b"ping".to_vec(), target)]);
+        let mut buf = [0u8; 16];
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(from, target);
+    }
+
+    #[tokio::test]
+    async fn test_local_addr() {
+        let handler = Handler {
+            address: "127.0.0.1".to_string(),
+            port: 5353,
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            bind_interface: None,
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        };
+        let datagram = handler.connect(&sess, None, None).await.ok().unwrap();
+        let local_addr = datagram.local_addr().unwrap();
+        assert_eq!(local_addr.ip(), "127.0.0.1".parse::<IpAddr>().unwrap());
+        assert_ne!(local_addr.port(), 0);
+    }
+}
@@ -0,0 +1,31 @@
+pub mod tcp;
+pub mod udp;
+
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
+
+pub static NAME: &str = "reject";
+
+/// How rejected connections are treated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Fails the connection right away.
+    Reject,
+    /// Accepts the connection and silently discards everything sent to it,
+    /// nothing is ever received.
+    Blackhole,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "" | "reject" => Some(Mode::Reject),
+            "blackhole" => Some(Mode::Blackhole),
+            _ => None,
+        }
+    }
+}
+
+fn rejected() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "rejected")
+}
@@ -0,0 +1,107 @@
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::Mode;
+use crate::{
+    proxy::{ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+/// A stream which discards all writes and never yields any data.
+struct BlackholeStream;
+
+impl ProxyStream for BlackholeStream {}
+
+impl AsyncRead for BlackholeStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // Left to the relay timeouts to tear the connection down.
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for BlackholeStream {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub struct Handler {
+    pub mode: Mode,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        _sess: &'a Session,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        match self.mode {
+            Mode::Reject => Err(super::rejected()),
+            Mode::Blackhole => Ok(Box::new(BlackholeStream)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    fn sess() -> Session {
+        Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("ads.example.com".to_string(), 443),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reject_tcp() {
+        let handler = Handler { mode: Mode::Reject };
+        let err = handler.handle(&sess(), None).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_blackhole_tcp() {
+        let handler = Handler {
+            mode: Mode::Blackhole,
+        };
+        let mut stream = handler.handle(&sess(), None).await.unwrap();
+        stream.write_all(&[0u8; 64 * 1024]).await.unwrap();
+        stream.flush().await.unwrap();
+        let mut buf = [0u8; 16];
+        let res = tokio::time::timeout(Duration::from_millis(100), stream.read(&mut buf)).await;
+        assert!(res.is_err());
+        stream.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+
+use super::Mode;
+use crate::{
+    proxy::{
+        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
+        UdpTransportType,
+    },
+    session::Session,
+};
+
+/// A datagram which discards all packets sent and never receives any.
+struct BlackholeDatagram;
+
+impl ProxyDatagram for BlackholeDatagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        (Box::new(BlackholeDatagram), Box::new(BlackholeDatagram))
+    }
+}
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for BlackholeDatagram {
+    async fn recv_from(&mut self, _buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        futures::future::pending().await
+    }
+}
+
+#[async_trait]
+impl ProxyDatagramSendHalf for BlackholeDatagram {
+    async fn send_to(&mut self, buf: &[u8], _target: &SocketAddr) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+}
+
+pub struct Handler {
+    pub mode: Mode,
+}
+
+#[async_trait]
+impl ProxyUdpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn udp_transport_type(&self) -> UdpTransportType {
+        UdpTransportType::Packet
+    }
+
+    async fn connect<'a>(
+        &'a self,
+        _sess: &'a Session,
+        _datagram: Option<Box<dyn ProxyDatagram>>,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        match self.mode {
+            Mode::Reject => Err(super::rejected()),
+            Mode::Blackhole => Ok(Box::new(BlackholeDatagram)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::session::SocksAddr;
+
+    fn sess() -> Session {
+        Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("ads.example.com".to_string(), 443),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reject_udp() {
+        let handler = Handler { mode: Mode::Reject };
+        let err = handler.connect(&sess(), None, None).await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[tokio::test]
+    async fn test_blackhole_udp() {
+        let handler = Handler {
+            mode: Mode::Blackhole,
+        };
+        let (mut r, mut s) = handler.connect(&sess(), None, None).await.unwrap().split();
+        let target = "1.2.3.4:53".parse().unwrap();
+        assert_eq!(s.send_to(b"query", &target).await.unwrap(), 5);
+        let mut buf = [0u8; 16];
+        let res = tokio::time::timeout(Duration::from_millis(100), r.recv_from(&mut buf)).await;
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use log::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::time::{delay_for, timeout_at, Instant};
+
+use crate::proxy::{deadline::deadline as connect_deadline, ProxyError};
+
+pub mod tcp;
+pub mod udp;
+
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
+
+pub static NAME: &str = "retry";
+
+/// How a failed attempt is retried.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Number of attempts, including the first one.
+    pub attempts: u32,
+    /// Delay before the first retry, doubled for each following retry.
+    pub delay_base: Duration,
+    /// Upper bound of a single delay.
+    pub max_delay: Duration,
+    /// Time allowed for all attempts and delays together.
+    pub deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            delay_base: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            deadline: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Full jitter, a random delay between zero and the exponential backoff
+    // of the `retry`th retry.
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .delay_base
+            .checked_mul(1 << retry.min(16))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let millis = backoff.as_millis() as u64;
+        if millis == 0 {
+            return backoff;
+        }
+        Duration::from_millis(StdRng::from_entropy().gen_range(0, millis + 1))
+    }
+
+    /// Runs `attempt` until it succeeds or the attempts run out, in which
+    /// case the last error is returned.
+    pub async fn retry<T, F, Fut>(&self, mut attempt: F) -> io::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = io::Result<T>>,
+    {
+        let deadline = self.deadline.map(|d| (d, Instant::now() + d));
+        let mut retry = 0;
+        loop {
+            let res = match deadline {
+                Some((d, at)) => match timeout_at(at, attempt()).await {
+                    Ok(res) => res,
+                    Err(_) => return Err(ProxyError::Timeout(d).into()),
+                },
+                None => attempt().await,
+            };
+            let err = match res {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+            retry += 1;
+            if retry >= self.attempts {
+                return Err(err);
+            }
+            let delay = self.delay(retry - 1);
+            // Not enough time left for another attempt, either before the
+            // deadline of the policy or the one of the connect.
+            let at = deadline
+                .map(|(_, at)| at)
+                .into_iter()
+                .chain(connect_deadline())
+                .min();
+            if let Some(at) = at {
+                if Instant::now() + delay >= at {
+                    return Err(err);
+                }
+            }
+            debug!(
+                "attempt {} failed: {}, retrying in {}ms",
+                retry,
+                err,
+                delay.as_millis()
+            );
+            delay_for(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn policy(attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            attempts,
+            delay_base: Duration::from_millis(10),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_success() {
+        let calls = &AtomicU32::new(0);
+        let res = policy(3)
+            .retry(move || async move {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+                } else {
+                    Ok(1)
+                }
+            })
+            .await;
+        assert_eq!(res.unwrap(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_exhausted() {
+        let calls = &AtomicU32::new(0);
+        let res: io::Result<()> = policy(3)
+            .retry(move || async move {
+                let n = calls.fetch_add(1, Ordering::SeqCst);
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("attempt {}", n),
+                ))
+            })
+            .await;
+        assert_eq!(res.unwrap_err().to_string(), "attempt 2");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_deadline() {
+        let calls = &AtomicU32::new(0);
+        let policy = RetryPolicy {
+            attempts: 100,
+            delay_base: Duration::from_millis(50),
+            max_delay: Duration::from_millis(50),
+            deadline: Some(Duration::from_millis(300)),
+        };
+        let start = Instant::now();
+        let res: io::Result<()> = policy
+            .retry(move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+            })
+            .await;
+        assert!(start.elapsed() < Duration::from_millis(400));
+        assert!(calls.load(Ordering::SeqCst) < 100);
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+
+        // An attempt outliving the deadline is cut short.
+        let res: io::Result<()> = policy
+            .retry(move || async move {
+                delay_for(Duration::from_secs(10)).await;
+                Ok(())
+            })
+            .await;
+        let err = res.unwrap_err();
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Timeout(_))
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use std::net::SocketAddr;
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+
+use super::RetryPolicy;
+use crate::{
+    proxy::{ProxyHandler, ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+pub struct Handler {
+    pub actor: Arc<dyn ProxyHandler>,
+    pub policy: RetryPolicy,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        self.policy
+            .retry(move || self.actor.handle(sess, None))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::proxy::{
+        stream::SimpleStream, Handler as ProxyHandlerImpl, ProxyDatagram, ProxyHandlerType,
+        ProxyUdpHandler, UdpTransportType,
+    };
+    use crate::session::SocksAddr;
+
+    // Fails the first `failures` attempts.
+    struct Flaky {
+        failures: u32,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(io::ErrorKind::ConnectionRefused.into());
+            }
+            Ok(Box::new(SimpleStream(std::io::Cursor::new(Vec::new()))) as Box<dyn ProxyStream>)
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Err(io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    fn retry_handler(failures: u32, attempts: u32) -> (Handler, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let actor = ProxyHandlerImpl::new(
+            "flaky".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Flaky {
+                failures,
+                calls: calls.clone(),
+            }),
+            Box::new(Flaky {
+                failures,
+                calls: calls.clone(),
+            }),
+        );
+        let handler = Handler {
+            actor,
+            policy: RetryPolicy {
+                attempts,
+                delay_base: Duration::from_millis(10),
+                ..Default::default()
+            },
+        };
+        (handler, calls)
+    }
+
+    fn sess() -> Session {
+        Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_tcp_success() {
+        let (handler, calls) = retry_handler(2, 3);
+        assert!(handler.handle(&sess(), None).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_tcp_exhausted() {
+        let (handler, calls) = retry_handler(5, 3);
+        let err = match handler.handle(&sess(), None).await {
+            Ok(_) => panic!("handled with a failing actor"),
+            Err(e) => e,
+        };
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
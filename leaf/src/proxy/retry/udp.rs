@@ -0,0 +1,41 @@
+use std::net::SocketAddr;
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+
+use super::RetryPolicy;
+use crate::{
+    proxy::{ProxyDatagram, ProxyHandler, ProxyStream, ProxyUdpHandler, UdpTransportType},
+    session::Session,
+};
+
+pub struct Handler {
+    pub actor: Arc<dyn ProxyHandler>,
+    pub policy: RetryPolicy,
+}
+
+#[async_trait]
+impl ProxyUdpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn udp_transport_type(&self) -> UdpTransportType {
+        UdpTransportType::Unknown
+    }
+
+    async fn connect<'a>(
+        &'a self,
+        sess: &'a Session,
+        _datagram: Option<Box<dyn ProxyDatagram>>,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        self.policy
+            .retry(move || self.actor.connect(sess, None, None))
+            .await
+    }
+}
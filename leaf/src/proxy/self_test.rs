@@ -0,0 +1,309 @@
+//! Self-tests of handlers, for embedders to check a configuration works,
+//! e.g. behind a "test" button.
+
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::time::{timeout, Instant};
+use trust_dns_proto::op::{header::MessageType, op_code::OpCode, query::Query, Message};
+use trust_dns_proto::rr::{record_type::RecordType, Name};
+
+use super::{ProxyTcpHandler, ProxyUdpHandler};
+use crate::session::{Session, SocksAddr};
+
+/// The User-Agent of self-test and health check requests unless configured
+/// otherwise.
+pub static DEFAULT_USER_AGENT: &str = concat!("leaf/", env!("CARGO_PKG_VERSION"));
+
+/// Time allowed for a self-test.
+pub const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The outcome of a self-test.
+#[derive(Clone, Debug)]
+pub struct TestResult {
+    /// The time it took to get a response from the target, or the error
+    /// which stopped the test.
+    pub outcome: Result<Duration, String>,
+    /// The results of the actors of an ensemble, along with their tags.
+    pub actors: Vec<(String, TestResult)>,
+}
+
+impl TestResult {
+    pub fn ok(latency: Duration) -> Self {
+        TestResult {
+            outcome: Ok(latency),
+            actors: Vec::new(),
+        }
+    }
+
+    pub fn failed<E: ToString>(error: E) -> Self {
+        TestResult {
+            outcome: Err(error.to_string()),
+            actors: Vec::new(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.outcome.is_ok()
+    }
+
+    /// Combines the results of the actors of an ensemble, which works as
+    /// fast as its fastest working actor, and fails if none works.
+    pub fn aggregate(actors: Vec<(String, TestResult)>) -> Self {
+        let fastest = actors
+            .iter()
+            .filter_map(|(_, r)| r.outcome.as_ref().ok())
+            .min()
+            .cloned();
+        let outcome = match fastest {
+            Some(latency) => Ok(latency),
+            None if actors.is_empty() => Err("no actors".to_string()),
+            None => Err(actors
+                .iter()
+                .map(|(tag, r)| format!("[{}] {}", tag, r.outcome.as_ref().unwrap_err()))
+                .collect::<Vec<String>>()
+                .join(", ")),
+        };
+        TestResult { outcome, actors }
+    }
+
+    fn from_timed(start: Instant, res: Result<io::Result<()>, tokio::time::Elapsed>) -> Self {
+        match res {
+            Ok(Ok(())) => TestResult::ok(start.elapsed()),
+            Ok(Err(e)) => TestResult::failed(e),
+            Err(_) => TestResult::failed("timed out"),
+        }
+    }
+}
+
+/// The target of TCP self-tests, the same as of health checks.
+pub fn tcp_target() -> SocksAddr {
+    SocksAddr::Domain("www.google.com".to_string(), 80)
+}
+
+/// The DNS server queried by UDP self-tests.
+pub fn udp_target() -> SocksAddr {
+    SocksAddr::Ip("8.8.8.8:53".parse().unwrap())
+}
+
+fn session(destination: &SocksAddr) -> Session {
+    Session {
+        source: "0.0.0.0:0".parse().unwrap(),
+        destination: destination.clone(),
+    }
+}
+
+/// Connects to the HTTP server `target` with `handler` and requests its
+/// root, the test passes once a status line shows the server was reached.
+pub async fn test_tcp<H: ProxyTcpHandler + ?Sized>(handler: &H, target: &SocksAddr) -> TestResult {
+    let sess = session(target);
+    let start = Instant::now();
+    let res = timeout(SELF_TEST_TIMEOUT, async {
+        let mut stream = handler.handle(&sess, None).await?;
+        let request = http_request("HEAD", target, "/", DEFAULT_USER_AGENT);
+        stream.write_all(request.as_bytes()).await?;
+        match read_status_line(&mut stream).await? {
+            code if is_reachable(code) => Ok(()),
+            code => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unexpected status {}", code),
+            )),
+        }
+    })
+    .await;
+    TestResult::from_timed(start, res)
+}
+
+/// Sends a DNS query to the server `target` with `handler`, the test passes
+/// once a response comes back.
+pub async fn test_udp<H: ProxyUdpHandler + ?Sized>(handler: &H, target: &SocksAddr) -> TestResult {
+    let sess = session(target);
+    let start = Instant::now();
+    let res = timeout(SELF_TEST_TIMEOUT, async {
+        let server = match target {
+            SocksAddr::Ip(a) => *a,
+            SocksAddr::Domain(..) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "dns server must be an ip",
+                ))
+            }
+        };
+        let datagram = handler.connect(&sess, None, None).await?;
+        let (mut recv, mut send) = datagram.split();
+        let mut msg = Message::new();
+        msg.add_query(Query::query(
+            Name::from_str("www.google.com.").unwrap(),
+            RecordType::A,
+        ));
+        msg.set_id(rand::random())
+            .set_op_code(OpCode::Query)
+            .set_message_type(MessageType::Query)
+            .set_recursion_desired(true);
+        let query = msg
+            .to_vec()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        send.send_to(&query, &server).await?;
+        let mut buf = [0u8; 512];
+        let (n, _) = recv.recv_from(&mut buf).await?;
+        match Message::from_vec(&buf[..n]) {
+            Ok(resp) if resp.id() == msg.id() => Ok(()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid dns response",
+            )),
+        }
+    })
+    .await;
+    TestResult::from_timed(start, res)
+}
+
+// A plain `method` request for `path` on `destination`.
+pub(crate) fn http_request(
+    method: &str,
+    destination: &SocksAddr,
+    path: &str,
+    user_agent: &str,
+) -> String {
+    let host = if destination.port() == 80 {
+        destination.host()
+    } else {
+        destination.to_string()
+    };
+    format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nConnection: close\r\n\r\n",
+        method, path, host, user_agent
+    )
+}
+
+// Whether a response with status `code` shows the destination was reached,
+// errors of the server itself or of a proxy on the way don't count.
+pub(crate) fn is_reachable(code: u16) -> bool {
+    (200..500).contains(&code)
+}
+
+// Reads the status line of a response, e.g. `HTTP/1.1 204 No Content`,
+// returns the status code.
+pub(crate) async fn read_status_line<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<u16> {
+    let mut line = Vec::new();
+    let mut buf = [0u8; 1];
+    while !line.ends_with(b"\r\n") {
+        if line.len() > 1024 {
+            break;
+        }
+        stream.read_exact(&mut buf).await?;
+        line.push(buf[0]);
+    }
+    let line = String::from_utf8_lossy(&line);
+    let mut parts = line.split(' ');
+    match (
+        parts.next(),
+        parts.next().map(|code| (code.len(), code.parse::<u16>())),
+    ) {
+        (Some(version), Some((3, Ok(code)))) if version.starts_with("HTTP/1.") => Ok(code),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid status line",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use async_trait::async_trait;
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::testing::{udp_actor, Behavior, MockUdpHandler};
+    use crate::proxy::{ProxyStream, SimpleStream};
+
+    // Dials `addr` whatever the destination, or refuses if none.
+    struct Dial(Option<SocketAddr>);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Dial {
+        fn name(&self) -> &str {
+            "dial"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            match self.0 {
+                Some(addr) => Ok(Box::new(SimpleStream(TcpStream::connect(addr).await?))),
+                None => Err(io::Error::new(io::ErrorKind::ConnectionRefused, "refused")),
+            }
+        }
+    }
+
+    // Starts an HTTP server answering every request with `reply`.
+    async fn server(reply: &'static [u8]) -> SocketAddr {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream.write_all(reply).await;
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_tcp_self_test() {
+        let addr = server(b"HTTP/1.1 204 No Content\r\n\r\n").await;
+        let target = SocksAddr::Ip(addr);
+        let res = test_tcp(&Dial(Some(addr)), &target).await;
+        assert!(res.is_ok(), "{:?}", res);
+
+        let res = test_tcp(&Dial(None), &target).await;
+        assert_eq!(res.outcome.unwrap_err(), "refused");
+
+        // Reached a proxy, not the target.
+        let addr = server(b"HTTP/1.1 502 Bad Gateway\r\n\r\n").await;
+        let res = test_tcp(&Dial(Some(addr)), &target).await;
+        assert_eq!(res.outcome.unwrap_err(), "unexpected status 502");
+    }
+
+    #[tokio::test]
+    async fn test_udp_self_test() {
+        // The query is echoed back, which will do for a response.
+        let actor = udp_actor("echo", MockUdpHandler::new(Behavior::Echo));
+        let res = ProxyUdpHandler::self_test(&*actor).await;
+        assert!(res.is_ok(), "{:?}", res);
+
+        let actor = udp_actor("refusing", MockUdpHandler::refusing());
+        let res = ProxyUdpHandler::self_test(&*actor).await;
+        assert!(res.outcome.unwrap_err().contains("refused"));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let ms = Duration::from_millis;
+        let res = TestResult::aggregate(vec![
+            ("a".to_string(), TestResult::failed("refused")),
+            ("b".to_string(), TestResult::ok(ms(30))),
+            ("c".to_string(), TestResult::ok(ms(20))),
+        ]);
+        assert_eq!(res.outcome, Ok(ms(20)));
+        assert_eq!(res.actors.len(), 3);
+
+        let res = TestResult::aggregate(vec![
+            ("a".to_string(), TestResult::failed("refused")),
+            ("b".to_string(), TestResult::failed("timed out")),
+        ]);
+        assert_eq!(res.outcome.unwrap_err(), "[a] refused, [b] timed out");
+    }
+}
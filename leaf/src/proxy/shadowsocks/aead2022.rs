@@ -0,0 +1,997 @@
+//! Shadowsocks 2022 (SIP022) framing.
+//!
+//! Compared to the classic AEAD construction, session keys are derived with
+//! BLAKE3, headers carry a timestamp, and responses are bound to requests, so
+//! replayed or reflected traffic is rejected.
+
+use std::{
+    cmp::min,
+    collections::HashMap,
+    convert::TryFrom,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use aes::block_cipher::generic_array::GenericArray;
+use aes::{Aes128, Aes256, BlockCipher, NewBlockCipher};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use byteorder::{BigEndian, ByteOrder};
+use bytes::{BufMut, BytesMut};
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use lazy_static::lazy_static;
+use log::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::common::crypto::{
+    aead::{AeadCipher, AeadDecryptor, AeadEncryptor},
+    Cipher, Decryptor, Encryptor, NonceSequence, SizedCipher,
+};
+use crate::proxy::{ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf};
+use crate::session::{SocksAddr, SocksAddrWireType};
+
+use super::crypto::{aead_2022_algorithm, psk_2022, session_subkey, ShadowsocksNonceSequence};
+
+const HEADER_TYPE_CLIENT: u8 = 0;
+const HEADER_TYPE_SERVER: u8 = 1;
+
+// Maximum difference allowed between the timestamp of a header and the local
+// clock, in seconds.
+const MAX_TIME_DIFF: u64 = 30;
+
+// Salts must be remembered for at least as long as their timestamps are
+// accepted, in both directions.
+const SALT_WINDOW: Duration = Duration::from_secs(2 * MAX_TIME_DIFF);
+
+const MAX_PADDING: usize = 900;
+const MAX_CHUNK_SIZE: usize = 0xffff;
+const MAX_SOCKS_ADDR_SIZE: usize = 1 + 1 + 255 + 2;
+
+lazy_static! {
+    static ref SALT_FILTER: SaltFilter = SaltFilter::new(SALT_WINDOW);
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn check_timestamp(ts: u64) -> io::Result<()> {
+    let now = unix_timestamp();
+    let diff = if now > ts { now - ts } else { ts - now };
+    if diff > MAX_TIME_DIFF {
+        return Err(invalid_data("timestamp out of range"));
+    }
+    Ok(())
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "early eof")
+}
+
+fn crypto_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "crypto error")
+}
+
+/// Remembers recently seen salts.
+pub struct SaltFilter {
+    window: Duration,
+    salts: Mutex<HashMap<Vec<u8>, Instant>>,
+}
+
+impl SaltFilter {
+    pub fn new(window: Duration) -> Self {
+        SaltFilter {
+            window,
+            salts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns false if the salt has already been seen within the window.
+    pub fn check_and_insert(&self, salt: &[u8]) -> bool {
+        let now = Instant::now();
+        let window = self.window;
+        let mut salts = self.salts.lock().unwrap();
+        salts.retain(|_, seen| now.duration_since(*seen) < window);
+        if salts.contains_key(salt) {
+            return false;
+        }
+        salts.insert(salt.to_vec(), now);
+        true
+    }
+}
+
+enum ReadState {
+    WaitingSalt,
+    WaitingHeader,
+    WaitingLength,
+    WaitingData(usize),
+    PendingData(usize),
+}
+
+enum WriteState {
+    WaitingHeader,
+    // Holds the consumed payload length if the header is sent by a write.
+    PendingHeader(Option<usize>),
+    WaitingChunk,
+    PendingChunk(usize),
+}
+
+/// A TCP stream of a Shadowsocks 2022 method.
+///
+/// The request header is sent along with the first write, or with random
+/// padding upon flush if nothing has been written yet.
+pub struct ShadowedStream2022<T> {
+    inner: T,
+    cipher: AeadCipher,
+    psk: Vec<u8>,
+    target: BytesMut,
+    request_salt: Vec<u8>,
+    enc: Option<AeadEncryptor<ShadowsocksNonceSequence>>,
+    dec: Option<AeadDecryptor<ShadowsocksNonceSequence>>,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+    read_state: ReadState,
+    write_state: WriteState,
+    read_pos: usize,
+}
+
+impl<T> ShadowedStream2022<T> {
+    pub fn new(s: T, method: &str, password: &str, target: &SocksAddr) -> Result<Self> {
+        let cipher = AeadCipher::new(aead_2022_algorithm(method)?)?;
+        let psk = psk_2022(password, cipher.key_len())?;
+        let mut addr = BytesMut::with_capacity(target.size());
+        target.write_buf(&mut addr, SocksAddrWireType::PortLast)?;
+        Ok(ShadowedStream2022 {
+            inner: s,
+            cipher,
+            psk,
+            target: addr,
+            request_salt: Vec::new(),
+            enc: None,
+            dec: None,
+            read_buf: BytesMut::with_capacity(MAX_CHUNK_SIZE + 0x20),
+            write_buf: BytesMut::new(),
+            read_state: ReadState::WaitingSalt,
+            write_state: WriteState::WaitingHeader,
+            read_pos: 0,
+        })
+    }
+
+    // Seals the request header into the write buffer, returns the length of
+    // the payload taken from `payload`.
+    fn encode_request(&mut self, payload: &[u8]) -> io::Result<usize> {
+        let mut rng = StdRng::from_entropy();
+        let tag_len = self.cipher.tag_len();
+        let mut salt = vec![0u8; self.cipher.key_len()];
+        rng.fill(&mut salt[..]);
+        let key = session_subkey(&self.psk, &salt);
+        let nonce = ShadowsocksNonceSequence::new(self.cipher.nonce_len());
+        let mut enc = self
+            .cipher
+            .encryptor(&key, nonce)
+            .map_err(|_| crypto_err())?;
+
+        // A request without payload must be padded.
+        let padding_len = if payload.is_empty() {
+            rng.gen_range(1, MAX_PADDING + 1)
+        } else {
+            0
+        };
+        let consumed = min(
+            payload.len(),
+            MAX_CHUNK_SIZE - self.target.len() - 2 - padding_len,
+        );
+        let mut var_header =
+            BytesMut::with_capacity(self.target.len() + 2 + padding_len + consumed + tag_len);
+        var_header.put_slice(&self.target);
+        var_header.put_u16(padding_len as u16);
+        for _ in 0..padding_len {
+            var_header.put_u8(rng.gen());
+        }
+        var_header.put_slice(&payload[..consumed]);
+
+        let mut fixed_header = BytesMut::with_capacity(1 + 8 + 2 + tag_len);
+        fixed_header.put_u8(HEADER_TYPE_CLIENT);
+        fixed_header.put_u64(unix_timestamp());
+        fixed_header.put_u16(var_header.len() as u16);
+
+        enc.encrypt(&mut fixed_header).map_err(|_| crypto_err())?;
+        enc.encrypt(&mut var_header).map_err(|_| crypto_err())?;
+
+        self.write_buf
+            .reserve(salt.len() + fixed_header.len() + var_header.len());
+        self.write_buf.put_slice(&salt);
+        self.write_buf.put_slice(&fixed_header);
+        self.write_buf.put_slice(&var_header);
+        self.request_salt = salt;
+        self.enc.replace(enc);
+        Ok(consumed)
+    }
+}
+
+impl<T> ShadowedStream2022<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read_exact(&mut self, cx: &mut Context, size: usize) -> Poll<io::Result<()>> {
+        self.read_buf.reserve(size);
+        unsafe { self.read_buf.set_len(size) };
+        loop {
+            if self.read_pos < size {
+                let n =
+                    ready!(Pin::new(&mut self.inner)
+                        .poll_read(cx, &mut self.read_buf[self.read_pos..]))?;
+                self.read_pos += n;
+                if n == 0 {
+                    return Err(eof()).into();
+                }
+            }
+            if self.read_pos >= size {
+                self.read_pos = 0;
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl<T> ShadowedStream2022<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write_pending(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            let nw = ready!(Pin::new(&mut self.inner).poll_write_buf(cx, &mut self.write_buf))?;
+            if nw == 0 {
+                return Err(eof()).into();
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> AsyncRead for ShadowedStream2022<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match self.read_state {
+                ReadState::WaitingSalt => {
+                    let me = &mut *self;
+                    let salt_size = me.cipher.key_len();
+                    ready!(me.poll_read_exact(cx, salt_size))?;
+                    let salt = &me.read_buf[..salt_size];
+                    if !SALT_FILTER.check_and_insert(salt) {
+                        return Err(invalid_data("replayed salt")).into();
+                    }
+                    let key = session_subkey(&me.psk, salt);
+                    let nonce = ShadowsocksNonceSequence::new(me.cipher.nonce_len());
+                    let dec = me.cipher.decryptor(&key, nonce).map_err(|_| crypto_err())?;
+                    me.dec.replace(dec);
+                    me.read_buf.clear();
+                    me.read_state = ReadState::WaitingHeader;
+                }
+                ReadState::WaitingHeader => {
+                    // type, timestamp, request salt and length of the first chunk
+                    let me = &mut *self;
+                    let salt_size = me.cipher.key_len();
+                    let read_size = 1 + 8 + salt_size + 2 + me.cipher.tag_len();
+                    ready!(me.poll_read_exact(cx, read_size))?;
+                    let dec = me.dec.as_mut().expect("uninitialized cipher");
+                    dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    if me.read_buf[0] != HEADER_TYPE_SERVER {
+                        return Err(invalid_data("invalid header type")).into();
+                    }
+                    check_timestamp(BigEndian::read_u64(&me.read_buf[1..9]))?;
+                    if me.read_buf[9..9 + salt_size] != me.request_salt[..] {
+                        return Err(invalid_data("request salt mismatch")).into();
+                    }
+                    let payload_len = BigEndian::read_u16(&me.read_buf[9 + salt_size..]) as usize;
+                    me.read_state = ReadState::WaitingData(payload_len);
+                }
+                ReadState::WaitingLength => {
+                    let me = &mut *self;
+                    let read_size = 2 + me.cipher.tag_len();
+                    ready!(me.poll_read_exact(cx, read_size))?;
+                    let dec = me.dec.as_mut().expect("uninitialized cipher");
+                    dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    let payload_len = BigEndian::read_u16(&me.read_buf) as usize;
+                    me.read_state = ReadState::WaitingData(payload_len);
+                }
+                ReadState::WaitingData(n) => {
+                    let me = &mut *self;
+                    let read_size = n + me.cipher.tag_len();
+                    ready!(me.poll_read_exact(cx, read_size))?;
+                    let dec = me.dec.as_mut().expect("uninitialized cipher");
+                    dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    // The first chunk may be empty, which must not be taken as EOF.
+                    if n == 0 {
+                        me.read_state = ReadState::WaitingLength;
+                    } else {
+                        me.read_state = ReadState::PendingData(n);
+                    }
+                }
+                ReadState::PendingData(n) => {
+                    let to_read = min(buf.len(), n);
+                    let payload = self.read_buf.split_to(to_read);
+                    (&mut buf[..to_read]).copy_from_slice(&payload);
+                    if to_read < n {
+                        self.read_state = ReadState::PendingData(n - to_read);
+                    } else {
+                        self.read_state = ReadState::WaitingLength;
+                    }
+                    return Poll::Ready(Ok(to_read));
+                }
+            }
+        }
+    }
+}
+
+impl<T> AsyncWrite for ShadowedStream2022<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match self.write_state {
+                WriteState::WaitingHeader => {
+                    let consumed = self.encode_request(buf)?;
+                    self.write_state = WriteState::PendingHeader(Some(consumed));
+                }
+                WriteState::PendingHeader(consumed) => {
+                    ready!(self.poll_write_pending(cx))?;
+                    self.write_state = WriteState::WaitingChunk;
+                    if let Some(consumed) = consumed {
+                        return Poll::Ready(Ok(consumed));
+                    }
+                }
+                WriteState::WaitingChunk => {
+                    if buf.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    let me = &mut *self;
+                    let consume_len = min(buf.len(), MAX_CHUNK_SIZE);
+                    let tag_len = me.cipher.tag_len();
+                    let enc = me.enc.as_mut().expect("uninitialized cipher");
+
+                    let mut length = BytesMut::with_capacity(2 + tag_len);
+                    length.put_u16(consume_len as u16);
+                    enc.encrypt(&mut length).map_err(|_| crypto_err())?;
+
+                    let mut payload = BytesMut::with_capacity(consume_len + tag_len);
+                    payload.put_slice(&buf[..consume_len]);
+                    enc.encrypt(&mut payload).map_err(|_| crypto_err())?;
+
+                    me.write_buf.reserve(length.len() + payload.len());
+                    me.write_buf.put_slice(&length);
+                    me.write_buf.put_slice(&payload);
+                    me.write_state = WriteState::PendingChunk(consume_len);
+                }
+                WriteState::PendingChunk(consumed) => {
+                    // Same as the classic stream, the caller is expected to
+                    // retry with the same buffer upon pending.
+                    ready!(self.poll_write_pending(cx))?;
+                    self.write_state = WriteState::WaitingChunk;
+                    return Poll::Ready(Ok(consumed));
+                }
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        if let WriteState::WaitingHeader = me.write_state {
+            me.encode_request(&[])?;
+            me.write_state = WriteState::PendingHeader(None);
+        }
+        // A header carrying payload is left to the pending write.
+        if let WriteState::PendingHeader(None) = me.write_state {
+            ready!(me.poll_write_pending(cx))?;
+            me.write_state = WriteState::WaitingChunk;
+        }
+        Pin::new(&mut me.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Sliding window over the packet IDs of a session, rejects duplicated
+/// packets and packets too old to tell.
+#[derive(Default)]
+pub struct PacketWindow {
+    last: u64,
+    bitmap: u128,
+    initialized: bool,
+}
+
+impl PacketWindow {
+    const SIZE: u64 = 128;
+
+    /// Returns false if the packet ID has been seen or falls behind the window.
+    pub fn check_and_update(&mut self, id: u64) -> bool {
+        if !self.initialized {
+            self.initialized = true;
+            self.last = id;
+            self.bitmap = 1;
+            return true;
+        }
+        if id > self.last {
+            let shift = id - self.last;
+            self.bitmap = if shift >= Self::SIZE {
+                0
+            } else {
+                self.bitmap << shift
+            };
+            self.bitmap |= 1;
+            self.last = id;
+            return true;
+        }
+        let offset = self.last - id;
+        if offset >= Self::SIZE {
+            return false;
+        }
+        let mask = 1u128 << offset;
+        if self.bitmap & mask != 0 {
+            return false;
+        }
+        self.bitmap |= mask;
+        true
+    }
+}
+
+// Encrypts the separate header of UDP packets, a single AES block.
+enum HeaderCipher {
+    Aes128(Aes128),
+    Aes256(Aes256),
+}
+
+impl HeaderCipher {
+    fn new(psk: &[u8]) -> Result<Self> {
+        match psk.len() {
+            16 => Ok(HeaderCipher::Aes128(Aes128::new(GenericArray::from_slice(
+                psk,
+            )))),
+            32 => Ok(HeaderCipher::Aes256(Aes256::new(GenericArray::from_slice(
+                psk,
+            )))),
+            n => Err(anyhow!("invalid psk length {}", n)),
+        }
+    }
+
+    fn encrypt(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            HeaderCipher::Aes128(c) => c.encrypt_block(block),
+            HeaderCipher::Aes256(c) => c.encrypt_block(block),
+        }
+    }
+
+    fn decrypt(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            HeaderCipher::Aes128(c) => c.decrypt_block(block),
+            HeaderCipher::Aes256(c) => c.decrypt_block(block),
+        }
+    }
+}
+
+// The nonce of a UDP packet is taken from its separate header, so it's used
+// exactly once.
+struct FixedNonce(Vec<u8>);
+
+impl NonceSequence for FixedNonce {
+    fn advance(&mut self) -> Result<Vec<u8>> {
+        Ok(self.0.clone())
+    }
+}
+
+struct UdpSession {
+    cipher: AeadCipher,
+    psk: Vec<u8>,
+    header_cipher: HeaderCipher,
+    session_id: u64,
+    session_key: Vec<u8>,
+}
+
+/// A UDP association of a Shadowsocks 2022 method.
+///
+/// All packets are sent to the server, with the target address carried in
+/// the packet.
+pub struct ShadowedDatagram2022 {
+    inner: Box<dyn ProxyDatagram>,
+    session: Arc<UdpSession>,
+    server: SocketAddr,
+}
+
+impl ShadowedDatagram2022 {
+    pub fn new(
+        inner: Box<dyn ProxyDatagram>,
+        method: &str,
+        password: &str,
+        server: SocketAddr,
+    ) -> Result<Self> {
+        let cipher = AeadCipher::new(aead_2022_algorithm(method)?)?;
+        let psk = psk_2022(password, cipher.key_len())?;
+        let header_cipher = HeaderCipher::new(&psk)?;
+        let session_id: u64 = StdRng::from_entropy().gen();
+        let session_key = session_subkey(&psk, &session_id.to_be_bytes());
+        Ok(ShadowedDatagram2022 {
+            inner,
+            session: Arc::new(UdpSession {
+                cipher,
+                psk,
+                header_cipher,
+                session_id,
+                session_key,
+            }),
+            server,
+        })
+    }
+}
+
+impl ProxyDatagram for ShadowedDatagram2022 {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        let (r, s) = self.inner.split();
+        (
+            Box::new(ShadowedDatagram2022RecvHalf {
+                half: r,
+                session: self.session.clone(),
+                windows: HashMap::new(),
+            }),
+            Box::new(ShadowedDatagram2022SendHalf {
+                half: s,
+                session: self.session,
+                server: self.server,
+                packet_id: 0,
+            }),
+        )
+    }
+}
+
+pub struct ShadowedDatagram2022RecvHalf {
+    half: Box<dyn ProxyDatagramRecvHalf>,
+    session: Arc<UdpSession>,
+    // Replay windows of server sessions.
+    windows: HashMap<u64, PacketWindow>,
+}
+
+impl ShadowedDatagram2022RecvHalf {
+    // Returns None if the packet is a replay.
+    fn open(&mut self, pkt: &mut [u8], buf: &mut [u8]) -> io::Result<Option<(usize, SocketAddr)>> {
+        let s = &self.session;
+        let tag_len = s.cipher.tag_len();
+        if pkt.len() < 16 + tag_len {
+            return Err(invalid_data("short packet"));
+        }
+        s.header_cipher.decrypt(&mut pkt[..16]);
+        let server_session_id = BigEndian::read_u64(&pkt[..8]);
+        let packet_id = BigEndian::read_u64(&pkt[8..16]);
+        let key = session_subkey(&s.psk, &pkt[..8]);
+        let mut dec = s
+            .cipher
+            .decryptor(&key, FixedNonce(pkt[4..16].to_vec()))
+            .map_err(|_| crypto_err())?;
+        let mut body = BytesMut::from(&pkt[16..]);
+        dec.decrypt(&mut body).map_err(|_| crypto_err())?;
+        let body = &body[..body.len() - tag_len];
+
+        // type, timestamp, client session ID and padding length
+        if body.len() < 1 + 8 + 8 + 2 {
+            return Err(invalid_data("short packet"));
+        }
+        if body[0] != HEADER_TYPE_SERVER {
+            return Err(invalid_data("invalid header type"));
+        }
+        check_timestamp(BigEndian::read_u64(&body[1..9]))?;
+        if BigEndian::read_u64(&body[9..17]) != s.session_id {
+            return Err(invalid_data("client session id mismatch"));
+        }
+        let addr_pos = 19 + BigEndian::read_u16(&body[17..19]) as usize;
+        if addr_pos > body.len() {
+            return Err(invalid_data("short packet"));
+        }
+        let addr = SocksAddr::try_from((&body[addr_pos..], SocksAddrWireType::PortLast))
+            .map_err(|e| invalid_data(&format!("invalid remote address: {}", e)))?;
+        let addr = match addr {
+            SocksAddr::Ip(addr) => addr,
+            _ => {
+                return Err(invalid_data(
+                    "udp receiving domain address is not supported",
+                ))
+            }
+        };
+
+        // Only authenticated packets may move the window.
+        if !self
+            .windows
+            .entry(server_session_id)
+            .or_default()
+            .check_and_update(packet_id)
+        {
+            return Ok(None);
+        }
+
+        let payload = &body[addr_pos + SocksAddr::from(addr).size()..];
+        let to_write = min(payload.len(), buf.len());
+        if to_write < payload.len() {
+            warn!("truncated udp packet, please report this issue");
+        }
+        buf[..to_write].copy_from_slice(&payload[..to_write]);
+        Ok(Some((to_write, addr)))
+    }
+}
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for ShadowedDatagram2022RecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let overhead =
+            16 + 1 + 8 + 8 + 2 + MAX_PADDING + MAX_SOCKS_ADDR_SIZE + self.session.cipher.tag_len();
+        let mut pkt = vec![0u8; buf.len() + overhead];
+        loop {
+            let (n, _) = self.half.recv_from(&mut pkt).await?;
+            match self.open(&mut pkt[..n], buf)? {
+                Some(res) => return Ok(res),
+                None => debug!("dropped replayed shadowsocks packet"),
+            }
+        }
+    }
+}
+
+pub struct ShadowedDatagram2022SendHalf {
+    half: Box<dyn ProxyDatagramSendHalf>,
+    session: Arc<UdpSession>,
+    server: SocketAddr,
+    packet_id: u64,
+}
+
+#[async_trait]
+impl ProxyDatagramSendHalf for ShadowedDatagram2022SendHalf {
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        let s = &self.session;
+        let tag_len = s.cipher.tag_len();
+        let target = SocksAddr::from(target);
+
+        let mut header = [0u8; 16];
+        BigEndian::write_u64(&mut header[..8], s.session_id);
+        BigEndian::write_u64(&mut header[8..], self.packet_id);
+        self.packet_id += 1;
+
+        let mut body = BytesMut::with_capacity(1 + 8 + 2 + target.size() + buf.len() + tag_len);
+        body.put_u8(HEADER_TYPE_CLIENT);
+        body.put_u64(unix_timestamp());
+        body.put_u16(0);
+        target.write_buf(&mut body, SocksAddrWireType::PortLast)?;
+        body.put_slice(buf);
+        let mut enc = s
+            .cipher
+            .encryptor(&s.session_key, FixedNonce(header[4..].to_vec()))
+            .map_err(|_| crypto_err())?;
+        enc.encrypt(&mut body).map_err(|_| crypto_err())?;
+        s.header_cipher.encrypt(&mut header);
+
+        let mut pkt = BytesMut::with_capacity(header.len() + body.len());
+        pkt.put_slice(&header);
+        pkt.put_slice(&body);
+        self.half.send_to(&pkt, &self.server).await?;
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+
+    use super::*;
+    use crate::proxy::SimpleDatagram;
+
+    const METHOD: &str = "2022-blake3-aes-256-gcm";
+    const PSK: [u8; 32] = [7u8; 32];
+
+    fn password() -> String {
+        base64::encode(&PSK)
+    }
+
+    fn cipher() -> AeadCipher {
+        AeadCipher::new("aes-256-gcm").unwrap()
+    }
+
+    fn nonce() -> ShadowsocksNonceSequence {
+        ShadowsocksNonceSequence::new(12)
+    }
+
+    fn random_salt() -> Vec<u8> {
+        let mut salt = vec![0u8; 32];
+        StdRng::from_entropy().fill(&mut salt[..]);
+        salt
+    }
+
+    async fn read_sealed<D: Decryptor>(stream: &mut TcpStream, dec: &mut D, n: usize) -> Vec<u8> {
+        let mut buf = vec![0u8; n + 16];
+        stream.read_exact(&mut buf).await.unwrap();
+        dec.decrypt(&mut buf).unwrap();
+        buf.truncate(n);
+        buf
+    }
+
+    struct Request {
+        salt: Vec<u8>,
+        target: SocksAddr,
+        padding_len: usize,
+        payload: Vec<u8>,
+        dec: AeadDecryptor<ShadowsocksNonceSequence>,
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> Request {
+        let mut salt = vec![0u8; 32];
+        stream.read_exact(&mut salt).await.unwrap();
+        let mut dec = cipher()
+            .decryptor(&session_subkey(&PSK, &salt), nonce())
+            .unwrap();
+        let fixed = read_sealed(stream, &mut dec, 1 + 8 + 2).await;
+        assert_eq!(fixed[0], HEADER_TYPE_CLIENT);
+        check_timestamp(BigEndian::read_u64(&fixed[1..9])).unwrap();
+        let var_len = BigEndian::read_u16(&fixed[9..]) as usize;
+        let var = read_sealed(stream, &mut dec, var_len).await;
+        let target = SocksAddr::try_from((&var[..], SocksAddrWireType::PortLast)).unwrap();
+        let pos = target.size();
+        let padding_len = BigEndian::read_u16(&var[pos..]) as usize;
+        let payload = var[pos + 2 + padding_len..].to_vec();
+        Request {
+            salt,
+            target,
+            padding_len,
+            payload,
+            dec,
+        }
+    }
+
+    fn response(salt: &[u8], request_salt: &[u8], timestamp: u64, payload: &[u8]) -> Vec<u8> {
+        let mut enc = cipher()
+            .encryptor(&session_subkey(&PSK, salt), nonce())
+            .unwrap();
+        let mut header = vec![HEADER_TYPE_SERVER];
+        header.extend_from_slice(&timestamp.to_be_bytes());
+        header.extend_from_slice(request_salt);
+        header.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        enc.encrypt(&mut header).unwrap();
+        let mut data = payload.to_vec();
+        enc.encrypt(&mut data).unwrap();
+        [salt, &header[..], &data[..]].concat()
+    }
+
+    fn target() -> SocksAddr {
+        SocksAddr::Domain("example.com".to_string(), 443)
+    }
+
+    #[tokio::test]
+    async fn test_tcp_round_trip() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let big: Vec<u8> = (0..100 * 1024).map(|i| i as u8).collect();
+        let expected = big.clone();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut req = read_request(&mut stream).await;
+            assert_eq!(req.target.to_string(), "example.com:443");
+            assert_eq!(req.padding_len, 0);
+            assert_eq!(req.payload, b"hello");
+
+            // The rest comes in chunks.
+            let mut received = Vec::new();
+            while received.len() < expected.len() {
+                let len = read_sealed(&mut stream, &mut req.dec, 2).await;
+                let len = BigEndian::read_u16(&len) as usize;
+                assert!(len <= MAX_CHUNK_SIZE);
+                received.extend(read_sealed(&mut stream, &mut req.dec, len).await);
+            }
+            assert_eq!(received, expected);
+
+            let resp = response(&random_salt(), &req.salt, unix_timestamp(), b"world");
+            stream.write_all(&resp).await.unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = ShadowedStream2022::new(stream, METHOD, &password(), &target()).unwrap();
+        stream.write_all(b"hello").await.unwrap();
+        stream.write_all(&big).await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"world");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_flush_sends_padded_header() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let req = read_request(&mut stream).await;
+            assert!(req.padding_len >= 1 && req.padding_len <= MAX_PADDING);
+            assert!(req.payload.is_empty());
+            // An empty first chunk is not the end of the stream.
+            let resp = response(&random_salt(), &req.salt, unix_timestamp(), b"");
+            stream.write_all(&resp).await.unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = ShadowedStream2022::new(stream, METHOD, &password(), &target()).unwrap();
+        stream.flush().await.unwrap();
+        server.await.unwrap();
+        let mut buf = [0u8; 1];
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // Serves a single connection, replies with the given salt and timestamp.
+    async fn reply_with(salt: Vec<u8>, timestamp: u64) -> io::Result<Vec<u8>> {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let req = read_request(&mut stream).await;
+            let resp = response(&salt, &req.salt, timestamp, b"world");
+            stream.write_all(&resp).await.unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = ShadowedStream2022::new(stream, METHOD, &password(), &target()).unwrap();
+        stream.write_all(b"hello").await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await?;
+        Ok(buf)
+    }
+
+    #[tokio::test]
+    async fn test_tcp_replayed_salt_rejected() {
+        let salt = random_salt();
+        assert_eq!(
+            reply_with(salt.clone(), unix_timestamp()).await.unwrap(),
+            b"world"
+        );
+        let err = reply_with(salt, unix_timestamp()).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_stale_timestamp_rejected() {
+        let err = reply_with(random_salt(), unix_timestamp() - 2 * MAX_TIME_DIFF)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_packet_window() {
+        let mut window = PacketWindow::default();
+        assert!(window.check_and_update(10));
+        assert!(!window.check_and_update(10));
+        // Out of order but within the window.
+        assert!(window.check_and_update(8));
+        assert!(window.check_and_update(9));
+        assert!(!window.check_and_update(8));
+        assert!(window.check_and_update(200));
+        assert!(!window.check_and_update(200));
+        // Too old to tell, ID 72 has just fallen out of the window.
+        assert!(!window.check_and_update(72));
+        assert!(window.check_and_update(73));
+        assert!(!window.check_and_update(73));
+        // A large jump clears the window.
+        assert!(window.check_and_update(1000));
+        assert!(window.check_and_update(999));
+        assert!(!window.check_and_update(200));
+    }
+
+    fn server_packet(
+        server_session_id: u64,
+        packet_id: u64,
+        client_session_id: u64,
+        from: &SocketAddr,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut header = [0u8; 16];
+        BigEndian::write_u64(&mut header[..8], server_session_id);
+        BigEndian::write_u64(&mut header[8..], packet_id);
+        let mut body = vec![HEADER_TYPE_SERVER];
+        body.extend_from_slice(&unix_timestamp().to_be_bytes());
+        body.extend_from_slice(&client_session_id.to_be_bytes());
+        body.extend_from_slice(&[0, 3, 0, 0, 0]);
+        SocksAddr::from(from)
+            .write_buf(&mut body, SocksAddrWireType::PortLast)
+            .unwrap();
+        body.extend_from_slice(payload);
+        let mut enc = cipher()
+            .encryptor(
+                &session_subkey(&PSK, &header[..8]),
+                FixedNonce(header[4..].to_vec()),
+            )
+            .unwrap();
+        enc.encrypt(&mut body).unwrap();
+        HeaderCipher::new(&PSK).unwrap().encrypt(&mut header);
+        [&header[..], &body[..]].concat()
+    }
+
+    #[tokio::test]
+    async fn test_udp_round_trip_and_replay() {
+        let mut server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let dgram = ShadowedDatagram2022::new(
+            Box::new(SimpleDatagram(socket)),
+            METHOD,
+            &password(),
+            server_addr,
+        )
+        .unwrap();
+        let (mut client_r, mut client_s) = Box::new(dgram).split();
+
+        let target: SocketAddr = "1.2.3.4:53".parse().unwrap();
+        assert_eq!(client_s.send_to(b"hello", &target).await.unwrap(), 5);
+
+        let mut buf = [0u8; 2048];
+        let (n, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let mut header = [0u8; 16];
+        header.copy_from_slice(&buf[..16]);
+        HeaderCipher::new(&PSK).unwrap().decrypt(&mut header);
+        let client_session_id = BigEndian::read_u64(&header[..8]);
+        assert_eq!(BigEndian::read_u64(&header[8..]), 0);
+        let mut body = buf[16..n].to_vec();
+        cipher()
+            .decryptor(
+                &session_subkey(&PSK, &header[..8]),
+                FixedNonce(header[4..].to_vec()),
+            )
+            .unwrap()
+            .decrypt(&mut body)
+            .unwrap();
+        body.truncate(body.len() - 16);
+        assert_eq!(body[0], HEADER_TYPE_CLIENT);
+        check_timestamp(BigEndian::read_u64(&body[1..9])).unwrap();
+        assert_eq!(BigEndian::read_u16(&body[9..11]), 0);
+        let addr = SocksAddr::try_from((&body[11..], SocksAddrWireType::PortLast)).unwrap();
+        assert_eq!(&body[11 + addr.size()..], b"hello");
+        assert_eq!(addr.must_ip(), target);
+
+        // The second packet is a replay of the first one and must be dropped.
+        let first = server_packet(42, 0, client_session_id, &target, b"first");
+        let second = server_packet(42, 1, client_session_id, &target, b"second");
+        for pkt in [&first, &first, &second].iter() {
+            server.send_to(pkt, client_addr).await.unwrap();
+        }
+        let (n, from) = client_r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"first");
+        assert_eq!(from, target);
+        let (n, _) = client_r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"second");
+    }
+}
@@ -1,10 +1,11 @@
 use anyhow::anyhow;
 use anyhow::Result;
 use hkdf::Hkdf;
+use lazy_static::lazy_static;
 use md5::{Digest, Md5};
 use sha1::Sha1;
 
-use crate::common::crypto::NonceSequence;
+use crate::common::crypto::{CipherRegistry, NonceSequence};
 
 pub struct ShadowsocksNonceSequence(Vec<u8>);
 
@@ -49,3 +50,116 @@ pub fn hkdf_sha1(key: &[u8], salt: &[u8], info: Vec<u8>, size: usize) -> Result<
         .map_err(|_| anyhow!("hkdf expand failed"))?;
     Ok(okm.to_vec())
 }
+
+/// Families of shadowsocks ciphers, they differ in key derivation and framing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CipherKind {
+    /// AEAD ciphers of SIP004, keys are derived from arbitrary passwords.
+    Aead,
+    /// AEAD-2022 ciphers of SIP022, the password is a base64 encoded key.
+    Aead2022,
+}
+
+impl CipherKind {
+    pub fn from_method(method: &str) -> Self {
+        if method.starts_with("2022-") {
+            CipherKind::Aead2022
+        } else {
+            CipherKind::Aead
+        }
+    }
+}
+
+/// A shadowsocks method.
+#[derive(Clone, Copy, Debug)]
+pub struct Method {
+    pub kind: CipherKind,
+    /// Name of the underlying AEAD algorithm, as taken by `AeadCipher::new`.
+    pub algorithm: &'static str,
+}
+
+lazy_static! {
+    static ref METHODS: CipherRegistry<Method> = {
+        let mut r = CipherRegistry::new();
+        for name in &[
+            "chacha20-ietf-poly1305",
+            "chacha20-poly1305",
+            "aes-256-gcm",
+            "aes-128-gcm",
+        ] {
+            r.register(
+                *name,
+                Method {
+                    kind: CipherKind::Aead,
+                    algorithm: *name,
+                },
+            );
+        }
+        r.register(
+            "2022-blake3-aes-128-gcm",
+            Method {
+                kind: CipherKind::Aead2022,
+                algorithm: "aes-128-gcm",
+            },
+        );
+        r.register(
+            "2022-blake3-aes-256-gcm",
+            Method {
+                kind: CipherKind::Aead2022,
+                algorithm: "aes-256-gcm",
+            },
+        );
+        r
+    };
+}
+
+/// Returns the shadowsocks method named `name`.
+pub fn method(name: &str) -> Result<Method> {
+    METHODS.get(name).map(|m| *m)
+}
+
+/// Returns the AEAD algorithm underlying a 2022 method.
+pub fn aead_2022_algorithm(name: &str) -> Result<&'static str> {
+    match method(name)? {
+        Method {
+            kind: CipherKind::Aead2022,
+            algorithm,
+        } => Ok(algorithm),
+        _ => Err(anyhow!("not a 2022 cipher: {}", name)),
+    }
+}
+
+/// Returns the AEAD algorithm underlying a SIP004 method.
+pub fn aead_algorithm(name: &str) -> Result<&'static str> {
+    match method(name)? {
+        Method {
+            kind: CipherKind::Aead,
+            algorithm,
+        } => Ok(algorithm),
+        _ => Err(anyhow!("not a SIP004 cipher: {}", name)),
+    }
+}
+
+/// Decodes the pre-shared key of a 2022 method, the key length must match
+/// the cipher exactly.
+pub fn psk_2022(password: &str, size: usize) -> Result<Vec<u8>> {
+    let psk = base64::decode(password).map_err(|e| anyhow!("invalid psk: {}", e))?;
+    if psk.len() != size {
+        return Err(anyhow!(
+            "invalid psk length {}, expected {}",
+            psk.len(),
+            size
+        ));
+    }
+    Ok(psk)
+}
+
+/// Derives the session subkey of a 2022 method.
+pub fn session_subkey(psk: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut hasher = blake3::Hasher::new_derive_key("shadowsocks 2022 session subkey");
+    hasher.update(psk);
+    hasher.update(salt);
+    let mut key = vec![0u8; psk.len()];
+    hasher.finalize_xof().fill(&mut key);
+    key
+}
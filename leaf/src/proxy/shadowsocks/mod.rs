@@ -1,6 +1,9 @@
+mod aead2022;
 mod crypto;
 mod shadow;
 
+pub use aead2022::{ShadowedDatagram2022, ShadowedStream2022};
+pub use crypto::CipherKind;
 pub use shadow::{
     ShadowedDatagram, ShadowedDatagramRecvHalf, ShadowedDatagramSendHalf, ShadowedStream,
 };
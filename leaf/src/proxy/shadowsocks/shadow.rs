@@ -17,7 +17,7 @@ use crate::common::crypto::{
 };
 use crate::proxy::{ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf};
 
-use super::crypto::{hkdf_sha1, kdf, ShadowsocksNonceSequence};
+use super::crypto::{aead_algorithm, hkdf_sha1, kdf, ShadowsocksNonceSequence};
 
 enum ReadState {
     WaitingSalt,
@@ -48,7 +48,7 @@ pub struct ShadowedStream<T> {
 
 impl<T> ShadowedStream<T> {
     pub fn new(s: T, cipher: &str, password: &str) -> Result<Self> {
-        let cipher = AeadCipher::new(cipher)?;
+        let cipher = AeadCipher::new(aead_algorithm(cipher)?)?;
         let psk = kdf(password, cipher.key_len())?;
         Ok(ShadowedStream {
             inner: s,
@@ -438,8 +438,8 @@ pub struct ShadowedDatagram {
 
 impl ShadowedDatagram {
     pub fn new(socket: Box<dyn ProxyDatagram>, cipher: &str, password: &str) -> Result<Self> {
-        let cipher =
-            AeadCipher::new(cipher).map_err(|e| anyhow!("new aead cipher failed: {}", e))?;
+        let cipher = AeadCipher::new(aead_algorithm(cipher)?)
+            .map_err(|e| anyhow!("new aead cipher failed: {}", e))?;
         let psk =
             kdf(password, cipher.key_len()).map_err(|e| anyhow!("derive key failed: {}", e))?;
         Ok(ShadowedDatagram {
@@ -460,8 +460,8 @@ impl ShadowedDatagram {
         password: &str,
         buf_size: usize,
     ) -> Result<Self> {
-        let cipher =
-            AeadCipher::new(cipher).map_err(|e| anyhow!("new aead cipher failed: {}", e))?;
+        let cipher = AeadCipher::new(aead_algorithm(cipher)?)
+            .map_err(|e| anyhow!("new aead cipher failed: {}", e))?;
         let psk =
             kdf(password, cipher.key_len()).map_err(|e| anyhow!("derive key failed: {}", e))?;
         Ok(ShadowedDatagram {
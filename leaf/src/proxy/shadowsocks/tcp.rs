@@ -1,8 +1,9 @@
 use std::{io, net::SocketAddr, sync::Arc};
 
 use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
 
-use super::ShadowedStream;
+use super::{crypto::method, CipherKind, ShadowedStream, ShadowedStream2022};
 use crate::{
     common::dns_client::DnsClient,
     proxy::{stream::SimpleStream, ProxyStream, ProxyTcpHandler},
@@ -18,6 +19,28 @@ pub struct Handler {
     pub dns_client: Arc<DnsClient>,
 }
 
+impl Handler {
+    /// Fails if `cipher` isn't a supported method.
+    pub fn new(
+        address: String,
+        port: u16,
+        cipher: String,
+        password: String,
+        bind_addr: SocketAddr,
+        dns_client: Arc<DnsClient>,
+    ) -> anyhow::Result<Self> {
+        method(&cipher)?;
+        Ok(Handler {
+            address,
+            port,
+            cipher,
+            password,
+            bind_addr,
+            dns_client,
+        })
+    }
+}
+
 #[async_trait]
 impl ProxyTcpHandler for Handler {
     fn name(&self) -> &str {
@@ -44,16 +67,69 @@ impl ProxyTcpHandler for Handler {
             )
             .await?
         };
-        let mut stream =
-            ShadowedStream::new(stream, &self.cipher, &self.password).map_err(|e| {
-                io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("create shadowsocks stream failed: {}", e),
+        let create_err = |e: anyhow::Error| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("create shadowsocks stream failed: {}", e),
+            )
+        };
+        match CipherKind::from_method(&self.cipher) {
+            CipherKind::Aead => {
+                let mut stream = ShadowedStream::new(stream, &self.cipher, &self.password)
+                    .map_err(create_err)?;
+                sess.destination
+                    .write_to(&mut stream, SocksAddrWireType::PortLast)
+                    .await?;
+                Ok(Box::new(SimpleStream(stream)))
+            }
+            CipherKind::Aead2022 => {
+                let mut stream = ShadowedStream2022::new(
+                    stream,
+                    &self.cipher,
+                    &self.password,
+                    &sess.destination,
                 )
-            })?;
-        sess.destination
-            .write_to(&mut stream, SocksAddrWireType::PortLast)
-            .await?;
-        Ok(Box::new(SimpleStream(stream)))
+                .map_err(create_err)?;
+                // The target address goes in the request header, sends it
+                // right away so server-first protocols don't stall.
+                stream.flush().await?;
+                Ok(Box::new(SimpleStream(stream)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler(cipher: &str) -> anyhow::Result<Handler> {
+        let dns_client = Arc::new(DnsClient::new(
+            vec!["127.0.0.1:53".parse().unwrap()],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        Handler::new(
+            "127.0.0.1".to_string(),
+            8388,
+            cipher.to_string(),
+            "password".to_string(),
+            "0.0.0.0:0".parse().unwrap(),
+            dns_client,
+        )
+    }
+
+    #[test]
+    fn test_new_validates_method() {
+        for cipher in &[
+            "chacha20-ietf-poly1305",
+            "AES-256-GCM",
+            "aes-128-gcm",
+            "2022-blake3-aes-256-gcm",
+        ] {
+            assert!(handler(cipher).is_ok(), "{}", cipher);
+        }
+        let err = handler("rc4-md5").err().unwrap().to_string();
+        assert!(err.starts_with("unsupported cipher: rc4-md5, expected one of "));
+        assert!(err.contains("2022-blake3-aes-128-gcm"));
     }
 }
@@ -11,9 +11,12 @@ use bytes::{BufMut, BytesMut};
 use log::*;
 use tokio::net::UdpSocket;
 
-use super::{ShadowedDatagram, ShadowedDatagramRecvHalf, ShadowedDatagramSendHalf};
+use super::{
+    crypto::method, CipherKind, ShadowedDatagram, ShadowedDatagram2022, ShadowedDatagramRecvHalf,
+    ShadowedDatagramSendHalf,
+};
 use crate::{
-    common::dns_client::DnsClient,
+    common::{buf_pool, dns_client::DnsClient},
     proxy::{
         ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
         SimpleDatagram, UdpTransportType,
@@ -21,6 +24,8 @@ use crate::{
     session::{Session, SocksAddr, SocksAddrWireType},
 };
 
+const MAX_SOCKS_ADDR_SIZE: usize = 1 + 1 + 255 + 2;
+
 pub struct Handler {
     pub address: String,
     pub port: u16,
@@ -30,6 +35,28 @@ pub struct Handler {
     pub dns_client: Arc<DnsClient>,
 }
 
+impl Handler {
+    /// Fails if `cipher` isn't a supported method.
+    pub fn new(
+        address: String,
+        port: u16,
+        cipher: String,
+        password: String,
+        bind_addr: SocketAddr,
+        dns_client: Arc<DnsClient>,
+    ) -> anyhow::Result<Self> {
+        method(&cipher)?;
+        Ok(Handler {
+            address,
+            port,
+            cipher,
+            password,
+            bind_addr,
+            dns_client,
+        })
+    }
+}
+
 #[async_trait]
 impl ProxyUdpHandler for Handler {
     fn name(&self) -> &str {
@@ -78,6 +105,17 @@ impl ProxyUdpHandler for Handler {
             Box::new(SimpleDatagram(socket))
         };
 
+        if CipherKind::from_method(&self.cipher) == CipherKind::Aead2022 {
+            let dgram = ShadowedDatagram2022::new(socket, &self.cipher, &self.password, addr)
+                .map_err(|e| {
+                    Error::new(
+                        ErrorKind::Other,
+                        format!("new shadowed datagram failed: {}", e),
+                    )
+                })?;
+            return Ok(Box::new(dgram));
+        }
+
         let dgram = ShadowedDatagram::with_initial_buffer_size(
             socket,
             &self.cipher,
@@ -123,8 +161,8 @@ pub struct DatagramRecvHalf(ShadowedDatagramRecvHalf);
 #[async_trait]
 impl ProxyDatagramRecvHalf for DatagramRecvHalf {
     async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        // TODO optimize
-        let mut buf2 = [0u8; 2 * 1024];
+        // Leaves room for the address header in front of the payload.
+        let mut buf2 = buf_pool::get(buf.len() + MAX_SOCKS_ADDR_SIZE);
         let (n, _) = self.0.recv_from(&mut buf2).await?;
         let tgt_addr = match SocksAddr::try_from((&buf2[..n], SocksAddrWireType::PortLast)) {
             Ok(v) => v,
@@ -172,3 +210,110 @@ impl ProxyDatagramSendHalf for DatagramSendHalf {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    const CIPHER: &str = "chacha20-ietf-poly1305";
+    const PASSWORD: &str = "password";
+
+    async fn new_server() -> (
+        ShadowedDatagramRecvHalf,
+        ShadowedDatagramSendHalf,
+        SocketAddr,
+    ) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let dgram =
+            ShadowedDatagram::new(Box::new(SimpleDatagram(socket)), CIPHER, PASSWORD).unwrap();
+        let (r, s) = dgram.split();
+        (r, s, addr)
+    }
+
+    async fn new_client(server: SocketAddr) -> Box<dyn ProxyDatagram> {
+        let handler = Handler {
+            address: server.ip().to_string(),
+            port: server.port(),
+            cipher: CIPHER.to_string(),
+            password: PASSWORD.to_string(),
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::empty_ipv4(),
+        };
+        handler.connect(&sess, None, None).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_udp_round_trip() {
+        let (mut server_r, mut server_s, server_addr) = new_server().await;
+        let (mut client_r, mut client_s) = new_client(server_addr).await.split();
+
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 53);
+        let n = client_s.send_to(b"hello", &target).await.unwrap();
+        assert_eq!(n, 5);
+
+        // The server decrypts a packet of [SocksAddr][payload].
+        let mut buf = [0u8; 1024];
+        let (n, client_addr) = server_r.recv_from(&mut buf).await.unwrap();
+        let addr = SocksAddr::try_from((&buf[..n], SocksAddrWireType::PortLast)).unwrap();
+        let addr_size = addr.size();
+        assert_eq!(addr.must_ip(), target);
+        assert_eq!(&buf[addr_size..n], b"hello");
+
+        let mut reply = BytesMut::new();
+        SocksAddr::from(target)
+            .write_buf(&mut reply, SocksAddrWireType::PortLast)
+            .unwrap();
+        reply.put_slice(b"world");
+        server_s.send_to(&reply, &client_addr).await.unwrap();
+
+        let (n, from) = client_r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"world");
+        assert_eq!(from, target);
+    }
+
+    #[tokio::test]
+    async fn test_udp_ipv6_target() {
+        let (mut server_r, _, server_addr) = new_server().await;
+        let (_, mut client_s) = new_client(server_addr).await.split();
+
+        let target: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
+        client_s.send_to(b"v6", &target).await.unwrap();
+
+        let mut buf = [0u8; 1024];
+        let (n, _) = server_r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(buf[0], 0x4);
+        let addr = SocksAddr::try_from((&buf[..n], SocksAddrWireType::PortLast)).unwrap();
+        let addr_size = addr.size();
+        assert_eq!(addr_size, 1 + 16 + 2);
+        assert_eq!(addr.must_ip(), target);
+        assert_eq!(&buf[addr_size..n], b"v6");
+    }
+
+    #[tokio::test]
+    async fn test_udp_domain_source_rejected() {
+        let (mut server_r, mut server_s, server_addr) = new_server().await;
+        let (mut client_r, mut client_s) = new_client(server_addr).await.split();
+
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 53);
+        client_s.send_to(b"hello", &target).await.unwrap();
+        let mut buf = [0u8; 1024];
+        let (_, client_addr) = server_r.recv_from(&mut buf).await.unwrap();
+
+        let mut reply = BytesMut::new();
+        SocksAddr::Domain("example.com".to_string(), 53)
+            .write_buf(&mut reply, SocksAddrWireType::PortLast)
+            .unwrap();
+        assert_eq!(reply[0], 0x3);
+        assert_eq!(reply[1] as usize, "example.com".len());
+        reply.put_slice(b"world");
+        server_s.send_to(&reply, &client_addr).await.unwrap();
+
+        assert!(client_r.recv_from(&mut buf).await.is_err());
+    }
+}
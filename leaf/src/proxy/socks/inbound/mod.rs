@@ -1,5 +1,5 @@
-mod tcp;
-mod udp;
+pub mod tcp;
+pub mod udp;
 
 use std::sync::Arc;
 
@@ -14,6 +14,7 @@ use crate::{
 };
 
 pub use super::NAME;
+pub use tcp::Credentials;
 
 pub fn new(
     inbound: &Inbound,
@@ -25,6 +26,14 @@ pub fn new(
 
     let settings = protobuf::parse_from_bytes::<SocksInboundSettings>(&inbound.settings).unwrap();
     let mut bind = settings.bind;
+    let credentials = if settings.username.is_empty() {
+        None
+    } else {
+        Some(Credentials {
+            username: settings.username,
+            password: settings.password,
+        })
+    };
 
     let mut runners: Vec<Runner> = Vec::new();
 
@@ -36,7 +45,7 @@ pub fn new(
         bind = "0.0.0.0".to_string();
     }
 
-    if let Ok(r) = tcp::new(listen, port, bind, dispatcher) {
+    if let Ok(r) = tcp::new(listen, port, bind, credentials, dispatcher) {
         runners.push(r);
     }
 
@@ -48,3 +57,115 @@ pub fn new(
         Err(anyhow!("no runners"))
     }
 }
+
+#[cfg(all(test, feature = "outbound-direct"))]
+mod tests {
+    use std::convert::TryFrom;
+    use std::net::SocketAddr;
+
+    use bytes::{BufMut, BytesMut};
+
+    use protobuf::Message;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpStream, UdpSocket};
+
+    use super::*;
+    use crate::app::{handler_manager::HandlerManager, nat_manager::NatType, router::Router};
+    use crate::common::shutdown::ShutdownToken;
+    use crate::config;
+    use crate::session::{SocksAddr, SocksAddrWireType};
+
+    fn dispatcher() -> Arc<Dispatcher> {
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "direct".to_string();
+        outbound.protocol = "direct".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let router = Router::new(&protobuf::RepeatedField::new());
+        Arc::new(Dispatcher::new(handler_manager, router))
+    }
+
+    // Replies to every packet with its payload.
+    async fn echo() -> SocketAddr {
+        let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            while let Ok((n, from)) = socket.recv_from(&mut buf).await {
+                let _ = socket.send_to(&buf[..n], &from).await;
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_udp_associate() {
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut inbound = Inbound::new();
+        inbound.protocol = "socks".to_string();
+        inbound.listen = "127.0.0.1".to_string();
+        inbound.port = port as u32;
+        let mut settings = SocksInboundSettings::new();
+        settings.bind = "127.0.0.1".to_string();
+        inbound.settings = settings.write_to_bytes().unwrap();
+        let dispatcher = dispatcher();
+        let nat_manager = Arc::new(NatManager::new(
+            dispatcher.clone(),
+            ShutdownToken::never(),
+            NatType::Cone,
+        ));
+        let runner = new(&inbound, dispatcher, nat_manager).unwrap();
+
+        let echo = echo().await;
+        let client = async move {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            stream.write_all(&[5, 1, 0]).await.unwrap();
+            let mut buf = [0u8; 2];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream
+                .write_all(&[5, 3, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+            let mut reply = [0u8; 10];
+            stream.read_exact(&mut reply).await.unwrap();
+            assert_eq!(reply[1], 0);
+            let relay = SocketAddr::from((
+                [reply[4], reply[5], reply[6], reply[7]],
+                u16::from_be_bytes([reply[8], reply[9]]),
+            ));
+
+            let mut socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let mut pkt = BytesMut::new();
+            pkt.put_slice(&[0, 0, 0]);
+            SocksAddr::from(echo)
+                .write_buf(&mut pkt, SocksAddrWireType::PortLast)
+                .unwrap();
+            pkt.put_slice(b"hello");
+            socket.send_to(&pkt, &relay).await.unwrap();
+
+            let mut buf = [0u8; 64];
+            let (n, _) = socket.recv_from(&mut buf).await.unwrap();
+            let src = SocksAddr::try_from((&buf[3..n], SocksAddrWireType::PortLast)).unwrap();
+            assert_eq!(src, SocksAddr::from(echo));
+            assert_eq!(&buf[3 + src.size()..n], b"hello");
+        };
+        let res = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            futures::future::select(runner, Box::pin(client)),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(res, futures::future::Either::Right(_)));
+    }
+}
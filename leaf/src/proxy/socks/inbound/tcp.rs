@@ -1,10 +1,11 @@
-use std::net::IpAddr;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 use anyhow::Result;
 use bytes::{BufMut, BytesMut};
 use log::*;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpListener;
 use tokio::stream::StreamExt;
 
@@ -14,169 +15,370 @@ use crate::{
     Runner,
 };
 
+const NO_AUTH: u8 = 0x00;
+const USER_PASS_AUTH: u8 = 0x02;
+const NO_ACCEPTABLE_METHODS: u8 = 0xff;
+
+const CMD_CONNECT: u8 = 0x01;
+const CMD_UDP_ASSOCIATE: u8 = 0x03;
+
+const REPLY_SUCCEEDED: u8 = 0x00;
+const REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+
+/// Username and password clients must authenticate with, RFC 1929.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum Request {
+    Connect(SocksAddr),
+    UdpAssociate,
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+async fn reply<S>(stream: &mut S, rep: u8, addr: &SocksAddr) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut buf = BytesMut::new();
+    buf.put_u8(0x05); // version 5
+    buf.put_u8(rep);
+    buf.put_u8(0x0); // rsv
+    addr.write_buf(&mut buf, SocksAddrWireType::PortLast)?;
+    stream.write_all(&buf[..]).await
+}
+
+async fn authenticate<S>(stream: &mut S, credentials: &Credentials) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // ver, ulen
+    let mut buf = [0u8; 2];
+    stream.read_exact(&mut buf).await?;
+    if buf[0] != 0x01 {
+        return Err(invalid_data(format!("unknown auth version {}", buf[0])));
+    }
+    let mut username = vec![0u8; buf[1] as usize];
+    stream.read_exact(&mut username).await?;
+    let mut plen = [0u8; 1];
+    stream.read_exact(&mut plen).await?;
+    let mut password = vec![0u8; plen[0] as usize];
+    stream.read_exact(&mut password).await?;
+
+    if username != credentials.username.as_bytes() || password != credentials.password.as_bytes() {
+        stream.write_all(&[0x01, 0x01]).await?;
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "invalid username or password",
+        ));
+    }
+    stream.write_all(&[0x01, 0x00]).await
+}
+
+// Performs the server side of the handshake, the request is replied to with
+// success, a UDP association with `udp_addr`.
+async fn handshake<S>(
+    stream: &mut S,
+    credentials: Option<&Credentials>,
+    udp_addr: &SocksAddr,
+) -> io::Result<Request>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // ver, nmethods
+    let mut buf = [0u8; 2];
+    stream.read_exact(&mut buf).await?;
+    if buf[0] != 0x05 {
+        return Err(invalid_data(format!("unknown socks version {}", buf[0])));
+    }
+    if buf[1] == 0 {
+        return Err(invalid_data(
+            "no socks5 authentication method specified".to_string(),
+        ));
+    }
+    let mut methods = vec![0u8; buf[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    let method = if credentials.is_some() {
+        USER_PASS_AUTH
+    } else {
+        NO_AUTH
+    };
+    if !methods.contains(&method) {
+        stream.write_all(&[0x05, NO_ACCEPTABLE_METHODS]).await?;
+        return Err(invalid_data(
+            "unsupported socks5 authentication methods".to_string(),
+        ));
+    }
+    stream.write_all(&[0x05, method]).await?;
+    if let Some(credentials) = credentials {
+        authenticate(stream, credentials).await?;
+    }
+
+    // ver, cmd, rsv
+    let mut buf = [0u8; 3];
+    stream.read_exact(&mut buf).await?;
+    if buf[0] != 0x05 {
+        return Err(invalid_data(format!("unknown socks version {}", buf[0])));
+    }
+    if buf[2] != 0x0 {
+        return Err(invalid_data("non-zero socks5 reserved field".to_string()));
+    }
+    let destination = SocksAddr::read_from(stream, SocksAddrWireType::PortLast).await?;
+    match buf[1] {
+        CMD_CONNECT => {
+            reply(stream, REPLY_SUCCEEDED, &SocksAddr::empty_ipv4()).await?;
+            Ok(Request::Connect(destination))
+        }
+        CMD_UDP_ASSOCIATE => {
+            reply(stream, REPLY_SUCCEEDED, udp_addr).await?;
+            Ok(Request::UdpAssociate)
+        }
+        cmd => {
+            reply(
+                stream,
+                REPLY_COMMAND_NOT_SUPPORTED,
+                &SocksAddr::empty_ipv4(),
+            )
+            .await?;
+            Err(invalid_data(format!("unsupported socks5 cmd {}", cmd)))
+        }
+    }
+}
+
+/// Serves a SOCKS5 client connection, `udp_addr` is the relay address
+/// returned for UDP associations.
+pub async fn serve<S>(
+    mut stream: S,
+    source: SocketAddr,
+    credentials: Option<&Credentials>,
+    udp_addr: &SocksAddr,
+    dispatcher: Arc<Dispatcher>,
+) where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    let request = match handshake(&mut stream, credentials, udp_addr).await {
+        Ok(v) => v,
+        Err(e) => {
+            debug!("socks handshake failed: {}", e);
+            return;
+        }
+    };
+    match request {
+        Request::Connect(destination) => {
+            let mut sess = Session {
+                source,
+                destination,
+            };
+            let _ = dispatcher.dispatch_tcp(&mut sess, stream).await;
+        }
+        Request::UdpAssociate => {
+            // The association lasts as long as the TCP connection.
+            let mut buf = [0u8; 1];
+            if stream.read_exact(&mut buf).await.is_err() {
+                debug!("udp association end");
+            }
+        }
+    }
+}
+
 pub fn new(
     listen: String,
     port: u16,
     bind_addr: String,
+    credentials: Option<Credentials>,
     dispatcher: Arc<Dispatcher>,
 ) -> Result<Runner> {
-    let t =
-        async move {
-            let mut listener = TcpListener::bind(format!("{}:{}", listen.clone(), port).as_str())
-                .await
-                .unwrap();
-            info!("socks inbound listening tcp {}:{}", listen.clone(), port);
-            let bind_addr = bind_addr
-                .parse::<IpAddr>()
-                .expect("illegal socks5 udp bind address");
-            while let Some(stream) = listener.next().await {
-                if let Ok(mut stream) = stream {
-                    let dispatcher = dispatcher.clone();
-                    let bind_addr = bind_addr;
-                    tokio::spawn(async move {
-                        let mut buf = BytesMut::with_capacity(1024);
-
-                        // handle auth
-                        buf.resize(2, 0);
-                        // ver, nmethods
-                        if let Err(e) = stream.read_exact(&mut buf[..]).await {
-                            debug!("read ver, nmethods failed: {}", e);
-                            return;
-                        };
-                        if buf[0] != 0x05 {
-                            warn!("unknown socks version {}", buf[0]);
-                            return;
-                        }
-                        if buf[1] == 0 {
-                            warn!("no socks5 authentication method specified");
-                            return;
-                        }
-                        let nmethods = buf[1] as usize;
-                        buf.resize(nmethods, 0);
-                        // methods
-                        if let Err(e) = stream.read_exact(&mut buf[..]).await {
-                            debug!("read methods failed: {}", e);
-                            return;
-                        };
-                        let mut method_accepted = false;
-                        let mut method_idx: u8 = 0;
-                        let supported_method: u8 = 0x0;
-                        for (idx, method) in buf[..].iter().enumerate() {
-                            if method == &supported_method {
-                                method_accepted = true;
-                                method_idx = idx as u8;
-                                break;
-                            }
-                        }
-                        if !method_accepted {
-                            warn!("unsupported socks5 authentication methods");
-                            if let Err(e) = stream.write_all(&[0x05, 0xff]).await {
-                                debug!("write auth response failed: {}", e);
-                            };
-                            return;
-                        } else if let Err(e) = stream.write_all(&[0x05, method_idx]).await {
-                            debug!("write auth response failed: {}", e);
-                            return;
-                        };
-
-                        // handle request
-                        buf.resize(3, 0);
-                        // ver, cmd, rsv
-                        if let Err(e) = stream.read_exact(&mut buf[..]).await {
-                            debug!("read request failed: {}", e);
-                            return;
-                        };
-                        if buf[0] != 0x05 {
-                            warn!("unknown socks version {}", buf[0]);
-                            // TODO reply?
-                            return;
-                        }
-                        if buf[2] != 0x0 {
-                            warn!("non-zero socks5 reserved field");
-                            // TODO reply?
-                            return;
-                        }
-                        let cmd = buf[1];
-                        match cmd {
-                            // connect
-                            0x01 => {}
-                            // udp associate
-                            0x03 => {}
-                            _ => {
-                                warn!("unsupported socks5 cmd {}", cmd);
-                                // TODO reply?
-                                return;
-                            }
-                        }
-                        let destination =
-                            match SocksAddr::read_from(&mut stream, SocksAddrWireType::PortLast)
-                                .await
-                            {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    debug!("read address failed: {}", e);
-                                    return;
-                                }
-                            };
-
-                        match cmd {
-                            0x01 => {
-                                // handle response
-                                buf.clear();
-                                buf.put_u8(0x05); // version 5
-                                buf.put_u8(0x0); // succeeded
-                                buf.put_u8(0x0); // rsv
-                                let resp_addr = SocksAddr::empty_ipv4();
-                                if let Err(e) =
-                                    resp_addr.write_buf(&mut buf, SocksAddrWireType::PortLast)
-                                {
-                                    debug!("write address buffer: {}", e);
-                                    return;
-                                };
-                                if let Err(e) = stream.write_all(&buf[..]).await {
-                                    debug!("write response failed: {}", e);
-                                    return;
-                                };
-
-                                let source = stream
-                                    .peer_addr()
-                                    .unwrap_or_else(|_| "0.0.0.0:0".parse().unwrap());
-                                let mut sess = Session {
-                                    source,
-                                    destination,
-                                };
-
-                                let _ = dispatcher.dispatch_tcp(&mut sess, stream).await;
-                            }
-                            0x03 => {
-                                buf.clear();
-                                buf.put_u8(0x05); // version 5
-                                buf.put_u8(0x0); // succeeded
-                                buf.put_u8(0x0); // rsv
-                                let resp_addr = SocksAddr::from((bind_addr, port));
-                                if let Err(e) =
-                                    resp_addr.write_buf(&mut buf, SocksAddrWireType::PortLast)
-                                {
-                                    debug!("write address buffer: {}", e);
-                                    return;
-                                };
-                                if let Err(e) = stream.write_all(&buf[..]).await {
-                                    debug!("write response failed: {}", e);
-                                    return;
-                                };
-                                let mut buf = [0u8; 1];
-                                // TODO explicitly drop resources allocated above before waiting?
-                                if stream.read_exact(&mut buf).await.is_err() {
-                                    // perhaps explicitly notifies the NAT manager?
-                                    debug!("udp association end");
-                                }
-                            }
-                            _ => (),
-                        }
-                    });
-                }
+    let t = async move {
+        let mut listener = TcpListener::bind(format!("{}:{}", listen.clone(), port).as_str())
+            .await
+            .unwrap();
+        info!("socks inbound listening tcp {}:{}", listen.clone(), port);
+        let bind_addr = bind_addr
+            .parse::<IpAddr>()
+            .expect("illegal socks5 udp bind address");
+        let udp_addr = SocksAddr::from((bind_addr, port));
+        while let Some(stream) = listener.next().await {
+            if let Ok(stream) = stream {
+                let dispatcher = dispatcher.clone();
+                let credentials = credentials.clone();
+                let udp_addr = udp_addr.clone();
+                tokio::spawn(async move {
+                    let source = stream
+                        .peer_addr()
+                        .unwrap_or_else(|_| "0.0.0.0:0".parse().unwrap());
+                    serve(stream, source, credentials.as_ref(), &udp_addr, dispatcher).await;
+                });
             }
-        };
+        }
+    };
 
     Ok(Box::pin(t))
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (a, b) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (a.unwrap(), b.unwrap().0)
+    }
+
+    fn udp_addr() -> SocksAddr {
+        SocksAddr::Ip("127.0.0.1:1080".parse().unwrap())
+    }
+
+    fn credentials() -> Credentials {
+        Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        }
+    }
+
+    // A CONNECT request to example.com:443.
+    const CONNECT: &[u8] = &[
+        5, 1, 0, 3, 11, b'e', b'x', b'a', b'm', b'p', b'l', b'e', b'.', b'c', b'o', b'm', 1, 187,
+    ];
+
+    #[tokio::test]
+    async fn test_handshake_connect() {
+        let (mut client, mut server) = tcp_pair().await;
+        let udp_addr = udp_addr();
+        let server = tokio::spawn(async move { handshake(&mut server, None, &udp_addr).await });
+
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [5, 0]);
+        client.write_all(CONNECT).await.unwrap();
+        let mut buf = [0u8; 10];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [5, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            server.await.unwrap().unwrap(),
+            Request::Connect(SocksAddr::Domain("example.com".to_string(), 443))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handshake_connect_auth() {
+        let (mut client, mut server) = tcp_pair().await;
+        let udp_addr = udp_addr();
+        let server =
+            tokio::spawn(
+                async move { handshake(&mut server, Some(&credentials()), &udp_addr).await },
+            );
+
+        client.write_all(&[5, 2, 0, 2]).await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [5, 2]);
+        client
+            .write_all(&[1, 4, b'u', b's', b'e', b'r', 4, b'p', b'a', b's', b's'])
+            .await
+            .unwrap();
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [1, 0]);
+        client.write_all(CONNECT).await.unwrap();
+        let mut buf = [0u8; 10];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf[1], 0);
+
+        assert_eq!(
+            server.await.unwrap().unwrap(),
+            Request::Connect(SocksAddr::Domain("example.com".to_string(), 443))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handshake_auth_failed() {
+        let (mut client, mut server) = tcp_pair().await;
+        let udp_addr = udp_addr();
+        let server =
+            tokio::spawn(
+                async move { handshake(&mut server, Some(&credentials()), &udp_addr).await },
+            );
+
+        client.write_all(&[5, 1, 2]).await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+        client
+            .write_all(&[1, 4, b'u', b's', b'e', b'r', 4, b'p', b'a', b's', b'x'])
+            .await
+            .unwrap();
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [1, 1]);
+
+        let err = server.await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_auth_required() {
+        let (mut client, mut server) = tcp_pair().await;
+        let udp_addr = udp_addr();
+        let server =
+            tokio::spawn(
+                async move { handshake(&mut server, Some(&credentials()), &udp_addr).await },
+            );
+
+        // Only offers no authentication.
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [5, 0xff]);
+        assert!(server.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_udp_associate() {
+        let (mut client, mut server) = tcp_pair().await;
+        let udp_addr = udp_addr();
+        let server = tokio::spawn(async move { handshake(&mut server, None, &udp_addr).await });
+
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+        client
+            .write_all(&[5, 3, 0, 1, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let mut buf = [0u8; 10];
+        client.read_exact(&mut buf).await.unwrap();
+        // The address to send the datagrams to.
+        assert_eq!(buf, [5, 0, 0, 1, 127, 0, 0, 1, 0x04, 0x38]);
+
+        assert_eq!(server.await.unwrap().unwrap(), Request::UdpAssociate);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_unsupported_command() {
+        let (mut client, mut server) = tcp_pair().await;
+        let udp_addr = udp_addr();
+        let server = tokio::spawn(async move { handshake(&mut server, None, &udp_addr).await });
+
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let mut buf = [0u8; 2];
+        client.read_exact(&mut buf).await.unwrap();
+        // BIND
+        client
+            .write_all(&[5, 2, 0, 1, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let mut buf = [0u8; 10];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf[1], REPLY_COMMAND_NOT_SUPPORTED);
+        assert!(server.await.unwrap().is_err());
+    }
+}
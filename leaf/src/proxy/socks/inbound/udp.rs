@@ -88,14 +88,15 @@ pub fn new(listen: String, port: u16, nat_manager: Arc<NatManager>) -> Result<Ru
                             }
                         };
 
-                    if !nat_manager.contains_key(&src_addr).await {
+                    let key = nat_manager.session_key(src_addr, &dst_addr);
+                    if !nat_manager.contains_key(&key).await {
                         let sess = Session {
                             source: src_addr,
                             destination: dst_addr.clone(),
                         };
 
                         if nat_manager
-                            .add_session(&sess, src_addr, client_ch_tx.clone())
+                            .add_session(&sess, client_ch_tx.clone())
                             .await
                             .is_err()
                         {
@@ -116,7 +117,7 @@ pub fn new(listen: String, port: u16, nat_manager: Arc<NatManager>) -> Result<Ru
                         src_addr: Some(SocksAddr::from(src_addr)),
                         dst_addr: Some(dst_addr),
                     };
-                    nat_manager.send(&src_addr, pkt).await;
+                    nat_manager.send(&key, pkt).await;
                 }
             }
         }
@@ -2,6 +2,6 @@ mod tcp;
 mod udp;
 
 pub use tcp::Handler as TcpHandler;
-pub use udp::Handler as UdpHandler;
+pub use udp::{Backoff, Handler as UdpHandler};
 
 pub use super::NAME;
@@ -1,15 +1,11 @@
-use std::{
-    io::{Error, ErrorKind, Result},
-    net::SocketAddr,
-    sync::Arc,
-};
+use std::{io::Result, net::SocketAddr, sync::Arc};
 
 use async_trait::async_trait;
 use futures::future::TryFutureExt;
 
 use crate::{
     common::dns_client::DnsClient,
-    proxy::{ProxyStream, ProxyTcpHandler},
+    proxy::{ProxyError, ProxyStream, ProxyTcpHandler},
     session::{Session, SocksAddr},
 };
 
@@ -49,13 +45,13 @@ impl ProxyTcpHandler for Handler {
         match &sess.destination {
             SocksAddr::Ip(a) => {
                 let _ = async_socks5::connect(&mut stream, a.to_owned(), None)
-                    .map_err(|x| Error::new(ErrorKind::Other, x))
+                    .map_err(|x| ProxyError::Handshake(x.into()))
                     .await?;
             }
             SocksAddr::Domain(domain, port) => {
                 let _ =
                     async_socks5::connect(&mut stream, (domain.to_owned(), port.to_owned()), None)
-                        .map_err(|x| Error::new(ErrorKind::Other, x))
+                        .map_err(|x| ProxyError::Handshake(x.into()))
                         .await?;
             }
         }
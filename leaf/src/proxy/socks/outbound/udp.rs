@@ -1,29 +1,85 @@
 use std::{
-    io::{Error, ErrorKind, Result},
-    net::SocketAddr,
+    cmp::min,
+    convert::TryFrom,
+    io::Result,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
     sync::Arc,
+    time::Duration,
 };
 
-use async_socks5::{AddrKind, Auth, SocksDatagram, SocksDatagramRecvHalf, SocksDatagramSendHalf};
 use async_trait::async_trait;
-use futures::future::TryFutureExt;
-use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::net::UdpSocket;
+use bytes::{BufMut, BytesMut};
+use log::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{delay_for, Instant};
 
 use crate::{
-    common::dns_client::DnsClient,
+    common::{buf_pool, dns_client::DnsClient},
     proxy::{
-        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
+        bind_udp_socket, deadline, no_local_addr, ProxyDatagram, ProxyDatagramRecvHalf,
+        ProxyDatagramSendHalf, ProxyError, ProxyStream, ProxyUdpHandler, SimpleDatagram,
         UdpTransportType,
     },
-    session::Session,
+    session::{Session, SocksAddr, SocksAddrWireType},
 };
 
+// RSV, FRAG and the longest address.
+const MAX_HEADER_SIZE: usize = 2 + 1 + 1 + 1 + 255 + 2;
+
+/// How failed associations are retried, so a recovering server isn't
+/// hammered with attempts.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    /// Delay before the first retry, doubled for each following retry.
+    pub base: Duration,
+    /// Upper bound of a single delay.
+    pub max: Duration,
+    /// Percentage of each delay randomized, up to 100. Each delay is at
+    /// least the previous one as long as it's at most 50.
+    pub jitter: u32,
+    /// Time allowed for all attempts and delays together, the last error is
+    /// returned once it's up.
+    pub give_up_after: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            base: Duration::from_millis(500),
+            max: Duration::from_secs(10),
+            jitter: 50,
+            give_up_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Backoff {
+    // The exponential backoff of the `retry`th retry, shortened by up to
+    // the jitter.
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self
+            .base
+            .checked_mul(1 << retry.min(16))
+            .unwrap_or(self.max)
+            .min(self.max);
+        let jitter = backoff.as_millis() as u64 * self.jitter.min(100) as u64 / 100;
+        if jitter == 0 {
+            return backoff;
+        }
+        backoff - Duration::from_millis(StdRng::from_entropy().gen_range(0, jitter + 1))
+    }
+}
+
 pub struct Handler {
     pub address: String,
     pub port: u16,
     pub bind_addr: SocketAddr,
+    pub bind_interface: Option<String>,
     pub dns_client: Arc<DnsClient>,
+    /// Retries failed associations when set, only when the handler dials
+    /// the server itself.
+    pub backoff: Option<Backoff>,
 }
 
 #[async_trait]
@@ -43,86 +99,425 @@ impl ProxyUdpHandler for Handler {
     async fn connect<'a>(
         &'a self,
         _sess: &'a Session,
-        _datagram: Option<Box<dyn ProxyDatagram>>,
-        _stream: Option<Box<dyn ProxyStream>>,
+        datagram: Option<Box<dyn ProxyDatagram>>,
+        stream: Option<Box<dyn ProxyStream>>,
     ) -> Result<Box<dyn ProxyDatagram>> {
-        // TODO support chaining, this requires implementing our own socks5 client
-        let stream = self
-            .dial_tcp_stream(
-                self.dns_client.clone(),
-                &self.bind_addr,
+        let (stream, relay) = match stream {
+            Some(mut stream) => {
+                let relay = self.associate(&mut stream).await?;
+                (stream, relay)
+            }
+            None => self.dial_and_associate().await?,
+        };
+        let datagram: Box<dyn ProxyDatagram> = match datagram {
+            Some(datagram) => datagram,
+            // The socket takes the family of the relay, whatever the one of
+            // the bind address.
+            None => {
+                let bind_addr = relay_bind_addr(&self.bind_addr, &relay);
+                let socket = bind_udp_socket(&bind_addr, self.bind_interface.as_deref())?;
+                Box::new(SimpleDatagram(socket))
+            }
+        };
+        let local_addr = datagram.local_addr().ok();
+        let (r, s) = datagram.split();
+        Ok(Box::new(RelayDatagram {
+            r,
+            s,
+            relay,
+            control: stream,
+            local_addr,
+        }))
+    }
+}
+
+impl Handler {
+    // Connects to the server and requests a UDP association, backing off
+    // between attempts until the backoff gives up.
+    async fn dial_and_associate(&self) -> Result<(Box<dyn ProxyStream>, SocketAddr)> {
+        let start = Instant::now();
+        let mut retry = 0;
+        loop {
+            let attempt = async {
+                let mut stream = self
+                    .dial_tcp_stream(
+                        self.dns_client.clone(),
+                        &self.bind_addr,
+                        &self.address,
+                        &self.port,
+                    )
+                    .await?;
+                let relay = self.associate(&mut stream).await?;
+                Ok::<_, std::io::Error>((stream, relay))
+            };
+            let err = match attempt.await {
+                Ok(v) => return Ok(v),
+                Err(e) => e,
+            };
+            let backoff = match &self.backoff {
+                Some(backoff) => backoff,
+                None => return Err(err),
+            };
+            let delay = backoff.delay(retry);
+            // Not enough time left for another attempt, either before the
+            // backoff gives up or before the deadline of the connect.
+            let at = std::iter::once(start + backoff.give_up_after)
+                .chain(deadline::deadline())
+                .min()
+                .unwrap();
+            if Instant::now() + delay >= at {
+                return Err(err);
+            }
+            debug!(
+                "udp associate with {}:{} failed: {}, retrying in {}ms",
                 &self.address,
-                &self.port,
-            )
-            .await?;
-        let socket = UdpSocket::bind("0.0.0.0:0").await?;
-        let socket = SocksDatagram::associate(stream, socket, None::<Auth>, None::<AddrKind>)
-            .map_err(|x| Error::new(ErrorKind::Other, x))
-            .await?;
-        Ok(Box::new(Datagram { socket }))
+                self.port,
+                err,
+                delay.as_millis()
+            );
+            delay_for(delay).await;
+            retry += 1;
+        }
+    }
+
+    // Requests a UDP association over the control connection, returns the
+    // address of the relay.
+    async fn associate(&self, control: &mut Box<dyn ProxyStream>) -> Result<SocketAddr> {
+        match associate(control).await? {
+            SocksAddr::Ip(addr) if !addr.ip().is_unspecified() => Ok(addr),
+            // The relay is on the server itself.
+            relay => {
+                let ips = self
+                    .dns_client
+                    .lookup_with_bind(self.address.clone(), &self.bind_addr)
+                    .await
+                    .map_err(|e| ProxyError::Dns {
+                        host: self.address.clone(),
+                        source: e.into(),
+                    })?;
+                match ips.first() {
+                    Some(ip) => Ok(SocketAddr::new(*ip, relay.port())),
+                    None => Err(ProxyError::Dns {
+                        host: self.address.clone(),
+                        source: "could not resolve to any address".into(),
+                    }
+                    .into()),
+                }
+            }
+        }
     }
 }
 
-pub struct Datagram<S> {
-    pub socket: SocksDatagram<S>,
+// The bind address for a socket sending to `relay`, `bind_addr` if it's of
+// the same family, the unspecified address of the family of `relay` if not.
+fn relay_bind_addr(bind_addr: &SocketAddr, relay: &SocketAddr) -> SocketAddr {
+    match (bind_addr, relay) {
+        (SocketAddr::V4(_), SocketAddr::V4(_)) | (SocketAddr::V6(_), SocketAddr::V6(_)) => {
+            *bind_addr
+        }
+        (_, SocketAddr::V4(_)) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+        (_, SocketAddr::V6(_)) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+    }
 }
 
-impl<S> ProxyDatagram for Datagram<S>
-where
-    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync,
-{
+// Requests a UDP association without authentication, returns the address of
+// the relay.
+async fn associate(control: &mut Box<dyn ProxyStream>) -> Result<SocksAddr> {
+    control.write_all(&[5, 1, 0]).await?;
+    let mut buf = [0u8; 3];
+    control.read_exact(&mut buf[..2]).await?;
+    if buf[..2] != [5, 0] {
+        return Err(ProxyError::Handshake("unexpected method selection".into()).into());
+    }
+    let mut req = BytesMut::with_capacity(10);
+    req.put_slice(&[5, 3, 0]);
+    SocksAddr::from(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0))
+        .write_buf(&mut req, SocksAddrWireType::PortLast)?;
+    control.write_all(&req).await?;
+    control.read_exact(&mut buf).await?;
+    if buf[0] != 5 || buf[1] != 0 {
+        return Err(ProxyError::Handshake(
+            format!("associate rejected with reply {}", buf[1]).into(),
+        )
+        .into());
+    }
+    SocksAddr::read_from(control, SocksAddrWireType::PortLast).await
+}
+
+// A datagram framing packets for a relay, sent through a socket of its own
+// or the datagram of the actor it's chained after.
+pub struct RelayDatagram {
+    r: Box<dyn ProxyDatagramRecvHalf>,
+    s: Box<dyn ProxyDatagramSendHalf>,
+    relay: SocketAddr,
+    // The association lasts as long as the control connection.
+    control: Box<dyn ProxyStream>,
+    local_addr: Option<SocketAddr>,
+}
+
+impl ProxyDatagram for RelayDatagram {
     fn split(
         self: Box<Self>,
     ) -> (
         Box<dyn ProxyDatagramRecvHalf>,
         Box<dyn ProxyDatagramSendHalf>,
     ) {
-        let (rh, sh) = self.socket.split();
         (
-            Box::new(DatagramRecvHalf(rh)),
-            Box::new(DatagramSendHalf(sh)),
+            Box::new(RelayDatagramRecvHalf(self.r)),
+            Box::new(RelayDatagramSendHalf {
+                send_half: self.s,
+                relay: self.relay,
+                _control: self.control,
+            }),
         )
     }
+    fn local_addr(&self) -> Result<SocketAddr> {
+        self.local_addr.ok_or_else(no_local_addr)
+    }
 }
 
-pub struct DatagramRecvHalf<S>(SocksDatagramRecvHalf<S>);
-
-// unsafe impl<S> Send for DatagramRecvHalf<S> {}
+pub struct RelayDatagramRecvHalf(Box<dyn ProxyDatagramRecvHalf>);
 
 #[async_trait]
-impl<S> ProxyDatagramRecvHalf for DatagramRecvHalf<S>
-where
-    S: 'static + AsyncRead + AsyncWrite + Send + Unpin + Sync,
-{
+impl ProxyDatagramRecvHalf for RelayDatagramRecvHalf {
     async fn recv_from(&mut self, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
-        let (n, addr) = self
-            .0
-            .recv_from(buf)
-            .map_err(|x| Error::new(ErrorKind::Other, x))
-            .await?;
+        let mut buf2 = buf_pool::get(buf.len() + MAX_HEADER_SIZE);
+        let (n, _) = self.0.recv_from(&mut buf2).await?;
+        if n < 3 || buf2[2] != 0 {
+            return Err(ProxyError::Protocol("invalid or fragmented udp packet".into()).into());
+        }
+        let addr = SocksAddr::try_from((&buf2[3..n], SocksAddrWireType::PortLast))
+            .map_err(|e| ProxyError::Protocol(e.into()))?;
+        let header_size = 3 + addr.size();
         match addr {
-            AddrKind::Ip(addr) => Ok((n, addr)),
-            _ => Err(Error::new(
-                ErrorKind::Other,
-                "udp receiving domain address is not supported",
-            )),
+            SocksAddr::Ip(addr) => {
+                let to_write = min(n - header_size, buf.len());
+                buf[..to_write].copy_from_slice(&buf2[header_size..header_size + to_write]);
+                Ok((to_write, addr))
+            }
+            _ => Err(
+                ProxyError::Protocol("udp receiving domain address is not supported".into()).into(),
+            ),
         }
     }
 }
 
-pub struct DatagramSendHalf<S>(SocksDatagramSendHalf<S>);
-
-// unsafe impl<S> Send for DatagramSendHalf<S> {}
+pub struct RelayDatagramSendHalf {
+    send_half: Box<dyn ProxyDatagramSendHalf>,
+    relay: SocketAddr,
+    _control: Box<dyn ProxyStream>,
+}
 
 #[async_trait]
-impl<S> ProxyDatagramSendHalf for DatagramSendHalf<S>
-where
-    S: 'static + AsyncRead + AsyncWrite + Send + Unpin + Sync,
-{
+impl ProxyDatagramSendHalf for RelayDatagramSendHalf {
     async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> Result<usize> {
-        self.0
-            .send_to(buf, target.to_owned())
-            .map_err(|x| Error::new(ErrorKind::Other, x))
+        let mut buf2 = BytesMut::with_capacity(MAX_HEADER_SIZE + buf.len());
+        buf2.put_slice(&[0, 0, 0]);
+        SocksAddr::from(target).write_buf(&mut buf2, SocksAddrWireType::PortLast)?;
+        buf2.put_slice(buf);
+        self.send_half.send_to(&buf2, &self.relay).await?;
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::{TcpListener, UdpSocket};
+
+    use super::*;
+    use crate::proxy::testing::{Behavior, MockDatagram};
+
+    // A SOCKS server granting a UDP association on `relay`.
+    async fn server(relay: SocketAddr) -> u16 {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 3];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&[5, 0]).await.unwrap();
+            // VER, CMD, RSV and the IPv4 address of the client.
+            let mut req = [0u8; 3 + 1 + 4 + 2];
+            stream.read_exact(&mut req).await.unwrap();
+            let mut reply = BytesMut::new();
+            reply.put_slice(&[5, 0, 0]);
+            SocksAddr::from(relay)
+                .write_buf(&mut reply, SocksAddrWireType::PortLast)
+                .unwrap();
+            stream.write_all(&reply).await.unwrap();
+            // The association lasts as long as the connection.
+            let _ = stream.read(&mut buf).await;
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_relay() {
+        let mut relay = match UdpSocket::bind("[::1]:0").await {
+            Ok(relay) => relay,
+            // No IPv6 on this host.
+            Err(_) => return,
+        };
+        let relay_addr = relay.local_addr().unwrap();
+        // Echoes the packets, header included.
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1500];
+            while let Ok((n, from)) = relay.recv_from(&mut buf).await {
+                let _ = relay.send_to(&buf[..n], &from).await;
+            }
+        });
+        let handler = handler(server(relay_addr).await);
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        };
+
+        // The socket is bound to IPv6 despite the IPv4 bind address.
+        let datagram = handler.connect(&sess, None, None).await.ok().unwrap();
+        let local_addr = datagram.local_addr().unwrap();
+        assert!(local_addr.is_ipv6());
+        assert_ne!(local_addr.port(), 0);
+        let (mut r, mut s) = datagram.split();
+        let target = "1.2.3.4:53".parse().unwrap();
+        s.send_to(b"ping", &target).await.unwrap();
+        let mut buf = [0u8; 16];
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(from, target);
+    }
+
+    fn handler(port: u16) -> Handler {
+        Handler {
+            address: "127.0.0.1".to_string(),
+            port,
+            bind_addr: "0.0.0.0:0".parse().unwrap(),
+            bind_interface: None,
+            dns_client: Arc::new(DnsClient::new(
+                vec!["127.0.0.1:53".parse().unwrap()],
+                "127.0.0.1:0".parse().unwrap(),
+            )),
+            backoff: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay_over_datagram() {
+        let relay: SocketAddr = "10.0.0.1:1081".parse().unwrap();
+        let handler = handler(server(relay).await);
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        };
+        let target = "1.2.3.4:53".parse().unwrap();
+        let mut buf = [0u8; 16];
+
+        // The packets are framed for the relay, the echo is unframed.
+        let datagram = MockDatagram::new(Behavior::Echo);
+        let sent = datagram.sent();
+        let datagram = handler
+            .connect(&sess, Some(Box::new(datagram)), None)
+            .await
+            .ok()
+            .unwrap();
+        let (mut r, mut s) = datagram.split();
+        s.send_to(b"ping", &target).await.unwrap();
+        let mut framed = vec![0, 0, 0, 1, 1, 2, 3, 4, 0, 53];
+        framed.extend_from_slice(b"ping");
+        assert_eq!(*sent.lock().unwrap(), vec![(framed, relay)]);
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(from, target);
+
+        // A garbled packet isn't taken for a payload.
+        let handler = self::handler(server(relay).await);
+        let datagram = Box::new(MockDatagram::new(Behavior::Corrupt));
+        let datagram = handler
+            .connect(&sess, Some(datagram), None)
             .await
+            .ok()
+            .unwrap();
+        let (mut r, mut s) = datagram.split();
+        s.send_to(b"ping", &target).await.unwrap();
+        let err = r.recv_from(&mut buf).await.unwrap_err();
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Protocol(_))
+        ));
+    }
+
+    #[test]
+    fn test_relay_bind_addr() {
+        let v4: SocketAddr = "192.168.1.2:0".parse().unwrap();
+        let v6: SocketAddr = "[fe80::1]:0".parse().unwrap();
+        let relay4: SocketAddr = "1.2.3.4:1080".parse().unwrap();
+        let relay6: SocketAddr = "[2001:db8::1]:1080".parse().unwrap();
+        assert_eq!(relay_bind_addr(&v4, &relay4), v4);
+        assert_eq!(relay_bind_addr(&v6, &relay6), v6);
+        assert_eq!(relay_bind_addr(&v4, &relay6), "[::]:0".parse().unwrap());
+        assert_eq!(relay_bind_addr(&v6, &relay4), "0.0.0.0:0".parse().unwrap());
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let backoff = Backoff {
+            base: Duration::from_millis(100),
+            max: Duration::from_millis(1000),
+            jitter: 50,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let delays: Vec<Duration> = (0..6).map(|retry| backoff.delay(retry)).collect();
+            for (retry, delay) in delays.iter().enumerate() {
+                let full = Duration::from_millis(100 << retry).min(backoff.max);
+                assert!(*delay <= full && *delay >= full / 2);
+            }
+            assert!(delays.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reassociate_backoff() {
+        use std::sync::Mutex;
+
+        // Refuses the associations, recording when they're attempted.
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+        let attempts2 = attempts.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                attempts2.lock().unwrap().push(Instant::now());
+                let mut buf = [0u8; 3];
+                let _ = stream.read_exact(&mut buf).await;
+                let _ = stream.write_all(&[5, 0xff]).await;
+            }
+        });
+        let mut handler = handler(port);
+        handler.backoff = Some(Backoff {
+            base: Duration::from_millis(20),
+            max: Duration::from_secs(1),
+            jitter: 50,
+            give_up_after: Duration::from_millis(500),
+        });
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Ip("1.2.3.4:53".parse().unwrap()),
+        };
+
+        let start = Instant::now();
+        let err = handler.connect(&sess, None, None).await.err().unwrap();
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::Handshake(_))
+        ));
+        assert!(start.elapsed() < Duration::from_millis(600));
+        let attempts = attempts.lock().unwrap();
+        // Delays of 10-20, 20-40, 40-80, 80-160 and 160-320ms fit.
+        assert!(attempts.len() >= 5, "{} attempts", attempts.len());
+        let gaps: Vec<Duration> = attempts.windows(2).map(|w| w[1] - w[0]).collect();
+        for w in gaps.windows(2) {
+            assert!(w[1] + Duration::from_millis(5) >= w[0], "gaps {:?}", gaps);
+        }
+        assert!(gaps[gaps.len() - 1] > gaps[0] * 2, "gaps {:?}", gaps);
     }
 }
@@ -1,13 +1,23 @@
-use std::{io, pin::Pin};
+use std::{cmp::min, future::Future, io, pin::Pin, time::Duration};
 
+use bytes::{Buf, BytesMut};
 use futures::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::{delay_for, Delay, Instant};
 
-use super::ProxyStream;
+use super::{ProxyError, ProxyStream};
 
 pub struct SimpleStream<T>(pub T);
 
-impl<T: AsyncRead + AsyncWrite + Send + Sync + Unpin> ProxyStream for SimpleStream<T> {}
+impl<T: 'static + AsyncRead + AsyncWrite + Send + Sync + Unpin> ProxyStream for SimpleStream<T> {
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn tcp_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        use std::os::unix::io::AsRawFd;
+        (&self.0 as &dyn std::any::Any)
+            .downcast_ref::<tokio::net::TcpStream>()
+            .map(|s| s.as_raw_fd())
+    }
+}
 
 impl<T: AsyncRead + Unpin> AsyncRead for SimpleStream<T> {
     fn poll_read(
@@ -36,3 +46,315 @@ impl<T: AsyncWrite + Unpin> AsyncWrite for SimpleStream<T> {
         AsyncWrite::poll_shutdown(Pin::new(&mut self.0), cx)
     }
 }
+
+/// Replays bytes read ahead of time, e.g. to detect the protocol, before
+/// reading on from the inner stream.
+pub struct ReplayStream<T> {
+    inner: T,
+    buf: BytesMut,
+}
+
+impl<T> ReplayStream<T> {
+    pub fn new(inner: T, buf: BytesMut) -> Self {
+        ReplayStream { inner, buf }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ReplayStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.buf.is_empty() {
+            return AsyncRead::poll_read(Pin::new(&mut self.inner), cx, buf);
+        }
+        let n = min(buf.len(), self.buf.len());
+        buf[..n].copy_from_slice(&self.buf[..n]);
+        self.buf.advance(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ReplayStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.inner), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
+
+/// Fails reads and writes with a `TimedOut` error once no bytes have flowed in
+/// either direction for the given duration.
+///
+/// There's a single timer, a stream shared by several tasks only wakes up the
+/// last one to poll it on timeout.
+pub struct IdleTimeoutStream<T> {
+    inner: T,
+    timeout: Duration,
+    delay: Delay,
+}
+
+impl<T> IdleTimeoutStream<T> {
+    pub fn new(inner: T, timeout: Duration) -> Self {
+        IdleTimeoutStream {
+            inner,
+            timeout,
+            delay: delay_for(timeout),
+        }
+    }
+
+    fn on_activity(&mut self) {
+        self.delay.reset(Instant::now() + self.timeout);
+    }
+
+    fn poll_idle(&mut self, cx: &mut Context) -> io::Result<()> {
+        match Pin::new(&mut self.delay).poll(cx) {
+            Poll::Ready(()) => Err(io::Error::new(io::ErrorKind::TimedOut, "idle timeout")),
+            Poll::Pending => Ok(()),
+        }
+    }
+}
+
+impl<T: ProxyStream> ProxyStream for IdleTimeoutStream<T> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+
+    // Spliced relays bypass the stream, they enforce the timeout themselves.
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn tcp_fd(&self) -> Option<std::os::unix::io::RawFd> {
+        self.inner.tcp_fd()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "splice"))]
+    fn idle_timeout(&self) -> Option<Duration> {
+        Some(
+            self.inner
+                .idle_timeout()
+                .map_or(self.timeout, |t| t.min(self.timeout)),
+        )
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for IdleTimeoutStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        match AsyncRead::poll_read(Pin::new(&mut me.inner), cx, buf) {
+            Poll::Ready(Ok(n)) => {
+                if n > 0 {
+                    me.on_activity();
+                }
+                Poll::Ready(Ok(n))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => {
+                me.poll_idle(cx)?;
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for IdleTimeoutStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        match AsyncWrite::poll_write(Pin::new(&mut me.inner), cx, buf) {
+            Poll::Ready(Ok(n)) => {
+                if n > 0 {
+                    me.on_activity();
+                }
+                Poll::Ready(Ok(n))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => {
+                me.poll_idle(cx)?;
+                Poll::Pending
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let me = &mut *self;
+        match AsyncWrite::poll_flush(Pin::new(&mut me.inner), cx) {
+            Poll::Pending => {
+                me.poll_idle(cx)?;
+                Poll::Pending
+            }
+            res => res,
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
+
+/// Fails reads, writes and flushes with `ProxyError::MaxLifetime` once the
+/// stream is older than the given duration, whatever its activity, so that
+/// long-lived connections get recycled. Shutting down still goes through.
+///
+/// The stream can't be spliced, that would bypass the timer.
+pub struct MaxLifetimeStream<T> {
+    inner: T,
+    max_lifetime: Duration,
+    delay: Delay,
+}
+
+impl<T> MaxLifetimeStream<T> {
+    pub fn new(inner: T, max_lifetime: Duration) -> Self {
+        MaxLifetimeStream {
+            inner,
+            max_lifetime,
+            delay: delay_for(max_lifetime),
+        }
+    }
+
+    fn poll_expired(&mut self, cx: &mut Context) -> io::Result<()> {
+        match Pin::new(&mut self.delay).poll(cx) {
+            Poll::Ready(()) => Err(ProxyError::MaxLifetime(self.max_lifetime).into()),
+            Poll::Pending => Ok(()),
+        }
+    }
+}
+
+impl<T: ProxyStream> ProxyStream for MaxLifetimeStream<T> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for MaxLifetimeStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_expired(cx)?;
+        AsyncRead::poll_read(Pin::new(&mut self.inner), cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for MaxLifetimeStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_expired(cx)?;
+        AsyncWrite::poll_write(Pin::new(&mut self.inner), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.poll_expired(cx)?;
+        AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    async fn socket_pair() -> (TcpStream, TcpStream) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (a, b) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (a.unwrap(), b.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_idle_stream_closed() {
+        let (a, mut b) = socket_pair().await;
+        let mut a = IdleTimeoutStream::new(a, Duration::from_millis(100));
+        a.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        b.read_exact(&mut buf).await.unwrap();
+
+        let start = Instant::now();
+        let err = a.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(90), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timer_reset() {
+        let (a, mut b) = socket_pair().await;
+        let mut a = IdleTimeoutStream::new(a, Duration::from_millis(100));
+        // Data arriving more often than the timeout keeps the stream open.
+        tokio::spawn(async move {
+            for _ in 0..6 {
+                delay_for(Duration::from_millis(50)).await;
+                b.write_all(b"x").await.unwrap();
+            }
+            // Stays connected, but silent.
+            delay_for(Duration::from_secs(5)).await;
+        });
+        let mut buf = [0u8; 1];
+        for _ in 0..6 {
+            a.read_exact(&mut buf).await.unwrap();
+        }
+        let err = a.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_max_lifetime_stream_closed() {
+        let (a, mut b) = socket_pair().await;
+        let mut a = MaxLifetimeStream::new(a, Duration::from_millis(200));
+        let start = Instant::now();
+        // Activity doesn't extend the lifetime.
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1];
+            loop {
+                if b.write_all(b"x").await.is_err() || b.read(&mut buf).await.is_err() {
+                    break;
+                }
+                delay_for(Duration::from_millis(20)).await;
+            }
+        });
+        let mut buf = [0u8; 1];
+        let err = loop {
+            if let Err(e) = a.read_exact(&mut buf).await {
+                break e;
+            }
+            if let Err(e) = a.write_all(b"y").await {
+                break e;
+            }
+        };
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(190), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(1000), "{:?}", elapsed);
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::MaxLifetime(_))
+        ));
+    }
+}
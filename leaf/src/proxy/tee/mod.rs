@@ -0,0 +1,126 @@
+//! Mirrors the traffic of another outbound for offline analysis.
+//!
+//! Mirroring is best-effort, chunks are dropped rather than slowing down the
+//! relayed connection when the sink can't keep up.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytes::Bytes;
+use log::*;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+pub mod stream;
+pub mod tcp;
+pub mod udp;
+
+pub use stream::TeeStream;
+pub use tcp::Handler as TcpHandler;
+pub use udp::Handler as UdpHandler;
+
+pub static NAME: &str = "tee";
+
+/// Number of chunks buffered for the sink before new ones are dropped.
+pub const MIRROR_CAPACITY: usize = 1024;
+
+static NEXT_CONN: AtomicU64 = AtomicU64::new(1);
+
+/// Returns an id telling apart the chunks of concurrent connections.
+pub fn next_conn_id() -> u64 {
+    NEXT_CONN.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// From the client to the remote peer.
+    Up,
+    /// From the remote peer to the client.
+    Down,
+}
+
+/// Bytes read or written on a mirrored connection.
+#[derive(Debug)]
+pub struct Chunk {
+    pub conn: u64,
+    pub direction: Direction,
+    pub data: Bytes,
+}
+
+/// The sending side of a mirror, cloned for every connection.
+#[derive(Clone)]
+pub struct Mirror {
+    tx: Sender<Chunk>,
+}
+
+impl Mirror {
+    /// Mirrors into a channel holding up to `capacity` chunks.
+    pub fn channel(capacity: usize) -> (Self, Receiver<Chunk>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        (Mirror { tx }, rx)
+    }
+
+    /// Mirrors into the file at `path`, appended to if it exists.
+    ///
+    /// Each chunk is written as the connection id (8 bytes), the direction
+    /// (1 byte, 0 for up and 1 for down), the data length (4 bytes) and the
+    /// data, integers in big-endian. The file is written from a thread of
+    /// its own.
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (mirror, mut rx) = Self::channel(MIRROR_CAPACITY);
+        std::thread::spawn(move || {
+            while let Some(chunk) = futures::executor::block_on(rx.recv()) {
+                let direction = match chunk.direction {
+                    Direction::Up => 0u8,
+                    Direction::Down => 1u8,
+                };
+                let res = file
+                    .write_all(&chunk.conn.to_be_bytes())
+                    .and_then(|_| file.write_all(&[direction]))
+                    .and_then(|_| file.write_all(&(chunk.data.len() as u32).to_be_bytes()))
+                    .and_then(|_| file.write_all(&chunk.data));
+                if let Err(e) = res {
+                    warn!("write tee mirror failed: {}", e);
+                    return;
+                }
+            }
+        });
+        Ok(mirror)
+    }
+
+    /// Copies `data` to the mirror, unless the mirror is full or gone.
+    pub fn send(&mut self, conn: u64, direction: Direction, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let chunk = Chunk {
+            conn,
+            direction,
+            data: Bytes::copy_from_slice(data),
+        };
+        match self.tx.try_send(chunk) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => trace!("tee mirror full, chunk dropped"),
+            Err(TrySendError::Closed(_)) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mirror_drops_when_full() {
+        let (mut mirror, mut rx) = Mirror::channel(1);
+        mirror.send(1, Direction::Up, b"first");
+        // Must not wait for the receiver.
+        mirror.send(1, Direction::Up, b"second");
+        drop(mirror);
+        let chunk = rx.recv().await.unwrap();
+        assert_eq!(&chunk.data[..], b"first");
+        assert!(rx.recv().await.is_none());
+    }
+}
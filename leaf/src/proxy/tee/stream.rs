@@ -0,0 +1,75 @@
+use std::{io, pin::Pin};
+
+use futures::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::{next_conn_id, Direction, Mirror};
+use crate::proxy::ProxyStream;
+
+/// Copies the bytes read from and written to `inner` to a mirror.
+///
+/// The socket is never exposed to splice(2), which would bypass the mirror.
+pub struct TeeStream<S> {
+    inner: S,
+    conn: u64,
+    mirror: Mirror,
+}
+
+impl<S> TeeStream<S> {
+    pub fn new(inner: S, mirror: Mirror) -> Self {
+        TeeStream {
+            inner,
+            conn: next_conn_id(),
+            mirror,
+        }
+    }
+
+    /// Returns the id of the chunks of this stream.
+    pub fn conn(&self) -> u64 {
+        self.conn
+    }
+}
+
+impl<S: ProxyStream> ProxyStream for TeeStream<S> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TeeStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let res = AsyncRead::poll_read(Pin::new(&mut me.inner), cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            me.mirror.send(me.conn, Direction::Down, &buf[..n]);
+        }
+        res
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TeeStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        let res = AsyncWrite::poll_write(Pin::new(&mut me.inner), cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            me.mirror.send(me.conn, Direction::Up, &buf[..n]);
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
@@ -0,0 +1,149 @@
+use std::net::SocketAddr;
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+
+use super::{Mirror, TeeStream};
+use crate::{
+    proxy::{ProxyHandler, ProxyStream, ProxyTcpHandler},
+    session::Session,
+};
+
+pub struct Handler {
+    pub actor: Arc<dyn ProxyHandler>,
+    pub mirror: Mirror,
+}
+
+#[async_trait]
+impl ProxyTcpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.actor.is_warm(sess)
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        sess: &'a Session,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        let stream = self.actor.handle(sess, None).await?;
+        Ok(Box::new(TeeStream::new(stream, self.mirror.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::tee::Direction;
+    use crate::proxy::{
+        stream::SimpleStream, Handler as ProxyHandlerImpl, ProxyDatagram, ProxyHandlerType,
+        ProxyUdpHandler, UdpTransportType,
+    };
+    use crate::session::SocksAddr;
+
+    // Connects every session to the same address.
+    struct Fixed(SocketAddr);
+
+    #[async_trait]
+    impl ProxyTcpHandler for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            let stream = TcpStream::connect(&self.0).await?;
+            Ok(Box::new(SimpleStream(stream)) as Box<dyn ProxyStream>)
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Err(io::ErrorKind::ConnectionRefused.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tee_mirrors_both_directions() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Answers with the uppercased request.
+        tokio::spawn(async move {
+            let (mut s, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 11];
+            s.read_exact(&mut buf).await.unwrap();
+            s.write_all(&buf.to_ascii_uppercase()).await.unwrap();
+        });
+
+        let actor = ProxyHandlerImpl::new(
+            "fixed".to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Fixed(addr)),
+            Box::new(Fixed(addr)),
+        );
+        let (mirror, mut rx) = Mirror::channel(16);
+        let handler = Handler { actor, mirror };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 80),
+        };
+
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        stream.write_all(b"hello world").await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"HELLO WORLD");
+        drop(stream);
+        drop(handler);
+
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+        let mut conns = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            conns.push(chunk.conn);
+            match chunk.direction {
+                Direction::Up => up.extend_from_slice(&chunk.data),
+                Direction::Down => down.extend_from_slice(&chunk.data),
+            }
+        }
+        assert_eq!(&up, b"hello world");
+        assert_eq!(&down, b"HELLO WORLD");
+        assert!(conns.iter().all(|c| *c == conns[0]));
+    }
+}
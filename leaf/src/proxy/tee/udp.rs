@@ -0,0 +1,107 @@
+use std::net::SocketAddr;
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+
+use super::{next_conn_id, Direction, Mirror};
+use crate::{
+    proxy::{
+        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyHandler, ProxyStream,
+        ProxyUdpHandler, UdpTransportType,
+    },
+    session::Session,
+};
+
+pub struct Handler {
+    pub actor: Arc<dyn ProxyHandler>,
+    pub mirror: Mirror,
+}
+
+#[async_trait]
+impl ProxyUdpHandler for Handler {
+    fn name(&self) -> &str {
+        super::NAME
+    }
+
+    fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn udp_transport_type(&self) -> UdpTransportType {
+        UdpTransportType::Unknown
+    }
+
+    async fn connect<'a>(
+        &'a self,
+        sess: &'a Session,
+        _datagram: Option<Box<dyn ProxyDatagram>>,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        let datagram = self.actor.connect(sess, None, None).await?;
+        Ok(Box::new(TeeDatagram {
+            inner: datagram,
+            conn: next_conn_id(),
+            mirror: self.mirror.clone(),
+        }))
+    }
+}
+
+/// Copies the payloads sent and received on a datagram to a mirror.
+pub struct TeeDatagram {
+    inner: Box<dyn ProxyDatagram>,
+    conn: u64,
+    mirror: Mirror,
+}
+
+impl ProxyDatagram for TeeDatagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        let (r, s) = self.inner.split();
+        (
+            Box::new(TeeDatagramRecvHalf {
+                inner: r,
+                conn: self.conn,
+                mirror: self.mirror.clone(),
+            }),
+            Box::new(TeeDatagramSendHalf {
+                inner: s,
+                conn: self.conn,
+                mirror: self.mirror,
+            }),
+        )
+    }
+}
+
+pub struct TeeDatagramRecvHalf {
+    inner: Box<dyn ProxyDatagramRecvHalf>,
+    conn: u64,
+    mirror: Mirror,
+}
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for TeeDatagramRecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (n, addr) = self.inner.recv_from(buf).await?;
+        self.mirror.send(self.conn, Direction::Down, &buf[..n]);
+        Ok((n, addr))
+    }
+}
+
+pub struct TeeDatagramSendHalf {
+    inner: Box<dyn ProxyDatagramSendHalf>,
+    conn: u64,
+    mirror: Mirror,
+}
+
+#[async_trait]
+impl ProxyDatagramSendHalf for TeeDatagramSendHalf {
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        let n = self.inner.send_to(buf, target).await?;
+        self.mirror.send(self.conn, Direction::Up, &buf[..n]);
+        Ok(n)
+    }
+}
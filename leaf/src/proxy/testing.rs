@@ -0,0 +1,295 @@
+//! Test doubles of UDP handlers, built with the `testing` feature.
+//!
+//! A `MockDatagram` keeps its packets in memory and answers them according to
+//! its `Behavior`, a `MockUdpHandler` connects such datagrams, or refuses to.
+//! `check_tcp_connect_addr` checks a TCP handler dials the way its
+//! `tcp_connect_addr` says.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::join;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::{delay_for, timeout};
+
+use crate::session::Session;
+
+use super::{
+    Handler, ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyHandler,
+    ProxyHandlerType, ProxyStream, ProxyTcpHandler, ProxyUdpHandler, SimpleStream,
+    UdpTransportType,
+};
+
+/// How a `MockDatagram` answers the packets sent through it. Answers come
+/// from the address the packet was sent to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Behavior {
+    /// Sends every packet back.
+    Echo,
+    /// Answers nothing.
+    Drop,
+    /// Sends every packet back after a delay.
+    Delay(Duration),
+    /// Sends every packet back with its bytes inverted.
+    Corrupt,
+}
+
+/// The packets sent through a datagram, with their targets.
+pub type Sent = Arc<Mutex<Vec<(Vec<u8>, SocketAddr)>>>;
+
+type Packet = (Vec<u8>, SocketAddr);
+
+pub struct MockDatagram {
+    behavior: Behavior,
+    sent: Sent,
+}
+
+impl MockDatagram {
+    pub fn new(behavior: Behavior) -> Self {
+        MockDatagram {
+            behavior,
+            sent: Sent::default(),
+        }
+    }
+
+    /// Returns the packets sent so far, kept up to date after the datagram
+    /// is split.
+    pub fn sent(&self) -> Sent {
+        self.sent.clone()
+    }
+}
+
+impl ProxyDatagram for MockDatagram {
+    fn split(
+        self: Box<Self>,
+    ) -> (
+        Box<dyn ProxyDatagramRecvHalf>,
+        Box<dyn ProxyDatagramSendHalf>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (
+            Box::new(MockRecvHalf(rx)),
+            Box::new(MockSendHalf {
+                behavior: self.behavior,
+                sent: self.sent,
+                tx,
+            }),
+        )
+    }
+}
+
+struct MockRecvHalf(UnboundedReceiver<Packet>);
+
+#[async_trait]
+impl ProxyDatagramRecvHalf for MockRecvHalf {
+    async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        match self.0.recv().await {
+            Some((data, from)) => {
+                let n = data.len().min(buf.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                Ok((n, from))
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "send half dropped",
+            )),
+        }
+    }
+}
+
+struct MockSendHalf {
+    behavior: Behavior,
+    sent: Sent,
+    tx: UnboundedSender<Packet>,
+}
+
+#[async_trait]
+impl ProxyDatagramSendHalf for MockSendHalf {
+    async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        self.sent.lock().unwrap().push((buf.to_vec(), *target));
+        let answer = (buf.to_vec(), *target);
+        match self.behavior {
+            Behavior::Echo => {
+                let _ = self.tx.send(answer);
+            }
+            Behavior::Drop => (),
+            Behavior::Delay(delay) => {
+                let tx = self.tx.clone();
+                tokio::spawn(async move {
+                    delay_for(delay).await;
+                    let _ = tx.send(answer);
+                });
+            }
+            Behavior::Corrupt => {
+                let corrupted = buf.iter().map(|b| !b).collect();
+                let _ = self.tx.send((corrupted, *target));
+            }
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Connects a `MockDatagram` of its behavior, ignoring the datagram or stream
+/// it may be given, or fails to connect if refusing.
+pub struct MockUdpHandler {
+    behavior: Behavior,
+    refuse: bool,
+    connects: Arc<AtomicUsize>,
+}
+
+impl MockUdpHandler {
+    pub fn new(behavior: Behavior) -> Self {
+        MockUdpHandler {
+            behavior,
+            refuse: false,
+            connects: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// A handler failing every connect with `ConnectionRefused`.
+    pub fn refusing() -> Self {
+        MockUdpHandler {
+            refuse: true,
+            ..Self::new(Behavior::Drop)
+        }
+    }
+
+    /// Returns the number of connects, refused ones included, kept up to
+    /// date after the handler is moved.
+    pub fn connects(&self) -> Arc<AtomicUsize> {
+        self.connects.clone()
+    }
+}
+
+#[async_trait]
+impl ProxyUdpHandler for MockUdpHandler {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    fn udp_transport_type(&self) -> UdpTransportType {
+        UdpTransportType::Packet
+    }
+
+    async fn connect<'a>(
+        &'a self,
+        _sess: &'a Session,
+        _datagram: Option<Box<dyn ProxyDatagram>>,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyDatagram>> {
+        self.connects.fetch_add(1, Ordering::SeqCst);
+        if self.refuse {
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+        Ok(Box::new(MockDatagram::new(self.behavior)))
+    }
+}
+
+struct NoTcp;
+
+#[async_trait]
+impl ProxyTcpHandler for NoTcp {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+        None
+    }
+
+    async fn handle<'a>(
+        &'a self,
+        _sess: &'a Session,
+        _stream: Option<Box<dyn ProxyStream>>,
+    ) -> io::Result<Box<dyn ProxyStream>> {
+        Err(io::Error::new(io::ErrorKind::Other, "not supported"))
+    }
+}
+
+/// Returns an endpoint handler tagged `tag` with `udp` for UDP, its TCP
+/// connects fail.
+pub fn udp_actor(tag: &str, udp: MockUdpHandler) -> Arc<dyn ProxyHandler> {
+    Handler::new(
+        tag.to_string(),
+        colored::Color::White,
+        ProxyHandlerType::Endpoint,
+        Box::new(NoTcp),
+        Box::new(udp),
+    )
+}
+
+/// Checks `handler` keeps to the contract of `tcp_connect_addr`, which must
+/// be the address of `server`: given no stream, the handler dials the server
+/// itself, given one, it uses it and dials nothing.
+///
+/// The server answers nothing, the handler must not wait on it to connect.
+pub async fn check_tcp_connect_addr(
+    handler: &dyn ProxyTcpHandler,
+    server: &mut TcpListener,
+    sess: &Session,
+) {
+    let server_addr = server.local_addr().unwrap();
+    let (address, port, _) = handler
+        .tcp_connect_addr()
+        .expect("handler without a connect address");
+    assert_eq!(address.parse::<IpAddr>().ok(), Some(server_addr.ip()));
+    assert_eq!(port, server_addr.port());
+
+    let (dialed, accepted) = join(handler.handle(sess, None), server.accept()).await;
+    let _dialed = dialed.expect("dial failed");
+    let _accepted = accepted.unwrap();
+
+    let (stream, accepted) = join(TcpStream::connect(server_addr), server.accept()).await;
+    let _accepted = accepted.unwrap();
+    let stream: Box<dyn ProxyStream> = Box::new(SimpleStream(stream.unwrap()));
+    let _handled = handler
+        .handle(sess, Some(stream))
+        .await
+        .expect("handling a connected stream failed");
+    assert!(
+        timeout(Duration::from_millis(100), server.accept())
+            .await
+            .is_err(),
+        "dialed the server despite a connected stream"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_datagram() {
+        let target: SocketAddr = "10.0.0.1:53".parse().unwrap();
+        let mut buf = [0u8; 16];
+
+        let datagram = MockDatagram::new(Behavior::Corrupt);
+        let sent = datagram.sent();
+        let (mut r, mut s) = Box::new(datagram).split();
+        s.send_to(&[0, 1], &target).await.unwrap();
+        assert_eq!(r.recv_from(&mut buf).await.unwrap(), (2, target));
+        assert_eq!(buf[..2], [0xff, 0xfe]);
+        assert_eq!(*sent.lock().unwrap(), vec![(vec![0, 1], target)]);
+
+        let (mut r, mut s) = Box::new(MockDatagram::new(Behavior::Drop)).split();
+        s.send_to(b"ping", &target).await.unwrap();
+        let res = tokio::time::timeout(Duration::from_millis(50), r.recv_from(&mut buf)).await;
+        assert!(res.is_err());
+
+        let delay = Duration::from_millis(50);
+        let (mut r, mut s) = Box::new(MockDatagram::new(Behavior::Delay(delay))).split();
+        let start = tokio::time::Instant::now();
+        s.send_to(b"ping", &target).await.unwrap();
+        r.recv_from(&mut buf).await.unwrap();
+        assert!(start.elapsed() >= delay);
+    }
+}
@@ -2,18 +2,27 @@ use std::io;
 use std::net::SocketAddr;
 
 use async_trait::async_trait;
-use futures::TryFutureExt;
 use log::*;
 
 use crate::{
-    common::tls,
-    proxy::{ProxyStream, ProxyTcpHandler},
+    proxy::{
+        transport::tls::{self, SessionCache, TlsConfig},
+        ProxyError, ProxyStream, ProxyTcpHandler,
+    },
     session::Session,
 };
 
 pub struct Handler {
+    /// Name sent in SNI, defaults to the destination host of the session.
+    /// Setting it allows the SNI to differ from the dialed address.
     pub server_name: String,
     pub alpns: Vec<String>,
+    pub insecure: bool,
+    /// SHA-256 digests of the SubjectPublicKeyInfo accepted from the server,
+    /// checked on top of the CA verification.
+    pub pinned_spki: Vec<Vec<u8>>,
+    /// Sessions resumed on reconnects to the same server.
+    pub session_cache: SessionCache,
 }
 
 #[async_trait]
@@ -40,14 +49,95 @@ impl ProxyTcpHandler for Handler {
         trace!("wrapping tls with name {}", &name);
         match stream {
             Some(stream) => {
-                let tls_stream = tls::wrapper::wrap_tls(stream, &name, self.alpns.clone())
-                    .map_err(|e| {
-                        io::Error::new(io::ErrorKind::Other, format!("wrap tls failed: {}", e))
-                    })
-                    .await?;
-                Ok(tls_stream)
+                let config = TlsConfig {
+                    server_name: name,
+                    alpns: self.alpns.clone(),
+                    insecure: self.insecure,
+                    pinned_spki: self.pinned_spki.clone(),
+                    session_cache: Some(self.session_cache.clone()),
+                    ..Default::default()
+                };
+                let tls_stream = tls::connect(stream, &config)
+                    .await
+                    .map_err(ProxyError::Tls)?;
+                if let Some(alpn) = tls_stream.alpn_protocol() {
+                    trace!("negotiated alpn {}", String::from_utf8_lossy(alpn));
+                }
+                Ok(Box::new(tls_stream))
             }
             None => Err(io::Error::new(io::ErrorKind::Other, "invalid tls input")),
         }
     }
 }
+
+#[cfg(all(test, feature = "rustls-tls"))]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_rustls::{
+        rustls::{Certificate, NoClientAuth, PrivateKey, ServerConfig},
+        TlsAcceptor,
+    };
+
+    use super::*;
+    use crate::proxy::stream::SimpleStream;
+    use crate::session::SocksAddr;
+
+    // Returns the SNI seen by the server for a handshake by the handler.
+    async fn client_hello_sni(handler: Handler) -> Option<String> {
+        let mut server_config = ServerConfig::new(NoClientAuth::new());
+        server_config
+            .set_single_cert(
+                vec![Certificate(
+                    include_bytes!("../transport/testdata/cert.der").to_vec(),
+                )],
+                PrivateKey(include_bytes!("../transport/testdata/key.der").to_vec()),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let stream = acceptor.accept(stream).await.unwrap();
+            stream.get_ref().1.get_sni_hostname().map(|s| s.to_string())
+        });
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("origin.example.com".to_string(), 443),
+        };
+        let stream = TcpStream::connect(addr).await.unwrap();
+        handler
+            .handle(&sess, Some(Box::new(SimpleStream(stream))))
+            .await
+            .unwrap();
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tls_sni_override() {
+        let sni = client_hello_sni(Handler {
+            server_name: "front.example.com".to_string(),
+            alpns: Vec::new(),
+            insecure: true,
+            pinned_spki: Vec::new(),
+            session_cache: SessionCache::new(1),
+        })
+        .await;
+        assert_eq!(sni.as_deref(), Some("front.example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_tls_sni_defaults_to_destination() {
+        let sni = client_hello_sni(Handler {
+            server_name: String::new(),
+            alpns: Vec::new(),
+            insecure: true,
+            pinned_spki: Vec::new(),
+            session_cache: SessionCache::new(1),
+        })
+        .await;
+        assert_eq!(sni.as_deref(), Some("origin.example.com"));
+    }
+}
@@ -0,0 +1,420 @@
+use std::cmp::min;
+use std::io;
+use std::pin::Pin;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use bytes::Bytes;
+use futures::{
+    future::{self, Either},
+    ready,
+    stream::Stream,
+    task::{Context, Poll},
+};
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::{delay_for, timeout};
+
+use super::KeepaliveConfig;
+use crate::proxy::ProxyStream;
+
+/// Client side HTTP/2 settings.
+#[derive(Clone, Debug, Default)]
+pub struct H2Config {
+    /// Authority of the request URI.
+    pub host: String,
+    /// Request path, defaults to `/`.
+    pub path: String,
+    /// Sends PING frames periodically, the connection and all its streams
+    /// are torn down if one is not acknowledged in time.
+    pub keepalive: Option<KeepaliveConfig>,
+}
+
+fn h2_error<E: std::fmt::Display>(msg: &str, e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}: {}", msg, e))
+}
+
+async fn keepalive(mut ping_pong: h2::PingPong, config: KeepaliveConfig) -> io::Error {
+    loop {
+        delay_for(config.interval).await;
+        match timeout(config.timeout, ping_pong.ping(h2::Ping::opaque())).await {
+            Ok(Ok(_)) => trace!("h2 keepalive pong received"),
+            Ok(Err(e)) => return h2_error("h2 ping failed", e),
+            Err(_) => return io::Error::new(io::ErrorKind::TimedOut, "h2 keepalive timeout"),
+        }
+    }
+}
+
+/// An HTTP/2 connection carrying many proxy streams.
+///
+/// Each stream is a `PUT` request whose request and response bodies form
+/// the two directions of the stream. The connection is cheap to clone, all
+/// clones share the same underlying connection.
+#[derive(Clone)]
+pub struct H2Connection {
+    send_request: h2::client::SendRequest<Bytes>,
+    config: Arc<H2Config>,
+    closed: Arc<AtomicBool>,
+}
+
+impl H2Connection {
+    /// Performs the HTTP/2 handshake over `stream`.
+    ///
+    /// The stream is assumed to be ready for h2, e.g. a TLS connection
+    /// negotiated with ALPN h2.
+    pub async fn handshake<S>(stream: S, config: H2Config) -> io::Result<Self>
+    where
+        S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let (send_request, mut conn) = h2::client::handshake(stream)
+            .await
+            .map_err(|e| h2_error("h2 handshake failed", e))?;
+        let ping_pong = match config.keepalive.clone() {
+            Some(k) => conn.ping_pong().map(|p| (p, k)),
+            None => None,
+        };
+        let closed = Arc::new(AtomicBool::new(false));
+        let conn_closed = closed.clone();
+        tokio::spawn(async move {
+            if let Some((ping_pong, k)) = ping_pong {
+                // Dropping the connection on ping timeout resets all its
+                // streams.
+                match future::select(Box::pin(conn), Box::pin(keepalive(ping_pong, k))).await {
+                    Either::Left((Err(e), _)) => debug!("h2 connection failed: {}", e),
+                    Either::Right((e, _)) => debug!("h2 connection failed: {}", e),
+                    _ => (),
+                }
+            } else if let Err(e) = conn.await {
+                debug!("h2 connection failed: {}", e);
+            }
+            conn_closed.store(true, Ordering::Relaxed);
+        });
+        Ok(H2Connection {
+            send_request,
+            config: Arc::new(config),
+            closed,
+        })
+    }
+
+    /// Returns true if the underlying connection has terminated, in which
+    /// case no new streams can be opened.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Opens a new stream on this connection.
+    pub async fn open_stream(&self) -> io::Result<H2Stream> {
+        if self.is_closed() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "h2 connection closed",
+            ));
+        }
+        let uri = if self.config.path.starts_with('/') {
+            format!("https://{}{}", self.config.host, self.config.path)
+        } else {
+            format!("https://{}/{}", self.config.host, self.config.path)
+        };
+        let req = http::Request::builder()
+            .method(http::Method::PUT)
+            .uri(&uri)
+            .body(())
+            .map_err(|e| h2_error("invalid h2 request", e))?;
+        let mut client = self
+            .send_request
+            .clone()
+            .ready()
+            .await
+            .map_err(|e| h2_error("h2 error", e))?;
+        let (resp, send_stream) = client
+            .send_request(req, false)
+            .map_err(|e| h2_error("h2 error", e))?;
+        let (parts, recv_stream) = resp
+            .await
+            .map_err(|e| h2_error("h2 error", e))?
+            .into_parts();
+        if parts.status != http::status::StatusCode::OK {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("h2 failed with status code: {}", parts.status),
+            ));
+        }
+        Ok(H2Stream {
+            send_stream,
+            recv_stream,
+            recv_buf: Bytes::new(),
+            shutdown: false,
+        })
+    }
+}
+
+/// A single stream of an HTTP/2 connection.
+pub struct H2Stream {
+    send_stream: h2::SendStream<Bytes>,
+    recv_stream: h2::RecvStream,
+    recv_buf: Bytes,
+    shutdown: bool,
+}
+
+impl ProxyStream for H2Stream {}
+
+impl AsyncRead for H2Stream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.recv_buf.is_empty() {
+            match ready!(Pin::new(&mut self.recv_stream).poll_next(cx)) {
+                Some(Ok(data)) => {
+                    // The data is buffered here, give the window back to the
+                    // peer right away.
+                    let _ = self.recv_stream.flow_control().release_capacity(data.len());
+                    self.recv_buf = data;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(h2_error("receive data failed", e))),
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+        let to_read = min(buf.len(), self.recv_buf.len());
+        let data = self.recv_buf.split_to(to_read);
+        (&mut buf[..to_read]).copy_from_slice(&data);
+        Poll::Ready(Ok(to_read))
+    }
+}
+
+impl AsyncWrite for H2Stream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        // Waits for the peer to open the flow control window.
+        self.send_stream.reserve_capacity(buf.len());
+        match ready!(self.send_stream.poll_capacity(cx)) {
+            Some(Ok(capacity)) => {
+                let n = min(capacity, buf.len());
+                self.send_stream
+                    .send_data(Bytes::copy_from_slice(&buf[..n]), false)
+                    .map_err(|e| h2_error("send data failed", e))?;
+                Poll::Ready(Ok(n))
+            }
+            Some(Err(e)) => Poll::Ready(Err(h2_error("send data failed", e))),
+            None => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "h2 stream closed",
+            ))),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        if !self.shutdown {
+            self.shutdown = true;
+            self.send_stream
+                .send_data(Bytes::new(), true)
+                .map_err(|e| h2_error("send data failed", e))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use futures::future::poll_fn;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    async fn echo(mut body: h2::RecvStream, mut respond: h2::server::SendResponse<Bytes>) {
+        let resp = http::Response::builder().status(200).body(()).unwrap();
+        let mut send = respond.send_response(resp, false).unwrap();
+        while let Some(data) = body.data().await {
+            let mut data = match data {
+                Ok(data) => data,
+                Err(_) => return,
+            };
+            let _ = body.flow_control().release_capacity(data.len());
+            while !data.is_empty() {
+                send.reserve_capacity(data.len());
+                let capacity = match poll_fn(|cx| send.poll_capacity(cx)).await {
+                    Some(Ok(n)) => n,
+                    _ => return,
+                };
+                let chunk = data.split_to(min(capacity, data.len()));
+                if send.send_data(chunk, false).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = send.send_data(Bytes::new(), true);
+    }
+
+    // Accepts a single TCP connection and echoes every h2 stream on it,
+    // returns the number of streams served.
+    async fn serve(mut listener: TcpListener) -> usize {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut conn = h2::server::handshake(stream).await.unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        while let Some(res) = conn.accept().await {
+            let (req, respond) = match res {
+                Ok(r) => r,
+                Err(_) => break,
+            };
+            assert_eq!(*req.method(), http::Method::PUT);
+            assert_eq!(req.uri().path(), "/h2");
+            count.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(echo(req.into_body(), respond));
+        }
+        count.load(Ordering::Relaxed)
+    }
+
+    async fn echo_through(stream: H2Stream, payload: Vec<u8>) {
+        let (mut r, mut w) = tokio::io::split(stream);
+        let expected = payload.clone();
+        let write = async move {
+            w.write_all(&payload).await.unwrap();
+            w.shutdown().await.unwrap();
+        };
+        let read = async move {
+            let mut buf = Vec::new();
+            r.read_to_end(&mut buf).await.unwrap();
+            buf
+        };
+        let (_, echoed) = tokio::join!(write, read);
+        assert_eq!(echoed, expected);
+    }
+
+    fn config() -> H2Config {
+        H2Config {
+            host: "example.com".to_string(),
+            path: "h2".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn keepalive_config() -> H2Config {
+        H2Config {
+            keepalive: Some(KeepaliveConfig {
+                interval: std::time::Duration::from_millis(100),
+                timeout: std::time::Duration::from_millis(200),
+            }),
+            ..config()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_h2_multiplexing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener));
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let conn = H2Connection::handshake(stream, config()).await.unwrap();
+        let mut tasks = Vec::new();
+        for i in 0..8u8 {
+            let conn = conn.clone();
+            tasks.push(tokio::spawn(async move {
+                let stream = conn.open_stream().await.unwrap();
+                echo_through(stream, vec![i; 1024 * (i as usize + 1)]).await;
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        drop(conn);
+        assert_eq!(server.await.unwrap(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_h2_flow_control() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener));
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let conn = H2Connection::handshake(stream, config()).await.unwrap();
+        // Well beyond the default 64KB window of both stream and connection.
+        let payload: Vec<u8> = (0..1024 * 1024).map(|i| i as u8).collect();
+        let stream = conn.open_stream().await.unwrap();
+        echo_through(stream, payload).await;
+        drop(conn);
+        assert_eq!(server.await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_h2_connection_error() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(stream);
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let res = match H2Connection::handshake(stream, config()).await {
+            Ok(conn) => {
+                let res = conn.open_stream().await.map(|_| ());
+                assert!(res.is_err());
+                tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
+                assert!(conn.is_closed());
+                assert!(conn.open_stream().await.is_err());
+                res
+            }
+            Err(e) => Err(e),
+        };
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_h2_keepalive_dead_connection() {
+        // Completes the handshake but stops processing frames afterwards.
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _conn = h2::server::handshake(stream).await.unwrap();
+            tokio::time::delay_for(std::time::Duration::from_secs(10)).await;
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let conn = H2Connection::handshake(stream, keepalive_config())
+            .await
+            .unwrap();
+        assert!(!conn.is_closed());
+        // Detected after one interval and one timeout.
+        tokio::time::delay_for(std::time::Duration::from_millis(500)).await;
+        assert!(conn.is_closed());
+        assert!(conn.open_stream().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_h2_keepalive_healthy_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve(listener));
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let conn = H2Connection::handshake(stream, keepalive_config())
+            .await
+            .unwrap();
+        // Idles over several intervals, the peer acknowledges every ping.
+        tokio::time::delay_for(std::time::Duration::from_millis(600)).await;
+        assert!(!conn.is_closed());
+        let stream = conn.open_stream().await.unwrap();
+        echo_through(stream, b"alive".to_vec()).await;
+        drop(conn);
+        assert_eq!(server.await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(feature = "outbound-h2")]
+pub mod h2;
+#[cfg(feature = "outbound-mux")]
+pub mod mux;
+#[cfg(feature = "outbound-obfs")]
+pub mod obfs;
+pub mod pool;
+#[cfg(feature = "outbound-quic")]
+pub mod quic;
+#[cfg(any(feature = "rustls-tls", feature = "openssl-tls"))]
+pub mod tls;
+#[cfg(feature = "outbound-ws")]
+pub mod ws;
+
+use std::time::Duration;
+
+/// Keepalive settings of multiplexed connections.
+#[derive(Clone, Debug)]
+pub struct KeepaliveConfig {
+    /// Idle time after which a ping is sent.
+    pub interval: Duration,
+    /// Time to wait for the peer to respond to a ping before the connection
+    /// is considered dead.
+    pub timeout: Duration,
+}
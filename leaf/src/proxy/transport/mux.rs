@@ -0,0 +1,520 @@
+use std::cmp::min;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::time::{delay_until, Delay, Instant};
+use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt, Tokio02AsyncReadCompatExt};
+
+use super::KeepaliveConfig;
+use crate::proxy::ProxyStream;
+
+/// Multiplexing settings of a connection. The number of streams and of
+/// connections is capped by the `ConnectionPool` they're kept in.
+#[derive(Clone, Debug)]
+pub struct MuxConfig {
+    /// Receive window of each stream, in bytes.
+    pub receive_window: u32,
+    /// Pings idle connections, a connection failing to respond is torn down
+    /// along with all its streams.
+    pub keepalive: Option<KeepaliveConfig>,
+}
+
+impl Default for MuxConfig {
+    fn default() -> Self {
+        MuxConfig {
+            receive_window: 256 * 1024,
+            keepalive: None,
+        }
+    }
+}
+
+fn mux_error<E: std::fmt::Display>(msg: &str, e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}: {}", msg, e))
+}
+
+const FRAME_HEADER_SIZE: usize = 12;
+const FRAME_TYPE_DATA: u8 = 0;
+
+// A yamux ping frame with the SYN flag and an opaque value of 0.
+const PING_FRAME: [u8; FRAME_HEADER_SIZE] = [0, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0];
+
+struct Keepalive {
+    config: KeepaliveConfig,
+    timer: Delay,
+    last_read: Instant,
+    ping_sent: Option<Instant>,
+}
+
+/// Wraps the physical connection to inject pings.
+///
+/// yamux doesn't initiate pings by itself, but answers them. Frames written
+/// by yamux are tracked so pings only go in between frames, and any data
+/// read from the peer counts as a sign of life.
+struct KeepaliveIo<S> {
+    inner: S,
+    keepalive: Option<Keepalive>,
+    ping_buf: Vec<u8>,
+    ping_pos: usize,
+    header: [u8; FRAME_HEADER_SIZE],
+    header_len: usize,
+    body_left: usize,
+}
+
+impl<S> KeepaliveIo<S> {
+    fn new(inner: S, config: Option<KeepaliveConfig>) -> Self {
+        let keepalive = config.map(|config| {
+            let now = Instant::now();
+            Keepalive {
+                timer: delay_until(now + config.interval),
+                config,
+                last_read: now,
+                ping_sent: None,
+            }
+        });
+        KeepaliveIo {
+            inner,
+            keepalive,
+            ping_buf: Vec::new(),
+            ping_pos: 0,
+            header: [0u8; FRAME_HEADER_SIZE],
+            header_len: 0,
+            body_left: 0,
+        }
+    }
+
+    fn at_frame_boundary(&self) -> bool {
+        self.header_len == 0 && self.body_left == 0
+    }
+
+    // Follows the frames in the data written by yamux.
+    fn track(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.body_left > 0 {
+                let n = min(self.body_left, data.len());
+                self.body_left -= n;
+                data = &data[n..];
+                continue;
+            }
+            let n = min(FRAME_HEADER_SIZE - self.header_len, data.len());
+            self.header[self.header_len..self.header_len + n].copy_from_slice(&data[..n]);
+            self.header_len += n;
+            data = &data[n..];
+            if self.header_len == FRAME_HEADER_SIZE {
+                self.header_len = 0;
+                if self.header[1] == FRAME_TYPE_DATA {
+                    let mut len = [0u8; 4];
+                    len.copy_from_slice(&self.header[8..]);
+                    self.body_left = u32::from_be_bytes(len) as usize;
+                }
+            }
+        }
+    }
+
+    // Checks the timer, queues a ping if the connection has been idle, fails
+    // if a ping went unanswered.
+    fn poll_keepalive(&mut self, cx: &mut Context) -> io::Result<()> {
+        let keepalive = match self.keepalive.as_mut() {
+            Some(k) => k,
+            None => return Ok(()),
+        };
+        while Pin::new(&mut keepalive.timer).poll(cx).is_ready() {
+            let now = Instant::now();
+            match keepalive.ping_sent {
+                Some(sent) if keepalive.last_read <= sent => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "mux keepalive timeout",
+                    ));
+                }
+                _ => {
+                    let idle = now.duration_since(keepalive.last_read);
+                    if idle >= keepalive.config.interval {
+                        trace!("sending mux keepalive ping");
+                        self.ping_buf.extend_from_slice(&PING_FRAME);
+                        keepalive.ping_sent = Some(now);
+                        keepalive.timer.reset(now + keepalive.config.timeout);
+                    } else {
+                        keepalive.ping_sent = None;
+                        keepalive
+                            .timer
+                            .reset(keepalive.last_read + keepalive.config.interval);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: AsyncWrite + Unpin> KeepaliveIo<S> {
+    fn poll_write_ping(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while self.ping_pos < self.ping_buf.len() {
+            let n =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.ping_buf[self.ping_pos..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.ping_pos += n;
+        }
+        self.ping_buf.clear();
+        self.ping_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for KeepaliveIo<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        me.poll_keepalive(cx)?;
+        // yamux may stay idle on the write side, sends the ping from here.
+        if !me.ping_buf.is_empty() && me.at_frame_boundary() {
+            if let Poll::Ready(Err(e)) = me.poll_write_ping(cx) {
+                return Poll::Ready(Err(e));
+            }
+        }
+        let n = ready!(Pin::new(&mut me.inner).poll_read(cx, buf))?;
+        if let Some(keepalive) = me.keepalive.as_mut() {
+            keepalive.last_read = Instant::now();
+        }
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for KeepaliveIo<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = &mut *self;
+        if me.ping_pos > 0 || (!me.ping_buf.is_empty() && me.at_frame_boundary()) {
+            ready!(me.poll_write_ping(cx))?;
+        }
+        let n = ready!(Pin::new(&mut me.inner).poll_write(cx, buf))?;
+        me.track(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// A physical connection carrying yamux streams.
+#[derive(Clone)]
+pub struct MuxConnection {
+    control: yamux::Control,
+    closed: Arc<AtomicBool>,
+}
+
+impl MuxConnection {
+    /// Starts a yamux client session over `stream`.
+    pub fn new<S>(stream: S, config: &MuxConfig) -> Self
+    where
+        S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
+    {
+        let mut cfg = yamux::Config::default();
+        cfg.set_receive_window(config.receive_window);
+        // Only gives the window back once the data has been consumed by the
+        // reader, so a slow reader pushes back on the remote writer.
+        cfg.set_window_update_mode(yamux::WindowUpdateMode::OnRead);
+        let stream = KeepaliveIo::new(stream, config.keepalive.clone());
+        let mut conn = yamux::Connection::new(stream.compat(), cfg, yamux::Mode::Client);
+        let control = conn.control();
+        let closed = Arc::new(AtomicBool::new(false));
+        let conn_closed = closed.clone();
+        tokio::spawn(async move {
+            // Drives the connection, inbound streams are not expected on the
+            // client side and are simply dropped.
+            loop {
+                match conn.next_stream().await {
+                    Ok(Some(_)) => (),
+                    Ok(None) => break,
+                    Err(e) => {
+                        debug!("mux connection failed: {}", e);
+                        break;
+                    }
+                }
+            }
+            conn_closed.store(true, Ordering::Relaxed);
+        });
+        MuxConnection { control, closed }
+    }
+
+    /// Returns true if the underlying connection has terminated.
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    /// Opens a new stream on this connection.
+    pub async fn open_stream(&self) -> io::Result<MuxStream> {
+        let stream = self
+            .control
+            .clone()
+            .open_stream()
+            .await
+            .map_err(|e| mux_error("open mux stream failed", e))?;
+        Ok(MuxStream {
+            inner: stream.compat(),
+        })
+    }
+}
+
+/// A logical stream of a multiplexed connection.
+pub struct MuxStream {
+    inner: Compat<yamux::Stream>,
+}
+
+impl ProxyStream for MuxStream {}
+
+impl AsyncRead for MuxStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for MuxStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::proxy::transport::pool::{ConnectionPool, PoolConfig, PoolKey, PooledStream};
+
+    // Serves yamux connections, the first byte of each stream selects the
+    // behavior: `e` echoes, `h` holds the stream without ever reading it.
+    async fn serve(mut listener: TcpListener, accepted: Arc<AtomicUsize>) {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(s) => s,
+                Err(_) => return,
+            };
+            accepted.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(async move {
+                let mut cfg = yamux::Config::default();
+                cfg.set_window_update_mode(yamux::WindowUpdateMode::OnRead);
+                let mut conn = yamux::Connection::new(stream.compat(), cfg, yamux::Mode::Server);
+                while let Ok(Some(stream)) = conn.next_stream().await {
+                    tokio::spawn(async move {
+                        let mut stream = stream.compat();
+                        let mut cmd = [0u8; 1];
+                        if stream.read_exact(&mut cmd).await.is_err() {
+                            return;
+                        }
+                        match cmd[0] {
+                            b'e' => {
+                                let (mut r, mut w) = tokio::io::split(stream);
+                                let _ = tokio::io::copy(&mut r, &mut w).await;
+                                let _ = w.shutdown().await;
+                            }
+                            _ => {
+                                tokio::time::delay_for(Duration::from_secs(10)).await;
+                            }
+                        }
+                    });
+                }
+            });
+        }
+    }
+
+    async fn setup() -> (std::net::SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        tokio::spawn(serve(listener, accepted.clone()));
+        (addr, accepted)
+    }
+
+    fn key() -> PoolKey {
+        PoolKey {
+            host: "127.0.0.1".to_string(),
+            port: 443,
+            transport: "mux".to_string(),
+        }
+    }
+
+    fn pool(max_streams: usize, max_connections: usize) -> ConnectionPool<MuxConnection> {
+        ConnectionPool::new(PoolConfig {
+            max_streams,
+            max_connections,
+            ..Default::default()
+        })
+    }
+
+    async fn open_stream(
+        pool: &ConnectionPool<MuxConnection>,
+        addr: std::net::SocketAddr,
+        config: &MuxConfig,
+    ) -> io::Result<PooledStream<MuxStream>> {
+        pool.open_stream(&key(), || async move {
+            Ok(MuxConnection::new(TcpStream::connect(addr).await?, config))
+        })
+        .await
+    }
+
+    async fn echo_through(stream: PooledStream<MuxStream>, payload: Vec<u8>) {
+        let (mut r, mut w) = tokio::io::split(stream);
+        let expected = payload.clone();
+        let write = async move {
+            w.write_all(b"e").await.unwrap();
+            w.write_all(&payload).await.unwrap();
+            w.shutdown().await.unwrap();
+        };
+        let read = async move {
+            let mut buf = Vec::new();
+            r.read_to_end(&mut buf).await.unwrap();
+            buf
+        };
+        let (_, echoed) = tokio::join!(write, read);
+        assert_eq!(echoed, expected);
+    }
+
+    #[tokio::test]
+    async fn test_mux_many_streams() {
+        let (addr, accepted) = setup().await;
+        let pool = pool(16, 4);
+        let config = MuxConfig::default();
+        let mut streams = Vec::new();
+        for _ in 0..48 {
+            streams.push(open_stream(&pool, addr, &config).await.unwrap());
+        }
+        assert_eq!(pool.num_connections(&key()).await, 3);
+        assert_eq!(accepted.load(Ordering::Relaxed), 3);
+
+        let mut tasks = Vec::new();
+        for (i, stream) in streams.into_iter().enumerate() {
+            // Every stream carries its own pattern so any cross talk shows up.
+            let payload: Vec<u8> = (0..4096 * (i % 8 + 1)).map(|j| (i + j) as u8).collect();
+            tasks.push(tokio::spawn(echo_through(stream, payload)));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mux_stream_slots_reused() {
+        let (addr, accepted) = setup().await;
+        let pool = pool(2, 1);
+        let config = MuxConfig::default();
+        let s1 = open_stream(&pool, addr, &config).await.unwrap();
+        let s2 = open_stream(&pool, addr, &config).await.unwrap();
+        assert!(open_stream(&pool, addr, &config).await.is_err());
+        drop(s1);
+        let s3 = open_stream(&pool, addr, &config).await.unwrap();
+        echo_through(s2, b"foo".to_vec()).await;
+        echo_through(s3, b"bar".to_vec()).await;
+        assert_eq!(accepted.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mux_backpressure() {
+        let (addr, _) = setup().await;
+        let pool = pool(4, 1);
+        let config = MuxConfig {
+            receive_window: 64 * 1024,
+            ..Default::default()
+        };
+        let mut held = open_stream(&pool, addr, &config).await.unwrap();
+        held.write_all(b"h").await.unwrap();
+        // The peer never reads, writes must stall once the window is used up.
+        let payload = vec![0u8; 1024 * 1024];
+        let res = tokio::time::timeout(Duration::from_millis(500), held.write_all(&payload)).await;
+        assert!(res.is_err());
+
+        // Other streams on the same connection are not affected.
+        let stream = open_stream(&pool, addr, &config).await.unwrap();
+        echo_through(stream, vec![1u8; 128 * 1024]).await;
+        assert_eq!(pool.num_connections(&key()).await, 1);
+    }
+
+    fn keepalive_config() -> MuxConfig {
+        MuxConfig {
+            keepalive: Some(KeepaliveConfig {
+                interval: Duration::from_millis(100),
+                timeout: Duration::from_millis(200),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mux_keepalive_dead_connection() {
+        // Accepts the connection but never answers anything.
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            tokio::time::delay_for(Duration::from_secs(10)).await;
+        });
+
+        let pool = pool(16, 4);
+        let mut stream = open_stream(&pool, addr, &keepalive_config()).await.unwrap();
+        assert_eq!(pool.num_connections(&key()).await, 1);
+
+        // Detected after one interval and one timeout.
+        tokio::time::delay_for(Duration::from_millis(500)).await;
+        assert_eq!(pool.num_connections(&key()).await, 0);
+        let mut buf = [0u8; 1];
+        let res = tokio::time::timeout(Duration::from_millis(100), stream.read(&mut buf))
+            .await
+            .expect("stream not failed");
+        assert!(!matches!(res, Ok(n) if n > 0));
+    }
+
+    #[tokio::test]
+    async fn test_mux_keepalive_healthy_connection() {
+        let (addr, accepted) = setup().await;
+        let pool = pool(16, 4);
+        let stream = open_stream(&pool, addr, &keepalive_config()).await.unwrap();
+        // Idles over several intervals, the peer answers every ping.
+        tokio::time::delay_for(Duration::from_millis(600)).await;
+        assert_eq!(pool.num_connections(&key()).await, 1);
+        echo_through(stream, b"alive".to_vec()).await;
+        assert_eq!(accepted.load(Ordering::Relaxed), 1);
+    }
+}
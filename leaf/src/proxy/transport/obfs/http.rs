@@ -0,0 +1,294 @@
+use std::{cmp::min, io, pin::Pin};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::ObfsConfig;
+use crate::proxy::ProxyStream;
+
+// Upper bound on the size of the fake response header.
+const MAX_RESPONSE_HEADER_SIZE: usize = 8 * 1024;
+
+enum ReadState {
+    WaitingHeader,
+    Streaming,
+}
+
+enum WriteState {
+    WaitingHeader,
+    PendingHeader(usize),
+    Streaming,
+}
+
+/// simple-obfs HTTP mode.
+///
+/// The first write is sent as the body of a fake WebSocket upgrade request,
+/// and the fake response header in front of the first read is stripped.
+/// Everything after that passes through untouched.
+pub struct HttpObfsStream<S> {
+    inner: S,
+    host: String,
+    path: String,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+    read_state: ReadState,
+    write_state: WriteState,
+}
+
+impl<S> HttpObfsStream<S> {
+    pub fn new(inner: S, config: &ObfsConfig) -> Self {
+        let path = if config.path.starts_with('/') {
+            config.path.clone()
+        } else {
+            format!("/{}", config.path)
+        };
+        HttpObfsStream {
+            inner,
+            host: config.host.clone(),
+            path,
+            read_buf: BytesMut::new(),
+            write_buf: BytesMut::new(),
+            read_state: ReadState::WaitingHeader,
+            write_state: WriteState::WaitingHeader,
+        }
+    }
+
+    fn encode_request(&mut self, payload: &[u8]) {
+        let mut rng = StdRng::from_entropy();
+        let mut key = [0u8; 16];
+        rng.fill(&mut key);
+        let header = format!(
+            "GET {} HTTP/1.1\r\n\
+             Host: {}\r\n\
+             User-Agent: curl/7.{}.{}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\n\
+             Content-Length: {}\r\n\r\n",
+            self.path,
+            self.host,
+            rng.gen_range(0, 51),
+            rng.gen_range(0, 2),
+            base64::encode(&key),
+            payload.len(),
+        );
+        self.write_buf.reserve(header.len() + payload.len());
+        self.write_buf.put_slice(header.as_bytes());
+        self.write_buf.put_slice(payload);
+    }
+}
+
+fn find_header_end(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|w| w == b"\r\n\r\n").map(|p| p + 4)
+}
+
+impl<S: AsyncRead + AsyncWrite + Send + Sync + Unpin> ProxyStream for HttpObfsStream<S> {}
+
+impl<S: AsyncRead + Unpin> AsyncRead for HttpObfsStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match self.read_state {
+                ReadState::WaitingHeader => {
+                    let me = &mut *self;
+                    let mut tmp = [0u8; 2048];
+                    let n = ready!(Pin::new(&mut me.inner).poll_read(cx, &mut tmp))?;
+                    if n == 0 {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "eof before obfs response header",
+                        )));
+                    }
+                    me.read_buf.put_slice(&tmp[..n]);
+                    if let Some(end) = find_header_end(&me.read_buf) {
+                        if !me.read_buf.starts_with(b"HTTP/1.") {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "invalid obfs response header",
+                            )));
+                        }
+                        me.read_buf.advance(end);
+                        me.read_state = ReadState::Streaming;
+                    } else if me.read_buf.len() > MAX_RESPONSE_HEADER_SIZE {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "obfs response header too large",
+                        )));
+                    }
+                }
+                ReadState::Streaming => {
+                    if !self.read_buf.is_empty() {
+                        let to_read = min(buf.len(), self.read_buf.len());
+                        let data = self.read_buf.split_to(to_read);
+                        (&mut buf[..to_read]).copy_from_slice(&data);
+                        return Poll::Ready(Ok(to_read));
+                    }
+                    return Pin::new(&mut self.inner).poll_read(cx, buf);
+                }
+            }
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for HttpObfsStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match self.write_state {
+                WriteState::WaitingHeader => {
+                    self.encode_request(buf);
+                    self.write_state = WriteState::PendingHeader(buf.len());
+                }
+                WriteState::PendingHeader(consumed) => {
+                    let me = &mut *self;
+                    // Same as other stream wrappers, the caller is expected to
+                    // retry with the same buffer upon pending.
+                    let nw = ready!(Pin::new(&mut me.inner).poll_write_buf(cx, &mut me.write_buf))?;
+                    if nw == 0 {
+                        return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero)));
+                    }
+                    if me.write_buf.is_empty() {
+                        me.write_state = WriteState::Streaming;
+                        return Poll::Ready(Ok(consumed));
+                    }
+                }
+                WriteState::Streaming => {
+                    return Pin::new(&mut self.inner).poll_write(cx, buf);
+                }
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    async fn read_header(stream: &mut TcpStream) -> String {
+        let mut header = Vec::new();
+        while !header.ends_with(b"\r\n\r\n") {
+            header.push(stream.read_u8().await.unwrap());
+        }
+        String::from_utf8(header).unwrap()
+    }
+
+    fn config() -> ObfsConfig {
+        ObfsConfig {
+            host: "www.bing.com".to_string(),
+            path: "obfs".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_obfs_http_first_flight() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let header = read_header(&mut stream).await;
+            let mut payload = [0u8; 5];
+            stream.read_exact(&mut payload).await.unwrap();
+            assert_eq!(&payload, b"hello");
+            stream
+                .write_all(
+                    b"HTTP/1.1 101 Switching Protocols\r\n\
+                      Server: nginx/1.18.0\r\n\
+                      Upgrade: websocket\r\n\
+                      Connection: Upgrade\r\n\r\n\
+                      world",
+                )
+                .await
+                .unwrap();
+            // No more obfuscation afterwards.
+            let mut raw = [0u8; 4];
+            stream.read_exact(&mut raw).await.unwrap();
+            assert_eq!(&raw, b"more");
+            stream.write_all(b"data").await.unwrap();
+            header
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = HttpObfsStream::new(stream, &config());
+        stream.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"world");
+        stream.write_all(b"more").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"data");
+
+        let header = server.await.unwrap();
+        assert!(header.starts_with("GET /obfs HTTP/1.1\r\n"));
+        assert!(header.contains("\r\nHost: www.bing.com\r\n"));
+        assert!(header.contains("\r\nUpgrade: websocket\r\n"));
+        assert!(header.contains("\r\nConnection: Upgrade\r\n"));
+        assert!(header.contains("\r\nSec-WebSocket-Key: "));
+        assert!(header.contains("\r\nContent-Length: 5\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_obfs_http_split_response_header() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_header(&mut stream).await;
+            let mut payload = [0u8; 1];
+            stream.read_exact(&mut payload).await.unwrap();
+            let chunks: [&[u8]; 3] = [b"HTTP/1.1 101 Switching", b" Protocols\r\n\r", b"\npayload"];
+            for chunk in chunks.iter() {
+                stream.write_all(chunk).await.unwrap();
+                stream.flush().await.unwrap();
+                tokio::time::delay_for(std::time::Duration::from_millis(10)).await;
+            }
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = HttpObfsStream::new(stream, &config());
+        stream.write_all(b"x").await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"payload");
+    }
+
+    #[tokio::test]
+    async fn test_obfs_http_invalid_response() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_header(&mut stream).await;
+            stream.write_all(b"SSH-2.0-OpenSSH\r\n\r\n").await.unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = HttpObfsStream::new(stream, &config());
+        stream.write_all(b"x").await.unwrap();
+        let mut buf = [0u8; 1];
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,16 @@
+//! simple-obfs compatible obfuscation.
+
+pub mod http;
+pub mod tls;
+
+pub use self::http::HttpObfsStream;
+pub use self::tls::TlsObfsStream;
+
+/// Settings shared by the obfuscation modes.
+#[derive(Clone, Debug, Default)]
+pub struct ObfsConfig {
+    /// Host the traffic pretends to be for.
+    pub host: String,
+    /// Request path, only used by the HTTP mode.
+    pub path: String,
+}
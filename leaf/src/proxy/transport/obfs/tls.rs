@@ -0,0 +1,418 @@
+use std::{cmp::min, io, pin::Pin};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures::{
+    ready,
+    task::{Context, Poll},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use super::ObfsConfig;
+use crate::proxy::ProxyStream;
+
+const RECORD_HANDSHAKE: u8 = 0x16;
+const RECORD_CHANGE_CIPHER_SPEC: u8 = 0x14;
+const RECORD_APPLICATION_DATA: u8 = 0x17;
+
+// Maximum payload carried by a single TLS record.
+const MAX_RECORD_PAYLOAD: usize = 0x4000;
+// Maximum payload carried in the session ticket of the fake ClientHello.
+const MAX_HELLO_PAYLOAD: usize = 0x2000;
+
+// Cipher suites, extensions and their order are the same as simple-obfs.
+const CIPHER_SUITES: [u8; 56] = [
+    0xc0, 0x2c, 0xc0, 0x30, 0x00, 0x9f, 0xcc, 0xa9, 0xcc, 0xa8, 0xcc, 0xaa, 0xc0, 0x2b, 0xc0, 0x2f,
+    0x00, 0x9e, 0xc0, 0x24, 0xc0, 0x28, 0x00, 0x6b, 0xc0, 0x23, 0xc0, 0x27, 0x00, 0x67, 0xc0, 0x0a,
+    0xc0, 0x14, 0x00, 0x39, 0xc0, 0x09, 0xc0, 0x13, 0x00, 0x33, 0x00, 0x9d, 0x00, 0x9c, 0x00, 0x3d,
+    0x00, 0x3c, 0x00, 0x35, 0x00, 0x2f, 0x00, 0xff,
+];
+
+#[rustfmt::skip]
+const OTHER_EXTENSIONS: [u8; 66] = [
+    // ec_point_formats
+    0x00, 0x0b, 0x00, 0x04, 0x03, 0x00, 0x01, 0x02,
+    // elliptic_curves
+    0x00, 0x0a, 0x00, 0x0a, 0x00, 0x08, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x19, 0x00, 0x18,
+    // signature_algorithms
+    0x00, 0x0d, 0x00, 0x20, 0x00, 0x1e, 0x06, 0x01, 0x06, 0x02, 0x06, 0x03, 0x05, 0x01, 0x05, 0x02,
+    0x05, 0x03, 0x04, 0x01, 0x04, 0x02, 0x04, 0x03, 0x03, 0x01, 0x03, 0x02, 0x03, 0x03, 0x02, 0x01,
+    0x02, 0x02, 0x02, 0x03,
+    // encrypt_then_mac
+    0x00, 0x16, 0x00, 0x00,
+    // extended_master_secret
+    0x00, 0x17, 0x00, 0x00,
+];
+
+enum ReadState {
+    WaitingHeader,
+    Skipping(usize),
+    Reading(usize),
+}
+
+enum WriteState {
+    WaitingHello,
+    Streaming,
+    Pending(usize),
+}
+
+fn invalid_record() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid obfs tls record")
+}
+
+/// simple-obfs TLS mode.
+///
+/// The first write is carried in the session ticket extension of a fake
+/// ClientHello, later writes are framed as TLS application data records.
+/// On the read side, the fake server handshake records are skipped and
+/// application data records are unwrapped.
+pub struct TlsObfsStream<S> {
+    inner: S,
+    host: String,
+    read_buf: BytesMut,
+    write_buf: BytesMut,
+    read_state: ReadState,
+    write_state: WriteState,
+    handshake_done: bool,
+}
+
+impl<S> TlsObfsStream<S> {
+    pub fn new(inner: S, config: &ObfsConfig) -> Self {
+        TlsObfsStream {
+            inner,
+            host: config.host.clone(),
+            read_buf: BytesMut::new(),
+            write_buf: BytesMut::new(),
+            read_state: ReadState::WaitingHeader,
+            write_state: WriteState::WaitingHello,
+            handshake_done: false,
+        }
+    }
+
+    fn encode_client_hello(&mut self, payload: &[u8]) {
+        let mut rng = StdRng::from_entropy();
+        let host = self.host.as_bytes();
+
+        let ext_len = 4 + payload.len() + 9 + host.len() + OTHER_EXTENSIONS.len();
+        let hello_len = 2 + 32 + 1 + 32 + 2 + CIPHER_SUITES.len() + 1 + 1 + 2 + ext_len;
+        let buf = &mut self.write_buf;
+        buf.reserve(5 + 4 + hello_len);
+
+        // record header
+        buf.put_u8(RECORD_HANDSHAKE);
+        buf.put_u16(0x0301);
+        buf.put_u16((4 + hello_len) as u16);
+
+        // handshake header
+        buf.put_u8(0x01);
+        buf.put_u8(0);
+        buf.put_u16(hello_len as u16);
+
+        buf.put_u16(0x0303);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or_default();
+        buf.put_u32(now);
+        let mut random = [0u8; 28];
+        rng.fill(&mut random);
+        buf.put_slice(&random);
+        let mut session_id = [0u8; 32];
+        rng.fill(&mut session_id);
+        buf.put_u8(32);
+        buf.put_slice(&session_id);
+        buf.put_u16(CIPHER_SUITES.len() as u16);
+        buf.put_slice(&CIPHER_SUITES);
+        // compression methods
+        buf.put_u8(1);
+        buf.put_u8(0);
+
+        buf.put_u16(ext_len as u16);
+        // session_ticket
+        buf.put_u16(0x0023);
+        buf.put_u16(payload.len() as u16);
+        buf.put_slice(payload);
+        // server_name
+        buf.put_u16(0x0000);
+        buf.put_u16((host.len() + 5) as u16);
+        buf.put_u16((host.len() + 3) as u16);
+        buf.put_u8(0);
+        buf.put_u16(host.len() as u16);
+        buf.put_slice(host);
+        buf.put_slice(&OTHER_EXTENSIONS);
+    }
+
+    fn encode_application_data(&mut self, payload: &[u8]) {
+        self.write_buf.reserve(5 + payload.len());
+        self.write_buf.put_u8(RECORD_APPLICATION_DATA);
+        self.write_buf.put_u16(0x0303);
+        self.write_buf.put_u16(payload.len() as u16);
+        self.write_buf.put_slice(payload);
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Send + Sync + Unpin> ProxyStream for TlsObfsStream<S> {}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TlsObfsStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let me = &mut *self;
+            match me.read_state {
+                ReadState::WaitingHeader if me.read_buf.len() >= 5 => {
+                    let record_type = me.read_buf[0];
+                    let len = ((me.read_buf[3] as usize) << 8) | me.read_buf[4] as usize;
+                    me.read_state = match record_type {
+                        RECORD_APPLICATION_DATA => {
+                            me.handshake_done = true;
+                            ReadState::Reading(len)
+                        }
+                        RECORD_HANDSHAKE | RECORD_CHANGE_CIPHER_SPEC if !me.handshake_done => {
+                            ReadState::Skipping(len)
+                        }
+                        _ => return Poll::Ready(Err(invalid_record())),
+                    };
+                    me.read_buf.advance(5);
+                    continue;
+                }
+                ReadState::Skipping(remaining) if !me.read_buf.is_empty() => {
+                    let n = min(remaining, me.read_buf.len());
+                    me.read_buf.advance(n);
+                    me.read_state = if remaining == n {
+                        ReadState::WaitingHeader
+                    } else {
+                        ReadState::Skipping(remaining - n)
+                    };
+                    continue;
+                }
+                ReadState::Skipping(0) | ReadState::Reading(0) => {
+                    me.read_state = ReadState::WaitingHeader;
+                    continue;
+                }
+                ReadState::Reading(remaining) if !me.read_buf.is_empty() => {
+                    let n = min(min(remaining, me.read_buf.len()), buf.len());
+                    let data = me.read_buf.split_to(n);
+                    (&mut buf[..n]).copy_from_slice(&data);
+                    me.read_state = ReadState::Reading(remaining - n);
+                    return Poll::Ready(Ok(n));
+                }
+                _ => {}
+            }
+
+            // More data is needed to make progress.
+            let mut tmp = [0u8; 4096];
+            let n = ready!(Pin::new(&mut me.inner).poll_read(cx, &mut tmp))?;
+            if n == 0 {
+                return match me.read_state {
+                    ReadState::WaitingHeader if me.read_buf.is_empty() => Poll::Ready(Ok(0)),
+                    _ => Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "eof in the middle of obfs tls record",
+                    ))),
+                };
+            }
+            me.read_buf.put_slice(&tmp[..n]);
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TlsObfsStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            match self.write_state {
+                WriteState::WaitingHello => {
+                    let consume_len = min(buf.len(), MAX_HELLO_PAYLOAD);
+                    self.encode_client_hello(&buf[..consume_len]);
+                    self.write_state = WriteState::Pending(consume_len);
+                }
+                WriteState::Streaming => {
+                    if buf.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    let consume_len = min(buf.len(), MAX_RECORD_PAYLOAD);
+                    self.encode_application_data(&buf[..consume_len]);
+                    self.write_state = WriteState::Pending(consume_len);
+                }
+                WriteState::Pending(consumed) => {
+                    let me = &mut *self;
+                    // Same as other stream wrappers, the caller is expected to
+                    // retry with the same buffer upon pending.
+                    let nw = ready!(Pin::new(&mut me.inner).poll_write_buf(cx, &mut me.write_buf))?;
+                    if nw == 0 {
+                        return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero)));
+                    }
+                    if me.write_buf.is_empty() {
+                        me.write_state = WriteState::Streaming;
+                        return Poll::Ready(Ok(consumed));
+                    }
+                }
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    async fn read_record(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header).await.unwrap();
+        let len = ((header[3] as usize) << 8) | header[4] as usize;
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).await.unwrap();
+        (header[0], body)
+    }
+
+    // Returns the extensions of a ClientHello handshake message.
+    fn extensions(hello: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        assert_eq!(hello[0], 0x01);
+        let mut pos = 4 + 2 + 32;
+        pos += 1 + hello[pos] as usize;
+        pos += 2 + (((hello[pos] as usize) << 8) | hello[pos + 1] as usize);
+        pos += 1 + hello[pos] as usize;
+        let ext_len = ((hello[pos] as usize) << 8) | hello[pos + 1] as usize;
+        pos += 2;
+        assert_eq!(pos + ext_len, hello.len());
+        let mut exts = Vec::new();
+        while pos < hello.len() {
+            let ext_type = ((hello[pos] as u16) << 8) | hello[pos + 1] as u16;
+            let len = ((hello[pos + 2] as usize) << 8) | hello[pos + 3] as usize;
+            exts.push((ext_type, hello[pos + 4..pos + 4 + len].to_vec()));
+            pos += 4 + len;
+        }
+        exts
+    }
+
+    const SERVER_HELLO: &[u8] = &[
+        0x16, 0x03, 0x03, 0x00, 0x04, 0x02, 0x00, 0x00, 0x00, // server_hello
+        0x14, 0x03, 0x03, 0x00, 0x01, 0x01, // change_cipher_spec
+        0x16, 0x03, 0x03, 0x00, 0x02, 0xaa, 0xbb, // encrypted handshake
+    ];
+
+    fn config() -> ObfsConfig {
+        ObfsConfig {
+            host: "www.bing.com".to_string(),
+            path: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_obfs_tls_handshake_framing() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (record_type, hello) = read_record(&mut stream).await;
+            assert_eq!(record_type, RECORD_HANDSHAKE);
+            let mut response = SERVER_HELLO.to_vec();
+            response.extend_from_slice(&[0x17, 0x03, 0x03, 0x00, 0x05]);
+            response.extend_from_slice(b"world");
+            stream.write_all(&response).await.unwrap();
+            let (record_type, data) = read_record(&mut stream).await;
+            assert_eq!(record_type, RECORD_APPLICATION_DATA);
+            assert_eq!(data, b"more");
+            hello
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsObfsStream::new(stream, &config());
+        stream.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"world");
+        stream.write_all(b"more").await.unwrap();
+
+        let hello = server.await.unwrap();
+        let exts = extensions(&hello);
+        assert_eq!(exts[0], (0x0023, b"hello".to_vec()));
+        let mut sni = vec![0x00, 0x0f, 0x00, 0x00, 0x0c];
+        sni.extend_from_slice(b"www.bing.com");
+        assert_eq!(exts[1], (0x0000, sni));
+    }
+
+    #[tokio::test]
+    async fn test_obfs_tls_pass_through() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let payload: Vec<u8> = (0..40000).map(|i| i as u8).collect();
+        let expected = payload.clone();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_record(&mut stream).await;
+            stream.write_all(SERVER_HELLO).await.unwrap();
+            // Echo in records of a different size, written byte by byte to
+            // exercise partial reads.
+            let mut received = Vec::new();
+            while received.len() < expected.len() {
+                let (record_type, data) = read_record(&mut stream).await;
+                assert_eq!(record_type, RECORD_APPLICATION_DATA);
+                assert!(data.len() <= MAX_RECORD_PAYLOAD);
+                received.extend_from_slice(&data);
+            }
+            assert_eq!(received, expected);
+            for chunk in received.chunks(1000) {
+                let mut record = vec![
+                    0x17,
+                    0x03,
+                    0x03,
+                    (chunk.len() >> 8) as u8,
+                    chunk.len() as u8,
+                ];
+                record.extend_from_slice(chunk);
+                for b in record {
+                    stream.write_u8(b).await.unwrap();
+                }
+            }
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsObfsStream::new(stream, &config());
+        stream.write_all(b"x").await.unwrap();
+        stream.write_all(&payload).await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, payload);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_obfs_tls_invalid_record() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            read_record(&mut stream).await;
+            // alert
+            stream
+                .write_all(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28])
+                .await
+                .unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = TlsObfsStream::new(stream, &config());
+        stream.write_all(b"x").await.unwrap();
+        let mut buf = [0u8; 1];
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
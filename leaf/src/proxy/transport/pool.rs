@@ -0,0 +1,570 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::task::{Context, Poll};
+use log::*;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::proxy::ProxyStream;
+
+/// A physical connection able to carry many logical streams.
+#[async_trait]
+pub trait MultiplexedConnection: Clone + Send + Sync + 'static {
+    type Stream: Send;
+
+    /// Returns true if the connection has terminated and can't be reused.
+    fn is_closed(&self) -> bool;
+
+    /// Opens a new logical stream on this connection.
+    async fn open_stream(&self) -> io::Result<Self::Stream>;
+}
+
+#[cfg(feature = "outbound-h2")]
+#[async_trait]
+impl MultiplexedConnection for super::h2::H2Connection {
+    type Stream = super::h2::H2Stream;
+
+    fn is_closed(&self) -> bool {
+        super::h2::H2Connection::is_closed(self)
+    }
+
+    async fn open_stream(&self) -> io::Result<Self::Stream> {
+        super::h2::H2Connection::open_stream(self).await
+    }
+}
+
+#[cfg(feature = "outbound-mux")]
+#[async_trait]
+impl MultiplexedConnection for super::mux::MuxConnection {
+    type Stream = super::mux::MuxStream;
+
+    fn is_closed(&self) -> bool {
+        super::mux::MuxConnection::is_closed(self)
+    }
+
+    async fn open_stream(&self) -> io::Result<Self::Stream> {
+        super::mux::MuxConnection::open_stream(self).await
+    }
+}
+
+/// Identifies the physical connections which can be shared.
+///
+/// `transport` describes the transport stack on top of the TCP connection,
+/// e.g. TLS and h2 settings, connections are only reused by flows with the
+/// exact same description.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    pub host: String,
+    pub port: u16,
+    pub transport: String,
+}
+
+/// Connection pool settings.
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    /// Maximum number of concurrent streams on a single connection.
+    pub max_streams: usize,
+    /// Maximum number of physical connections to a single target.
+    pub max_connections: usize,
+    /// Connections without any stream for this long are closed.
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_streams: 16,
+            max_connections: 4,
+            idle_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+struct Usage {
+    streams: AtomicUsize,
+    // When the last stream was closed.
+    idle_since: std::sync::Mutex<Instant>,
+}
+
+impl Usage {
+    fn new() -> Self {
+        Usage {
+            streams: AtomicUsize::new(0),
+            idle_since: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    fn streams(&self) -> usize {
+        self.streams.load(Ordering::Relaxed)
+    }
+
+    fn idle_for(&self, now: Instant) -> Option<Duration> {
+        if self.streams() > 0 {
+            return None;
+        }
+        Some(now.saturating_duration_since(*self.idle_since.lock().unwrap()))
+    }
+}
+
+struct Pooled<C> {
+    conn: C,
+    usage: Arc<Usage>,
+}
+
+type Connections<C> = Arc<Mutex<Vec<Pooled<C>>>>;
+
+/// A pool of multiplexed connections keyed by target.
+///
+/// A new stream is opened on the least loaded connection to the same target
+/// which still has room for it, a new physical connection is dialed only if
+/// there's none. Idle connections are dropped on access to the pool, or by
+/// calling `evict_idle`.
+pub struct ConnectionPool<C> {
+    config: PoolConfig,
+    conns: std::sync::Mutex<HashMap<PoolKey, Connections<C>>>,
+}
+
+impl<C: MultiplexedConnection> ConnectionPool<C> {
+    pub fn new(config: PoolConfig) -> Self {
+        ConnectionPool {
+            config,
+            conns: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn connections(&self, key: &PoolKey) -> Connections<C> {
+        self.conns
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(Mutex::new(Vec::new())))
+            .clone()
+    }
+
+    fn retain(&self, conns: &mut Vec<Pooled<C>>, now: Instant) {
+        let idle_timeout = self.config.idle_timeout;
+        conns.retain(|c| {
+            if c.conn.is_closed() {
+                return false;
+            }
+            match c.usage.idle_for(now) {
+                Some(idle) if idle >= idle_timeout => {
+                    debug!("evicting idle connection");
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+
+    /// Returns the number of live physical connections to `key`.
+    pub async fn num_connections(&self, key: &PoolKey) -> usize {
+        let conns = self.connections(key);
+        let mut conns = conns.lock().await;
+        self.retain(&mut conns, Instant::now());
+        conns.len()
+    }
+
+    /// Drops connections which have been idle for longer than the idle
+    /// timeout, as well as terminated ones.
+    pub async fn evict_idle(&self) {
+        let all: Vec<(PoolKey, Connections<C>)> = self
+            .conns
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let now = Instant::now();
+        for (key, entry) in all {
+            let mut conns = entry.lock().await;
+            self.retain(&mut conns, now);
+            // Keeps the entry if anyone else is about to use it.
+            let mut all = self.conns.lock().unwrap();
+            if conns.is_empty() && Arc::strong_count(&entry) == 2 {
+                all.remove(&key);
+            }
+        }
+    }
+
+    /// Returns true if a stream to `key` can be opened on an existing
+    /// connection, without dialing. A busy pool is reported as not warm
+    /// rather than waited for.
+    pub fn is_warm(&self, key: &PoolKey) -> bool {
+        let conns = match self.conns.lock().unwrap().get(key) {
+            Some(conns) => conns.clone(),
+            None => return false,
+        };
+        let conns = match conns.try_lock() {
+            Ok(conns) => conns,
+            Err(_) => return false,
+        };
+        let now = Instant::now();
+        conns.iter().any(|c| {
+            !c.conn.is_closed()
+                && c.usage.streams() < self.config.max_streams
+                && c.usage
+                    .idle_for(now)
+                    .map_or(true, |idle| idle < self.config.idle_timeout)
+        })
+    }
+
+    /// Opens a new logical stream to `key`, `dial` is called to establish a
+    /// new physical connection when needed.
+    pub async fn open_stream<F, Fut>(
+        &self,
+        key: &PoolKey,
+        dial: F,
+    ) -> io::Result<PooledStream<C::Stream>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = io::Result<C>>,
+    {
+        let conns = self.connections(key);
+        // The lock is held while dialing so concurrent flows to the same
+        // target don't end up creating more connections than needed.
+        let mut conns = conns.lock().await;
+        self.retain(&mut conns, Instant::now());
+        let (conn, usage) = match conns
+            .iter()
+            .filter(|c| c.usage.streams() < self.config.max_streams)
+            .min_by_key(|c| c.usage.streams())
+        {
+            Some(c) => (c.conn.clone(), c.usage.clone()),
+            None => {
+                if conns.len() >= self.config.max_connections {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("too many connections to {}:{}", key.host, key.port),
+                    ));
+                }
+                trace!("dialing new pooled connection to {}:{}", key.host, key.port);
+                let conn = dial().await?;
+                let usage = Arc::new(Usage::new());
+                conns.push(Pooled {
+                    conn: conn.clone(),
+                    usage: usage.clone(),
+                });
+                (conn, usage)
+            }
+        };
+        // Reserves the slot before releasing the lock.
+        usage.streams.fetch_add(1, Ordering::Relaxed);
+        drop(conns);
+        Self::open_reserved(conn, usage).await
+    }
+
+    /// Adds `conn`, a connection to `key` established by the caller, to the
+    /// pool and opens a new logical stream on it. The connection is added
+    /// even if there are already `max_connections` to `key`.
+    pub async fn add_connection(
+        &self,
+        key: &PoolKey,
+        conn: C,
+    ) -> io::Result<PooledStream<C::Stream>> {
+        let conns = self.connections(key);
+        let mut conns = conns.lock().await;
+        self.retain(&mut conns, Instant::now());
+        let usage = Arc::new(Usage::new());
+        usage.streams.fetch_add(1, Ordering::Relaxed);
+        conns.push(Pooled {
+            conn: conn.clone(),
+            usage: usage.clone(),
+        });
+        drop(conns);
+        Self::open_reserved(conn, usage).await
+    }
+
+    // Opens a stream on a connection with a slot already reserved in usage.
+    async fn open_reserved(conn: C, usage: Arc<Usage>) -> io::Result<PooledStream<C::Stream>> {
+        let guard = StreamGuard(usage);
+        let stream = conn.open_stream().await?;
+        Ok(PooledStream {
+            inner: stream,
+            _guard: guard,
+        })
+    }
+}
+
+struct StreamGuard(Arc<Usage>);
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        // Stamps the time under the lock so it's never older than the
+        // moment the count dropped to zero.
+        let mut idle_since = self.0.idle_since.lock().unwrap();
+        if self.0.streams.fetch_sub(1, Ordering::Relaxed) == 1 {
+            *idle_since = Instant::now();
+        }
+    }
+}
+
+/// A logical stream opened through the pool, the slot on its connection is
+/// released when the stream is dropped.
+pub struct PooledStream<S> {
+    inner: S,
+    _guard: StreamGuard,
+}
+
+impl<S> PooledStream<S> {
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+impl<S: ProxyStream> ProxyStream for PooledStream<S> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.inner.negotiated_protocol()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for PooledStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for PooledStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct FakeConnection {
+        id: usize,
+        closed: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl MultiplexedConnection for FakeConnection {
+        type Stream = usize;
+
+        fn is_closed(&self) -> bool {
+            self.closed.load(Ordering::Relaxed)
+        }
+
+        async fn open_stream(&self) -> io::Result<usize> {
+            Ok(self.id)
+        }
+    }
+
+    struct Dialer {
+        dialed: AtomicUsize,
+        closed: Arc<AtomicBool>,
+    }
+
+    impl Dialer {
+        fn new() -> Self {
+            Dialer {
+                dialed: AtomicUsize::new(0),
+                closed: Arc::new(AtomicBool::new(false)),
+            }
+        }
+
+        async fn dial(&self) -> io::Result<FakeConnection> {
+            Ok(FakeConnection {
+                id: self.dialed.fetch_add(1, Ordering::Relaxed),
+                closed: self.closed.clone(),
+            })
+        }
+
+        fn dialed(&self) -> usize {
+            self.dialed.load(Ordering::Relaxed)
+        }
+    }
+
+    fn key(host: &str) -> PoolKey {
+        PoolKey {
+            host: host.to_string(),
+            port: 443,
+            transport: "tls+h2".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_reuse() {
+        let pool = ConnectionPool::new(PoolConfig {
+            max_streams: 2,
+            ..Default::default()
+        });
+        let dialer = Dialer::new();
+        let k = key("example.com");
+        let s1 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        let s2 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!((*s1.get_ref(), *s2.get_ref()), (0, 0));
+        assert_eq!(dialer.dialed(), 1);
+
+        // The connection is full.
+        let s3 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!(*s3.get_ref(), 1);
+        assert_eq!(dialer.dialed(), 2);
+
+        // Freed slots are reused.
+        drop(s1);
+        let s4 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!(*s4.get_ref(), 0);
+        assert_eq!(dialer.dialed(), 2);
+        assert_eq!(pool.num_connections(&k).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_pool_keys_not_shared() {
+        let pool = ConnectionPool::new(PoolConfig::default());
+        let dialer = Dialer::new();
+        let _s1 = pool
+            .open_stream(&key("a.example.com"), || dialer.dial())
+            .await
+            .unwrap();
+        let _s2 = pool
+            .open_stream(&key("b.example.com"), || dialer.dial())
+            .await
+            .unwrap();
+        let mut k = key("a.example.com");
+        k.transport = "tls+mux".to_string();
+        let _s3 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!(dialer.dialed(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pool_idle_eviction() {
+        let pool = ConnectionPool::new(PoolConfig {
+            idle_timeout: Duration::from_millis(100),
+            ..Default::default()
+        });
+        let dialer = Dialer::new();
+        let k = key("example.com");
+        let s1 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        // Connections with streams are never evicted.
+        tokio::time::delay_for(Duration::from_millis(150)).await;
+        pool.evict_idle().await;
+        assert_eq!(pool.num_connections(&k).await, 1);
+
+        drop(s1);
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        pool.evict_idle().await;
+        assert_eq!(pool.num_connections(&k).await, 1);
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+        pool.evict_idle().await;
+        assert_eq!(pool.num_connections(&k).await, 0);
+
+        let _s2 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!(dialer.dialed(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pool_max_connections() {
+        let pool = ConnectionPool::new(PoolConfig {
+            max_streams: 1,
+            max_connections: 2,
+            ..Default::default()
+        });
+        let dialer = Dialer::new();
+        let k = key("example.com");
+        let s1 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        let _s2 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert!(pool.open_stream(&k, || dialer.dial()).await.is_err());
+        assert_eq!(dialer.dialed(), 2);
+
+        // Other targets have their own cap.
+        assert!(pool
+            .open_stream(&key("other.example.com"), || dialer.dial())
+            .await
+            .is_ok());
+
+        drop(s1);
+        assert!(pool.open_stream(&k, || dialer.dial()).await.is_ok());
+        assert_eq!(dialer.dialed(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_pool_add_connection() {
+        let pool = ConnectionPool::new(PoolConfig {
+            max_connections: 1,
+            ..Default::default()
+        });
+        let dialer = Dialer::new();
+        let k = key("example.com");
+        let s1 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        let s2 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        // Added even though the pool has room on its connection, and is full.
+        let conn = dialer.dial().await.unwrap();
+        let s3 = pool.add_connection(&k, conn).await.unwrap();
+        assert_eq!((*s1.get_ref(), *s2.get_ref(), *s3.get_ref()), (0, 0, 1));
+        assert_eq!(pool.num_connections(&k).await, 2);
+
+        // The added connection is used by later streams too.
+        let s4 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!(*s4.get_ref(), 1);
+        assert_eq!(dialer.dialed(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pool_closed_connection_replaced() {
+        let pool = ConnectionPool::new(PoolConfig::default());
+        let dialer = Dialer::new();
+        let k = key("example.com");
+        let _s1 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        dialer.closed.store(true, Ordering::Relaxed);
+        assert_eq!(pool.num_connections(&k).await, 0);
+        dialer.closed.store(false, Ordering::Relaxed);
+        let s2 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        assert_eq!(*s2.get_ref(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pool_warm() {
+        let pool = ConnectionPool::new(PoolConfig {
+            max_streams: 1,
+            max_connections: 1,
+            ..Default::default()
+        });
+        let dialer = Dialer::new();
+        let k = key("example.com");
+        assert!(!pool.is_warm(&k));
+        let s1 = pool.open_stream(&k, || dialer.dial()).await.unwrap();
+        // The only connection is full.
+        assert!(!pool.is_warm(&k));
+        drop(s1);
+        assert!(pool.is_warm(&k));
+        assert!(!pool.is_warm(&key("other.example.com")));
+        dialer.closed.store(true, Ordering::Relaxed);
+        assert!(!pool.is_warm(&k));
+    }
+}
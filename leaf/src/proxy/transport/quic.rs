@@ -0,0 +1,261 @@
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::proxy::ProxyStream;
+
+/// NewReno congestion control parameters, unset fields keep quinn's
+/// defaults.
+#[derive(Clone, Debug, Default)]
+pub struct CongestionConfig {
+    /// Congestion window at the start of the connection, in bytes.
+    pub initial_window: Option<u64>,
+    /// Lower bound of the congestion window, in bytes.
+    pub minimum_window: Option<u64>,
+}
+
+/// Client side QUIC settings.
+#[derive(Clone, Debug, Default)]
+pub struct QuicConfig {
+    /// Name sent in SNI and used for certificate verification.
+    pub server_name: String,
+    pub alpns: Vec<String>,
+    /// Additional trusted CA certificates, DER encoded.
+    pub certificates: Vec<Vec<u8>>,
+    /// Sends streams opened right after the handshake as 0-RTT data if the
+    /// server has been seen before.
+    pub zero_rtt: bool,
+    pub congestion: CongestionConfig,
+}
+
+fn quic_error<E: std::fmt::Display>(msg: &str, e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}: {}", msg, e))
+}
+
+fn client_config(config: &QuicConfig) -> io::Result<quinn::ClientConfig> {
+    let mut builder = quinn::ClientConfigBuilder::default();
+    let alpns: Vec<&[u8]> = config.alpns.iter().map(|a| a.as_bytes()).collect();
+    builder.protocols(&alpns);
+    for cert in config.certificates.iter() {
+        let cert =
+            quinn::Certificate::from_der(cert).map_err(|e| quic_error("invalid certificate", e))?;
+        builder
+            .add_certificate_authority(cert)
+            .map_err(|e| quic_error("invalid certificate", e))?;
+    }
+    if config.zero_rtt {
+        builder.enable_0rtt();
+    }
+    let mut client_config = builder.build();
+    let mut transport = quinn::TransportConfig::default();
+    if let Some(window) = config.congestion.initial_window {
+        transport.initial_window(window);
+    }
+    if let Some(window) = config.congestion.minimum_window {
+        transport.minimum_window(window);
+    }
+    client_config.transport = Arc::new(transport);
+    Ok(client_config)
+}
+
+/// A QUIC connection carrying many proxy streams, each flow is a
+/// bidirectional QUIC stream.
+#[derive(Clone)]
+pub struct QuicConnection {
+    // Keeps the endpoint alive as long as the connection.
+    _endpoint: quinn::Endpoint,
+    conn: quinn::Connection,
+}
+
+impl QuicConnection {
+    /// Connects to `addr`, the local UDP socket is bound to `bind_addr`.
+    pub async fn connect(
+        addr: &SocketAddr,
+        bind_addr: &SocketAddr,
+        config: &QuicConfig,
+    ) -> io::Result<Self> {
+        let mut builder = quinn::Endpoint::builder();
+        builder.default_client_config(client_config(config)?);
+        let (endpoint, _) = builder
+            .bind(bind_addr)
+            .map_err(|e| quic_error("bind quic endpoint failed", e))?;
+        let connecting = endpoint
+            .connect(addr, &config.server_name)
+            .map_err(|e| quic_error("quic connect failed", e))?;
+        let new_conn = if config.zero_rtt {
+            match connecting.into_0rtt() {
+                Ok((new_conn, _)) => new_conn,
+                Err(connecting) => connecting
+                    .await
+                    .map_err(|e| quic_error("quic handshake failed", e))?,
+            }
+        } else {
+            connecting
+                .await
+                .map_err(|e| quic_error("quic handshake failed", e))?
+        };
+        Ok(QuicConnection {
+            _endpoint: endpoint,
+            conn: new_conn.connection,
+        })
+    }
+
+    /// Opens a new stream on this connection.
+    pub async fn open_stream(&self) -> io::Result<QuicStream> {
+        let (send, recv) = self
+            .conn
+            .open_bi()
+            .await
+            .map_err(|e| quic_error("open quic stream failed", e))?;
+        Ok(QuicStream { send, recv })
+    }
+}
+
+/// A bidirectional stream of a QUIC connection.
+pub struct QuicStream {
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+impl ProxyStream for QuicStream {}
+
+impl AsyncRead for QuicStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncRead::poll_read(Pin::new(&mut self.recv), cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.send), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.send), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.send), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    const CERT: &[u8] = include_bytes!("testdata/cert.der");
+    const KEY: &[u8] = include_bytes!("testdata/key.der");
+
+    // Accepts a single QUIC connection and echoes every stream on it,
+    // returns the number of streams served.
+    fn serve() -> (SocketAddr, tokio::task::JoinHandle<usize>) {
+        let mut server_config = quinn::ServerConfigBuilder::default();
+        server_config
+            .certificate(
+                quinn::CertificateChain::from_certs(vec![
+                    quinn::Certificate::from_der(CERT).unwrap()
+                ]),
+                quinn::PrivateKey::from_der(KEY).unwrap(),
+            )
+            .unwrap();
+        server_config.protocols(&[b"leaf"]);
+        let mut builder = quinn::Endpoint::builder();
+        builder.listen(server_config.build());
+        let (endpoint, mut incoming) = builder.bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let _endpoint = endpoint;
+            let new_conn = incoming.next().await.unwrap().await.unwrap();
+            let mut bi_streams = new_conn.bi_streams;
+            let count = Arc::new(AtomicUsize::new(0));
+            while let Some(Ok((mut send, mut recv))) = bi_streams.next().await {
+                count.fetch_add(1, Ordering::Relaxed);
+                tokio::spawn(async move {
+                    let _ = tokio::io::copy(&mut recv, &mut send).await;
+                    let _ = send.finish().await;
+                });
+            }
+            count.load(Ordering::Relaxed)
+        });
+        (addr, server)
+    }
+
+    fn config() -> QuicConfig {
+        QuicConfig {
+            server_name: "localhost".to_string(),
+            alpns: vec!["leaf".to_string()],
+            certificates: vec![CERT.to_vec()],
+            congestion: CongestionConfig {
+                initial_window: Some(64 * 1200),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn echo_through(stream: QuicStream, payload: Vec<u8>) {
+        let (mut r, mut w) = tokio::io::split(stream);
+        let expected = payload.clone();
+        let write = async move {
+            w.write_all(&payload).await.unwrap();
+            w.shutdown().await.unwrap();
+        };
+        let read = async move {
+            let mut buf = Vec::new();
+            r.read_to_end(&mut buf).await.unwrap();
+            buf
+        };
+        let (_, echoed) = tokio::join!(write, read);
+        assert_eq!(echoed, expected);
+    }
+
+    #[tokio::test]
+    async fn test_quic_multiple_streams() {
+        let (addr, server) = serve();
+        let bind_addr = "127.0.0.1:0".parse().unwrap();
+        let conn = QuicConnection::connect(&addr, &bind_addr, &config())
+            .await
+            .unwrap();
+        let mut tasks = Vec::new();
+        for i in 0..8u8 {
+            let conn = conn.clone();
+            tasks.push(tokio::spawn(async move {
+                let stream = conn.open_stream().await.unwrap();
+                echo_through(stream, vec![i; 16 * 1024 * (i as usize + 1)]).await;
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        drop(conn);
+        assert_eq!(server.await.unwrap(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_quic_untrusted_certificate() {
+        let (addr, _) = serve();
+        let bind_addr = "127.0.0.1:0".parse().unwrap();
+        let mut config = config();
+        config.certificates.clear();
+        assert!(QuicConnection::connect(&addr, &bind_addr, &config)
+            .await
+            .is_err());
+    }
+}
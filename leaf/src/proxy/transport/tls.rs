@@ -0,0 +1,765 @@
+use std::{fmt, io, pin::Pin};
+
+use futures::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::proxy::ProxyStream;
+
+/// Client side TLS settings.
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+    /// Name sent in SNI and used for certificate verification.
+    pub server_name: String,
+    pub alpns: Vec<String>,
+    /// Skips certificate verification entirely.
+    pub insecure: bool,
+    /// Additional trusted CA certificates, DER encoded.
+    pub certificates: Vec<Vec<u8>>,
+    /// SHA-256 digests of DER encoded certificates. If not empty, the server
+    /// certificate must also match one of them, on top of the CA and server
+    /// name verification.
+    pub pinned_certificates: Vec<Vec<u8>>,
+    /// SHA-256 digests of DER encoded SubjectPublicKeyInfo. Same as
+    /// `pinned_certificates` but survives renewals keeping the same key.
+    pub pinned_spki: Vec<Vec<u8>>,
+    /// Sessions of previous handshakes, resumed when connecting to the same
+    /// server name again.
+    pub session_cache: Option<SessionCache>,
+}
+
+/// Sessions kept by default for resumption.
+pub const DEFAULT_SESSION_CACHE_SIZE: usize = 32;
+
+/// TLS sessions kept for resumption, shared by the connections of an
+/// outbound. The least recently used sessions are evicted past `size`.
+#[derive(Clone)]
+pub struct SessionCache(imp::SessionStore);
+
+impl SessionCache {
+    pub fn new(size: usize) -> Self {
+        SessionCache(imp::new_session_store(size))
+    }
+}
+
+impl fmt::Debug for SessionCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SessionCache")
+    }
+}
+
+fn tls_error<E: std::fmt::Display>(msg: &str, e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}: {}", msg, e))
+}
+
+#[cfg_attr(not(feature = "openssl-tls"), allow(dead_code))]
+fn alpns_to_wire(alpns: &[String]) -> Vec<u8> {
+    alpns
+        .iter()
+        .map(|a| [&[a.len() as u8], a.as_bytes()].concat())
+        .collect::<Vec<Vec<u8>>>()
+        .concat()
+}
+
+// Reads the header of a DER element, returns the tag, the length of the
+// header and the length of the content.
+fn der_header(buf: &[u8]) -> Option<(u8, usize, usize)> {
+    let tag = *buf.get(0)?;
+    let first = *buf.get(1)? as usize;
+    if first < 0x80 {
+        return Some((tag, 2, first));
+    }
+    let n = first & 0x7f;
+    if n == 0 || n > 4 {
+        return None;
+    }
+    let mut len = 0usize;
+    for i in 0..n {
+        len = (len << 8) | *buf.get(2 + i)? as usize;
+    }
+    Some((tag, 2 + n, len))
+}
+
+/// Extracts the DER encoded SubjectPublicKeyInfo of an X.509 certificate.
+fn spki_of(cert: &[u8]) -> Option<&[u8]> {
+    let (_, hl, _) = der_header(cert)?;
+    let tbs = cert.get(hl..)?;
+    let (_, hl, len) = der_header(tbs)?;
+    let mut fields = tbs.get(hl..hl + len)?;
+    // Skips the optional version, then serial number, signature, issuer,
+    // validity and subject.
+    let skip = if fields.first() == Some(&0xa0) { 6 } else { 5 };
+    for _ in 0..skip {
+        let (_, hl, len) = der_header(fields)?;
+        fields = fields.get(hl + len..)?;
+    }
+    let (tag, hl, len) = der_header(fields)?;
+    if tag != 0x30 {
+        return None;
+    }
+    fields.get(..hl + len)
+}
+
+#[cfg(feature = "rustls-tls")]
+mod imp {
+    use std::sync::Arc;
+
+    use sha2::{Digest, Sha256};
+    use tokio_rustls::{
+        rustls::{
+            Certificate, ClientConfig, ClientSessionMemoryCache, RootCertStore, ServerCertVerified,
+            ServerCertVerifier, Session, TLSError, WebPKIVerifier,
+        },
+        webpki::DNSNameRef,
+        TlsConnector,
+    };
+
+    use super::*;
+
+    pub type Inner<S> = tokio_rustls::client::TlsStream<S>;
+
+    pub type SessionStore = Arc<ClientSessionMemoryCache>;
+
+    pub fn new_session_store(size: usize) -> SessionStore {
+        ClientSessionMemoryCache::new(size)
+    }
+
+    struct InsecureVerifier;
+
+    impl ServerCertVerifier for InsecureVerifier {
+        fn verify_server_cert(
+            &self,
+            _roots: &RootCertStore,
+            _presented_certs: &[Certificate],
+            _dns_name: DNSNameRef<'_>,
+            _ocsp_response: &[u8],
+        ) -> Result<ServerCertVerified, TLSError> {
+            Ok(ServerCertVerified::assertion())
+        }
+    }
+
+    struct PinnedVerifier {
+        certificates: Vec<Vec<u8>>,
+        spki: Vec<Vec<u8>>,
+        webpki: WebPKIVerifier,
+    }
+
+    impl ServerCertVerifier for PinnedVerifier {
+        fn verify_server_cert(
+            &self,
+            roots: &RootCertStore,
+            presented_certs: &[Certificate],
+            dns_name: DNSNameRef<'_>,
+            ocsp_response: &[u8],
+        ) -> Result<ServerCertVerified, TLSError> {
+            // The pins are checked once the chain and the name are valid.
+            self.webpki
+                .verify_server_cert(roots, presented_certs, dns_name, ocsp_response)?;
+            let cert = presented_certs.first().ok_or(TLSError::NoCertificatesPresented)?;
+            let digest = Sha256::digest(&cert.0);
+            let spki_digest = spki_of(&cert.0).map(Sha256::digest);
+            if self
+                .certificates
+                .iter()
+                .any(|pin| pin.as_slice() == digest.as_slice())
+                || spki_digest.map_or(false, |d| {
+                    self.spki.iter().any(|pin| pin.as_slice() == d.as_slice())
+                })
+            {
+                Ok(ServerCertVerified::assertion())
+            } else {
+                Err(TLSError::General(
+                    "certificate does not match any pin".to_string(),
+                ))
+            }
+        }
+    }
+
+    pub async fn connect<S>(stream: S, config: &TlsConfig) -> io::Result<Inner<S>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        for cert in config.certificates.iter() {
+            client_config
+                .root_store
+                .add(&Certificate(cert.clone()))
+                .map_err(|e| tls_error("invalid certificate", e))?;
+        }
+        for alpn in config.alpns.iter() {
+            client_config.alpn_protocols.push(alpn.as_bytes().to_vec());
+        }
+        if config.insecure {
+            client_config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(InsecureVerifier));
+        } else if !config.pinned_certificates.is_empty() || !config.pinned_spki.is_empty() {
+            client_config
+                .dangerous()
+                .set_certificate_verifier(Arc::new(PinnedVerifier {
+                    certificates: config.pinned_certificates.clone(),
+                    spki: config.pinned_spki.clone(),
+                    webpki: WebPKIVerifier::new(),
+                }));
+        }
+        if let Some(cache) = &config.session_cache {
+            client_config.set_persistence(cache.0.clone());
+        }
+        let connector = TlsConnector::from(Arc::new(client_config));
+        let name = DNSNameRef::try_from_ascii_str(&config.server_name)
+            .map_err(|e| tls_error("invalid server name", e))?;
+        connector
+            .connect(name, stream)
+            .await
+            .map_err(|e| tls_error("tls handshake failed", e))
+    }
+
+    pub fn alpn_protocol<S>(stream: &Inner<S>) -> Option<&[u8]> {
+        stream.get_ref().1.get_alpn_protocol()
+    }
+}
+
+#[cfg(feature = "openssl-tls")]
+mod imp {
+    use std::sync::{Arc, Mutex, Once};
+
+    use lru::LruCache;
+    use openssl::ssl::{SslConnector, SslMethod, SslSession, SslSessionCacheMode, SslVerifyMode};
+    use openssl::x509::X509;
+
+    use super::*;
+
+    pub type Inner<S> = tokio_openssl::SslStream<S>;
+
+    // Sessions by server name.
+    pub type SessionStore = Arc<Mutex<LruCache<String, SslSession>>>;
+
+    pub fn new_session_store(size: usize) -> SessionStore {
+        Arc::new(Mutex::new(LruCache::new(size)))
+    }
+
+    pub async fn connect<S>(stream: S, config: &TlsConfig) -> io::Result<Inner<S>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        {
+            static ONCE: Once = Once::new();
+            ONCE.call_once(openssl_probe::init_ssl_cert_env_vars);
+        }
+
+        let mut builder = SslConnector::builder(SslMethod::tls())
+            .map_err(|e| tls_error("create tls builder failed", e))?;
+        if !config.alpns.is_empty() {
+            builder
+                .set_alpn_protos(&alpns_to_wire(&config.alpns))
+                .map_err(|e| tls_error("set alpn failed", e))?;
+        }
+        for cert in config.certificates.iter() {
+            let cert = X509::from_der(cert).map_err(|e| tls_error("invalid certificate", e))?;
+            builder
+                .cert_store_mut()
+                .add_cert(cert)
+                .map_err(|e| tls_error("invalid certificate", e))?;
+        }
+        if config.insecure {
+            builder.set_verify(SslVerifyMode::NONE);
+        } else if !config.pinned_certificates.is_empty() || !config.pinned_spki.is_empty() {
+            let pins = config.pinned_certificates.clone();
+            let spki_pins = config.pinned_spki.clone();
+            builder.set_verify_callback(SslVerifyMode::PEER, move |preverify_ok, ctx| {
+                // The pins are checked once the chain and the name are valid,
+                // only against the leaf certificate.
+                if !preverify_ok {
+                    return false;
+                }
+                if ctx.error_depth() != 0 {
+                    return true;
+                }
+                match ctx.current_cert().and_then(|c| c.to_der().ok()) {
+                    Some(der) => {
+                        let digest = openssl::sha::sha256(&der);
+                        let spki_digest = spki_of(&der).map(openssl::sha::sha256);
+                        pins.iter().any(|pin| pin.as_slice() == digest)
+                            || spki_digest
+                                .map_or(false, |d| spki_pins.iter().any(|pin| pin.as_slice() == d))
+                    }
+                    None => false,
+                }
+            });
+        }
+        if let Some(cache) = &config.session_cache {
+            let store = cache.0.clone();
+            let name = config.server_name.clone();
+            builder.set_session_cache_mode(SslSessionCacheMode::CLIENT);
+            builder.set_new_session_callback(move |_, session| {
+                store.lock().unwrap().put(name.clone(), session);
+            });
+        }
+        let mut ssl_config = builder
+            .build()
+            .configure()
+            .map_err(|e| tls_error("configure tls failed", e))?;
+        if let Some(cache) = &config.session_cache {
+            if let Some(session) = cache.0.lock().unwrap().get(&config.server_name) {
+                // Sessions come from connections of the same outbound, made
+                // with the same settings.
+                unsafe { ssl_config.set_session(session) }
+                    .map_err(|e| tls_error("set tls session failed", e))?;
+            }
+        }
+        tokio_openssl::connect(ssl_config, &config.server_name, stream)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "tls handshake failed"))
+    }
+
+    pub fn alpn_protocol<S>(stream: &Inner<S>) -> Option<&[u8]> {
+        stream.ssl().selected_alpn_protocol()
+    }
+}
+
+/// A client TLS session over an arbitrary stream.
+pub struct TlsStream<S>(imp::Inner<S>);
+
+impl<S> TlsStream<S> {
+    /// Returns the protocol selected by ALPN, if any.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        imp::alpn_protocol(&self.0)
+    }
+}
+
+/// Performs a TLS handshake over `stream` with the given settings.
+pub async fn connect<S>(stream: S, config: &TlsConfig) -> io::Result<TlsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    Ok(TlsStream(imp::connect(stream, config).await?))
+}
+
+impl<S: AsyncRead + AsyncWrite + Send + Sync + Unpin> ProxyStream for TlsStream<S> {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.alpn_protocol().map(|p| p.to_vec())
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for TlsStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for TlsStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.0), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(Pin::new(&mut self.0), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alpns_list_to_wire() {
+        let alpns = vec!["h2".to_string(), "http/1.1".to_string()];
+        let expected = b"\x02h2\x08http/1.1";
+        assert_eq!(alpns_to_wire(&alpns), expected);
+    }
+
+    #[test]
+    fn test_spki_of_certificate() {
+        let cert = include_bytes!("testdata/cert.der");
+        let spki = spki_of(cert).unwrap();
+        // A SEQUENCE of the algorithm identifier and the key bit string.
+        let (tag, outer_hl, len) = der_header(spki).unwrap();
+        assert_eq!(tag, 0x30);
+        assert_eq!(outer_hl + len, spki.len());
+        let (tag, hl, len) = der_header(&spki[outer_hl..]).unwrap();
+        assert_eq!(tag, 0x30);
+        let (tag, _, _) = der_header(&spki[outer_hl + hl + len..]).unwrap();
+        assert_eq!(tag, 0x03);
+        assert!(cert.windows(spki.len()).any(|w| w == spki));
+        assert!(spki_of(&cert[..cert.len() / 2]).is_none());
+    }
+
+    // Returns the protocols offered in the ALPN extension of a ClientHello
+    // record.
+    fn offered_alpns(record: &[u8]) -> Vec<String> {
+        // record header, handshake header, version and random
+        let mut pos = 5 + 4 + 2 + 32;
+        pos += 1 + record[pos] as usize; // session id
+        pos += 2 + u16::from_be_bytes([record[pos], record[pos + 1]]) as usize; // cipher suites
+        pos += 1 + record[pos] as usize; // compression methods
+        let end = pos + 2 + u16::from_be_bytes([record[pos], record[pos + 1]]) as usize;
+        pos += 2;
+        while pos < end {
+            let ext_type = u16::from_be_bytes([record[pos], record[pos + 1]]);
+            let ext_len = u16::from_be_bytes([record[pos + 2], record[pos + 3]]) as usize;
+            pos += 4;
+            if ext_type == 0x0010 {
+                let mut alpns = Vec::new();
+                let mut p = pos + 2;
+                while p < pos + ext_len {
+                    let len = record[p] as usize;
+                    alpns.push(String::from_utf8(record[p + 1..p + 1 + len].to_vec()).unwrap());
+                    p += 1 + len;
+                }
+                return alpns;
+            }
+            pos += ext_len;
+        }
+        Vec::new()
+    }
+
+    async fn client_hello_alpns(alpns: Vec<String>) -> Vec<String> {
+        use tokio::io::AsyncReadExt;
+        use tokio::net::{TcpListener, TcpStream};
+
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut header = [0u8; 5];
+            stream.read_exact(&mut header).await.unwrap();
+            assert_eq!(header[0], 0x16);
+            let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+            let mut record = header.to_vec();
+            record.resize(5 + len, 0);
+            stream.read_exact(&mut record[5..]).await.unwrap();
+            offered_alpns(&record)
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let config = TlsConfig {
+            server_name: "example.com".to_string(),
+            alpns,
+            ..Default::default()
+        };
+        // The server never answers, the handshake fails once it's gone.
+        let client = tokio::spawn(async move {
+            let _ = connect(stream, &config).await;
+        });
+        let offered = server.await.unwrap();
+        client.await.unwrap();
+        offered
+    }
+
+    #[tokio::test]
+    async fn test_alpn_offered_in_client_hello() {
+        let offered = client_hello_alpns(vec!["h2".to_string(), "http/1.1".to_string()]).await;
+        assert_eq!(offered, vec!["h2", "http/1.1"]);
+    }
+
+    #[tokio::test]
+    async fn test_no_alpn_offered_by_default() {
+        assert!(client_hello_alpns(Vec::new()).await.is_empty());
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    mod rustls_server {
+        use std::sync::Arc;
+
+        use sha2::{Digest, Sha256};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::{TcpListener, TcpStream};
+        use tokio_rustls::{
+            rustls::{Certificate, NoClientAuth, PrivateKey, ServerConfig},
+            TlsAcceptor,
+        };
+
+        use super::super::*;
+
+        const CERT: &[u8] = include_bytes!("testdata/cert.der");
+        const KEY: &[u8] = include_bytes!("testdata/key.der");
+
+        // Accepts one TLS connection, echoes 4 bytes and returns the SNI
+        // and ALPN seen by the server.
+        async fn run(config: TlsConfig) -> (io::Result<()>, Option<String>, Option<Vec<u8>>) {
+            let mut server_config = ServerConfig::new(NoClientAuth::new());
+            server_config
+                .set_single_cert(vec![Certificate(CERT.to_vec())], PrivateKey(KEY.to_vec()))
+                .unwrap();
+            server_config.set_protocols(&[b"h2".to_vec()]);
+            let acceptor = TlsAcceptor::from(Arc::new(server_config));
+            let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = tokio::spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = match acceptor.accept(stream).await {
+                    Ok(s) => s,
+                    Err(_) => return None,
+                };
+                let sni = stream.get_ref().1.get_sni_hostname().map(|s| s.to_string());
+                let mut buf = [0u8; 4];
+                stream.read_exact(&mut buf).await.unwrap();
+                stream.write_all(&buf).await.unwrap();
+                stream.flush().await.unwrap();
+                sni
+            });
+
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let res = connect(stream, &config).await;
+            let (res, alpn) = match res {
+                Ok(mut stream) => {
+                    let alpn = stream.alpn_protocol().map(|p| p.to_vec());
+                    stream.write_all(b"ping").await.unwrap();
+                    let mut buf = [0u8; 4];
+                    stream.read_exact(&mut buf).await.unwrap();
+                    assert_eq!(&buf, b"ping");
+                    (Ok(()), alpn)
+                }
+                Err(e) => (Err(e), None),
+            };
+            let sni = server.await.unwrap();
+            (res, sni, alpn)
+        }
+
+        #[tokio::test]
+        async fn test_tls_handshake_insecure() {
+            let (res, sni, alpn) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                alpns: vec!["h2".to_string(), "http/1.1".to_string()],
+                insecure: true,
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_ok());
+            assert_eq!(sni.as_deref(), Some("example.com"));
+            assert_eq!(alpn.as_deref(), Some(&b"h2"[..]));
+        }
+
+        #[tokio::test]
+        async fn test_tls_alpn_not_negotiated() {
+            let (res, _, alpn) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                insecure: true,
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_ok());
+            assert!(alpn.is_none());
+        }
+
+        #[tokio::test]
+        async fn test_tls_sni_propagation() {
+            let (res, sni, _) = run(TlsConfig {
+                server_name: "localhost".to_string(),
+                insecure: true,
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_ok());
+            assert_eq!(sni.as_deref(), Some("localhost"));
+        }
+
+        #[tokio::test]
+        async fn test_tls_untrusted_certificate() {
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_tls_pinned_certificate() {
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                certificates: vec![CERT.to_vec()],
+                pinned_certificates: vec![vec![0u8; 32], Sha256::digest(CERT).to_vec()],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_tls_trusted_certificate() {
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                certificates: vec![CERT.to_vec()],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_tls_pinned_certificate_untrusted() {
+            // A matching pin doesn't stand in for the CA verification.
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                pinned_certificates: vec![Sha256::digest(CERT).to_vec()],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_tls_pinned_spki_wrong_name() {
+            // Nor for the server name verification.
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.org".to_string(),
+                certificates: vec![CERT.to_vec()],
+                pinned_spki: vec![spki_pin()],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_err());
+        }
+
+        fn spki_pin() -> Vec<u8> {
+            Sha256::digest(spki_of(CERT).unwrap()).to_vec()
+        }
+
+        #[tokio::test]
+        async fn test_tls_pinned_spki() {
+            // The old pin stays around during a key rotation.
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                certificates: vec![CERT.to_vec()],
+                pinned_spki: vec![vec![1u8; 32], spki_pin()],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_ok());
+        }
+
+        #[tokio::test]
+        async fn test_tls_pinned_spki_mismatch() {
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                certificates: vec![CERT.to_vec()],
+                pinned_spki: vec![vec![1u8; 32]],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_tls_certificate_digest_is_not_spki_pin() {
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                certificates: vec![CERT.to_vec()],
+                pinned_spki: vec![Sha256::digest(CERT).to_vec()],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.is_err());
+        }
+
+        // Counts the bytes read from the server.
+        struct CountingStream {
+            inner: TcpStream,
+            read: usize,
+        }
+
+        impl AsyncRead for CountingStream {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                let res = AsyncRead::poll_read(Pin::new(&mut self.inner), cx, buf);
+                if let Poll::Ready(Ok(n)) = res {
+                    self.read += n;
+                }
+                res
+            }
+        }
+
+        impl AsyncWrite for CountingStream {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                AsyncWrite::poll_write(Pin::new(&mut self.inner), cx, buf)
+            }
+
+            fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+                AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+            }
+
+            fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+                AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+            }
+        }
+
+        // Returns the bytes the server sent for a handshake and an echo.
+        async fn exchange(addr: std::net::SocketAddr, config: &TlsConfig) -> usize {
+            let stream = CountingStream {
+                inner: TcpStream::connect(addr).await.unwrap(),
+                read: 0,
+            };
+            let mut stream = connect(stream, config).await.unwrap();
+            stream.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.0.get_ref().0.read
+        }
+
+        #[tokio::test]
+        async fn test_tls_session_resumption() {
+            let mut server_config = ServerConfig::new(NoClientAuth::new());
+            server_config
+                .set_single_cert(vec![Certificate(CERT.to_vec())], PrivateKey(KEY.to_vec()))
+                .unwrap();
+            server_config.ticketer = tokio_rustls::rustls::Ticketer::new();
+            let acceptor = TlsAcceptor::from(Arc::new(server_config));
+            let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let mut stream = acceptor.accept(stream).await.unwrap();
+                    let mut buf = [0u8; 4];
+                    stream.read_exact(&mut buf).await.unwrap();
+                    stream.write_all(&buf).await.unwrap();
+                    stream.flush().await.unwrap();
+                }
+            });
+
+            let config = TlsConfig {
+                server_name: "example.com".to_string(),
+                insecure: true,
+                session_cache: Some(SessionCache::new(DEFAULT_SESSION_CACHE_SIZE)),
+                ..Default::default()
+            };
+            let full = exchange(addr, &config).await;
+            let resumed = exchange(addr, &config).await;
+            // No certificate sent on the resumed handshake.
+            assert!(resumed + CERT.len() < full, "{} {}", resumed, full);
+        }
+
+        #[tokio::test]
+        async fn test_tls_pinned_certificate_mismatch() {
+            let (res, _, _) = run(TlsConfig {
+                server_name: "example.com".to_string(),
+                certificates: vec![CERT.to_vec()],
+                pinned_certificates: vec![vec![0u8; 32]],
+                ..Default::default()
+            })
+            .await;
+            assert!(res.unwrap_err().to_string().contains("tls handshake failed"));
+        }
+    }
+}
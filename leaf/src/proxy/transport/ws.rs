@@ -0,0 +1,605 @@
+use std::cmp::min;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use bytes::BytesMut;
+use futures::ready;
+use futures::sink::{Sink, SinkExt};
+use futures::stream::Stream;
+use futures::task::{Context, Poll, Waker};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::{client_async_with_config, WebSocketStream};
+use tungstenite::error::Error as WsError;
+use tungstenite::handshake::client::Request;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::Message;
+
+use crate::proxy::ProxyStream;
+
+/// Client side WebSocket settings.
+#[derive(Clone, Debug, Default)]
+pub struct WsConfig {
+    /// Request path, defaults to `/`.
+    pub path: String,
+    /// Value of the `Host` header, usually the authority of the target.
+    pub host: String,
+    /// Extra headers sent with the upgrade request.
+    pub headers: Vec<(String, String)>,
+    /// Header carrying early data, `Sec-WebSocket-Protocol` if empty.
+    pub early_data_header_name: String,
+    /// Maximum number of bytes of the first write sent as early data, 0
+    /// disables early data.
+    pub max_early_data: usize,
+}
+
+fn ws_error<E: std::fmt::Display>(msg: &str, e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{}: {}", msg, e))
+}
+
+fn early_data_header_name(config: &WsConfig) -> &str {
+    if config.early_data_header_name.is_empty() {
+        "Sec-WebSocket-Protocol"
+    } else {
+        config.early_data_header_name.as_str()
+    }
+}
+
+fn build_request(config: &WsConfig, early_data: Option<&[u8]>) -> io::Result<Request> {
+    let uri = if config.path.starts_with('/') {
+        format!("ws://{}{}", config.host, config.path)
+    } else {
+        format!("ws://{}/{}", config.host, config.path)
+    };
+    let mut builder = Request::builder().uri(&uri);
+    for (k, v) in config.headers.iter() {
+        builder = builder.header(k.as_str(), v.as_str());
+    }
+    if let Some(data) = early_data {
+        builder = builder.header(
+            early_data_header_name(config),
+            base64::encode_config(data, base64::URL_SAFE_NO_PAD).as_str(),
+        );
+    }
+    builder
+        .body(())
+        .map_err(|e| ws_error(&format!("invalid ws request {}", &uri), e))
+}
+
+/// Performs the WebSocket upgrade over `stream`.
+///
+/// If `early_data` is given, it's sent in a header of the upgrade request
+/// and the server is expected to treat it as the first payload. A server
+/// accepting early data echoes the header in its response, otherwise the
+/// data is sent again as the first message.
+pub async fn connect<S>(
+    stream: S,
+    config: &WsConfig,
+    early_data: Option<&[u8]>,
+) -> io::Result<WsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request = build_request(config, early_data)?;
+    let ws_config = WebSocketConfig {
+        max_send_queue: Some(1),
+        max_message_size: Some(64 << 20),
+        max_frame_size: Some(16 << 20),
+    };
+    let (mut socket, resp) = client_async_with_config(request, stream, Some(ws_config))
+        .await
+        .map_err(|e| {
+            ws_error(
+                &format!("connect ws {}{} failed", &config.host, &config.path),
+                e,
+            )
+        })?;
+    if let Some(data) = early_data {
+        if !resp.headers().contains_key(early_data_header_name(config)) {
+            socket
+                .send(Message::Binary(data.to_vec()))
+                .await
+                .map_err(|e| ws_error("send early data failed", e))?;
+        }
+    }
+    Ok(Adapter::new(socket))
+}
+
+// The handshake future is only polled through a mutable reference, the
+// mutex merely makes the stream Sync.
+type Connecting<S> = Mutex<Pin<Box<dyn Future<Output = io::Result<WsStream<S>>> + Send>>>;
+
+enum LazyState<S> {
+    Idle(Option<S>),
+    // Holds the length of the early data if the handshake was started by a
+    // write.
+    Connecting(Connecting<S>, Option<usize>),
+    Connected(WsStream<S>),
+}
+
+/// A WebSocket stream deferring the upgrade until the first write, whose
+/// payload is then sent as early data.
+///
+/// A read before any write starts the upgrade without early data.
+pub struct LazyWsStream<S> {
+    config: WsConfig,
+    state: LazyState<S>,
+    // Length of the early data not yet reported to the writer.
+    early_data_sent: Option<usize>,
+    // Tasks waiting for the handshake, both halves of a split stream may be
+    // polling it.
+    waiters: Vec<Waker>,
+}
+
+impl<S> LazyWsStream<S> {
+    pub fn new(stream: S, config: WsConfig) -> Self {
+        LazyWsStream {
+            config,
+            state: LazyState::Idle(Some(stream)),
+            early_data_sent: None,
+            waiters: Vec::new(),
+        }
+    }
+}
+
+impl<S> LazyWsStream<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
+{
+    fn start_connect(&mut self, early_data: Option<&[u8]>) {
+        if let LazyState::Idle(stream) = &mut self.state {
+            let stream = stream.take().expect("stream taken");
+            let config = self.config.clone();
+            let early_data = early_data.map(|d| d.to_vec());
+            let len = early_data.as_ref().map(|d| d.len());
+            let fut = async move { connect(stream, &config, early_data.as_deref()).await };
+            self.state = LazyState::Connecting(Mutex::new(Box::pin(fut)), len);
+        }
+    }
+
+    fn poll_connected(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if let LazyState::Connecting(fut, len) = &mut self.state {
+            let len = *len;
+            match fut.get_mut().unwrap().as_mut().poll(cx) {
+                Poll::Ready(res) => {
+                    for waiter in self.waiters.drain(..) {
+                        waiter.wake();
+                    }
+                    self.state = LazyState::Connected(res?);
+                    self.early_data_sent = len;
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Pending => {
+                    if !self.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                        self.waiters.push(cx.waker().clone());
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> ProxyStream for LazyWsStream<S> where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send + Sync
+{
+}
+
+impl<S> AsyncRead for LazyWsStream<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.start_connect(None);
+        ready!(self.poll_connected(cx))?;
+        match &mut self.state {
+            LazyState::Connected(ws) => Pin::new(ws).poll_read(cx, buf),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<S> AsyncWrite for LazyWsStream<S>
+where
+    S: 'static + AsyncRead + AsyncWrite + Unpin + Send,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let early_data = &buf[..min(buf.len(), self.config.max_early_data)];
+        if early_data.is_empty() {
+            self.start_connect(None);
+        } else {
+            self.start_connect(Some(early_data));
+        }
+        ready!(self.poll_connected(cx))?;
+        // The caller retries with the same buffer after pending, the part
+        // already sent as early data is not written again.
+        if let Some(n) = self.early_data_sent.take() {
+            return Poll::Ready(Ok(n));
+        }
+        match &mut self.state {
+            LazyState::Connected(ws) => Pin::new(ws).poll_write(cx, buf),
+            _ => unreachable!(),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_connected(cx))?;
+        match &mut self.state {
+            LazyState::Connected(ws) => Pin::new(ws).poll_flush(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_connected(cx))?;
+        match &mut self.state {
+            LazyState::Connected(ws) => Pin::new(ws).poll_shutdown(cx),
+            LazyState::Idle(stream) => match stream {
+                Some(stream) => Pin::new(stream).poll_shutdown(cx),
+                None => Poll::Ready(Ok(())),
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// A WebSocket connection carrying stream data in binary messages.
+pub type WsStream<S> = Adapter<WebSocketStream<S>>;
+
+pub struct Adapter<S> {
+    buf: BytesMut,
+    inner: S,
+}
+
+impl<S> Adapter<S> {
+    pub fn new(stream: S) -> Self {
+        Adapter {
+            buf: BytesMut::new(),
+            inner: stream,
+        }
+    }
+}
+
+impl<S> ProxyStream for Adapter<S> where
+    S: Stream<Item = Result<Message, WsError>> + Sink<Message> + Send + Sync + Unpin
+{
+}
+
+impl<S: Stream<Item = Result<Message, WsError>> + Sink<Message> + Unpin> AsyncRead for Adapter<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.buf.is_empty() {
+            let to_read = min(buf.len(), self.buf.len());
+            let for_read = self.buf.split_to(to_read);
+            (&mut buf[..to_read]).copy_from_slice(&for_read[..to_read]);
+            return Poll::Ready(Ok(to_read));
+        }
+        let item = match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(item) => item,
+            Poll::Pending => return Poll::Pending,
+        };
+        match item {
+            Some(item) => {
+                match item {
+                    Ok(msg) => {
+                        match msg {
+                            Message::Binary(data) => {
+                                let to_read = min(buf.len(), data.len());
+                                (&mut buf[..to_read]).copy_from_slice(&data[..to_read]);
+                                if data.len() > to_read {
+                                    self.buf.extend_from_slice(&data[to_read..]);
+                                }
+                                Poll::Ready(Ok(to_read))
+                            }
+                            Message::Close(_) => {
+                                // FIXME should we send close here?
+                                Pin::new(&mut self.inner)
+                                    .poll_close(cx)
+                                    .map_ok(|_| 0)
+                                    .map_err(|_| {
+                                        io::Error::new(io::ErrorKind::Other, "error closing")
+                                    })
+                            }
+                            _ => {
+                                // FIXME
+                                Poll::Ready(Err(io::Error::new(
+                                    io::ErrorKind::Interrupted,
+                                    "unexpected ws msg",
+                                )))
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        // FIXME
+                        Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::Interrupted,
+                            format!("ws error: {}", err),
+                        )))
+                    }
+                }
+            }
+            None => {
+                // FIXME
+                Poll::Ready(Err(io::Error::new(io::ErrorKind::Interrupted, "none msg")))
+            }
+        }
+    }
+}
+
+impl<S: Sink<Message> + Unpin> AsyncWrite for Adapter<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if let Poll::Pending = Pin::new(&mut self.inner).poll_ready(cx) {
+            return Poll::Pending;
+        }
+
+        let msg = Message::Binary(Vec::from(buf));
+        match Pin::new(&mut self.inner).start_send(msg) {
+            Err(_) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "ws send error",
+            ))),
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        match Pin::new(&mut self.inner).poll_flush(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        match Pin::new(&mut self.inner).poll_close(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(_) => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tungstenite::handshake::server::{ErrorResponse, Request as ServerRequest, Response};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Upgrade {
+        path: String,
+        host: String,
+        user_agent: String,
+        protocol: String,
+    }
+
+    fn header(req: &ServerRequest, name: &str) -> String {
+        req.headers()
+            .get(name)
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default()
+    }
+
+    // Accepts a WebSocket connection, records the upgrade request and echoes
+    // `n` messages back. Early data is acknowledged by echoing the protocol
+    // header if `ack_early_data` is set.
+    async fn serve<S>(stream: S, n: usize, ack_early_data: bool) -> (Upgrade, Vec<Vec<u8>>)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let upgrade = Arc::new(Mutex::new(Upgrade::default()));
+        let recorded = upgrade.clone();
+        let callback =
+            move |req: &ServerRequest, resp: Response| -> Result<Response, ErrorResponse> {
+                let mut u = recorded.lock().unwrap();
+                u.path = req.uri().to_string();
+                u.host = header(req, "host");
+                u.user_agent = header(req, "user-agent");
+                u.protocol = header(req, "sec-websocket-protocol");
+                let mut resp = resp;
+                if let Some(protocol) = req.headers().get("sec-websocket-protocol") {
+                    if ack_early_data {
+                        resp.headers_mut()
+                            .insert("sec-websocket-protocol", protocol.clone());
+                    }
+                }
+                Ok(resp)
+            };
+        let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+            .await
+            .unwrap();
+        let mut received = Vec::new();
+        for _ in 0..n {
+            let msg = ws.next().await.unwrap().unwrap();
+            if let Message::Binary(data) = &msg {
+                received.push(data.clone());
+            }
+            ws.send(msg).await.unwrap();
+        }
+        let upgrade = std::mem::take(&mut *upgrade.lock().unwrap());
+        (upgrade, received)
+    }
+
+    async fn run(
+        config: WsConfig,
+        early_data: Option<&[u8]>,
+        payloads: &[&[u8]],
+    ) -> (Upgrade, Vec<Vec<u8>>) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let n = payloads.len();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, n, true).await
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut ws = connect(stream, &config, early_data).await.unwrap();
+        for payload in payloads {
+            ws.write_all(payload).await.unwrap();
+            ws.flush().await.unwrap();
+        }
+        let expected = payloads.concat();
+        let mut buf = vec![0u8; expected.len()];
+        ws.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, expected);
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ws_upgrade_handshake() {
+        let config = WsConfig {
+            path: "/ray?ed=2048".to_string(),
+            host: "cdn.example.com".to_string(),
+            headers: vec![("User-Agent".to_string(), "leaf".to_string())],
+            ..Default::default()
+        };
+        let (upgrade, _) = run(config, None, &[]).await;
+        assert_eq!(upgrade.path, "/ray?ed=2048");
+        assert_eq!(upgrade.host, "cdn.example.com");
+        assert_eq!(upgrade.user_agent, "leaf");
+        assert!(upgrade.protocol.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ws_default_path() {
+        let config = WsConfig {
+            host: "example.com:80".to_string(),
+            ..Default::default()
+        };
+        let (upgrade, _) = run(config, None, &[]).await;
+        assert_eq!(upgrade.path, "/");
+        assert_eq!(upgrade.host, "example.com:80");
+    }
+
+    #[tokio::test]
+    async fn test_ws_framing_round_trip() {
+        let config = WsConfig {
+            host: "example.com".to_string(),
+            ..Default::default()
+        };
+        let (_, received) = run(config, None, &[b"hello", b"world", &[0u8; 70000]]).await;
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[0], b"hello");
+        assert_eq!(received[1], b"world");
+        assert_eq!(received[2].len(), 70000);
+    }
+
+    #[tokio::test]
+    async fn test_ws_early_data_header() {
+        let config = WsConfig {
+            host: "example.com".to_string(),
+            ..Default::default()
+        };
+        let (upgrade, _) = run(config, Some(b"\x00early\xff"), &[]).await;
+        let data = base64::decode_config(&upgrade.protocol, base64::URL_SAFE_NO_PAD).unwrap();
+        assert_eq!(data, b"\x00early\xff");
+    }
+
+    // Writes a payload through a lazy stream, returns what the server sees,
+    // expecting `n` messages.
+    async fn run_lazy(ack_early_data: bool, n: usize) -> (Upgrade, Vec<Vec<u8>>) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, n, ack_early_data).await
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let config = WsConfig {
+            host: "example.com".to_string(),
+            max_early_data: 8,
+            ..Default::default()
+        };
+        let mut ws = LazyWsStream::new(stream, config);
+        ws.write_all(b"hello world!").await.unwrap();
+        ws.flush().await.unwrap();
+        let res = server.await.unwrap();
+        drop(ws);
+        res
+    }
+
+    #[tokio::test]
+    async fn test_ws_lazy_early_data() {
+        let (upgrade, received) = run_lazy(true, 1).await;
+        let data = base64::decode_config(&upgrade.protocol, base64::URL_SAFE_NO_PAD).unwrap();
+        assert_eq!(data, b"hello wo");
+        assert_eq!(received, vec![b"rld!".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_ws_early_data_fallback() {
+        // The server ignores the early data, it comes again as a message.
+        let (upgrade, received) = run_lazy(false, 2).await;
+        assert!(!upgrade.protocol.is_empty());
+        assert_eq!(received, vec![b"hello wo".to_vec(), b"rld!".to_vec()]);
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    #[tokio::test]
+    async fn test_wss_over_tls_transport() {
+        use tokio_rustls::{
+            rustls::{Certificate, NoClientAuth, PrivateKey, ServerConfig},
+            TlsAcceptor,
+        };
+
+        use crate::proxy::transport::tls::{self, TlsConfig};
+
+        let mut server_config = ServerConfig::new(NoClientAuth::new());
+        server_config
+            .set_single_cert(
+                vec![Certificate(include_bytes!("testdata/cert.der").to_vec())],
+                PrivateKey(include_bytes!("testdata/key.der").to_vec()),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let stream = acceptor.accept(stream).await.unwrap();
+            serve(stream, 1, true).await
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let tls_config = TlsConfig {
+            server_name: "example.com".to_string(),
+            insecure: true,
+            ..Default::default()
+        };
+        let stream = tls::connect(stream, &tls_config).await.unwrap();
+        let ws_config = WsConfig {
+            path: "/wss".to_string(),
+            host: "example.com".to_string(),
+            ..Default::default()
+        };
+        let mut ws = connect(stream, &ws_config, None).await.unwrap();
+        ws.write_all(b"secure").await.unwrap();
+        ws.flush().await.unwrap();
+        let mut buf = [0u8; 6];
+        ws.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"secure");
+        let (upgrade, received) = server.await.unwrap();
+        assert_eq!(upgrade.path, "/wss");
+        assert_eq!(received, vec![b"secure".to_vec()]);
+    }
+}
@@ -60,3 +60,66 @@ impl ProxyTcpHandler for Handler {
         Ok(stream)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    use crate::session::SocksAddr;
+
+    const PASSWORD_HASH: &[u8] = b"d63dc919e201d7bc4c825630d2cf25fdc93d4b2f0d46706d29038d01";
+
+    #[tokio::test]
+    async fn test_trojan_tcp_handshake() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+
+        // A minimal trojan server which validates the request header and
+        // echoes back whatever follows it.
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; PASSWORD_HASH.len() + 2 + 1];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf[..PASSWORD_HASH.len()], PASSWORD_HASH);
+            assert_eq!(&buf[PASSWORD_HASH.len()..PASSWORD_HASH.len() + 2], b"\r\n");
+            assert_eq!(buf[PASSWORD_HASH.len() + 2], 0x01);
+            let addr = SocksAddr::read_from(&mut stream, SocksAddrWireType::PortLast)
+                .await
+                .unwrap();
+            let mut crlf = [0u8; 2];
+            stream.read_exact(&mut crlf).await.unwrap();
+            assert_eq!(&crlf, b"\r\n");
+            let mut payload = [0u8; 4];
+            stream.read_exact(&mut payload).await.unwrap();
+            stream.write_all(&payload).await.unwrap();
+            addr
+        });
+
+        let handler = Handler {
+            address: server_addr.ip().to_string(),
+            port: server_addr.port(),
+            password: "password".to_string(),
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        match server.await.unwrap() {
+            SocksAddr::Domain(domain, port) => {
+                assert_eq!(domain, "example.com");
+                assert_eq!(port, 443);
+            }
+            _ => panic!("unexpected address type"),
+        }
+    }
+}
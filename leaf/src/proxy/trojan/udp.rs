@@ -1,21 +1,18 @@
-use std::cmp::min;
 use std::io;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use byteorder::{BigEndian, ByteOrder};
 use bytes::{BufMut, BytesMut};
 use futures::future::TryFutureExt;
-use log::*;
 use sha2::{Digest, Sha224};
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
 
 use crate::{
     common::dns_client::DnsClient,
     proxy::{
-        ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyStream, ProxyUdpHandler,
-        UdpTransportType,
+        DatagramCodec, DatagramReader, ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf,
+        ProxyStream, ProxyUdpHandler, UdpTransportType,
     },
     session::{Session, SocksAddr, SocksAddrWireType},
 };
@@ -89,11 +86,17 @@ where
         Box<dyn ProxyDatagramSendHalf>,
     ) {
         let (r, w) = tokio::io::split(self.stream);
-        (Box::new(DatagramRecvHalf(r)), Box::new(DatagramSendHalf(w)))
+        (
+            Box::new(DatagramRecvHalf(DatagramReader::new(
+                r,
+                DatagramCodec::with_crlf(),
+            ))),
+            Box::new(DatagramSendHalf(w)),
+        )
     }
 }
 
-pub struct DatagramRecvHalf<T>(ReadHalf<T>);
+pub struct DatagramRecvHalf<T>(DatagramReader<ReadHalf<T>>);
 
 #[async_trait]
 impl<T> ProxyDatagramRecvHalf for DatagramRecvHalf<T>
@@ -101,33 +104,7 @@ where
     T: AsyncRead + AsyncWrite + Send + Sync,
 {
     async fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
-        let addr = SocksAddr::read_from(&mut self.0, SocksAddrWireType::PortLast).await?;
-        let mut buf2 = BytesMut::new();
-        buf2.resize(2, 0);
-        let _ = self.0.read_exact(&mut buf2).await?;
-        let payload_len = BigEndian::read_u16(&buf2);
-        let _ = self.0.read_exact(&mut buf2).await?;
-        if &buf2[..2] != b"\r\n" {
-            return Err(io::Error::new(io::ErrorKind::Other, "expected CLRF"));
-        }
-        buf2.resize(payload_len as usize, 0);
-        let _ = self.0.read_exact(&mut buf2).await?;
-        let to_write = min(buf2.len(), buf.len());
-        if to_write < buf2.len() {
-            warn!(
-                "trucated udp payload, buf size too small: {} < {}",
-                buf.len(),
-                buf2.len()
-            );
-        }
-        buf[..to_write].copy_from_slice(&buf2[..to_write]);
-        match addr {
-            SocksAddr::Ip(a) => Ok((to_write, a)),
-            _ => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "unexpected domain address",
-            )),
-        }
+        self.0.recv_from(buf).await
     }
 }
 
@@ -141,10 +118,99 @@ where
     async fn send_to(&mut self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
         let mut data = BytesMut::new();
         let target = SocksAddr::from(target.to_owned());
-        target.write_buf(&mut data, SocksAddrWireType::PortLast)?;
-        data.put_u16(buf.len() as u16);
-        data.put_slice(b"\r\n");
-        data.put_slice(buf);
+        DatagramCodec::with_crlf().encode(&target, buf, &mut data)?;
         self.0.write_all(&data).map_ok(|_| buf.len()).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        (client.unwrap(), server.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn test_trojan_udp_framing() {
+        let (client, mut server) = tcp_pair().await;
+        let (mut r, mut s) = Box::new(Datagram { stream: client }).split();
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 53);
+
+        assert_eq!(s.send_to(b"hello", &target).await.unwrap(), 5);
+        let mut buf = [0u8; 1 + 4 + 2 + 2 + 2 + 5];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(
+            &buf[..],
+            &[1, 1, 2, 3, 4, 0, 53, 0, 5, b'\r', b'\n', b'h', b'e', b'l', b'l', b'o'][..]
+        );
+
+        // Echo the frame back, the receiving half must parse it identically.
+        server.write_all(&buf).await.unwrap();
+        let mut recv_buf = [0u8; 64];
+        let (n, from) = r.recv_from(&mut recv_buf).await.unwrap();
+        assert_eq!(&recv_buf[..n], b"hello");
+        assert_eq!(from, target);
+    }
+
+    #[tokio::test]
+    async fn test_trojan_udp_bad_frame() {
+        let (client, mut server) = tcp_pair().await;
+        let (mut r, _) = Box::new(Datagram { stream: client }).split();
+        server
+            .write_all(&[1, 1, 2, 3, 4, 0, 53, 0, 5, b'x', b'x'])
+            .await
+            .unwrap();
+        let mut buf = [0u8; 64];
+        assert!(r.recv_from(&mut buf).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trojan_udp_through_server() {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = listener.local_addr().unwrap();
+
+        // A minimal trojan server which checks the UDP associate request and
+        // echoes the first packet frame back.
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 56 + 2 + 1];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf[58], 0x03);
+            let _ = SocksAddr::read_from(&mut stream, SocksAddrWireType::PortLast)
+                .await
+                .unwrap();
+            let mut crlf = [0u8; 2];
+            stream.read_exact(&mut crlf).await.unwrap();
+            assert_eq!(&crlf, b"\r\n");
+            let mut frame = [0u8; 1 + 4 + 2 + 2 + 2 + 4];
+            stream.read_exact(&mut frame).await.unwrap();
+            stream.write_all(&frame).await.unwrap();
+        });
+
+        let handler = Handler {
+            address: server_addr.ip().to_string(),
+            port: server_addr.port(),
+            password: "password".to_string(),
+            bind_addr: "127.0.0.1:0".parse().unwrap(),
+            dns_client: Arc::new(DnsClient::default()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::empty_ipv4(),
+        };
+        let (mut r, mut s) = handler.connect(&sess, None, None).await.unwrap().split();
+        let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53);
+        s.send_to(b"ping", &target).await.unwrap();
+        let mut buf = [0u8; 64];
+        let (n, from) = r.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(from, target);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod stream;
 pub mod tcp;
 pub mod udp;
 
@@ -5,3 +6,14 @@ pub use tcp::Handler as TcpHandler;
 pub use udp::Handler as UdpHandler;
 
 pub static NAME: &str = "tryall";
+
+/// How far a TCP attempt has to get to win the race.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RaceUntil {
+    /// The actor returns a stream.
+    Connect,
+    /// The first payload is written to the stream.
+    Write,
+    /// The first payload is written and a byte of response is read.
+    Response,
+}
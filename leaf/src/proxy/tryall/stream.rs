@@ -0,0 +1,276 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+use bytes::Bytes;
+use futures::{
+    future::{BoxFuture, FutureExt},
+    ready,
+    stream::{FuturesUnordered, StreamExt},
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::proxy::ProxyStream;
+
+pub type Connect = BoxFuture<'static, io::Result<Box<dyn ProxyStream>>>;
+
+type Attempt = BoxFuture<'static, io::Result<(Box<dyn ProxyStream>, Option<u8>)>>;
+
+fn all_failed(last_err: Option<io::Error>) -> io::Error {
+    match last_err {
+        Some(e) => io::Error::new(
+            io::ErrorKind::Other,
+            format!("all outbound attempts failed, last error: {}", e),
+        ),
+        None => io::Error::new(io::ErrorKind::Other, "all outbound attempts failed"),
+    }
+}
+
+// Writes the payload, then reads a byte of response if asked to.
+fn attempt(mut stream: Box<dyn ProxyStream>, payload: Bytes, wait_response: bool) -> Attempt {
+    async move {
+        stream.write_all(&payload).await?;
+        stream.flush().await?;
+        if !wait_response {
+            return Ok((stream, None));
+        }
+        let mut buf = [0u8; 1];
+        if stream.read(&mut buf).await? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "closed before responding",
+            ));
+        }
+        Ok((stream, Some(buf[0])))
+    }
+    .boxed()
+}
+
+// The actors still in the race.
+struct Race {
+    connects: FuturesUnordered<Connect>,
+    // Connected, waiting for the payload.
+    idle: Vec<Box<dyn ProxyStream>>,
+    attempts: FuturesUnordered<Attempt>,
+    payload: Option<Bytes>,
+    wait_response: bool,
+    last_err: Option<io::Error>,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+}
+
+impl Race {
+    fn start(&mut self, payload: Bytes) {
+        for stream in self.idle.drain(..) {
+            self.attempts
+                .push(attempt(stream, payload.clone(), self.wait_response));
+        }
+        self.payload = Some(payload);
+    }
+
+    fn poll_winner(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<io::Result<(Box<dyn ProxyStream>, Option<u8>)>> {
+        let res = ready!(self.poll_race(cx));
+        // Either half may be waiting on the other to decide.
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+        if let Some(waker) = self.write_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(res)
+    }
+
+    fn poll_race(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<io::Result<(Box<dyn ProxyStream>, Option<u8>)>> {
+        while let Poll::Ready(Some(res)) = self.connects.poll_next_unpin(cx) {
+            match res {
+                Ok(stream) => match &self.payload {
+                    Some(payload) => {
+                        self.attempts
+                            .push(attempt(stream, payload.clone(), self.wait_response));
+                    }
+                    None => self.idle.push(stream),
+                },
+                Err(e) => self.last_err = Some(e),
+            }
+        }
+        while let Poll::Ready(Some(res)) = self.attempts.poll_next_unpin(cx) {
+            match res {
+                Ok(winner) => return Poll::Ready(Ok(winner)),
+                Err(e) => self.last_err = Some(e),
+            }
+        }
+        if self.payload.is_some() && self.connects.is_empty() && self.attempts.is_empty() {
+            return Poll::Ready(Err(all_failed(self.last_err.take())));
+        }
+        Poll::Pending
+    }
+}
+
+/// A stream racing the actors of a `tryall` past the connect.
+///
+/// The first payload written is sent on every connected stream, including
+/// the ones connecting later, and the first stream to take it wins. When
+/// `wait_response` is set, the winner must also return a byte of response,
+/// so an actor accepting connections but black-holing data can't win. The
+/// losers are dropped. Meant for protocols where the client speaks first, a
+/// stream shut down before any write settles for the first connected actor.
+pub struct RaceStream {
+    // Behind a lock only to make the stream `Sync`, it's never contended.
+    race: Option<Mutex<Race>>,
+    winner: Option<Box<dyn ProxyStream>>,
+    // The byte of response read by the winner, not returned yet.
+    response: Option<u8>,
+    // Length of the payload, until its write is acknowledged.
+    unacked: Option<usize>,
+}
+
+impl RaceStream {
+    /// Waits for the first of `connects` to succeed, the others keep
+    /// connecting as the stream is polled.
+    pub async fn connect(connects: Vec<Connect>, wait_response: bool) -> io::Result<Self> {
+        let mut race = Race {
+            connects: connects.into_iter().collect(),
+            idle: Vec::new(),
+            attempts: FuturesUnordered::new(),
+            payload: None,
+            wait_response,
+            last_err: None,
+            read_waker: None,
+            write_waker: None,
+        };
+        while let Some(res) = race.connects.next().await {
+            match res {
+                Ok(stream) => {
+                    race.idle.push(stream);
+                    return Ok(RaceStream {
+                        race: Some(Mutex::new(race)),
+                        winner: None,
+                        response: None,
+                        unacked: None,
+                    });
+                }
+                Err(e) => race.last_err = Some(e),
+            }
+        }
+        Err(all_failed(race.last_err))
+    }
+
+    fn race(&mut self) -> Option<&mut Race> {
+        self.race.as_mut().map(|r| r.get_mut().unwrap())
+    }
+
+    // Waits for the race to be decided, `reading` tells which half is
+    // waiting.
+    fn poll_winner(&mut self, cx: &mut Context, reading: bool) -> Poll<io::Result<()>> {
+        let race = match self.race() {
+            Some(race) => race,
+            None => return Poll::Ready(Ok(())),
+        };
+        let (winner, response) = match race.poll_winner(cx) {
+            Poll::Ready(res) => res?,
+            Poll::Pending => {
+                if reading {
+                    race.read_waker = Some(cx.waker().clone());
+                } else {
+                    race.write_waker = Some(cx.waker().clone());
+                }
+                return Poll::Pending;
+            }
+        };
+        self.race = None;
+        self.winner = Some(winner);
+        self.response = response;
+        Poll::Ready(Ok(()))
+    }
+
+    fn winner(&mut self) -> io::Result<Pin<&mut Box<dyn ProxyStream>>> {
+        match self.winner.as_mut() {
+            Some(winner) => Ok(Pin::new(winner)),
+            None => Err(all_failed(None)),
+        }
+    }
+}
+
+impl ProxyStream for RaceStream {
+    fn negotiated_protocol(&self) -> Option<Vec<u8>> {
+        self.winner.as_ref().and_then(|w| w.negotiated_protocol())
+    }
+}
+
+impl AsyncRead for RaceStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.poll_winner(cx, true))?;
+        if !buf.is_empty() {
+            if let Some(b) = self.response.take() {
+                buf[0] = b;
+                return Poll::Ready(Ok(1));
+            }
+        }
+        self.winner()?.poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for RaceStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.unacked.is_none() && self.winner.is_none() {
+            if buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            if let Some(race) = self.race() {
+                race.start(Bytes::copy_from_slice(buf));
+            }
+            self.unacked = Some(buf.len());
+        }
+        if self.unacked.is_some() {
+            ready!(self.poll_winner(cx, false))?;
+            // The payload has been written by the winner.
+            if let Some(n) = self.unacked.take() {
+                return Poll::Ready(Ok(n));
+            }
+        }
+        self.winner()?.poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        match self.winner.as_mut() {
+            Some(winner) => Pin::new(winner).poll_flush(cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        if self.winner.is_none() {
+            if let Some(race) = self.race() {
+                if race.payload.is_none() && !race.idle.is_empty() {
+                    let winner = race.idle.remove(0);
+                    if let Some(waker) = race.read_waker.take() {
+                        waker.wake();
+                    }
+                    self.race = None;
+                    self.winner = Some(winner);
+                }
+            }
+        }
+        match self.winner.as_mut() {
+            Some(winner) => Pin::new(winner).poll_shutdown(cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
@@ -2,16 +2,25 @@ use std::net::SocketAddr;
 use std::{io, sync::Arc};
 
 use async_trait::async_trait;
-use futures::future::select_ok;
+use futures::future::{select_ok, FutureExt};
+use log::*;
 
+use super::{
+    stream::{Connect, RaceStream},
+    RaceUntil,
+};
 use crate::{
-    proxy::{ProxyHandler, ProxyStream, ProxyTcpHandler},
+    common::health::HealthMap,
+    proxy::{deadline, ProxyError, ProxyHandler, ProxyStream, ProxyTcpHandler},
     session::Session,
 };
 
 pub struct Handler {
     pub actors: Vec<Arc<dyn ProxyHandler>>,
     pub delay_base: u32,
+    pub until: RaceUntil,
+    /// Actors failing there are left out of the race.
+    pub health: Option<Arc<HealthMap>>,
 }
 
 #[async_trait]
@@ -24,13 +33,59 @@ impl ProxyTcpHandler for Handler {
         None
     }
 
+    fn is_warm(&self, sess: &Session) -> bool {
+        self.actors.iter().any(|a| a.is_warm(sess))
+    }
+
     async fn handle<'a>(
         &'a self,
         sess: &'a Session,
         _stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>> {
+        // A warm actor doesn't need a handshake, there's nothing to gain from
+        // racing it.
+        if let Some(a) = self.actors.iter().find(|a| a.is_warm(sess)) {
+            match a.handle(sess, None).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => debug!("warm actor [{}] failed: {}", a.tag(), e),
+            }
+        }
+
+        let actors: Vec<&Arc<dyn ProxyHandler>> = match &self.health {
+            Some(health) => self
+                .actors
+                .iter()
+                .filter(|a| health.is_healthy(a.tag()))
+                .collect(),
+            None => self.actors.iter().collect(),
+        };
+        if actors.is_empty() {
+            debug!("all actors are failing health checks");
+            return Err(ProxyError::NoOutbound.into());
+        }
+
+        if self.until != RaceUntil::Connect {
+            let mut connects: Vec<Connect> = Vec::new();
+            for (i, a) in actors.iter().enumerate() {
+                let a = (*a).clone();
+                let sess = sess.clone();
+                let delay = (self.delay_base * i as u32) as u64;
+                let t = async move {
+                    if delay > 0 {
+                        tokio::time::delay_for(std::time::Duration::from_millis(delay)).await;
+                    }
+                    // The deadline may have passed while waiting.
+                    deadline::check()?;
+                    a.handle(&sess, None).await
+                };
+                connects.push(t.boxed());
+            }
+            let stream = RaceStream::connect(connects, self.until == RaceUntil::Response).await?;
+            return Ok(Box::new(stream));
+        }
+
         let mut tasks = Vec::new();
-        for (i, a) in self.actors.iter().enumerate() {
+        for (i, a) in actors.iter().enumerate() {
             let t = async move {
                 if self.delay_base > 0 {
                     tokio::time::delay_for(std::time::Duration::from_millis(
@@ -38,6 +93,7 @@ impl ProxyTcpHandler for Handler {
                     ))
                     .await;
                 }
+                deadline::check()?;
                 a.handle(sess, None).await
             };
             tasks.push(Box::pin(t));
@@ -51,3 +107,271 @@ impl ProxyTcpHandler for Handler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::time::{delay_for, timeout, Instant};
+
+    use super::*;
+    use crate::proxy::{
+        stream::SimpleStream, Handler as ProxyHandlerImpl, ProxyDatagram, ProxyHandlerType,
+        ProxyUdpHandler, UdpTransportType,
+    };
+    use crate::session::SocksAddr;
+
+    // Succeeds after a simulated handshake, unless it's warm.
+    struct Actor {
+        warm: bool,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for Actor {
+        fn name(&self) -> &str {
+            "actor"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn is_warm(&self, _sess: &Session) -> bool {
+            self.warm
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if !self.warm {
+                delay_for(Duration::from_millis(200)).await;
+            }
+            Ok(Box::new(SimpleStream(std::io::Cursor::new(Vec::new()))))
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Actor {
+        fn name(&self) -> &str {
+            "actor"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Err(io::ErrorKind::Other.into())
+        }
+    }
+
+    fn actor(tag: &str, warm: bool) -> (Arc<dyn ProxyHandler>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = ProxyHandlerImpl::new(
+            tag.to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(Actor {
+                warm,
+                calls: calls.clone(),
+            }),
+            Box::new(Actor {
+                warm,
+                calls: calls.clone(),
+            }),
+        );
+        (handler, calls)
+    }
+
+    // Connects to `addr` after `delay`.
+    #[derive(Clone)]
+    struct Dialer {
+        addr: SocketAddr,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl ProxyTcpHandler for Dialer {
+        fn name(&self) -> &str {
+            "dialer"
+        }
+
+        fn tcp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        async fn handle<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyStream>> {
+            delay_for(self.delay).await;
+            let stream = TcpStream::connect(self.addr).await?;
+            Ok(Box::new(SimpleStream(stream)))
+        }
+    }
+
+    #[async_trait]
+    impl ProxyUdpHandler for Dialer {
+        fn name(&self) -> &str {
+            "dialer"
+        }
+
+        fn udp_connect_addr(&self) -> Option<(String, u16, SocketAddr)> {
+            None
+        }
+
+        fn udp_transport_type(&self) -> UdpTransportType {
+            UdpTransportType::Unknown
+        }
+
+        async fn connect<'a>(
+            &'a self,
+            _sess: &'a Session,
+            _datagram: Option<Box<dyn ProxyDatagram>>,
+            _stream: Option<Box<dyn ProxyStream>>,
+        ) -> io::Result<Box<dyn ProxyDatagram>> {
+            Err(io::ErrorKind::Other.into())
+        }
+    }
+
+    fn dialer(tag: &str, addr: SocketAddr, delay: Duration) -> Arc<dyn ProxyHandler> {
+        let dialer = Dialer { addr, delay };
+        ProxyHandlerImpl::new(
+            tag.to_string(),
+            colored::Color::White,
+            ProxyHandlerType::Endpoint,
+            Box::new(dialer.clone()),
+            Box::new(dialer),
+        )
+    }
+
+    // Starts a server accepting connections, echoing the data if `echo`,
+    // discarding it otherwise.
+    async fn server(echo: bool) -> SocketAddr {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    loop {
+                        let n = match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => n,
+                        };
+                        if echo && stream.write_all(&buf[..n]).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_tryall_until_response() {
+        // The black hole connects first, it would win a race on connect.
+        let handler = Handler {
+            actors: vec![
+                dialer("black-hole", server(false).await, Duration::from_millis(0)),
+                dialer("echo", server(true).await, Duration::from_millis(200)),
+            ],
+            delay_base: 0,
+            until: RaceUntil::Response,
+            health: None,
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let mut stream = handler.handle(&sess, None).await.unwrap();
+        timeout(Duration::from_secs(5), stream.write_all(b"ping"))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut buf = [0u8; 4];
+        timeout(Duration::from_secs(5), stream.read_exact(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_tryall_prefers_warm_actor() {
+        let (cold, cold_calls) = actor("cold", false);
+        let (warm, warm_calls) = actor("warm", true);
+        // The warm actor would normally start last.
+        let handler = Handler {
+            actors: vec![cold, warm],
+            delay_base: 500,
+            until: RaceUntil::Connect,
+            health: None,
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        assert!(handler.is_warm(&sess));
+        let start = Instant::now();
+        assert!(handler.handle(&sess, None).await.is_ok());
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(warm_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cold_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tryall_skips_failing_actors() {
+        let (failing, failing_calls) = actor("failing", false);
+        let (healthy, healthy_calls) = actor("healthy", false);
+        let health = Arc::new(HealthMap::new());
+        health.set_healthy("failing", false);
+        // The healthy actor would normally start after 500ms.
+        let handler = Handler {
+            actors: vec![failing, healthy],
+            delay_base: 500,
+            until: RaceUntil::Connect,
+            health: Some(health.clone()),
+        };
+        let sess = Session {
+            source: "127.0.0.1:10000".parse().unwrap(),
+            destination: SocksAddr::Domain("example.com".to_string(), 443),
+        };
+        let start = Instant::now();
+        assert!(handler.handle(&sess, None).await.is_ok());
+        assert!(start.elapsed() < Duration::from_millis(400));
+        assert_eq!(failing_calls.load(Ordering::SeqCst), 0);
+        assert_eq!(healthy_calls.load(Ordering::SeqCst), 1);
+
+        // Nothing left to race.
+        health.set_healthy("healthy", false);
+        let start = Instant::now();
+        let err = handler.handle(&sess, None).await.err().unwrap();
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert!(matches!(
+            ProxyError::downcast_ref(&err),
+            Some(ProxyError::NoOutbound)
+        ));
+        assert_eq!(failing_calls.load(Ordering::SeqCst), 0);
+        assert_eq!(healthy_calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -4,18 +4,16 @@ use anyhow::Result;
 use futures::{sink::SinkExt, stream::StreamExt};
 use log::*;
 use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
-use tokio::sync::Mutex as TokioMutex;
 use tun::{self, Device, TunPacket};
 
 use crate::{
     app::dispatcher::Dispatcher,
     app::nat_manager::NatManager,
-    common::fake_dns::FakeDns,
     config::{Inbound, TUNInboundSettings},
     Runner,
 };
 
-use super::netstack::NetStack;
+use super::netstack::{FlowLimit, NetStack};
 
 const MTU: usize = 1500;
 
@@ -57,16 +55,19 @@ pub fn new(
     // });
 
     let fake_dns_exclude = settings.fake_dns_exclude;
+    let flow_limit = FlowLimit::new(settings.max_flows as usize);
 
     Ok(Box::pin(async move {
         let tun = tun::create_as_async(&cfg).unwrap();
 
-        let fakedns = Arc::new(TokioMutex::new(FakeDns::new()));
+        // Shared with the dns outbounds, the domains they fake are mapped back
+        // as well.
+        let fakedns = dispatcher.fake_dns();
         for domain in fake_dns_exclude.into_iter() {
             fakedns.lock().await.exclude(domain);
         }
 
-        let stack = NetStack::new(dispatcher, nat_manager, fakedns);
+        let stack = NetStack::new(dispatcher, nat_manager, fakedns, flow_limit);
 
         let mtu = tun.get_ref().mtu().unwrap_or(MTU as i32);
         let framed = tun.into_framed();
@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::app::metrics::{self, Counter, Gauge};
+
+pub const NETSTACK_FLOWS_IN_FLIGHT: &str = "netstack_flows_in_flight";
+pub const NETSTACK_FLOWS_REJECTED: &str = "netstack_flows_rejected_total";
+
+/// Caps the TCP flows the netstack dispatches concurrently, so that a flood
+/// of connections into the TUN can't spawn unbounded dispatch tasks. Flows
+/// beyond the cap are turned away rather than queued.
+#[derive(Clone)]
+pub struct FlowLimit {
+    max: usize,
+    in_flight: Arc<AtomicUsize>,
+    gauge: Arc<Gauge>,
+    rejected: Arc<Counter>,
+}
+
+impl FlowLimit {
+    /// Admits up to `max` flows at a time, any number if 0.
+    pub fn new(max: usize) -> Self {
+        let registry = metrics::registry();
+        FlowLimit {
+            max,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            gauge: registry.gauge(NETSTACK_FLOWS_IN_FLIGHT),
+            rejected: registry.counter(NETSTACK_FLOWS_REJECTED),
+        }
+    }
+
+    /// Returns the permit of a new flow, held until the flow is done, or
+    /// `None` if the cap is reached.
+    pub fn try_admit(&self) -> Option<FlowPermit> {
+        let mut n = self.in_flight.load(Ordering::Relaxed);
+        loop {
+            if self.max > 0 && n >= self.max {
+                self.rejected.inc();
+                return None;
+            }
+            match self.in_flight.compare_exchange_weak(
+                n,
+                n + 1,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => n = current,
+            }
+        }
+        self.gauge.inc();
+        Some(FlowPermit(self.clone()))
+    }
+
+    /// Returns the number of flows admitted and not done yet.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts a flow as in flight until dropped.
+pub struct FlowPermit(FlowLimit);
+
+impl Drop for FlowPermit {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+        self.0.gauge.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flows_past_cap() {
+        let limit = FlowLimit::new(4);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut admitted = 0;
+        let mut rejected = 0;
+        for _ in 0..16 {
+            match limit.try_admit() {
+                Some(permit) => {
+                    admitted += 1;
+                    let tx = tx.clone();
+                    // A flow taking a while.
+                    tokio::spawn(async move {
+                        tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+                        drop(permit);
+                        let _ = tx.send(());
+                    });
+                }
+                None => rejected += 1,
+            }
+        }
+        assert_eq!(admitted, 4);
+        assert_eq!(rejected, 12);
+        assert_eq!(limit.in_flight(), 4);
+        assert!(metrics::metrics().gauge(NETSTACK_FLOWS_IN_FLIGHT) >= 4);
+
+        // Room again once flows are done.
+        for _ in 0..4 {
+            rx.recv().await.unwrap();
+        }
+        assert_eq!(limit.in_flight(), 0);
+        assert!(limit.try_admit().is_some());
+
+        // No cap.
+        let limit = FlowLimit::new(0);
+        let permits: Vec<FlowPermit> = (0..16).filter_map(|_| limit.try_admit()).collect();
+        assert_eq!(permits.len(), 16);
+    }
+}
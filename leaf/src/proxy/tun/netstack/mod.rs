@@ -1,3 +1,4 @@
+mod flow_limit;
 mod lwip;
 mod output;
 mod stack;
@@ -7,6 +8,8 @@ mod tcp_listener_impl;
 mod tcp_stream;
 mod tcp_stream_impl;
 mod udp;
+mod uplink;
 mod util;
 
+pub use flow_limit::FlowLimit;
 pub use stack::NetStack;
@@ -8,6 +8,7 @@ use crate::app::dispatcher::Dispatcher;
 use crate::app::nat_manager::NatManager;
 use crate::common::fake_dns::FakeDns;
 
+use super::flow_limit::FlowLimit;
 use super::stack_impl::NetStackImpl;
 
 pub struct NetStack(Box<NetStackImpl>);
@@ -17,8 +18,14 @@ impl NetStack {
         dispatcher: Arc<Dispatcher>,
         nat_manager: Arc<NatManager>,
         fakedns: Arc<TokioMutex<FakeDns>>,
+        flow_limit: FlowLimit,
     ) -> Self {
-        NetStack(NetStackImpl::new(dispatcher, nat_manager, fakedns))
+        NetStack(NetStackImpl::new(
+            dispatcher,
+            nat_manager,
+            fakedns,
+            flow_limit,
+        ))
     }
 }
 
@@ -23,19 +23,17 @@ use tokio::{
 };
 
 use crate::{
-    app::dispatcher::Dispatcher,
-    app::nat_manager::NatManager,
-    app::nat_manager::UdpPacket,
-    common::fake_dns::FakeDns,
-    common::mutex::AtomicMutex,
-    session::{Session, SocksAddr},
+    app::dispatcher::Dispatcher, app::nat_manager::NatManager, app::nat_manager::UdpPacket,
+    common::fake_dns::FakeDns, common::mutex::AtomicMutex, session::SocksAddr,
 };
 
+use super::flow_limit::FlowLimit;
 use super::lwip::*;
 use super::output::{output_ip4, OUTPUT_CB_PTR};
 use super::tcp_listener::TcpListener;
 use super::tcp_stream::TcpStream;
 use super::udp::{send_udp, UdpListener};
+use super::uplink::{send_packet, tcp_session};
 
 static LWIP_INIT: Once = Once::new();
 
@@ -47,6 +45,7 @@ pub struct NetStackImpl {
     dispatcher: Arc<Dispatcher>,
     nat_manager: Arc<NatManager>,
     fakedns: Arc<TokioMutex<FakeDns>>,
+    flow_limit: FlowLimit,
 }
 
 unsafe impl Sync for NetStackImpl {}
@@ -57,6 +56,7 @@ impl NetStackImpl {
         dispatcher: Arc<Dispatcher>,
         nat_manager: Arc<NatManager>,
         fakedns: Arc<TokioMutex<FakeDns>>,
+        flow_limit: FlowLimit,
     ) -> Box<Self> {
         LWIP_INIT.call_once(|| unsafe { lwip_init() });
 
@@ -76,6 +76,7 @@ impl NetStackImpl {
             dispatcher,
             nat_manager,
             fakedns,
+            flow_limit,
         });
 
         unsafe {
@@ -96,35 +97,30 @@ impl NetStackImpl {
         let lwip_locktcp = stack.lwip_lock.clone();
         let dispatcher = stack.dispatcher.clone();
         let fakedns = stack.fakedns.clone();
+        let flow_limit = stack.flow_limit.clone();
         tokio::spawn(async move {
             let mut listener = TcpListener::new(lwip_locktcp);
 
             while let Some(stream) = listener.next().await {
+                let permit = match flow_limit.try_admit() {
+                    Some(permit) => permit,
+                    None => {
+                        debug!(
+                            "too many tcp flows in flight, reset {} -> {}",
+                            stream.local_addr(),
+                            stream.remote_addr()
+                        );
+                        stream.abort();
+                        continue;
+                    }
+                };
                 let dispatcher = dispatcher.clone();
                 let fakedns = fakedns.clone();
 
                 tokio::spawn(async move {
-                    let mut sess = if fakedns.lock().await.is_fake_ip(&stream.remote_addr().ip()) {
-                        match fakedns
-                            .lock()
-                            .await
-                            .query_domain(&stream.remote_addr().ip())
-                        {
-                            Some(domain) => Session {
-                                source: stream.local_addr().to_owned(),
-                                destination: SocksAddr::Domain(domain, stream.remote_addr().port()),
-                            },
-                            None => Session {
-                                source: stream.local_addr().to_owned(),
-                                destination: SocksAddr::Ip(*stream.remote_addr()),
-                            },
-                        }
-                    } else {
-                        Session {
-                            source: stream.local_addr().to_owned(),
-                            destination: SocksAddr::Ip(*stream.remote_addr()),
-                        }
-                    };
+                    let _permit = permit;
+                    let mut sess =
+                        tcp_session(&fakedns, *stream.local_addr(), *stream.remote_addr()).await;
 
                     // dispatch err logging was handled in dispatcher
                     let _ = dispatcher
@@ -215,49 +211,18 @@ impl NetStackImpl {
                     }
                 };
 
-                if dst_addr.port() == 53 {
-                    match fakedns.lock().await.generate_fake_response(&pkt.data) {
-                        Ok(resp) => {
-                            send_udp(lwip_lock.clone(), &dst_addr, &src_addr, pcb, resp.as_ref());
-                            continue;
-                        }
-                        Err(err) => {
-                            trace!("generate fake ip failed: {}", err);
-                        }
-                    }
-                }
-
-                if !nat_manager.contains_key(&src_addr).await {
-                    let sess = Session {
-                        source: src_addr,
-                        destination: SocksAddr::Ip(dst_addr),
-                    };
-
-                    if nat_manager
-                        .add_session(&sess, src_addr, client_ch_tx.clone())
-                        .await
-                        .is_err()
-                    {
-                        // dispatch err logging was handled in dispatcher
-                        continue; // in case the pkt was sent to drop, err is returned immediately
-                    }
-
-                    debug!(
-                        "udp session {}:{} -> {}:{} ({})",
-                        &src_addr.ip(),
-                        &src_addr.port(),
-                        &dst_addr.ip(),
-                        &dst_addr.port(),
-                        nat_manager.size().await,
-                    );
+                if let Some(resp) = send_packet(
+                    &fakedns,
+                    &nat_manager,
+                    &client_ch_tx,
+                    src_addr,
+                    dst_addr,
+                    pkt.data,
+                )
+                .await
+                {
+                    send_udp(lwip_lock.clone(), &dst_addr, &src_addr, pcb, resp.as_ref());
                 }
-
-                let pkt = UdpPacket {
-                    data: pkt.data,
-                    src_addr: Some(SocksAddr::Ip(src_addr)),
-                    dst_addr: Some(SocksAddr::Ip(dst_addr)),
-                };
-                nat_manager.send(&src_addr, pkt).await;
             }
         });
 
@@ -170,6 +170,23 @@ impl TcpStreamImpl {
     pub fn remote_addr(&self) -> &SocketAddr {
         &self.dest_addr
     }
+
+    /// Resets the connection instead of closing it.
+    pub fn abort(mut self: Box<Self>) {
+        debug!("tcp abort {}", self.local_addr());
+        unsafe {
+            let _g = self.lwip_lock.lock();
+            if !self.errored {
+                tcp_arg(self.pcb, std::ptr::null_mut());
+                tcp_recv(self.pcb, None);
+                tcp_sent(self.pcb, None);
+                tcp_err(self.pcb, None);
+                tcp_abort(self.pcb);
+                // The pcb is freed, there's nothing left to close.
+                self.errored = true;
+            }
+        }
+    }
 }
 
 impl Drop for TcpStreamImpl {
@@ -0,0 +1,396 @@
+use std::net::SocketAddr;
+
+use log::*;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex as TokioMutex;
+
+use crate::{
+    app::nat_manager::{NatManager, UdpPacket},
+    common::fake_dns::{self, FakeDns},
+    session::{Session, SocksAddr},
+};
+
+/// The session of a TCP flow from the client, towards the domain `remote`
+/// was faked for if it's a fake IP.
+pub async fn tcp_session(
+    fakedns: &TokioMutex<FakeDns>,
+    local: SocketAddr,
+    remote: SocketAddr,
+) -> Session {
+    let mut fakedns = fakedns.lock().await;
+    let destination = if fakedns.is_fake_ip(&remote.ip()) {
+        match fakedns.query_domain(&remote.ip()) {
+            Some(domain) => SocksAddr::Domain(domain, remote.port()),
+            None => SocksAddr::Ip(remote),
+        }
+    } else {
+        SocksAddr::Ip(remote)
+    };
+    Session {
+        source: local,
+        destination,
+    }
+}
+
+/// Sends a UDP packet from the client to its NAT session, added if new.
+/// Returns the response to send back to the client right away, if any: the
+/// fake DNS answer of a query, or a SERVFAIL if the query can neither be
+/// answered by the fake DNS nor dispatched for real resolution, so that the
+/// client fails fast and retries.
+pub async fn send_packet(
+    fakedns: &TokioMutex<FakeDns>,
+    nat_manager: &NatManager,
+    client_ch_tx: &Sender<UdpPacket>,
+    src_addr: SocketAddr,
+    dst_addr: SocketAddr,
+    data: Vec<u8>,
+) -> Option<Vec<u8>> {
+    let dns = dst_addr.port() == 53;
+    if dns {
+        match fakedns.lock().await.generate_fake_response(&data) {
+            Ok(resp) => return Some(resp),
+            Err(err) => {
+                trace!("generate fake ip failed: {}", err);
+            }
+        }
+    }
+
+    let key = nat_manager.session_key(src_addr, &SocksAddr::Ip(dst_addr));
+    if !nat_manager.contains_key(&key).await {
+        let sess = Session {
+            source: src_addr,
+            destination: SocksAddr::Ip(dst_addr),
+        };
+
+        if nat_manager
+            .add_session(&sess, client_ch_tx.clone())
+            .await
+            .is_err()
+        {
+            // dispatch err logging was handled in dispatcher
+            if dns {
+                debug!(
+                    "dns query {} -> {} failed, answering servfail",
+                    &src_addr, &dst_addr
+                );
+                return fake_dns::generate_servfail_response(&data).ok();
+            }
+            return None; // in case the pkt was sent to drop, err is returned immediately
+        }
+
+        debug!(
+            "udp session {}:{} -> {}:{} ({})",
+            &src_addr.ip(),
+            &src_addr.port(),
+            &dst_addr.ip(),
+            &dst_addr.port(),
+            nat_manager.size().await,
+        );
+    }
+
+    let pkt = UdpPacket {
+        data,
+        src_addr: Some(SocksAddr::Ip(src_addr)),
+        dst_addr: Some(SocksAddr::Ip(dst_addr)),
+    };
+    nat_manager.send(&key, pkt).await;
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
+    use trust_dns_proto::op::{
+        header::MessageType, op_code::OpCode, query::Query, response_code::ResponseCode, Message,
+    };
+    use trust_dns_proto::rr::{record_type::RecordType, Name};
+
+    use super::*;
+    use crate::app::nat_manager::{NatType, SessionTimeouts};
+    use crate::app::{dispatcher::Dispatcher, handler_manager::HandlerManager, router::Router};
+    use crate::common::shutdown::ShutdownToken;
+    use crate::config;
+
+    fn query(id: u16) -> Vec<u8> {
+        let mut msg = Message::new();
+        msg.set_id(id)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true);
+        msg.add_query(Query::query(
+            Name::from_str("example.com.").unwrap(),
+            RecordType::A,
+        ));
+        msg.to_vec().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_servfail_when_dns_fails() {
+        // Every domain excluded from the fake DNS, and no outbound to
+        // dispatch the query to.
+        let mut fakedns = FakeDns::new();
+        fakedns.exclude("*".to_string());
+        let fakedns = TokioMutex::new(fakedns);
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::new(),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let dispatcher = Dispatcher::new(
+            handler_manager,
+            Router::new(&protobuf::RepeatedField::new()),
+        );
+        let nat_manager = NatManager::new(
+            Arc::new(dispatcher),
+            ShutdownToken::never(),
+            NatType::Symmetric,
+        );
+        let (client_ch_tx, _client_ch_rx) = mpsc::channel(10);
+        let src_addr: SocketAddr = "10.0.0.2:10000".parse().unwrap();
+
+        let resp = timeout(
+            Duration::from_secs(1),
+            send_packet(
+                &fakedns,
+                &nat_manager,
+                &client_ch_tx,
+                src_addr,
+                "10.0.0.1:53".parse().unwrap(),
+                query(42),
+            ),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let resp = Message::from_vec(&resp).unwrap();
+        assert_eq!(resp.id(), 42);
+        assert_eq!(resp.response_code(), ResponseCode::ServFail);
+
+        // Other packets are dropped silently.
+        let resp = send_packet(
+            &fakedns,
+            &nat_manager,
+            &client_ch_tx,
+            src_addr,
+            "10.0.0.1:443".parse().unwrap(),
+            b"ping".to_vec(),
+        )
+        .await;
+        assert!(resp.is_none());
+    }
+
+    #[cfg(feature = "outbound-direct")]
+    #[tokio::test]
+    async fn test_servfail_when_upstream_silent() {
+        let mut fakedns = FakeDns::new();
+        fakedns.exclude("*".to_string());
+        let fakedns = TokioMutex::new(fakedns);
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "direct".to_string();
+        outbound.protocol = "direct".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let dispatcher = Dispatcher::new(
+            handler_manager,
+            Router::new(&protobuf::RepeatedField::new()),
+        );
+        let mut timeouts = SessionTimeouts::default();
+        timeouts.dns_query = Duration::from_millis(500);
+        let nat_manager = NatManager::with_timeouts(
+            Arc::new(dispatcher),
+            ShutdownToken::never(),
+            NatType::Symmetric,
+            timeouts,
+        );
+        let (client_ch_tx, mut client_ch_rx) = mpsc::channel(10);
+        let src_addr: SocketAddr = "10.0.0.2:10000".parse().unwrap();
+        // Nothing listens there, the query is sent but never answered.
+        let dst_addr: SocketAddr = "127.1.2.3:53".parse().unwrap();
+
+        let resp = send_packet(
+            &fakedns,
+            &nat_manager,
+            &client_ch_tx,
+            src_addr,
+            dst_addr,
+            query(42),
+        )
+        .await;
+        assert!(resp.is_none());
+
+        let pkt = timeout(Duration::from_secs(2), client_ch_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pkt.src_addr, Some(SocksAddr::Ip(dst_addr)));
+        assert_eq!(pkt.dst_addr, Some(SocksAddr::Ip(src_addr)));
+        let resp = Message::from_vec(&pkt.data).unwrap();
+        assert_eq!(resp.id(), 42);
+        assert_eq!(resp.response_code(), ResponseCode::ServFail);
+    }
+
+    #[cfg(all(feature = "outbound-direct", feature = "outbound-dns"))]
+    #[tokio::test]
+    async fn test_fake_ip_flow_resolves_once() {
+        use std::net::Ipv4Addr;
+        use std::sync::atomic::Ordering;
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::{TcpListener, TcpStream};
+
+        use crate::common::dns_client::DnsClient;
+        use crate::proxy::dns::test_utils::{self, counting_upstream};
+
+        let mut server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = server.local_addr().unwrap().port();
+        let (upstream, queries) = counting_upstream(Ipv4Addr::new(127, 0, 0, 1)).await;
+        let dns_client = Arc::new(DnsClient::new(
+            vec![upstream],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "direct".to_string();
+        outbound.protocol = "direct".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::with_dns_client(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            dns_client,
+            ShutdownToken::never(),
+            None,
+        );
+        let dispatcher = Arc::new(Dispatcher::new(
+            handler_manager,
+            Router::new(&protobuf::RepeatedField::new()),
+        ));
+        let fakedns = dispatcher.fake_dns();
+        let nat_manager = NatManager::new(
+            dispatcher.clone(),
+            ShutdownToken::never(),
+            NatType::Symmetric,
+        );
+        let (client_ch_tx, _client_ch_rx) = mpsc::channel(10);
+        let src_addr: SocketAddr = "10.0.0.2:10000".parse().unwrap();
+
+        // The fake answer doesn't resolve the domain.
+        let resp = send_packet(
+            &fakedns,
+            &nat_manager,
+            &client_ch_tx,
+            src_addr,
+            "10.0.0.1:53".parse().unwrap(),
+            test_utils::query("example.com.", 1, RecordType::A),
+        )
+        .await
+        .unwrap();
+        let (_, _, ips) = test_utils::answers(&resp);
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        assert_eq!(queries.load(Ordering::SeqCst), 0);
+
+        let mut sess = tcp_session(&fakedns, src_addr, SocketAddr::new(ips[0], port)).await;
+        assert_eq!(
+            sess.destination,
+            SocksAddr::Domain("example.com".to_string(), port)
+        );
+
+        // The dial of the flow does, once.
+        let mut inbound = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(inbound.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (lhs, _) = inbound.accept().await.unwrap();
+        tokio::spawn(async move {
+            let _ = dispatcher.dispatch_tcp(&mut sess, lhs).await;
+        });
+        client.write_all(b"ping").await.unwrap();
+        let (mut stream, _) = timeout(Duration::from_secs(2), server.accept())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "outbound-dns")]
+    #[tokio::test]
+    async fn test_dns_outbound_fake_ip_mapped_by_tun() {
+        use protobuf::Message as _;
+
+        use crate::proxy::dns::test_utils;
+        use crate::proxy::{
+            ProxyDatagram, ProxyDatagramRecvHalf, ProxyDatagramSendHalf, ProxyUdpHandler,
+        };
+
+        let mut settings = config::DNSOutboundSettings::new();
+        settings.fake_ip = true;
+        let mut outbound = config::Outbound::new();
+        outbound.tag = "dns".to_string();
+        outbound.protocol = "dns".to_string();
+        outbound.bind = "0.0.0.0".to_string();
+        outbound.settings = settings.write_to_bytes().unwrap();
+        let mut dns = config::DNS::new();
+        dns.servers = protobuf::RepeatedField::from_vec(vec!["127.0.0.1".to_string()]);
+        dns.bind = "0.0.0.0".to_string();
+        let handler_manager = HandlerManager::new(
+            &protobuf::RepeatedField::from_vec(vec![outbound]),
+            &dns,
+            ShutdownToken::never(),
+        );
+        let handler = handler_manager.get("dns").unwrap().clone();
+        let dispatcher = Dispatcher::new(
+            handler_manager,
+            Router::new(&protobuf::RepeatedField::new()),
+        );
+
+        // Resolved through the dns outbound.
+        let target: SocketAddr = "1.1.1.1:53".parse().unwrap();
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Ip(target),
+        };
+        let datagram = ProxyUdpHandler::connect(&*handler, &sess, None, None)
+            .await
+            .unwrap();
+        let (mut r, mut s) = datagram.split();
+        s.send_to(
+            &test_utils::query("example.com.", 1, RecordType::A),
+            &target,
+        )
+        .await
+        .unwrap();
+        let mut buf = [0u8; 512];
+        let (n, _) = timeout(Duration::from_secs(1), r.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        let (_, _, ips) = test_utils::answers(&buf[..n]);
+
+        // Mapped back by the tun.
+        let sess = tcp_session(
+            &dispatcher.fake_dns(),
+            "10.0.0.2:10000".parse().unwrap(),
+            SocketAddr::new(ips[0], 443),
+        )
+        .await;
+        assert_eq!(
+            sess.destination,
+            SocksAddr::Domain("example.com".to_string(), 443)
+        );
+    }
+}
@@ -3,13 +3,18 @@ use std::io::Read;
 use anyhow::{anyhow, Result};
 use byteorder::{BigEndian, ByteOrder};
 use digest::ExtendableOutputDirty;
+use lazy_static::lazy_static;
 use log::*;
 use md5::{Digest, Md5};
 use sha3::Shake128;
 
 use crate::common::crypto::{
     aead::{AeadCipher, AeadDecryptor, AeadEncryptor},
-    Cipher, NonceSequence, SizedCipher,
+    Cipher, CipherRegistry, NonceSequence, SizedCipher,
+};
+
+use super::protocol::{
+    Security, SECURITY_TYPE_AES128_GCM, SECURITY_TYPE_CHACHA20_POLY1305, SECURITY_TYPE_NONE,
 };
 
 pub fn generate_chacha20poly1305_key(key: &[u8]) -> Vec<u8> {
@@ -18,17 +23,73 @@ pub fn generate_chacha20poly1305_key(key: &[u8]) -> Vec<u8> {
     [key_1, key_2].concat()
 }
 
+fn copy_key(key: &[u8]) -> Vec<u8> {
+    key.to_vec()
+}
+
+/// A vmess security method.
+pub struct SecurityMethod {
+    /// Security type of the request header.
+    pub security: Security,
+    /// Name of the underlying AEAD algorithm, `None` for no encryption.
+    pub algorithm: Option<&'static str>,
+    /// Derives the key of the AEAD algorithm from the body key.
+    pub derive_key: fn(&[u8]) -> Vec<u8>,
+}
+
+lazy_static! {
+    static ref METHODS: CipherRegistry<SecurityMethod> = {
+        let mut r = CipherRegistry::new();
+        for name in &["chacha20-poly1305", "chacha20-ietf-poly1305"] {
+            r.register(
+                *name,
+                SecurityMethod {
+                    security: SECURITY_TYPE_CHACHA20_POLY1305,
+                    algorithm: Some("chacha20-poly1305"),
+                    derive_key: generate_chacha20poly1305_key,
+                },
+            );
+        }
+        r.register(
+            "aes-128-gcm",
+            SecurityMethod {
+                security: SECURITY_TYPE_AES128_GCM,
+                algorithm: Some("aes-128-gcm"),
+                derive_key: copy_key,
+            },
+        );
+        r.register(
+            "none",
+            SecurityMethod {
+                security: SECURITY_TYPE_NONE,
+                algorithm: None,
+                derive_key: copy_key,
+            },
+        );
+        r
+    };
+}
+
+/// Returns the security method named `name`.
+pub fn security_method(name: &str) -> Result<&'static SecurityMethod> {
+    METHODS.get(name)
+}
+
+// The AEAD cipher and key of an encrypting method.
+fn aead_cipher(name: &str, key: &[u8]) -> Result<(AeadCipher, Vec<u8>)> {
+    let method = security_method(name)?;
+    let algorithm = method
+        .algorithm
+        .ok_or_else(|| anyhow!("cipher {} doesn't encrypt", name))?;
+    Ok((AeadCipher::new(algorithm)?, (method.derive_key)(key)))
+}
+
 pub fn new_encryptor(
     cipher: &str,
     key: &[u8],
     iv: &[u8],
 ) -> Result<AeadEncryptor<VMessAEADSequence>> {
-    let aead_cipher = AeadCipher::new(cipher)?;
-    let key = match cipher.to_lowercase().as_str() {
-        "chacha20-poly1305" | "chacha20-ietf-poly1305" => generate_chacha20poly1305_key(key),
-        "aes-128-gcm" => key.to_vec(),
-        _ => return Err(anyhow!("unsupported cipher: {}", cipher)),
-    };
+    let (aead_cipher, key) = aead_cipher(cipher, key)?;
     let nonce = VMessAEADSequence::new(iv.to_vec(), aead_cipher.nonce_len());
     let enc = aead_cipher.encryptor(&key, nonce)?;
     Ok(enc)
@@ -39,12 +100,7 @@ pub fn new_decryptor(
     key: &[u8],
     iv: &[u8],
 ) -> Result<AeadDecryptor<VMessAEADSequence>> {
-    let aead_cipher = AeadCipher::new(cipher)?;
-    let key = match cipher.to_lowercase().as_str() {
-        "chacha20-poly1305" | "chacha20-ietf-poly1305" => generate_chacha20poly1305_key(key),
-        "aes-128-gcm" => key.to_vec(),
-        _ => return Err(anyhow!("unsupported cipher: {}", cipher)),
-    };
+    let (aead_cipher, key) = aead_cipher(cipher, key)?;
     let nonce = VMessAEADSequence::new(iv.to_vec(), aead_cipher.nonce_len());
     let dec = aead_cipher.decryptor(&key, nonce)?;
     Ok(dec)
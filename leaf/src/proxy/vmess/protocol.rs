@@ -19,10 +19,11 @@ type RequestCommand = u8;
 pub const REQUEST_COMMAND_TCP: RequestCommand = 0x01;
 pub const REQUEST_COMMAND_UDP: RequestCommand = 0x02;
 
-type Security = u8;
+pub type Security = u8;
 
 pub const SECURITY_TYPE_AES128_GCM: Security = 0x03;
 pub const SECURITY_TYPE_CHACHA20_POLY1305: Security = 0x04;
+pub const SECURITY_TYPE_NONE: Security = 0x05;
 
 type RequestOption = u8;
 
@@ -161,3 +162,74 @@ impl ClientSession {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_header_encode() {
+        let uuid = Uuid::parse_str("b831381d-6324-4d53-ad4f-8cda48b30811").unwrap();
+        let header = RequestHeader {
+            version: 0x1,
+            command: REQUEST_COMMAND_TCP,
+            option: REQUEST_OPTION_CHUNK_STREAM,
+            security: SECURITY_TYPE_AES128_GCM,
+            address: SocksAddr::Domain("example.com".to_string(), 443),
+            uuid,
+        };
+        let sess = ClientSession::new();
+        let mut buf = BytesMut::new();
+        header.encode(&mut buf, &sess).unwrap();
+
+        // find the timestamp the auth info was generated from
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let timestamp = (now - 60..now + 60)
+            .find(|ts| {
+                let mut mac = Hmac::<Md5>::new_varkey(uuid.as_bytes()).unwrap();
+                let mut tmp = [0u8; 8];
+                BigEndian::write_u64(&mut tmp, *ts);
+                mac.update(&tmp);
+                mac.finalize().into_bytes()[..] == buf[..16]
+            })
+            .expect("invalid auth info");
+
+        let mut tmp = [0u8; 8];
+        BigEndian::write_u64(&mut tmp, timestamp);
+        let mut hasher = Md5::new();
+        for _ in 0..4 {
+            hasher.update(&tmp);
+        }
+        let iv = hasher.finalize();
+        let mut hasher = Md5::new();
+        hasher.update(uuid.as_bytes());
+        hasher.update(b"c48619fe-8f02-49e0-b9e9-edf763e17e21");
+        let key = hasher.finalize();
+        let mut dec = Cfb::<Aes128>::new_var(&key, &iv).unwrap();
+        dec.decrypt(&mut buf[16..]);
+
+        let cmd = &buf[16..];
+        assert_eq!(cmd[0], 0x1);
+        assert_eq!(&cmd[1..17], &sess.request_body_iv[..]);
+        assert_eq!(&cmd[17..33], &sess.request_body_key[..]);
+        assert_eq!(cmd[33], sess.response_header);
+        assert_eq!(cmd[34], REQUEST_OPTION_CHUNK_STREAM);
+        assert_eq!(cmd[35] & 0x0f, SECURITY_TYPE_AES128_GCM);
+        let padding_len = (cmd[35] >> 4) as usize;
+        assert_eq!(cmd[36], 0);
+        assert_eq!(cmd[37], REQUEST_COMMAND_TCP);
+        assert_eq!(&cmd[38..40], &[0x01, 0xbb]); // port
+        assert_eq!(cmd[40], 0x02); // domain
+        assert_eq!(cmd[41], 11);
+        assert_eq!(&cmd[42..53], b"example.com");
+
+        let end = 53 + padding_len;
+        assert_eq!(cmd.len(), end + 4);
+        let mut hasher = Fnv1a::<u32>::default();
+        hasher.write(&cmd[..end]);
+        assert_eq!(BigEndian::read_u32(&cmd[end..]), hasher.finish());
+    }
+}
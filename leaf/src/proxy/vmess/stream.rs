@@ -34,9 +34,9 @@ enum WriteState {
 pub struct VMessAuthStream<T> {
     inner: T,
     sess: ClientSession,
-    enc: AeadEncryptor<VMessAEADSequence>,
+    enc: Option<AeadEncryptor<VMessAEADSequence>>,
     enc_size_parser: ShakeSizeParser,
-    dec: AeadDecryptor<VMessAEADSequence>,
+    dec: Option<AeadDecryptor<VMessAEADSequence>>,
     dec_size_parser: ShakeSizeParser,
     tag_len: usize,
     read_buf: BytesMut,
@@ -47,12 +47,15 @@ pub struct VMessAuthStream<T> {
 }
 
 impl<T> VMessAuthStream<T> {
+    /// Creates a stream with chunked data frames. Frames are sealed with the
+    /// given AEAD ciphers and padded, or sent as is if there are no ciphers,
+    /// which is the case for security type `none`.
     pub fn new(
         s: T,
         sess: ClientSession,
-        enc: AeadEncryptor<VMessAEADSequence>,
+        enc: Option<AeadEncryptor<VMessAEADSequence>>,
         enc_size_parser: ShakeSizeParser,
-        dec: AeadDecryptor<VMessAEADSequence>,
+        dec: Option<AeadDecryptor<VMessAEADSequence>>,
         dec_size_parser: ShakeSizeParser,
         tag_len: usize,
     ) -> Self {
@@ -140,7 +143,11 @@ impl<T: AsyncRead + Unpin> AsyncRead for VMessAuthStream<T> {
                     let me = &mut *self;
                     let size_bytes = me.dec_size_parser.size_bytes();
                     ready!(me.poll_read_exact(cx, size_bytes))?;
-                    let padding_size = me.dec_size_parser.next_padding_len() as usize;
+                    let padding_size = if me.dec.is_some() {
+                        me.dec_size_parser.next_padding_len() as usize
+                    } else {
+                        0
+                    };
                     let size = me.dec_size_parser.decode(&me.read_buf[..size_bytes]) as usize;
 
                     // ready to read payload
@@ -152,7 +159,9 @@ impl<T: AsyncRead + Unpin> AsyncRead for VMessAuthStream<T> {
                     ready!(me.poll_read_exact(cx, size))?;
                     let encrypted_size = size - padding_size;
                     let _ = me.read_buf.split_off(encrypted_size); // trim padding
-                    me.dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    if let Some(dec) = me.dec.as_mut() {
+                        dec.decrypt(&mut me.read_buf).map_err(|_| crypto_err())?;
+                    }
 
                     // ready to read plaintext payload into buf
                     me.read_state = ReadState::PendingData(encrypted_size - me.tag_len);
@@ -186,7 +195,11 @@ impl<T: AsyncWrite + Unpin> AsyncWrite for VMessAuthStream<T> {
             match self.write_state {
                 WriteState::WaitingChunk => {
                     let me = &mut *self;
-                    let padding_size = me.enc_size_parser.next_padding_len() as usize;
+                    let padding_size = if me.enc.is_some() {
+                        me.enc_size_parser.next_padding_len() as usize
+                    } else {
+                        0
+                    };
                     let max_payload_size = 0x4000 - me.tag_len - padding_size;
                     let consume_len = min(buf.len(), max_payload_size);
                     let payload_len = consume_len + me.tag_len + padding_size;
@@ -202,7 +215,9 @@ impl<T: AsyncWrite + Unpin> AsyncWrite for VMessAuthStream<T> {
                     // seal payload
                     piece2.reserve(consume_len + me.tag_len);
                     piece2.put_slice(&buf[..consume_len]);
-                    me.enc.encrypt(&mut piece2).map_err(|_| crypto_err())?;
+                    if let Some(enc) = me.enc.as_mut() {
+                        enc.encrypt(&mut piece2).map_err(|_| crypto_err())?;
+                    }
 
                     let mut piece3 = piece2.split_off(consume_len + me.tag_len);
 
@@ -256,3 +271,88 @@ impl<T: AsyncWrite + Unpin> AsyncWrite for VMessAuthStream<T> {
         Pin::new(&mut self.inner).poll_shutdown(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::super::crypto::{new_decryptor, new_encryptor};
+
+    fn new_stream<T>(s: T, sess: ClientSession, security: &str) -> VMessAuthStream<T> {
+        let enc_size_parser = ShakeSizeParser::new(&sess.request_body_iv);
+        let dec_size_parser = ShakeSizeParser::new(&sess.response_body_iv);
+        if security == "none" {
+            return VMessAuthStream::new(s, sess, None, enc_size_parser, None, dec_size_parser, 0);
+        }
+        let enc = new_encryptor(security, &sess.request_body_key, &sess.request_body_iv).unwrap();
+        let dec =
+            new_decryptor(security, &sess.response_body_key, &sess.response_body_iv).unwrap();
+        VMessAuthStream::new(
+            s,
+            sess,
+            Some(enc),
+            enc_size_parser,
+            Some(dec),
+            dec_size_parser,
+            16,
+        )
+    }
+
+    // Writes the payload through a client stream, then reads the produced frames
+    // back with a stream using the reversed session, i.e. the server's view.
+    async fn round_trip(security: &str, payload: &[u8]) -> Vec<u8> {
+        let sess = ClientSession::new();
+        let server_sess = ClientSession {
+            request_body_key: sess.response_body_key.clone(),
+            request_body_iv: sess.response_body_iv.clone(),
+            response_body_key: sess.request_body_key.clone(),
+            response_body_iv: sess.request_body_iv.clone(),
+            response_header: sess.response_header,
+        };
+
+        let mut client = new_stream(Vec::new(), sess, security);
+        client.write_all(payload).await.unwrap();
+        let frames = client.inner;
+
+        let mut data = vec![server_sess.response_header, 0, 0, 0];
+        let mut enc = Cfb::<Aes128>::new_var(
+            &server_sess.response_body_key,
+            &server_sess.response_body_iv,
+        )
+        .unwrap();
+        enc.encrypt(&mut data);
+        data.extend_from_slice(&frames);
+
+        let mut server = new_stream(&data[..], server_sess, security);
+        let mut buf = vec![0u8; payload.len()];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf[..], payload);
+        frames
+    }
+
+    fn payload() -> Vec<u8> {
+        // spans multiple chunks
+        (0..0x4000 * 2 + 100).map(|i| i as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_aes_128_gcm_framing() {
+        round_trip("aes-128-gcm", &payload()).await;
+    }
+
+    #[tokio::test]
+    async fn test_chacha20_poly1305_framing() {
+        round_trip("chacha20-poly1305", &payload()).await;
+    }
+
+    #[tokio::test]
+    async fn test_none_framing() {
+        let payload = payload();
+        let frames = round_trip("none", &payload).await;
+
+        // plaintext chunks with masked sizes, no tags and no paddings
+        assert_eq!(frames.len(), payload.len() + 3 * 2);
+        assert_eq!(&frames[2..0x4000 + 2], &payload[..0x4000]);
+    }
+}
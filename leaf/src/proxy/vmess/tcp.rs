@@ -24,6 +24,28 @@ pub struct Handler {
     pub dns_client: Arc<DnsClient>,
 }
 
+impl Handler {
+    /// Fails if `security` isn't a supported method.
+    pub fn new(
+        address: String,
+        port: u16,
+        uuid: String,
+        security: String,
+        bind_addr: SocketAddr,
+        dns_client: Arc<DnsClient>,
+    ) -> anyhow::Result<Self> {
+        security_method(&security)?;
+        Ok(Handler {
+            address,
+            port,
+            uuid,
+            security,
+            bind_addr,
+            dns_client,
+        })
+    }
+}
+
 #[async_trait]
 impl ProxyTcpHandler for Handler {
     fn name(&self) -> &str {
@@ -53,20 +75,9 @@ impl ProxyTcpHandler for Handler {
         request_header.set_option(REQUEST_OPTION_CHUNK_MASKING);
         request_header.set_option(REQUEST_OPTION_GLOBAL_PADDING);
 
-        match self.security.to_lowercase().as_str() {
-            "chacha20-poly1305" | "chacha20-ietf-poly1305" => {
-                request_header.security = SECURITY_TYPE_CHACHA20_POLY1305;
-            }
-            "aes-128-gcm" => {
-                request_header.security = SECURITY_TYPE_AES128_GCM;
-            }
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("unsupported cipher: {}", &self.security),
-                ))
-            }
-        }
+        request_header.security = security_method(&self.security)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?
+            .security;
 
         let mut header_buf = BytesMut::new();
         let client_sess = ClientSession::new();
@@ -80,25 +91,31 @@ impl ProxyTcpHandler for Handler {
             })?;
 
         let enc_size_parser = ShakeSizeParser::new(&client_sess.request_body_iv);
-
-        let enc = new_encryptor(
-            self.security.as_str(),
-            &client_sess.request_body_key,
-            &client_sess.request_body_iv,
-        )
-        .map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("new encryptor failed: {}", e))
-        })?;
-
         let dec_size_parser = ShakeSizeParser::new(&client_sess.response_body_iv);
-        let dec = new_decryptor(
-            self.security.as_str(),
-            &client_sess.response_body_key,
-            &client_sess.response_body_iv,
-        )
-        .map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("new decryptor failed: {}", e))
-        })?;
+
+        let (enc, dec, tag_len) = if request_header.security == SECURITY_TYPE_NONE {
+            (None, None, 0)
+        } else {
+            let enc = new_encryptor(
+                self.security.as_str(),
+                &client_sess.request_body_key,
+                &client_sess.request_body_iv,
+            )
+            .map_err(|e| {
+                io::Error::new(io::ErrorKind::Other, format!("new encryptor failed: {}", e))
+            })?;
+            let dec = new_decryptor(
+                self.security.as_str(),
+                &client_sess.response_body_key,
+                &client_sess.response_body_iv,
+            )
+            .map_err(|e| {
+                io::Error::new(io::ErrorKind::Other, format!("new decryptor failed: {}", e))
+            })?;
+            // Both AEAD ciphers, aes-128-gcm and chacha20-poly1305, append
+            // a 16 bytes tag to each chunk.
+            (Some(enc), Some(dec), 16)
+        };
 
         let mut stream = if let Some(stream) = stream {
             stream
@@ -120,8 +137,46 @@ impl ProxyTcpHandler for Handler {
             enc_size_parser,
             dec,
             dec_size_parser,
-            16, // FIXME
+            tag_len,
         );
         Ok(Box::new(SimpleStream(stream)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler(security: &str) -> anyhow::Result<Handler> {
+        let dns_client = Arc::new(DnsClient::new(
+            vec!["127.0.0.1:53".parse().unwrap()],
+            "127.0.0.1:0".parse().unwrap(),
+        ));
+        Handler::new(
+            "127.0.0.1".to_string(),
+            10086,
+            "b831381d-6324-4d53-ad4f-8cda48b30811".to_string(),
+            security.to_string(),
+            "0.0.0.0:0".parse().unwrap(),
+            dns_client,
+        )
+    }
+
+    #[test]
+    fn test_new_validates_security() {
+        for security in &[
+            "chacha20-poly1305",
+            "chacha20-ietf-poly1305",
+            "AES-128-GCM",
+            "none",
+        ] {
+            assert!(handler(security).is_ok(), "{}", security);
+        }
+        let err = handler("aes-256-cfb").err().unwrap().to_string();
+        assert_eq!(
+            err,
+            "unsupported cipher: aes-256-cfb, expected one of chacha20-poly1305, \
+             chacha20-ietf-poly1305, aes-128-gcm, none"
+        );
+    }
+}
@@ -29,6 +29,38 @@ pub struct Handler {
     pub dns_client: Arc<DnsClient>,
 }
 
+// Packets are always encrypted.
+fn udp_security_method(name: &str) -> anyhow::Result<&'static SecurityMethod> {
+    let method = security_method(name)?;
+    if method.algorithm.is_none() {
+        return Err(anyhow::anyhow!("unsupported cipher for udp: {}", name));
+    }
+    Ok(method)
+}
+
+impl Handler {
+    /// Fails if `security` isn't a supported method, `none` is accepted
+    /// for the sake of TCP but fails on connect.
+    pub fn new(
+        address: String,
+        port: u16,
+        uuid: String,
+        security: String,
+        bind_addr: SocketAddr,
+        dns_client: Arc<DnsClient>,
+    ) -> anyhow::Result<Self> {
+        security_method(&security)?;
+        Ok(Handler {
+            address,
+            port,
+            uuid,
+            security,
+            bind_addr,
+            dns_client,
+        })
+    }
+}
+
 #[async_trait]
 impl ProxyUdpHandler for Handler {
     fn name(&self) -> &str {
@@ -63,20 +95,9 @@ impl ProxyUdpHandler for Handler {
         request_header.set_option(REQUEST_OPTION_CHUNK_MASKING);
         request_header.set_option(REQUEST_OPTION_GLOBAL_PADDING);
 
-        match self.security.to_lowercase().as_str() {
-            "chacha20-poly1305" | "chacha20-ietf-poly1305" => {
-                request_header.security = SECURITY_TYPE_CHACHA20_POLY1305;
-            }
-            "aes-128-gcm" => {
-                request_header.security = SECURITY_TYPE_AES128_GCM;
-            }
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("unsupported cipher: {}", &self.security),
-                ))
-            }
-        }
+        request_header.security = udp_security_method(&self.security)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?
+            .security;
 
         let mut header_buf = BytesMut::new();
         let client_sess = ClientSession::new();
@@ -125,11 +146,11 @@ impl ProxyUdpHandler for Handler {
         let stream = VMessAuthStream::new(
             stream,
             client_sess,
-            enc,
+            Some(enc),
             enc_size_parser,
-            dec,
+            Some(dec),
             dec_size_parser,
-            16, // FIXME
+            16, // tag length of both AEAD ciphers
         );
         Ok(Box::new(Datagram {
             stream,
@@ -1,5 +1,3 @@
-mod stream;
-
 pub mod tcp;
 pub mod udp;
 
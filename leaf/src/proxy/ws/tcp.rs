@@ -2,20 +2,25 @@ use std::io;
 use std::net::SocketAddr;
 
 use async_trait::async_trait;
-use futures::TryFutureExt;
-use tokio_tungstenite::client_async_with_config;
-use tungstenite::protocol::WebSocketConfig;
-use url::Url;
 
 use crate::{
-    proxy::{ProxyStream, ProxyTcpHandler, SimpleStream},
+    proxy::{
+        transport::ws::{self, LazyWsStream, WsConfig},
+        ProxyStream, ProxyTcpHandler,
+    },
     session::Session,
 };
 
-use super::stream;
-
 pub struct Handler {
     pub path: String,
+    /// Value of the `Host` header, defaults to the destination of the
+    /// session, set it to front through a CDN.
+    pub host: String,
+    /// Maximum length of the first payload sent along with the upgrade
+    /// request, 0 disables early data.
+    pub max_early_data: usize,
+    /// Header carrying early data, `Sec-WebSocket-Protocol` if empty.
+    pub early_data_header_name: String,
     // FIXME headers
 }
 
@@ -34,27 +39,89 @@ impl ProxyTcpHandler for Handler {
         sess: &'a Session,
         stream: Option<Box<dyn ProxyStream>>,
     ) -> io::Result<Box<dyn ProxyStream>> {
-        let mut url = Url::parse(&format!("ws://{}", sess.destination.to_string())).unwrap();
-        url = url.join(self.path.as_str()).unwrap();
         match stream {
             Some(stream) => {
-                let ws_config = WebSocketConfig {
-                    max_send_queue: Some(1),
-                    max_message_size: Some(64 << 20),
-                    max_frame_size: Some(16 << 20),
+                let host = if !self.host.is_empty() {
+                    self.host.clone()
+                } else {
+                    sess.destination.to_string()
+                };
+                let config = WsConfig {
+                    path: self.path.clone(),
+                    host,
+                    early_data_header_name: self.early_data_header_name.clone(),
+                    max_early_data: self.max_early_data,
+                    ..Default::default()
                 };
-                let (socket, _) = client_async_with_config(&url, stream, Some(ws_config))
-                    .map_err(|e| {
-                        io::Error::new(
-                            io::ErrorKind::Other,
-                            format!("connect ws {} failed: {}", &url, e),
-                        )
-                    })
-                    .await?;
-                let ws_stream = stream::Adapter::new(socket);
-                Ok(Box::new(SimpleStream(ws_stream)))
+                if config.max_early_data > 0 {
+                    return Ok(Box::new(LazyWsStream::new(stream, config)));
+                }
+                let ws_stream = ws::connect(stream, &config, None).await?;
+                Ok(Box::new(ws_stream))
             }
             None => Err(io::Error::new(io::ErrorKind::Other, "invalid tls input")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::{TcpListener, TcpStream};
+    use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+    use super::*;
+    use crate::proxy::stream::SimpleStream;
+    use crate::session::SocksAddr;
+
+    // Returns the Host header of the upgrade request sent by the handler.
+    async fn upgrade_host(handler: Handler) -> String {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut host = String::new();
+            let callback = |req: &Request, resp: Response| -> Result<Response, ErrorResponse> {
+                host = req.headers()["host"].to_str().unwrap().to_string();
+                Ok(resp)
+            };
+            tokio_tungstenite::accept_hdr_async(stream, callback)
+                .await
+                .unwrap();
+            host
+        });
+        let sess = Session {
+            source: "127.0.0.1:0".parse().unwrap(),
+            destination: SocksAddr::Domain("origin.example.com".to_string(), 443),
+        };
+        let stream = TcpStream::connect(addr).await.unwrap();
+        handler
+            .handle(&sess, Some(Box::new(SimpleStream(stream))))
+            .await
+            .unwrap();
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ws_host_override() {
+        let host = upgrade_host(Handler {
+            path: "/".to_string(),
+            host: "cdn.example.com".to_string(),
+            max_early_data: 0,
+            early_data_header_name: String::new(),
+        })
+        .await;
+        assert_eq!(host, "cdn.example.com");
+    }
+
+    #[tokio::test]
+    async fn test_ws_host_defaults_to_destination() {
+        let host = upgrade_host(Handler {
+            path: "/".to_string(),
+            host: String::new(),
+            max_early_data: 0,
+            early_data_header_name: String::new(),
+        })
+        .await;
+        assert_eq!(host, "origin.example.com:443");
+    }
+}
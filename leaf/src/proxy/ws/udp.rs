@@ -10,6 +10,7 @@ use crate::{
 
 pub struct Handler {
     pub path: String,
+    pub host: String,
 }
 
 #[async_trait]
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     convert::TryFrom,
     fmt, io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
@@ -10,6 +11,8 @@ use bytes::BufMut;
 use log::*;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+use crate::common::idna;
+
 pub struct Session {
     pub source: SocketAddr,
     pub destination: SocksAddr,
@@ -45,7 +48,7 @@ pub enum SocksAddrWireType {
     PortLast,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub enum SocksAddr {
     Ip(SocketAddr),
     Domain(String, u16),
@@ -61,6 +64,19 @@ fn invalid_addr_type() -> io::Error {
     io::Error::new(io::ErrorKind::Other, "invalid address type")
 }
 
+/// Returns `domain` as sent on the wire, IDNA encoded if it isn't ASCII.
+/// Fails if it doesn't fit the one byte length prefix.
+fn wire_domain(domain: &str) -> io::Result<Cow<'_, str>> {
+    let domain = idna::to_ascii(domain)?;
+    if domain.len() > 0xff {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("domain too long: {} bytes, at most 255", domain.len()),
+        ));
+    }
+    Ok(domain)
+}
+
 impl SocksAddr {
     pub fn empty_ipv4() -> Self {
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0);
@@ -83,9 +99,28 @@ impl SocksAddr {
                 SocketAddr::V4(_addr) => 1 + 4 + 2,
                 SocketAddr::V6(_addr) => 1 + 16 + 2,
             },
-            Self::Domain(domain, _port) => 1 + 1 + domain.len() + 2,
+            Self::Domain(domain, _port) => {
+                1 + 1 + idna::to_ascii(domain).map_or(domain.len(), |d| d.len()) + 2
+            }
         }
     }
+
+    /// Returns the size of the `PortLast` encoded address at the start of
+    /// `buf`, or `None` if `buf` is too short to tell.
+    pub fn peek_size(buf: &[u8]) -> io::Result<Option<usize>> {
+        let size = match buf.first() {
+            None => return Ok(None),
+            Some(&SocksAddrPortLastType::V4) => 1 + 4 + 2,
+            Some(&SocksAddrPortLastType::V6) => 1 + 16 + 2,
+            Some(&SocksAddrPortLastType::DOMAIN) => match buf.get(1) {
+                None => return Ok(None),
+                Some(&len) => 1 + 1 + len as usize + 2,
+            },
+            Some(_) => return Err(invalid_addr_type()),
+        };
+        Ok(Some(size))
+    }
+
     pub fn port(&self) -> u16 {
         match self {
             SocksAddr::Ip(addr) => addr.port(),
@@ -159,14 +194,14 @@ impl SocksAddr {
                     }
                 },
             },
-            Self::Domain(domain, port) => match addr_type {
-                SocksAddrWireType::PortLast => {
+            Self::Domain(domain, port) => match (wire_domain(domain)?, addr_type) {
+                (domain, SocksAddrWireType::PortLast) => {
                     buf.put_u8(SocksAddrPortLastType::DOMAIN);
                     buf.put_u8(domain.len() as u8);
                     buf.put_slice(domain.as_bytes());
                     buf.put_u16(*port);
                 }
-                SocksAddrWireType::PortFirst => {
+                (domain, SocksAddrWireType::PortFirst) => {
                     buf.put_u16(*port);
                     buf.put_u8(SocksAddrPortFirstType::DOMAIN);
                     buf.put_u8(domain.len() as u8);
@@ -262,14 +297,14 @@ impl SocksAddr {
                     }
                 },
             },
-            Self::Domain(domain, port) => match addr_type {
-                SocksAddrWireType::PortLast => {
+            Self::Domain(domain, port) => match (wire_domain(domain)?, addr_type) {
+                (domain, SocksAddrWireType::PortLast) => {
                     w.write_u8(SocksAddrPortLastType::DOMAIN).await?;
                     w.write_u8(domain.len() as u8).await?;
                     w.write_all(domain.as_bytes()).await?;
                     w.write_u16(port.to_owned()).await?;
                 }
-                SocksAddrWireType::PortFirst => {
+                (domain, SocksAddrWireType::PortFirst) => {
                     w.write_u16(port.to_owned()).await?;
                     w.write_u8(SocksAddrPortFirstType::DOMAIN).await?;
                     w.write_u8(domain.len() as u8).await?;
@@ -474,3 +509,42 @@ impl TryFrom<(&[u8], SocksAddrWireType)> for SocksAddr {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_domain_wire_encoding() {
+        let addr = SocksAddr::from(("bücher.example", 443));
+        let mut buf = BytesMut::new();
+        addr.write_buf(&mut buf, SocksAddrWireType::PortLast)
+            .unwrap();
+        assert_eq!(buf.len(), addr.size());
+        let decoded = SocksAddr::try_from((&buf[..], SocksAddrWireType::PortLast)).unwrap();
+        assert_eq!(decoded, SocksAddr::from(("xn--bcher-kva.example", 443)));
+
+        let mut written = Vec::new();
+        addr.write_to(&mut written, SocksAddrWireType::PortLast)
+            .await
+            .unwrap();
+        assert_eq!(written, &buf[..]);
+
+        let addr = SocksAddr::from(("a".repeat(256), 443));
+        for addr_type in vec![SocksAddrWireType::PortLast, SocksAddrWireType::PortFirst] {
+            let err = addr.write_buf(&mut buf, addr_type).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        let err = addr
+            .write_to(&mut Vec::new(), SocksAddrWireType::PortFirst)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let addr = SocksAddr::from(("a".repeat(255), 443));
+        assert!(addr
+            .write_buf(&mut BytesMut::new(), SocksAddrWireType::PortLast)
+            .is_ok());
+    }
+}
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use log::*;
@@ -11,6 +12,9 @@ use crate::proxy::socks;
 #[cfg(feature = "inbound-http")]
 use crate::proxy::http;
 
+#[cfg(feature = "inbound-mixed")]
+use crate::proxy::mixed;
+
 #[cfg(all(
     feature = "inbound-tun",
     any(target_os = "ios", target_os = "macos", target_os = "linux")
@@ -19,20 +23,98 @@ use crate::proxy::tun;
 
 use crate::{
     app::{
-        dispatcher::Dispatcher, handler_manager::HandlerManager, nat_manager::NatManager,
-        router::Router,
+        dispatcher::{Dispatcher, PreDispatchHook},
+        handler_manager::HandlerManager,
+        nat_manager::{NatManager, NatType, SessionTimeouts},
+        router::{Router, RuleProvider},
     },
+    common::shutdown::ShutdownToken,
     config::Config,
+    proxy::limit::TokenBucket,
     session::{Session, SocksAddr},
     Runner,
 };
 
 pub fn create_runners(config: Config) -> Result<Vec<Runner>> {
-    let handler_manager = HandlerManager::new(&config.outbounds, config.dns.as_ref().unwrap());
-    let router = Router::new(&config.routing_rules);
-    let dispatcher = Arc::new(Dispatcher::new(handler_manager, router));
-    let nat_manager = Arc::new(NatManager::new(dispatcher.clone()));
+    create_runners_with_shutdown(config, ShutdownToken::never())
+}
+
+/// Same as `create_runners`, background tasks of the outbounds and the UDP
+/// sessions stop once `shutdown` is signaled.
+pub fn create_runners_with_shutdown(
+    config: Config,
+    shutdown: ShutdownToken,
+) -> Result<Vec<Runner>> {
+    create_runners_with_hook(config, shutdown, None)
+}
+
+/// Same as `create_runners_with_shutdown`, `pre_dispatch` decides on every
+/// connection before it's routed.
+pub fn create_runners_with_hook(
+    config: Config,
+    shutdown: ShutdownToken,
+    pre_dispatch: Option<PreDispatchHook>,
+) -> Result<Vec<Runner>> {
+    create_runners_with_rules(config, shutdown, pre_dispatch, None)
+}
+
+/// Same as `create_runners_with_hook`, connections are routed by the rules
+/// of `rules` if any, instead of the ones of `config`.
+pub fn create_runners_with_rules(
+    config: Config,
+    shutdown: ShutdownToken,
+    pre_dispatch: Option<PreDispatchHook>,
+    rules: Option<Arc<dyn RuleProvider>>,
+) -> Result<Vec<Runner>> {
+    let dns = config.dns.as_ref().unwrap();
+    let egress = if config.egress_rate > 0 {
+        Some(Arc::new(TokenBucket::new(config.egress_rate)))
+    } else {
+        None
+    };
+    let handler_manager =
+        HandlerManager::with_egress(&config.outbounds, dns, shutdown.clone(), egress);
+    let dns_warmer = if !dns.warm_domains.is_empty() {
+        let warm = handler_manager.dns_client().warm(dns.warm_domains.to_vec());
+        let shutdown = shutdown.clone();
+        Some(async move {
+            shutdown.run_until(warm).await;
+        })
+    } else {
+        None
+    };
+    let router = match rules {
+        Some(rules) => Router::with_provider(rules),
+        None => Router::new(&config.routing_rules),
+    };
+    let mut dispatcher = Dispatcher::new(handler_manager, router);
+    dispatcher.set_pre_dispatch_hook(pre_dispatch);
+    let dispatcher = Arc::new(dispatcher);
+    let nat_type = match config.udp.as_ref().map(|udp| udp.nat_type) {
+        Some(crate::config::UDP_NatType::SYMMETRIC) => NatType::Symmetric,
+        _ => NatType::Cone,
+    };
+    let mut timeouts = SessionTimeouts::default();
+    if let Some(udp) = config.udp.as_ref() {
+        if udp.session_timeout > 0 {
+            timeouts.default = Duration::from_secs(udp.session_timeout as u64);
+        }
+        for t in udp.port_timeouts.iter().filter(|t| t.timeout > 0) {
+            timeouts
+                .ports
+                .insert(t.port as u16, Duration::from_secs(t.timeout as u64));
+        }
+    }
+    let nat_manager = Arc::new(NatManager::with_timeouts(
+        dispatcher.clone(),
+        shutdown,
+        nat_type,
+        timeouts,
+    ));
     let mut runners: Vec<Runner> = Vec::new();
+    if let Some(dns_warmer) = dns_warmer {
+        runners.push(Box::pin(dns_warmer));
+    }
     for inbound in config.inbounds.into_iter() {
         match inbound.protocol.as_str() {
             #[cfg(feature = "inbound-http")]
@@ -49,6 +131,14 @@ pub fn create_runners(config: Config) -> Result<Vec<Runner>> {
                     runners.push(r);
                 }
             }
+            #[cfg(feature = "inbound-mixed")]
+            "mixed" => {
+                if let Ok(r) =
+                    mixed::inbound::new(&inbound, dispatcher.clone(), nat_manager.clone())
+                {
+                    runners.push(r);
+                }
+            }
             #[cfg(all(
                 feature = "inbound-tun",
                 any(target_os = "ios", target_os = "macos", target_os = "linux")
@@ -78,7 +168,11 @@ pub fn run_with_config(config: Config) -> Result<()> {
 }
 
 pub async fn test_outbound(tag: &str, config: &Config) {
-    let handler_manager = HandlerManager::new(&config.outbounds, config.dns.as_ref().unwrap());
+    let handler_manager = HandlerManager::new(
+        &config.outbounds,
+        config.dns.as_ref().unwrap(),
+        ShutdownToken::never(),
+    );
     let handler = if let Some(v) = handler_manager.get(tag) {
         v
     } else {
@@ -97,7 +191,7 @@ pub async fn test_outbound(tag: &str, config: &Config) {
                 println!("write to outbound {} failed: {}", &handler.tag(), e);
                 return;
             }
-            let mut buf = vec![0u8; 1];
+            let mut buf = [0u8; 1];
             match stream.read_exact(&mut buf).await {
                 Ok(_) => {
                     let elapsed = tokio::time::Instant::now().duration_since(start);